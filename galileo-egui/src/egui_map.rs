@@ -14,6 +14,7 @@ use galileo::layer::attribution::Attribution;
 use galileo::render::WgpuRenderer;
 use galileo::{Map, Messenger};
 
+use crate::frame_limiter::{FrameDecision, FrameLimiter};
 use crate::init::EguiMapOptions;
 
 pub struct EguiMap<'a> {
@@ -77,6 +78,7 @@ pub struct EguiMapState {
     texture_id: TextureId,
     texture_view: TextureView,
     event_processor: EventProcessor,
+    frame_limiter: FrameLimiter,
 }
 
 impl<'a> EguiMapState {
@@ -133,6 +135,7 @@ impl<'a> EguiMapState {
             texture_id,
             texture_view: texture,
             event_processor,
+            frame_limiter: FrameLimiter::new(options.max_fps),
         }
     }
 
@@ -140,6 +143,10 @@ impl<'a> EguiMapState {
         self.map.redraw();
     }
 
+    pub fn set_max_fps(&mut self, max_fps: Option<u32>) {
+        self.frame_limiter = FrameLimiter::new(max_fps);
+    }
+
     pub fn render(&mut self, ui: &mut egui::Ui) {
         let available_size = ui.available_size().floor();
         let map_size = self.renderer.size().cast::<f32>();
@@ -169,8 +176,15 @@ impl<'a> EguiMapState {
             self.resize_map(available_size);
         }
 
-        if self.requires_redraw.swap(false, Ordering::Relaxed) {
-            self.draw();
+        if self.requires_redraw.load(Ordering::Relaxed) {
+            let now = ui.input(|input_state| input_state.time);
+            match self.frame_limiter.poll(now) {
+                FrameDecision::Render => {
+                    self.requires_redraw.store(false, Ordering::Relaxed);
+                    self.draw();
+                }
+                FrameDecision::Skip(wait) => ui.ctx().request_repaint_after(wait),
+            }
         }
 
         Image::new(ImageSource::Texture(SizedTexture::new(
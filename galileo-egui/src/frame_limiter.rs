@@ -0,0 +1,87 @@
+use std::time::Duration;
+
+/// Frames requested slightly earlier than the target interval are still rendered, otherwise
+/// a jitter in the display refresh timing would skip every other frame when the limit equals the
+/// refresh rate.
+const FRAME_TIME_TOLERANCE: f64 = 0.001;
+
+/// Limits how often the map is re-rendered.
+///
+/// Time is given as seconds since an arbitrary moment (e.g. [`egui::InputState::time`]), so the
+/// limiter works the same way on native and web targets.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct FrameLimiter {
+    min_interval: Option<f64>,
+    last_frame: Option<f64>,
+}
+
+/// Result of [`FrameLimiter::poll`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum FrameDecision {
+    /// The frame should be rendered now.
+    Render,
+    /// The frame should be skipped and requested again after the given time.
+    Skip(Duration),
+}
+
+impl FrameLimiter {
+    /// Creates a new limiter that allows at most `max_fps` frames per second. If `max_fps` is
+    /// `None` or `0`, frames are not limited.
+    pub(crate) fn new(max_fps: Option<u32>) -> Self {
+        Self {
+            min_interval: max_fps.filter(|fps| *fps > 0).map(|fps| 1.0 / fps as f64),
+            last_frame: None,
+        }
+    }
+
+    /// Decides whether a frame can be rendered at time `now`. If it can, the frame is counted as
+    /// rendered.
+    pub(crate) fn poll(&mut self, now: f64) -> FrameDecision {
+        if let (Some(min_interval), Some(last_frame)) = (self.min_interval, self.last_frame) {
+            let elapsed = now - last_frame;
+            if elapsed >= 0.0 && elapsed + FRAME_TIME_TOLERANCE < min_interval {
+                return FrameDecision::Skip(Duration::from_secs_f64(min_interval - elapsed));
+            }
+        }
+
+        self.last_frame = Some(now);
+        FrameDecision::Render
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_frames_to_hold_target_rate() {
+        let mut limiter = FrameLimiter::new(Some(32));
+
+        // Frames requested at 128 FPS for one second
+        let rendered = (0..128)
+            .map(|frame| limiter.poll(frame as f64 / 128.0))
+            .filter(|decision| *decision == FrameDecision::Render)
+            .count();
+
+        assert_eq!(rendered, 32);
+    }
+
+    #[test]
+    fn skipped_frame_waits_until_next_interval() {
+        let mut limiter = FrameLimiter::new(Some(10));
+        assert_eq!(limiter.poll(1.0), FrameDecision::Render);
+
+        let FrameDecision::Skip(wait) = limiter.poll(1.04) else {
+            panic!("frame was not skipped");
+        };
+        assert!((wait.as_secs_f64() - 0.06).abs() < 1e-9);
+
+        assert_eq!(limiter.poll(1.1), FrameDecision::Render);
+    }
+
+    #[test]
+    fn no_limit_renders_every_frame() {
+        let mut limiter = FrameLimiter::new(None);
+        assert!((0..10).all(|frame| limiter.poll(frame as f64 * 0.001) == FrameDecision::Render));
+    }
+}
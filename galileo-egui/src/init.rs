@@ -33,12 +33,14 @@ pub struct InitBuilder {
 
 pub struct EguiMapOptions {
     pub horizon_options: Option<HorizonOptions>,
+    pub max_fps: Option<u32>,
 }
 
 impl Default for EguiMapOptions {
     fn default() -> Self {
         Self {
             horizon_options: Some(HorizonOptions::default()),
+            max_fps: None,
         }
     }
 }
@@ -96,6 +98,11 @@ impl InitBuilder {
         self
     }
 
+    pub fn with_max_fps(mut self, max_fps: Option<u32>) -> Self {
+        self.options.max_fps = max_fps;
+        self
+    }
+
     pub fn init(self) -> eframe::Result {
         #[cfg(not(target_arch = "wasm32"))]
         {
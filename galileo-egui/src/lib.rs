@@ -1,4 +1,5 @@
 mod egui_map;
+mod frame_limiter;
 pub use egui_map::{EguiMap, EguiMapState};
 
 #[cfg(feature = "init")]
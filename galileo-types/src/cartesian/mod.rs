@@ -3,11 +3,13 @@
 mod impls;
 mod orient;
 mod rect;
+mod simplify;
 mod size;
 mod traits;
 
 pub use impls::{Point2, Point3, Vector2, Vector3};
 pub use orient::Orientation;
 pub use rect::Rect;
+pub use simplify::{douglas_peucker, simplify_polygons, simplify_polygons_by};
 pub use size::Size;
 pub use traits::*;
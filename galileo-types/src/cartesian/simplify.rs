@@ -0,0 +1,410 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::cartesian::{CartesianPoint2d, Point2};
+use crate::impls::{ClosedContour, Polygon};
+
+type PointKey = (u64, u64);
+
+/// Simplifies a set of polygons with the Douglas-Peucker algorithm, keeping the boundaries shared
+/// between polygons coincident.
+///
+/// When adjacent polygons (e.g. administrative regions) are simplified one by one, a border shared
+/// by two polygons is simplified differently in each of them, which creates gaps and overlaps
+/// along the border. This function first splits all contours into chains of edges that are used
+/// by the same set of contours, simplifies every chain only once, and then assembles the contours
+/// back from the simplified chains. Points where three or more chains meet are never removed.
+///
+/// Boundaries are considered shared only if their vertices are exactly equal.
+///
+/// `tolerance` is the maximum distance between the original and the simplified chains. Chains of
+/// contours that would degenerate into less than 3 points after simplification are left
+/// unchanged in every contour that uses them.
+pub fn simplify_polygons<P>(polygons: &[Polygon<P>], tolerance: f64) -> Vec<Polygon<P>>
+where
+    P: CartesianPoint2d<Num = f64> + Copy,
+{
+    simplify_polygons_by(polygons, tolerance, &|p: &P| Point2::new(p.x(), p.y()))
+}
+
+/// Same as [`simplify_polygons`], but the positions of the points are given by `xy`.
+///
+/// Other coordinates of the points (e.g. `z`) are ignored when looking for shared boundaries and
+/// measuring distances, but kept in the returned polygons.
+///
+/// ```
+/// use galileo_types::cartesian::{simplify_polygons_by, CartesianPoint3d, Point2, Point3};
+/// use galileo_types::impls::{ClosedContour, Polygon};
+///
+/// let polygon: Polygon<Point3> = ClosedContour::new(vec![
+///     Point3::new(0.0, 0.0, 1.0),
+///     Point3::new(0.5, 0.01, 1.0),
+///     Point3::new(1.0, 0.0, 1.0),
+///     Point3::new(1.0, 1.0, 1.0),
+///     Point3::new(0.0, 1.0, 1.0),
+/// ])
+/// .into();
+/// let simplified = simplify_polygons_by(&[polygon], 0.1, &|p: &Point3| Point2::new(p.x(), p.y()));
+/// assert_eq!(simplified[0].outer_contour.points.len(), 4);
+/// ```
+pub fn simplify_polygons_by<P: Copy>(
+    polygons: &[Polygon<P>],
+    tolerance: f64,
+    xy: &impl Fn(&P) -> Point2,
+) -> Vec<Polygon<P>> {
+    let rings: Vec<Vec<P>> = polygons
+        .iter()
+        .flat_map(|polygon| {
+            std::iter::once(&polygon.outer_contour).chain(polygon.inner_contours.iter())
+        })
+        .map(|contour| ring_points(contour, xy))
+        .collect();
+
+    let nodes = find_nodes(&rings, xy);
+    let mut chain_indices = HashMap::new();
+    let mut chains = vec![];
+    let ring_chains: Vec<_> = rings
+        .iter()
+        .map(|ring| split_ring(ring, &nodes, tolerance, xy, &mut chain_indices, &mut chains))
+        .collect();
+
+    // A ring that would degenerate keeps all its chains unsimplified, and so do all other rings
+    // sharing these chains. Restoring chains only adds points to rings, so one pass is enough.
+    for refs in ring_chains.iter().flatten() {
+        if assembled_len(refs, &chains) < 3 {
+            for chain_ref in refs {
+                chains[chain_ref.index].keep_original = true;
+            }
+        }
+    }
+
+    let mut simplified = rings
+        .iter()
+        .zip(&ring_chains)
+        .map(|(ring, refs)| match refs {
+            Some(refs) => assemble_ring(refs, &chains),
+            None => ring.clone(),
+        });
+
+    polygons
+        .iter()
+        .map(|polygon| {
+            let mut next = || {
+                ClosedContour::new(
+                    simplified
+                        .next()
+                        .expect("every contour of the polygon is simplified"),
+                )
+            };
+            let outer_contour = next();
+            let inner_contours = polygon.inner_contours.iter().map(|_| next()).collect();
+            Polygon::new(outer_contour, inner_contours)
+        })
+        .collect()
+}
+
+fn key<P>(point: &P, xy: &impl Fn(&P) -> Point2) -> PointKey {
+    let point = xy(point);
+    // Adding zero turns `-0.0` into `0.0`, so both have the same key.
+    ((point.x() + 0.0).to_bits(), (point.y() + 0.0).to_bits())
+}
+
+fn ring_points<P: Copy>(contour: &ClosedContour<P>, xy: &impl Fn(&P) -> Point2) -> Vec<P> {
+    let mut points = contour.points.clone();
+    points.dedup_by(|a, b| key(a, xy) == key(b, xy));
+    if points.len() > 1 && key(&points[0], xy) == key(&points[points.len() - 1], xy) {
+        points.pop();
+    }
+
+    points
+}
+
+/// Vertices that must be kept: vertices where the set of contours using the adjacent edges
+/// changes, and a fixed vertex for contours that do not have any such points.
+fn find_nodes<P>(rings: &[Vec<P>], xy: &impl Fn(&P) -> Point2) -> HashSet<PointKey> {
+    let mut edges: HashMap<(PointKey, PointKey), Vec<usize>> = HashMap::new();
+    let mut neighbours: HashMap<PointKey, HashSet<PointKey>> = HashMap::new();
+
+    for (ring_index, ring) in rings.iter().enumerate() {
+        for i in 0..ring.len() {
+            let a = key(&ring[i], xy);
+            let b = key(&ring[(i + 1) % ring.len()], xy);
+            edges.entry(edge_key(a, b)).or_default().push(ring_index);
+            neighbours.entry(a).or_default().insert(b);
+            neighbours.entry(b).or_default().insert(a);
+        }
+    }
+
+    let mut nodes = HashSet::new();
+    for (vertex, adjacent) in &neighbours {
+        let is_node = match adjacent.iter().copied().collect::<Vec<_>>()[..] {
+            [a, b] => edges.get(&edge_key(*vertex, a)) != edges.get(&edge_key(*vertex, b)),
+            _ => true,
+        };
+        if is_node {
+            nodes.insert(*vertex);
+        }
+    }
+
+    for ring in rings {
+        if !ring.iter().any(|p| nodes.contains(&key(p, xy))) {
+            if let Some(anchor) = ring.iter().map(|p| key(p, xy)).min() {
+                nodes.insert(anchor);
+            }
+        }
+    }
+
+    nodes
+}
+
+fn edge_key(a: PointKey, b: PointKey) -> (PointKey, PointKey) {
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+/// Part of a ring between two nodes. Chains are shared by all rings that use them.
+struct Chain<P> {
+    original: Vec<P>,
+    simplified: Vec<P>,
+    keep_original: bool,
+}
+
+impl<P> Chain<P> {
+    fn points(&self) -> &[P] {
+        if self.keep_original {
+            &self.original
+        } else {
+            &self.simplified
+        }
+    }
+}
+
+/// Reference from a ring to one of its chains. The chain is stored in canonical direction, so
+/// `is_reversed` tells whether the ring goes along the chain backwards.
+struct ChainRef {
+    index: usize,
+    is_reversed: bool,
+}
+
+/// Splits the ring into chains between nodes, simplifying every chain that was not seen before.
+///
+/// Returns `None` if the ring has no nodes.
+fn split_ring<P: Copy>(
+    ring: &[P],
+    nodes: &HashSet<PointKey>,
+    tolerance: f64,
+    xy: &impl Fn(&P) -> Point2,
+    chain_indices: &mut HashMap<Vec<PointKey>, usize>,
+    chains: &mut Vec<Chain<P>>,
+) -> Option<Vec<ChainRef>> {
+    let start = ring.iter().position(|p| nodes.contains(&key(p, xy)))?;
+
+    let mut refs = vec![];
+    let mut chain = vec![ring[start]];
+    for i in 1..=ring.len() {
+        let point = ring[(start + i) % ring.len()];
+        chain.push(point);
+        if nodes.contains(&key(&point, xy)) {
+            refs.push(add_chain(&chain, tolerance, xy, chain_indices, chains));
+            chain = vec![point];
+        }
+    }
+
+    Some(refs)
+}
+
+/// Adds the chain to the list, returning the same chain for the chain and its reversed version.
+fn add_chain<P: Copy>(
+    chain: &[P],
+    tolerance: f64,
+    xy: &impl Fn(&P) -> Point2,
+    chain_indices: &mut HashMap<Vec<PointKey>, usize>,
+    chains: &mut Vec<Chain<P>>,
+) -> ChainRef {
+    let keys: Vec<PointKey> = chain.iter().map(|p| key(p, xy)).collect();
+    let reversed_keys: Vec<PointKey> = keys.iter().rev().copied().collect();
+    let is_reversed = reversed_keys < keys;
+    let canonical_keys = if is_reversed { reversed_keys } else { keys };
+
+    let index = *chain_indices.entry(canonical_keys).or_insert_with(|| {
+        let mut original = chain.to_vec();
+        if is_reversed {
+            original.reverse();
+        }
+        let simplified = douglas_peucker(&original, tolerance, xy);
+        chains.push(Chain {
+            original,
+            simplified,
+            keep_original: false,
+        });
+        chains.len() - 1
+    });
+
+    ChainRef { index, is_reversed }
+}
+
+fn assembled_len<P>(refs: &[ChainRef], chains: &[Chain<P>]) -> usize {
+    refs.iter()
+        .map(|r| chains[r.index].points().len() - 1)
+        .sum()
+}
+
+fn assemble_ring<P: Copy>(refs: &[ChainRef], chains: &[Chain<P>]) -> Vec<P> {
+    let mut result = vec![];
+    for chain_ref in refs {
+        let points = chains[chain_ref.index].points();
+        if chain_ref.is_reversed {
+            result.extend(points[1..].iter().rev());
+        } else {
+            result.extend_from_slice(&points[..points.len() - 1]);
+        }
+    }
+
+    result
+}
+
+/// Simplifies the line with the Douglas-Peucker algorithm, so that the simplified line differs from
+/// the original one by not more than `tolerance`. The first and the last points are always kept.
+///
+/// Distances are measured between the positions of the points returned by `xy`, so other
+/// coordinates of the points (e.g. `z`) are ignored, but kept in the returned points.
+///
+/// ```
+/// use galileo_types::cartesian::{douglas_peucker, CartesianPoint3d, Point2, Point3};
+///
+/// let line = [
+///     Point3::new(0.0, 0.0, 1.0),
+///     Point3::new(1.0, 0.01, 2.0),
+///     Point3::new(2.0, 0.0, 3.0),
+/// ];
+/// let simplified = douglas_peucker(&line, 0.1, &|p: &Point3| Point2::new(p.x(), p.y()));
+/// assert_eq!(simplified, vec![line[0], line[2]]);
+/// ```
+pub fn douglas_peucker<P: Copy>(
+    points: &[P],
+    tolerance: f64,
+    xy: &impl Fn(&P) -> Point2,
+) -> Vec<P> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+
+    let first = xy(&points[0]);
+    let last = xy(&points[points.len() - 1]);
+    let (index, max_distance) = points[1..points.len() - 1]
+        .iter()
+        .enumerate()
+        .map(|(i, p)| (i + 1, distance_to_segment(&xy(p), &first, &last)))
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap_or((0, 0.0));
+
+    if max_distance <= tolerance {
+        return vec![points[0], points[points.len() - 1]];
+    }
+
+    let mut result = douglas_peucker(&points[..=index], tolerance, xy);
+    result.pop();
+    result.extend(douglas_peucker(&points[index..], tolerance, xy));
+
+    result
+}
+
+fn distance_to_segment(point: &Point2, a: &Point2, b: &Point2) -> f64 {
+    let (dx, dy) = (b.x() - a.x(), b.y() - a.y());
+    let length_sq = dx * dx + dy * dy;
+    let t = if length_sq > 0.0 {
+        (((point.x() - a.x()) * dx + (point.y() - a.y()) * dy) / length_sq).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+
+    let (x, y) = (a.x() + t * dx, a.y() + t * dy);
+    ((point.x() - x).powi(2) + (point.y() - y).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cartesian::Point2;
+
+    fn polygon(points: &[(f64, f64)]) -> Polygon<Point2> {
+        ClosedContour::new(points.iter().map(|(x, y)| Point2::new(*x, *y)).collect()).into()
+    }
+
+    fn border_points(polygon: &Polygon<Point2>) -> Vec<(f64, f64)> {
+        let mut points: Vec<_> = polygon
+            .outer_contour
+            .points
+            .iter()
+            .filter(|p| p.x() > 0.9 && p.x() < 1.1)
+            .map(|p| (p.x(), p.y()))
+            .collect();
+        points.sort_by(|a, b| a.1.total_cmp(&b.1));
+        points
+    }
+
+    fn adjacent_polygons() -> Vec<Polygon<Point2>> {
+        vec![
+            polygon(&[
+                (0.0, 0.0),
+                (1.0, 0.0),
+                (1.0, 0.3),
+                (1.05, 0.5),
+                (1.0, 0.7),
+                (1.0, 1.0),
+                (0.0, 1.0),
+            ]),
+            polygon(&[
+                (1.0, 0.0),
+                (2.0, 0.0),
+                (2.0, 1.0),
+                (1.0, 1.0),
+                (1.0, 0.7),
+                (1.05, 0.5),
+                (1.0, 0.3),
+            ]),
+        ]
+    }
+
+    #[test]
+    fn shared_edge_stays_shared() {
+        let simplified = simplify_polygons(&adjacent_polygons(), 0.1);
+
+        let left = border_points(&simplified[0]);
+        let right = border_points(&simplified[1]);
+        assert_eq!(left, vec![(1.0, 0.0), (1.0, 1.0)]);
+        assert_eq!(left, right);
+    }
+
+    #[test]
+    fn shared_edge_details_above_tolerance_are_kept() {
+        let simplified = simplify_polygons(&adjacent_polygons(), 0.01);
+
+        let left = border_points(&simplified[0]);
+        let right = border_points(&simplified[1]);
+        assert!(left.contains(&(1.05, 0.5)));
+        assert_eq!(left, right);
+    }
+
+    #[test]
+    fn degenerate_ring_keeps_shared_chains_in_neighbours() {
+        let sliver = polygon(&[(1.0, 0.0), (1.05, 0.5), (1.0, 1.0), (1.01, 0.5)]);
+        let neighbour = polygon(&[(1.0, 0.0), (2.0, 0.0), (2.0, 1.0), (1.0, 1.0), (1.05, 0.5)]);
+        let simplified = simplify_polygons(&[sliver.clone(), neighbour], 0.1);
+
+        assert_eq!(simplified[0], sliver);
+        assert!(simplified[1]
+            .outer_contour
+            .points
+            .contains(&Point2::new(1.05, 0.5)));
+    }
+
+    #[test]
+    fn isolated_ring_is_simplified() {
+        let ring = polygon(&[(0.0, 0.0), (0.5, 0.01), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]);
+        let simplified = simplify_polygons(&[ring], 0.1);
+        assert_eq!(simplified[0].outer_contour.points.len(), 4);
+    }
+}
@@ -23,6 +23,12 @@ impl Datum {
     pub fn inv_flattening(&self) -> f64 {
         self.inv_flattening
     }
+
+    /// Mean radius of the ellipsoid (arithmetic mean of its three semi-axes).
+    pub fn mean_radius(&self) -> f64 {
+        let semiminor = self.semimajor * (1.0 - 1.0 / self.inv_flattening);
+        (2.0 * self.semimajor + semiminor) / 3.0
+    }
 }
 
 impl Default for Datum {
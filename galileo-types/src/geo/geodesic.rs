@@ -0,0 +1,75 @@
+//! Calculations of distances and directions on the surface of a celestial body.
+
+use crate::geo::{Datum, GeoPoint, NewGeoPoint};
+
+/// Returns the point at the given `distance` (in meters) from the `start` point moving along the
+/// great circle with the initial `bearing` (in degrees clockwise from north).
+///
+/// The calculation uses a spherical model of the Earth with the mean radius of the WGS84
+/// ellipsoid, so the result can be off by up to 0.5% from the precise geodesic solution. Paths
+/// going over a pole continue on the other side of it, and the longitude of the resulting point
+/// is always normalized into `[-180, 180)` range.
+pub fn destination_point<P>(start: &impl GeoPoint<Num = f64>, bearing: f64, distance: f64) -> P
+where
+    P: NewGeoPoint<f64>,
+{
+    destination_point_with_datum(start, bearing, distance, &Datum::WGS84)
+}
+
+/// Same as [`destination_point`], but uses the mean radius of the given `datum`.
+pub fn destination_point_with_datum<P>(
+    start: &impl GeoPoint<Num = f64>,
+    bearing: f64,
+    distance: f64,
+    datum: &Datum,
+) -> P
+where
+    P: NewGeoPoint<f64>,
+{
+    let angular_distance = distance / datum.mean_radius();
+    let bearing = bearing.to_radians();
+    let lat = start.lat_rad();
+    let lon = start.lon_rad();
+
+    let (sin_lat, cos_lat) = lat.sin_cos();
+    let (sin_dist, cos_dist) = angular_distance.sin_cos();
+
+    let sin_end_lat = (sin_lat * cos_dist + cos_lat * sin_dist * bearing.cos()).clamp(-1.0, 1.0);
+    let end_lat = sin_end_lat.asin();
+    let end_lon =
+        lon + (bearing.sin() * sin_dist * cos_lat).atan2(cos_dist - sin_lat * sin_end_lat);
+
+    P::latlon(end_lat.to_degrees(), normalize_lon(end_lon.to_degrees()))
+}
+
+fn normalize_lon(lon: f64) -> f64 {
+    (lon + 180.0).rem_euclid(360.0) - 180.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geo::impls::GeoPoint2d;
+    use crate::latlon;
+
+    #[test]
+    fn north_from_equator() {
+        let end: GeoPoint2d = destination_point(&latlon!(0.0, 30.0), 0.0, 111_200.0);
+        assert!((end.lat() - 1.0).abs() < 1e-3);
+        assert!((end.lon() - 30.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn east_across_antimeridian() {
+        let end: GeoPoint2d = destination_point(&latlon!(0.0, 179.5), 90.0, 111_200.0);
+        assert!(end.lat().abs() < 1e-9);
+        assert!((end.lon() + 179.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn north_over_pole() {
+        let end: GeoPoint2d = destination_point(&latlon!(89.0, 10.0), 0.0, 2.0 * 111_200.0);
+        assert!((end.lat() - 89.0).abs() < 1e-2);
+        assert!((end.lon() + 170.0).abs() < 1e-6);
+    }
+}
@@ -39,6 +39,28 @@ impl Geometry for geojson::Geometry {
     }
 }
 
+impl TryFrom<&geojson::Geometry> for Geom<GeoPoint2d> {
+    type Error = GalileoTypesError;
+
+    fn try_from(value: &geojson::Geometry) -> Result<Self, Self::Error> {
+        let geom = match &value.value {
+            Value::Point(p) => Some(Geom::Point(GeoPoint2d::try_from(p.clone())?)),
+            Value::MultiPoint(points) => convert_multi_point(points).map(Geom::MultiPoint),
+            Value::LineString(points) => convert_contour(points).map(Geom::Contour),
+            Value::MultiLineString(lines) => convert_multi_contour(lines).map(Geom::MultiContour),
+            Value::Polygon(polygon) => convert_polygon(polygon).map(Geom::Polygon),
+            Value::MultiPolygon(mp) => convert_multi_polygon(mp).map(Geom::MultiPolygon),
+            Value::GeometryCollection(_) => {
+                return Err(GalileoTypesError::Conversion(
+                    "geometry collections are not supported".to_string(),
+                ))
+            }
+        };
+
+        geom.ok_or_else(|| GalileoTypesError::Conversion("invalid GeoJSON geometry".to_string()))
+    }
+}
+
 fn convert_contour(line_string: &LineStringType) -> Option<Contour<GeoPoint2d>> {
     let is_closed = !line_string.is_empty() && line_string[0] == line_string[line_string.len() - 1];
     Some(Contour::new(
@@ -83,3 +105,47 @@ fn convert_multi_polygon(mp: &[PolygonType]) -> Option<MultiPolygon<GeoPoint2d>>
         mp.iter().map(convert_polygon).collect::<Option<Vec<_>>>()?,
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn geometry_to_geom() {
+        let point = geojson::Geometry::new(Value::Point(vec![10.0, 20.0]));
+        assert_eq!(
+            Geom::<GeoPoint2d>::try_from(&point).unwrap(),
+            Geom::Point(GeoPoint2d::latlon(20.0, 10.0))
+        );
+
+        let line = geojson::Geometry::new(Value::LineString(vec![vec![0.0, 0.0], vec![1.0, 1.0]]));
+        assert!(matches!(
+            Geom::<GeoPoint2d>::try_from(&line).unwrap(),
+            Geom::Contour(_)
+        ));
+
+        let polygon = geojson::Geometry::new(Value::Polygon(vec![vec![
+            vec![0.0, 0.0],
+            vec![1.0, 0.0],
+            vec![1.0, 1.0],
+            vec![0.0, 0.0],
+        ]]));
+        assert!(matches!(
+            Geom::<GeoPoint2d>::try_from(&polygon).unwrap(),
+            Geom::Polygon(_)
+        ));
+    }
+
+    #[test]
+    fn invalid_geometry_to_geom() {
+        let not_closed = geojson::Geometry::new(Value::Polygon(vec![vec![
+            vec![0.0, 0.0],
+            vec![1.0, 0.0],
+            vec![1.0, 1.0],
+        ]]));
+        assert!(Geom::<GeoPoint2d>::try_from(&not_closed).is_err());
+
+        let collection = geojson::Geometry::new(Value::GeometryCollection(vec![]));
+        assert!(Geom::<GeoPoint2d>::try_from(&collection).is_err());
+    }
+}
@@ -68,6 +68,7 @@ impl CartesianGeometry2d<Point2> for Country {
 }
 
 #[derive(Debug, Deserialize)]
+#[allow(dead_code)]
 pub struct City {
     lat: f64,
     lng: f64,
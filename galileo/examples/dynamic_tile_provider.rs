@@ -60,7 +60,9 @@ fn runtime_update_example() -> Result<(), Box<dyn std::error::Error>> {
     loader.add_parameter("quality", "high");
 
     // Update the URL template
-    loader.update_url_template("https://custom.tiles.com/{z}/{x}/{y}.png");
+    loader
+        .update_url_template("https://custom.tiles.com/{z}/{x}/{y}.png")
+        .expect("valid template");
 
     // Update parameters
     loader.update_parameters(vec![
@@ -160,6 +160,7 @@ fn create_countries_layer() -> FeatureLayer<Point2, Country, CountrySymbol, Cart
         sort_by_depth: false,
         buffer_size_limit: 1_000_000,
         use_antialiasing: true,
+        ..Default::default()
     })
 }
 
@@ -185,6 +185,8 @@ impl Symbol<PointMarker> for ColoredPointSymbol {
                     image,
                     anchor: Vector2::new(0.5, 1.0),
                     size: None,
+                    source_rect: None,
+                    rotation: 0.0,
                 },
             );
         }
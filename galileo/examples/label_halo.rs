@@ -0,0 +1,222 @@
+//! Labels with halo over a dark raster layer
+
+use std::sync::Arc;
+
+use egui::Color32;
+use galileo::layer::feature_layer::Feature;
+use galileo::layer::raster_tile_layer::{Basemap, RasterTileLayerBuilder};
+use galileo::layer::FeatureLayer;
+use galileo::render::render_bundle::RenderBundle;
+use galileo::render::text::text_service::TextService;
+use galileo::render::text::{
+    FontWeight, HorizontalAlignment, RustybuzzRasterizer, TextStyle, VerticalAlignment,
+};
+use galileo::symbol::Symbol;
+use galileo::{Color, Map, MapBuilder};
+use galileo_egui::{EguiMap, EguiMapState};
+use galileo_types::cartesian::{Point3, Vector2};
+use galileo_types::geo::impls::GeoPoint2d;
+use galileo_types::geo::Crs;
+use galileo_types::geometry::Geom;
+use galileo_types::geometry_type::GeoSpace2d;
+use galileo_types::latlon;
+use parking_lot::RwLock;
+
+struct EguiMapApp {
+    map: EguiMapState,
+    feature_layer: Arc<RwLock<FeatureLayer<GeoPoint2d, City, HaloLabelSymbol, GeoSpace2d>>>,
+    halo_width: f32,
+    halo_color: Color32,
+    text_color: Color32,
+}
+
+impl EguiMapApp {
+    fn new(mut egui_map_state: EguiMapState) -> Self {
+        let halo_width = 2.0;
+        let halo_color = Color32::BLACK;
+        let text_color = Color32::WHITE;
+
+        let symbol = HaloLabelSymbol::new(halo_width, halo_color, text_color);
+        let layer = FeatureLayer::new(cities(), symbol, Crs::WGS84);
+        let layer = Arc::new(RwLock::new(layer));
+
+        egui_map_state.map_mut().layers_mut().push(layer.clone());
+
+        Self {
+            map: egui_map_state,
+            feature_layer: layer,
+            halo_width,
+            halo_color,
+            text_color,
+        }
+    }
+
+    fn update_symbol(&mut self) {
+        let symbol = HaloLabelSymbol::new(self.halo_width, self.halo_color, self.text_color);
+        self.feature_layer.write().set_symbol(symbol);
+    }
+}
+
+impl eframe::App for EguiMapApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            EguiMap::new(&mut self.map).show_ui(ui);
+
+            egui::Window::new("Label halo").show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Halo width");
+                    if ui
+                        .add(
+                            egui::DragValue::new(&mut self.halo_width)
+                                .speed(0.1)
+                                .range(0.0..=8.0),
+                        )
+                        .changed()
+                    {
+                        self.update_symbol();
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Halo color");
+                    if ui.color_edit_button_srgba(&mut self.halo_color).changed() {
+                        self.update_symbol();
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Text color");
+                    if ui.color_edit_button_srgba(&mut self.text_color).changed() {
+                        self.update_symbol();
+                    }
+                });
+            });
+        });
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() {
+    run()
+}
+
+pub(crate) fn run() {
+    initialize_font_service();
+    let map = create_map();
+    galileo_egui::InitBuilder::new(map)
+        .with_app_builder(|egui_map_state| Box::new(EguiMapApp::new(egui_map_state)))
+        .init()
+        .expect("failed to initialize");
+}
+
+fn initialize_font_service() {
+    let rasterizer = RustybuzzRasterizer::default();
+    TextService::initialize(rasterizer).load_fonts("galileo/examples/data/fonts");
+}
+
+fn create_map() -> Map {
+    let layer = RasterTileLayerBuilder::new_basemap(Basemap::CartoDark)
+        .with_file_cache_checked(".tile_cache")
+        .build()
+        .expect("failed to create layer");
+
+    MapBuilder::default()
+        .with_layer(layer)
+        .with_latlon(48.0, 10.0)
+        .with_z_level(5)
+        .build()
+}
+
+fn cities() -> Vec<City> {
+    vec![
+        City {
+            position: latlon!(51.5074, -0.1278),
+            name: "London",
+        },
+        City {
+            position: latlon!(48.8566, 2.3522),
+            name: "Paris",
+        },
+        City {
+            position: latlon!(52.52, 13.405),
+            name: "Berlin",
+        },
+        City {
+            position: latlon!(41.9028, 12.4964),
+            name: "Rome",
+        },
+        City {
+            position: latlon!(40.4168, -3.7038),
+            name: "Madrid",
+        },
+        City {
+            position: latlon!(48.2082, 16.3738),
+            name: "Vienna",
+        },
+        City {
+            position: latlon!(50.0755, 14.4378),
+            name: "Prague",
+        },
+    ]
+}
+
+struct City {
+    position: GeoPoint2d,
+    name: &'static str,
+}
+
+impl Feature for City {
+    type Geom = GeoPoint2d;
+
+    fn geometry(&self) -> &Self::Geom {
+        &self.position
+    }
+}
+
+struct HaloLabelSymbol {
+    style: TextStyle,
+}
+
+impl HaloLabelSymbol {
+    fn new(halo_width: f32, halo_color: Color32, text_color: Color32) -> Self {
+        Self {
+            style: TextStyle {
+                font_family: vec!["DejaVu Sans".to_string(), "Noto Sans".to_string()],
+                font_size: 18.0,
+                font_color: to_color(text_color),
+                horizontal_alignment: HorizontalAlignment::Center,
+                vertical_alignment: VerticalAlignment::Middle,
+                weight: FontWeight::BOLD,
+                outline_width: halo_width,
+                outline_color: to_color(halo_color),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+fn to_color(color: Color32) -> Color {
+    Color::rgba(color.r(), color.g(), color.b(), color.a())
+}
+
+impl Symbol<City> for HaloLabelSymbol {
+    fn render(
+        &self,
+        feature: &City,
+        geometry: &Geom<Point3>,
+        _min_resolution: f64,
+        bundle: &mut RenderBundle,
+    ) {
+        let Geom::Point(point) = geometry else {
+            return;
+        };
+
+        bundle.add_label(
+            point,
+            feature.name,
+            &self.style,
+            Vector2::new(0.0, 0.0),
+            false,
+        );
+    }
+}
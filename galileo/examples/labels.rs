@@ -71,7 +71,6 @@ impl EguiMapApp {
             style: TextStyle {
                 font_family: LabeledSymbol::new().style.font_family,
                 font_size: self.font_size,
-                font_color: Color::BLACK,
                 horizontal_alignment: self.horizontal_align,
                 vertical_alignment: self.vertical_align,
                 weight,
@@ -83,6 +82,7 @@ impl EguiMapApp {
                     self.outline_color.b(),
                     self.outline_color.a(),
                 ),
+                ..Default::default()
             },
             attach_to_map: self.attach_to_map,
         };
@@ -322,13 +322,8 @@ impl LabeledSymbol {
                     "Noto Sans".to_string(),
                 ],
                 font_size: 20.0,
-                font_color: Color::BLACK,
-                horizontal_alignment: Default::default(),
-                vertical_alignment: Default::default(),
-                weight: Default::default(),
-                style: Default::default(),
-                outline_width: Default::default(),
                 outline_color: Default::default(),
+                ..Default::default()
             },
             attach_to_map: false,
         }
@@ -14,6 +14,7 @@ use std::sync::Arc;
 
 struct EguiMapApp {
     map: EguiMapState,
+    #[allow(dead_code)]
     feature_layer: Arc<RwLock<FeatureLayer<GeoPoint2d, TextMarker, TextMarkerSymbol, GeoSpace2d>>>,
 }
 
@@ -57,17 +57,15 @@ pub(crate) fn run() {
                         "Noto Sans JP".to_string(),
                     ],
                     font_size: 12.0,
-                    font_color: Color::BLACK,
-                    horizontal_alignment: Default::default(),
-                    vertical_alignment: Default::default(),
                     weight: FontWeight::BOLD,
-                    style: Default::default(),
                     outline_width: 2.0,
                     outline_color: Color::WHITE,
+                    ..Default::default()
                 },
             }),
         }],
         background: Default::default(),
+        scale_factor: 1.0,
     };
 
     let label_layer = VectorTileLayer::new(
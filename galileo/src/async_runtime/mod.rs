@@ -1,4 +1,5 @@
 use std::future::Future;
+use std::time::Duration;
 
 #[cfg(not(target_arch = "wasm32"))]
 use maybe_sync::MaybeSend;
@@ -12,6 +13,18 @@ where
     tokio::spawn(future);
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await;
+}
+
+/// Waits for the `future` to complete for at most `duration`. Returns `None` if the deadline is
+/// reached first.
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+    tokio::time::timeout(duration, future).await.ok()
+}
+
 #[cfg(target_arch = "wasm32")]
 pub fn spawn<T>(future: T)
 where
@@ -22,3 +35,53 @@ where
         future.await;
     });
 }
+
+#[cfg(target_arch = "wasm32")]
+pub async fn sleep(duration: Duration) {
+    use wasm_bindgen::JsCast;
+
+    let millis = duration.as_millis().min(i32::MAX as u128) as i32;
+    let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+        let global = js_sys::global();
+        let result = if let Some(window) = global.dyn_ref::<web_sys::Window>() {
+            window.set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, millis)
+        } else if let Some(worker) = global.dyn_ref::<web_sys::DedicatedWorkerGlobalScope>() {
+            worker.set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, millis)
+        } else {
+            resolve.call0(&wasm_bindgen::JsValue::NULL).map(|_| 0)
+        };
+
+        if let Err(err) = result {
+            log::warn!("Failed to set timeout: {err:?}");
+            let _ = resolve.call0(&wasm_bindgen::JsValue::NULL);
+        }
+    });
+
+    let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+}
+
+/// Waits for the `future` to complete for at most `duration`. Returns `None` if the deadline is
+/// reached first.
+#[cfg(target_arch = "wasm32")]
+pub async fn timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+    use futures::future::{select, Either};
+
+    match select(Box::pin(future), Box::pin(sleep(duration))).await {
+        Either::Left((output, _)) => Some(output),
+        Either::Right(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn timeout_abandons_slow_future() {
+        let slow = timeout(Duration::from_millis(10), sleep(Duration::from_secs(10))).await;
+        assert!(slow.is_none());
+
+        let fast = timeout(Duration::from_secs(10), async { 42 }).await;
+        assert_eq!(fast, Some(42));
+    }
+}
@@ -0,0 +1,315 @@
+use std::time::Duration;
+
+use galileo_types::cartesian::{CartesianPoint2d, CartesianPoint3d, Point2, Rect};
+use maybe_sync::{MaybeSend, MaybeSync};
+use parking_lot::Mutex;
+
+use crate::control::{EventPropagation, MouseButton, MouseEvent, UserEvent, UserEventHandler};
+use crate::map::Map;
+use crate::view::MapView;
+
+const DEFAULT_FLY_DURATION: Duration = Duration::from_millis(300);
+const DEFAULT_FLY_PADDING: f64 = 50.0;
+const DEFAULT_SPIDER_RADIUS: f64 = 20.0;
+const DEFAULT_SPIDER_SPACING: f64 = 24.0;
+
+/// Defines what happens when a user clicks on a cluster of points.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ClusterExpansion {
+    /// The map is animated to the bounds of the cluster members.
+    FlyTo {
+        /// Duration of the animation.
+        duration: Duration,
+        /// Space in pixels left between the cluster members and the edges of the map.
+        padding: f64,
+    },
+    /// Members of the cluster are spread on a circle around the cluster center, so that each
+    /// member can be selected individually.
+    Spiderfy {
+        /// Minimum radius of the circle in pixels.
+        radius: f64,
+        /// Minimum distance in pixels between two neighbouring members on the circle. If there are
+        /// too many members to fit into the circle of `radius`, the radius is increased.
+        spacing: f64,
+    },
+}
+
+impl ClusterExpansion {
+    /// Fly to expansion with the default animation parameters.
+    pub fn fly_to() -> Self {
+        Self::FlyTo {
+            duration: DEFAULT_FLY_DURATION,
+            padding: DEFAULT_FLY_PADDING,
+        }
+    }
+
+    /// Spiderfy expansion with the default radius and spacing.
+    pub fn spiderfy() -> Self {
+        Self::Spiderfy {
+            radius: DEFAULT_SPIDER_RADIUS,
+            spacing: DEFAULT_SPIDER_SPACING,
+        }
+    }
+}
+
+impl Default for ClusterExpansion {
+    fn default() -> Self {
+        Self::spiderfy()
+    }
+}
+
+/// Result of expanding a cluster.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExpandedCluster<Id> {
+    /// The map is being animated to show the cluster members.
+    Animating,
+    /// Ids of the members and the screen positions (in pixels from the top-left corner) they were
+    /// spread to, in the order the members were given.
+    Spread(Vec<(Id, Point2)>),
+}
+
+/// Event handler that expands clusters of points when a user clicks on them.
+///
+/// The handler itself does not know anything about clusters. Instead, it is given a `lookup`
+/// function, that for the map position of the click returns the members of the cluster as pairs
+/// of an id and a position in the map CRS, or `None` if there is no cluster at that position.
+/// The id can be anything that identifies a member for the application, e.g. a
+/// [`FeatureId`](crate::layer::feature_layer::FeatureId) or the payload of the feature.
+///
+/// When the expansion mode is [`ClusterExpansion::Spiderfy`], the ids and screen positions of the
+/// members of the last clicked cluster can be obtained with [`ClusterExpander::expanded`].
+pub struct ClusterExpander<Id, L> {
+    mode: ClusterExpansion,
+    lookup: L,
+    expanded: Mutex<Option<ExpandedCluster<Id>>>,
+}
+
+impl<Id, L> ClusterExpander<Id, L>
+where
+    Id: Clone + MaybeSend + MaybeSync,
+    L: Fn(&Point2, &MapView) -> Option<Vec<(Id, Point2)>> + MaybeSend + MaybeSync,
+{
+    /// Creates a new handler.
+    pub fn new(mode: ClusterExpansion, lookup: L) -> Self {
+        Self {
+            mode,
+            lookup,
+            expanded: Mutex::new(None),
+        }
+    }
+
+    /// Current expansion mode.
+    pub fn mode(&self) -> ClusterExpansion {
+        self.mode
+    }
+
+    /// Sets the expansion mode.
+    pub fn set_mode(&mut self, mode: ClusterExpansion) {
+        self.mode = mode;
+    }
+
+    /// Result of the last cluster expansion, if any.
+    pub fn expanded(&self) -> Option<ExpandedCluster<Id>> {
+        self.expanded.lock().clone()
+    }
+
+    /// Forgets the result of the last expansion, e.g. when the spread members are closed.
+    pub fn collapse(&self) {
+        *self.expanded.lock() = None;
+    }
+
+    /// Expands the cluster with the given `members` (ids and positions in the map CRS).
+    ///
+    /// Returns `None` if the cluster is empty or cannot be positioned on the screen.
+    pub fn expand(&self, map: &mut Map, members: &[(Id, Point2)]) -> Option<ExpandedCluster<Id>> {
+        let positions = members.iter().map(|(_, position)| *position);
+        let result = match self.mode {
+            ClusterExpansion::FlyTo { duration, padding } => {
+                let target = fit_view(map.view(), positions, padding)?;
+                map.animate_to(target, duration);
+                map.redraw();
+                ExpandedCluster::Animating
+            }
+            ClusterExpansion::Spiderfy { radius, spacing } => {
+                let bbox = Rect::from_points(positions)?;
+                let center = map.view().map_to_screen(bbox.center())?;
+                let spread = spider_positions(center, members.len(), radius, spacing);
+                ExpandedCluster::Spread(
+                    members
+                        .iter()
+                        .map(|(id, _)| id.clone())
+                        .zip(spread)
+                        .collect(),
+                )
+            }
+        };
+
+        *self.expanded.lock() = Some(result.clone());
+        Some(result)
+    }
+}
+
+impl<Id, L> UserEventHandler for ClusterExpander<Id, L>
+where
+    Id: Clone + MaybeSend + MaybeSync,
+    L: Fn(&Point2, &MapView) -> Option<Vec<(Id, Point2)>> + MaybeSend + MaybeSync,
+{
+    fn handle(&self, event: &UserEvent, map: &mut Map) -> EventPropagation {
+        let UserEvent::Click(
+            MouseButton::Left,
+            MouseEvent {
+                screen_pointer_position,
+                ..
+            },
+        ) = event
+        else {
+            return EventPropagation::Propagate;
+        };
+
+        let Some(position) = map.view().screen_to_map(*screen_pointer_position) else {
+            return EventPropagation::Propagate;
+        };
+
+        match (self.lookup)(&position, map.view()) {
+            Some(members) if members.len() > 1 => match self.expand(map, &members) {
+                Some(_) => EventPropagation::Stop,
+                None => EventPropagation::Propagate,
+            },
+            _ => {
+                self.collapse();
+                EventPropagation::Propagate
+            }
+        }
+    }
+}
+
+fn fit_view(
+    view: &MapView,
+    members: impl Iterator<Item = Point2>,
+    padding: f64,
+) -> Option<MapView> {
+    let bbox = Rect::from_points(members)?;
+    let size = view.size();
+    let width = size.width() - padding * 2.0;
+    let height = size.height() - padding * 2.0;
+    if width <= 0.0 || height <= 0.0 {
+        return None;
+    }
+
+    let resolution = (bbox.width() / width).max(bbox.height() / height);
+    let resolution = if resolution > 0.0 {
+        resolution
+    } else {
+        // All members are at the same point, so there is no extent to fit.
+        view.resolution() / 2.0
+    };
+
+    let center = bbox.center();
+    let current = view.projected_position()?;
+    Some(
+        view.translate(Point2::new(current.x(), current.y()) - center)
+            .with_resolution(resolution),
+    )
+}
+
+fn spider_positions(center: Point2, count: usize, radius: f64, spacing: f64) -> Vec<Point2> {
+    if count < 2 {
+        return vec![center; count];
+    }
+
+    let radius = radius.max(spacing * count as f64 / (2.0 * std::f64::consts::PI));
+    (0..count)
+        .map(|i| {
+            let angle =
+                2.0 * std::f64::consts::PI * i as f64 / count as f64 - std::f64::consts::FRAC_PI_2;
+            Point2::new(
+                center.x() + radius * angle.cos(),
+                center.y() + radius * angle.sin(),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use galileo_types::cartesian::Size;
+
+    use super::*;
+    use crate::control::MouseButtonsState;
+    use crate::MapBuilder;
+
+    fn test_map() -> Map {
+        let mut map = MapBuilder::default()
+            .with_projected_position(Point2::new(0.0, 0.0))
+            .with_resolution(1.0)
+            .build();
+        map.set_size(Size::new(200.0, 200.0));
+        map
+    }
+
+    fn click(x: f64, y: f64) -> UserEvent {
+        UserEvent::Click(
+            MouseButton::Left,
+            MouseEvent {
+                screen_pointer_position: Point2::new(x, y),
+                buttons: MouseButtonsState::default(),
+            },
+        )
+    }
+
+    #[test]
+    fn spiderfy_spreads_coincident_points() {
+        let mut map = test_map();
+        let expander =
+            ClusterExpander::new(ClusterExpansion::spiderfy(), |_: &Point2, _: &MapView| {
+                Some(vec![
+                    ("first", Point2::new(10.0, 10.0)),
+                    ("second", Point2::new(10.0, 10.0)),
+                ])
+            });
+
+        let propagation = expander.handle(&click(110.0, 90.0), &mut map);
+        assert!(matches!(propagation, EventPropagation::Stop));
+
+        let Some(ExpandedCluster::Spread(members)) = expander.expanded() else {
+            panic!("cluster was not spread");
+        };
+        let ids: Vec<_> = members.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, ["first", "second"]);
+        assert!(members[0].1.distance_sq(&members[1].1) > 1.0);
+    }
+
+    #[test]
+    fn fly_to_animates_to_cluster_bounds() {
+        let mut map = test_map();
+        let expander =
+            ClusterExpander::new(ClusterExpansion::fly_to(), |_: &Point2, _: &MapView| {
+                Some(vec![
+                    (1, Point2::new(10.0, 10.0)),
+                    (2, Point2::new(20.0, 20.0)),
+                ])
+            });
+
+        expander.handle(&click(110.0, 90.0), &mut map);
+
+        assert_eq!(expander.expanded(), Some(ExpandedCluster::Animating));
+        let target = map.target_view();
+        assert!(target.resolution() < 1.0);
+        let position = target.projected_position().expect("position is set");
+        assert!((position.x() - 15.0).abs() < 1e-6);
+        assert!((position.y() - 15.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn click_outside_cluster_is_propagated() {
+        let mut map = test_map();
+        let expander =
+            ClusterExpander::new(ClusterExpansion::spiderfy(), |_: &Point2, _: &MapView| {
+                None::<Vec<(usize, Point2)>>
+            });
+
+        let propagation = expander.handle(&click(10.0, 10.0), &mut map);
+        assert!(matches!(propagation, EventPropagation::Propagate));
+        assert_eq!(expander.expanded(), None);
+    }
+}
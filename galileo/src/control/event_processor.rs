@@ -70,6 +70,14 @@ impl EventProcessor {
     /// Handles the event.
     pub fn handle(&mut self, event: RawUserEvent, map: &mut Map) {
         if let Some(user_events) = self.process(event) {
+            // Hovering the pointer over the map does not interact with it.
+            if user_events
+                .iter()
+                .any(|event| !matches!(event, UserEvent::PointerMoved(..)))
+            {
+                map.register_interaction();
+            }
+
             for user_event in user_events {
                 log::trace!("Handling user event: {user_event:?}");
 
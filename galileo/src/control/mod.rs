@@ -16,9 +16,11 @@ use maybe_sync::{MaybeSend, MaybeSync};
 
 use crate::map::Map;
 
+mod cluster;
 mod event_processor;
 mod map;
 
+pub use cluster::{ClusterExpander, ClusterExpansion, ExpandedCluster};
 pub use event_processor::EventProcessor;
 pub use map::{MapController, MapControllerConfiguration};
 
@@ -9,6 +9,10 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use crate::error::GalileoError;
 
 /// An image that has been loaded into memory.
+///
+/// Bitmap images store pixels as straight (non-premultiplied) RGBA, which is what the renderer expects
+/// when blending images onto the map. Pixel data with premultiplied alpha must be loaded with
+/// [`DecodedImage::from_raw_premultiplied`], otherwise semi-transparent pixels are rendered too dark.
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
 pub struct DecodedImage(pub(crate) DecodedImageType);
 
@@ -45,12 +49,18 @@ impl std::hash::Hash for DecodedImageType {
 impl DecodedImage {
     /// Decode an image from a byte slice.
     ///
-    /// Attempts to guess the format of the image from the data. Non-RGBA images
+    /// The format of the image is detected from the data itself, so it does not matter what
+    /// extension or content type the image was served with. PNG, JPEG and WebP images are
+    /// supported, and AVIF images if the `avif` feature is enabled. Non-RGBA images
     /// will be converted to RGBA.
     #[cfg(feature = "image")]
     pub fn decode(bytes: &[u8]) -> Result<Self, GalileoError> {
         use image::GenericImageView;
-        let decoded = image::load_from_memory(bytes).map_err(|_| GalileoError::ImageDecode)?;
+        let format = image::guess_format(bytes)
+            .map_err(|_| GalileoError::InvalidImage("unknown image format".into()))?;
+        let decoded = image::load_from_memory_with_format(bytes, format).map_err(|err| {
+            GalileoError::InvalidImage(format!("failed to decode {format:?} image: {err}"))
+        })?;
         let bytes = decoded.to_rgba8();
         let dimensions = decoded.dimensions();
 
@@ -60,6 +70,24 @@ impl DecodedImage {
         }))
     }
 
+    /// Decode an image from the encoded bytes of an image file.
+    ///
+    /// Same as [`DecodedImage::decode`].
+    #[cfg(feature = "image")]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, GalileoError> {
+        Self::decode(bytes)
+    }
+
+    /// Load and decode an image file from the file system path.
+    ///
+    /// The format is detected from the file contents the same way as in [`DecodedImage::decode`].
+    #[cfg(all(feature = "image", not(target_arch = "wasm32")))]
+    pub fn from_path(path: &str) -> Result<Self, GalileoError> {
+        let bytes = std::fs::read(path)
+            .map_err(|err| GalileoError::InvalidImage(format!("failed to read {path}: {err}")))?;
+        Self::decode(&bytes)
+    }
+
     /// Create a DecodedImage from a buffer of raw RGBA pixels.
     // #[cfg(not(target_arch = "wasm32"))]
     pub fn from_raw(
@@ -77,6 +105,88 @@ impl DecodedImage {
         Ok(Self(DecodedImageType::Bitmap { bytes, dimensions }))
     }
 
+    /// Create a DecodedImage from a buffer of raw RGBA pixels with premultiplied alpha.
+    ///
+    /// The pixels are converted to straight alpha.
+    pub fn from_raw_premultiplied(
+        bytes: impl Into<Vec<u8>>,
+        dimensions: Size<u32>,
+    ) -> Result<Self, GalileoError> {
+        let mut bytes = bytes.into();
+        for pixel in bytes.chunks_exact_mut(4) {
+            let alpha = pixel[3] as u32;
+            for channel in &mut pixel[..3] {
+                *channel = match alpha {
+                    0 => 0,
+                    _ => ((*channel as u32 * 255 + alpha / 2) / alpha).min(255) as u8,
+                };
+            }
+        }
+
+        Self::from_raw(bytes, dimensions)
+    }
+
+    /// Returns the pixels of the image as RGBA with premultiplied alpha.
+    ///
+    /// Returns `None` if the image is not stored as a bitmap in memory.
+    pub fn to_premultiplied(&self) -> Option<Vec<u8>> {
+        match &self.0 {
+            DecodedImageType::Bitmap { bytes, .. } => {
+                let mut bytes = bytes.clone();
+                for pixel in bytes.chunks_exact_mut(4) {
+                    let alpha = pixel[3] as u32;
+                    for channel in &mut pixel[..3] {
+                        *channel = ((*channel as u32 * alpha + 127) / 255) as u8;
+                    }
+                }
+
+                Some(bytes)
+            }
+            #[cfg(target_arch = "wasm32")]
+            DecodedImageType::JsImageBitmap { .. } => None,
+        }
+    }
+
+    /// Returns a copy of the image scaled to the given size using a triangle (bilinear) filter.
+    ///
+    /// Pixels are filtered with premultiplied alpha, so fully transparent pixels do not bleed
+    /// their color into the edges of the image.
+    #[cfg(feature = "image")]
+    pub fn resized(&self, size: Size<u32>) -> Result<Self, GalileoError> {
+        let bytes = self
+            .to_premultiplied()
+            .ok_or_else(|| GalileoError::Generic("only bitmap images can be resized".into()))?;
+        let buffer =
+            image::RgbaImage::from_raw(self.width(), self.height(), bytes).ok_or_else(|| {
+                GalileoError::Generic("invalid image dimensions for buffer size".into())
+            })?;
+        let resized = image::imageops::resize(
+            &buffer,
+            size.width().max(1),
+            size.height().max(1),
+            image::imageops::FilterType::Triangle,
+        );
+        let dimensions = Size::new(resized.width(), resized.height());
+
+        Self::from_raw_premultiplied(resized.into_raw(), dimensions)
+    }
+
+    /// Generates mip levels of the image.
+    ///
+    /// Every level is half the size of the previous one, down to a single pixel. The image itself is
+    /// not included.
+    #[cfg(feature = "image")]
+    pub fn mip_levels(&self) -> Result<Vec<Self>, GalileoError> {
+        let mut levels = vec![];
+        let mut size = self.size();
+        while size.width() > 1 || size.height() > 1 {
+            size = Size::new((size.width() / 2).max(1), (size.height() / 2).max(1));
+            levels.push(self.resized(size)?);
+        }
+
+        Ok(levels)
+    }
+
     /// Return width of the image in pixels.
     pub fn width(&self) -> u32 {
         self.0.width()
@@ -201,6 +311,14 @@ mod serialization {
 mod tests {
     use super::*;
 
+    fn bitmap_bytes(image: &DecodedImage) -> &[u8] {
+        match &image.0 {
+            DecodedImageType::Bitmap { bytes, .. } => bytes,
+            #[cfg(target_arch = "wasm32")]
+            _ => panic!("bitmap expected"),
+        }
+    }
+
     #[cfg(feature = "image")]
     #[test]
     fn serialize_and_deserialize_decoded_image() {
@@ -214,4 +332,82 @@ mod tests {
         assert!(serialized.starts_with('\"'));
         assert!(serialized.ends_with('\"'));
     }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn decode_webp_tile() {
+        let image = DecodedImage::decode(include_bytes!("../test-data/tile.webp"))
+            .expect("failed to decode WebP");
+        assert_eq!(image.size(), Size::new(16, 8));
+    }
+
+    #[cfg(all(feature = "image", not(target_arch = "wasm32")))]
+    #[test]
+    fn from_path_decodes_file() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/test-data/tile.webp");
+        let image = DecodedImage::from_path(path).expect("failed to load image");
+        assert_eq!(
+            image,
+            DecodedImage::from_bytes(include_bytes!("../test-data/tile.webp"))
+                .expect("failed to decode image")
+        );
+    }
+
+    #[cfg(all(feature = "image", not(target_arch = "wasm32")))]
+    #[test]
+    fn from_path_missing_file_fails() {
+        let result = DecodedImage::from_path("does/not/exist.png");
+        assert!(matches!(result, Err(GalileoError::InvalidImage(_))));
+    }
+
+    #[test]
+    fn premultiplied_pixels_are_stored_straight() {
+        let image = DecodedImage::from_raw_premultiplied(
+            vec![128, 0, 0, 128, 10, 20, 30, 0],
+            Size::new(2, 1),
+        )
+        .expect("valid image");
+        assert_eq!(bitmap_bytes(&image), &[255, 0, 0, 128, 0, 0, 0, 0]);
+
+        assert_eq!(
+            image.to_premultiplied(),
+            Some(vec![128, 0, 0, 128, 0, 0, 0, 0])
+        );
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn resized_does_not_bleed_transparent_color() {
+        let image = DecodedImage::from_raw(vec![255, 0, 0, 255, 0, 0, 0, 0], Size::new(2, 1))
+            .expect("valid image");
+        let resized = image.resized(Size::new(1, 1)).expect("resized");
+        let bytes = bitmap_bytes(&resized);
+        assert_eq!(bytes[0], 255);
+        assert_eq!(&bytes[1..3], &[0, 0]);
+        assert!(bytes[3] > 0 && bytes[3] < 255);
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn mip_levels_halve_down_to_one_pixel() {
+        let image =
+            DecodedImage::from_raw(vec![255; 4 * 8 * 2], Size::new(8, 2)).expect("valid image");
+        let sizes: Vec<_> = image
+            .mip_levels()
+            .expect("mip levels")
+            .iter()
+            .map(DecodedImage::size)
+            .collect();
+        assert_eq!(
+            sizes,
+            vec![Size::new(4, 1), Size::new(2, 1), Size::new(1, 1)]
+        );
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn decode_unknown_format_fails() {
+        let result = DecodedImage::decode(b"definitely not an image");
+        assert!(matches!(result, Err(GalileoError::InvalidImage(_))));
+    }
 }
@@ -21,10 +21,20 @@ pub enum GalileoError {
     /// Item not found.
     #[error("item not found")]
     NotFound,
+    /// The operation did not complete in the given time.
+    #[error("operation timed out")]
+    Timeout,
+    /// The server responded with an unsuccessful HTTP status code.
+    #[error("request failed with HTTP status {0}")]
+    HttpStatus(u16),
     /// Image decoding error.
     #[cfg(feature = "image")]
     #[error("image decode error")]
     ImageDecode,
+    /// Image decoding error with the description of what went wrong.
+    #[cfg(any(feature = "image", feature = "svg"))]
+    #[error("image decode error: {0}")]
+    InvalidImage(String),
     /// Generic error - details are inside.
     #[error("{0}")]
     Generic(String),
@@ -34,6 +44,9 @@ pub enum GalileoError {
     /// Invalid configuration for the type.
     #[error("invalid configuration: {0}")]
     Configuration(String),
+    /// Error reading serialized data.
+    #[error("failed to deserialize data: {0}")]
+    Deserialization(String),
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -43,6 +56,13 @@ impl From<reqwest::Error> for GalileoError {
     }
 }
 
+#[cfg(all(feature = "mbtiles", not(target_arch = "wasm32")))]
+impl From<rusqlite::Error> for GalileoError {
+    fn from(value: rusqlite::Error) -> Self {
+        Self::FsIo(value.to_string())
+    }
+}
+
 impl From<std::io::Error> for GalileoError {
     fn from(value: Error) -> Self {
         Self::FsIo(value.to_string())
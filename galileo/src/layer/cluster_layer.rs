@@ -0,0 +1,397 @@
+//! [`ClusterLayer`] groups nearby point features into cluster markers.
+
+use std::any::Any;
+use std::collections::HashMap;
+
+use galileo_types::cartesian::{CartesianPoint3d, Point2, Point3};
+use galileo_types::geo::impls::projection::AddDimensionProjection;
+use galileo_types::geo::{ChainProjection, Crs, NewGeoPoint};
+use galileo_types::geometry::{Geom, Geometry};
+use maybe_sync::{MaybeSend, MaybeSync};
+use parking_lot::{Mutex, RwLock};
+
+use crate::layer::attribution::Attribution;
+use crate::layer::feature_layer::symbol::{Symbol, TextMarkerSymbol, TextProvider};
+use crate::layer::feature_layer::Feature;
+use crate::layer::Layer;
+use crate::messenger::Messenger;
+use crate::render::render_bundle::RenderBundle;
+use crate::render::{Canvas, PackedBundle, RenderOptions};
+use crate::view::MapView;
+
+/// Default size of the clustering grid cell in pixels.
+const DEFAULT_CLUSTER_RADIUS: f64 = 40.0;
+
+/// Layer that draws point features, grouping the points that are close to each other on the screen into clusters.
+///
+/// The screen is divided into a grid with the cell size of [`ClusterLayer::with_cluster_radius`] pixels, and all the
+/// points in one cell are drawn as a single cluster marker showing the number of the points in it. As the map is
+/// zoomed in, the cells cover smaller areas and the clusters break up into separate points, which are drawn with the
+/// symbol of the layer.
+///
+/// The grid is aligned to the origin of the map CRS, so the clusters do not change when the map is panned. They are
+/// recomputed in [`Layer::prepare`] only when the resolution of the map changes. Until then, the clusters computed for
+/// the previous resolution are drawn.
+///
+/// Features with geometries other than points are never clustered and are always drawn with the symbol of the layer.
+pub struct ClusterLayer<F, S> {
+    features: Vec<F>,
+    symbol: S,
+    cluster_symbol: TextMarkerSymbol,
+    cluster_radius: f64,
+    min_cluster_resolution: f64,
+    clusters: Mutex<Option<Clusters>>,
+    messenger: RwLock<Option<Box<dyn Messenger>>>,
+}
+
+/// Clusters computed for one resolution of the map.
+struct Clusters {
+    crs: Crs,
+    resolution: f64,
+    bundle: ClustersBundle,
+}
+
+enum ClustersBundle {
+    /// Computed in `prepare`, but not yet moved into GPU memory.
+    Prepared(RenderBundle),
+    Packed(Box<dyn PackedBundle>),
+}
+
+/// Marker of a cluster drawn by the cluster symbol.
+struct ClusterMarker {
+    text: String,
+    size: usize,
+}
+
+impl TextProvider for ClusterMarker {
+    fn get_text(&self) -> &str {
+        &self.text
+    }
+
+    fn label_priority(&self) -> f32 {
+        self.size as f32
+    }
+}
+
+impl<F, S> ClusterLayer<F, S> {
+    /// Creates a new layer that draws the `features` with the `symbol`.
+    ///
+    /// Clusters are drawn with the default [`TextMarkerSymbol`] with decluttering enabled, so if cluster markers
+    /// overlap, the larger cluster is shown.
+    pub fn new(features: Vec<F>, symbol: S) -> Self {
+        Self {
+            features,
+            symbol,
+            cluster_symbol: TextMarkerSymbol::new().with_declutter(true),
+            cluster_radius: DEFAULT_CLUSTER_RADIUS,
+            min_cluster_resolution: 0.0,
+            clusters: Mutex::new(None),
+            messenger: RwLock::new(None),
+        }
+    }
+
+    /// Sets the symbol used to draw cluster markers. The text of a marker is the number of the points in the cluster.
+    pub fn with_cluster_symbol(mut self, cluster_symbol: TextMarkerSymbol) -> Self {
+        self.cluster_symbol = cluster_symbol;
+        self
+    }
+
+    /// Sets the size of the clustering grid cell in pixels.
+    pub fn with_cluster_radius(mut self, radius: f64) -> Self {
+        self.cluster_radius = radius;
+        self
+    }
+
+    /// Disables clustering when the map resolution is not greater than the given value, so that all points are
+    /// drawn separately when the map is zoomed in enough.
+    pub fn with_min_cluster_resolution(mut self, resolution: f64) -> Self {
+        self.min_cluster_resolution = resolution;
+        self
+    }
+
+    /// Features of the layer.
+    pub fn features(&self) -> &[F] {
+        &self.features
+    }
+
+    /// Replaces the features of the layer. The clusters will be recomputed on the next [`Layer::prepare`] call.
+    pub fn set_features(&mut self, features: Vec<F>) {
+        self.features = features;
+        self.invalidate();
+    }
+
+    /// Changes the symbol used to draw the features that are not clustered.
+    pub fn set_symbol(&mut self, symbol: S) {
+        self.symbol = symbol;
+        self.invalidate();
+    }
+
+    fn invalidate(&mut self) {
+        *self.clusters.get_mut() = None;
+        if let Some(messenger) = self.messenger.read().as_ref() {
+            messenger.request_redraw();
+        }
+    }
+}
+
+impl<F, S> ClusterLayer<F, S>
+where
+    F: Feature,
+    F::Geom: Geometry,
+    <F::Geom as Geometry>::Point: NewGeoPoint + 'static,
+    S: Symbol<F>,
+{
+    fn render_bundle(&self, crs: &Crs, resolution: f64) -> Option<RenderBundle> {
+        let Some(projection) = crs.get_projection::<<F::Geom as Geometry>::Point, Point2>() else {
+            log::warn!("Cannot draw cluster layer: map CRS {crs:?} has no projection");
+            return None;
+        };
+        let projection =
+            ChainProjection::new(projection, Box::new(AddDimensionProjection::new(0.0)));
+
+        let mut bundle = RenderBundle::default();
+        let mut points = vec![];
+        for (index, feature) in self.features.iter().enumerate() {
+            match feature.geometry().project(&projection) {
+                Some(Geom::Point(point)) if resolution > self.min_cluster_resolution => {
+                    points.push((index, point));
+                }
+                Some(geometry) => self
+                    .symbol
+                    .render(feature, &geometry, resolution, &mut bundle),
+                None => {}
+            }
+        }
+
+        for cluster in cluster_points(&points, self.cluster_radius * resolution) {
+            if let [index] = cluster[..] {
+                let (feature_index, point) = points[index];
+                self.symbol.render(
+                    &self.features[feature_index],
+                    &Geom::Point(point),
+                    resolution,
+                    &mut bundle,
+                );
+                continue;
+            }
+
+            let count = cluster.len() as f64;
+            let (x, y) = cluster.iter().fold((0.0, 0.0), |(x, y), &index| {
+                let point = points[index].1;
+                (x + point.x(), y + point.y())
+            });
+            let marker = ClusterMarker {
+                text: cluster.len().to_string(),
+                size: cluster.len(),
+            };
+            self.cluster_symbol.render(
+                &marker,
+                &Geom::Point(Point3::new(x / count, y / count, 0.0)),
+                resolution,
+                &mut bundle,
+            );
+        }
+
+        Some(bundle)
+    }
+}
+
+/// Groups the points into the cells of the grid with the given cell size. Returns the indices of the points in
+/// each group in the order the groups first appear in the `points`.
+fn cluster_points(points: &[(usize, Point3)], cell_size: f64) -> Vec<Vec<usize>> {
+    if cell_size.is_nan() || cell_size <= 0.0 {
+        return (0..points.len()).map(|index| vec![index]).collect();
+    }
+
+    let mut cells = HashMap::new();
+    let mut clusters: Vec<Vec<usize>> = vec![];
+    for (index, (_, point)) in points.iter().enumerate() {
+        let cell = (
+            (point.x() / cell_size).floor() as i64,
+            (point.y() / cell_size).floor() as i64,
+        );
+        let cluster_index = *cells.entry(cell).or_insert_with(|| {
+            clusters.push(vec![]);
+            clusters.len() - 1
+        });
+        clusters[cluster_index].push(index);
+    }
+
+    clusters
+}
+
+impl<F, S> Layer for ClusterLayer<F, S>
+where
+    F: Feature + MaybeSend + MaybeSync + 'static,
+    F::Geom: Geometry,
+    <F::Geom as Geometry>::Point: NewGeoPoint + 'static,
+    S: Symbol<F> + MaybeSend + MaybeSync + 'static,
+{
+    fn render(&self, _view: &MapView, canvas: &mut dyn Canvas) {
+        let mut clusters = self.clusters.lock();
+        let Some(clusters) = clusters.as_mut() else {
+            return;
+        };
+
+        if let ClustersBundle::Prepared(bundle) = &clusters.bundle {
+            clusters.bundle = ClustersBundle::Packed(canvas.pack_bundle(bundle));
+        }
+
+        if let ClustersBundle::Packed(bundle) = &clusters.bundle {
+            canvas.draw_bundles(&[&**bundle], RenderOptions { antialias: true });
+        }
+    }
+
+    fn prepare(&self, view: &MapView) {
+        let resolution = view.resolution();
+        if self
+            .clusters
+            .lock()
+            .as_ref()
+            .is_some_and(|c| &c.crs == view.crs() && c.resolution == resolution)
+        {
+            return;
+        }
+
+        let Some(bundle) = self.render_bundle(view.crs(), resolution) else {
+            return;
+        };
+        *self.clusters.lock() = Some(Clusters {
+            crs: view.crs().clone(),
+            resolution,
+            bundle: ClustersBundle::Prepared(bundle),
+        });
+
+        if let Some(messenger) = self.messenger.read().as_ref() {
+            messenger.request_redraw();
+        }
+    }
+
+    fn is_ready(&self, view: &MapView) -> bool {
+        self.clusters
+            .lock()
+            .as_ref()
+            .is_some_and(|c| &c.crs == view.crs() && c.resolution == view.resolution())
+    }
+
+    fn set_messenger(&mut self, messenger: Box<dyn Messenger>) {
+        *self.messenger.write() = Some(messenger);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn attribution(&self) -> Option<Attribution> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use galileo_types::cartesian::Size;
+    use galileo_types::geo::impls::GeoPoint2d;
+    use galileo_types::latlon;
+
+    use super::*;
+    use crate::layer::feature_layer::symbol::CirclePointSymbol;
+    use crate::Color;
+
+    fn point(x: f64, y: f64) -> Point3 {
+        Point3::new(x, y, 0.0)
+    }
+
+    #[test]
+    fn points_in_one_cell_are_clustered() {
+        let points = [
+            (0, point(1.0, 1.0)),
+            (1, point(25.0, 1.0)),
+            (2, point(9.0, 9.0)),
+            (3, point(-1.0, 1.0)),
+        ];
+
+        assert_eq!(
+            cluster_points(&points, 10.0),
+            vec![vec![0, 2], vec![1], vec![3]]
+        );
+        assert_eq!(cluster_points(&points, 100.0), vec![vec![0, 1, 2], vec![3]]);
+    }
+
+    #[test]
+    fn zero_cell_size_does_not_cluster() {
+        let points = [(0, point(1.0, 1.0)), (1, point(1.0, 1.0))];
+        assert_eq!(cluster_points(&points, 0.0), vec![vec![0], vec![1]]);
+    }
+
+    struct TestBundle;
+
+    impl PackedBundle for TestBundle {
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    #[derive(Default)]
+    struct CountingCanvas {
+        packed: Cell<usize>,
+    }
+
+    impl Canvas for CountingCanvas {
+        fn size(&self) -> Size {
+            Size::new(100.0, 100.0)
+        }
+
+        fn pack_bundle(&self, _bundle: &RenderBundle) -> Box<dyn PackedBundle> {
+            self.packed.set(self.packed.get() + 1);
+            Box::new(TestBundle)
+        }
+
+        fn draw_bundles(&mut self, _bundles: &[&dyn PackedBundle], _options: RenderOptions) {}
+
+        fn draw_bundles_with_opacity(
+            &mut self,
+            _bundles: &[(&dyn PackedBundle, f32)],
+            _options: RenderOptions,
+        ) {
+        }
+
+        fn draw_screen_sets(&mut self) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn clusters_are_recomputed_only_on_resolution_change() {
+        let layer: ClusterLayer<GeoPoint2d, _> = ClusterLayer::new(
+            vec![latlon!(0.0, 0.0), latlon!(0.01, 0.01)],
+            CirclePointSymbol::new(Color::RED, 5.0),
+        );
+        let view = MapView::new(&latlon!(0.0, 0.0), 100.0).with_size(Size::new(100.0, 100.0));
+        let mut canvas = CountingCanvas::default();
+
+        layer.render(&view, &mut canvas);
+        assert_eq!(canvas.packed.get(), 0);
+        assert!(!layer.is_ready(&view));
+
+        layer.prepare(&view);
+        assert!(layer.is_ready(&view));
+        layer.render(&view, &mut canvas);
+        let panned = view.translate_by_pixels(Point2::new(0.0, 0.0), Point2::new(30.0, 10.0));
+        layer.prepare(&panned);
+        layer.render(&panned, &mut canvas);
+        assert_eq!(canvas.packed.get(), 1);
+
+        let zoomed = view.with_resolution(10.0);
+        layer.render(&zoomed, &mut canvas);
+        assert_eq!(canvas.packed.get(), 1);
+        layer.prepare(&zoomed);
+        layer.render(&zoomed, &mut canvas);
+        assert_eq!(canvas.packed.get(), 2);
+    }
+}
@@ -1,22 +1,82 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
 use bytes::Bytes;
 use log::debug;
+use parking_lot::Mutex;
 
 use crate::error::GalileoError;
 use crate::layer::data_provider::PersistentCacheController;
 
+/// Suffix of the temporary files the data is written to before being moved to the cache file.
+const TMP_SUFFIX: &str = ".galileo-tmp";
+
+/// Name of the file in the cache folder that lists the paths of the cache files relative to the folder, one per
+/// line.
+const INDEX_FILE_NAME: &str = ".galileo-cache-index";
+
 /// Stores the cached data as a set of files in the specified folder. It generates file names from the given urls.
 ///
-/// Currently, there is no eviction mechanism.
+/// By default the cache grows without bound. Use [`FileCacheController::with_max_size()`] to limit the total size of
+/// the cached files. In this case the oldest files are deleted when a new entry is inserted and the limit is
+/// exceeded.
+///
+/// The paths of the written files are recorded in an index file in the cache folder. Only the files listed in the
+/// index are counted towards the size limit and evicted, so other files in the folder are never deleted. Every file
+/// is listed once, and the lines of the evicted files are dropped from the index, so the index stays proportional
+/// to the number of cached files.
+///
+/// Every entry is first written to a temporary file and then renamed, so an interrupted write or eviction never
+/// leaves a partially written entry in the cache.
+///
+/// Entries are kept forever unless a time-to-live is set with [`FileCacheController::with_ttl()`]. The modification
+/// time of the file is used as the time the entry was inserted, so the age of the entries survives restarts.
 #[derive(Debug, Clone)]
 pub struct FileCacheController {
     folder_path: PathBuf,
+    size_limit: Option<Arc<Mutex<SizeLimit>>>,
+    ttl: Option<Duration>,
+    index: Arc<Mutex<Option<CacheIndex>>>,
+}
+
+/// Paths of the cache files listed in the index file. Loaded from the index file on first use.
+#[derive(Debug)]
+struct CacheIndex {
+    paths: HashSet<PathBuf>,
+    /// Number of lines in the index file that list evicted files.
+    stale_lines: usize,
+}
+
+/// Sizes and ages of the cache files, used to evict the oldest files when the size limit is exceeded.
+#[derive(Debug)]
+struct SizeLimit {
+    max_size: u64,
+    current_size: u64,
+    files: HashMap<PathBuf, (u64, FileAge)>,
+    by_age: BTreeMap<FileAge, PathBuf>,
+    counter: u64,
 }
 
+/// Modification time of the file. The counter distinguishes files with the same modification time.
+type FileAge = (SystemTime, u64);
+
 impl PersistentCacheController<str, Bytes> for FileCacheController {
     fn get(&self, key: &str) -> Option<Bytes> {
         let file_path = self.get_file_path(key);
+        if let Some(ttl) = self.ttl {
+            let modified = std::fs::metadata(&file_path)
+                .and_then(|m| m.modified())
+                .ok()?;
+            if !is_fresh(modified, ttl) {
+                debug!("Cache entry {key} is expired");
+                return None;
+            }
+        }
+
         if let Ok(bytes) = std::fs::read(file_path) {
             Some(bytes.into())
         } else {
@@ -30,8 +90,22 @@ impl PersistentCacheController<str, Bytes> for FileCacheController {
             Some(folder) => match ensure_folder_exists(folder) {
                 Ok(()) => {
                     debug!("Saving entry {key} to the cache file {file_path:?}");
-                    std::fs::write(&file_path, data)?;
+                    write_atomically(&file_path, data)?;
                     debug!("Entry {key} saved to cache file {file_path:?}");
+
+                    if let Err(err) = self.add_to_index(&file_path) {
+                        log::warn!("Failed to add cache file {file_path:?} to the index: {err}");
+                    }
+
+                    if let Some(size_limit) = &self.size_limit {
+                        let evicted = size_limit.lock().insert(file_path, data.len() as u64);
+                        if let Err(err) = self.remove_from_index(&evicted) {
+                            log::warn!(
+                                "Failed to remove evicted files from the cache index: {err}"
+                            );
+                        }
+                    }
+
                     Ok(())
                 }
                 Err(err) => {
@@ -61,9 +135,186 @@ impl FileCacheController {
         })?;
         Ok(Self {
             folder_path: path.as_ref().into(),
+            size_limit: None,
+            ttl: None,
+            index: Arc::new(Mutex::new(None)),
         })
     }
 
+    /// Sets the time-to-live of the cache entries. Entries inserted more than `ttl` ago are not returned by `get`,
+    /// so that the data is loaded again and the entry is replaced. `None` keeps the entries forever, which is the
+    /// default.
+    pub fn with_ttl(mut self, ttl: Option<Duration>) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Time-to-live of the cache entries, if set.
+    pub fn ttl(&self) -> Option<Duration> {
+        self.ttl
+    }
+
+    /// Limits the total size of the cached files to `max_size` bytes.
+    ///
+    /// The files previously written to the cache folder are counted towards the limit, and if the limit is already
+    /// exceeded, the oldest of them are deleted.
+    pub fn with_max_size(mut self, max_size: u64) -> Result<Self, GalileoError> {
+        let files = self.indexed_files().map_err(|err| {
+            GalileoError::FsIo(format!(
+                "failed to read file cache index in {:?}: {err}",
+                self.folder_path
+            ))
+        })?;
+
+        let mut size_limit = SizeLimit {
+            max_size,
+            current_size: 0,
+            files: HashMap::new(),
+            by_age: BTreeMap::new(),
+            counter: 0,
+        };
+        for (path, size, modified) in files {
+            size_limit.add(path, size, modified);
+        }
+        size_limit.evict();
+
+        // Drop the evicted and duplicate entries from the index.
+        let paths: HashSet<_> = size_limit.files.keys().cloned().collect();
+        self.write_index(&paths)?;
+        *self.index.lock() = Some(CacheIndex {
+            paths,
+            stale_lines: 0,
+        });
+
+        self.size_limit = Some(Arc::new(Mutex::new(size_limit)));
+        Ok(self)
+    }
+
+    /// Maximum total size of the cached files in bytes, if the limit is set.
+    pub fn max_size(&self) -> Option<u64> {
+        self.size_limit
+            .as_ref()
+            .map(|size_limit| size_limit.lock().max_size)
+    }
+
+    /// Total size of the cached files in bytes.
+    ///
+    /// If the size limit is not set, this method checks every file listed in the cache index, which can be slow for
+    /// large caches.
+    pub fn current_size(&self) -> Result<u64, GalileoError> {
+        if let Some(size_limit) = &self.size_limit {
+            return Ok(size_limit.lock().current_size);
+        }
+
+        Ok(self.indexed_files()?.iter().map(|(_, size, _)| size).sum())
+    }
+
+    /// Appends the path of the cache file to the index, unless it is already listed there.
+    fn add_to_index(&self, file_path: &Path) -> std::io::Result<()> {
+        let Some(line) = self.index_line(file_path) else {
+            return Ok(());
+        };
+
+        self.with_index(|index| {
+            if index.paths.contains(file_path) {
+                return Ok(());
+            }
+
+            std::fs::File::options()
+                .create(true)
+                .append(true)
+                .open(self.folder_path.join(INDEX_FILE_NAME))?
+                .write_all(line.as_bytes())?;
+            index.paths.insert(file_path.to_path_buf());
+            Ok(())
+        })
+    }
+
+    /// Forgets the evicted files. The index file is rewritten once it has more lines of evicted files than of the
+    /// cached ones.
+    fn remove_from_index(&self, evicted: &[PathBuf]) -> std::io::Result<()> {
+        if evicted.is_empty() {
+            return Ok(());
+        }
+
+        self.with_index(|index| {
+            for path in evicted {
+                if index.paths.remove(path) {
+                    index.stale_lines += 1;
+                }
+            }
+
+            if index.stale_lines > index.paths.len() {
+                self.write_index(&index.paths)?;
+                index.stale_lines = 0;
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Calls `f` with the index of the cache files, loading it from the index file if needed.
+    fn with_index<T>(
+        &self,
+        f: impl FnOnce(&mut CacheIndex) -> std::io::Result<T>,
+    ) -> std::io::Result<T> {
+        let mut index = self.index.lock();
+        let index = match &mut *index {
+            Some(index) => index,
+            empty => empty.insert(CacheIndex {
+                paths: self.read_index()?,
+                stale_lines: 0,
+            }),
+        };
+
+        f(index)
+    }
+
+    /// Replaces the content of the index file with the given paths.
+    fn write_index(&self, paths: &HashSet<PathBuf>) -> std::io::Result<()> {
+        let index: String = paths
+            .iter()
+            .filter_map(|path| self.index_line(path))
+            .collect();
+        write_atomically(&self.folder_path.join(INDEX_FILE_NAME), index.as_bytes())
+    }
+
+    /// Line of the index for the cache file, or `None` if the path cannot be stored in the index.
+    fn index_line(&self, file_path: &Path) -> Option<String> {
+        let relative = file_path.strip_prefix(&self.folder_path).ok()?.to_str()?;
+        (!relative.contains('\n')).then(|| format!("{relative}\n"))
+    }
+
+    /// Reads the paths listed in the index file.
+    fn read_index(&self) -> std::io::Result<HashSet<PathBuf>> {
+        let index = match std::fs::read_to_string(self.folder_path.join(INDEX_FILE_NAME)) {
+            Ok(index) => index,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(HashSet::new()),
+            Err(err) => return Err(err),
+        };
+
+        Ok(index
+            .lines()
+            .map(|relative| self.folder_path.join(relative))
+            .collect())
+    }
+
+    /// Collects paths, sizes and modification times of the existing files listed in the index.
+    fn indexed_files(&self) -> std::io::Result<Vec<(PathBuf, u64, SystemTime)>> {
+        let mut files = vec![];
+        for path in self.read_index()? {
+            let Ok(metadata) = std::fs::metadata(&path) else {
+                continue;
+            };
+            if metadata.is_file() {
+                let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                files.push((path, metadata.len(), modified));
+            }
+        }
+
+        Ok(files)
+    }
+
     fn get_file_path(&self, url: &str) -> PathBuf {
         let stripped = if let Some(v) = url.strip_prefix("http://") {
             v
@@ -77,6 +328,219 @@ impl FileCacheController {
     }
 }
 
+impl SizeLimit {
+    /// Adds the file and returns the paths of the evicted files.
+    fn insert(&mut self, path: PathBuf, size: u64) -> Vec<PathBuf> {
+        self.add(path, size, SystemTime::now());
+        self.evict()
+    }
+
+    fn add(&mut self, path: PathBuf, size: u64, modified: SystemTime) {
+        self.remove(&path);
+
+        self.counter += 1;
+        let age = (modified, self.counter);
+        self.current_size += size;
+        self.by_age.insert(age, path.clone());
+        self.files.insert(path, (size, age));
+    }
+
+    fn remove(&mut self, path: &Path) {
+        if let Some((size, age)) = self.files.remove(path) {
+            self.current_size -= size;
+            self.by_age.remove(&age);
+        }
+    }
+
+    /// Deletes the oldest files until the total size is within the limit. Returns the paths of the deleted files.
+    fn evict(&mut self) -> Vec<PathBuf> {
+        let mut evicted = vec![];
+        while self.current_size > self.max_size {
+            let Some((_, path)) = self.by_age.pop_first() else {
+                break;
+            };
+
+            debug!("Evicting cache file {path:?}");
+            if let Err(err) = std::fs::remove_file(&path) {
+                if err.kind() != std::io::ErrorKind::NotFound {
+                    log::warn!("Failed to remove cache file {path:?}: {err}");
+                }
+            }
+
+            if let Some((size, _)) = self.files.remove(&path) {
+                self.current_size -= size;
+            }
+
+            evicted.push(path);
+        }
+
+        evicted
+    }
+}
+
+/// Files with the modification time in the future (e.g. after the system clock was changed) are considered fresh.
+fn is_fresh(modified: SystemTime, ttl: Duration) -> bool {
+    SystemTime::now()
+        .duration_since(modified)
+        .map_or(true, |age| age < ttl)
+}
+
 fn ensure_folder_exists(folder_path: &Path) -> std::io::Result<()> {
     std::fs::create_dir_all(folder_path)
 }
+
+/// Writes the data to a temporary file and then moves it to the `file_path`, so that the file at `file_path` is
+/// never partially written.
+fn write_atomically(file_path: &Path, data: &[u8]) -> std::io::Result<()> {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let mut tmp_name = file_path.as_os_str().to_owned();
+    tmp_name.push(format!(
+        ".{}{TMP_SUFFIX}",
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let tmp_path = PathBuf::from(tmp_name);
+
+    std::fs::write(&tmp_path, data)?;
+    std::fs::rename(&tmp_path, file_path).inspect_err(|_| {
+        let _ = std::fs::remove_file(&tmp_path);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_folder(name: &str) -> PathBuf {
+        let path = Path::new("target").join("test_file_cache").join(name);
+        let _ = std::fs::remove_dir_all(&path);
+        path
+    }
+
+    #[test]
+    fn evicts_oldest_entries_when_size_limit_exceeded() {
+        let cache = FileCacheController::new(empty_folder("evicts_oldest"))
+            .unwrap()
+            .with_max_size(10)
+            .unwrap();
+        assert_eq!(cache.max_size(), Some(10));
+
+        cache
+            .insert("https://example.com/a", &Bytes::from(vec![0; 4]))
+            .unwrap();
+        cache
+            .insert("https://example.com/b", &Bytes::from(vec![0; 4]))
+            .unwrap();
+        assert_eq!(cache.current_size().unwrap(), 8);
+
+        cache
+            .insert("https://example.com/c", &Bytes::from(vec![0; 4]))
+            .unwrap();
+        assert_eq!(cache.current_size().unwrap(), 8);
+        assert!(cache.get("https://example.com/a").is_none());
+        assert!(cache.get("https://example.com/b").is_some());
+        assert!(cache.get("https://example.com/c").is_some());
+    }
+
+    #[test]
+    fn index_lists_every_cached_file_once() {
+        let path = empty_folder("index_lists_once");
+        let cache = FileCacheController::new(&path)
+            .unwrap()
+            .with_max_size(8)
+            .unwrap();
+
+        for _ in 0..3 {
+            cache
+                .insert("https://example.com/a", &Bytes::from(vec![0; 4]))
+                .unwrap();
+        }
+        for i in 0..10 {
+            cache
+                .insert(
+                    &format!("https://example.com/{i}"),
+                    &Bytes::from(vec![0; 4]),
+                )
+                .unwrap();
+        }
+
+        let index = std::fs::read_to_string(path.join(INDEX_FILE_NAME)).unwrap();
+        assert!(index.lines().count() <= 4);
+        assert!(index.lines().any(|line| line == "example.com/9"));
+        assert!(!index.lines().any(|line| line == "example.com/a"));
+        assert_eq!(cache.current_size().unwrap(), 8);
+    }
+
+    #[test]
+    fn expired_entries_are_not_returned() {
+        let path = empty_folder("expired_entries");
+        let cache = FileCacheController::new(&path)
+            .unwrap()
+            .with_ttl(Some(Duration::from_secs(3600)));
+        assert_eq!(cache.ttl(), Some(Duration::from_secs(3600)));
+
+        cache
+            .insert("https://example.com/a", &Bytes::from(vec![0; 4]))
+            .unwrap();
+        assert!(cache.get("https://example.com/a").is_some());
+
+        std::fs::File::options()
+            .write(true)
+            .open(path.join("example.com/a"))
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(7200))
+            .unwrap();
+        assert!(cache.get("https://example.com/a").is_none());
+
+        let static_cache = cache.with_ttl(None);
+        assert!(static_cache.get("https://example.com/a").is_some());
+    }
+
+    #[test]
+    fn counts_existing_files() {
+        let path = empty_folder("existing_files");
+        let cache = FileCacheController::new(&path).unwrap();
+        assert_eq!(cache.max_size(), None);
+        cache
+            .insert("https://example.com/a", &Bytes::from(vec![0; 4]))
+            .unwrap();
+        cache
+            .insert("https://example.com/b", &Bytes::from(vec![0; 6]))
+            .unwrap();
+        assert_eq!(cache.current_size().unwrap(), 10);
+
+        let limited = FileCacheController::new(&path)
+            .unwrap()
+            .with_max_size(100)
+            .unwrap();
+        assert_eq!(limited.current_size().unwrap(), 10);
+    }
+
+    #[test]
+    fn does_not_evict_foreign_files() {
+        let path = empty_folder("foreign_files");
+        let cache = FileCacheController::new(&path).unwrap();
+        cache
+            .insert("https://example.com/a", &Bytes::from(vec![0; 4]))
+            .unwrap();
+
+        std::fs::create_dir_all(path.join("build")).unwrap();
+        std::fs::write(path.join("build/artifact"), [0; 20]).unwrap();
+        std::fs::write(path.join(format!("example.com/c.1{TMP_SUFFIX}")), [0; 3]).unwrap();
+
+        let limited = FileCacheController::new(&path)
+            .unwrap()
+            .with_max_size(4)
+            .unwrap();
+        assert_eq!(limited.current_size().unwrap(), 4);
+
+        limited
+            .insert("https://example.com/b", &Bytes::from(vec![0; 4]))
+            .unwrap();
+        assert_eq!(limited.current_size().unwrap(), 4);
+        assert!(limited.get("https://example.com/a").is_none());
+        assert!(limited.get("https://example.com/b").is_some());
+        assert!(path.join("build/artifact").exists());
+        assert!(path.join(format!("example.com/c.1{TMP_SUFFIX}")).exists());
+    }
+}
@@ -0,0 +1,296 @@
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use parking_lot::Mutex;
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
+
+use crate::error::GalileoError;
+use crate::layer::vector_tile_layer::tile_provider::loader::decompress;
+use crate::tile_schema::TileIndex;
+
+/// Reader of the tiles stored in an [MBTiles](https://github.com/mapbox/mbtiles-spec) file, a
+/// SQLite database commonly used to distribute tilesets for offline use.
+///
+/// The tiles are read from the `tiles` table, converting the `y` index of the tile to the TMS row
+/// number used by MBTiles. Vector tiles, which MBTiles stores gzip-compressed, are decompressed
+/// before they are returned.
+///
+/// This is a shared reader for [`MbTilesLoader`] and [`MbTilesRasterLoader`], which should be used
+/// to display the tiles on the map. SQLite queries are blocking, so the loaders read the tiles with
+/// [`MbTiles::load_tile_data`], which runs the query on the blocking thread pool of the runtime.
+///
+/// [`MbTilesLoader`]: crate::layer::vector_tile_layer::tile_provider::loader::MbTilesLoader
+/// [`MbTilesRasterLoader`]: crate::layer::raster_tile_layer::MbTilesRasterLoader
+pub struct MbTiles {
+    connection: Arc<Mutex<Connection>>,
+    metadata: MbTilesMetadata,
+}
+
+impl MbTiles {
+    /// Opens the MBTiles file at `path` for reading and loads its metadata.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, GalileoError> {
+        let connection = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        let metadata = MbTilesMetadata::read(&connection)?;
+
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+            metadata,
+        })
+    }
+
+    /// Metadata of the tileset.
+    pub fn metadata(&self) -> &MbTilesMetadata {
+        &self.metadata
+    }
+
+    /// Reads the data of the tile with the given index. Returns `None` if the file does not contain
+    /// the tile.
+    ///
+    /// This method blocks the current thread until the query is done. In async code use
+    /// [`MbTiles::load_tile_data`] instead.
+    pub fn tile_data(&self, index: TileIndex) -> Result<Option<Bytes>, GalileoError> {
+        read_tile_data(&self.connection.lock(), index, self.metadata.is_gzipped())
+    }
+
+    /// Reads the data of the tile with the given index on the blocking thread pool, so that the
+    /// query does not block the async runtime. Returns `None` if the file does not contain the tile.
+    pub async fn load_tile_data(&self, index: TileIndex) -> Result<Option<Bytes>, GalileoError> {
+        let connection = self.connection.clone();
+        let is_gzipped = self.metadata.is_gzipped();
+
+        tokio::task::spawn_blocking(move || read_tile_data(&connection.lock(), index, is_gzipped))
+            .await
+            .map_err(|err| GalileoError::Generic(err.to_string()))?
+    }
+}
+
+fn read_tile_data(
+    connection: &Connection,
+    index: TileIndex,
+    is_gzipped: bool,
+) -> Result<Option<Bytes>, GalileoError> {
+    let Some(tile_row) = tms_row(index) else {
+        return Ok(None);
+    };
+
+    let data: Option<Vec<u8>> = connection
+        .prepare_cached(
+            "SELECT tile_data FROM tiles WHERE zoom_level = ?1 AND tile_column = ?2 AND tile_row = ?3",
+        )?
+        .query_row(params![index.z, index.x, tile_row], |row| row.get(0))
+        .optional()?;
+
+    // Some writers do not compress vector tiles despite the specification, so the compression
+    // is detected by the leading bytes of the data.
+    match data {
+        Some(data) if is_gzipped => decompress(data.into()).map(Some),
+        data => Ok(data.map(Bytes::from)),
+    }
+}
+
+/// Content of the `metadata` table of an MBTiles file.
+#[derive(Debug, Clone, Default)]
+pub struct MbTilesMetadata {
+    entries: HashMap<String, String>,
+}
+
+impl MbTilesMetadata {
+    fn read(connection: &Connection) -> Result<Self, GalileoError> {
+        let mut statement = connection.prepare("SELECT name, value FROM metadata")?;
+        let entries = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { entries })
+    }
+
+    /// Value of the metadata entry with the given name.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.entries.get(name).map(String::as_str)
+    }
+
+    /// Format of the tile data: `pbf` for vector tiles, or the image format (e.g. `png`, `jpg`,
+    /// `webp`) for raster tiles.
+    pub fn format(&self) -> Option<&str> {
+        self.get("format")
+    }
+
+    /// Returns true if the file contains vector tiles.
+    pub fn is_vector(&self) -> bool {
+        self.format() == Some("pbf")
+    }
+
+    /// Range of z-levels of the tiles in the file, if specified by the `minzoom` and `maxzoom`
+    /// entries.
+    pub fn zoom_range(&self) -> Option<RangeInclusive<u32>> {
+        let min_zoom = self.get("minzoom")?.trim().parse().ok()?;
+        let max_zoom = self.get("maxzoom")?.trim().parse().ok()?;
+        Some(min_zoom..=max_zoom)
+    }
+
+    /// Vector tiles are always compressed with gzip, and some writers also set the `compression`
+    /// entry for other formats.
+    fn is_gzipped(&self) -> bool {
+        self.is_vector() || self.get("compression") == Some("gzip")
+    }
+}
+
+/// Row of the tile in the `tiles` table, which numbers the rows from the bottom as in TMS.
+fn tms_row(index: TileIndex) -> Option<i64> {
+    let rows = 1i64.checked_shl(index.z)?;
+    let row = rows - 1 - index.y as i64;
+    (0..rows).contains(&row).then_some(row)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn create_mbtiles(
+        name: &str,
+        metadata: &[(&str, &str)],
+        tiles: &[(u32, i32, i64, &[u8])],
+    ) -> PathBuf {
+        let path = std::env::temp_dir().join(name);
+        let _ = std::fs::remove_file(&path);
+        let connection = Connection::open(&path).unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE metadata (name TEXT, value TEXT);
+                 CREATE TABLE tiles (zoom_level INTEGER, tile_column INTEGER, tile_row INTEGER, tile_data BLOB);",
+            )
+            .unwrap();
+        for (name, value) in metadata {
+            connection
+                .execute("INSERT INTO metadata VALUES (?1, ?2)", params![name, value])
+                .unwrap();
+        }
+        for (z, x, row, data) in tiles {
+            connection
+                .execute(
+                    "INSERT INTO tiles VALUES (?1, ?2, ?3, ?4)",
+                    params![z, x, row, data],
+                )
+                .unwrap();
+        }
+
+        path
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn zlib(data: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::ZlibEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn reads_metadata() {
+        let path = create_mbtiles(
+            "galileo_mbtiles_reads_metadata.mbtiles",
+            &[("format", "png"), ("minzoom", "2"), ("maxzoom", "14")],
+            &[],
+        );
+
+        let mbtiles = MbTiles::open(&path).unwrap();
+        assert_eq!(mbtiles.metadata().format(), Some("png"));
+        assert!(!mbtiles.metadata().is_vector());
+        assert_eq!(mbtiles.metadata().zoom_range(), Some(2..=14));
+        assert_eq!(mbtiles.metadata().get("name"), None);
+    }
+
+    #[test]
+    fn flips_y_index() {
+        let path = create_mbtiles(
+            "galileo_mbtiles_flips_y_index.mbtiles",
+            &[("format", "png")],
+            &[(2, 1, 3, &b"top"[..]), (2, 1, 0, &b"bottom"[..])],
+        );
+
+        let mbtiles = MbTiles::open(&path).unwrap();
+        assert_eq!(
+            mbtiles.tile_data(TileIndex::new(1, 0, 2)).unwrap(),
+            Some(Bytes::from_static(b"top"))
+        );
+        assert_eq!(
+            mbtiles.tile_data(TileIndex::new(1, 3, 2)).unwrap(),
+            Some(Bytes::from_static(b"bottom"))
+        );
+        assert_eq!(mbtiles.tile_data(TileIndex::new(2, 0, 2)).unwrap(), None);
+        assert_eq!(mbtiles.tile_data(TileIndex::new(1, 4, 2)).unwrap(), None);
+    }
+
+    #[test]
+    fn decompresses_vector_tiles() {
+        let compressed = gzip(b"vector tile");
+        let zlib_compressed = zlib(b"zlib tile");
+        let path = create_mbtiles(
+            "galileo_mbtiles_decompresses_vector_tiles.mbtiles",
+            &[("format", "pbf")],
+            &[
+                (0, 0, 0, &compressed[..]),
+                (1, 0, 0, &b"uncompressed"[..]),
+                (1, 1, 0, &zlib_compressed[..]),
+            ],
+        );
+
+        let mbtiles = MbTiles::open(&path).unwrap();
+        assert_eq!(
+            mbtiles.tile_data(TileIndex::new(0, 0, 0)).unwrap(),
+            Some(Bytes::from_static(b"vector tile"))
+        );
+        assert_eq!(
+            mbtiles.tile_data(TileIndex::new(0, 1, 1)).unwrap(),
+            Some(Bytes::from_static(b"uncompressed"))
+        );
+        assert_eq!(
+            mbtiles.tile_data(TileIndex::new(1, 1, 1)).unwrap(),
+            Some(Bytes::from_static(b"zlib tile"))
+        );
+    }
+
+    #[tokio::test]
+    async fn loads_tile_data_without_blocking() {
+        let compressed = gzip(b"vector tile");
+        let path = create_mbtiles(
+            "galileo_mbtiles_loads_tile_data_without_blocking.mbtiles",
+            &[("format", "pbf")],
+            &[(0, 0, 0, &compressed[..])],
+        );
+
+        let mbtiles = MbTiles::open(&path).unwrap();
+        assert_eq!(
+            mbtiles
+                .load_tile_data(TileIndex::new(0, 0, 0))
+                .await
+                .unwrap(),
+            Some(Bytes::from_static(b"vector tile"))
+        );
+        assert_eq!(
+            mbtiles
+                .load_tile_data(TileIndex::new(0, 0, 1))
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn open_fails_for_missing_file() {
+        assert!(MbTiles::open(std::env::temp_dir().join("galileo_missing.mbtiles")).is_err());
+    }
+}
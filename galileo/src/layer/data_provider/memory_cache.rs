@@ -0,0 +1,263 @@
+use std::collections::{BTreeMap, HashMap};
+
+use bytes::Bytes;
+use parking_lot::Mutex;
+use web_time::{Duration, Instant};
+
+use crate::error::GalileoError;
+use crate::layer::data_provider::PersistentCacheController;
+
+/// Keeps the most recently used data in memory, evicting the least recently used entries when the
+/// size limit is reached.
+///
+/// The cache can be put in front of another cache (e.g. a [`FileCacheController`](super::FileCacheController))
+/// with [`MemoryCacheController::in_front_of()`] method. In this case the entries missing in memory are looked up
+/// in the next cache, and the inserted entries are written to both caches.
+///
+/// Entries are kept until evicted unless a time-to-live is set with [`MemoryCacheController::with_ttl()`]. Entries
+/// loaded from the next cache are counted as inserted at the time they were loaded, so the next cache should have
+/// the same or shorter time-to-live.
+///
+/// ```
+/// use bytes::Bytes;
+/// use galileo::layer::data_provider::{
+///     FileCacheController, MemoryCacheController, PersistentCacheController,
+/// };
+///
+/// let cache = MemoryCacheController::with_max_entries(1000)
+///     .in_front_of(FileCacheController::new("target")?);
+///
+/// cache.insert("https://example.com/1/0/0.pbf", &Bytes::from_static(b"tile"))?;
+/// assert!(cache.get("https://example.com/1/0/0.pbf").is_some());
+/// # Ok::<(), galileo::error::GalileoError>(())
+/// ```
+pub struct MemoryCacheController {
+    max_entries: usize,
+    max_bytes: usize,
+    state: Mutex<LruState>,
+    next: Option<Box<dyn PersistentCacheController<str, Bytes>>>,
+    ttl: Option<Duration>,
+}
+
+#[derive(Default)]
+struct LruState {
+    entries: HashMap<String, LruEntry>,
+    // Keys of the entries ordered by the time of last use, from the oldest to the newest.
+    usage: BTreeMap<u64, String>,
+    counter: u64,
+    bytes: usize,
+}
+
+struct LruEntry {
+    data: Bytes,
+    last_used: u64,
+    inserted_at: Instant,
+}
+
+impl MemoryCacheController {
+    /// Creates a cache that holds at most `max_entries` entries.
+    pub fn with_max_entries(max_entries: usize) -> Self {
+        Self::new(max_entries, usize::MAX)
+    }
+
+    /// Creates a cache that holds at most `max_bytes` bytes of data. Entries larger than the
+    /// limit are not cached.
+    pub fn with_max_bytes(max_bytes: usize) -> Self {
+        Self::new(usize::MAX, max_bytes)
+    }
+
+    fn new(max_entries: usize, max_bytes: usize) -> Self {
+        Self {
+            max_entries,
+            max_bytes,
+            state: Mutex::new(LruState::default()),
+            next: None,
+            ttl: None,
+        }
+    }
+
+    /// Sets the time-to-live of the entries. Entries inserted more than `ttl` ago are dropped instead of being
+    /// returned by `get`. `None` keeps the entries until they are evicted, which is the default.
+    pub fn with_ttl(mut self, ttl: Option<Duration>) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Time-to-live of the entries, if set.
+    pub fn ttl(&self) -> Option<Duration> {
+        self.ttl
+    }
+
+    /// Puts the memory cache in front of the `next` cache.
+    pub fn in_front_of(
+        mut self,
+        next: impl PersistentCacheController<str, Bytes> + 'static,
+    ) -> Self {
+        self.next = Some(Box::new(next));
+        self
+    }
+
+    /// Number of entries stored in memory.
+    pub fn len(&self) -> usize {
+        self.state.lock().entries.len()
+    }
+
+    /// Returns true if there are no entries stored in memory.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Total size of the data stored in memory in bytes.
+    pub fn size_bytes(&self) -> usize {
+        self.state.lock().bytes
+    }
+
+    fn put_in_memory(&self, key: &str, data: &Bytes) {
+        let mut state = self.state.lock();
+        state.remove(key);
+
+        if data.len() > self.max_bytes || self.max_entries == 0 {
+            return;
+        }
+
+        while state.entries.len() >= self.max_entries || state.bytes + data.len() > self.max_bytes {
+            let Some((_, oldest)) = state.usage.pop_first() else {
+                break;
+            };
+            if let Some(entry) = state.entries.remove(&oldest) {
+                state.bytes -= entry.data.len();
+            }
+        }
+
+        let last_used = state.touch(key);
+        state.bytes += data.len();
+        state.entries.insert(
+            key.to_string(),
+            LruEntry {
+                data: data.clone(),
+                last_used,
+                inserted_at: Instant::now(),
+            },
+        );
+    }
+}
+
+impl LruState {
+    /// Marks the key as the most recently used and returns its new usage counter.
+    fn touch(&mut self, key: &str) -> u64 {
+        self.counter += 1;
+        self.usage.insert(self.counter, key.to_string());
+        self.counter
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.usage.remove(&entry.last_used);
+            self.bytes -= entry.data.len();
+        }
+    }
+}
+
+impl PersistentCacheController<str, Bytes> for MemoryCacheController {
+    fn get(&self, key: &str) -> Option<Bytes> {
+        {
+            let mut state = self.state.lock();
+            let is_expired = |entry: &LruEntry| {
+                self.ttl
+                    .is_some_and(|ttl| entry.inserted_at.elapsed() >= ttl)
+            };
+            if state.entries.get(key).is_some_and(is_expired) {
+                state.remove(key);
+            }
+
+            if let Some(previous) = state.entries.get(key).map(|entry| entry.last_used) {
+                state.usage.remove(&previous);
+                let last_used = state.touch(key);
+                let entry = state.entries.get_mut(key)?;
+                entry.last_used = last_used;
+                return Some(entry.data.clone());
+            }
+        }
+
+        let data = self.next.as_ref()?.get(key)?;
+        self.put_in_memory(key, &data);
+        Some(data)
+    }
+
+    fn insert(&self, key: &str, data: &Bytes) -> Result<(), GalileoError> {
+        self.put_in_memory(key, data);
+        match &self.next {
+            Some(next) => next.insert(key, data),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(size: usize) -> Bytes {
+        Bytes::from(vec![0; size])
+    }
+
+    #[test]
+    fn evicts_least_recently_used_entry() {
+        let cache = MemoryCacheController::with_max_entries(2);
+        cache.insert("a", &data(1)).unwrap();
+        cache.insert("b", &data(1)).unwrap();
+        assert!(cache.get("a").is_some());
+
+        cache.insert("c", &data(1)).unwrap();
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("b").is_none());
+        assert!(cache.get("c").is_some());
+    }
+
+    #[test]
+    fn respects_byte_budget() {
+        let cache = MemoryCacheController::with_max_bytes(10);
+        cache.insert("a", &data(4)).unwrap();
+        cache.insert("b", &data(4)).unwrap();
+        cache.insert("c", &data(4)).unwrap();
+        assert_eq!(cache.size_bytes(), 8);
+        assert!(cache.get("a").is_none());
+
+        cache.insert("b", &data(6)).unwrap();
+        assert_eq!(cache.size_bytes(), 10);
+
+        cache.insert("huge", &data(11)).unwrap();
+        assert!(cache.get("huge").is_none());
+        assert_eq!(cache.size_bytes(), 10);
+    }
+
+    #[test]
+    fn expired_entries_are_dropped() {
+        let cache = MemoryCacheController::with_max_entries(10).with_ttl(Some(Duration::ZERO));
+        cache.insert("a", &data(4)).unwrap();
+        assert!(cache.get("a").is_none());
+        assert!(cache.is_empty());
+        assert_eq!(cache.size_bytes(), 0);
+
+        let cache =
+            MemoryCacheController::with_max_entries(10).with_ttl(Some(Duration::from_secs(3600)));
+        cache.insert("a", &data(4)).unwrap();
+        assert!(cache.get("a").is_some());
+    }
+
+    #[test]
+    fn falls_back_to_next_cache() {
+        let next = MemoryCacheController::with_max_entries(10);
+        next.insert("a", &data(1)).unwrap();
+
+        let cache = MemoryCacheController::with_max_entries(10).in_front_of(next);
+        assert!(cache.is_empty());
+        assert!(cache.get("a").is_some());
+        assert_eq!(cache.len(), 1);
+
+        cache.insert("b", &data(1)).unwrap();
+        let next = cache.next.as_ref().unwrap();
+        assert!(next.get("b").is_some());
+    }
+}
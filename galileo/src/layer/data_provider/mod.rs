@@ -1,8 +1,25 @@
 //! Data sources for layers.
 
 mod file_cache;
+#[cfg(all(feature = "mbtiles", not(target_arch = "wasm32")))]
+mod mbtiles;
+mod memory_cache;
+mod retry;
+mod tile_fetcher;
+mod url_template;
 pub use file_cache::FileCacheController;
 use maybe_sync::{MaybeSend, MaybeSync};
+#[cfg(all(feature = "mbtiles", not(target_arch = "wasm32")))]
+pub use mbtiles::{MbTiles, MbTilesMetadata};
+pub use memory_cache::MemoryCacheController;
+pub use retry::RetryPolicy;
+pub use tile_fetcher::{FetchedData, TileFetcher};
+pub(crate) use url_template::{
+    expand_url_template, high_dpi_suffix, opposite_y, validate_flipped_rows,
+};
+pub use url_template::{
+    quadkey, validate_subdomains, validate_url_template, DEFAULT_HIGH_DPI_SUFFIX,
+};
 
 use crate::error::GalileoError;
 
@@ -0,0 +1,210 @@
+use std::future::Future;
+use std::time::Duration;
+
+use crate::error::GalileoError;
+
+/// Defines how failed requests for data are repeated.
+///
+/// After a failed attempt the request is repeated after a delay that starts at `base_delay` and is multiplied by
+/// `multiplier` after every attempt, until `max_attempts` attempts are made. Only transient failures are repeated:
+/// connection errors, timeouts, `429 Too Many Requests` and `5xx` server errors. Other errors, like
+/// [`GalileoError::NotFound`] or `401 Unauthorized`, will not go away on the next try.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use galileo::layer::data_provider::RetryPolicy;
+///
+/// let policy = RetryPolicy::new(4, Duration::from_millis(100)).with_jitter(0.2);
+///
+/// assert_eq!(policy.delay(0), Duration::from_millis(100));
+/// assert_eq!(policy.delay(2), Duration::from_millis(400));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts, including the first one. Values `0` and `1` disable retries.
+    pub max_attempts: u32,
+    /// Delay before the first retry.
+    pub base_delay: Duration,
+    /// Factor the delay is multiplied by after every retry.
+    pub multiplier: f64,
+    /// Random part of the delay relative to its value, from `0.0` to `1.0`. With jitter of `0.2` the actual delay
+    /// is randomly chosen between 80% and 120% of the calculated value, so that many clients that failed at the
+    /// same time do not repeat their requests at the same time too. `0.0` disables the jitter.
+    pub jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    /// Policy that does not repeat failed requests.
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl RetryPolicy {
+    /// Creates a new policy with the given number of attempts and the delay before the first retry. The delay is
+    /// doubled after every retry.
+    pub fn new(max_attempts: u32, base_delay: Duration) -> Self {
+        Self {
+            max_attempts,
+            base_delay,
+            multiplier: 2.0,
+            jitter: 0.0,
+        }
+    }
+
+    /// Policy that does not repeat failed requests.
+    pub fn none() -> Self {
+        Self::new(1, Duration::ZERO)
+    }
+
+    /// Sets the factor the delay is multiplied by after every retry.
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Sets the random part of the delay. See [`RetryPolicy::jitter`].
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Delay before the retry that follows the failed attempt with the given index (starting from `0`), without
+    /// the jitter.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = self
+            .multiplier
+            .max(0.0)
+            .powi(attempt.min(i32::MAX as u32) as i32);
+        Duration::try_from_secs_f64(self.base_delay.as_secs_f64() * factor).unwrap_or(Duration::MAX)
+    }
+
+    fn delay_with_jitter(&self, attempt: u32) -> Duration {
+        let delay = self.delay(attempt);
+        if self.jitter <= 0.0 {
+            return delay;
+        }
+
+        let random = ahash::RandomState::new().hash_one(attempt) as f64 / u64::MAX as f64;
+        let factor = 1.0 + self.jitter * (2.0 * random - 1.0);
+        delay.mul_f64(factor.max(0.0))
+    }
+
+    /// Calls `request` until it succeeds, fails with an error that is not transient or the attempts are exhausted,
+    /// and returns the last result.
+    pub(crate) async fn run<T, Fut>(
+        &self,
+        mut request: impl FnMut() -> Fut,
+    ) -> Result<T, GalileoError>
+    where
+        Fut: Future<Output = Result<T, GalileoError>>,
+    {
+        let mut attempt = 0;
+        loop {
+            match request().await {
+                Err(err) if is_transient(&err) && attempt + 1 < self.max_attempts => {
+                    let delay = self.delay_with_jitter(attempt);
+                    log::debug!("Request failed: {err}. Retrying in {delay:?}");
+                    crate::async_runtime::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Returns true if the request that failed with the error might succeed if repeated.
+fn is_transient(err: &GalileoError) -> bool {
+    matches!(
+        err,
+        GalileoError::IO | GalileoError::Timeout | GalileoError::HttpStatus(429 | 500..=599)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy::new(3, Duration::from_millis(1))
+    }
+
+    #[tokio::test]
+    async fn retries_until_success() {
+        let calls = AtomicU32::new(0);
+        let result = policy()
+            .run(|| async {
+                match calls.fetch_add(1, Ordering::SeqCst) {
+                    0 | 1 => Err(GalileoError::IO),
+                    _ => Ok(42),
+                }
+            })
+            .await;
+
+        assert!(matches!(result, Ok(42)));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        let calls = AtomicU32::new(0);
+        let result: Result<(), _> = policy()
+            .run(|| async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(GalileoError::IO)
+            })
+            .await;
+
+        assert!(matches!(result, Err(GalileoError::IO)));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn not_found_is_not_retried() {
+        let calls = AtomicU32::new(0);
+        let result: Result<(), _> = policy()
+            .run(|| async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(GalileoError::NotFound)
+            })
+            .await;
+
+        assert!(matches!(result, Err(GalileoError::NotFound)));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn only_transient_http_errors_are_retried() {
+        for (status, expected_calls) in [(400, 1), (401, 1), (403, 1), (429, 3), (500, 3), (503, 3)]
+        {
+            let calls = AtomicU32::new(0);
+            let result: Result<(), _> = policy()
+                .run(|| async {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    Err(GalileoError::HttpStatus(status))
+                })
+                .await;
+
+            assert!(matches!(result, Err(GalileoError::HttpStatus(s)) if s == status));
+            assert_eq!(
+                calls.load(Ordering::SeqCst),
+                expected_calls,
+                "status {status}"
+            );
+        }
+    }
+
+    #[test]
+    fn jitter_stays_within_bounds() {
+        let policy = RetryPolicy::new(5, Duration::from_millis(100)).with_jitter(0.5);
+        for attempt in 0..4 {
+            let base = policy.delay(attempt);
+            let delay = policy.delay_with_jitter(attempt);
+            assert!(delay >= base.mul_f64(0.5) && delay <= base.mul_f64(1.5));
+        }
+    }
+}
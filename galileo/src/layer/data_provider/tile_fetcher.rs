@@ -0,0 +1,457 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use bytes::Bytes;
+use tokio::sync::Semaphore;
+use web_time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::GalileoError;
+use crate::layer::data_provider::{PersistentCacheController, RetryPolicy};
+use crate::platform::{HttpResponse, PlatformService};
+
+/// Loads tile data with HTTP GET requests, keeping the loaded data in a persistent cache.
+///
+/// This is the part shared by the web loaders of raster and vector tiles, so that they are configured the same
+/// way: requests are repeated according to the [`RetryPolicy`], the given headers are sent with every request,
+/// requests can be limited in time and number, and cached data can be revalidated with the server.
+///
+/// In offline mode only the data from the cache is returned, and no requests are sent to the server.
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use galileo::layer::data_provider::{MemoryCacheController, RetryPolicy, TileFetcher};
+///
+/// let fetcher = TileFetcher::new(
+///     Some(Box::new(MemoryCacheController::with_max_entries(1000))),
+///     false,
+/// )
+/// .with_header("Authorization", "Bearer <token>")
+/// .with_retry_policy(RetryPolicy::new(3, Duration::from_millis(200)))
+/// .with_timeout(Duration::from_secs(10));
+///
+/// # tokio_test::block_on(async {
+/// let data = fetcher
+///     .fetch("https://tile.openstreetmap.org/0/0/0.png")
+///     .await
+///     .expect("failed to load tile");
+/// # });
+/// ```
+pub struct TileFetcher {
+    cache: Option<Box<dyn PersistentCacheController<str, Bytes>>>,
+    offline_mode: AtomicBool,
+    retry_policy: RetryPolicy,
+    headers: Vec<(String, String)>,
+    timeout: Option<Duration>,
+    request_limit: Option<Semaphore>,
+    max_age: Option<Duration>,
+}
+
+/// Data returned by [`TileFetcher::fetch`].
+#[derive(Debug, Clone)]
+pub struct FetchedData {
+    /// The data as served by the server.
+    pub bytes: Bytes,
+    /// `true` if the data was taken from the cache without sending a request to the server.
+    pub from_cache: bool,
+}
+
+impl TileFetcher {
+    /// Creates a new instance.
+    ///
+    /// Failed requests are not repeated unless a policy is set with
+    /// [`with_retry_policy`](Self::with_retry_policy).
+    pub fn new(
+        cache: Option<Box<dyn PersistentCacheController<str, Bytes>>>,
+        offline_mode: bool,
+    ) -> Self {
+        Self {
+            cache,
+            offline_mode: AtomicBool::new(offline_mode),
+            retry_policy: RetryPolicy::none(),
+            headers: vec![],
+            timeout: None,
+            request_limit: None,
+            max_age: None,
+        }
+    }
+
+    /// Sets the policy of repeating the requests that failed because of network errors.
+    ///
+    /// By default failed requests are not repeated.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Adds an HTTP header that is sent with every request, e.g. to authenticate with the tile
+    /// server.
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Adds HTTP headers that are sent with every request.
+    pub fn with_headers(mut self, headers: impl IntoIterator<Item = (String, String)>) -> Self {
+        self.headers.extend(headers);
+        self
+    }
+
+    /// Sets the maximum time to wait for a response. If the server does not respond in time, the
+    /// request fails with [`GalileoError::Timeout`].
+    ///
+    /// By default there is no timeout.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Limits the number of requests sent to the server at the same time. Data loaded from the
+    /// cache does not count towards the limit.
+    ///
+    /// By default the number of requests is not limited.
+    pub fn with_max_concurrent_requests(mut self, max_requests: usize) -> Self {
+        self.request_limit = Some(Semaphore::new(max_requests.max(1)));
+        self
+    }
+
+    /// Enables revalidation of the cached data. Data that was received from the server more than
+    /// `max_age` ago is requested again.
+    ///
+    /// If the server sent an `ETag` or `Last-Modified` header with the data, the request is
+    /// conditional: a `304 Not Modified` response keeps the cached data and resets its age, and new
+    /// data replaces the cached one. If the server sent neither, the data is simply loaded again.
+    /// If the request fails because of a network error, the stale cached data is used.
+    ///
+    /// The validators are stored in the cache as separate entries next to the data. By default
+    /// cached data is never revalidated.
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Switches the offline mode. In offline mode only the data from the cache is returned, and no
+    /// requests are sent to the server.
+    pub fn set_offline_mode(&self, offline_mode: bool) {
+        self.offline_mode.store(offline_mode, Ordering::Relaxed);
+    }
+
+    /// Returns true if the fetcher is in offline mode.
+    pub fn is_offline_mode(&self) -> bool {
+        self.offline_mode.load(Ordering::Relaxed)
+    }
+
+    /// Loads the data from the `url`, or takes it from the cache.
+    ///
+    /// Returns [`GalileoError::NotFound`] if the data is not in the cache in offline mode, and
+    /// [`GalileoError::HttpStatus`] if the server responded with an unsuccessful status.
+    pub async fn fetch(&self, url: &str) -> Result<FetchedData, GalileoError> {
+        let cached = self.cache.as_ref().and_then(|cache| cache.get(url));
+        let validators = match (&self.cache, &cached, self.max_age) {
+            (Some(cache), Some(_), Some(_)) => cache
+                .get(&CacheValidators::key(url))
+                .and_then(|bytes| CacheValidators::decode(&bytes)),
+            _ => None,
+        };
+
+        if let Some(data) = &cached {
+            let is_fresh = match self.max_age {
+                Some(max_age) => validators
+                    .as_ref()
+                    .is_some_and(|validators| validators.is_fresh(max_age, unix_time())),
+                None => true,
+            };
+            if is_fresh || self.is_offline_mode() {
+                log::trace!("Cache hit for url {url}");
+                return Ok(FetchedData {
+                    bytes: data.clone(),
+                    from_cache: true,
+                });
+            }
+        }
+
+        if self.is_offline_mode() {
+            return Err(GalileoError::NotFound);
+        }
+
+        let _permit = match &self.request_limit {
+            Some(limit) => Some(
+                limit
+                    .acquire()
+                    .await
+                    .map_err(|err| GalileoError::Generic(err.to_string()))?,
+            ),
+            None => None,
+        };
+
+        let mut headers = self.headers.clone();
+        if let Some(validators) = &validators {
+            headers.extend(validators.request_headers());
+        }
+        let response = match self.load_with_retries(url, &headers).await {
+            Ok(response) => response,
+            Err(GalileoError::NotFound) => return Err(GalileoError::NotFound),
+            Err(err) => match cached {
+                Some(data) => {
+                    log::warn!("Failed to revalidate {url}, using the cached data: {err}");
+                    return Ok(FetchedData {
+                        bytes: data,
+                        from_cache: true,
+                    });
+                }
+                None => return Err(err),
+            },
+        };
+
+        let bytes = match (response.is_not_modified(), cached) {
+            (true, Some(cached)) => {
+                log::trace!("Cached data for url {url} is not modified");
+                cached
+            }
+            (true, None) => {
+                return Err(GalileoError::Generic(
+                    "unexpected 304 response to an unconditional request".into(),
+                ))
+            }
+            (false, _) => {
+                log::info!("Loaded data from url: {url}");
+
+                if let Some(cache) = &self.cache {
+                    if let Err(error) = cache.insert(url, &response.body) {
+                        log::warn!("Failed to write persistent cache entry: {error:?}");
+                    }
+                }
+
+                response.body.clone()
+            }
+        };
+
+        if let (Some(cache), Some(_)) = (&self.cache, self.max_age) {
+            let validators =
+                CacheValidators::from_response(&response, validators.as_ref(), unix_time());
+            if let Err(error) = cache.insert(&CacheValidators::key(url), &validators.encode()) {
+                log::warn!("Failed to write persistent cache entry: {error:?}");
+            }
+        }
+
+        Ok(FetchedData {
+            bytes,
+            from_cache: false,
+        })
+    }
+
+    /// Loads the response from the `url`, repeating the request on network errors according to the retry policy.
+    ///
+    /// Each request is abandoned if it takes longer than the timeout.
+    async fn load_with_retries(
+        &self,
+        url: &str,
+        headers: &[(String, String)],
+    ) -> Result<HttpResponse, GalileoError> {
+        let timeout = self.timeout;
+        self.retry_policy
+            .run(|| async move {
+                let request = crate::platform::instance().load_response_from_url(url, headers);
+                match timeout {
+                    Some(timeout) => crate::async_runtime::timeout(timeout, request)
+                        .await
+                        .ok_or(GalileoError::Timeout)?,
+                    None => request.await,
+                }
+            })
+            .await
+    }
+}
+
+/// Validators of cached data, used to revalidate the data with a conditional request.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct CacheValidators {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    /// Time the data was last received from the server or confirmed by it, in seconds since the
+    /// Unix epoch.
+    validated_at: u64,
+}
+
+impl CacheValidators {
+    /// Cache key of the validators of the data with the given URL.
+    fn key(url: &str) -> String {
+        format!("{url}.validators")
+    }
+
+    /// Validators sent with the `response`. If the response does not contain them, e.g. a `304`
+    /// response from some servers, the `previous` ones are kept.
+    fn from_response(response: &HttpResponse, previous: Option<&Self>, now: u64) -> Self {
+        let header = |name: &str, previous: Option<&String>| {
+            response
+                .header(name)
+                .map(str::to_string)
+                .or_else(|| previous.filter(|_| response.is_not_modified()).cloned())
+        };
+
+        Self {
+            etag: header("ETag", previous.and_then(|v| v.etag.as_ref())),
+            last_modified: header(
+                "Last-Modified",
+                previous.and_then(|v| v.last_modified.as_ref()),
+            ),
+            validated_at: now,
+        }
+    }
+
+    fn is_fresh(&self, max_age: Duration, now: u64) -> bool {
+        Duration::from_secs(now.saturating_sub(self.validated_at)) < max_age
+    }
+
+    fn request_headers(&self) -> Vec<(String, String)> {
+        let mut headers = vec![];
+        if let Some(etag) = &self.etag {
+            headers.push(("If-None-Match".to_string(), etag.clone()));
+        }
+        if let Some(last_modified) = &self.last_modified {
+            headers.push(("If-Modified-Since".to_string(), last_modified.clone()));
+        }
+
+        headers
+    }
+
+    /// Header values cannot contain line breaks, so the validators are stored as lines of text.
+    fn encode(&self) -> Bytes {
+        format!(
+            "{}\n{}\n{}",
+            self.validated_at,
+            self.etag.as_deref().unwrap_or_default(),
+            self.last_modified.as_deref().unwrap_or_default()
+        )
+        .into()
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        let text = std::str::from_utf8(bytes).ok()?;
+        let mut lines = text.split('\n');
+        let validated_at = lines.next()?.parse().ok()?;
+        let mut next = || {
+            lines
+                .next()
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+
+        Some(Self {
+            etag: next(),
+            last_modified: next(),
+            validated_at,
+        })
+    }
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layer::data_provider::MemoryCacheController;
+
+    fn response(status: u16, headers: &[(&str, &str)]) -> HttpResponse {
+        HttpResponse {
+            status,
+            headers: headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            body: Bytes::new(),
+        }
+    }
+
+    #[test]
+    fn cache_validators_round_trip() {
+        let validators = CacheValidators::from_response(
+            &response(200, &[("etag", "\"abc\""), ("content-type", "x")]),
+            None,
+            100,
+        );
+        assert_eq!(validators.etag.as_deref(), Some("\"abc\""));
+        assert_eq!(validators.last_modified, None);
+        assert_eq!(
+            CacheValidators::decode(&validators.encode()),
+            Some(validators.clone())
+        );
+        assert_eq!(
+            validators.request_headers(),
+            vec![("If-None-Match".to_string(), "\"abc\"".to_string())]
+        );
+
+        assert_eq!(CacheValidators::decode(b"not a number\n\n"), None);
+    }
+
+    #[test]
+    fn not_modified_response_keeps_validators() {
+        let previous = CacheValidators {
+            etag: Some("\"abc\"".into()),
+            last_modified: Some("Wed, 21 Oct 2015 07:28:00 GMT".into()),
+            validated_at: 100,
+        };
+
+        let refreshed = CacheValidators::from_response(
+            &response(HttpResponse::NOT_MODIFIED, &[]),
+            Some(&previous),
+            200,
+        );
+        assert_eq!(
+            refreshed,
+            CacheValidators {
+                validated_at: 200,
+                ..previous.clone()
+            }
+        );
+
+        let replaced = CacheValidators::from_response(&response(200, &[]), Some(&previous), 200);
+        assert_eq!(replaced.etag, None);
+        assert_eq!(replaced.last_modified, None);
+    }
+
+    #[test]
+    fn cache_validators_expire() {
+        let validators = CacheValidators {
+            validated_at: 100,
+            ..Default::default()
+        };
+        assert!(validators.is_fresh(Duration::from_secs(60), 159));
+        assert!(!validators.is_fresh(Duration::from_secs(60), 160));
+        assert!(validators.request_headers().is_empty());
+    }
+
+    fn cached_fetcher(url: &str, offline_mode: bool) -> TileFetcher {
+        let cache = MemoryCacheController::with_max_entries(10);
+        cache.insert(url, &Bytes::from_static(b"tile")).unwrap();
+        TileFetcher::new(Some(Box::new(cache)), offline_mode)
+    }
+
+    #[tokio::test]
+    async fn offline_mode_returns_only_cached_data() {
+        let cached = "https://tiles.example.com/3/1/2.pbf";
+        let fetcher = cached_fetcher(cached, true);
+
+        let fetched = fetcher.fetch(cached).await.unwrap();
+        assert_eq!(fetched.bytes, Bytes::from_static(b"tile"));
+        assert!(fetched.from_cache);
+        assert!(matches!(
+            fetcher.fetch("https://tiles.example.com/3/1/3.pbf").await,
+            Err(GalileoError::NotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn cache_hits_do_not_wait_for_request_limit() {
+        let cached = "https://tiles.example.com/3/1/2.pbf";
+        let fetcher = cached_fetcher(cached, false).with_max_concurrent_requests(1);
+
+        let _permit = fetcher.request_limit.as_ref().unwrap().acquire().await;
+        let result = tokio::time::timeout(Duration::from_secs(1), fetcher.fetch(cached)).await;
+        assert_eq!(result.unwrap().unwrap().bytes, Bytes::from_static(b"tile"));
+    }
+}
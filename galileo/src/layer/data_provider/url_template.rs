@@ -0,0 +1,364 @@
+use crate::error::GalileoError;
+use crate::tile_schema::TileIndex;
+use crate::TileSchema;
+
+/// Placeholders that must be present in a tile URL template.
+const REQUIRED_PLACEHOLDERS: [&str; 3] = ["z", "x", "y"];
+
+/// Placeholder for the subdomain of the tile server.
+const SUBDOMAIN_PLACEHOLDER: &str = "s";
+
+/// Placeholder for the quadkey of the tile, which replaces `{z}`, `{x}` and `{y}` placeholders.
+const QUADKEY_PLACEHOLDER: &str = "q";
+
+/// Placeholder for the Y index of the tile counted from the opposite edge of the tile schema, which
+/// replaces the `{y}` placeholder.
+const OPPOSITE_Y_PLACEHOLDER: &str = "-y";
+
+/// Placeholder for the suffix of the high resolution tiles, which is empty on standard displays.
+const HIGH_DPI_PLACEHOLDER: &str = "r";
+
+/// Suffix substituted for the `{r}` placeholder of a tile URL template on high-DPI displays, as
+/// used by most tile servers that provide tiles of double resolution.
+pub const DEFAULT_HIGH_DPI_SUFFIX: &str = "@2x";
+
+/// Checks that the tile URL template contains `{z}`, `{x}` and `{y}` (or `{-y}`) placeholders (or
+/// the `{q}` quadkey placeholder instead of them) and no unknown placeholders. The template can also contain
+/// the `{s}` subdomain placeholder and the `{r}` placeholder for the suffix of high-DPI tiles, e.g.
+/// `https://tiles.example.com/{z}/{x}/{y}{r}.png`.
+///
+/// Placeholder names are case-sensitive, so a template with `{Z}` is rejected.
+pub fn validate_url_template(template: &str) -> Result<(), GalileoError> {
+    let mut found = vec![];
+    let mut rest = template;
+    while let Some(start) = rest.find(['{', '}']) {
+        if rest[start..].starts_with('}') {
+            return Err(invalid(template, "unmatched '}'"));
+        }
+
+        let Some(len) = rest[start + 1..].find(['{', '}']) else {
+            return Err(invalid(template, "unclosed '{'"));
+        };
+        let end = start + 1 + len;
+        if rest[end..].starts_with('{') {
+            return Err(invalid(template, "nested '{'"));
+        }
+
+        let name = &rest[start + 1..end];
+        if !REQUIRED_PLACEHOLDERS.contains(&name)
+            && name != OPPOSITE_Y_PLACEHOLDER
+            && name != SUBDOMAIN_PLACEHOLDER
+            && name != QUADKEY_PLACEHOLDER
+            && name != HIGH_DPI_PLACEHOLDER
+        {
+            return Err(invalid(
+                template,
+                &format!("unknown placeholder {{{name}}}"),
+            ));
+        }
+
+        if name == OPPOSITE_Y_PLACEHOLDER {
+            found.push("y");
+        } else {
+            found.push(name);
+        }
+        rest = &rest[end + 1..];
+    }
+
+    if found.contains(&QUADKEY_PLACEHOLDER) {
+        return Ok(());
+    }
+
+    let missing: Vec<_> = REQUIRED_PLACEHOLDERS
+        .iter()
+        .filter(|name| !found.contains(name))
+        .map(|name| format!("{{{name}}}"))
+        .collect();
+    if !missing.is_empty() {
+        return Err(invalid(
+            template,
+            &format!("missing placeholders {}", missing.join(", ")),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Returns the quadkey of the tile as used by Bing Maps.
+///
+/// The quadkey has one base-4 digit for every z-level, each digit combining one bit of the `x`
+/// and `y` indices of the tile. Tiles of the z-level 0 have an empty quadkey.
+///
+/// ```
+/// use galileo::layer::data_provider::quadkey;
+/// use galileo::tile_schema::TileIndex;
+///
+/// assert_eq!(quadkey(&TileIndex::new(3, 5, 3)), "213");
+/// ```
+pub fn quadkey(index: &TileIndex) -> String {
+    (1..=index.z)
+        .rev()
+        .map(|level| {
+            let mask = 1 << (level - 1);
+            let mut digit = 0;
+            if index.x & mask != 0 {
+                digit += 1;
+            }
+            if index.y & mask != 0 {
+                digit += 2;
+            }
+            char::from(b'0' + digit)
+        })
+        .collect()
+}
+
+/// Checks that subdomains are configured if the tile URL template contains the `{s}` placeholder.
+pub fn validate_subdomains(template: &str, subdomains: &[String]) -> Result<(), GalileoError> {
+    if subdomains.is_empty() && template.contains("{s}") {
+        return Err(invalid(
+            template,
+            "the template contains {s} placeholder, but no subdomains are configured",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Selects the subdomain to request the tile from.
+///
+/// The same tile is always requested from the same subdomain, so that it can be reused from the
+/// HTTP cache.
+pub(crate) fn select_subdomain<'a>(subdomains: &'a [String], index: &TileIndex) -> Option<&'a str> {
+    if subdomains.is_empty() {
+        return None;
+    }
+
+    let i = (index.x as i64 + index.y as i64).rem_euclid(subdomains.len() as i64);
+    Some(&subdomains[i as usize])
+}
+
+/// Returns the suffix to substitute for the `{r}` placeholder on a display with the given
+/// `pixel_ratio`: `suffix` for high-DPI displays and an empty string otherwise.
+pub(crate) fn high_dpi_suffix(pixel_ratio: f32, suffix: &str) -> &str {
+    if pixel_ratio > 1.0 {
+        suffix
+    } else {
+        ""
+    }
+}
+
+/// Builds the URL of the tile by substituting the placeholders of the `template` and appending the
+/// `parameters` as the query string.
+///
+/// `y` is the row number of the tile as used in the URL, which depends on the tile numbering
+/// convention of the server. `high_dpi_suffix` is substituted for the `{r}` placeholder.
+///
+/// The `{-y}` placeholder is replaced with the [opposite Y index](TileSchema::opposite_y) of the
+/// tile in the `tile_schema`. Without the schema, the square grid of [`TileSchema::web`] is
+/// assumed.
+pub(crate) fn expand_url_template(
+    template: &str,
+    index: &TileIndex,
+    y: i64,
+    subdomains: &[String],
+    parameters: &[(String, String)],
+    high_dpi_suffix: &str,
+    tile_schema: Option<&TileSchema>,
+) -> String {
+    let mut url = template
+        .replace("{z}", &index.z.to_string())
+        .replace("{x}", &index.x.to_string())
+        .replace("{y}", &y.to_string())
+        .replace("{r}", high_dpi_suffix);
+
+    if url.contains("{q}") {
+        url = url.replace("{q}", &quadkey(index));
+    }
+
+    if url.contains("{-y}") {
+        url = url.replace("{-y}", &opposite_y(index, tile_schema).to_string());
+    }
+
+    if let Some(subdomain) = select_subdomain(subdomains, index) {
+        url = url.replace("{s}", subdomain);
+    }
+
+    if !parameters.is_empty() {
+        let query_string: String = parameters
+            .iter()
+            .map(|(k, v)| format!("{}={}", urlencoding::encode(k), urlencoding::encode(v)))
+            .collect::<Vec<_>>()
+            .join("&");
+        url.push('?');
+        url.push_str(&query_string);
+    }
+
+    url
+}
+
+/// Y index of the tile counted from the opposite edge of the `tile_schema` (see
+/// [`TileSchema::opposite_y`]). Without the schema, the square grid of [`TileSchema::web`] is
+/// assumed.
+pub(crate) fn opposite_y(index: &TileIndex, tile_schema: Option<&TileSchema>) -> i64 {
+    tile_schema
+        .and_then(|tile_schema| tile_schema.opposite_y(index))
+        .map_or_else(
+            || (1i64 << index.z) - 1 - index.y as i64,
+            |opposite_y| opposite_y as i64,
+        )
+}
+
+/// Checks that the template does not use the `{-y}` placeholder together with the flipped rows of
+/// the TMS tile scheme, as the two would flip the Y index twice.
+pub(crate) fn validate_flipped_rows(template: &str, flipped: bool) -> Result<(), GalileoError> {
+    if flipped && template.contains("{-y}") {
+        return Err(invalid(
+            template,
+            "the {-y} placeholder cannot be used together with the TMS tile scheme, use {y} instead",
+        ));
+    }
+
+    Ok(())
+}
+
+fn invalid(template: &str, reason: &str) -> GalileoError {
+    GalileoError::Configuration(format!("invalid tile URL template '{template}': {reason}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use galileo_types::cartesian::Rect;
+
+    use super::*;
+
+    #[test]
+    fn accepts_valid_template() {
+        assert!(validate_url_template("https://tiles.example.com/{z}/{x}/{y}.png").is_ok());
+        assert!(validate_url_template("https://tiles.example.com/{y}/{x}/{z}.pbf").is_ok());
+    }
+
+    #[test]
+    fn rejects_missing_placeholder() {
+        let err = validate_url_template("https://tiles.example.com/{z}/{x}.png")
+            .expect_err("template without {y} is invalid");
+        assert!(err.to_string().contains("{y}"));
+    }
+
+    #[test]
+    fn rejects_unknown_placeholder() {
+        assert!(validate_url_template("https://tiles.example.com/{Z}/{x}/{y}.png").is_err());
+    }
+
+    #[test]
+    fn rejects_unbalanced_braces() {
+        assert!(validate_url_template("https://tiles.example.com/{z/{x}/{y}.png").is_err());
+        assert!(validate_url_template("https://tiles.example.com/{z}/{x}/{y.png").is_err());
+        assert!(validate_url_template("https://tiles.example.com/z}/{x}/{y}.png").is_err());
+    }
+
+    #[test]
+    fn quadkey_template_does_not_need_zxy() {
+        assert!(validate_url_template("https://tiles.example.com/{q}.png").is_ok());
+        assert!(validate_url_template("https://{s}.tiles.example.com/{q}.png").is_ok());
+        assert!(validate_url_template("https://tiles.example.com/{Q}.png").is_err());
+    }
+
+    #[test]
+    fn high_dpi_placeholder_is_optional() {
+        assert!(validate_url_template("https://tiles.example.com/{z}/{x}/{y}{r}.png").is_ok());
+        assert!(validate_url_template("https://tiles.example.com/{q}{r}.png").is_ok());
+        assert!(validate_url_template("https://tiles.example.com/{R}/{z}/{x}/{y}.png").is_err());
+    }
+
+    #[test]
+    fn high_dpi_suffix_is_substituted_on_high_dpi_displays() {
+        let template = "https://tiles.example.com/{z}/{x}/{y}{r}.png";
+        let index = TileIndex::new(3, 5, 4);
+        let expand = |pixel_ratio| {
+            expand_url_template(
+                template,
+                &index,
+                5,
+                &[],
+                &[],
+                high_dpi_suffix(pixel_ratio, DEFAULT_HIGH_DPI_SUFFIX),
+                None,
+            )
+        };
+
+        assert_eq!(expand(1.0), "https://tiles.example.com/4/3/5.png");
+        assert_eq!(expand(2.0), "https://tiles.example.com/4/3/5@2x.png");
+        assert_eq!(
+            expand_url_template(template, &index, 5, &[], &[], "_hd", None),
+            "https://tiles.example.com/4/3/5_hd.png"
+        );
+    }
+
+    #[test]
+    fn opposite_y_placeholder_replaces_y() {
+        assert!(validate_url_template("https://tiles.example.com/{z}/{x}/{-y}.png").is_ok());
+        assert!(validate_url_template("https://tiles.example.com/{z}/{x}/{+y}.png").is_err());
+    }
+
+    #[test]
+    fn opposite_y_is_calculated_from_tile_schema() {
+        let template = "https://tiles.example.com/{z}/{x}/{-y}.png";
+        let index = TileIndex::new(5, 2, 3);
+
+        assert_eq!(
+            expand_url_template(template, &index, 2, &[], &[], "", None),
+            "https://tiles.example.com/3/5/5.png"
+        );
+
+        let mut tile_schema = TileSchema::web(18);
+        tile_schema.bounds = Rect::new(
+            tile_schema.bounds.x_min(),
+            0.0,
+            tile_schema.bounds.x_max(),
+            tile_schema.bounds.y_max(),
+        );
+        assert_eq!(
+            expand_url_template(template, &index, 2, &[], &[], "", Some(&tile_schema)),
+            "https://tiles.example.com/3/5/1.png"
+        );
+    }
+
+    #[test]
+    fn quadkey_of_known_tiles() {
+        assert_eq!(quadkey(&TileIndex::new(0, 0, 0)), "");
+        assert_eq!(quadkey(&TileIndex::new(0, 0, 1)), "0");
+        assert_eq!(quadkey(&TileIndex::new(1, 0, 1)), "1");
+        assert_eq!(quadkey(&TileIndex::new(0, 1, 1)), "2");
+        assert_eq!(quadkey(&TileIndex::new(1, 1, 1)), "3");
+        assert_eq!(quadkey(&TileIndex::new(3, 5, 3)), "213");
+        assert_eq!(
+            quadkey(&TileIndex::new(35210, 21493, 16)),
+            "1202102332221212"
+        );
+    }
+
+    #[test]
+    fn subdomain_placeholder_requires_subdomains() {
+        let template = "https://{s}.tiles.example.com/{z}/{x}/{y}.png";
+        assert!(validate_url_template(template).is_ok());
+        assert!(validate_subdomains(template, &[]).is_err());
+        assert!(validate_subdomains(template, &["a".into()]).is_ok());
+        assert!(validate_subdomains("https://tiles.example.com/{z}/{x}/{y}.png", &[]).is_ok());
+    }
+
+    #[test]
+    fn same_tile_uses_same_subdomain() {
+        let subdomains: Vec<String> = ["a", "b", "c"].map(String::from).into();
+        let index = TileIndex::new(4, 7, 5);
+        assert_eq!(select_subdomain(&subdomains, &index), Some("c"));
+        assert_eq!(
+            select_subdomain(&subdomains, &index),
+            select_subdomain(&subdomains, &TileIndex::new(4, 7, 5))
+        );
+        assert_eq!(
+            select_subdomain(&subdomains, &TileIndex::new(5, 7, 5)),
+            Some("a")
+        );
+        assert_eq!(select_subdomain(&[], &index), None);
+    }
+}
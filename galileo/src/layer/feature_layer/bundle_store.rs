@@ -6,7 +6,7 @@ use super::FeatureId;
 use crate::render::render_bundle::RenderBundle;
 use crate::render::{Canvas, PackedBundle};
 
-#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub(super) struct BundleId(u64);
 
 static NEXT_ID: AtomicU64 = AtomicU64::new(0);
@@ -19,6 +19,8 @@ impl BundleId {
 
 pub(super) struct BundleStore {
     bundle_size_limit: usize,
+    tessellation_tolerance: f32,
+    scale_factor: f32,
     unpacked: Vec<(BundleId, RenderBundle)>,
     packed: HashMap<BundleId, Box<dyn PackedBundle>>,
     feature_to_bundle_map: HashMap<FeatureId, BundleId>,
@@ -61,6 +63,8 @@ impl BundleStore {
     pub(super) fn new(bundle_size_limit: usize) -> Self {
         Self {
             bundle_size_limit,
+            tessellation_tolerance: RenderBundle::default().tessellation_tolerance(),
+            scale_factor: RenderBundle::default().scale_factor(),
             unpacked: vec![],
             packed: HashMap::new(),
             feature_to_bundle_map: HashMap::new(),
@@ -76,14 +80,32 @@ impl BundleStore {
         self.required_update.updated();
     }
 
+    /// Packed bundles in the order they were created, so that features are drawn in the order they were
+    /// rendered into the bundles.
     pub(super) fn packed(&self) -> Vec<&dyn PackedBundle> {
-        self.packed.values().map(|v| &**v).collect()
+        let mut packed: Vec<_> = self.packed.iter().collect();
+        packed.sort_by_key(|(id, _)| **id);
+        packed.into_iter().map(|(_, v)| &**v).collect()
     }
 
     pub(super) fn set_bundle_size_limit(&mut self, limit: usize) {
         self.bundle_size_limit = limit;
     }
 
+    pub(super) fn set_tessellation_tolerance(&mut self, tolerance: f32) {
+        if self.tessellation_tolerance != tolerance {
+            self.tessellation_tolerance = tolerance;
+            self.clear();
+        }
+    }
+
+    pub(super) fn set_scale_factor(&mut self, scale_factor: f32) {
+        if self.scale_factor != scale_factor {
+            self.scale_factor = scale_factor;
+            self.clear();
+        }
+    }
+
     pub(super) fn clear(&mut self) {
         self.unpacked.clear();
         self.packed.clear();
@@ -107,7 +129,12 @@ impl BundleStore {
     fn curr_bundle(&mut self) -> &mut (BundleId, RenderBundle) {
         if self.last_bundle_is_full() {
             let new_id = BundleId::next();
-            self.unpacked.push((new_id, RenderBundle::default()));
+            self.unpacked.push((
+                new_id,
+                RenderBundle::default()
+                    .with_tessellation_tolerance(self.tessellation_tolerance)
+                    .with_scale_factor(self.scale_factor),
+            ));
         }
 
         let idx = self.unpacked.len() - 1;
@@ -13,6 +13,21 @@ pub trait Feature {
     fn geometry(&self) -> &Self::Geom;
 }
 
+/// A feature that carries arbitrary user data, e.g. an identifier of the object in the application
+/// model.
+///
+/// Features implementing this trait can be queried with [`FeatureLayer::get_payloads_at`], which
+/// returns the payloads of the matched features directly, so there is no need to keep a separate
+/// mapping from the feature indices to the application data.
+///
+/// [`FeatureLayer::get_payloads_at`]: crate::layer::FeatureLayer::get_payloads_at
+pub trait FeaturePayload: Feature {
+    /// Type of the payload.
+    type Payload;
+    /// Returns the payload of the feature.
+    fn payload(&self) -> &Self::Payload;
+}
+
 macro_rules! impl_feature {
     ($geom:ident) => {
         impl Feature for $geom {
@@ -56,3 +71,7 @@ where
 
 #[cfg(feature = "geojson")]
 mod geojson;
+mod property;
+#[cfg(feature = "geojson")]
+pub use geojson::GeoJsonFeature;
+pub use property::{FeatureProperties, PropertyFeature, PropertyValue};
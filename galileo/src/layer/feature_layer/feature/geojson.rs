@@ -1,4 +1,11 @@
-use crate::layer::feature_layer::feature::Feature;
+use galileo_types::error::GalileoTypesError;
+use galileo_types::geo::impls::GeoPoint2d;
+use galileo_types::geometry::Geom;
+use geojson::feature::Id;
+use geojson::{FeatureCollection, GeoJson, JsonObject, JsonValue};
+
+use crate::error::GalileoError;
+use crate::layer::feature_layer::feature::{Feature, FeatureProperties};
 
 impl Feature for geojson::Feature {
     type Geom = geojson::Geometry;
@@ -9,3 +16,186 @@ impl Feature for geojson::Feature {
             .expect("GeoJSON Feature has no geometry")
     }
 }
+
+/// A feature read from a GeoJSON document, with its geometry converted into a [`Geom`] and its
+/// properties available for data-driven symbols.
+///
+/// Coordinates of GeoJSON documents are always in WGS84, so a layer with these features should be
+/// created with [`Crs::WGS84`](galileo_types::geo::Crs::WGS84):
+///
+/// ```no_run
+/// # use galileo::layer::feature_layer::GeoJsonFeature;
+/// # use galileo::layer::FeatureLayer;
+/// # use galileo::symbol::ArbitraryGeometrySymbol;
+/// # use galileo_types::geo::Crs;
+/// # use galileo_types::geometry_type::GeoSpace2d;
+/// let json = std::fs::read_to_string("data.geojson").unwrap();
+/// let features = GeoJsonFeature::parse_collection(&json).unwrap();
+/// let layer: FeatureLayer<_, _, _, GeoSpace2d> =
+///     FeatureLayer::new(features, ArbitraryGeometrySymbol::default(), Crs::WGS84);
+/// ```
+#[derive(Debug, Clone)]
+pub struct GeoJsonFeature {
+    id: Option<Id>,
+    geometry: Geom<GeoPoint2d>,
+    properties: JsonObject,
+}
+
+impl GeoJsonFeature {
+    /// Parses a GeoJSON document containing a `FeatureCollection`.
+    ///
+    /// Features without geometry are skipped. Returns an error if the document cannot be parsed or
+    /// contains an invalid or unsupported geometry (geometry collections are not supported).
+    pub fn parse_collection(json: &str) -> Result<Vec<Self>, GalileoError> {
+        let geojson = json
+            .parse::<GeoJson>()
+            .map_err(|err| GalileoError::Deserialization(err.to_string()))?;
+        let collection = FeatureCollection::try_from(geojson)
+            .map_err(|err| GalileoError::Deserialization(err.to_string()))?;
+
+        Self::from_collection(collection)
+    }
+
+    /// Converts the features of the collection. Features without geometry are skipped.
+    pub fn from_collection(collection: FeatureCollection) -> Result<Vec<Self>, GalileoError> {
+        collection
+            .features
+            .into_iter()
+            .filter(|feature| feature.geometry.is_some())
+            .map(Self::try_from)
+            .collect()
+    }
+
+    /// Identifier of the feature, if set in the document.
+    pub fn id(&self) -> Option<&Id> {
+        self.id.as_ref()
+    }
+
+    /// All properties of the feature.
+    pub fn properties(&self) -> &JsonObject {
+        &self.properties
+    }
+
+    /// Value of the property with the given name.
+    pub fn property(&self, name: &str) -> Option<&JsonValue> {
+        self.properties.get(name)
+    }
+
+    /// Value of the property with the given name if it is a string.
+    pub fn property_str(&self, name: &str) -> Option<&str> {
+        self.property(name)?.as_str()
+    }
+
+    /// Value of the property with the given name if it is a number.
+    pub fn property_f64(&self, name: &str) -> Option<f64> {
+        self.property(name)?.as_f64()
+    }
+}
+
+impl Feature for GeoJsonFeature {
+    type Geom = Geom<GeoPoint2d>;
+
+    fn geometry(&self) -> &Self::Geom {
+        &self.geometry
+    }
+}
+
+impl FeatureProperties for GeoJsonFeature {
+    fn property_f64(&self, name: &str) -> Option<f64> {
+        self.property(name)?.as_f64()
+    }
+
+    fn property_str(&self, name: &str) -> Option<&str> {
+        self.property(name)?.as_str()
+    }
+}
+
+impl TryFrom<geojson::Feature> for GeoJsonFeature {
+    type Error = GalileoError;
+
+    fn try_from(value: geojson::Feature) -> Result<Self, Self::Error> {
+        let geometry = value
+            .geometry
+            .as_ref()
+            .ok_or_else(|| GalileoError::Deserialization("feature has no geometry".to_string()))?;
+        let geometry = Geom::try_from(geometry)
+            .map_err(|err: GalileoTypesError| GalileoError::Deserialization(err.to_string()))?;
+
+        Ok(Self {
+            id: value.id,
+            geometry,
+            properties: value.properties.unwrap_or_default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use galileo_types::geo::NewGeoPoint;
+
+    use super::*;
+
+    const COLLECTION: &str = r#"{
+        "type": "FeatureCollection",
+        "features": [
+            {
+                "type": "Feature",
+                "id": 1,
+                "geometry": { "type": "Point", "coordinates": [10.0, 20.0] },
+                "properties": { "name": "point", "population": 1500 }
+            },
+            {
+                "type": "Feature",
+                "geometry": { "type": "LineString", "coordinates": [[0.0, 0.0], [1.0, 1.0]] },
+                "properties": { "name": "line" }
+            },
+            {
+                "type": "Feature",
+                "geometry": {
+                    "type": "Polygon",
+                    "coordinates": [[[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 0.0]]]
+                },
+                "properties": null
+            },
+            {
+                "type": "Feature",
+                "geometry": null,
+                "properties": { "name": "empty" }
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn parse_collection_converts_geometries() {
+        let features = GeoJsonFeature::parse_collection(COLLECTION).unwrap();
+        assert_eq!(features.len(), 3);
+
+        assert_eq!(
+            features[0].geometry(),
+            &Geom::Point(GeoPoint2d::latlon(20.0, 10.0))
+        );
+        assert!(matches!(features[1].geometry(), Geom::Contour(_)));
+        assert!(matches!(features[2].geometry(), Geom::Polygon(_)));
+    }
+
+    #[test]
+    fn parse_collection_reads_properties() {
+        let features = GeoJsonFeature::parse_collection(COLLECTION).unwrap();
+
+        assert_eq!(features[0].id(), Some(&Id::Number(1.into())));
+        assert_eq!(features[0].property_str("name"), Some("point"));
+        assert_eq!(features[0].property_f64("population"), Some(1500.0));
+        assert_eq!(features[0].property_f64("name"), None);
+        assert_eq!(features[1].property("population"), None);
+        assert!(features[2].properties().is_empty());
+    }
+
+    #[test]
+    fn parse_collection_fails_for_invalid_input() {
+        assert!(GeoJsonFeature::parse_collection("not json").is_err());
+        assert!(GeoJsonFeature::parse_collection(
+            r#"{ "type": "Point", "coordinates": [0.0, 0.0] }"#
+        )
+        .is_err());
+    }
+}
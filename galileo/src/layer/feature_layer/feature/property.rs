@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+
+use galileo_types::geometry::Geometry;
+
+use crate::layer::feature_layer::feature::Feature;
+
+/// Features with named attributes that can be read by data-driven symbols, e.g.
+/// [`GraduatedCircleSymbol::from_property`](crate::symbol::GraduatedCircleSymbol::from_property).
+pub trait FeatureProperties {
+    /// Value of the property with the given name if it is a number.
+    fn property_f64(&self, name: &str) -> Option<f64>;
+    /// Value of the property with the given name if it is a string.
+    fn property_str(&self, name: &str) -> Option<&str>;
+}
+
+/// Value of a [`PropertyFeature`] property.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyValue {
+    /// String value.
+    String(String),
+    /// Numeric value.
+    Number(f64),
+    /// Boolean value.
+    Bool(bool),
+    /// Property is set, but has no value.
+    Null,
+}
+
+impl PropertyValue {
+    /// Returns the value if it is a number.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Number(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it is a string.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it is a boolean.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(v) => Some(*v),
+            _ => None,
+        }
+    }
+}
+
+impl From<String> for PropertyValue {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl From<&str> for PropertyValue {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
+impl From<f64> for PropertyValue {
+    fn from(value: f64) -> Self {
+        Self::Number(value)
+    }
+}
+
+impl From<i64> for PropertyValue {
+    fn from(value: i64) -> Self {
+        Self::Number(value as f64)
+    }
+}
+
+impl From<bool> for PropertyValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+/// A feature consisting of a geometry and a map of named properties.
+///
+/// This type can be used with a [`FeatureLayer`](crate::layer::FeatureLayer) when the features
+/// don't need a dedicated struct, with the symbols reading the properties by name:
+///
+/// ```
+/// # use galileo::layer::feature_layer::PropertyFeature;
+/// # use galileo::layer::FeatureLayer;
+/// # use galileo::symbol::GraduatedCircleSymbol;
+/// # use galileo::Color;
+/// # use galileo_types::geo::Crs;
+/// # use galileo_types::geometry_type::GeoSpace2d;
+/// # use galileo_types::latlon;
+/// let city = PropertyFeature::new(latlon!(52.52, 13.40))
+///     .with_property("name", "Berlin")
+///     .with_property("population", 3_878_000.0);
+/// assert_eq!(city.get_str("name"), Some("Berlin"));
+///
+/// let symbol =
+///     GraduatedCircleSymbol::from_property(Color::RED, "population", 0.0, 1e7, 2.0, 20.0);
+/// let layer: FeatureLayer<_, _, _, GeoSpace2d> =
+///     FeatureLayer::new(vec![city], symbol, Crs::WGS84);
+/// ```
+#[derive(Debug, Clone)]
+pub struct PropertyFeature<G> {
+    geometry: G,
+    properties: HashMap<String, PropertyValue>,
+}
+
+impl<G> PropertyFeature<G> {
+    /// Creates a new feature without properties.
+    pub fn new(geometry: G) -> Self {
+        Self {
+            geometry,
+            properties: HashMap::new(),
+        }
+    }
+
+    /// Creates a new feature with the given properties.
+    pub fn with_properties(geometry: G, properties: HashMap<String, PropertyValue>) -> Self {
+        Self {
+            geometry,
+            properties,
+        }
+    }
+
+    /// Sets the value of the property.
+    pub fn with_property(
+        mut self,
+        name: impl Into<String>,
+        value: impl Into<PropertyValue>,
+    ) -> Self {
+        self.set(name, value);
+        self
+    }
+
+    /// Sets the value of the property, returning its previous value.
+    pub fn set(
+        &mut self,
+        name: impl Into<String>,
+        value: impl Into<PropertyValue>,
+    ) -> Option<PropertyValue> {
+        self.properties.insert(name.into(), value.into())
+    }
+
+    /// Value of the property with the given name.
+    pub fn get(&self, name: &str) -> Option<&PropertyValue> {
+        self.properties.get(name)
+    }
+
+    /// Value of the property with the given name if it is a number.
+    pub fn get_f64(&self, name: &str) -> Option<f64> {
+        self.get(name)?.as_f64()
+    }
+
+    /// Value of the property with the given name if it is a string.
+    pub fn get_str(&self, name: &str) -> Option<&str> {
+        self.get(name)?.as_str()
+    }
+
+    /// Value of the property with the given name if it is a boolean.
+    pub fn get_bool(&self, name: &str) -> Option<bool> {
+        self.get(name)?.as_bool()
+    }
+
+    /// All properties of the feature.
+    pub fn properties(&self) -> &HashMap<String, PropertyValue> {
+        &self.properties
+    }
+
+    /// Mutable reference to the properties of the feature.
+    pub fn properties_mut(&mut self) -> &mut HashMap<String, PropertyValue> {
+        &mut self.properties
+    }
+
+    /// Mutable reference to the geometry of the feature.
+    pub fn geometry_mut(&mut self) -> &mut G {
+        &mut self.geometry
+    }
+}
+
+impl<G: Geometry> Feature for PropertyFeature<G> {
+    type Geom = G;
+
+    fn geometry(&self) -> &Self::Geom {
+        &self.geometry
+    }
+}
+
+impl<G> FeatureProperties for PropertyFeature<G> {
+    fn property_f64(&self, name: &str) -> Option<f64> {
+        self.get_f64(name)
+    }
+
+    fn property_str(&self, name: &str) -> Option<&str> {
+        self.get_str(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use galileo_types::cartesian::Point2;
+
+    use super::*;
+
+    #[test]
+    fn typed_getters() {
+        let feature = PropertyFeature::new(Point2::new(0.0, 0.0))
+            .with_property("name", "a")
+            .with_property("value", 5i64)
+            .with_property("visible", true)
+            .with_property("empty", PropertyValue::Null);
+
+        assert_eq!(feature.get_str("name"), Some("a"));
+        assert_eq!(feature.get_f64("value"), Some(5.0));
+        assert_eq!(feature.get_bool("visible"), Some(true));
+        assert_eq!(feature.get("empty"), Some(&PropertyValue::Null));
+
+        assert_eq!(feature.get_f64("name"), None);
+        assert_eq!(feature.get_str("value"), None);
+        assert_eq!(feature.get_str("missing"), None);
+    }
+
+    #[test]
+    fn set_replaces_value() {
+        let mut feature = PropertyFeature::new(Point2::new(0.0, 0.0)).with_property("value", 1.0);
+        assert_eq!(feature.set("value", 2.0), Some(PropertyValue::Number(1.0)));
+        assert_eq!(feature.property_f64("value"), Some(2.0));
+    }
+}
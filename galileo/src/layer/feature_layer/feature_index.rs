@@ -0,0 +1,87 @@
+use galileo_types::cartesian::Rect;
+use galileo_types::geo::Crs;
+use rstar::primitives::{GeomWithData, Rectangle};
+use rstar::{RTree, AABB};
+
+type IndexEntry = GeomWithData<Rectangle<[f64; 2]>, usize>;
+
+/// Spatial index of the bounding rectangles of the features of a [`FeatureLayer`](super::FeatureLayer),
+/// projected into the CRS of the view.
+///
+/// The index stores positions of the features in the [`FeatureStore::iter`](super::FeatureStore::iter)
+/// iterator, so it must be rebuilt every time the features of the layer are edited.
+pub(super) struct FeatureIndex {
+    crs: Crs,
+    tree: RTree<IndexEntry>,
+}
+
+impl FeatureIndex {
+    /// Creates an index of the given feature bounding rectangles in the `crs`.
+    pub(super) fn new(crs: Crs, bboxes: impl IntoIterator<Item = (usize, Rect)>) -> Self {
+        let entries = bboxes
+            .into_iter()
+            .map(|(index, bbox)| {
+                IndexEntry::new(
+                    Rectangle::from_corners(
+                        [bbox.x_min(), bbox.y_min()],
+                        [bbox.x_max(), bbox.y_max()],
+                    ),
+                    index,
+                )
+            })
+            .collect();
+
+        Self {
+            crs,
+            tree: RTree::bulk_load(entries),
+        }
+    }
+
+    /// CRS the bounding rectangles are projected into.
+    pub(super) fn crs(&self) -> &Crs {
+        &self.crs
+    }
+
+    /// Returns positions and bounding rectangles of the features that intersect the `bbox`.
+    pub(super) fn locate(&self, bbox: Rect) -> impl Iterator<Item = (usize, Rect)> + '_ {
+        let envelope =
+            AABB::from_corners([bbox.x_min(), bbox.y_min()], [bbox.x_max(), bbox.y_max()]);
+        self.tree
+            .locate_in_envelope_intersecting(&envelope)
+            .map(|entry| {
+                let [x_min, y_min] = entry.geom().lower();
+                let [x_max, y_max] = entry.geom().upper();
+                (entry.data, Rect::new(x_min, y_min, x_max, y_max))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locate_returns_intersecting_features() {
+        let index = FeatureIndex::new(
+            Crs::EPSG3857,
+            [
+                (0, Rect::new(0.0, 0.0, 10.0, 10.0)),
+                (1, Rect::new(100.0, 100.0, 110.0, 110.0)),
+                (2, Rect::new(5.0, -20.0, 15.0, 1.0)),
+            ],
+        );
+
+        let mut found: Vec<_> = index
+            .locate(Rect::new(-5.0, -5.0, 20.0, 20.0))
+            .map(|(index, _)| index)
+            .collect();
+        found.sort();
+        assert_eq!(found, vec![0, 2]);
+
+        let (_, bbox) = index
+            .locate(Rect::new(105.0, 105.0, 106.0, 106.0))
+            .next()
+            .expect("feature is found");
+        assert_eq!(bbox, Rect::new(100.0, 100.0, 110.0, 110.0));
+    }
+}
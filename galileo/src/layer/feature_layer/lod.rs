@@ -0,0 +1,319 @@
+use galileo_types::cartesian::{self, CartesianPoint3d, Point2, Point3};
+use galileo_types::geometry::Geom;
+use galileo_types::impls::{Contour, MultiContour, MultiPolygon};
+use galileo_types::{Contour as _, MultiContour as _};
+use parking_lot::Mutex;
+
+use super::bundle_store::BundleStore;
+
+/// Relative amount by which the map resolution must leave the range of the current level of detail before a
+/// [`FeatureLayer`](super::FeatureLayer) switches to another level.
+///
+/// Without it, the layer would switch between the levels on every frame when the resolution oscillates around
+/// the boundary, e.g. while the user zooms with a touchpad.
+pub const LOD_HYSTERESIS: f64 = 0.1;
+
+/// Level of detail of a [`FeatureLayer`](super::FeatureLayer).
+///
+/// See [`FeatureLayer::with_lod_levels`](super::FeatureLayer::with_lod_levels).
+#[derive(Debug, Clone)]
+pub struct FeatureLod<S> {
+    min_resolution: f64,
+    simplification_tolerance: f64,
+    symbol: Option<S>,
+}
+
+impl<S> FeatureLod<S> {
+    /// Creates a level of detail used for the map resolutions above `min_resolution`. Features are rendered with
+    /// full detail and the symbol of the layer.
+    pub fn new(min_resolution: f64) -> Self {
+        Self {
+            min_resolution,
+            simplification_tolerance: 0.0,
+            symbol: None,
+        }
+    }
+
+    /// Simplifies geometries of the features at this level, so that they differ from the original ones by not more
+    /// than `tolerance` pixels at the `min_resolution` of the level.
+    ///
+    /// Borders shared by polygons of different features are simplified the same way in all of them, so no gaps or
+    /// overlaps appear between adjacent polygons.
+    pub fn with_simplification(mut self, tolerance: f64) -> Self {
+        self.simplification_tolerance = tolerance;
+        self
+    }
+
+    /// Renders the features at this level with the given symbol instead of the symbol of the layer.
+    pub fn with_symbol(mut self, symbol: S) -> Self {
+        self.symbol = Some(symbol);
+        self
+    }
+
+    /// Minimum resolution the level is used for.
+    pub fn min_resolution(&self) -> f64 {
+        self.min_resolution
+    }
+}
+
+pub(super) struct Lod<S> {
+    pub(super) min_resolution: f64,
+    simplification_tolerance: f64,
+    pub(super) symbol: Option<S>,
+    pub(super) bundles: Mutex<BundleStore>,
+}
+
+impl<S> Lod<S> {
+    pub(super) fn new(level: FeatureLod<S>, bundle_size_limit: usize) -> Self {
+        Self {
+            min_resolution: level.min_resolution,
+            simplification_tolerance: level.simplification_tolerance,
+            symbol: level.symbol,
+            bundles: Mutex::new(BundleStore::new(bundle_size_limit)),
+        }
+    }
+
+    /// Returns true if the geometries are simplified at this level.
+    pub(super) fn simplifies(&self) -> bool {
+        self.simplification_tolerance > 0.0
+    }
+
+    /// Simplifies the projected geometries according to the level's tolerance.
+    ///
+    /// All polygons are simplified together, so borders shared by adjacent features stay coincident (see
+    /// [`cartesian::simplify_polygons_by`]). Because of that, all the features of the layer must be simplified at
+    /// once.
+    pub(super) fn simplify(
+        &self,
+        geometries: Vec<Option<Geom<Point3>>>,
+    ) -> Vec<Option<Geom<Point3>>> {
+        if !self.simplifies() {
+            return geometries;
+        }
+
+        simplify_geometries(
+            geometries,
+            self.simplification_tolerance * self.min_resolution,
+        )
+    }
+}
+
+/// Index of the level that should be used for the `resolution`, given the currently used level. Levels must be
+/// sorted by their `min_resolution` in descending order.
+pub(super) fn select_lod_index<S>(
+    lods: &[Lod<S>],
+    resolution: f64,
+    current: Option<usize>,
+) -> usize {
+    let candidate = lods
+        .iter()
+        .position(|lod| lod.min_resolution < resolution)
+        .unwrap_or(lods.len() - 1);
+
+    match current {
+        Some(current) if current < lods.len() && current != candidate => {
+            let lower = if current == lods.len() - 1 {
+                0.0
+            } else {
+                lods[current].min_resolution
+            };
+            let upper = if current == 0 {
+                f64::INFINITY
+            } else {
+                lods[current - 1].min_resolution
+            };
+
+            if resolution > lower / (1.0 + LOD_HYSTERESIS)
+                && resolution <= upper * (1.0 + LOD_HYSTERESIS)
+            {
+                current
+            } else {
+                candidate
+            }
+        }
+        _ => candidate,
+    }
+}
+
+fn simplify_geometries(
+    geometries: Vec<Option<Geom<Point3>>>,
+    tolerance: f64,
+) -> Vec<Option<Geom<Point3>>> {
+    let mut polygons = vec![];
+    for geometry in geometries.iter().flatten() {
+        match geometry {
+            Geom::Polygon(polygon) => polygons.push(polygon.clone()),
+            Geom::MultiPolygon(multi_polygon) => {
+                polygons.extend(multi_polygon.parts.iter().cloned())
+            }
+            _ => {}
+        }
+    }
+
+    let mut simplified = cartesian::simplify_polygons_by(&polygons, tolerance, &xy).into_iter();
+    let mut next_polygon = || {
+        simplified
+            .next()
+            .expect("every polygon of the features is simplified")
+    };
+
+    geometries
+        .into_iter()
+        .map(|geometry| {
+            Some(match geometry? {
+                Geom::Contour(contour) => Geom::Contour(simplify_contour(&contour, tolerance)),
+                Geom::MultiContour(contours) => Geom::MultiContour(MultiContour::from(
+                    contours
+                        .contours()
+                        .map(|contour| simplify_contour(contour, tolerance))
+                        .collect::<Vec<_>>(),
+                )),
+                Geom::Polygon(_) => Geom::Polygon(next_polygon()),
+                Geom::MultiPolygon(multi_polygon) => Geom::MultiPolygon(MultiPolygon {
+                    parts: multi_polygon.parts.iter().map(|_| next_polygon()).collect(),
+                }),
+                points => points,
+            })
+        })
+        .collect()
+}
+
+fn simplify_contour(contour: &Contour<Point3>, tolerance: f64) -> Contour<Point3> {
+    let points: Vec<_> = contour.iter_points().collect();
+    let simplified = if contour.is_closed() {
+        simplify_ring(points, tolerance)
+    } else {
+        douglas_peucker(&points, tolerance)
+    };
+
+    Contour::new(simplified, contour.is_closed())
+}
+
+/// Simplifies a closed ring, keeping the original ring if it would degenerate.
+fn simplify_ring(mut points: Vec<Point3>, tolerance: f64) -> Vec<Point3> {
+    let Some(&first) = points.first() else {
+        return points;
+    };
+
+    points.push(first);
+    let mut simplified = douglas_peucker(&points, tolerance);
+    simplified.pop();
+    points.pop();
+
+    if simplified.len() < 3 {
+        points
+    } else {
+        simplified
+    }
+}
+
+/// Simplifies the line in the XY plane, keeping the z coordinates of the remaining points.
+fn douglas_peucker(points: &[Point3], tolerance: f64) -> Vec<Point3> {
+    cartesian::douglas_peucker(points, tolerance, &xy)
+}
+
+fn xy(point: &Point3) -> Point2 {
+    Point2::new(point.x(), point.y())
+}
+
+#[cfg(test)]
+mod tests {
+    use galileo_types::impls::{ClosedContour, Polygon};
+
+    use super::*;
+
+    fn lods(min_resolutions: &[f64]) -> Vec<Lod<()>> {
+        min_resolutions
+            .iter()
+            .map(|&r| Lod::new(FeatureLod::new(r), 1000))
+            .collect()
+    }
+
+    #[test]
+    fn lod_switch_is_hysteretic() {
+        let lods = lods(&[100.0, 1.0]);
+
+        let mut current = select_lod_index(&lods, 150.0, None);
+        assert_eq!(current, 0);
+
+        // Slightly below the boundary the coarse level is kept.
+        current = select_lod_index(&lods, 95.0, Some(current));
+        assert_eq!(current, 0);
+
+        current = select_lod_index(&lods, 80.0, Some(current));
+        assert_eq!(current, 1);
+
+        // Slightly above the boundary the fine level is kept.
+        current = select_lod_index(&lods, 105.0, Some(current));
+        assert_eq!(current, 1);
+
+        current = select_lod_index(&lods, 120.0, Some(current));
+        assert_eq!(current, 0);
+    }
+
+    #[test]
+    fn ring_does_not_degenerate() {
+        let ring = vec![
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(0.0, 1.0, 0.0),
+            Point3::new(1.0, 1.0, 0.0),
+            Point3::new(1.0, 0.0, 0.0),
+        ];
+
+        assert_eq!(simplify_ring(ring.clone(), 10.0), ring);
+    }
+
+    #[test]
+    fn shared_borders_of_features_stay_coincident() {
+        let polygon = |points: &[(f64, f64)]| {
+            Some(Geom::Polygon(Polygon::new(
+                ClosedContour::new(
+                    points
+                        .iter()
+                        .map(|&(x, y)| Point3::new(x, y, 0.0))
+                        .collect(),
+                ),
+                vec![],
+            )))
+        };
+        let left = polygon(&[
+            (0.0, 0.0),
+            (1.0, 0.0),
+            (1.0, 0.3),
+            (1.05, 0.5),
+            (1.0, 0.7),
+            (1.0, 1.0),
+            (0.0, 1.0),
+        ]);
+        let right = polygon(&[
+            (1.0, 0.0),
+            (2.0, 0.0),
+            (2.0, 1.0),
+            (1.0, 1.0),
+            (1.0, 0.7),
+            (1.05, 0.5),
+            (1.0, 0.3),
+        ]);
+
+        let lod = Lod::<()>::new(FeatureLod::new(1.0).with_simplification(0.1), 1000);
+        let simplified = lod.simplify(vec![left, None, right]);
+        assert!(simplified[1].is_none());
+
+        let border = |geometry: &Option<Geom<Point3>>| {
+            let Some(Geom::Polygon(polygon)) = geometry else {
+                panic!("polygon is expected");
+            };
+            let mut points: Vec<_> = polygon
+                .outer_contour
+                .points
+                .iter()
+                .filter(|p| p.x() > 0.9 && p.x() < 1.1)
+                .map(|p| (p.x(), p.y()))
+                .collect();
+            points.sort_by(|a, b| a.1.total_cmp(&b.1));
+            points
+        };
+        assert_eq!(border(&simplified[0]), vec![(1.0, 0.0), (1.0, 1.0)]);
+        assert_eq!(border(&simplified[0]), border(&simplified[2]));
+    }
+}
@@ -5,7 +5,8 @@ use std::marker::PhantomData;
 use std::ops::Deref;
 
 use galileo_types::cartesian::{
-    CartesianPoint2d, NewCartesianPoint2d, NewCartesianPoint3d, Point2, Point3, Rect,
+    CartesianPoint2d, CartesianPoint3d, NewCartesianPoint2d, NewCartesianPoint3d, Point2, Point3,
+    Rect,
 };
 use galileo_types::geo::impls::projection::{AddDimensionProjection, IdentityProjection};
 use galileo_types::geo::impls::GeoPoint2d;
@@ -13,7 +14,7 @@ use galileo_types::geo::{ChainProjection, Crs, InvertedProjection, NewGeoPoint,
 use galileo_types::geometry::{CartesianGeometry2d, Geometry};
 use galileo_types::geometry_type::{CartesianSpace2d, CartesianSpace3d, GeoSpace2d};
 use maybe_sync::{MaybeSend, MaybeSync};
-use num_traits::AsPrimitive;
+use num_traits::{AsPrimitive, FromPrimitive, Zero};
 use parking_lot::{Mutex, RwLock};
 
 use crate::layer::attribution::Attribution;
@@ -27,11 +28,22 @@ mod feature_store;
 pub mod symbol;
 
 mod bundle_store;
+mod feature_index;
+mod lod;
 use bundle_store::{BundleStore, UpdateType};
-pub use feature::Feature;
+#[cfg(feature = "geojson")]
+pub use feature::GeoJsonFeature;
+pub use feature::{Feature, FeaturePayload, FeatureProperties, PropertyFeature, PropertyValue};
+use feature_index::FeatureIndex;
 use feature_store::VecFeatureStore;
 pub use feature_store::{FeatureId, FeatureStore};
-pub use symbol::{Symbol, CirclePointSymbol, ImagePointSymbol, TextMarkerSymbol, TextProvider};
+use lod::{select_lod_index, Lod};
+pub use lod::{FeatureLod, LOD_HYSTERESIS};
+pub use symbol::{
+    CategoricalPointSymbol, CirclePointSymbol, GraduatedCircleSymbol, GraduatedScale,
+    ImagePointSymbol, RotatedImagePointSymbol, SpriteSheetSymbol, Symbol, TextMarkerSymbol,
+    TextProvider,
+};
 
 /// Feature layers render a set of [features](Feature) using [symbols](Symbol).
 ///
@@ -44,7 +56,7 @@ pub use symbol::{Symbol, CirclePointSymbol, ImagePointSymbol, TextMarkerSymbol,
 /// hand, the CRS of the layer doesn't have to be same as the CRS of the map. When the layer is requested to be rendered,
 /// it will project all its features into needed CRS automatically.
 ///
-/// Feature layer can render features differently at different resolutions. See [`FeatureLayer::with_lods`] for
+/// Feature layer can render features differently at different resolutions. See [`FeatureLayer::with_lod_levels`] for
 /// details.
 pub struct FeatureLayer<P, F, S, Space>
 where
@@ -54,14 +66,33 @@ where
     features: Box<dyn FeatureStore<F>>,
     symbol: S,
     crs: Crs,
-    lods: Vec<Lod>,
+    lods: Vec<Lod<S>>,
+    selected_lod: Mutex<Option<usize>>,
     messenger: RwLock<Option<Box<dyn Messenger>>>,
     options: FeatureLayerOptions,
+    sort_key: Option<Box<dyn SortKey<F>>>,
+    feature_index: Mutex<Option<FeatureIndex>>,
 
     space: PhantomData<Space>,
 }
 
+/// Function that returns the draw order key of a feature. See [`FeatureLayer::with_sort_key`].
+pub trait SortKey<F>: Fn(&F) -> i32 + MaybeSend + MaybeSync {}
+impl<F, T: Fn(&F) -> i32 + MaybeSend + MaybeSync> SortKey<F> for T {}
+
 /// Configuration of a [FeatureLayer].
+///
+/// New options may be added in the future, so prefer filling the options that are not set explicitly with
+/// `..Default::default()`:
+///
+/// ```
+/// use galileo::layer::feature_layer::FeatureLayerOptions;
+///
+/// let options = FeatureLayerOptions {
+///     sort_by_depth: true,
+///     ..Default::default()
+/// };
+/// ```
 #[derive(Debug, Copy, Clone)]
 pub struct FeatureLayerOptions {
     /// If set to true, images drawn by the layer will be sorted by the depth value (relative to viewer) before being
@@ -85,6 +116,20 @@ pub struct FeatureLayerOptions {
     /// If set to true, the layer will be rendered with anti-aliasing. It makes rendered lines look smoother but is a
     /// little less performant.
     pub use_antialiasing: bool,
+
+    /// Maximum distance in pixels between curved shapes and their tessellation. Lower values give smoother curves,
+    /// higher values produce fewer vertices. See [`RenderBundle::with_tessellation_tolerance`].
+    ///
+    /// [`RenderBundle::with_tessellation_tolerance`]: crate::render::render_bundle::RenderBundle::with_tessellation_tolerance
+    pub tessellation_tolerance: f32,
+
+    /// Scale factor of the display the layer is rendered to. Font sizes given in points are multiplied by it. See
+    /// [`RenderBundle::with_scale_factor`].
+    ///
+    /// If not set, the scale factor of the canvas the layer is drawn to is used (see [`Canvas::scale_factor`]).
+    ///
+    /// [`RenderBundle::with_scale_factor`]: crate::render::render_bundle::RenderBundle::with_scale_factor
+    pub scale_factor: Option<f32>,
 }
 
 impl Default for FeatureLayerOptions {
@@ -93,20 +138,8 @@ impl Default for FeatureLayerOptions {
             sort_by_depth: false,
             buffer_size_limit: 10_000_000,
             use_antialiasing: true,
-        }
-    }
-}
-
-struct Lod {
-    min_resolution: f64,
-    bundles: Mutex<BundleStore>,
-}
-
-impl Lod {
-    fn new(min_resolution: f64, bundle_size_limit: usize) -> Self {
-        Self {
-            min_resolution,
-            bundles: Mutex::new(BundleStore::new(bundle_size_limit)),
+            tessellation_tolerance: 0.1,
+            scale_factor: None,
         }
     }
 }
@@ -119,26 +152,40 @@ where
 {
     /// Creates a new layer with the given parameters.
     pub fn new(features: Vec<F>, style: S, crs: Crs) -> Self {
-        let options = FeatureLayerOptions::default();
-        Self {
-            features: Box::new(VecFeatureStore::new(features)),
-            symbol: style,
-            crs,
-            messenger: RwLock::new(None),
-            lods: vec![Lod::new(1.0, options.buffer_size_limit)],
-            options,
-            space: Default::default(),
-        }
+        Self::with_lod_levels(features, style, crs, vec![])
     }
 
     /// Creates a new layer with specified levels of detail.
     ///
     /// Levels of details specify resolution boundaries at which feature must be rendered separately.
     pub fn with_lods(features: Vec<F>, style: S, crs: Crs, lods: &[f64]) -> Self {
+        let levels = lods.iter().map(|&r| FeatureLod::new(r)).collect();
+        Self::with_lod_levels(features, style, crs, levels)
+    }
+
+    /// Creates a new layer with the given levels of detail.
+    ///
+    /// Each level is used for the map resolutions between its own `min_resolution` and the `min_resolution` of the
+    /// next coarser level. At each level the geometries of the features can be simplified and rendered with a
+    /// different symbol, so that a large layer can be drawn with fewer vertices and simpler symbols when the map is
+    /// zoomed out. Near the boundary between two levels the layer keeps the level it used last
+    /// (see [`LOD_HYSTERESIS`]), so that it does not flicker when the resolution changes slightly.
+    ///
+    /// If no levels are given, a single level without simplification is used.
+    pub fn with_lod_levels(
+        features: Vec<F>,
+        style: S,
+        crs: Crs,
+        mut levels: Vec<FeatureLod<S>>,
+    ) -> Self {
         let options = FeatureLayerOptions::default();
-        let mut lods: Vec<_> = lods
-            .iter()
-            .map(|&min_resolution| Lod::new(min_resolution, options.buffer_size_limit))
+        if levels.is_empty() {
+            levels.push(FeatureLod::new(1.0));
+        }
+
+        let mut lods: Vec<_> = levels
+            .into_iter()
+            .map(|level| Lod::new(level, options.buffer_size_limit))
             .collect();
         lods.sort_by(|a, b| b.min_resolution.total_cmp(&a.min_resolution));
 
@@ -148,7 +195,10 @@ where
             crs,
             messenger: RwLock::new(None),
             lods,
+            selected_lod: Mutex::new(None),
             options,
+            sort_key: None,
+            feature_index: Mutex::new(None),
             space: Default::default(),
         }
     }
@@ -160,11 +210,35 @@ where
         for lod in &self.lods {
             let mut store = lod.bundles.lock();
             store.set_bundle_size_limit(options.buffer_size_limit);
+            store.set_tessellation_tolerance(options.tessellation_tolerance);
+            if let Some(scale_factor) = options.scale_factor {
+                store.set_scale_factor(scale_factor);
+            }
         }
 
         self
     }
 
+    /// Sets the function that specifies the order in which features are drawn.
+    ///
+    /// Features with higher keys are drawn on top of features with lower keys. Features with equal keys are drawn in
+    /// the order they are stored in the layer.
+    ///
+    /// Note, that with a sort key set, updating a single feature requires all the features of the layer to be
+    /// rendered again to keep the order.
+    pub fn with_sort_key(mut self, sort_key: impl SortKey<F> + 'static) -> Self {
+        self.set_sort_key(Some(Box::new(sort_key)));
+        self
+    }
+
+    /// Changes the function that specifies the order in which features are drawn, or resets it to the
+    /// insertion order if `None` is given. See [`FeatureLayer::with_sort_key`].
+    pub fn set_sort_key(&mut self, sort_key: Option<Box<dyn SortKey<F>>>) {
+        self.sort_key = sort_key;
+        self.drop_render_cache();
+        self.request_redraw();
+    }
+
     /// Returns a reference to the feature store.
     pub fn features(&self) -> &dyn FeatureStore<F> {
         &*self.features
@@ -172,6 +246,7 @@ where
 
     /// Returns a mutable reference to the feature store.
     pub fn features_mut(&mut self) -> &mut dyn FeatureStore<F> {
+        *self.feature_index.get_mut() = None;
         &mut *self.features
     }
 
@@ -189,6 +264,7 @@ where
 
     /// Marks the feature with the given id to be redrawn on the next render cycles.
     pub fn update_feature(&self, feature_id: FeatureId) {
+        *self.feature_index.lock() = None;
         for lod in &self.lods {
             lod.bundles.lock().reset_feature(feature_id);
         }
@@ -196,6 +272,7 @@ where
 
     /// Rerenders all features in the layer.
     pub fn update_all_features(&mut self) {
+        *self.feature_index.get_mut() = None;
         self.drop_render_cache();
     }
 
@@ -212,16 +289,14 @@ where
         }
     }
 
-    fn select_lod(&self, resolution: f64) -> &Lod {
+    fn select_lod(&self, resolution: f64) -> &Lod<S> {
         debug_assert!(!self.lods.is_empty());
 
-        for lod in &self.lods {
-            if lod.min_resolution < resolution {
-                return lod;
-            }
-        }
+        let mut selected = self.selected_lod.lock();
+        let index = select_lod_index(&self.lods, resolution, *selected);
+        *selected = Some(index);
 
-        &self.lods[self.lods.len() - 1]
+        &self.lods[index]
     }
 
     fn render_with_projection<Proj: Projection<InPoint = P, OutPoint = Point3> + ?Sized>(
@@ -233,37 +308,12 @@ where
         let lod = self.select_lod(view.resolution());
         let mut store = lod.bundles.lock();
 
-        match store.required_update() {
-            UpdateType::All => {
-                for (id, feature) in self.features.iter() {
-                    store.with_bundle(|bundle| {
-                        if let Some(projected) = feature.geometry().project(&*projection) {
-                            self.symbol
-                                .render(feature, &projected, lod.min_resolution, bundle);
-                        }
-
-                        id
-                    });
-                }
-            }
-            UpdateType::Selected(ids) => {
-                for id in ids {
-                    let Some(feature) = self.features.get(id) else {
-                        continue;
-                    };
-                    store.with_bundle(|bundle| {
-                        if let Some(projected) = feature.geometry().project(&*projection) {
-                            self.symbol
-                                .render(feature, &projected, lod.min_resolution, bundle);
-                        }
-
-                        id
-                    });
-                }
-            }
-            UpdateType::None => {}
-        }
-
+        store.set_scale_factor(
+            self.options
+                .scale_factor
+                .unwrap_or_else(|| canvas.scale_factor()),
+        );
+        self.update_bundles(lod, &mut store, &*projection);
         store.pack(canvas);
 
         canvas.draw_bundles(
@@ -273,6 +323,162 @@ where
             },
         );
     }
+
+    fn update_bundles<Proj: Projection<InPoint = P, OutPoint = Point3> + ?Sized>(
+        &self,
+        lod: &Lod<S>,
+        store: &mut BundleStore,
+        projection: &Proj,
+    ) {
+        let update = match store.required_update() {
+            UpdateType::Selected(_) if self.sort_key.is_some() || lod.simplifies() => {
+                // Updated features would be added to the end of the draw list, so to keep the order
+                // everything must be rendered again. Simplified features share borders with their
+                // neighbours, so those must be simplified again too.
+                store.clear();
+                store.required_update()
+            }
+            update => update,
+        };
+
+        let mut features: Vec<(FeatureId, &F)> = match update {
+            UpdateType::All => self.features.iter().collect(),
+            UpdateType::Selected(ids) => ids
+                .into_iter()
+                .filter_map(|id| Some((id, self.features.get(id)?)))
+                .collect(),
+            UpdateType::None => return,
+        };
+
+        if let Some(sort_key) = &self.sort_key {
+            features.sort_by_key(|&(_, feature)| sort_key(feature));
+        }
+
+        let projected = lod.simplify(
+            features
+                .iter()
+                .map(|(_, feature)| feature.geometry().project(projection))
+                .collect(),
+        );
+
+        for ((id, feature), projected) in features.into_iter().zip(projected) {
+            store.with_bundle(|bundle| {
+                if let Some(projected) = projected {
+                    lod.symbol.as_ref().unwrap_or(&self.symbol).render(
+                        feature,
+                        &projected,
+                        lod.min_resolution,
+                        bundle,
+                    );
+                }
+
+                id
+            });
+        }
+    }
+}
+
+impl<P, F, S, Space> FeatureLayer<P, F, S, Space>
+where
+    F: Feature,
+    F::Geom: Geometry<Point = P>,
+{
+    fn visible_features_with_projection<Proj>(
+        &self,
+        view: &MapView,
+        projection: &Proj,
+        sort_by_distance: bool,
+    ) -> Vec<usize>
+    where
+        Proj: Projection<InPoint = P, OutPoint = Point2> + ?Sized,
+    {
+        let Some(view_bbox) = view.get_bbox() else {
+            return vec![];
+        };
+        let Some(center) = view.projected_position() else {
+            return vec![];
+        };
+        let center = Point2::new(center.x(), center.y());
+
+        let mut feature_index = self.feature_index.lock();
+        if feature_index.as_ref().map(FeatureIndex::crs) != Some(view.crs()) {
+            *feature_index = Some(self.build_index(view.crs(), projection));
+        }
+        let Some(feature_index) = feature_index.as_ref() else {
+            return vec![];
+        };
+
+        let mut visible: Vec<(usize, f64)> = feature_index
+            .locate(view_bbox)
+            .filter_map(|(index, bbox)| {
+                if !Self::is_on_screen(view, bbox) {
+                    return None;
+                }
+
+                let dx = (bbox.x_min() - center.x())
+                    .max(center.x() - bbox.x_max())
+                    .max(0.0);
+                let dy = (bbox.y_min() - center.y())
+                    .max(center.y() - bbox.y_max())
+                    .max(0.0);
+                Some((index, dx * dx + dy * dy))
+            })
+            .collect();
+
+        // The index returns features in arbitrary order.
+        visible.sort_unstable_by_key(|(index, _)| *index);
+        if sort_by_distance {
+            visible.sort_by(|a, b| a.1.total_cmp(&b.1));
+        }
+
+        visible.into_iter().map(|(index, _)| index).collect()
+    }
+
+    fn build_index<Proj>(&self, crs: &Crs, projection: &Proj) -> FeatureIndex
+    where
+        Proj: Projection<InPoint = P, OutPoint = Point2> + ?Sized,
+    {
+        FeatureIndex::new(
+            crs.clone(),
+            self.features
+                .iter()
+                .enumerate()
+                .filter_map(|(index, (_, feature))| {
+                    let bbox = feature
+                        .geometry()
+                        .project(projection)?
+                        .bounding_rectangle()?;
+                    Some((index, bbox))
+                }),
+        )
+    }
+
+    /// The bbox of the view is axis aligned, so when the map is rotated it covers more than the
+    /// visible area. This checks the `bbox` against the actual screen rectangle.
+    ///
+    /// With a tilted view the corners of the `bbox` can be behind the camera, so the check is only
+    /// done for views that are rotated around the *Z* axis.
+    fn is_on_screen(view: &MapView, bbox: Rect) -> bool {
+        if view.rotation_x() != 0.0 {
+            return true;
+        }
+
+        let Some(corners) = bbox
+            .into_quadrangle()
+            .into_iter()
+            .map(|p| view.map_to_screen(p))
+            .collect::<Option<Vec<_>>>()
+        else {
+            return true;
+        };
+
+        let Some(screen_bbox) = Rect::from_points(corners) else {
+            return true;
+        };
+
+        let size = view.size();
+        screen_bbox.intersects(Rect::new(0.0, 0.0, size.width(), size.height()))
+    }
 }
 
 impl<P, F, S> FeatureLayer<P, F, S, GeoSpace2d>
@@ -281,6 +487,32 @@ where
     F: Feature + MaybeSync + MaybeSend,
     F::Geom: Geometry<Point = P>,
 {
+    /// Returns indices of the features that are inside the area currently shown by the `view`.
+    ///
+    /// Indices correspond to the position of the feature in the [`FeatureStore::iter`] iterator.
+    /// A feature is considered visible if its bounding rectangle, projected into the CRS of the
+    /// view, intersects the visible area. Rotation of the view is taken into account.
+    ///
+    /// The bounding rectangles are kept in a spatial index, which is built on the first call and
+    /// rebuilt after the features are edited or the CRS of the view changes.
+    ///
+    /// Features that cannot be projected into the CRS of the view are skipped.
+    pub fn visible_features(&self, view: &MapView) -> Vec<usize> {
+        let Some(projection) = view.crs().get_projection::<P, Point2>() else {
+            return vec![];
+        };
+        self.visible_features_with_projection(view, &*projection, false)
+    }
+
+    /// Same as [`FeatureLayer::visible_features`], but the returned indices are sorted by the
+    /// distance from the center of the view to the feature's bounding rectangle.
+    pub fn visible_features_by_distance(&self, view: &MapView) -> Vec<usize> {
+        let Some(projection) = view.crs().get_projection::<P, Point2>() else {
+            return vec![];
+        };
+        self.visible_features_with_projection(view, &*projection, true)
+    }
+
     /// Extend (bounding rectangle) of the layer, projected into given CRS.
     ///
     /// If the layer doesn't contain any features, or if at least one of them cannot be projected into the given
@@ -334,10 +566,62 @@ where
     where
         F::Geom: CartesianGeometry2d<P>,
     {
+        *self.feature_index.get_mut() = None;
         self.features
             .iter_mut()
             .filter(move |(_, f)| f.geometry().is_point_inside(point, tolerance))
     }
+
+    /// Returns an iterator of payloads of the features that are within `tolerance` units from the
+    /// `point`. See [`FeatureLayer::get_features_at`] for details.
+    pub fn get_payloads_at<'a>(
+        &'a self,
+        point: &'a impl CartesianPoint2d<Num = P::Num>,
+        tolerance: P::Num,
+    ) -> impl Iterator<Item = (FeatureId, &'a F::Payload)> + 'a
+    where
+        F: FeaturePayload,
+        F::Geom: CartesianGeometry2d<P>,
+    {
+        self.get_features_at(point, tolerance)
+            .map(|(id, feature)| (id, feature.payload()))
+    }
+
+    /// Returns an iterator of features that are within `pixel_tolerance` screen pixels from the
+    /// `point` when displayed with the given `view`. The tolerance is converted into the layer
+    /// units using the resolution of the view, so the same pixel tolerance selects the same
+    /// features on the screen at any zoom level.
+    ///
+    /// The `point` is expected to be set in the layer's CRS, and the CRS of the view is expected
+    /// to be the same as the one of the layer.
+    pub fn get_features_within_pixels<'a>(
+        &'a self,
+        point: &'a impl CartesianPoint2d<Num = P::Num>,
+        pixel_tolerance: f64,
+        view: &MapView,
+    ) -> impl Iterator<Item = (FeatureId, &'a F)> + 'a
+    where
+        F::Geom: CartesianGeometry2d<P>,
+    {
+        self.get_features_at(point, pixels_to_units::<P::Num>(pixel_tolerance, view))
+    }
+
+    /// Mutable version of [`FeatureLayer::get_features_within_pixels`].
+    pub fn get_features_within_pixels_mut<'a>(
+        &'a mut self,
+        point: &'a impl CartesianPoint2d<Num = P::Num>,
+        pixel_tolerance: f64,
+        view: &MapView,
+    ) -> impl Iterator<Item = (FeatureId, &'a mut F)> + 'a
+    where
+        F::Geom: CartesianGeometry2d<P>,
+    {
+        self.get_features_at_mut(point, pixels_to_units::<P::Num>(pixel_tolerance, view))
+    }
+}
+
+fn pixels_to_units<N: FromPrimitive + Zero>(pixels: f64, view: &MapView) -> N {
+    N::from_f64(pixels * view.resolution()).unwrap_or_else(N::zero)
 }
 
 impl<P, F, S> FeatureLayer<P, F, S, GeoSpace2d>
@@ -416,6 +700,49 @@ where
             )))
         }
     }
+
+    fn get_projection_2d(
+        &self,
+        crs: &Crs,
+    ) -> Option<Box<dyn Projection<InPoint = P, OutPoint = Point2>>> {
+        if crs == &self.crs {
+            Some(Box::new(IdentityProjection::<_, _, CartesianSpace2d>::new()))
+        } else {
+            let self_proj = self.crs.get_projection::<GeoPoint2d, P>()?;
+            let view_proj: Box<dyn Projection<InPoint = _, OutPoint = Point2>> =
+                crs.get_projection()?;
+            Some(Box::new(ChainProjection::new(
+                Box::new(InvertedProjection::new(self_proj)),
+                view_proj,
+            )))
+        }
+    }
+
+    /// Returns indices of the features that are inside the area currently shown by the `view`.
+    ///
+    /// Indices correspond to the position of the feature in the [`FeatureStore::iter`] iterator.
+    /// A feature is considered visible if its bounding rectangle, projected into the CRS of the
+    /// view, intersects the visible area. Rotation of the view is taken into account.
+    ///
+    /// The bounding rectangles are kept in a spatial index, which is built on the first call and
+    /// rebuilt after the features are edited or the CRS of the view changes.
+    ///
+    /// Features that cannot be projected into the CRS of the view are skipped.
+    pub fn visible_features(&self, view: &MapView) -> Vec<usize> {
+        let Some(projection) = self.get_projection_2d(view.crs()) else {
+            return vec![];
+        };
+        self.visible_features_with_projection(view, &*projection, false)
+    }
+
+    /// Same as [`FeatureLayer::visible_features`], but the returned indices are sorted by the
+    /// distance from the center of the view to the feature's bounding rectangle.
+    pub fn visible_features_by_distance(&self, view: &MapView) -> Vec<usize> {
+        let Some(projection) = self.get_projection_2d(view.crs()) else {
+            return vec![];
+        };
+        self.visible_features_with_projection(view, &*projection, true)
+    }
 }
 
 impl<P, F, S> Layer for FeatureLayer<P, F, S, CartesianSpace2d>
@@ -504,3 +831,257 @@ where
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use galileo_types::cartesian::Size;
+    use galileo_types::geometry::Geom;
+    use galileo_types::impls::Contour;
+
+    use super::*;
+    use crate::layer::feature_layer::symbol::SimpleContourSymbol;
+    use crate::render::render_bundle::RenderBundle;
+    use crate::Color;
+
+    fn test_layer(
+        points: Vec<Point2>,
+    ) -> FeatureLayer<Point2, Point2, CirclePointSymbol, CartesianSpace2d> {
+        FeatureLayer::new(
+            points,
+            CirclePointSymbol::new(Color::BLACK, 5.0),
+            Crs::EPSG3857,
+        )
+    }
+
+    fn test_view() -> MapView {
+        MapView::new_projected(&Point2::new(0.0, 0.0), 1.0).with_size(Size::new(100.0, 100.0))
+    }
+
+    #[test]
+    fn visible_features_returns_only_features_in_view() {
+        let layer = test_layer(vec![
+            Point2::new(0.0, 0.0),
+            Point2::new(1000.0, 1000.0),
+            Point2::new(-40.0, 20.0),
+            Point2::new(0.0, -60.0),
+        ]);
+
+        assert_eq!(layer.visible_features(&test_view()), vec![0, 2]);
+    }
+
+    #[test]
+    fn visible_features_accounts_for_rotation() {
+        let layer = test_layer(vec![Point2::new(45.0, 45.0), Point2::new(60.0, 0.0)]);
+
+        assert_eq!(layer.visible_features(&test_view()), vec![0]);
+
+        let rotated = test_view().with_rotation_z(std::f64::consts::FRAC_PI_4);
+        assert_eq!(layer.visible_features(&rotated), vec![1]);
+    }
+
+    #[test]
+    fn visible_features_sees_edited_features() {
+        let mut layer = test_layer(vec![Point2::new(0.0, 0.0)]);
+        assert_eq!(layer.visible_features(&test_view()), vec![0]);
+
+        layer.features_mut().add(Point2::new(10.0, 10.0));
+        assert_eq!(layer.visible_features(&test_view()), vec![0, 1]);
+
+        for (_, point) in layer.features_mut().iter_mut() {
+            *point = Point2::new(1000.0, 1000.0);
+        }
+        assert!(layer.visible_features(&test_view()).is_empty());
+    }
+
+    #[test]
+    fn visible_features_by_distance_sorts_from_center() {
+        let layer = test_layer(vec![
+            Point2::new(30.0, 30.0),
+            Point2::new(5.0, 0.0),
+            Point2::new(0.0, -20.0),
+        ]);
+
+        assert_eq!(
+            layer.visible_features_by_distance(&test_view()),
+            vec![1, 2, 0]
+        );
+    }
+
+    struct Place {
+        position: Point2,
+        id: u32,
+    }
+
+    impl Feature for Place {
+        type Geom = Point2;
+
+        fn geometry(&self) -> &Self::Geom {
+            &self.position
+        }
+    }
+
+    impl FeaturePayload for Place {
+        type Payload = u32;
+
+        fn payload(&self) -> &Self::Payload {
+            &self.id
+        }
+    }
+
+    #[test]
+    fn get_payloads_at_returns_payload_of_matched_feature() {
+        let layer: FeatureLayer<Point2, Place, CirclePointSymbol, CartesianSpace2d> =
+            FeatureLayer::new(
+                vec![
+                    Place {
+                        position: Point2::new(0.0, 0.0),
+                        id: 10,
+                    },
+                    Place {
+                        position: Point2::new(100.0, 0.0),
+                        id: 20,
+                    },
+                ],
+                CirclePointSymbol::new(Color::BLACK, 5.0),
+                Crs::EPSG3857,
+            );
+
+        let payloads: Vec<u32> = layer
+            .get_payloads_at(&Point2::new(99.0, 1.0), 2.0)
+            .map(|(_, id)| *id)
+            .collect();
+        assert_eq!(payloads, vec![20]);
+    }
+
+    #[test]
+    fn pixel_tolerance_scales_with_resolution() {
+        let line = Contour::open(vec![Point2::new(0.0, 0.0), Point2::new(100.0, 0.0)]);
+        let layer: FeatureLayer<Point2, Contour<Point2>, SimpleContourSymbol, CartesianSpace2d> =
+            FeatureLayer::new(
+                vec![line],
+                SimpleContourSymbol::new(Color::BLACK, 1.0),
+                Crs::EPSG3857,
+            );
+
+        let close = test_view();
+        let far = test_view().with_resolution(10.0);
+
+        // 4 units from the line is 4 pixels at resolution 1 and 0.4 pixels at resolution 10.
+        let point = Point2::new(50.0, 4.0);
+        assert_eq!(
+            layer
+                .get_features_within_pixels(&point, 5.0, &close)
+                .count(),
+            1
+        );
+        assert_eq!(
+            layer.get_features_within_pixels(&point, 5.0, &far).count(),
+            1
+        );
+
+        // 40 units from the line is 40 pixels at resolution 1 and 4 pixels at resolution 10.
+        let point = Point2::new(50.0, 40.0);
+        assert_eq!(
+            layer
+                .get_features_within_pixels(&point, 5.0, &close)
+                .count(),
+            0
+        );
+        assert_eq!(
+            layer.get_features_within_pixels(&point, 5.0, &far).count(),
+            1
+        );
+    }
+
+    #[derive(Default)]
+    struct RecordingSymbol {
+        rendered: Mutex<Vec<u32>>,
+    }
+
+    impl Symbol<Place> for RecordingSymbol {
+        fn render(
+            &self,
+            feature: &Place,
+            _geometry: &Geom<Point3>,
+            _min_resolution: f64,
+            _bundle: &mut RenderBundle,
+        ) {
+            self.rendered.lock().push(feature.id);
+        }
+    }
+
+    #[test]
+    fn sort_key_draws_higher_keys_on_top() {
+        let place = |id| Place {
+            position: Point2::new(0.0, 0.0),
+            id,
+        };
+        let layer: FeatureLayer<Point2, Place, RecordingSymbol, CartesianSpace2d> =
+            FeatureLayer::new(
+                vec![place(10), place(20), place(30)],
+                RecordingSymbol::default(),
+                Crs::EPSG3857,
+            )
+            .with_sort_key(|place: &Place| if place.id == 10 { 2 } else { 1 });
+
+        let projection = layer
+            .get_projection(&Crs::EPSG3857)
+            .expect("projection exists");
+        let lod = &layer.lods[0];
+        layer.update_bundles(lod, &mut lod.bundles.lock(), &*projection);
+
+        assert_eq!(*layer.symbol.rendered.lock(), vec![20, 30, 10]);
+    }
+
+    #[derive(Default)]
+    struct VertexCountSymbol {
+        counts: Mutex<Vec<usize>>,
+    }
+
+    impl Symbol<Contour<Point2>> for VertexCountSymbol {
+        fn render(
+            &self,
+            _feature: &Contour<Point2>,
+            geometry: &Geom<Point3>,
+            _min_resolution: f64,
+            _bundle: &mut RenderBundle,
+        ) {
+            if let Geom::Contour(contour) = geometry {
+                use galileo_types::Contour as _;
+                self.counts.lock().push(contour.iter_points().count());
+            }
+        }
+    }
+
+    #[test]
+    fn coarse_lod_renders_fewer_vertices() {
+        let zigzag = Contour::open(
+            (0..100)
+                .map(|i| Point2::new(i as f64, (i % 2) as f64 * 0.5))
+                .collect(),
+        );
+        let layer: FeatureLayer<Point2, Contour<Point2>, VertexCountSymbol, CartesianSpace2d> =
+            FeatureLayer::with_lod_levels(
+                vec![zigzag],
+                VertexCountSymbol::default(),
+                Crs::EPSG3857,
+                vec![
+                    FeatureLod::new(1.0),
+                    FeatureLod::new(100.0).with_simplification(1.0),
+                ],
+            );
+        let projection = layer
+            .get_projection(&Crs::EPSG3857)
+            .expect("projection exists");
+
+        for resolution in [1000.0, 2.0] {
+            let lod = layer.select_lod(resolution);
+            layer.update_bundles(lod, &mut lod.bundles.lock(), &*projection);
+        }
+
+        let counts = layer.symbol.counts.lock().clone();
+        assert_eq!(counts.len(), 2);
+        assert!(counts[0] < counts[1]);
+        assert_eq!(counts[1], 100);
+    }
+}
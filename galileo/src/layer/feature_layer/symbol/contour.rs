@@ -4,7 +4,7 @@ use galileo_types::MultiContour;
 
 use crate::layer::feature_layer::symbol::Symbol;
 use crate::render::render_bundle::RenderBundle;
-use crate::render::{LineCap, LinePaint};
+use crate::render::{ArrowPaint, LineCap, LinePaint};
 use crate::Color;
 
 /// Renders a contour as a line of fixed width.
@@ -36,6 +36,8 @@ impl<F> Symbol<F> for SimpleContourSymbol {
             width: self.width,
             offset: 0.0,
             line_cap: LineCap::Butt,
+            dash_pattern: None,
+            dash_offset: 0.0,
         };
 
         match geometry {
@@ -51,3 +53,67 @@ impl<F> Symbol<F> for SimpleContourSymbol {
         }
     }
 }
+
+/// Renders a contour as a line of fixed width with arrows along it showing the direction of the contour.
+///
+/// See [`RenderBundle::add_line_with_arrows`] for details about arrow placement.
+#[derive(Debug, Copy, Clone)]
+pub struct ArrowLineSymbol {
+    /// Color of the line.
+    pub color: Color,
+    /// Width of the line in pixels.
+    pub width: f64,
+    /// Type of the cap of the line.
+    pub line_cap: LineCap,
+    /// Arrows drawn over the line.
+    pub arrows: ArrowPaint,
+}
+
+impl ArrowLineSymbol {
+    /// Creates a new instance with butt line caps.
+    pub fn new(color: Color, width: f64, arrows: ArrowPaint) -> Self {
+        Self {
+            color,
+            width,
+            line_cap: LineCap::Butt,
+            arrows,
+        }
+    }
+
+    /// Sets the type of the cap of the line.
+    pub fn with_line_cap(mut self, line_cap: LineCap) -> Self {
+        self.line_cap = line_cap;
+        self
+    }
+}
+
+impl<F> Symbol<F> for ArrowLineSymbol {
+    fn render(
+        &self,
+        _feature: &F,
+        geometry: &Geom<Point3>,
+        min_resolution: f64,
+        bundle: &mut RenderBundle,
+    ) {
+        let paint = LinePaint {
+            color: self.color,
+            width: self.width,
+            offset: 0.0,
+            line_cap: self.line_cap,
+            dash_pattern: None,
+            dash_offset: 0.0,
+        };
+
+        match geometry {
+            Geom::Contour(contour) => {
+                bundle.add_line_with_arrows(contour, &paint, &self.arrows, min_resolution);
+            }
+            Geom::MultiContour(contours) => {
+                contours.contours().for_each(|contour| {
+                    bundle.add_line_with_arrows(contour, &paint, &self.arrows, min_resolution);
+                });
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use galileo_types::cartesian::{CartesianPoint3d, Point3, Vector3};
+use galileo_types::geometry::Geom;
+use galileo_types::impls::{ClosedContour, Polygon};
+use maybe_sync::{MaybeSend, MaybeSync};
+
+use crate::layer::feature_layer::symbol::Symbol;
+use crate::render::render_bundle::RenderBundle;
+use crate::render::PolygonPaint;
+use crate::Color;
+
+/// Part of the light that illuminates the surfaces not facing the light source.
+const AMBIENT_LIGHT: f64 = 0.5;
+
+/// Renders polygons as prisms extruded up from the polygon plane, e.g. for a 2.5D view of buildings.
+///
+/// The height of every feature is given by a closure, so it can be taken from the feature attributes. The walls of
+/// the prism are shaded according to their orientation relative to the light direction, which makes the shapes
+/// readable when the map is tilted.
+///
+/// The extrusion starts from the `z` coordinate of the polygon, so polygons with elevation are extruded from their
+/// ground level.
+pub struct ExtrudedPolygonSymbol<F> {
+    color: Color,
+    height: Box<dyn Fn(&F) -> f64 + MaybeSend + MaybeSync>,
+    light_direction: Vector3,
+}
+
+impl<F> ExtrudedPolygonSymbol<F> {
+    /// Creates a new symbol with the given color. The `height` closure returns the height of the feature in map
+    /// units. Features with zero or negative height are rendered as flat polygons.
+    pub fn new(color: Color, height: impl Fn(&F) -> f64 + MaybeSend + MaybeSync + 'static) -> Self {
+        Self {
+            color,
+            height: Box::new(height),
+            light_direction: Vector3::new(-1.0, 1.0, 2.0),
+        }
+    }
+
+    /// Sets the direction towards the light source used to shade the walls and the roof.
+    ///
+    /// Defaults to light coming from the north-west and above.
+    pub fn with_light_direction(mut self, light_direction: Vector3) -> Self {
+        self.light_direction = light_direction;
+        self
+    }
+
+    fn render_poly(
+        &self,
+        polygon: &Polygon<Point3>,
+        height: f64,
+        min_resolution: f64,
+        bundle: &mut RenderBundle,
+    ) {
+        let rings = std::iter::once((&polygon.outer_contour, false))
+            .chain(polygon.inner_contours.iter().map(|ring| (ring, true)));
+        for (ring, is_hole) in rings {
+            self.render_walls(ring, is_hole, height, bundle);
+        }
+
+        let raise = |ring: &ClosedContour<Point3>| {
+            ClosedContour::new(
+                ring.points
+                    .iter()
+                    .map(|p| Point3::new(p.x(), p.y(), p.z() + height))
+                    .collect(),
+            )
+        };
+        let roof = Polygon::new(
+            raise(&polygon.outer_contour),
+            polygon.inner_contours.iter().map(raise).collect(),
+        );
+        let roof_color = self.shade(Vector3::new(0.0, 0.0, 1.0));
+        bundle.add_polygon(&roof, &PolygonPaint::new(roof_color), min_resolution);
+    }
+
+    fn render_walls(
+        &self,
+        ring: &ClosedContour<Point3>,
+        is_hole: bool,
+        height: f64,
+        bundle: &mut RenderBundle,
+    ) {
+        let points = &ring.points;
+        if points.len() < 3 {
+            return;
+        }
+
+        // Outward normal of an edge is on the right side of the counterclockwise ring. For holes the walls face
+        // the inside of the ring.
+        let outward = if (signed_area(points) > 0.0) != is_hole {
+            1.0
+        } else {
+            -1.0
+        };
+
+        for i in 0..points.len() {
+            let from = points[i];
+            let to = points[(i + 1) % points.len()];
+            let normal = Vector3::new(
+                (to.y() - from.y()) * outward,
+                (from.x() - to.x()) * outward,
+                0.0,
+            );
+
+            let color = self.shade(normal);
+            let from_top = Point3::new(from.x(), from.y(), from.z() + height);
+            let to_top = Point3::new(to.x(), to.y(), to.z() + height);
+            bundle.add_quad([&from, &to, &to_top, &from_top], color);
+        }
+    }
+
+    fn shade(&self, normal: Vector3) -> Color {
+        let light = self.light_direction;
+        let length = |v: Vector3| (v.dx().powi(2) + v.dy().powi(2) + v.dz().powi(2)).sqrt();
+        let lengths = length(normal) * length(light);
+        let cos = if lengths > 0.0 {
+            (normal.dx() * light.dx() + normal.dy() * light.dy() + normal.dz() * light.dz())
+                / lengths
+        } else {
+            0.0
+        };
+
+        let k = AMBIENT_LIGHT + (1.0 - AMBIENT_LIGHT) * cos.max(0.0);
+        let channel = |c: u8| (c as f64 * k).round() as u8;
+        Color::rgba(
+            channel(self.color.r()),
+            channel(self.color.g()),
+            channel(self.color.b()),
+            self.color.a(),
+        )
+    }
+}
+
+/// Doubled signed area of the ring, positive for counterclockwise rings.
+fn signed_area(points: &[Point3]) -> f64 {
+    (0..points.len())
+        .map(|i| {
+            let (a, b) = (points[i], points[(i + 1) % points.len()]);
+            a.x() * b.y() - b.x() * a.y()
+        })
+        .sum()
+}
+
+impl<F> Symbol<F> for ExtrudedPolygonSymbol<F> {
+    fn render(
+        &self,
+        feature: &F,
+        geometry: &Geom<Point3>,
+        min_resolution: f64,
+        bundle: &mut RenderBundle,
+    ) {
+        let height = (self.height)(feature).max(0.0);
+        match geometry {
+            Geom::Polygon(polygon) => self.render_poly(polygon, height, min_resolution, bundle),
+            Geom::MultiPolygon(polygons) => polygons
+                .parts()
+                .iter()
+                .for_each(|polygon| self.render_poly(polygon, height, min_resolution, bundle)),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn square_is_extruded_into_four_walls_and_roof() {
+        let height = 15.0;
+        let symbol = ExtrudedPolygonSymbol::new(Color::BLUE, |h: &f64| *h);
+        let square = Polygon::from(ClosedContour::new(vec![
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(10.0, 0.0, 0.0),
+            Point3::new(10.0, 10.0, 0.0),
+            Point3::new(0.0, 10.0, 0.0),
+        ]));
+
+        let mut bundle = RenderBundle::default();
+        symbol.render(&height, &Geom::Polygon(square), 1.0, &mut bundle);
+
+        let tessellation = &bundle.world_set.poly_tessellation;
+        // 4 walls of 4 vertices and 2 triangles each, and a roof of 4 vertices and 2 triangles.
+        assert_eq!(tessellation.vertices.len(), 4 * 4 + 4);
+        assert_eq!(tessellation.indices.len(), 4 * 6 + 6);
+
+        let on_ground = tessellation
+            .vertices
+            .iter()
+            .filter(|v| v.position[2] == 0.0)
+            .count();
+        let on_top = tessellation
+            .vertices
+            .iter()
+            .filter(|v| v.position[2] == height as f32)
+            .count();
+        assert_eq!(on_ground, 4 * 2);
+        assert_eq!(on_top, 4 * 2 + 4);
+    }
+
+    #[test]
+    fn walls_facing_light_are_brighter() {
+        let symbol = ExtrudedPolygonSymbol::<()>::new(Color::rgba(200, 200, 200, 255), |_| 1.0)
+            .with_light_direction(Vector3::new(1.0, 0.0, 0.0));
+
+        let lit = symbol.shade(Vector3::new(1.0, 0.0, 0.0));
+        let shadowed = symbol.shade(Vector3::new(-1.0, 0.0, 0.0));
+        assert_eq!(lit, Color::rgba(200, 200, 200, 255));
+        assert_eq!(shadowed, Color::rgba(100, 100, 100, 255));
+    }
+}
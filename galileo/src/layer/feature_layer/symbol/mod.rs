@@ -4,15 +4,24 @@
 
 mod arbitrary;
 mod contour;
+mod extruded;
 mod point;
 mod polygon;
+mod sprite;
+#[cfg(feature = "svg")]
+mod svg_icon;
 
 pub use arbitrary::ArbitraryGeometrySymbol;
-pub use contour::SimpleContourSymbol;
+pub use contour::{ArrowLineSymbol, SimpleContourSymbol};
+pub use extruded::ExtrudedPolygonSymbol;
 use galileo_types::cartesian::Point3;
 use galileo_types::geometry::Geom;
-pub use point::{CirclePointSymbol, ImagePointSymbol, TextMarkerSymbol, TextProvider};
+pub use point::{
+    CategoricalPointSymbol, CirclePointSymbol, GraduatedCircleSymbol, GraduatedScale,
+    ImagePointSymbol, RotatedImagePointSymbol, TextMarkerSymbol, TextProvider,
+};
 pub use polygon::SimplePolygonSymbol;
+pub use sprite::SpriteSheetSymbol;
 
 use crate::render::render_bundle::RenderBundle;
 
@@ -28,6 +37,10 @@ pub trait Symbol<F> {
     /// The `min_resolution` argument specifies the minimum map resolution that the returned primitives will be
     /// rendered with. This can be use to choose tolerances or pick entirely different rendering strategy. For example,
     /// a building may be rendered as a polygon at high resolution or as a point at low resolutions.
+    ///
+    /// The `z` coordinate of the `geometry` points is preserved by the layer and the render bundle. For features with
+    /// 3d geometries it contains the elevation of the point, so a symbol can use it for styling, e.g. to choose the
+    /// color of a contour line by its height. For 2d geometries it is always `0`.
     fn render(
         &self,
         feature: &F,
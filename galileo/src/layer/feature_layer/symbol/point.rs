@@ -1,18 +1,24 @@
-#[cfg(not(target_arch = "wasm32"))]
-use std::ops::Deref;
+use std::collections::HashMap;
+use std::hash::Hash;
 use std::sync::Arc;
 
-use galileo_types::cartesian::{Point3, Vector2};
+use galileo_types::cartesian::{Point2, Point3, Size, Vector2};
 use galileo_types::geometry::Geom;
+use galileo_types::impls::ClosedContour;
 use galileo_types::MultiPoint;
-use image::EncodableLayout;
+use maybe_sync::{MaybeSend, MaybeSync};
 
 use crate::decoded_image::DecodedImage;
 use crate::error::GalileoError;
+#[cfg(feature = "svg")]
+use crate::layer::feature_layer::symbol::svg_icon::SvgIcon;
 use crate::layer::feature_layer::symbol::Symbol;
+use crate::layer::feature_layer::FeatureProperties;
 use crate::render::point_paint::{MarkerStyle, PointPaint};
 use crate::render::render_bundle::RenderBundle;
-use crate::render::text::TextStyle;
+use crate::render::text::{
+    break_lines, HorizontalAlignment, LabelBackground, TextService, TextStyle, VerticalAlignment,
+};
 use crate::Color;
 
 /// Renders a point as a circle of fixes size.
@@ -54,68 +60,357 @@ impl<F> Symbol<F> for CirclePointSymbol {
     }
 }
 
+/// The way [`GraduatedCircleSymbol`] maps feature values to circle sizes.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum GraduatedScale {
+    /// Radius of the circle is proportional to the value.
+    #[default]
+    Linear,
+    /// Area of the circle is proportional to the value. Readers compare circles by their area, so this gives
+    /// a more truthful picture of the values than the linear scale.
+    Sqrt,
+}
+
+/// Renders a point as a circle with the radius depending on a value of the feature (proportional symbol).
+///
+/// The value is mapped onto the range of radii. Values outside of the value range are clamped to it.
+pub struct GraduatedCircleSymbol<F> {
+    color: Color,
+    value: Box<dyn Fn(&F) -> f64 + MaybeSend + MaybeSync>,
+    min_value: f64,
+    max_value: f64,
+    min_radius: f64,
+    max_radius: f64,
+    scale: GraduatedScale,
+}
+
+impl<F> GraduatedCircleSymbol<F> {
+    /// Creates a new symbol with linear scale.
+    ///
+    /// Features with the value of `min_value` or less are drawn with the `min_radius`, and features with the
+    /// value of `max_value` or more with the `max_radius`. Radius is set in pixels.
+    pub fn new(
+        color: Color,
+        value: impl Fn(&F) -> f64 + MaybeSend + MaybeSync + 'static,
+        min_value: f64,
+        max_value: f64,
+        min_radius: f64,
+        max_radius: f64,
+    ) -> Self {
+        Self {
+            color,
+            value: Box::new(value),
+            min_value,
+            max_value,
+            min_radius,
+            max_radius,
+            scale: GraduatedScale::Linear,
+        }
+    }
+
+    /// Creates a new symbol with linear scale, reading the value from the feature property with the
+    /// given name.
+    ///
+    /// Features without a numeric value of the property are not drawn.
+    pub fn from_property(
+        color: Color,
+        property: impl Into<String>,
+        min_value: f64,
+        max_value: f64,
+        min_radius: f64,
+        max_radius: f64,
+    ) -> Self
+    where
+        F: FeatureProperties + 'static,
+    {
+        let property = property.into();
+        Self::new(
+            color,
+            move |feature: &F| feature.property_f64(&property).unwrap_or(f64::NAN),
+            min_value,
+            max_value,
+            min_radius,
+            max_radius,
+        )
+    }
+
+    /// Sets the way the values are mapped to the circle sizes.
+    pub fn with_scale(mut self, scale: GraduatedScale) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Radius of the circle for the given value. Returns `None` if the value is not a finite number.
+    fn radius(&self, value: f64) -> Option<f64> {
+        if !value.is_finite() {
+            return None;
+        }
+
+        let value_range = self.max_value - self.min_value;
+        let t = if value_range > 0.0 {
+            ((value - self.min_value) / value_range).clamp(0.0, 1.0)
+        } else if value < self.min_value {
+            0.0
+        } else {
+            1.0
+        };
+
+        let radius = match self.scale {
+            GraduatedScale::Linear => self.min_radius + t * (self.max_radius - self.min_radius),
+            GraduatedScale::Sqrt => {
+                let min_area = self.min_radius * self.min_radius;
+                let max_area = self.max_radius * self.max_radius;
+                (min_area + t * (max_area - min_area)).sqrt()
+            }
+        };
+
+        Some(radius)
+    }
+}
+
+impl<F> Symbol<F> for GraduatedCircleSymbol<F> {
+    fn render(
+        &self,
+        feature: &F,
+        geometry: &Geom<Point3>,
+        min_resolution: f64,
+        bundle: &mut RenderBundle,
+    ) {
+        let Some(radius) = self.radius((self.value)(feature)) else {
+            return;
+        };
+
+        let paint = PointPaint::circle(self.color, (radius * 2.0) as f32);
+        match geometry {
+            Geom::Point(point) => {
+                bundle.add_point(point, &paint, min_resolution);
+            }
+            Geom::MultiPoint(points) => {
+                points.iter_points().for_each(|p| {
+                    bundle.add_point(&p, &paint, min_resolution);
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Renders a point as a circle of fixed size with the color selected by a category of the feature.
+pub struct CategoricalPointSymbol<F, K> {
+    category: Box<dyn Fn(&F) -> K + MaybeSend + MaybeSync>,
+    colors: HashMap<K, Color>,
+    default_color: Color,
+    size: f64,
+}
+
+impl<F, K: Eq + Hash> CategoricalPointSymbol<F, K> {
+    /// Creates a new symbol.
+    ///
+    /// * `category` - returns the category of a feature.
+    /// * `colors` - colors of the categories.
+    /// * `default_color` - color of the features with categories not present in `colors`.
+    /// * `size` - diameter of the circles in pixels.
+    pub fn new(
+        category: impl Fn(&F) -> K + MaybeSend + MaybeSync + 'static,
+        colors: HashMap<K, Color>,
+        default_color: Color,
+        size: f64,
+    ) -> Self {
+        Self {
+            category: Box::new(category),
+            colors,
+            default_color,
+            size,
+        }
+    }
+
+    /// Color of the circle for the feature.
+    fn color(&self, feature: &F) -> Color {
+        self.colors
+            .get(&(self.category)(feature))
+            .copied()
+            .unwrap_or(self.default_color)
+    }
+}
+
+impl<F: FeatureProperties + 'static> CategoricalPointSymbol<F, String> {
+    /// Creates a new symbol with the category read from the string feature property with the given
+    /// name.
+    ///
+    /// Features without a string value of the property are drawn with the `default_color`.
+    pub fn from_property(
+        property: impl Into<String>,
+        colors: HashMap<String, Color>,
+        default_color: Color,
+        size: f64,
+    ) -> Self {
+        let property = property.into();
+        Self::new(
+            move |feature: &F| {
+                feature
+                    .property_str(&property)
+                    .map(str::to_string)
+                    .unwrap_or_default()
+            },
+            colors,
+            default_color,
+            size,
+        )
+    }
+}
+
+impl<F, K: Eq + Hash> Symbol<F> for CategoricalPointSymbol<F, K> {
+    fn render(
+        &self,
+        feature: &F,
+        geometry: &Geom<Point3>,
+        min_resolution: f64,
+        bundle: &mut RenderBundle,
+    ) {
+        CirclePointSymbol::new(self.color(feature), self.size).render(
+            feature,
+            geometry,
+            min_resolution,
+            bundle,
+        );
+    }
+}
+
 /// Symbol that renders a point with an image. The image size is fixed on the screen and does not depend on map
 /// resolution.
 pub struct ImagePointSymbol {
     image: Arc<DecodedImage>,
+    levels: Vec<Arc<DecodedImage>>,
     offset: Vector2<f32>,
     scale: f32,
+    rotation: f32,
+    #[cfg(feature = "svg")]
+    svg: Option<SvgIcon>,
 }
 
 impl ImagePointSymbol {
     /// Loads the image from the file system path.
-    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg(all(feature = "image", not(target_arch = "wasm32")))]
     pub fn from_path(path: &str, offset: Vector2<f32>, scale: f32) -> Result<Self, GalileoError> {
-        use galileo_types::cartesian::Size;
+        Self::from_image(DecodedImage::from_path(path)?, offset, scale)
+    }
+
+    /// Decodes the image from the raw bytes.
+    #[cfg(feature = "image")]
+    pub fn from_bytes(data: &[u8], offset: Vector2<f32>, scale: f32) -> Result<Self, GalileoError> {
+        Self::from_image(DecodedImage::from_bytes(data)?, offset, scale)
+    }
 
-        let image = image::io::Reader::open(path)?
-            .decode()
-            .map_err(|_| GalileoError::ImageDecode)?;
+    /// Creates a symbol from the decoded image. If the image is scaled down, downscaled levels of the
+    /// image are generated, so that small icons are rendered without aliasing.
+    #[cfg(feature = "image")]
+    fn from_image(
+        image: DecodedImage,
+        offset: Vector2<f32>,
+        scale: f32,
+    ) -> Result<Self, GalileoError> {
+        let min_width = ((image.width() as f32 * scale).ceil() as u32).max(1);
+        let mut levels = vec![];
+        let mut size = image.size();
+        while size.width() / 2 >= min_width && size.height() > 1 {
+            size = Size::new(size.width() / 2, size.height() / 2);
+            levels.push(Arc::new(image.resized(size)?));
+        }
 
         Ok(Self {
-            image: Arc::new(DecodedImage::from_raw(
-                Vec::from(image.to_rgba8().deref()),
-                Size::new(image.width(), image.height()),
-            )?),
+            image: Arc::new(image),
+            levels,
             offset,
             scale,
+            rotation: 0.0,
+            #[cfg(feature = "svg")]
+            svg: None,
         })
     }
+}
 
-    /// Decodes the image from the raw bytes.
-    pub fn from_bytes(data: &[u8], offset: Vector2<f32>, scale: f32) -> Result<Self, GalileoError> {
-        use galileo_types::cartesian::Size;
-
-        let image = image::load_from_memory(data)
-            .map_err(|_| GalileoError::ImageDecode)?
-            .to_rgba8();
+impl ImagePointSymbol {
+    /// Parses an SVG image from the raw bytes.
+    ///
+    /// The image is rasterized when rendered at the size given by the SVG document multiplied by
+    /// the `scale` and the display scale factor of the render bundle, so it stays crisp on high
+    /// DPI screens. Rasterized bitmaps are cached by their size.
+    #[cfg(feature = "svg")]
+    pub fn from_svg_bytes(
+        data: &[u8],
+        offset: Vector2<f32>,
+        scale: f32,
+    ) -> Result<Self, GalileoError> {
+        let svg = SvgIcon::parse(data)?;
+        let image = svg.rasterize(svg_pixel_size(svg.size(), 1.0))?;
 
         Ok(Self {
-            image: Arc::new(DecodedImage::from_raw(
-                Vec::from(image.as_bytes()),
-                Size::new(image.width(), image.height()),
-            )?),
+            image,
+            levels: vec![],
             offset,
             scale,
+            rotation: 0.0,
+            svg: Some(svg),
         })
     }
-}
 
-impl<F> Symbol<F> for ImagePointSymbol {
-    fn render(
-        &self,
-        _feature: &F,
-        geometry: &Geom<Point3>,
-        _min_resolution: f64,
-        bundle: &mut RenderBundle,
-    ) {
+    /// Sets the rotation of the image around its anchor point in radians. Positive values rotate
+    /// the image clockwise on the screen.
+    pub fn with_rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Rotates the image of every feature by the angle returned by the `rotation` closure, e.g. to
+    /// show the heading of a vehicle. The angle is given in radians clockwise and is added to the
+    /// rotation of the symbol.
+    pub fn with_feature_rotation<F>(
+        self,
+        rotation: impl Fn(&F) -> f32 + MaybeSend + MaybeSync + 'static,
+    ) -> RotatedImagePointSymbol<F> {
+        RotatedImagePointSymbol {
+            symbol: self,
+            rotation: Box::new(rotation),
+        }
+    }
+
+    /// Image of the marker and its size on the screen in pixels.
+    ///
+    /// Bitmap images are taken from the smallest downscaled level that is still at least as large
+    /// as the marker in physical pixels.
+    fn marker_image(&self, scale_factor: f32) -> (Arc<DecodedImage>, Size<u32>) {
+        #[cfg(feature = "svg")]
+        if let Some(svg) = &self.svg {
+            let size = svg_pixel_size(svg.size(), self.scale * scale_factor);
+            match svg.rasterize(size) {
+                Ok(image) => return (image, size),
+                Err(err) => log::warn!("Failed to rasterize SVG image: {err}"),
+            }
+        }
+
+        let size: Size<u32> = (self.image.size().cast::<f32>() * self.scale).cast();
+        let target_width = (size.width() as f32 * scale_factor).ceil() as u32;
+        let image = self
+            .levels
+            .iter()
+            .rev()
+            .find(|level| level.width() >= target_width)
+            .unwrap_or(&self.image);
+
+        (image.clone(), size)
+    }
+
+    fn render_rotated(&self, geometry: &Geom<Point3>, rotation: f32, bundle: &mut RenderBundle) {
+        let (image, size) = self.marker_image(bundle.scale_factor());
         let add_marker = |point: &Point3, bundle: &mut RenderBundle| {
             bundle.add_marker(
                 point,
                 &MarkerStyle::Image {
-                    image: self.image.clone(),
+                    image: image.clone(),
                     anchor: self.offset,
-                    size: Some((self.image.size().cast::<f32>() * self.scale).cast()),
+                    size: Some(size),
+                    source_rect: None,
+                    rotation,
                 },
             );
         };
@@ -130,7 +425,48 @@ impl<F> Symbol<F> for ImagePointSymbol {
     }
 }
 
-/// Symbol that renders text with a black rectangular background and white text.
+impl<F> Symbol<F> for ImagePointSymbol {
+    fn render(
+        &self,
+        _feature: &F,
+        geometry: &Geom<Point3>,
+        _min_resolution: f64,
+        bundle: &mut RenderBundle,
+    ) {
+        self.render_rotated(geometry, self.rotation, bundle);
+    }
+}
+
+/// Pixel size of an SVG image with the given document size rendered with the `scale`.
+#[cfg(feature = "svg")]
+fn svg_pixel_size(svg_size: Size<f32>, scale: f32) -> Size<u32> {
+    Size::new(
+        (svg_size.width() * scale).ceil() as u32,
+        (svg_size.height() * scale).ceil() as u32,
+    )
+}
+
+/// [`ImagePointSymbol`] with the image rotated by an angle taken from the feature. Created with
+/// [`ImagePointSymbol::with_feature_rotation`].
+pub struct RotatedImagePointSymbol<F> {
+    symbol: ImagePointSymbol,
+    rotation: Box<dyn Fn(&F) -> f32 + MaybeSend + MaybeSync>,
+}
+
+impl<F> Symbol<F> for RotatedImagePointSymbol<F> {
+    fn render(
+        &self,
+        feature: &F,
+        geometry: &Geom<Point3>,
+        _min_resolution: f64,
+        bundle: &mut RenderBundle,
+    ) {
+        let rotation = self.symbol.rotation + (self.rotation)(feature);
+        self.symbol.render_rotated(geometry, rotation, bundle);
+    }
+}
+
+/// Symbol that renders text with a background shape behind it.
 /// The background size is calculated automatically to fit the text.
 #[derive(Debug, Clone)]
 pub struct TextMarkerSymbol {
@@ -138,31 +474,37 @@ pub struct TextMarkerSymbol {
     pub text_style: TextStyle,
     /// Padding around the text in pixels.
     pub padding: f32,
+    /// Background drawn behind the text. The padding of the background is replaced with the
+    /// [`padding`](Self::padding) of the symbol.
+    pub background: LabelBackground,
+    /// If set, markers that overlap markers with higher priority are hidden. See
+    /// [`TextProvider::label_priority`].
+    pub declutter: bool,
 }
 
 /// Trait for features that can provide text for TextMarkerSymbol
 pub trait TextProvider {
     /// Returns the text to display in the marker
     fn get_text(&self) -> &str;
+
+    /// Priority of the marker when markers overlap on the screen. If decluttering is enabled in the
+    /// [`TextMarkerSymbol`], markers with higher priority are kept and the rest are hidden.
+    fn label_priority(&self) -> f32 {
+        0.0
+    }
 }
 
 impl TextMarkerSymbol {
     /// Create a new text marker with default styling.
     pub fn new() -> Self {
-        Self {
-            text_style: TextStyle {
-                font_family: vec!["DejaVu Sans".to_string()],
-                font_size: 12.0,
-                font_color: Color::WHITE,
-                horizontal_alignment: crate::render::text::HorizontalAlignment::Center,
-                vertical_alignment: crate::render::text::VerticalAlignment::Middle,
-                weight: crate::render::text::FontWeight::NORMAL,
-                style: crate::render::text::FontStyle::Normal,
-                outline_width: 0.0,
-                outline_color: Color::TRANSPARENT,
-            },
-            padding: 4.0,
-        }
+        Self::with_style(TextStyle {
+            font_family: vec!["DejaVu Sans".to_string()],
+            font_size: 12.0,
+            font_color: Color::WHITE,
+            horizontal_alignment: HorizontalAlignment::Center,
+            vertical_alignment: VerticalAlignment::Middle,
+            ..Default::default()
+        })
     }
 
     /// Create a new text marker with custom text style.
@@ -170,6 +512,8 @@ impl TextMarkerSymbol {
         Self {
             text_style,
             padding: 4.0,
+            background: LabelBackground::new(Color::BLACK),
+            declutter: false,
         }
     }
 
@@ -178,6 +522,120 @@ impl TextMarkerSymbol {
         self.padding = padding;
         self
     }
+
+    /// Set the background drawn behind the text.
+    pub fn with_background(mut self, background: LabelBackground) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// Set the fill color of the background.
+    pub fn with_background_color(mut self, color: Color) -> Self {
+        self.background.fill = color;
+        self
+    }
+
+    /// Set the outline of the background. No outline is drawn if the `width` is `0`.
+    pub fn with_border(mut self, color: Color, width: f32) -> Self {
+        self.background = self.background.with_border(color, width);
+        self
+    }
+
+    /// Set the color of the text.
+    pub fn with_text_color(mut self, color: Color) -> Self {
+        self.text_style.font_color = color;
+        self
+    }
+
+    /// Hide the markers that overlap markers with higher priority.
+    ///
+    /// Decluttered markers are drawn in screen space, and the overlapping markers are resolved on every frame.
+    pub fn with_declutter(mut self, declutter: bool) -> Self {
+        self.declutter = declutter;
+        self
+    }
+
+    /// Round the corners of the background with the given radius in pixels. Use `f32::INFINITY`
+    /// for a pill shape.
+    pub fn with_corner_radius(mut self, corner_radius: f32) -> Self {
+        self.background.corner_radius = corner_radius;
+        self
+    }
+
+    /// Size of the given text in pixels as `(width, height)`.
+    ///
+    /// The text is measured with the fonts of the [`TextService`](crate::render::text::text_service::TextService).
+    /// If the service is not initialized or the font is not available, the size is estimated from the font size and
+    /// the number of characters.
+    pub fn measure_text(&self, text: &str) -> (f32, f32) {
+        match TextService::measure(text, &self.text_style, 1.0) {
+            Ok(size) => (size.width(), size.height()),
+            Err(err) => {
+                log::debug!("Failed to measure text, using estimated size: {err}");
+                let font_size = self.text_style.font_size_px(1.0);
+                let letter_spacing = self.text_style.letter_spacing;
+                let estimate_width = |line: &str| {
+                    let glyphs_width = line
+                        .chars()
+                        .map(|c| {
+                            if is_wide_char(c) {
+                                font_size
+                            } else {
+                                font_size * 0.6
+                            }
+                        })
+                        .sum::<f32>();
+                    let gaps = line.chars().count().saturating_sub(1);
+                    glyphs_width + gaps as f32 * letter_spacing
+                };
+                let lines = break_lines(text, self.text_style.max_width, estimate_width);
+                let width = lines
+                    .iter()
+                    .map(|line| estimate_width(line))
+                    .fold(0.0, f32::max);
+                let line_step = font_size * self.text_style.line_height;
+                let height = font_size + lines.len().saturating_sub(1) as f32 * line_step;
+                (width, height)
+            }
+        }
+    }
+
+    /// Contour of the background for the given text in pixels relative to the anchor point.
+    pub fn background_contour(&self, text: &str) -> ClosedContour<Point2<f32>> {
+        let (width, height) = self.measure_text(text);
+        self.label_background().contour(
+            Size::new(width, height),
+            &self.text_style,
+            Vector2::default(),
+        )
+    }
+
+    /// Background of the labels with the padding of the symbol.
+    fn label_background(&self) -> LabelBackground {
+        self.background.with_padding(self.padding)
+    }
+}
+
+/// Returns true for the characters that usually take the full em width, like CJK ideographs and
+/// kana, Hangul syllables and full-width forms.
+fn is_wide_char(c: char) -> bool {
+    matches!(
+        c,
+        '\u{1100}'..='\u{115F}'
+            | '\u{2E80}'..='\u{A4CF}'
+            | '\u{AC00}'..='\u{D7A3}'
+            | '\u{F900}'..='\u{FAFF}'
+            | '\u{FE30}'..='\u{FE4F}'
+            | '\u{FF00}'..='\u{FF60}'
+            | '\u{FFE0}'..='\u{FFE6}'
+            | '\u{20000}'..='\u{3FFFD}'
+    )
+}
+
+impl Default for TextMarkerSymbol {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<F: TextProvider> Symbol<F> for TextMarkerSymbol {
@@ -185,27 +643,32 @@ impl<F: TextProvider> Symbol<F> for TextMarkerSymbol {
         &self,
         feature: &F,
         geometry: &Geom<Point3>,
-        min_resolution: f64,
+        _min_resolution: f64,
         bundle: &mut RenderBundle,
     ) {
         let text = feature.get_text();
+        let background = self.label_background();
+
         let render_text_marker = |point: &Point3, bundle: &mut RenderBundle| {
-            // First render a black rectangle as background
-            // We'll estimate the text size based on font size and character count
-            let estimated_char_width = self.text_style.font_size * 0.6;
-            let text_width = estimated_char_width * text.len() as f32;
-            let text_height = self.text_style.font_size;
-            
-            let bg_width = text_width + (self.padding * 2.0);
-            let bg_height = text_height + (self.padding * 2.0);
-            
-            // Render black rectangle background
-            let bg_paint = PointPaint::square(Color::BLACK, bg_width.max(bg_height));
-            bundle.add_point(point, &bg_paint, min_resolution);
-            
-            // Render white text on top
-            let text_paint = PointPaint::label(text, &self.text_style);
-            bundle.add_point(point, &text_paint, min_resolution);
+            if self.declutter {
+                bundle.add_label_with_priority(
+                    point,
+                    text,
+                    &self.text_style,
+                    Vector2::default(),
+                    Some(&background),
+                    feature.label_priority(),
+                );
+            } else {
+                bundle.add_label_with_background(
+                    point,
+                    text,
+                    &self.text_style,
+                    Vector2::default(),
+                    &background,
+                    true,
+                );
+            }
         };
 
         match geometry {
@@ -220,7 +683,10 @@ impl<F: TextProvider> Symbol<F> for TextMarkerSymbol {
 
 #[cfg(test)]
 mod tests {
+    use galileo_types::cartesian::CartesianPoint2d;
+
     use super::*;
+    use crate::layer::feature_layer::PropertyFeature;
 
     #[test]
     fn image_symbol_from_file() {
@@ -234,4 +700,214 @@ mod tests {
         assert_eq!(symbol.image.height(), 99);
         assert_eq!(symbol.image.byte_size(), 62 * 99 * 4);
     }
+
+    #[test]
+    fn downscaled_image_symbol_uses_mip_level() {
+        let image =
+            DecodedImage::from_raw(vec![255; 4 * 64 * 32], Size::new(64, 32)).expect("valid image");
+        let symbol = ImagePointSymbol::from_image(image, Vector2::new(0.5, 0.5), 0.2).unwrap();
+        let level_sizes: Vec<_> = symbol.levels.iter().map(|level| level.size()).collect();
+        assert_eq!(level_sizes, vec![Size::new(32, 16), Size::new(16, 8)]);
+
+        let (image, size) = symbol.marker_image(1.0);
+        assert_eq!(size, Size::new(12, 6));
+        assert_eq!(image.size(), Size::new(16, 8));
+
+        let (image, _) = symbol.marker_image(2.0);
+        assert_eq!(image.size(), Size::new(32, 16));
+    }
+
+    fn graduated(scale: GraduatedScale) -> GraduatedCircleSymbol<f64> {
+        GraduatedCircleSymbol::new(Color::RED, |v: &f64| *v, 0.0, 100.0, 2.0, 10.0)
+            .with_scale(scale)
+    }
+
+    #[test]
+    fn graduated_radius_is_clamped_to_value_range() {
+        let symbol = graduated(GraduatedScale::Linear);
+        assert_eq!(symbol.radius(0.0), Some(2.0));
+        assert_eq!(symbol.radius(50.0), Some(6.0));
+        assert_eq!(symbol.radius(100.0), Some(10.0));
+        assert_eq!(symbol.radius(-10.0), Some(2.0));
+        assert_eq!(symbol.radius(1000.0), Some(10.0));
+        assert_eq!(symbol.radius(f64::NAN), None);
+    }
+
+    #[test]
+    fn sqrt_scale_interpolates_area() {
+        let symbol = graduated(GraduatedScale::Sqrt);
+        assert_eq!(symbol.radius(0.0), Some(2.0));
+        assert_eq!(symbol.radius(100.0), Some(10.0));
+
+        let radius = symbol.radius(50.0).expect("finite value");
+        assert!((radius * radius - 52.0).abs() < 1e-9, "radius: {radius}");
+    }
+
+    #[test]
+    fn categorical_symbol_falls_back_to_default_color() {
+        let colors = HashMap::from([("forest", Color::GREEN), ("water", Color::BLUE)]);
+        let symbol = CategoricalPointSymbol::new(|v: &&str| *v, colors, Color::BLACK, 4.0);
+
+        assert_eq!(symbol.color(&"forest"), Color::GREEN);
+        assert_eq!(symbol.color(&"water"), Color::BLUE);
+        assert_eq!(symbol.color(&"urban"), Color::BLACK);
+    }
+
+    #[test]
+    fn property_symbols_read_values_by_name() {
+        let feature = PropertyFeature::new(Point2::new(0.0, 0.0))
+            .with_property("class", "water")
+            .with_property("size", 50.0);
+        let unset = PropertyFeature::new(Point2::new(0.0, 0.0));
+
+        let colors = HashMap::from([("water".to_string(), Color::BLUE)]);
+        let symbol = CategoricalPointSymbol::from_property("class", colors, Color::BLACK, 4.0);
+        assert_eq!(symbol.color(&feature), Color::BLUE);
+        assert_eq!(symbol.color(&unset), Color::BLACK);
+
+        let symbol =
+            GraduatedCircleSymbol::from_property(Color::RED, "size", 0.0, 100.0, 2.0, 10.0);
+        assert_eq!(symbol.radius((symbol.value)(&feature)), Some(6.0));
+        assert_eq!(symbol.radius((symbol.value)(&unset)), None);
+    }
+
+    #[test]
+    fn feature_rotation_is_added_to_symbol_rotation() {
+        let symbol = ImagePointSymbol::from_path(
+            "examples/data/pin-yellow.png",
+            Vector2::new(0.5, 0.5),
+            1.0,
+        )
+        .expect("image is loaded")
+        .with_rotation(std::f32::consts::FRAC_PI_4)
+        .with_feature_rotation(|heading: &f32| *heading);
+
+        let mut bundle = RenderBundle::default();
+        symbol.render(
+            &std::f32::consts::FRAC_PI_4,
+            &Geom::Point(Point3::new(0.0, 0.0, 0.0)),
+            1.0,
+            &mut bundle,
+        );
+
+        // Rotated by 90 degrees in total, so the width and height of the image are swapped.
+        let bbox = bundle.screen_sets[0].bbox;
+        assert!(
+            (bbox.width() - 99.0).abs() < 1e-3,
+            "width: {}",
+            bbox.width()
+        );
+        assert!(
+            (bbox.height() - 62.0).abs() < 1e-3,
+            "height: {}",
+            bbox.height()
+        );
+    }
+
+    fn contour_bbox(contour: &ClosedContour<Point2<f32>>) -> (f32, f32, f32, f32) {
+        contour.points.iter().fold(
+            (f32::MAX, f32::MAX, f32::MIN, f32::MIN),
+            |(x_min, y_min, x_max, y_max), p| {
+                (
+                    x_min.min(p.x()),
+                    y_min.min(p.y()),
+                    x_max.max(p.x()),
+                    y_max.max(p.y()),
+                )
+            },
+        )
+    }
+
+    #[test]
+    fn pill_background_has_rounded_corners() {
+        let rect = TextMarkerSymbol::new();
+        let pill = TextMarkerSymbol::new().with_corner_radius(f32::INFINITY);
+
+        let rect_contour = rect.background_contour("Label");
+        let pill_contour = pill.background_contour("Label");
+
+        assert_eq!(rect_contour.points.len(), 4);
+        assert!(pill_contour.points.len() > rect_contour.points.len());
+    }
+
+    #[test]
+    fn cjk_text_is_measured_by_characters() {
+        let symbol = TextMarkerSymbol::new();
+        let font_size = symbol.text_style.font_size;
+        let (width, height) = symbol.measure_text("東京");
+
+        // Two full-width glyphs, not six bytes of UTF-8.
+        assert!(width >= font_size * 1.5 && width <= font_size * 2.5);
+        assert!(height >= font_size * 0.5 && height <= font_size * 2.0);
+        assert!(width < symbol.measure_text("東京東京").0);
+    }
+
+    #[test]
+    fn wrapped_text_is_measured_by_lines() {
+        let mut style = TextMarkerSymbol::new().text_style;
+        style.max_width = Some(50.0);
+        style.line_height = 1.5;
+        let symbol = TextMarkerSymbol::with_style(style);
+        let font_size = symbol.text_style.font_size;
+
+        let (single_width, single_height) = symbol.measure_text("abc");
+        let (width, height) = symbol.measure_text("abc abc abc");
+        assert_eq!(width, single_width);
+        assert_eq!(height, single_height + font_size * 1.5 * 2.0);
+
+        let (_, height) = symbol.measure_text("a\nb");
+        assert_eq!(height, single_height + font_size * 1.5);
+    }
+
+    #[test]
+    fn letter_spacing_is_added_between_letters() {
+        let mut style = TextMarkerSymbol::new().text_style;
+        let (width, _) = TextMarkerSymbol::with_style(style.clone()).measure_text("abc");
+
+        style.letter_spacing = 2.0;
+        let (spaced, _) = TextMarkerSymbol::with_style(style.clone()).measure_text("abc");
+        assert_eq!(spaced, width + 4.0);
+
+        style.letter_spacing = -1.0;
+        let (tight, _) = TextMarkerSymbol::with_style(style).measure_text("abc");
+        assert_eq!(tight, width - 2.0);
+    }
+
+    #[test]
+    fn background_uses_configured_colors() {
+        let default = TextMarkerSymbol::new();
+        let default_background = default.label_background();
+        assert_eq!(default_background.fill, Color::BLACK);
+        assert_eq!(default.text_style.font_color, Color::WHITE);
+        assert_eq!(default_background.border_width, 0.0);
+
+        let warning = TextMarkerSymbol::new()
+            .with_corner_radius(f32::INFINITY)
+            .with_background_color(Color::RED)
+            .with_text_color(Color::BLACK)
+            .with_border(Color::WHITE, 1.5);
+        let background = warning.label_background();
+        assert_eq!(background.fill, Color::RED);
+        assert_eq!(background.border_color, Color::WHITE);
+        assert_eq!(background.border_width, 1.5);
+        assert_eq!(background.corner_radius, f32::INFINITY);
+        assert_eq!(background.padding, warning.padding);
+        assert_eq!(warning.text_style.font_color, Color::BLACK);
+    }
+
+    #[test]
+    fn pill_background_fits_text() {
+        let symbol = TextMarkerSymbol::new()
+            .with_padding(3.0)
+            .with_corner_radius(f32::INFINITY);
+        let (text_width, text_height) = symbol.measure_text("Label");
+
+        let (x_min, y_min, x_max, y_max) = contour_bbox(&symbol.background_contour("Label"));
+
+        assert!((x_max - x_min - (text_width + 6.0)).abs() < 1e-3);
+        assert!((y_max - y_min - (text_height + 6.0)).abs() < 1e-3);
+        // Text is centered on the anchor point by default.
+        assert!((x_min + x_max).abs() < 1e-3);
+        assert!((y_min + y_max).abs() < 1e-3);
+    }
 }
@@ -4,7 +4,7 @@ use galileo_types::{MultiPolygon, Polygon};
 
 use crate::layer::feature_layer::symbol::Symbol;
 use crate::render::render_bundle::RenderBundle;
-use crate::render::{LineCap, LinePaint, PolygonPaint};
+use crate::render::{FillRule, LineCap, LinePaint, PolygonPaint};
 use crate::Color;
 
 /// Renders a polygon geometry as a filled polygon with an outline.
@@ -19,6 +19,8 @@ pub struct SimplePolygonSymbol {
     /// Offset of the outline in pixels. Positive offset will move outline outside of the polygon, negative offset
     /// will move the outline inside the polygon.
     pub stroke_offset: f64,
+    /// Rule that decides which rings of the polygon are filled and which are holes.
+    pub fill_rule: FillRule,
 }
 
 impl SimplePolygonSymbol {
@@ -29,6 +31,7 @@ impl SimplePolygonSymbol {
             stroke_color: Default::default(),
             stroke_width: 0.0,
             stroke_offset: 0.0,
+            fill_rule: FillRule::default(),
         }
     }
 
@@ -56,28 +59,30 @@ impl SimplePolygonSymbol {
         }
     }
 
-    fn render_poly(
+    /// Creates a new instance from a copy of the current, but with the given fill rule.
+    pub fn with_fill_rule(&self, fill_rule: FillRule) -> Self {
+        Self { fill_rule, ..*self }
+    }
+
+    fn fill_paint(&self) -> Option<PolygonPaint> {
+        (!self.fill_color.is_transparent())
+            .then(|| PolygonPaint::new(self.fill_color).with_fill_rule(self.fill_rule))
+    }
+
+    fn render_stroke(
         &self,
         polygon: &galileo_types::impls::Polygon<Point3>,
         min_resolution: f64,
         bundle: &mut RenderBundle,
     ) {
-        if !self.fill_color.is_transparent() {
-            bundle.add_polygon(
-                polygon,
-                &PolygonPaint {
-                    color: self.fill_color,
-                },
-                min_resolution,
-            );
-        }
-
         if !self.stroke_color.is_transparent() && self.stroke_width > 0.0 {
             let line_paint = LinePaint {
                 color: self.stroke_color,
                 width: self.stroke_width,
                 offset: self.stroke_offset,
                 line_cap: LineCap::Butt,
+                dash_pattern: None,
+                dash_offset: 0.0,
             };
 
             for contour in polygon.iter_contours() {
@@ -96,10 +101,20 @@ impl<F> Symbol<F> for SimplePolygonSymbol {
         bundle: &mut RenderBundle,
     ) {
         match geometry {
-            Geom::Polygon(poly) => self.render_poly(poly, min_resolution, bundle),
-            Geom::MultiPolygon(polygons) => polygons
-                .polygons()
-                .for_each(|polygon| self.render_poly(polygon, min_resolution, bundle)),
+            Geom::Polygon(poly) => {
+                if let Some(paint) = self.fill_paint() {
+                    bundle.add_polygon(poly, &paint, min_resolution);
+                }
+                self.render_stroke(poly, min_resolution, bundle);
+            }
+            Geom::MultiPolygon(polygons) => {
+                if let Some(paint) = self.fill_paint() {
+                    bundle.add_multi_polygon(polygons, &paint, min_resolution);
+                }
+                polygons
+                    .polygons()
+                    .for_each(|polygon| self.render_stroke(polygon, min_resolution, bundle));
+            }
             _ => {}
         }
     }
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use galileo_types::cartesian::{Point3, Rect, Size, Vector2};
+use galileo_types::geometry::Geom;
+use galileo_types::MultiPoint;
+use maybe_sync::{MaybeSend, MaybeSync};
+
+use crate::decoded_image::DecodedImage;
+use crate::layer::feature_layer::symbol::Symbol;
+use crate::render::point_paint::MarkerStyle;
+use crate::render::render_bundle::RenderBundle;
+
+type SpriteNameFn<F> = dyn Fn(&F) -> Option<&str> + MaybeSend + MaybeSync;
+
+/// Renders points with icons taken from a single sprite sheet (texture atlas).
+///
+/// The sprite sheet is one bitmap containing many icons. Each icon is identified by its name and is defined by a
+/// rectangle of the bitmap. The name of the icon to draw is provided by the feature. Since all icons share the
+/// same texture, a large number of different icons can be drawn without loading a separate image for each one.
+pub struct SpriteSheetSymbol<F> {
+    atlas: Arc<DecodedImage>,
+    sprites: HashMap<String, Rect<u32>>,
+    sprite_name: Box<SpriteNameFn<F>>,
+    offset: Vector2<f32>,
+    scale: f32,
+}
+
+impl<F> SpriteSheetSymbol<F> {
+    /// Creates a new symbol.
+    ///
+    /// * `atlas` - bitmap with all the icons.
+    /// * `sprites` - rectangles of the icons in pixels of the `atlas`, with the origin in the top left corner.
+    /// * `sprite_name` - returns the name of the icon to draw for a feature. Features, for which `None` or an
+    ///   unknown name is returned, are not rendered.
+    ///
+    /// Icons are drawn centered at the point with their original size. Use [`SpriteSheetSymbol::with_offset`] and
+    /// [`SpriteSheetSymbol::with_scale`] to change this.
+    pub fn new(
+        atlas: Arc<DecodedImage>,
+        sprites: HashMap<String, Rect<u32>>,
+        sprite_name: impl Fn(&F) -> Option<&str> + MaybeSend + MaybeSync + 'static,
+    ) -> Self {
+        Self {
+            atlas,
+            sprites,
+            sprite_name: Box::new(sprite_name),
+            offset: Vector2::new(0.5, 0.5),
+            scale: 1.0,
+        }
+    }
+
+    /// Sets the anchor point of the icons as a fraction of the icon size. See [`ImagePointSymbol`] for details.
+    ///
+    /// [`ImagePointSymbol`]: super::ImagePointSymbol
+    pub fn with_offset(mut self, offset: Vector2<f32>) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Sets the scale with which the icons are drawn.
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+}
+
+impl<F> Symbol<F> for SpriteSheetSymbol<F> {
+    fn render(
+        &self,
+        feature: &F,
+        geometry: &Geom<Point3>,
+        _min_resolution: f64,
+        bundle: &mut RenderBundle,
+    ) {
+        let Some(rect) = (self.sprite_name)(feature).and_then(|name| self.sprites.get(name)) else {
+            return;
+        };
+
+        let style = MarkerStyle::Image {
+            image: self.atlas.clone(),
+            anchor: self.offset,
+            size: Some(Size::new(
+                (rect.width() as f32 * self.scale).round() as u32,
+                (rect.height() as f32 * self.scale).round() as u32,
+            )),
+            source_rect: Some(*rect),
+            rotation: 0.0,
+        };
+
+        match geometry {
+            Geom::Point(point) => bundle.add_marker(point, &style),
+            Geom::MultiPoint(points) => points.iter_points().for_each(|point| {
+                bundle.add_marker(&point, &style);
+            }),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::render_bundle::screen_set::ScreenSetData;
+
+    fn symbol() -> SpriteSheetSymbol<&'static str> {
+        let atlas =
+            DecodedImage::from_raw(vec![0; 4 * 32 * 16], Size::new(32, 16)).expect("valid image");
+        let sprites = HashMap::from([
+            ("a".to_string(), Rect::new(0, 0, 16, 16)),
+            ("b".to_string(), Rect::new(16, 0, 32, 8)),
+        ]);
+
+        SpriteSheetSymbol::new(Arc::new(atlas), sprites, |name: &&str| Some(*name))
+    }
+
+    #[test]
+    fn sprite_is_selected_by_feature() {
+        let symbol = symbol().with_scale(2.0);
+        let mut bundle = RenderBundle::default();
+        symbol.render(
+            &"b",
+            &Geom::Point(Point3::new(0.0, 0.0, 0.0)),
+            1.0,
+            &mut bundle,
+        );
+
+        assert_eq!(bundle.screen_sets.len(), 1);
+        let set = &bundle.screen_sets[0];
+        assert_eq!(set.bbox.width(), 32.0);
+        assert_eq!(set.bbox.height(), 16.0);
+
+        let ScreenSetData::Image { vertices, .. } = &set.data else {
+            panic!("image marker expected");
+        };
+        assert_eq!(vertices[0].tex_coords, [0.5, 0.5]);
+        assert_eq!(vertices[3].tex_coords, [1.0, 0.0]);
+    }
+
+    #[test]
+    fn unknown_sprite_is_not_rendered() {
+        let mut bundle = RenderBundle::default();
+        symbol().render(
+            &"c",
+            &Geom::Point(Point3::new(0.0, 0.0, 0.0)),
+            1.0,
+            &mut bundle,
+        );
+
+        assert!(bundle.screen_sets.is_empty());
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use galileo_types::cartesian::Size;
+use parking_lot::Mutex;
+use resvg::{tiny_skia, usvg};
+
+use crate::decoded_image::DecodedImage;
+use crate::error::GalileoError;
+
+/// SVG image that is rasterized on demand.
+///
+/// Rasterized bitmaps are cached by their pixel size, so the image is rendered again only when
+/// the requested size changes, e.g. when the window is moved to a display with another scale
+/// factor.
+pub(crate) struct SvgIcon {
+    tree: usvg::Tree,
+    cache: Mutex<HashMap<(u32, u32), Arc<DecodedImage>>>,
+}
+
+impl SvgIcon {
+    /// Parses the SVG document.
+    pub(crate) fn parse(data: &[u8]) -> Result<Self, GalileoError> {
+        let tree = usvg::Tree::from_data(data, &usvg::Options::default()).map_err(|err| {
+            GalileoError::InvalidImage(format!("failed to parse SVG image: {err}"))
+        })?;
+
+        Ok(Self {
+            tree,
+            cache: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Size of the image as defined by the SVG document.
+    pub(crate) fn size(&self) -> Size<f32> {
+        let size = self.tree.size();
+        Size::new(size.width(), size.height())
+    }
+
+    /// Rasterizes the image scaled to the given pixel size.
+    pub(crate) fn rasterize(&self, size: Size<u32>) -> Result<Arc<DecodedImage>, GalileoError> {
+        let key = (size.width().max(1), size.height().max(1));
+        if let Some(image) = self.cache.lock().get(&key) {
+            return Ok(image.clone());
+        }
+
+        let mut pixmap = tiny_skia::Pixmap::new(key.0, key.1)
+            .ok_or_else(|| GalileoError::InvalidImage("invalid size of SVG image".into()))?;
+        let svg_size = self.size();
+        let transform = tiny_skia::Transform::from_scale(
+            key.0 as f32 / svg_size.width(),
+            key.1 as f32 / svg_size.height(),
+        );
+        resvg::render(&self.tree, transform, &mut pixmap.as_mut());
+
+        // Pixmap stores colors with premultiplied alpha.
+        let image = Arc::new(DecodedImage::from_raw_premultiplied(
+            pixmap.take(),
+            Size::new(key.0, key.1),
+        )?);
+        self.cache.lock().insert(key, image.clone());
+
+        Ok(image)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoded_image::DecodedImageType;
+
+    const SVG: &[u8] = br##"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="20">
+        <rect width="10" height="20" fill="#ff0000"/>
+    </svg>"##;
+
+    fn bitmap_bytes(image: &DecodedImage) -> &[u8] {
+        match &image.0 {
+            DecodedImageType::Bitmap { bytes, .. } => bytes,
+            #[cfg(target_arch = "wasm32")]
+            _ => panic!("bitmap expected"),
+        }
+    }
+
+    #[test]
+    fn svg_is_rasterized_at_requested_size() {
+        let icon = SvgIcon::parse(SVG).expect("valid svg");
+        assert_eq!(icon.size(), Size::new(10.0, 20.0));
+
+        let image = icon.rasterize(Size::new(20, 40)).expect("rasterized");
+        assert_eq!(image.size(), Size::new(20, 40));
+
+        let bytes = bitmap_bytes(&image);
+        let center = (20 * 20 + 10) * 4;
+        assert_eq!(&bytes[center..center + 4], &[255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn rasterized_images_are_cached() {
+        let icon = SvgIcon::parse(SVG).expect("valid svg");
+        let first = icon.rasterize(Size::new(10, 20)).expect("rasterized");
+        let second = icon.rasterize(Size::new(10, 20)).expect("rasterized");
+        assert!(Arc::ptr_eq(&first, &second));
+
+        let other = icon.rasterize(Size::new(20, 40)).expect("rasterized");
+        assert!(!Arc::ptr_eq(&first, &other));
+    }
+
+    #[test]
+    fn invalid_svg_is_rejected() {
+        assert!(SvgIcon::parse(b"not an svg").is_err());
+    }
+}
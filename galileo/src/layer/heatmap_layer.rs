@@ -0,0 +1,525 @@
+//! [`HeatmapLayer`] draws the density of point features as a heatmap.
+
+use std::any::Any;
+
+use galileo_types::cartesian::{CartesianPoint2d, Point2, Rect, Size};
+use galileo_types::geo::{Crs, NewGeoPoint};
+use galileo_types::geometry::{Geom, Geometry};
+use maybe_sync::{MaybeSend, MaybeSync};
+use parking_lot::{Mutex, RwLock};
+
+use crate::decoded_image::DecodedImage;
+use crate::layer::attribution::Attribution;
+use crate::layer::feature_layer::Feature;
+use crate::layer::Layer;
+use crate::messenger::Messenger;
+use crate::render::render_bundle::RenderBundle;
+use crate::render::{
+    color_at_offset, Canvas, GradientStop, ImagePaint, ImageSampling, PackedBundle, RenderOptions,
+};
+use crate::view::MapView;
+
+/// Size of a cell of the intensity field in pixels. The field is drawn with linear sampling, so
+/// computing it at a lower resolution than the screen is not noticeable.
+const CELL_SIZE: f64 = 2.0;
+
+/// Maximum width and height of the intensity field in cells.
+const MAX_FIELD_SIZE: f64 = 4096.0;
+
+/// How much larger than the visible area the calculated field is, so that the map can be panned
+/// a bit without recalculating it.
+const AREA_MARGIN: f64 = 1.5;
+
+/// Number of resolutions the calculated heatmaps are kept for, so that zooming back and forth does
+/// not recalculate the field.
+const MAX_CACHED_RESOLUTIONS: usize = 4;
+
+/// Shape of the contribution of a point to the heatmap intensity as a function of the distance
+/// from the point.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum HeatmapKernel {
+    /// Contribution decreases linearly to zero at the radius.
+    Linear,
+    /// Contribution decreases as `1 - d²`, where `d` is the distance relative to the radius.
+    Epanechnikov,
+    /// Gaussian bell curve with the radius equal to three standard deviations.
+    #[default]
+    Gaussian,
+}
+
+impl HeatmapKernel {
+    /// Contribution of a point at the distance of `distance` radii from it.
+    fn weight(&self, distance: f64) -> f64 {
+        if distance >= 1.0 {
+            return 0.0;
+        }
+
+        match self {
+            Self::Linear => 1.0 - distance,
+            Self::Epanechnikov => 1.0 - distance * distance,
+            Self::Gaussian => (-4.5 * distance * distance).exp(),
+        }
+    }
+}
+
+/// Defines how the features of a [`HeatmapLayer`] are turned into a heatmap.
+///
+/// Every point feature adds its weight, multiplied by the kernel value, to the intensity of the
+/// pixels within the radius around it. The intensity is then divided by the maximum intensity
+/// and mapped to a color through the color ramp.
+pub struct HeatmapSymbol<F> {
+    radius: f64,
+    kernel: HeatmapKernel,
+    color_ramp: Vec<GradientStop>,
+    max_intensity: f64,
+    weight: Box<dyn Fn(&F) -> f64 + MaybeSend + MaybeSync>,
+}
+
+impl<F> HeatmapSymbol<F> {
+    /// Creates a new symbol with the given radius of a point in pixels and the color ramp.
+    ///
+    /// The color ramp maps relative intensity from `0.0` to `1.0` to colors. Stops must be sorted
+    /// by their offsets. Pixels without any intensity are always transparent.
+    ///
+    /// All features have the weight of `1.0`, the [`HeatmapKernel::Gaussian`] kernel is used, and
+    /// the maximum intensity is `1.0`.
+    pub fn new(radius: f64, color_ramp: Vec<GradientStop>) -> Self {
+        Self {
+            radius,
+            kernel: HeatmapKernel::default(),
+            color_ramp,
+            max_intensity: 1.0,
+            weight: Box::new(|_| 1.0),
+        }
+    }
+
+    /// Sets the kernel of the point contribution.
+    pub fn with_kernel(mut self, kernel: HeatmapKernel) -> Self {
+        self.kernel = kernel;
+        self
+    }
+
+    /// Sets the function that returns the weight of a feature.
+    pub fn with_weight(
+        mut self,
+        weight: impl Fn(&F) -> f64 + MaybeSend + MaybeSync + 'static,
+    ) -> Self {
+        self.weight = Box::new(weight);
+        self
+    }
+
+    /// Sets the intensity that corresponds to the end of the color ramp. Higher intensities are
+    /// drawn with the last color of the ramp.
+    pub fn with_max_intensity(mut self, max_intensity: f64) -> Self {
+        self.max_intensity = max_intensity;
+        self
+    }
+
+    /// Color of a pixel with the given intensity.
+    fn color(&self, intensity: f64) -> [u8; 4] {
+        if intensity <= 0.0 {
+            return [0; 4];
+        }
+
+        let offset = if self.max_intensity > 0.0 {
+            (intensity / self.max_intensity).min(1.0)
+        } else {
+            1.0
+        };
+        color_at_offset(&self.color_ramp, offset as f32).map(|c| (c * 255.0).round() as u8)
+    }
+}
+
+/// Intensity field over a rectangular area of the map.
+struct IntensityField {
+    /// Index of the left column of cells.
+    x_min: i64,
+    /// Index of the top row of cells.
+    y_max: i64,
+    width: usize,
+    height: usize,
+    cell_size: f64,
+    /// Intensity of the cells row by row, starting from the top row.
+    values: Vec<f64>,
+}
+
+impl IntensityField {
+    /// Creates an empty field covering the `area` with cells of `cell_size` map units. The cells
+    /// are aligned to the origin of the map CRS.
+    fn new(area: Rect, cell_size: f64) -> Self {
+        let x_min = (area.x_min() / cell_size).floor() as i64;
+        let x_max = (area.x_max() / cell_size).ceil() as i64;
+        let y_min = (area.y_min() / cell_size).floor() as i64;
+        let y_max = (area.y_max() / cell_size).ceil() as i64;
+        let width = (x_max - x_min).max(0) as usize;
+        let height = (y_max - y_min).max(0) as usize;
+
+        Self {
+            x_min,
+            y_max,
+            width,
+            height,
+            cell_size,
+            values: vec![0.0; width * height],
+        }
+    }
+
+    /// Area covered by the field in map units.
+    fn area(&self) -> Rect {
+        Rect::new(
+            self.x_min as f64 * self.cell_size,
+            (self.y_max - self.height as i64) as f64 * self.cell_size,
+            (self.x_min + self.width as i64) as f64 * self.cell_size,
+            self.y_max as f64 * self.cell_size,
+        )
+    }
+
+    /// Adds the contribution of a point with the given `radius` in map units to the field.
+    fn add_point(&mut self, point: Point2, weight: f64, radius: f64, kernel: HeatmapKernel) {
+        if radius <= 0.0 || weight == 0.0 {
+            return;
+        }
+
+        let cx = point.x() / self.cell_size - self.x_min as f64;
+        let cy = self.y_max as f64 - point.y() / self.cell_size;
+        let r = radius / self.cell_size;
+
+        let col_min = (cx - r).floor().max(0.0) as usize;
+        let col_max = ((cx + r).ceil().max(0.0) as usize).min(self.width);
+        let row_min = (cy - r).floor().max(0.0) as usize;
+        let row_max = ((cy + r).ceil().max(0.0) as usize).min(self.height);
+
+        for row in row_min..row_max {
+            let dy = row as f64 + 0.5 - cy;
+            for col in col_min..col_max {
+                let dx = col as f64 + 0.5 - cx;
+                let distance = (dx * dx + dy * dy).sqrt() / r;
+                self.values[row * self.width + col] += weight * kernel.weight(distance);
+            }
+        }
+    }
+}
+
+/// Layer that draws the density of point features as a heatmap.
+///
+/// The intensity field is calculated on the CPU in [`Layer::prepare`] for an area somewhat larger
+/// than the visible part of the map and is drawn as an image. It is recalculated when the map is
+/// zoomed to a resolution that is not cached yet or panned outside of the calculated area. Until
+/// then, the heatmap calculated for the previous view is drawn.
+///
+/// Features with geometries other than points are ignored.
+pub struct HeatmapLayer<F> {
+    features: Vec<F>,
+    symbol: HeatmapSymbol<F>,
+    /// Calculated heatmaps, the most recently used last.
+    cache: Mutex<Vec<HeatmapEntry>>,
+    messenger: RwLock<Option<Box<dyn Messenger>>>,
+}
+
+/// Heatmap calculated for one resolution of the map.
+struct HeatmapEntry {
+    crs: Crs,
+    resolution: f64,
+    area: Rect,
+    bundle: HeatmapBundle,
+}
+
+enum HeatmapBundle {
+    /// Calculated in `prepare`, but not yet moved into GPU memory.
+    Prepared(RenderBundle),
+    Packed(Box<dyn PackedBundle>),
+}
+
+impl HeatmapEntry {
+    /// Returns `true` if the heatmap can be drawn for the `bbox` at the given resolution.
+    fn covers(&self, crs: &Crs, resolution: f64, bbox: Rect) -> bool {
+        &self.crs == crs
+            && self.resolution == resolution
+            && self.area.contains(&Point2::new(bbox.x_min(), bbox.y_min()))
+            && self.area.contains(&Point2::new(bbox.x_max(), bbox.y_max()))
+    }
+}
+
+impl<F> HeatmapLayer<F> {
+    /// Creates a new layer that draws the `features` with the `symbol`.
+    pub fn new(features: Vec<F>, symbol: HeatmapSymbol<F>) -> Self {
+        Self {
+            features,
+            symbol,
+            cache: Mutex::new(Vec::new()),
+            messenger: RwLock::new(None),
+        }
+    }
+
+    /// Features of the layer.
+    pub fn features(&self) -> &[F] {
+        &self.features
+    }
+
+    /// Replaces the features of the layer. The heatmap will be recalculated on the next
+    /// [`Layer::prepare`] call.
+    pub fn set_features(&mut self, features: Vec<F>) {
+        self.features = features;
+        self.invalidate();
+    }
+
+    /// Changes the symbol of the layer.
+    pub fn set_symbol(&mut self, symbol: HeatmapSymbol<F>) {
+        self.symbol = symbol;
+        self.invalidate();
+    }
+
+    fn invalidate(&mut self) {
+        self.cache.get_mut().clear();
+        if let Some(messenger) = self.messenger.read().as_ref() {
+            messenger.request_redraw();
+        }
+    }
+}
+
+impl<F> HeatmapLayer<F>
+where
+    F: Feature,
+    F::Geom: Geometry,
+    <F::Geom as Geometry>::Point: NewGeoPoint + 'static,
+{
+    fn intensity_field(&self, crs: &Crs, resolution: f64, area: Rect) -> Option<IntensityField> {
+        let Some(projection) = crs.get_projection::<<F::Geom as Geometry>::Point, Point2>() else {
+            log::warn!("Cannot draw heatmap layer: map CRS {crs:?} has no projection");
+            return None;
+        };
+
+        let cell_size = (resolution * CELL_SIZE)
+            .max(area.width() / MAX_FIELD_SIZE)
+            .max(area.height() / MAX_FIELD_SIZE);
+        let mut field = IntensityField::new(area, cell_size);
+        let radius = self.symbol.radius * resolution;
+        let area = field.area();
+        let influence_area = Rect::new(
+            area.x_min() - radius,
+            area.y_min() - radius,
+            area.x_max() + radius,
+            area.y_max() + radius,
+        );
+
+        for feature in &self.features {
+            let Some(Geom::Point(point)) = feature.geometry().project(&*projection) else {
+                continue;
+            };
+            if influence_area.contains(&point) {
+                let weight = (self.symbol.weight)(feature);
+                field.add_point(point, weight, radius, self.symbol.kernel);
+            }
+        }
+
+        Some(field)
+    }
+
+    fn render_bundle(&self, field: &IntensityField) -> Option<RenderBundle> {
+        let bytes: Vec<u8> = field
+            .values
+            .iter()
+            .flat_map(|&intensity| self.symbol.color(intensity))
+            .collect();
+        let size = Size::new(field.width as u32, field.height as u32);
+        let image = match DecodedImage::from_raw(bytes, size) {
+            Ok(image) => image,
+            Err(err) => {
+                log::warn!("Failed to create heatmap image: {err}");
+                return None;
+            }
+        };
+
+        let mut bundle = RenderBundle::default();
+        bundle.add_image_owned(
+            image,
+            field.area().into_quadrangle(),
+            ImagePaint {
+                opacity: 255,
+                sampling: ImageSampling::Linear,
+            },
+        );
+
+        Some(bundle)
+    }
+}
+
+impl<F> Layer for HeatmapLayer<F>
+where
+    F: Feature + MaybeSend + MaybeSync + 'static,
+    F::Geom: Geometry,
+    <F::Geom as Geometry>::Point: NewGeoPoint + 'static,
+{
+    fn render(&self, view: &MapView, canvas: &mut dyn Canvas) {
+        let Some(view_bbox) = view.get_bbox() else {
+            return;
+        };
+        let resolution = view.resolution();
+
+        let mut cache = self.cache.lock();
+        let position = cache
+            .iter()
+            .rposition(|entry| entry.covers(view.crs(), resolution, view_bbox))
+            .or_else(|| cache.iter().rposition(|entry| &entry.crs == view.crs()));
+        let Some(entry) = position.map(|position| &mut cache[position]) else {
+            return;
+        };
+
+        if let HeatmapBundle::Prepared(bundle) = &entry.bundle {
+            entry.bundle = HeatmapBundle::Packed(canvas.pack_bundle(bundle));
+        }
+
+        if let HeatmapBundle::Packed(bundle) = &entry.bundle {
+            canvas.draw_bundles(&[&**bundle], RenderOptions { antialias: false });
+        }
+    }
+
+    fn prepare(&self, view: &MapView) {
+        let Some(view_bbox) = view.get_bbox() else {
+            return;
+        };
+        let resolution = view.resolution();
+
+        {
+            let mut cache = self.cache.lock();
+            if let Some(position) = cache
+                .iter()
+                .position(|entry| entry.covers(view.crs(), resolution, view_bbox))
+            {
+                let entry = cache.remove(position);
+                cache.push(entry);
+                return;
+            }
+        }
+
+        let Some(entry) = self
+            .intensity_field(view.crs(), resolution, view_bbox.magnify(AREA_MARGIN))
+            .and_then(|field| {
+                Some(HeatmapEntry {
+                    crs: view.crs().clone(),
+                    resolution,
+                    area: field.area(),
+                    bundle: HeatmapBundle::Prepared(self.render_bundle(&field)?),
+                })
+            })
+        else {
+            return;
+        };
+
+        {
+            let mut cache = self.cache.lock();
+            cache.retain(|cached| cached.crs != entry.crs || cached.resolution != resolution);
+            cache.push(entry);
+            if cache.len() > MAX_CACHED_RESOLUTIONS {
+                cache.remove(0);
+            }
+        }
+
+        if let Some(messenger) = self.messenger.read().as_ref() {
+            messenger.request_redraw();
+        }
+    }
+
+    fn is_ready(&self, view: &MapView) -> bool {
+        let Some(view_bbox) = view.get_bbox() else {
+            return true;
+        };
+
+        self.cache
+            .lock()
+            .iter()
+            .any(|entry| entry.covers(view.crs(), view.resolution(), view_bbox))
+    }
+
+    fn set_messenger(&mut self, messenger: Box<dyn Messenger>) {
+        *self.messenger.write() = Some(messenger);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn attribution(&self) -> Option<Attribution> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use galileo_types::latlon;
+
+    use super::*;
+    use crate::Color;
+
+    #[test]
+    fn kernels_fall_off_to_zero_at_radius() {
+        for kernel in [
+            HeatmapKernel::Linear,
+            HeatmapKernel::Epanechnikov,
+            HeatmapKernel::Gaussian,
+        ] {
+            assert_eq!(kernel.weight(0.0), 1.0);
+            assert!(kernel.weight(0.5) < 1.0 && kernel.weight(0.5) > 0.0);
+            assert_eq!(kernel.weight(1.0), 0.0);
+        }
+    }
+
+    #[test]
+    fn point_contributions_are_accumulated() {
+        let mut field = IntensityField::new(Rect::new(0.0, 0.0, 10.0, 10.0), 1.0);
+        assert_eq!((field.width, field.height), (10, 10));
+
+        field.add_point(Point2::new(2.5, 7.5), 1.0, 2.0, HeatmapKernel::Linear);
+        field.add_point(Point2::new(2.5, 7.5), 2.0, 2.0, HeatmapKernel::Linear);
+
+        // Cell with the center at (2.5, 7.5) is in the 3rd row from the top and the 3rd column.
+        assert_eq!(field.values[2 * 10 + 2], 3.0);
+        assert_eq!(field.values[2 * 10 + 3], 1.5);
+        assert_eq!(field.values[2 * 10 + 5], 0.0);
+        assert_eq!(field.values.iter().filter(|v| **v > 0.0).count(), 9);
+    }
+
+    #[test]
+    fn intensity_is_mapped_through_color_ramp() {
+        let symbol: HeatmapSymbol<()> = HeatmapSymbol::new(
+            10.0,
+            vec![
+                GradientStop::new(0.0, Color::BLUE.with_alpha(0)),
+                GradientStop::new(1.0, Color::RED),
+            ],
+        )
+        .with_max_intensity(4.0);
+
+        assert_eq!(symbol.color(0.0), [0; 4]);
+        assert_eq!(symbol.color(2.0), [128, 0, 128, 128]);
+        assert_eq!(symbol.color(10.0), Color::RED.to_u8_array());
+    }
+
+    #[test]
+    fn heatmap_is_calculated_in_prepare_once_per_resolution() {
+        let layer = HeatmapLayer::new(
+            vec![latlon!(0.0, 0.0)],
+            HeatmapSymbol::new(10.0, vec![GradientStop::new(0.0, Color::RED)]),
+        );
+        let view = MapView::new(&latlon!(0.0, 0.0), 1000.0).with_size(Size::new(100.0, 100.0));
+        assert!(!layer.is_ready(&view));
+
+        layer.prepare(&view);
+        assert!(layer.is_ready(&view));
+        assert_eq!(layer.cache.lock().len(), 1);
+
+        layer.prepare(&view);
+        assert_eq!(layer.cache.lock().len(), 1);
+
+        let zoomed = view.with_resolution(500.0);
+        assert!(!layer.is_ready(&zoomed));
+        layer.prepare(&zoomed);
+        assert!(layer.is_ready(&zoomed));
+        assert!(layer.is_ready(&view));
+        assert_eq!(layer.cache.lock().len(), 2);
+    }
+}
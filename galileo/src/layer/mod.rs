@@ -13,23 +13,32 @@ use crate::view::MapView;
 use crate::TileSchema;
 
 pub mod attribution;
+pub mod cluster_layer;
 pub mod data_provider;
 pub mod feature_layer;
+pub mod heatmap_layer;
+pub mod point_cloud_layer;
 pub mod raster_tile_layer;
 pub(crate) mod tiles;
 pub mod vector_tile_layer;
 
+pub use cluster_layer::ClusterLayer;
 pub use feature_layer::{FeatureId, FeatureLayer};
+pub use heatmap_layer::HeatmapLayer;
+pub use point_cloud_layer::PointCloudLayer;
 pub use raster_tile_layer::RasterTileLayer;
 pub use vector_tile_layer::VectorTileLayer;
 
 /// Layers specify a data source and the way the data should be rendered to the map.
 ///
-/// There are currently 3 types of layers:
+/// There are currently 6 types of layers:
 /// * [`RasterTileLayer`] - downloads prerendered tiles from an Internet source and draws them as is.
 /// * [`VectorTileLayer`] - downloads vector tiles (in MVT format) from an Internet source and draws them using the
 ///   provided stylesheet.
 /// * [`FeatureLayer`] - draws custom set of geographic objects with the given [`feature_layer::Symbol`];
+/// * [`PointCloudLayer`] - draws a large static set of points as dots.
+/// * [`ClusterLayer`] - draws point features, grouping nearby points into cluster markers.
+/// * [`HeatmapLayer`] - draws the density of point features as a heatmap.
 pub trait Layer: MaybeSend + MaybeSync {
     /// Renders the layer to the given canvas.
     fn render(&self, view: &MapView, canvas: &mut dyn Canvas);
@@ -49,6 +58,13 @@ pub trait Layer: MaybeSend + MaybeSync {
     }
     /// Returns the attribution of the layer, if available.
     fn attribution(&self) -> Option<Attribution>;
+    /// Returns `true` if all the data needed to render the layer with the given `view` is loaded
+    /// (or failed to load), so rendering the layer will not produce a partial image.
+    ///
+    /// Layers that do not load data asynchronously are always ready.
+    fn is_ready(&self, _view: &MapView) -> bool {
+        true
+    }
 }
 
 impl<T: Layer + 'static> Layer for Arc<RwLock<T>> {
@@ -79,6 +95,10 @@ impl<T: Layer + 'static> Layer for Arc<RwLock<T>> {
     fn attribution(&self) -> Option<Attribution> {
         self.read().attribution()
     }
+
+    fn is_ready(&self, view: &MapView) -> bool {
+        self.read().is_ready(view)
+    }
 }
 
 /// Used for doc-tests
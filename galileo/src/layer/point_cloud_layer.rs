@@ -0,0 +1,289 @@
+//! [`PointCloudLayer`] draws large static sets of points.
+
+use std::any::Any;
+
+use galileo_types::cartesian::{CartesianPoint2d, Point2, Point3, Rect};
+use galileo_types::geo::impls::GeoPoint2d;
+use galileo_types::geo::Crs;
+use parking_lot::{Mutex, RwLock};
+
+use crate::layer::attribution::Attribution;
+use crate::layer::Layer;
+use crate::messenger::Messenger;
+use crate::render::point_paint::PointPaint;
+use crate::render::render_bundle::RenderBundle;
+use crate::render::{Canvas, PackedBundle, RenderOptions};
+use crate::view::MapView;
+use crate::Color;
+
+/// Layer that draws a large set of points as one-pixel dots.
+///
+/// Unlike [`FeatureLayer`](super::FeatureLayer), this layer does not process the points one by one when the map is
+/// rendered. The first time the layer is drawn, all the points are projected, split into spatially compact chunks of
+/// limited size and every chunk is uploaded to the GPU as a separate buffer. On every frame the layer only checks the
+/// bounding box of each chunk against the view and draws the buffers of the visible chunks, so panning and zooming
+/// the map does not require any per-point work on the CPU side.
+///
+/// The points are drawn with the same dot pipeline as [`PointPaint::dot`], there is no separate instanced pipeline
+/// for point clouds.
+///
+/// The buffers are created again only when the points or their color are changed, or when the map CRS changes. This
+/// makes the layer suitable for millions of points that do not change often.
+pub struct PointCloudLayer {
+    points: Vec<GeoPoint2d>,
+    color: Color,
+    packed: Mutex<Option<PackedPoints>>,
+    messenger: RwLock<Option<Box<dyn Messenger>>>,
+}
+
+/// Maximum number of points uploaded to the GPU in one buffer.
+const MAX_CHUNK_POINTS: usize = 1 << 16;
+
+struct PackedPoints {
+    crs: Crs,
+    chunks: Vec<PackedChunk>,
+}
+
+struct PackedChunk {
+    bbox: Rect,
+    bundle: Box<dyn PackedBundle>,
+}
+
+impl PointCloudLayer {
+    /// Creates a new layer that draws the `points` with the given color.
+    pub fn new(points: Vec<GeoPoint2d>, color: Color) -> Self {
+        Self {
+            points,
+            color,
+            packed: Mutex::new(None),
+            messenger: RwLock::new(None),
+        }
+    }
+
+    /// Points of the layer.
+    pub fn points(&self) -> &[GeoPoint2d] {
+        &self.points
+    }
+
+    /// Replaces the points of the layer. The points will be uploaded to the GPU on the next render.
+    pub fn set_points(&mut self, points: Vec<GeoPoint2d>) {
+        self.points = points;
+        self.invalidate();
+    }
+
+    /// Color of the points.
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    /// Changes the color of the points.
+    pub fn set_color(&mut self, color: Color) {
+        self.color = color;
+        self.invalidate();
+    }
+
+    fn invalidate(&mut self) {
+        *self.packed.get_mut() = None;
+        if let Some(messenger) = self.messenger.read().as_ref() {
+            messenger.request_redraw();
+        }
+    }
+
+    fn pack(&self, crs: &Crs, canvas: &dyn Canvas) -> Vec<PackedChunk> {
+        let Some(projection) = crs.get_projection::<GeoPoint2d, Point2>() else {
+            log::warn!("Cannot draw point cloud layer: map CRS {crs:?} has no projection");
+            return vec![];
+        };
+
+        let mut projected: Vec<Point2> = self
+            .points
+            .iter()
+            .filter_map(|point| projection.project(point))
+            .collect();
+        let mut chunks = vec![];
+        split_into_chunks(&mut projected, true, &mut chunks);
+
+        let paint = PointPaint::dot(self.color);
+        chunks
+            .into_iter()
+            .filter_map(|points| {
+                let bbox = Rect::from_points(points.iter().copied())?;
+                let mut bundle = RenderBundle::default();
+                for point in points {
+                    bundle.add_point(&Point3::new(point.x(), point.y(), 0.0), &paint, 1.0);
+                }
+
+                Some(PackedChunk {
+                    bbox,
+                    bundle: canvas.pack_bundle(&bundle),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Splits the points into chunks of at most [`MAX_CHUNK_POINTS`] points, dividing them by the median alternately
+/// along the x and y axes, so that every chunk covers a compact area.
+fn split_into_chunks<'a>(
+    points: &'a mut [Point2],
+    split_by_x: bool,
+    chunks: &mut Vec<&'a [Point2]>,
+) {
+    if points.len() <= MAX_CHUNK_POINTS {
+        if !points.is_empty() {
+            chunks.push(points);
+        }
+        return;
+    }
+
+    let middle = points.len() / 2;
+    points.select_nth_unstable_by(middle, |a, b| {
+        if split_by_x {
+            a.x().total_cmp(&b.x())
+        } else {
+            a.y().total_cmp(&b.y())
+        }
+    });
+
+    let (left, right) = points.split_at_mut(middle);
+    split_into_chunks(left, !split_by_x, chunks);
+    split_into_chunks(right, !split_by_x, chunks);
+}
+
+impl Layer for PointCloudLayer {
+    fn render(&self, view: &MapView, canvas: &mut dyn Canvas) {
+        let mut packed = self.packed.lock();
+        if packed.as_ref().is_none_or(|p| &p.crs != view.crs()) {
+            *packed = Some(PackedPoints {
+                crs: view.crs().clone(),
+                chunks: self.pack(view.crs(), canvas),
+            });
+        }
+
+        let view_bbox = view.get_bbox();
+        let bundles: Vec<&dyn PackedBundle> = packed
+            .iter()
+            .flat_map(|p| &p.chunks)
+            .filter(|chunk| view_bbox.is_none_or(|bbox| bbox.intersects(chunk.bbox)))
+            .map(|chunk| &*chunk.bundle)
+            .collect();
+
+        if !bundles.is_empty() {
+            canvas.draw_bundles(&bundles, RenderOptions { antialias: false });
+        }
+    }
+
+    fn prepare(&self, _view: &MapView) {
+        // do nothing
+    }
+
+    fn set_messenger(&mut self, messenger: Box<dyn Messenger>) {
+        *self.messenger.write() = Some(messenger);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn attribution(&self) -> Option<Attribution> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use galileo_types::cartesian::Size;
+    use galileo_types::latlon;
+
+    use super::*;
+
+    struct TestBundle;
+
+    impl PackedBundle for TestBundle {
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    #[derive(Default)]
+    struct CountingCanvas {
+        packed: Cell<usize>,
+        drawn: usize,
+    }
+
+    impl Canvas for CountingCanvas {
+        fn size(&self) -> Size {
+            Size::new(100.0, 100.0)
+        }
+
+        fn pack_bundle(&self, _bundle: &RenderBundle) -> Box<dyn PackedBundle> {
+            self.packed.set(self.packed.get() + 1);
+            Box::new(TestBundle)
+        }
+
+        fn draw_bundles(&mut self, bundles: &[&dyn PackedBundle], _options: RenderOptions) {
+            self.drawn += bundles.len();
+        }
+
+        fn draw_bundles_with_opacity(
+            &mut self,
+            bundles: &[(&dyn PackedBundle, f32)],
+            _options: RenderOptions,
+        ) {
+            self.drawn += bundles.len();
+        }
+
+        fn draw_screen_sets(&mut self) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn panning_reuses_uploaded_points() {
+        let mut layer = PointCloudLayer::new(
+            (0..1000)
+                .map(|i| latlon!(i as f64 * 0.01, i as f64 * 0.02))
+                .collect(),
+            Color::RED,
+        );
+        let view = MapView::new(&latlon!(0.0, 0.0), 100.0).with_size(Size::new(100.0, 100.0));
+        let mut canvas = CountingCanvas::default();
+
+        layer.render(&view, &mut canvas);
+        layer.render(
+            &view.translate_by_pixels(Point2::new(0.0, 0.0), Point2::new(30.0, 10.0)),
+            &mut canvas,
+        );
+        layer.render(&view.with_resolution(10.0), &mut canvas);
+        assert_eq!(canvas.packed.get(), 1);
+        assert_eq!(canvas.drawn, 3);
+
+        layer.set_points(vec![latlon!(1.0, 1.0)]);
+        layer.render(&view, &mut canvas);
+        assert_eq!(canvas.packed.get(), 2);
+    }
+
+    #[test]
+    fn chunks_outside_of_view_are_not_drawn() {
+        let cluster = |lat: f64, lon: f64| {
+            (0..MAX_CHUNK_POINTS)
+                .map(move |i| latlon!(lat + (i % 256) as f64 * 1e-4, lon + (i / 256) as f64 * 1e-4))
+        };
+        let layer = PointCloudLayer::new(
+            cluster(0.0, 0.0).chain(cluster(0.0, 90.0)).collect(),
+            Color::RED,
+        );
+        let view = MapView::new(&latlon!(0.01, 0.01), 10.0).with_size(Size::new(100.0, 100.0));
+        let mut canvas = CountingCanvas::default();
+
+        layer.render(&view, &mut canvas);
+        assert_eq!(canvas.packed.get(), 2);
+        assert_eq!(canvas.drawn, 1);
+    }
+}
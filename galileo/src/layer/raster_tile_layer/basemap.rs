@@ -0,0 +1,174 @@
+use super::{RasterTileLayer, RestTileLoader};
+use crate::layer::attribution::Attribution;
+use crate::render::ImageSampling;
+use crate::tile_schema::TileIndex;
+use crate::TileSchema;
+
+/// Commonly used free raster basemaps.
+///
+/// Each variant knows the URL pattern of its tiles, the attribution required by the tile provider
+/// and the maximum zoom level the tiles are available for. Use
+/// [`MapBuilder::with_basemap()`](crate::MapBuilder::with_basemap) to add a basemap to a map, or
+/// [`RasterTileLayerBuilder::new_basemap()`](super::RasterTileLayerBuilder::new_basemap) if the
+/// layer needs additional configuration, e.g. a tile cache.
+///
+/// Note that the providers have their own usage policies, which should be checked before using
+/// the tiles in an application with a lot of users.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Basemap {
+    /// Standard OpenStreetMap tiles.
+    OpenStreetMap,
+    /// Topographic map by OpenTopoMap.
+    OpenTopoMap,
+    /// Light "Positron" style by CARTO.
+    CartoLight,
+    /// Dark "Dark Matter" style by CARTO.
+    CartoDark,
+    /// "Voyager" style by CARTO.
+    CartoVoyager,
+    /// Satellite imagery by Esri.
+    EsriWorldImagery,
+}
+
+impl Basemap {
+    /// All the available basemaps.
+    pub const ALL: [Basemap; 6] = [
+        Basemap::OpenStreetMap,
+        Basemap::OpenTopoMap,
+        Basemap::CartoLight,
+        Basemap::CartoDark,
+        Basemap::CartoVoyager,
+        Basemap::EsriWorldImagery,
+    ];
+
+    /// URL of the tile with the given index.
+    ///
+    /// ```
+    /// use galileo::layer::raster_tile_layer::Basemap;
+    /// use galileo::tile_schema::TileIndex;
+    ///
+    /// assert_eq!(
+    ///     Basemap::OpenStreetMap.tile_url(&TileIndex::new(1, 2, 3)),
+    ///     "https://tile.openstreetmap.org/3/1/2.png"
+    /// );
+    /// ```
+    pub fn tile_url(&self, index: &TileIndex) -> String {
+        let (z, x, y) = (index.z, index.x, index.y);
+        match self {
+            Basemap::OpenStreetMap => format!("https://tile.openstreetmap.org/{z}/{x}/{y}.png"),
+            Basemap::OpenTopoMap => format!("https://tile.opentopomap.org/{z}/{x}/{y}.png"),
+            Basemap::CartoLight => {
+                format!("https://basemaps.cartocdn.com/light_all/{z}/{x}/{y}.png")
+            }
+            Basemap::CartoDark => {
+                format!("https://basemaps.cartocdn.com/dark_all/{z}/{x}/{y}.png")
+            }
+            Basemap::CartoVoyager => {
+                format!("https://basemaps.cartocdn.com/rastertiles/voyager/{z}/{x}/{y}.png")
+            }
+            Basemap::EsriWorldImagery => format!(
+                "https://server.arcgisonline.com/ArcGIS/rest/services/World_Imagery/MapServer/tile/{z}/{y}/{x}"
+            ),
+        }
+    }
+
+    /// Attribution that must be displayed with the tiles of the basemap.
+    pub fn attribution(&self) -> Attribution {
+        let (text, url) = match self {
+            Basemap::OpenStreetMap => (
+                "© OpenStreetMap contributors",
+                "https://www.openstreetmap.org/copyright",
+            ),
+            Basemap::OpenTopoMap => (
+                "© OpenStreetMap contributors, SRTM | © OpenTopoMap (CC-BY-SA)",
+                "https://opentopomap.org/about",
+            ),
+            Basemap::CartoLight | Basemap::CartoDark | Basemap::CartoVoyager => (
+                "© OpenStreetMap contributors © CARTO",
+                "https://carto.com/attributions",
+            ),
+            Basemap::EsriWorldImagery => (
+                "Tiles © Esri — Source: Esri, Maxar, Earthstar Geographics, and the GIS User Community",
+                "https://www.esri.com/en-us/legal/terms/full-master-agreement",
+            ),
+        };
+
+        Attribution::new(text.to_string(), Some(url.to_string()))
+    }
+
+    /// Maximum zoom level the tiles of the basemap are available for.
+    pub fn max_z_level(&self) -> u32 {
+        match self {
+            Basemap::OpenStreetMap => 19,
+            Basemap::OpenTopoMap => 17,
+            Basemap::CartoLight | Basemap::CartoDark | Basemap::CartoVoyager => 20,
+            Basemap::EsriWorldImagery => 19,
+        }
+    }
+
+    /// Tile schema of the basemap.
+    pub fn tile_schema(&self) -> TileSchema {
+        TileSchema::web(self.max_z_level() + 1)
+    }
+
+    fn tile_loader(self) -> RestTileLoader {
+        RestTileLoader::new(move |index: &TileIndex| self.tile_url(index), None, false)
+    }
+}
+
+impl RasterTileLayer {
+    /// Creates a layer with the given basemap without a tile cache.
+    pub(crate) fn from_basemap(basemap: Basemap) -> Self {
+        Self::new_raw(
+            Box::new(basemap.tile_loader()),
+            basemap.tile_schema(),
+            None,
+            Some(basemap.attribution()),
+            ImageSampling::default(),
+            None,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layer::raster_tile_layer::RasterTileLayerBuilder;
+
+    #[test]
+    fn tile_urls_contain_tile_index() {
+        let index = TileIndex::new(3, 5, 7);
+        for basemap in Basemap::ALL {
+            let url = basemap.tile_url(&index);
+            assert!(url.starts_with("https://"), "{basemap:?}: {url}");
+            for part in ["/7/", "3", "5"] {
+                assert!(url.contains(part), "{basemap:?}: {url}");
+            }
+        }
+    }
+
+    #[test]
+    fn layer_schema_includes_max_z_level() {
+        for basemap in Basemap::ALL {
+            let max_z = basemap.max_z_level();
+            let layers = [
+                RasterTileLayer::from_basemap(basemap),
+                RasterTileLayerBuilder::new_basemap(basemap).build().unwrap(),
+            ];
+            for layer in layers {
+                let schema = layer.tile_schema();
+                assert!(schema.lod_resolution(max_z).is_some(), "{basemap:?}");
+                assert!(schema.lod_resolution(max_z + 1).is_none(), "{basemap:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn layer_loader_requests_tiles_of_max_z_level() {
+        let loader = Basemap::OpenStreetMap.tile_loader();
+        assert_eq!(
+            loader.tile_url(&TileIndex::new(1, 2, 19)),
+            "https://tile.openstreetmap.org/19/1/2.png"
+        );
+    }
+}
@@ -1,11 +1,15 @@
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use bytes::Bytes;
 
-use super::{RasterTileLayer, RasterTileLoader, RestTileLoader};
+use super::{Basemap, DynamicUrlTileLoader, RasterTileLayer, RasterTileLoader, RestTileLoader};
 use crate::error::GalileoError;
 use crate::layer::attribution::Attribution;
-use crate::layer::data_provider::{FileCacheController, PersistentCacheController, UrlSource};
+use crate::layer::data_provider::{
+    FileCacheController, PersistentCacheController, RetryPolicy, UrlSource,
+};
+use crate::render::ImageSampling;
 use crate::tile_schema::TileIndex;
 use crate::{Messenger, TileSchema};
 
@@ -30,12 +34,24 @@ pub struct RasterTileLayerBuilder {
     tile_schema: Option<TileSchema>,
     messenger: Option<Box<dyn Messenger>>,
     cache: CacheType,
+    cache_ttl: Option<Duration>,
     offline_mode: bool,
+    retry_policy: Option<RetryPolicy>,
+    headers: Vec<(String, String)>,
+    timeout: Option<Duration>,
+    max_concurrent_requests: Option<usize>,
+    sampling: ImageSampling,
+    subdomains: Vec<String>,
+    pixel_ratio: Option<f32>,
+    high_dpi_suffix: Option<String>,
+    zoom_range: Option<(u32, u32)>,
+    overzoom: Option<u32>,
     attribution: Option<Attribution>,
 }
 
 enum LoaderType {
     Rest(Box<dyn UrlSource<TileIndex>>),
+    DynamicUrl(Box<DynamicUrlTileLoader>),
     Custom(Box<dyn RasterTileLoader>),
 }
 
@@ -66,7 +82,18 @@ impl RasterTileLayerBuilder {
             tile_schema: None,
             messenger: None,
             cache: CacheType::None,
+            cache_ttl: None,
             offline_mode: false,
+            retry_policy: None,
+            headers: Vec::new(),
+            timeout: None,
+            max_concurrent_requests: None,
+            sampling: ImageSampling::default(),
+            subdomains: Vec::new(),
+            pixel_ratio: None,
+            high_dpi_suffix: None,
+            zoom_range: None,
+            overzoom: None,
             attribution: None,
         }
     }
@@ -94,7 +121,18 @@ impl RasterTileLayerBuilder {
             tile_schema: None,
             messenger: None,
             cache: CacheType::None,
+            cache_ttl: None,
             offline_mode: false,
+            retry_policy: None,
+            headers: Vec::new(),
+            timeout: None,
+            max_concurrent_requests: None,
+            sampling: ImageSampling::default(),
+            subdomains: Vec::new(),
+            pixel_ratio: None,
+            high_dpi_suffix: None,
+            zoom_range: None,
+            overzoom: None,
             attribution: Some(Attribution::new(
                 "© OpenStreetMap contributors".to_string(),
                 Some("https://www.openstreetmap.org/copyright".to_string()),
@@ -102,6 +140,43 @@ impl RasterTileLayerBuilder {
         }
     }
 
+    /// Initializes a builder for a raster tile layer with one of the common [basemaps](Basemap).
+    ///
+    /// The builder is preconfigured with the URL pattern, attribution and tile schema of the
+    /// basemap.
+    ///
+    /// ```
+    /// use galileo::layer::raster_tile_layer::{Basemap, RasterTileLayerBuilder};
+    ///
+    /// let layer = RasterTileLayerBuilder::new_basemap(Basemap::CartoDark)
+    ///     .with_file_cache("target")
+    ///     .build()?;
+    /// # Ok::<(), galileo::error::GalileoError>(())
+    /// ```
+    pub fn new_basemap(basemap: Basemap) -> Self {
+        Self {
+            loader_type: LoaderType::Rest(Box::new(move |index: &TileIndex| {
+                basemap.tile_url(index)
+            })),
+            tile_schema: Some(basemap.tile_schema()),
+            messenger: None,
+            cache: CacheType::None,
+            cache_ttl: None,
+            offline_mode: false,
+            retry_policy: None,
+            headers: Vec::new(),
+            timeout: None,
+            max_concurrent_requests: None,
+            sampling: ImageSampling::default(),
+            subdomains: Vec::new(),
+            pixel_ratio: None,
+            high_dpi_suffix: None,
+            zoom_range: None,
+            overzoom: None,
+            attribution: Some(basemap.attribution()),
+        }
+    }
+
     /// Initializes a builder for a lyer with the given tile loader.
     ///
     /// ```
@@ -127,7 +202,18 @@ impl RasterTileLayerBuilder {
             tile_schema: None,
             messenger: None,
             cache: CacheType::None,
+            cache_ttl: None,
             offline_mode: false,
+            retry_policy: None,
+            headers: Vec::new(),
+            timeout: None,
+            max_concurrent_requests: None,
+            sampling: ImageSampling::default(),
+            subdomains: Vec::new(),
+            pixel_ratio: None,
+            high_dpi_suffix: None,
+            zoom_range: None,
+            overzoom: None,
             attribution: None,
         }
     }
@@ -149,15 +235,24 @@ impl RasterTileLayerBuilder {
     /// # Ok::<(), galileo::error::GalileoError>(())
     /// ```
     pub fn new_dynamic_url(url_template: impl Into<String>) -> Self {
-        use super::DynamicUrlTileLoader;
-
         let loader = DynamicUrlTileLoader::new(url_template, None, false);
         Self {
-            loader_type: LoaderType::Custom(Box::new(loader)),
+            loader_type: LoaderType::DynamicUrl(Box::new(loader)),
             tile_schema: None,
             messenger: None,
             cache: CacheType::None,
+            cache_ttl: None,
             offline_mode: false,
+            retry_policy: None,
+            headers: Vec::new(),
+            timeout: None,
+            max_concurrent_requests: None,
+            sampling: ImageSampling::default(),
+            subdomains: Vec::new(),
+            pixel_ratio: None,
+            high_dpi_suffix: None,
+            zoom_range: None,
+            overzoom: None,
             attribution: None,
         }
     }
@@ -181,15 +276,24 @@ impl RasterTileLayerBuilder {
         url_template: impl Into<String>,
         cache: Option<Box<dyn PersistentCacheController<str, Bytes>>>,
     ) -> Self {
-        use super::DynamicUrlTileLoader;
-
         let loader = DynamicUrlTileLoader::new(url_template, cache, false);
         Self {
-            loader_type: LoaderType::Custom(Box::new(loader)),
+            loader_type: LoaderType::DynamicUrl(Box::new(loader)),
             tile_schema: None,
             messenger: None,
             cache: CacheType::None,
+            cache_ttl: None,
             offline_mode: false,
+            retry_policy: None,
+            headers: Vec::new(),
+            timeout: None,
+            max_concurrent_requests: None,
+            sampling: ImageSampling::default(),
+            subdomains: Vec::new(),
+            pixel_ratio: None,
+            high_dpi_suffix: None,
+            zoom_range: None,
+            overzoom: None,
             attribution: None,
         }
     }
@@ -239,6 +343,37 @@ impl RasterTileLayerBuilder {
         self
     }
 
+    /// Sets the time-to-live of the tiles in the file cache set by
+    /// [`RasterTileLayerBuilder::with_file_cache()`]. Tiles cached more than `ttl` ago are loaded again,
+    /// which is useful for layers with frequently changing data like weather or traffic. `None`
+    /// keeps the cached tiles forever, which is the default and suits static basemaps.
+    ///
+    /// Only the file cache created by the builder is affected. Custom cache controllers set with
+    /// [`RasterTileLayerBuilder::with_cache_controller()`] are used as is, so in a cache stack every
+    /// controller must be configured with its own time-to-live:
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use galileo::layer::data_provider::{FileCacheController, MemoryCacheController};
+    /// use galileo::layer::raster_tile_layer::RasterTileLayerBuilder;
+    ///
+    /// let ttl = Some(Duration::from_secs(600));
+    /// let cache = MemoryCacheController::with_max_entries(1000)
+    ///     .with_ttl(ttl)
+    ///     .in_front_of(FileCacheController::new("target")?.with_ttl(ttl));
+    /// let layer = RasterTileLayerBuilder::new_rest(|index| {
+    ///     format!("https://tiles.example.com/{}/{}/{}", index.z, index.x, index.y)
+    /// })
+    /// .with_cache_controller(cache)
+    /// .build()?;
+    /// # Ok::<(), galileo::error::GalileoError>(())
+    /// ```
+    pub fn with_cache_ttl(mut self, ttl: Option<Duration>) -> Self {
+        self.cache_ttl = ttl;
+        self
+    }
+
     /// Sets the file cache if available on the target platform, or skips it otherwise.
     ///
     /// Currently it only checks if the target architecture is "wasm32".
@@ -331,6 +466,144 @@ impl RasterTileLayerBuilder {
         self
     }
 
+    /// Sets the policy of repeating tile requests that failed because of network errors.
+    ///
+    /// By default failed requests are not repeated.
+    ///
+    /// Cannot be used with custom tile loader given by
+    /// [`RasterTileLayerBuilder::new_with_loader()`] method as the loader must have already be
+    /// created with the retry policy. So in this case building will also return an error.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use galileo::layer::data_provider::RetryPolicy;
+    /// use galileo::layer::raster_tile_layer::RasterTileLayerBuilder;
+    ///
+    /// let layer = RasterTileLayerBuilder::new_osm()
+    ///     .with_retry_policy(RetryPolicy::new(3, Duration::from_millis(500)).with_jitter(0.2))
+    ///     .build()?;
+    /// # Ok::<(), galileo::error::GalileoError>(())
+    /// ```
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
+    /// Adds an HTTP header that is sent with every tile request, e.g. an API key required by the
+    /// tile server. Can be called several times to add multiple headers.
+    ///
+    /// Cannot be used with custom tile loader given by
+    /// [`RasterTileLayerBuilder::new_with_loader()`] method as the loader must have already be
+    /// created with the headers. So in this case building will also return an error.
+    ///
+    /// ```
+    /// use galileo::layer::raster_tile_layer::RasterTileLayerBuilder;
+    ///
+    /// let layer = RasterTileLayerBuilder::new_dynamic_url("https://tiles.example.com/{z}/{x}/{y}.png")
+    ///     .with_header("Authorization", "Bearer <token>")
+    ///     .build()?;
+    /// # Ok::<(), galileo::error::GalileoError>(())
+    /// ```
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Sets the maximum time to wait for a response to a tile request. Tiles that are not loaded
+    /// in time are treated as failed to load.
+    ///
+    /// By default there is no timeout.
+    ///
+    /// Cannot be used with custom tile loader given by
+    /// [`RasterTileLayerBuilder::new_with_loader()`] method as the loader must have already be
+    /// created with the timeout. So in this case building will also return an error.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use galileo::layer::raster_tile_layer::RasterTileLayerBuilder;
+    ///
+    /// let layer = RasterTileLayerBuilder::new_osm()
+    ///     .with_timeout(Duration::from_secs(10))
+    ///     .build()?;
+    /// # Ok::<(), galileo::error::GalileoError>(())
+    /// ```
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Limits the number of tile requests sent to the server at the same time, e.g. to follow the
+    /// usage policy of the tile server.
+    ///
+    /// By default the number of requests is not limited.
+    ///
+    /// Cannot be used with custom tile loader given by
+    /// [`RasterTileLayerBuilder::new_with_loader()`] method as the loader must have already be
+    /// created with the limit. So in this case building will also return an error.
+    ///
+    /// ```
+    /// use galileo::layer::raster_tile_layer::RasterTileLayerBuilder;
+    ///
+    /// let layer = RasterTileLayerBuilder::new_osm()
+    ///     .with_max_concurrent_requests(2)
+    ///     .build()?;
+    /// # Ok::<(), galileo::error::GalileoError>(())
+    /// ```
+    pub fn with_max_concurrent_requests(mut self, max_requests: usize) -> Self {
+        self.max_concurrent_requests = Some(max_requests);
+        self
+    }
+
+    /// Limits the z-levels of the tiles requested from the server, e.g. for a self-hosted tileset
+    /// that covers only some zoom levels.
+    ///
+    /// Tiles outside of the range are never requested. When the map is zoomed in beyond `max_zoom`,
+    /// the tiles of `max_zoom` are stretched, and when it is zoomed out beyond `min_zoom`, the tiles
+    /// of `min_zoom` are shown. See [`TileSchema::with_zoom_range()`].
+    ///
+    /// Building the layer returns an error if the tile schema has no levels in the range.
+    ///
+    /// ```
+    /// use galileo::layer::raster_tile_layer::RasterTileLayerBuilder;
+    ///
+    /// let layer = RasterTileLayerBuilder::new_dynamic_url("https://tiles.example.com/{z}/{x}/{y}.png")
+    ///     .with_zoom_range(0, 14)
+    ///     .build()?;
+    ///
+    /// assert_eq!(layer.tile_schema().lod_resolution(15), None);
+    /// # Ok::<(), galileo::error::GalileoError>(())
+    /// ```
+    pub fn with_zoom_range(mut self, min_zoom: u32, max_zoom: u32) -> Self {
+        self.zoom_range = Some((min_zoom, max_zoom));
+        self
+    }
+
+    /// Enables overzoom above the `max_zoom` level, e.g. for a tileset that does not have tiles
+    /// for the highest levels of the tile schema.
+    ///
+    /// Unlike [`with_zoom_range`](Self::with_zoom_range), the layer keeps all levels of the tile
+    /// schema. The tiles above `max_zoom` are never requested from the server. Instead, the
+    /// corresponding part of the ancestor tile at `max_zoom` is cut out and scaled up to the size
+    /// of the tile.
+    ///
+    /// ```
+    /// use galileo::layer::raster_tile_layer::RasterTileLayerBuilder;
+    ///
+    /// let layer = RasterTileLayerBuilder::new_osm()
+    ///     .with_overzoom(16)
+    ///     .build()?;
+    ///
+    /// assert_eq!(layer.overzoom(), Some(16));
+    /// assert!(layer.tile_schema().lod_resolution(17).is_some());
+    /// # Ok::<(), galileo::error::GalileoError>(())
+    /// ```
+    pub fn with_overzoom(mut self, max_zoom: u32) -> Self {
+        self.overzoom = Some(max_zoom);
+        self
+    }
+
     /// Sets the layer's tile schema.
     ///
     /// Defaults to `TileSchema::web(18)`.
@@ -373,6 +646,85 @@ impl RasterTileLayerBuilder {
         self
     }
 
+    /// Sets the subdomains substituted for the `{s}` placeholder of the dynamic URL template.
+    ///
+    /// Can only be used with the layers created by [`RasterTileLayerBuilder::new_dynamic_url()`] or
+    /// [`RasterTileLayerBuilder::new_dynamic_url_with_cache()`]. Building the layer returns an error if
+    /// the subdomains are set for other layers, or if the URL template contains `{s}` placeholder
+    /// but the subdomains are not set.
+    ///
+    /// ```
+    /// use galileo::layer::raster_tile_layer::RasterTileLayerBuilder;
+    ///
+    /// let layer = RasterTileLayerBuilder::new_dynamic_url("https://{s}.tile.openstreetmap.org/{z}/{x}/{y}.png")
+    ///     .with_subdomains(["a", "b", "c"])
+    ///     .build()?;
+    /// # Ok::<(), galileo::error::GalileoError>(())
+    /// ```
+    pub fn with_subdomains(
+        mut self,
+        subdomains: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.subdomains = subdomains.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets the number of physical pixels in a logical pixel of the display, e.g. `2.0` for a
+    /// retina display. On high-DPI displays the high-DPI suffix is substituted for the `{r}`
+    /// placeholder of the dynamic URL template, so that the tiles of double resolution are
+    /// requested. See [`DynamicUrlTileLoader::set_pixel_ratio()`].
+    ///
+    /// Can only be used with the layers created by [`RasterTileLayerBuilder::new_dynamic_url()`] or
+    /// [`RasterTileLayerBuilder::new_dynamic_url_with_cache()`]. Building the layer returns an error if
+    /// it is set for other layers.
+    ///
+    /// ```
+    /// use galileo::layer::raster_tile_layer::RasterTileLayerBuilder;
+    ///
+    /// let layer = RasterTileLayerBuilder::new_dynamic_url("https://tiles.example.com/{z}/{x}/{y}{r}.png")
+    ///     .with_pixel_ratio(2.0)
+    ///     .build()?;
+    /// # Ok::<(), galileo::error::GalileoError>(())
+    /// ```
+    pub fn with_pixel_ratio(mut self, pixel_ratio: f32) -> Self {
+        self.pixel_ratio = Some(pixel_ratio);
+        self
+    }
+
+    /// Sets the suffix substituted for the `{r}` placeholder of the dynamic URL template on
+    /// high-DPI displays, for the servers that do not use the default `@2x` suffix.
+    ///
+    /// Has the same restrictions as [`RasterTileLayerBuilder::with_pixel_ratio()`].
+    pub fn with_high_dpi_suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.high_dpi_suffix = Some(suffix.into());
+        self
+    }
+
+    /// Sets the way the tile images are sampled when they are scaled.
+    ///
+    /// Defaults to [`ImageSampling::Linear`], which gives smooth images. Use [`ImageSampling::Nearest`] for
+    /// classified rasters (e.g. land cover), where colors of neighbouring classes must not be blended.
+    ///
+    /// ```
+    /// use galileo::layer::raster_tile_layer::RasterTileLayerBuilder;
+    /// use galileo::render::ImageSampling;
+    ///
+    /// let layer = RasterTileLayerBuilder::new_rest(
+    ///     |index| {
+    ///         format!(
+    ///             "https://tile.openstreetmap.org/{}/{}/{}.png",
+    ///             index.z, index.x, index.y
+    ///         )
+    ///     })
+    ///     .with_sampling(ImageSampling::Nearest)
+    ///     .build()?;
+    /// # Ok::<(), galileo::error::GalileoError>(())
+    /// ```
+    pub fn with_sampling(mut self, sampling: ImageSampling) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
     /// Sets the custom attribution with the given text and URL.
     /// The attribution consists of a text
     /// description and an optional URL where more information or the source can be found.
@@ -391,15 +743,32 @@ impl RasterTileLayerBuilder {
             tile_schema,
             messenger,
             cache,
+            cache_ttl,
             offline_mode,
+            retry_policy,
+            headers,
+            timeout,
+            max_concurrent_requests,
+            sampling,
+            subdomains,
+            pixel_ratio,
+            high_dpi_suffix,
+            zoom_range,
+            overzoom,
             attribution,
         } = self;
 
         let tile_schema = tile_schema.unwrap_or_else(|| TileSchema::web(18));
+        let tile_schema = match zoom_range {
+            Some((min_zoom, max_zoom)) => tile_schema.with_zoom_range(min_zoom, max_zoom)?,
+            None => tile_schema,
+        };
 
         let cache_controller: Option<Box<dyn PersistentCacheController<str, Bytes>>> = match cache {
             CacheType::None => None,
-            CacheType::File(path_buf) => Some(Box::new(FileCacheController::new(&path_buf)?)),
+            CacheType::File(path_buf) => Some(Box::new(
+                FileCacheController::new(&path_buf)?.with_ttl(cache_ttl),
+            )),
             CacheType::Custom(persistent_cache_controller) => Some(persistent_cache_controller),
         };
 
@@ -409,12 +778,93 @@ impl RasterTileLayerBuilder {
             ));
         }
 
+        let is_dynamic_url = matches!(provider_type, LoaderType::DynamicUrl(_));
+        if !subdomains.is_empty() && !is_dynamic_url {
+            return Err(GalileoError::Configuration(
+                "subdomains can only be used with a dynamic URL tile loader".into(),
+            ));
+        }
+
+        if (pixel_ratio.is_some() || high_dpi_suffix.is_some())
+            && !matches!(provider_type, LoaderType::DynamicUrl(_))
+        {
+            return Err(GalileoError::Configuration(
+                "high DPI settings can only be used with a dynamic URL tile loader".into(),
+            ));
+        }
+
         let provider: Box<dyn RasterTileLoader> = match provider_type {
-            LoaderType::Rest(url_source) => Box::new(RestTileLoader::new(
-                url_source,
-                cache_controller,
-                offline_mode,
-            )),
+            LoaderType::Rest(url_source) => {
+                let loader = RestTileLoader::new(url_source, cache_controller, offline_mode);
+                let loader = headers.into_iter().fold(loader, |loader, (name, value)| {
+                    loader.with_header(name, value)
+                });
+                let loader = match retry_policy {
+                    Some(retry_policy) => loader.with_retry_policy(retry_policy),
+                    None => loader,
+                };
+                let loader = match timeout {
+                    Some(timeout) => loader.with_timeout(timeout),
+                    None => loader,
+                };
+                let loader = match max_concurrent_requests {
+                    Some(max_requests) => loader.with_max_concurrent_requests(max_requests),
+                    None => loader,
+                };
+                let loader = match zoom_range {
+                    Some((min_zoom, max_zoom)) => loader.with_zoom_range(min_zoom, max_zoom),
+                    None => loader,
+                };
+
+                Box::new(loader)
+            }
+            LoaderType::DynamicUrl(loader) => {
+                if cache_controller.is_some() {
+                    return Err(GalileoError::Configuration(
+                        "dynamic URL tile loader (use `new_dynamic_url_with_cache` to set its cache) \
+                         cannot be used together with a cache controller"
+                            .into(),
+                    ));
+                }
+
+                let loader = *loader;
+
+                loader.validate_url_template()?;
+                if !subdomains.is_empty() {
+                    loader.set_subdomains(subdomains);
+                }
+                loader.validate_subdomains()?;
+                if let Some(pixel_ratio) = pixel_ratio {
+                    loader.set_pixel_ratio(pixel_ratio);
+                }
+                let loader = match high_dpi_suffix {
+                    Some(suffix) => loader.with_high_dpi_suffix(suffix),
+                    None => loader,
+                };
+                let loader = loader.with_tile_schema(tile_schema.clone());
+
+                let loader = headers.into_iter().fold(loader, |loader, (name, value)| {
+                    loader.with_header(name, value)
+                });
+                let loader = match retry_policy {
+                    Some(retry_policy) => loader.with_retry_policy(retry_policy),
+                    None => loader,
+                };
+                let loader = match timeout {
+                    Some(timeout) => loader.with_timeout(timeout),
+                    None => loader,
+                };
+                let loader = match max_concurrent_requests {
+                    Some(max_requests) => loader.with_max_concurrent_requests(max_requests),
+                    None => loader,
+                };
+                let loader = match zoom_range {
+                    Some((min_zoom, max_zoom)) => loader.with_zoom_range(min_zoom, max_zoom),
+                    None => loader,
+                };
+
+                Box::new(loader)
+            }
             LoaderType::Custom(raster_tile_provider) => {
                 if cache_controller.is_some() {
                     return Err(GalileoError::Configuration(
@@ -423,6 +873,16 @@ impl RasterTileLayerBuilder {
                     ));
                 }
 
+                if retry_policy.is_some()
+                    || !headers.is_empty()
+                    || timeout.is_some()
+                    || max_concurrent_requests.is_some()
+                {
+                    return Err(GalileoError::Configuration(
+                        "custom tile provider cannot be used together with request settings".into(),
+                    ));
+                }
+
                 raster_tile_provider
             }
         };
@@ -432,6 +892,8 @@ impl RasterTileLayerBuilder {
             tile_schema,
             messenger,
             attribution,
+            sampling,
+            overzoom,
         ))
     }
 }
@@ -507,6 +969,44 @@ mod tests {
         assert_compact_debug_snapshot!(result, @r#"Err(Configuration("custom tile provider cannot be used together with a cache controller"))"#);
     }
 
+    #[test]
+    fn request_settings_fail_build_if_custom_provider() {
+        let provider = RestTileLoader::new(|_| unimplemented!(), None, false);
+        let result = RasterTileLayerBuilder::new_with_loader(provider)
+            .with_header("X-Api-Key", "secret")
+            .build();
+
+        assert!(result.is_err());
+        assert_compact_debug_snapshot!(result, @r#"Err(Configuration("custom tile provider cannot be used together with request settings"))"#);
+    }
+
+    #[test]
+    fn request_settings_are_accepted_by_dynamic_url_loader() {
+        let result =
+            RasterTileLayerBuilder::new_dynamic_url("https://tiles.example.com/{z}/{x}/{y}.png")
+                .with_header("X-Api-Key", "secret")
+                .with_retry_policy(RetryPolicy::new(3, Duration::from_millis(100)))
+                .with_timeout(Duration::from_secs(5))
+                .with_max_concurrent_requests(2)
+                .build();
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn with_zoom_range_limits_tile_schema() {
+        let layer = RasterTileLayerBuilder::new_osm()
+            .with_zoom_range(2, 10)
+            .build()
+            .unwrap();
+        assert_eq!(layer.tile_schema().lods.len(), 9);
+
+        let result = RasterTileLayerBuilder::new_osm()
+            .with_zoom_range(30, 40)
+            .build();
+        assert!(result.is_err());
+    }
+
     #[test]
     fn with_offline_mode_does_not_work_without_cache() {
         let result = RasterTileLayerBuilder::new_rest(|_| unimplemented!())
@@ -517,6 +1017,46 @@ mod tests {
         assert_compact_debug_snapshot!(result, @r#"Err(Configuration("offline mode cannot be used without cache"))"#);
     }
 
+    #[test]
+    fn with_subdomains_fails_build_if_not_dynamic_url() {
+        let result = RasterTileLayerBuilder::new_rest(|_| unimplemented!())
+            .with_subdomains(["a", "b"])
+            .build();
+
+        assert!(result.is_err());
+        assert_compact_debug_snapshot!(result, @r#"Err(Configuration("subdomains can only be used with a dynamic URL tile loader"))"#);
+    }
+
+    #[test]
+    fn with_pixel_ratio_fails_build_if_not_dynamic_url() {
+        let result = RasterTileLayerBuilder::new_rest(|_| unimplemented!())
+            .with_pixel_ratio(2.0)
+            .build();
+
+        assert!(result.is_err());
+        assert_compact_debug_snapshot!(result, @r#"Err(Configuration("high DPI settings can only be used with a dynamic URL tile loader"))"#);
+    }
+
+    #[test]
+    fn invalid_url_template_fails_build() {
+        let result =
+            RasterTileLayerBuilder::new_dynamic_url("https://tiles.example.com/{z}/{x}.png")
+                .build();
+        assert!(matches!(result, Err(GalileoError::Configuration(_))));
+    }
+
+    #[test]
+    fn subdomain_placeholder_requires_subdomains() {
+        let template = "https://{s}.tile.openstreetmap.org/{z}/{x}/{y}.png";
+        assert!(RasterTileLayerBuilder::new_dynamic_url(template)
+            .build()
+            .is_err());
+        assert!(RasterTileLayerBuilder::new_dynamic_url(template)
+            .with_subdomains(["a", "b", "c"])
+            .build()
+            .is_ok());
+    }
+
     #[test]
     fn default_tile_schema() {
         let layer = RasterTileLayerBuilder::new_rest(|_| unimplemented!())
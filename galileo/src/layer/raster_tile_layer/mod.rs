@@ -3,6 +3,7 @@
 use std::any::Any;
 use std::sync::Arc;
 
+use galileo_types::cartesian::Rect;
 use provider::RasterTileProvider;
 use web_time::Duration;
 
@@ -10,16 +11,21 @@ use super::tiles::TilesContainer;
 use super::Layer;
 use crate::layer::attribution::Attribution;
 use crate::messenger::Messenger;
-use crate::render::{Canvas, RenderOptions};
+use crate::render::{Canvas, ImageSampling, RenderOptions};
 use crate::tile_schema::{TileIndex, TileSchema};
 use crate::view::MapView;
 
 mod provider;
+#[cfg(all(feature = "mbtiles", not(target_arch = "wasm32")))]
+pub use provider::MbTilesRasterLoader;
 pub use provider::{DynamicUrlTileLoader, RasterTileLoader, RestTileLoader};
 
 mod builder;
 pub use builder::RasterTileLayerBuilder;
 
+mod basemap;
+pub use basemap::Basemap;
+
 /// Raster tile layers load prerendered tile sets using [tile loader](RasterTileLoader) and render them to the map.
 pub struct RasterTileLayer {
     tile_loader: Arc<dyn RasterTileLoader>,
@@ -28,6 +34,7 @@ pub struct RasterTileLayer {
     fade_in_duration: Duration,
     messenger: Option<Arc<dyn Messenger>>,
     attribution: Option<Attribution>,
+    overzoom: Option<u32>,
 }
 
 impl std::fmt::Debug for RasterTileLayer {
@@ -35,6 +42,7 @@ impl std::fmt::Debug for RasterTileLayer {
         f.debug_struct("RasterTileLayer")
             .field("tile_schema", &self.tile_schema)
             .field("fade_in_duration", &self.fade_in_duration)
+            .field("overzoom", &self.overzoom)
             .finish()
     }
 }
@@ -50,12 +58,13 @@ impl RasterTileLayer {
             tile_loader: Arc::new(tile_loader),
             tile_container: Arc::new(TilesContainer::new(
                 tile_schema.clone(),
-                RasterTileProvider::new(tile_schema.clone()),
+                RasterTileProvider::new(tile_schema.clone(), ImageSampling::default()),
             )),
             tile_schema,
             fade_in_duration: Duration::from_millis(300),
             messenger,
             attribution: None,
+            overzoom: None,
         }
     }
 
@@ -64,17 +73,20 @@ impl RasterTileLayer {
         tile_schema: TileSchema,
         messenger: Option<Box<dyn Messenger>>,
         attribution: Option<Attribution>,
+        sampling: ImageSampling,
+        overzoom: Option<u32>,
     ) -> Self {
         Self {
             tile_loader: tile_loader.into(),
             tile_container: Arc::new(TilesContainer::new(
                 tile_schema.clone(),
-                RasterTileProvider::new(tile_schema.clone()),
+                RasterTileProvider::new(tile_schema.clone(), sampling),
             )),
             tile_schema,
             fade_in_duration: Duration::from_millis(300),
             messenger: messenger.map(|m| m.into()),
             attribution,
+            overzoom,
         }
     }
 
@@ -83,12 +95,17 @@ impl RasterTileLayer {
         self.fade_in_duration = duration;
     }
 
+    /// Returns the z-level above which the tiles are cut out of the tiles of this level instead of
+    /// being loaded. See [`RasterTileLayerBuilder::with_overzoom`].
+    pub fn overzoom(&self) -> Option<u32> {
+        self.overzoom
+    }
+
     fn update_displayed_tiles(&self, view: &MapView, canvas: &dyn Canvas) {
-        let Some(tile_iter) = self.tile_schema.iter_tiles(view) else {
+        let Some(needed_indices) = self.tile_container.visible_tiles(view) else {
             return;
         };
 
-        let needed_indices: Vec<_> = tile_iter.collect();
         self.tile_container
             .tile_provider
             .pack_tiles(&needed_indices, canvas);
@@ -108,17 +125,30 @@ impl RasterTileLayer {
         tile_loader: Arc<dyn RasterTileLoader>,
         tiles: Arc<TilesContainer<(), RasterTileProvider>>,
         messenger: Option<Arc<dyn Messenger>>,
+        overzoom: Option<u32>,
     ) {
         if tiles.tile_provider.set_loading(index) {
             // Already loading
             return;
         }
 
-        let load_result = tile_loader.load(index).await;
+        let overzoom_source =
+            overzoom.and_then(|max_zoom| tiles.tile_provider.overzoom_source(index, max_zoom));
+        let load_result = match overzoom_source {
+            Some((source_index, source)) => tiles
+                .tile_provider
+                .load_overzoom_source(source_index, &*tile_loader)
+                .await
+                .map(|image| (image, source)),
+            None => tile_loader
+                .load(index)
+                .await
+                .map(|image| (Arc::new(image), Rect::new(0.0, 0.0, 1.0, 1.0))),
+        };
 
         match load_result {
-            Ok(decoded_image) => {
-                tiles.tile_provider.set_loaded(index, decoded_image);
+            Ok((decoded_image, source)) => {
+                tiles.tile_provider.set_loaded(index, decoded_image, source);
 
                 if let Some(messenger) = messenger {
                     messenger.request_redraw();
@@ -137,11 +167,51 @@ impl RasterTileLayer {
             for index in iter {
                 let tile_provider = self.tile_loader.clone();
                 let messenger = self.messenger.clone();
-                Self::load_tile(index, tile_provider, self.tile_container.clone(), messenger).await;
+                Self::load_tile(
+                    index,
+                    tile_provider,
+                    self.tile_container.clone(),
+                    messenger,
+                    self.overzoom,
+                )
+                .await;
             }
         }
     }
 
+    /// Loads the tiles with the given indices in the background without displaying them, e.g. to
+    /// fill the persistent cache of the tile loader with the tiles of the next zoom level or along
+    /// a route.
+    ///
+    /// The tiles are requested through the tile loader, so its offline mode applies. Failed loads
+    /// are only logged. If the layer is [overzoomed](RasterTileLayerBuilder::with_overzoom), the
+    /// tiles the given ones are cut out of are loaded instead.
+    pub fn prefetch(&self, indices: impl IntoIterator<Item = TileIndex>) {
+        let mut indices: Vec<_> = indices
+            .into_iter()
+            .map(|index| {
+                self.overzoom
+                    .and_then(|max_zoom| {
+                        self.tile_container
+                            .tile_provider
+                            .overzoom_source(index, max_zoom)
+                    })
+                    .map_or(index, |(source_index, _)| source_index)
+            })
+            .collect();
+        indices.dedup();
+
+        for index in indices {
+            let tile_loader = self.tile_loader.clone();
+            crate::async_runtime::spawn(async move {
+                match tile_loader.load(index).await {
+                    Ok(_) => log::trace!("Prefetched raster tile {index:?}"),
+                    Err(err) => log::debug!("Failed to prefetch raster tile {index:?}: {err}"),
+                }
+            });
+        }
+    }
+
     /// Returns tile schema of the layer.
     pub fn tile_schema(&self) -> &TileSchema {
         &self.tile_schema
@@ -167,8 +237,9 @@ impl Layer for RasterTileLayer {
                 let tile_provider = self.tile_loader.clone();
                 let container = self.tile_container.clone();
                 let messenger = self.messenger.clone();
+                let overzoom = self.overzoom;
                 crate::async_runtime::spawn(async move {
-                    Self::load_tile(index, tile_provider, container, messenger).await;
+                    Self::load_tile(index, tile_provider, container, messenger, overzoom).await;
                 });
             }
         }
@@ -193,4 +264,12 @@ impl Layer for RasterTileLayer {
     fn attribution(&self) -> Option<Attribution> {
         self.attribution.clone()
     }
+
+    fn is_ready(&self, view: &MapView) -> bool {
+        let Some(mut indices) = self.tile_schema.iter_tiles(view) else {
+            return true;
+        };
+
+        indices.all(|index| self.tile_container.tile_provider.is_settled(index))
+    }
 }
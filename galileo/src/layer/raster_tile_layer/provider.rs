@@ -1,19 +1,28 @@
+use std::ops::RangeInclusive;
 use std::sync::Arc;
+use std::time::Duration;
 
 use bytes::Bytes;
+use galileo_types::cartesian::Rect;
 use maybe_sync::{MaybeSend, MaybeSync};
 use parking_lot::Mutex;
 use quick_cache::sync::Cache;
 use quick_cache::GuardResult;
+use tokio::sync::OnceCell;
 
 use crate::decoded_image::DecodedImage;
 use crate::error::GalileoError;
-use crate::layer::data_provider::{PersistentCacheController, UrlSource};
+#[cfg(all(feature = "mbtiles", not(target_arch = "wasm32")))]
+use crate::layer::data_provider::MbTiles;
+use crate::layer::data_provider::{
+    expand_url_template, high_dpi_suffix, validate_subdomains, validate_url_template,
+    PersistentCacheController, RetryPolicy, TileFetcher, UrlSource, DEFAULT_HIGH_DPI_SUFFIX,
+};
 use crate::layer::tiles::TileProvider;
 use crate::platform::PlatformService;
 use crate::render::render_bundle::RenderBundle;
-use crate::render::{Canvas, ImagePaint, PackedBundle};
-use crate::tile_schema::TileIndex;
+use crate::render::{Canvas, ImagePaint, ImageSampling, PackedBundle};
+use crate::tile_schema::{TileIndex, VerticalDirection};
 use crate::TileSchema;
 
 /// Provider of tlies for a [`RusterTileLayer`](super::RasterTileLayer).
@@ -40,6 +49,9 @@ pub trait RasterTileLoader: MaybeSend + MaybeSync {
 /// load them from the source. Nevertheless, even in this case url source must be correct to
 /// identify the correct files to retrieve from the cache.
 ///
+/// Requests are sent with a [`TileFetcher`], so retries, headers, timeouts and request limits are
+/// configured the same way as for the vector tile loaders.
+///
 /// # Example
 ///
 /// ```no_run
@@ -63,8 +75,8 @@ pub trait RasterTileLoader: MaybeSend + MaybeSync {
 /// ```
 pub struct RestTileLoader {
     url_source: Box<dyn UrlSource<TileIndex>>,
-    cache: Option<Box<dyn PersistentCacheController<str, Bytes>>>,
-    offline_mode: bool,
+    fetcher: TileFetcher,
+    zoom_range: Option<RangeInclusive<u32>>,
 }
 
 impl RestTileLoader {
@@ -76,36 +88,80 @@ impl RestTileLoader {
     ) -> Self {
         Self {
             url_source: Box::new(url_source),
-            cache,
-            offline_mode,
+            fetcher: TileFetcher::new(cache, offline_mode),
+            zoom_range: None,
         }
     }
 
-    async fn download_tile(&self, index: TileIndex) -> Result<Bytes, GalileoError> {
-        let url = (self.url_source)(&index);
+    /// Switches the offline mode. In offline mode only the tiles from the cache are loaded, and no
+    /// requests are sent to the server.
+    pub fn set_offline_mode(&self, offline_mode: bool) {
+        self.fetcher.set_offline_mode(offline_mode);
+    }
 
-        if let Some(cache) = &self.cache {
-            if let Some(data) = cache.get(&url) {
-                return Ok(data);
-            }
-        }
+    /// Returns true if the loader is in offline mode.
+    pub fn is_offline_mode(&self) -> bool {
+        self.fetcher.is_offline_mode()
+    }
 
-        if self.offline_mode {
-            return Err(GalileoError::NotFound);
-        }
+    /// Sets the policy of repeating tile requests that failed because of network errors.
+    ///
+    /// By default failed requests are not repeated.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.fetcher = self.fetcher.with_retry_policy(retry_policy);
+        self
+    }
 
-        log::info!("Loading {url}");
-        let data = crate::platform::instance()
-            .load_bytes_from_url(&url)
-            .await?;
+    /// Adds an HTTP header that is sent with every tile request, e.g. an API key required by the
+    /// tile server.
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.fetcher = self.fetcher.with_header(name, value);
+        self
+    }
 
-        if let Some(cache) = &self.cache {
-            if let Err(error) = cache.insert(&url, &data) {
-                log::warn!("Failed to write persistent cache entry: {error:?}");
-            }
-        }
+    /// Sets the maximum time to wait for a response to a tile request. If the server does not
+    /// respond in time, the request fails with [`GalileoError::Timeout`].
+    ///
+    /// By default there is no timeout.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.fetcher = self.fetcher.with_timeout(timeout);
+        self
+    }
+
+    /// Limits the number of tile requests sent to the server at the same time. Tiles loaded from
+    /// the cache do not count towards the limit.
+    ///
+    /// By default the number of requests is not limited.
+    pub fn with_max_concurrent_requests(mut self, max_requests: usize) -> Self {
+        self.fetcher = self.fetcher.with_max_concurrent_requests(max_requests);
+        self
+    }
+
+    /// Enables revalidation of the cached tiles. Tiles that were received from the server more than
+    /// `max_age` ago are requested again. See [`TileFetcher::with_max_age`] for details.
+    ///
+    /// By default cached tiles are never revalidated.
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.fetcher = self.fetcher.with_max_age(max_age);
+        self
+    }
 
-        Ok(data)
+    /// Limits the z-levels of the tiles provided by the server. Tiles outside of the range fail
+    /// with [`GalileoError::NotFound`] without sending a request.
+    pub fn with_zoom_range(mut self, min_zoom: u32, max_zoom: u32) -> Self {
+        self.zoom_range = Some(min_zoom..=max_zoom);
+        self
+    }
+
+    /// URL of the tile with the given index.
+    pub(crate) fn tile_url(&self, index: &TileIndex) -> String {
+        (self.url_source)(index)
+    }
+
+    async fn download_tile(&self, index: TileIndex) -> Result<Bytes, GalileoError> {
+        check_zoom_range(self.zoom_range.as_ref(), index)?;
+        let url = self.tile_url(&index);
+        Ok(self.fetcher.fetch(&url).await?.bytes)
     }
 }
 
@@ -139,7 +195,7 @@ impl RasterTileLoader for RestTileLoader {
 /// );
 ///
 /// // Update the URL template and parameters
-/// loader.update_url_template("https://custom.tiles.com/{z}/{x}/{y}.png");
+/// loader.update_url_template("https://custom.tiles.com/{z}/{x}/{y}.png").expect("valid template");
 /// loader.update_parameters(vec![("api_key", "your_api_key"), ("style", "dark")]);
 ///
 /// # tokio_test::block_on(async {
@@ -149,31 +205,184 @@ impl RasterTileLoader for RestTileLoader {
 pub struct DynamicUrlTileLoader {
     url_template: Arc<parking_lot::RwLock<String>>,
     parameters: Arc<parking_lot::RwLock<Vec<(String, String)>>>,
-    cache: Option<Box<dyn PersistentCacheController<str, Bytes>>>,
-    offline_mode: bool,
+    subdomains: Arc<parking_lot::RwLock<Vec<String>>>,
+    pixel_ratio: Arc<parking_lot::RwLock<f32>>,
+    high_dpi_suffix: String,
+    tile_schema: Option<TileSchema>,
+    fetcher: TileFetcher,
+    zoom_range: Option<RangeInclusive<u32>>,
 }
 
 impl DynamicUrlTileLoader {
     /// Creates a new instance of the dynamic URL tile loader.
+    ///
+    /// An invalid URL template is only reported in the log, and no tiles can be loaded with it. Use
+    /// [`try_new`](Self::try_new) to get an error instead.
     pub fn new(
         url_template: impl Into<String>,
         cache: Option<Box<dyn PersistentCacheController<str, Bytes>>>,
         offline_mode: bool,
     ) -> Self {
+        let url_template = url_template.into();
+        if let Err(err) = validate_url_template(&url_template) {
+            log::warn!("{err}");
+        }
+
         Self {
-            url_template: Arc::new(parking_lot::RwLock::new(url_template.into())),
+            url_template: Arc::new(parking_lot::RwLock::new(url_template)),
             parameters: Arc::new(parking_lot::RwLock::new(Vec::new())),
-            cache,
-            offline_mode,
+            subdomains: Arc::new(parking_lot::RwLock::new(Vec::new())),
+            pixel_ratio: Arc::new(parking_lot::RwLock::new(1.0)),
+            high_dpi_suffix: DEFAULT_HIGH_DPI_SUFFIX.to_string(),
+            tile_schema: None,
+            fetcher: TileFetcher::new(cache, offline_mode),
+            zoom_range: None,
         }
     }
 
+    /// Creates a new instance of the dynamic URL tile loader, returning an error if the URL
+    /// template is invalid (see [`validate_url_template`]).
+    pub fn try_new(
+        url_template: impl Into<String>,
+        cache: Option<Box<dyn PersistentCacheController<str, Bytes>>>,
+        offline_mode: bool,
+    ) -> Result<Self, GalileoError> {
+        let url_template = url_template.into();
+        validate_url_template(&url_template)?;
+
+        Ok(Self::new(url_template, cache, offline_mode))
+    }
+
+    /// Switches the offline mode. In offline mode only the tiles from the cache are loaded, and no
+    /// requests are sent to the server.
+    pub fn set_offline_mode(&self, offline_mode: bool) {
+        self.fetcher.set_offline_mode(offline_mode);
+    }
+
+    /// Returns true if the loader is in offline mode.
+    pub fn is_offline_mode(&self) -> bool {
+        self.fetcher.is_offline_mode()
+    }
+
+    /// Sets the policy of repeating tile requests that failed because of network errors.
+    ///
+    /// By default failed requests are not repeated.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.fetcher = self.fetcher.with_retry_policy(retry_policy);
+        self
+    }
+
+    /// Adds an HTTP header that is sent with every tile request, e.g. an API key required by the
+    /// tile server.
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.fetcher = self.fetcher.with_header(name, value);
+        self
+    }
+
+    /// Sets the maximum time to wait for a response to a tile request. If the server does not
+    /// respond in time, the request fails with [`GalileoError::Timeout`].
+    ///
+    /// By default there is no timeout.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.fetcher = self.fetcher.with_timeout(timeout);
+        self
+    }
+
+    /// Limits the number of tile requests sent to the server at the same time. Tiles loaded from
+    /// the cache do not count towards the limit.
+    ///
+    /// By default the number of requests is not limited.
+    pub fn with_max_concurrent_requests(mut self, max_requests: usize) -> Self {
+        self.fetcher = self.fetcher.with_max_concurrent_requests(max_requests);
+        self
+    }
+
+    /// Enables revalidation of the cached tiles. Tiles that were received from the server more than
+    /// `max_age` ago are requested again. See [`TileFetcher::with_max_age`] for details.
+    ///
+    /// By default cached tiles are never revalidated.
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.fetcher = self.fetcher.with_max_age(max_age);
+        self
+    }
+
+    /// Limits the z-levels of the tiles provided by the server. Tiles outside of the range fail
+    /// with [`GalileoError::NotFound`] without sending a request.
+    pub fn with_zoom_range(mut self, min_zoom: u32, max_zoom: u32) -> Self {
+        self.zoom_range = Some(min_zoom..=max_zoom);
+        self
+    }
+
+    /// Sets the suffix substituted for the `{r}` placeholder of the URL template on high-DPI
+    /// displays, e.g. `@2x` or `_hd`. See [`set_pixel_ratio`](Self::set_pixel_ratio).
+    ///
+    /// Defaults to [`DEFAULT_HIGH_DPI_SUFFIX`].
+    pub fn with_high_dpi_suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.high_dpi_suffix = suffix.into();
+        self
+    }
+
+    /// Sets the tile schema of the tileset. It is used to calculate the row number for the `{-y}`
+    /// placeholder of the URL template (see [`TileSchema::opposite_y`]).
+    ///
+    /// Without the schema, the rows are counted as in [`TileSchema::web`].
+    pub fn with_tile_schema(mut self, tile_schema: TileSchema) -> Self {
+        self.tile_schema = Some(tile_schema);
+        self
+    }
+
     /// Updates the URL template used for generating tile URLs.
     ///
-    /// The template should contain placeholders for {z}, {x}, and {y} coordinates.
+    /// The template must contain placeholders for {z}, {x}, and {y} coordinates, or a {q}
+    /// placeholder for the Bing-style quadkey of the tile (see
+    /// [`quadkey`](crate::layer::data_provider::quadkey)).
     /// Example: "https://tiles.example.com/{z}/{x}/{y}.png"
-    pub fn update_url_template(&self, new_template: impl Into<String>) {
-        *self.url_template.write() = new_template.into();
+    ///
+    /// If the template is invalid (see [`validate_url_template`]), or it contains `{s}` placeholder
+    /// but no subdomains are set, an error is returned and the previous template is kept.
+    pub fn update_url_template(&self, new_template: impl Into<String>) -> Result<(), GalileoError> {
+        let new_template = new_template.into();
+        validate_url_template(&new_template)?;
+        validate_subdomains(&new_template, &self.subdomains.read())?;
+        *self.url_template.write() = new_template;
+
+        Ok(())
+    }
+
+    /// Sets the subdomains substituted for the `{s}` placeholder of the URL template, e.g.
+    /// `["a", "b", "c"]` for `"https://{s}.tile.openstreetmap.org/{z}/{x}/{y}.png"`.
+    ///
+    /// Requests are spread between the subdomains, but the same tile is always requested from the
+    /// same subdomain.
+    pub fn set_subdomains(&self, subdomains: impl IntoIterator<Item = impl Into<String>>) {
+        *self.subdomains.write() = subdomains.into_iter().map(Into::into).collect();
+    }
+
+    /// Checks that the URL template is valid (see [`validate_url_template`]).
+    pub fn validate_url_template(&self) -> Result<(), GalileoError> {
+        validate_url_template(&self.url_template.read())
+    }
+
+    /// Checks that subdomains are set if the URL template contains `{s}` placeholder.
+    pub fn validate_subdomains(&self) -> Result<(), GalileoError> {
+        validate_subdomains(&self.url_template.read(), &self.subdomains.read())
+    }
+
+    /// Sets the number of physical pixels in a logical pixel of the display.
+    ///
+    /// On high-DPI displays (pixel ratio above 1) the high-DPI suffix is substituted for the `{r}`
+    /// placeholder of the URL template, so that the server returns tiles of double resolution.
+    /// Such tiles are stretched to the same area as the standard ones, so the tile schema stays
+    /// the same.
+    ///
+    /// Defaults to 1.
+    pub fn set_pixel_ratio(&self, pixel_ratio: f32) {
+        *self.pixel_ratio.write() = pixel_ratio;
+    }
+
+    /// Returns the pixel ratio of the display the tiles are requested for.
+    pub fn pixel_ratio(&self) -> f32 {
+        *self.pixel_ratio.read()
     }
 
     /// Updates the parameters that will be appended to the URL as query parameters.
@@ -202,83 +411,130 @@ impl DynamicUrlTileLoader {
 
     /// Generates the URL for a given tile index using the current template and parameters.
     fn generate_url(&self, index: &TileIndex) -> String {
-        let template = self.url_template.read();
-        let params = self.parameters.read();
-
-        let mut url = template
-            .replace("{z}", &index.z.to_string())
-            .replace("{x}", &index.x.to_string())
-            .replace("{y}", &index.y.to_string());
-
-        if !params.is_empty() {
-            let query_string: String = params
-                .iter()
-                .map(|(k, v)| format!("{}={}", urlencoding::encode(k), urlencoding::encode(v)))
-                .collect::<Vec<_>>()
-                .join("&");
-            url.push_str("?");
-            url.push_str(&query_string);
-        }
-
-        url
+        expand_url_template(
+            &self.url_template.read(),
+            index,
+            index.y as i64,
+            &self.subdomains.read(),
+            &self.parameters.read(),
+            high_dpi_suffix(*self.pixel_ratio.read(), &self.high_dpi_suffix),
+            self.tile_schema.as_ref(),
+        )
     }
 
     async fn download_tile(&self, index: TileIndex) -> Result<Bytes, GalileoError> {
+        check_zoom_range(self.zoom_range.as_ref(), index)?;
         let url = self.generate_url(&index);
+        Ok(self.fetcher.fetch(&url).await?.bytes)
+    }
+}
 
-        if let Some(cache) = &self.cache {
-            if let Some(data) = cache.get(&url) {
-                return Ok(data);
-            }
-        }
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+impl RasterTileLoader for DynamicUrlTileLoader {
+    async fn load(&self, index: TileIndex) -> Result<DecodedImage, GalileoError> {
+        let bytes = self.download_tile(index).await?;
+        crate::platform::instance().decode_image(bytes).await
+    }
+}
 
-        if self.offline_mode {
-            return Err(GalileoError::NotFound);
-        }
+/// Loads raster tiles from an [`MbTiles`] file, e.g. for applications that must work fully
+/// offline.
+///
+/// Tiles outside of the zoom range given in the file metadata fail with
+/// [`GalileoError::NotFound`] without querying the file.
+///
+/// ```no_run
+/// use galileo::layer::raster_tile_layer::{MbTilesRasterLoader, RasterTileLayerBuilder};
+///
+/// let loader = MbTilesRasterLoader::open("data/satellite.mbtiles")?;
+/// let layer = RasterTileLayerBuilder::new_with_loader(loader).build()?;
+/// # Ok::<(), galileo::error::GalileoError>(())
+/// ```
+#[cfg(all(feature = "mbtiles", not(target_arch = "wasm32")))]
+pub struct MbTilesRasterLoader {
+    mbtiles: MbTiles,
+}
 
-        log::info!("Loading {url}");
-        let data = crate::platform::instance()
-            .load_bytes_from_url(&url)
-            .await?;
+#[cfg(all(feature = "mbtiles", not(target_arch = "wasm32")))]
+impl MbTilesRasterLoader {
+    /// Opens the MBTiles file at `path`. See [`MbTilesRasterLoader::new`].
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self, GalileoError> {
+        Self::new(MbTiles::open(path)?)
+    }
 
-        if let Some(cache) = &self.cache {
-            if let Err(error) = cache.insert(&url, &data) {
-                log::warn!("Failed to write persistent cache entry: {error:?}");
-            }
+    /// Creates a loader that reads the tiles from the given file.
+    ///
+    /// Returns an error if the file contains vector tiles.
+    pub fn new(mbtiles: MbTiles) -> Result<Self, GalileoError> {
+        if mbtiles.metadata().is_vector() {
+            return Err(GalileoError::Configuration(
+                "MBTiles file contains vector tiles, but raster tiles are expected".into(),
+            ));
         }
 
-        Ok(data)
+        Ok(Self { mbtiles })
+    }
+
+    /// The file the tiles are read from.
+    pub fn mbtiles(&self) -> &MbTiles {
+        &self.mbtiles
     }
 }
 
-#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
-#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
-impl RasterTileLoader for DynamicUrlTileLoader {
+#[cfg(all(feature = "mbtiles", not(target_arch = "wasm32")))]
+#[async_trait::async_trait]
+impl RasterTileLoader for MbTilesRasterLoader {
     async fn load(&self, index: TileIndex) -> Result<DecodedImage, GalileoError> {
-        let bytes = self.download_tile(index).await?;
+        check_zoom_range(self.mbtiles.metadata().zoom_range().as_ref(), index)?;
+
+        let bytes = self
+            .mbtiles
+            .load_tile_data(index)
+            .await?
+            .ok_or(GalileoError::NotFound)?;
         crate::platform::instance().decode_image(bytes).await
     }
 }
 
+/// Fails with [`GalileoError::NotFound`] if the z-level of the tile is outside of the `zoom_range`.
+fn check_zoom_range(
+    zoom_range: Option<&RangeInclusive<u32>>,
+    index: TileIndex,
+) -> Result<(), GalileoError> {
+    match zoom_range {
+        Some(range) if !range.contains(&index.z) => Err(GalileoError::NotFound),
+        _ => Ok(()),
+    }
+}
+
 #[derive(Clone)]
 enum TileState {
     Loading,
-    Loaded(Arc<DecodedImage>),
+    /// Image of the tile and the region of the image that covers the tile.
+    Loaded(Arc<DecodedImage>, Rect),
     Rendered(Arc<dyn PackedBundle>),
     Error,
 }
 
+/// Image of an overzoom source tile shared between all the tiles cut out of it.
+type SharedImage = Arc<OnceCell<Result<Arc<DecodedImage>, GalileoError>>>;
+
 #[derive(Debug)]
 pub(crate) struct RasterTileProvider {
     tiles: Mutex<Cache<TileIndex, TileState>>,
+    overzoom_sources: Mutex<Cache<TileIndex, SharedImage>>,
     tile_schema: TileSchema,
+    sampling: ImageSampling,
 }
 
 impl RasterTileProvider {
-    pub(crate) fn new(tile_schema: TileSchema) -> Self {
+    pub(crate) fn new(tile_schema: TileSchema, sampling: ImageSampling) -> Self {
         Self {
             tile_schema,
             tiles: Mutex::new(Cache::new(5000)),
+            overzoom_sources: Mutex::new(Cache::new(64)),
+            sampling,
         }
     }
 }
@@ -295,30 +551,99 @@ impl RasterTileProvider {
         }
     }
 
-    pub(crate) fn set_loaded(&self, index: TileIndex, image: DecodedImage) {
+    pub(crate) fn set_loaded(&self, index: TileIndex, image: Arc<DecodedImage>, source: Rect) {
         self.tiles
             .lock()
-            .insert(index, TileState::Loaded(Arc::new(image)));
+            .insert(index, TileState::Loaded(image, source));
+    }
+
+    /// Returns the index of the ancestor of the tile at the `max_zoom` level and the region of the
+    /// ancestor image that covers the tile. Returns `None` if the tile is not above `max_zoom`.
+    pub(crate) fn overzoom_source(
+        &self,
+        index: TileIndex,
+        max_zoom: u32,
+    ) -> Option<(TileIndex, Rect)> {
+        let levels = index.z.checked_sub(max_zoom).filter(|levels| *levels > 0)?;
+        let mut ancestor = index;
+        for _ in 0..levels {
+            ancestor = ancestor.parent()?;
+        }
+
+        let scale = 2f64.powi(levels as i32);
+        let dx = index.x as f64 - ancestor.x as f64 * scale;
+        let dy = index.y as f64 - ancestor.y as f64 * scale;
+        let top = match self.tile_schema.y_direction {
+            VerticalDirection::TopToBottom => dy,
+            VerticalDirection::BottomToTop => scale - 1.0 - dy,
+        };
+
+        Some((
+            ancestor,
+            Rect::new(
+                dx / scale,
+                top / scale,
+                (dx + 1.0) / scale,
+                (top + 1.0) / scale,
+            ),
+        ))
+    }
+
+    /// Loads the image of the overzoom source tile. Concurrent and repeated calls for the same tile
+    /// share a single request to the loader.
+    pub(crate) async fn load_overzoom_source(
+        &self,
+        index: TileIndex,
+        tile_loader: &dyn RasterTileLoader,
+    ) -> Result<Arc<DecodedImage>, GalileoError> {
+        let image = {
+            let sources = self.overzoom_sources.lock();
+            match sources.get(&index) {
+                Some(image) => image,
+                None => {
+                    let image = SharedImage::default();
+                    sources.insert(index, image.clone());
+                    image
+                }
+            }
+        };
+
+        image
+            .get_or_init(|| async { tile_loader.load(index).await.map(Arc::new) })
+            .await
+            .clone()
     }
 
     pub(crate) fn set_error(&self, index: TileIndex) {
         self.tiles.lock().insert(index, TileState::Error);
     }
 
+    /// Returns `true` if the tile is loaded or failed to load.
+    pub(crate) fn is_settled(&self, index: TileIndex) -> bool {
+        !matches!(
+            self.tiles.lock().peek(&index),
+            None | Some(TileState::Loading)
+        )
+    }
+
     pub(crate) fn pack_tiles(&self, indices: &[TileIndex], canvas: &dyn Canvas) {
         let tiles = self.tiles.lock();
         for index in indices {
-            if let Some(TileState::Loaded(image)) = tiles.get(index) {
+            if let Some(TileState::Loaded(image, source)) = tiles.get(index) {
                 let Some(tile_bbox) = self.tile_schema.tile_bbox(*index) else {
                     log::warn!("Failed to get bbox for tile {index:?}");
                     continue;
                 };
 
                 let mut bundle = RenderBundle::default();
-                bundle.add_image(
+                bundle.add_image_region(
                     image.clone(),
+                    source,
                     tile_bbox.into_quadrangle(),
-                    ImagePaint { opacity: 255 },
+                    ImagePaint {
+                        opacity: 255,
+                        sampling: self.sampling,
+                    },
                 );
                 let packed = canvas.pack_bundle(&bundle);
                 tiles.insert(*index, TileState::Rendered(packed.into()));
@@ -335,3 +660,71 @@ impl TileProvider<()> for RasterTileProvider {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use galileo_types::cartesian::Size;
+
+    use super::*;
+
+    struct CountingLoader {
+        requests: AtomicUsize,
+    }
+
+    #[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+    #[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+    impl RasterTileLoader for CountingLoader {
+        async fn load(&self, _index: TileIndex) -> Result<DecodedImage, GalileoError> {
+            self.requests.fetch_add(1, Ordering::Relaxed);
+            DecodedImage::from_raw(vec![0; 4], Size::new(1, 1))
+        }
+    }
+
+    fn provider(y_direction: VerticalDirection) -> RasterTileProvider {
+        let mut tile_schema = TileSchema::web(18);
+        tile_schema.y_direction = y_direction;
+        RasterTileProvider::new(tile_schema, ImageSampling::default())
+    }
+
+    #[test]
+    fn overzoom_source_is_ancestor_at_max_zoom() {
+        let provider = provider(VerticalDirection::TopToBottom);
+
+        assert_eq!(provider.overzoom_source(TileIndex::new(5, 3, 10), 10), None);
+        assert_eq!(provider.overzoom_source(TileIndex::new(5, 3, 9), 10), None);
+
+        let (ancestor, source) = provider
+            .overzoom_source(TileIndex::new(13, 6, 12), 10)
+            .unwrap();
+        assert_eq!(ancestor, TileIndex::new(3, 1, 10));
+        assert_eq!(source, Rect::new(0.25, 0.5, 0.5, 0.75));
+    }
+
+    #[test]
+    fn overzoom_source_respects_y_direction() {
+        let provider = provider(VerticalDirection::BottomToTop);
+
+        let (ancestor, source) = provider
+            .overzoom_source(TileIndex::new(13, 6, 12), 10)
+            .unwrap();
+        assert_eq!(ancestor, TileIndex::new(3, 1, 10));
+        assert_eq!(source, Rect::new(0.25, 0.25, 0.5, 0.5));
+    }
+
+    #[tokio::test]
+    async fn overzoom_source_is_loaded_once() {
+        let provider = provider(VerticalDirection::TopToBottom);
+        let loader = CountingLoader {
+            requests: AtomicUsize::new(0),
+        };
+
+        let index = TileIndex::new(3, 1, 10);
+        let first = provider.load_overzoom_source(index, &loader).await.unwrap();
+        let second = provider.load_overzoom_source(index, &loader).await.unwrap();
+
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(loader.requests.load(Ordering::Relaxed), 1);
+    }
+}
@@ -1,10 +1,13 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 
+use galileo_types::cartesian::Rect;
 use parking_lot::Mutex;
 
 use crate::render::PackedBundle;
 use crate::tile_schema::TileIndex;
+use crate::view::MapView;
 use crate::TileSchema;
 
 #[derive(Clone)]
@@ -26,12 +29,25 @@ pub(crate) trait TileProvider<StyleId> {
     fn get_tile(&self, index: TileIndex, style_id: StyleId) -> Option<Arc<dyn PackedBundle>>;
 }
 
+/// Tiles visible in a view together with their bounding boxes in map coordinates.
+///
+/// The values are only recalculated when the generation of the view changes.
+#[derive(Default)]
+struct VisibleTiles {
+    view_generation: Option<u64>,
+    indices: Vec<TileIndex>,
+    bboxes: HashMap<TileIndex, Rect>,
+    #[cfg(test)]
+    recalculations: usize,
+}
+
 pub(crate) struct TilesContainer<StyleId, Provider>
 where
     StyleId: Copy,
     Provider: TileProvider<StyleId>,
 {
     pub(crate) tiles: Mutex<Vec<DisplayedTile<StyleId>>>,
+    visible: Mutex<VisibleTiles>,
     tile_schema: TileSchema,
     pub(crate) tile_provider: Provider,
 }
@@ -44,11 +60,40 @@ where
     pub(crate) fn new(tile_schema: TileSchema, tile_provider: Provider) -> Self {
         Self {
             tiles: Default::default(),
+            visible: Default::default(),
             tile_schema,
             tile_provider,
         }
     }
 
+    /// Returns indices of the tiles that should be displayed for the `view`.
+    ///
+    /// If the view has not changed since the last call, the cached values are returned.
+    pub(crate) fn visible_tiles(&self, view: &MapView) -> Option<Vec<TileIndex>> {
+        let mut visible = self.visible.lock();
+        if visible.view_generation != Some(view.generation()) {
+            let indices: Vec<_> = self.tile_schema.iter_tiles(view)?.collect();
+            visible.bboxes = indices
+                .iter()
+                .filter_map(|index| Some((*index, self.tile_schema.tile_bbox(*index)?)))
+                .collect();
+            visible.indices = indices;
+            visible.view_generation = Some(view.generation());
+
+            #[cfg(test)]
+            {
+                visible.recalculations += 1;
+            }
+        }
+
+        Some(visible.indices.clone())
+    }
+
+    fn tile_bbox(&self, index: TileIndex) -> Option<Rect> {
+        let cached = self.visible.lock().bboxes.get(&index).copied();
+        cached.or_else(|| self.tile_schema.tile_bbox(index))
+    }
+
     pub(crate) fn update_displayed_tiles(
         &self,
         needed_indices: impl IntoIterator<Item = TileIndex>,
@@ -104,12 +149,12 @@ where
                 continue;
             }
 
-            let Some(displayed_bbox) = self.tile_schema.tile_bbox(displayed.index) else {
+            let Some(displayed_bbox) = self.tile_bbox(displayed.index) else {
                 continue;
             };
 
             for subst in &to_substitute {
-                let Some(subst_bbox) = self.tile_schema.tile_bbox(*subst) else {
+                let Some(subst_bbox) = self.tile_bbox(*subst) else {
                     continue;
                 };
 
@@ -130,3 +175,55 @@ where
         Duration::from_millis(300)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use galileo_types::cartesian::{Point2, Size, Vector2};
+
+    use super::*;
+
+    struct TestProvider;
+    impl TileProvider<()> for TestProvider {
+        fn get_tile(&self, _index: TileIndex, _style_id: ()) -> Option<Arc<dyn PackedBundle>> {
+            None
+        }
+    }
+
+    fn test_container() -> TilesContainer<(), TestProvider> {
+        TilesContainer::new(TileSchema::web(5), TestProvider)
+    }
+
+    fn test_view() -> MapView {
+        let resolution = TileSchema::web(5).lod_resolution(3).expect("lod exists");
+        MapView::new_projected(&Point2::new(0.0, 0.0), resolution)
+            .with_size(Size::new(512.0, 512.0))
+    }
+
+    #[test]
+    fn visible_tiles_are_reused_for_same_view() {
+        let container = test_container();
+        let view = test_view();
+
+        let first = container.visible_tiles(&view).expect("tiles are visible");
+        let second = container
+            .visible_tiles(&view.clone())
+            .expect("tiles are visible");
+
+        assert!(!first.is_empty());
+        assert_eq!(first, second);
+        assert_eq!(container.visible.lock().recalculations, 1);
+    }
+
+    #[test]
+    fn visible_tiles_are_recalculated_when_view_changes() {
+        let container = test_container();
+        let view = test_view();
+
+        container.visible_tiles(&view);
+        let moved = view.translate(Vector2::new(1000.0, 0.0));
+        container.visible_tiles(&moved);
+
+        assert_ne!(view.generation(), moved.generation());
+        assert_eq!(container.visible.lock().recalculations, 2);
+    }
+}
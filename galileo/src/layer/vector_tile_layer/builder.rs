@@ -1,18 +1,21 @@
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 
 use bytes::Bytes;
 
 use super::style::{
     StyleRule, VectorTileLineSymbol, VectorTilePolygonSymbol, VectorTileStyle, VectorTileSymbol,
 };
-use super::tile_provider::loader::WebVtLoader;
+use super::tile_provider::loader::{DynamicUrlVtLoader, VectorTileLoader, WebVtLoader};
 use super::tile_provider::processor::VectorTileProcessor;
-use super::tile_provider::VectorTileProvider;
+use super::tile_provider::{DecodeErrorFallback, VectorTileFilter, VectorTileProvider};
 use super::VectorTileLayer;
 use crate::error::GalileoError;
 use crate::layer::attribution::Attribution;
-use crate::layer::data_provider::{FileCacheController, PersistentCacheController, UrlSource};
+use crate::layer::data_provider::{
+    FileCacheController, PersistentCacheController, RetryPolicy, UrlSource,
+};
 use crate::layer::Layer;
 use crate::tile_schema::TileIndex;
 use crate::{Color, Messenger, TileSchema};
@@ -48,15 +51,44 @@ pub struct VectorTileLayerBuilder {
     tile_schema: Option<TileSchema>,
     messenger: Option<Box<dyn Messenger>>,
     cache: CacheType,
+    cache_ttl: Option<Duration>,
     offline_mode: bool,
+    retry_policy: Option<RetryPolicy>,
+    headers: Vec<(String, String)>,
+    timeout: Option<Duration>,
+    max_concurrent_requests: Option<usize>,
+    subdomains: Vec<String>,
+    pixel_ratio: Option<f32>,
+    high_dpi_suffix: Option<String>,
+    zoom_range: Option<(u32, u32)>,
     attribution: Option<Attribution>,
+    decode_error_fallback: Option<DecodeErrorFallback>,
+    filter: Option<VectorTileFilter>,
+    scale_factor: Option<f32>,
+    tessellation_threads: Option<usize>,
 }
 
 enum ProviderType {
     Rest(Box<dyn UrlSource<TileIndex>>),
+    DynamicUrl(Box<DynamicUrlVtLoader>),
+    Loader(Arc<dyn VectorTileLoader>),
     Custom(VectorTileProvider),
 }
 
+impl ProviderType {
+    /// Name of the provider type that manages caching by itself, used in the configuration errors.
+    fn custom_name(&self) -> Option<&'static str> {
+        match self {
+            Self::Rest(_) => None,
+            Self::DynamicUrl(_) => {
+                Some("dynamic URL tile loader (use `new_dynamic_url_with_cache` to set its cache)")
+            }
+            Self::Loader(_) => Some("custom tile loader"),
+            Self::Custom(_) => Some("custom tile provider"),
+        }
+    }
+}
+
 enum CacheType {
     None,
     File(PathBuf),
@@ -85,8 +117,21 @@ impl VectorTileLayerBuilder {
             tile_schema: None,
             messenger: None,
             cache: CacheType::None,
+            cache_ttl: None,
             offline_mode: false,
+            retry_policy: None,
+            headers: Vec::new(),
+            timeout: None,
+            max_concurrent_requests: None,
+            subdomains: Vec::new(),
+            pixel_ratio: None,
+            high_dpi_suffix: None,
+            zoom_range: None,
             attribution: None,
+            decode_error_fallback: None,
+            filter: None,
+            tessellation_threads: None,
+            scale_factor: None,
         }
     }
 
@@ -113,8 +158,51 @@ impl VectorTileLayerBuilder {
             tile_schema: None,
             messenger: None,
             cache: CacheType::None,
+            cache_ttl: None,
+            offline_mode: false,
+            retry_policy: None,
+            headers: Vec::new(),
+            timeout: None,
+            max_concurrent_requests: None,
+            subdomains: Vec::new(),
+            pixel_ratio: None,
+            high_dpi_suffix: None,
+            zoom_range: None,
+            attribution: None,
+            decode_error_fallback: None,
+            filter: None,
+            tessellation_threads: None,
+            scale_factor: None,
+        }
+    }
+
+    /// Initializes a builder for a layer with the given tile loader, e.g. a loader reading the tiles
+    /// from an MBTiles file.
+    ///
+    /// Request settings (retry policy, headers, timeout and request limit) and the cache cannot be
+    /// used with a custom loader.
+    pub fn new_with_loader(loader: impl VectorTileLoader + 'static) -> Self {
+        Self {
+            provider_type: ProviderType::Loader(Arc::new(loader)),
+            style: None,
+            tile_schema: None,
+            messenger: None,
+            cache: CacheType::None,
+            cache_ttl: None,
             offline_mode: false,
+            retry_policy: None,
+            headers: Vec::new(),
+            timeout: None,
+            max_concurrent_requests: None,
+            subdomains: Vec::new(),
+            pixel_ratio: None,
+            high_dpi_suffix: None,
+            zoom_range: None,
             attribution: None,
+            decode_error_fallback: None,
+            filter: None,
+            tessellation_threads: None,
+            scale_factor: None,
         }
     }
 
@@ -136,21 +224,29 @@ impl VectorTileLayerBuilder {
     /// # Ok::<(), galileo::error::GalileoError>(())
     /// ```
     pub fn new_dynamic_url(url_template: impl Into<String>) -> Self {
-        use super::tile_provider::loader::DynamicUrlVtLoader;
-        use super::tile_provider::VectorTileProvider;
-
-        let loader = Arc::new(DynamicUrlVtLoader::new(url_template, None, false));
-        let processor = Arc::new(Self::create_processor(TileSchema::web(18)));
-        let provider = VectorTileProvider::new(loader, processor);
+        let loader = DynamicUrlVtLoader::new(url_template, None, false);
 
         Self {
-            provider_type: ProviderType::Custom(provider),
+            provider_type: ProviderType::DynamicUrl(Box::new(loader)),
             style: None,
             tile_schema: None,
             messenger: None,
             cache: CacheType::None,
+            cache_ttl: None,
             offline_mode: false,
+            retry_policy: None,
+            headers: Vec::new(),
+            timeout: None,
+            max_concurrent_requests: None,
+            subdomains: Vec::new(),
+            pixel_ratio: None,
+            high_dpi_suffix: None,
+            zoom_range: None,
             attribution: None,
+            decode_error_fallback: None,
+            filter: None,
+            tessellation_threads: None,
+            scale_factor: None,
         }
     }
 
@@ -174,21 +270,29 @@ impl VectorTileLayerBuilder {
         url_template: impl Into<String>,
         cache: Option<Box<dyn PersistentCacheController<str, Bytes>>>,
     ) -> Self {
-        use super::tile_provider::loader::DynamicUrlVtLoader;
-        use super::tile_provider::VectorTileProvider;
-
-        let loader = Arc::new(DynamicUrlVtLoader::new(url_template, cache, false));
-        let processor = Arc::new(Self::create_processor(TileSchema::web(18)));
-        let provider = VectorTileProvider::new(loader, processor);
+        let loader = DynamicUrlVtLoader::new(url_template, cache, false);
 
         Self {
-            provider_type: ProviderType::Custom(provider),
+            provider_type: ProviderType::DynamicUrl(Box::new(loader)),
             style: None,
             tile_schema: None,
             messenger: None,
             cache: CacheType::None,
+            cache_ttl: None,
             offline_mode: false,
+            retry_policy: None,
+            headers: Vec::new(),
+            timeout: None,
+            max_concurrent_requests: None,
+            subdomains: Vec::new(),
+            pixel_ratio: None,
+            high_dpi_suffix: None,
+            zoom_range: None,
             attribution: None,
+            decode_error_fallback: None,
+            filter: None,
+            tessellation_threads: None,
+            scale_factor: None,
         }
     }
 
@@ -237,6 +341,37 @@ impl VectorTileLayerBuilder {
         self
     }
 
+    /// Sets the time-to-live of the tiles in the file cache set by
+    /// [`VectorTileLayerBuilder::with_file_cache()`]. Tiles cached more than `ttl` ago are loaded again,
+    /// which is useful for layers with frequently changing data like weather or traffic. `None`
+    /// keeps the cached tiles forever, which is the default and suits static basemaps.
+    ///
+    /// Only the file cache created by the builder is affected. Custom cache controllers set with
+    /// [`VectorTileLayerBuilder::with_cache_controller()`] are used as is, so in a cache stack every
+    /// controller must be configured with its own time-to-live:
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use galileo::layer::data_provider::{FileCacheController, MemoryCacheController};
+    /// use galileo::layer::vector_tile_layer::VectorTileLayerBuilder;
+    ///
+    /// let ttl = Some(Duration::from_secs(600));
+    /// let cache = MemoryCacheController::with_max_entries(1000)
+    ///     .with_ttl(ttl)
+    ///     .in_front_of(FileCacheController::new("target")?.with_ttl(ttl));
+    /// let layer = VectorTileLayerBuilder::new_rest(|index| {
+    ///     format!("https://tiles.example.com/{}/{}/{}", index.z, index.x, index.y)
+    /// })
+    /// .with_cache_controller(cache)
+    /// .build()?;
+    /// # Ok::<(), galileo::error::GalileoError>(())
+    /// ```
+    pub fn with_cache_ttl(mut self, ttl: Option<Duration>) -> Self {
+        self.cache_ttl = ttl;
+        self
+    }
+
     /// Sets the attribution for the vector tile layer with the given text and URL.
     ///
     /// This method allows specifying an attribution, typically used for citing sources
@@ -345,6 +480,282 @@ impl VectorTileLayerBuilder {
         self
     }
 
+    /// Sets the policy of repeating tile requests that failed because of network errors.
+    ///
+    /// By default failed requests are not repeated. Requests for tiles that do not exist on the
+    /// server are never repeated.
+    ///
+    /// Cannot be used with custom tile provider given by
+    /// [`VectorTileLayerBuilder::new_with_provider()`] method as the provider must have already be
+    /// created with the retry policy. So in this case building will also return an error.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use galileo::layer::data_provider::RetryPolicy;
+    /// use galileo::layer::vector_tile_layer::VectorTileLayerBuilder;
+    ///
+    /// let layer = VectorTileLayerBuilder::new_rest(
+    ///     |index| {
+    ///         format!(
+    ///             "https://vector_tiles.example.com/{}/{}/{}.png",
+    ///             index.z, index.x, index.y
+    ///         )
+    ///     })
+    ///     .with_retry_policy(RetryPolicy::new(3, Duration::from_millis(500)).with_jitter(0.2))
+    ///     .build()?;
+    /// # Ok::<(), galileo::error::GalileoError>(())
+    /// ```
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
+    /// Adds an HTTP header that is sent with every tile request, e.g. an API key required by the
+    /// tile server. Can be called several times to add multiple headers.
+    ///
+    /// Cannot be used with custom tile provider given by
+    /// [`VectorTileLayerBuilder::new_with_provider()`] method as the provider must have already be
+    /// created with the headers. So in this case building will also return an error.
+    ///
+    /// ```
+    /// use galileo::layer::vector_tile_layer::VectorTileLayerBuilder;
+    ///
+    /// let layer = VectorTileLayerBuilder::new_rest(
+    ///     |index| {
+    ///         format!(
+    ///             "https://vector_tiles.example.com/{}/{}/{}.png",
+    ///             index.z, index.x, index.y
+    ///         )
+    ///     })
+    ///     .with_header("Authorization", "Bearer <token>")
+    ///     .build()?;
+    /// # Ok::<(), galileo::error::GalileoError>(())
+    /// ```
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Sets the maximum time to wait for a response to a tile request. Tiles that are not loaded
+    /// in time are treated as failed to load because of a network error.
+    ///
+    /// By default there is no timeout.
+    ///
+    /// Cannot be used with custom tile provider given by
+    /// [`VectorTileLayerBuilder::new_with_provider()`] method as the provider must have already be
+    /// created with the timeout. So in this case building will also return an error.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use galileo::layer::vector_tile_layer::VectorTileLayerBuilder;
+    ///
+    /// let layer = VectorTileLayerBuilder::new_rest(
+    ///     |index| {
+    ///         format!(
+    ///             "https://vector_tiles.example.com/{}/{}/{}.png",
+    ///             index.z, index.x, index.y
+    ///         )
+    ///     })
+    ///     .with_timeout(Duration::from_secs(10))
+    ///     .build()?;
+    /// # Ok::<(), galileo::error::GalileoError>(())
+    /// ```
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Limits the number of tile requests sent to the server at the same time, e.g. to avoid
+    /// hitting the rate limits of the server when the map is panned fast.
+    ///
+    /// By default the number of requests is not limited.
+    ///
+    /// Cannot be used with custom tile provider given by
+    /// [`VectorTileLayerBuilder::new_with_provider()`] method as the provider must have already be
+    /// created with the limit. So in this case building will also return an error.
+    ///
+    /// ```
+    /// use galileo::layer::vector_tile_layer::VectorTileLayerBuilder;
+    ///
+    /// let layer = VectorTileLayerBuilder::new_rest(
+    ///     |index| {
+    ///         format!(
+    ///             "https://vector_tiles.example.com/{}/{}/{}.png",
+    ///             index.z, index.x, index.y
+    ///         )
+    ///     })
+    ///     .with_max_concurrent_requests(6)
+    ///     .build()?;
+    /// # Ok::<(), galileo::error::GalileoError>(())
+    /// ```
+    pub fn with_max_concurrent_requests(mut self, max_requests: usize) -> Self {
+        self.max_concurrent_requests = Some(max_requests);
+        self
+    }
+
+    /// Sets the subdomains substituted for the `{s}` placeholder of the dynamic URL template.
+    ///
+    /// Can only be used with the layers created by [`VectorTileLayerBuilder::new_dynamic_url()`] or
+    /// [`VectorTileLayerBuilder::new_dynamic_url_with_cache()`]. Building the layer returns an error if
+    /// the subdomains are set for other layers, or if the URL template contains `{s}` placeholder
+    /// but the subdomains are not set.
+    ///
+    /// ```
+    /// use galileo::layer::vector_tile_layer::VectorTileLayerBuilder;
+    ///
+    /// let layer = VectorTileLayerBuilder::new_dynamic_url("https://{s}.tiles.example.com/{z}/{x}/{y}.pbf")
+    ///     .with_subdomains(["a", "b", "c"])
+    ///     .build()?;
+    /// # Ok::<(), galileo::error::GalileoError>(())
+    /// ```
+    pub fn with_subdomains(
+        mut self,
+        subdomains: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.subdomains = subdomains.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets the number of physical pixels in a logical pixel of the display, e.g. `2.0` for a
+    /// retina display. On high-DPI displays the high-DPI suffix is substituted for the `{r}`
+    /// placeholder of the dynamic URL template, so that the tiles of double resolution are
+    /// requested. See [`DynamicUrlVtLoader::set_pixel_ratio()`].
+    ///
+    /// Can only be used with the layers created by [`VectorTileLayerBuilder::new_dynamic_url()`] or
+    /// [`VectorTileLayerBuilder::new_dynamic_url_with_cache()`]. Building the layer returns an error if
+    /// it is set for other layers.
+    ///
+    /// ```
+    /// use galileo::layer::vector_tile_layer::VectorTileLayerBuilder;
+    ///
+    /// let layer = VectorTileLayerBuilder::new_dynamic_url("https://tiles.example.com/{z}/{x}/{y}{r}.pbf")
+    ///     .with_pixel_ratio(2.0)
+    ///     .build()?;
+    /// # Ok::<(), galileo::error::GalileoError>(())
+    /// ```
+    pub fn with_pixel_ratio(mut self, pixel_ratio: f32) -> Self {
+        self.pixel_ratio = Some(pixel_ratio);
+        self
+    }
+
+    /// Sets the suffix substituted for the `{r}` placeholder of the dynamic URL template on
+    /// high-DPI displays, for the servers that do not use the default `@2x` suffix.
+    ///
+    /// Has the same restrictions as [`VectorTileLayerBuilder::with_pixel_ratio()`].
+    pub fn with_high_dpi_suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.high_dpi_suffix = Some(suffix.into());
+        self
+    }
+
+    /// Sets the behaviour of the layer for tiles that were loaded but could not be decoded.
+    ///
+    /// Defaults to [`DecodeErrorFallback::Blank`]. This option is also applied to the provider
+    /// given by [`VectorTileLayerBuilder::new_with_provider()`] method, if it is set.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use galileo::layer::vector_tile_layer::tile_provider::DecodeErrorFallback;
+    /// use galileo::layer::vector_tile_layer::VectorTileLayerBuilder;
+    ///
+    /// let layer = VectorTileLayerBuilder::new_rest(
+    ///     |index| {
+    ///         format!(
+    ///             "https://vector_tiles.example.com/{}/{}/{}.png",
+    ///             index.z, index.x, index.y
+    ///         )
+    ///     })
+    ///     .with_decode_error_fallback(DecodeErrorFallback::Retry {
+    ///         delay: Duration::from_secs(10),
+    ///     })
+    ///     .build()?;
+    /// # Ok::<(), galileo::error::GalileoError>(())
+    /// ```
+    pub fn with_decode_error_fallback(mut self, fallback: DecodeErrorFallback) -> Self {
+        self.decode_error_fallback = Some(fallback);
+        self
+    }
+
+    /// Sets the filter that selects the source layers and features of the tiles to be rendered.
+    ///
+    /// Features rejected by the filter are dropped before they are styled and tessellated, which
+    /// makes it cheap to draw only a few layers of a full basemap tileset. This option is also
+    /// applied to the provider given by [`VectorTileLayerBuilder::new_with_provider()`] method, if
+    /// it is set.
+    ///
+    /// ```
+    /// use galileo::layer::vector_tile_layer::tile_provider::VectorTileFilter;
+    /// use galileo::layer::vector_tile_layer::VectorTileLayerBuilder;
+    ///
+    /// let layer = VectorTileLayerBuilder::new_dynamic_url("https://tiles.example.com/{z}/{x}/{y}.pbf")
+    ///     .with_filter(VectorTileFilter::new().with_layers(["water"]))
+    ///     .build()?;
+    ///
+    /// assert!(layer.provider().filter().is_some());
+    /// # Ok::<(), galileo::error::GalileoError>(())
+    /// ```
+    pub fn with_filter(mut self, filter: VectorTileFilter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Sets the maximum number of threads that tessellate the tiles of the layer at the same time.
+    /// Values less than `1` are treated as `1`.
+    ///
+    /// By default one less than the number of available cores is used, so that the main thread is
+    /// not starved. See [`ThreadVtProcessor::with_thread_count()`].
+    ///
+    /// Cannot be used with custom tile provider given by
+    /// [`VectorTileLayerBuilder::new_with_provider()`] method as the provider must have already be
+    /// created with its processor. So in this case building will also return an error.
+    ///
+    /// # Platforms
+    ///
+    /// When compiling for the `wasm32` architecture, the tiles are tessellated by the shared pool of
+    /// web workers, so this setting is ignored.
+    ///
+    /// ```
+    /// use galileo::layer::vector_tile_layer::VectorTileLayerBuilder;
+    ///
+    /// let layer = VectorTileLayerBuilder::new_dynamic_url("https://tiles.example.com/{z}/{x}/{y}.pbf")
+    ///     .with_tessellation_threads(2)
+    ///     .build()?;
+    /// # Ok::<(), galileo::error::GalileoError>(())
+    /// ```
+    ///
+    /// [`ThreadVtProcessor::with_thread_count()`]: crate::platform::native::vt_processor::ThreadVtProcessor::with_thread_count
+    pub fn with_tessellation_threads(mut self, thread_count: usize) -> Self {
+        self.tessellation_threads = Some(thread_count);
+        self
+    }
+
+    /// Limits the z-levels of the tiles requested from the server, e.g. for a self-hosted tileset
+    /// that covers only some zoom levels.
+    ///
+    /// Tiles outside of the range are never requested. When the map is zoomed in beyond `max_zoom`,
+    /// the tiles of `max_zoom` are stretched, and when it is zoomed out beyond `min_zoom`, the tiles
+    /// of `min_zoom` are shown. See [`TileSchema::with_zoom_range()`].
+    ///
+    /// Building the layer returns an error if the tile schema has no levels in the range.
+    ///
+    /// ```
+    /// use galileo::layer::vector_tile_layer::VectorTileLayerBuilder;
+    /// use galileo::layer::Layer;
+    ///
+    /// let layer = VectorTileLayerBuilder::new_dynamic_url("https://tiles.example.com/{z}/{x}/{y}.pbf")
+    ///     .with_zoom_range(0, 14)
+    ///     .build()?;
+    ///
+    /// assert_eq!(layer.tile_schema().unwrap().lod_resolution(15), None);
+    /// # Ok::<(), galileo::error::GalileoError>(())
+    /// ```
+    pub fn with_zoom_range(mut self, min_zoom: u32, max_zoom: u32) -> Self {
+        self.zoom_range = Some((min_zoom, max_zoom));
+        self
+    }
+
     /// Sets the layer's tile schema.
     ///
     /// Defaults to `TileSchema::web(18)`. Note that for vector tiles you usually don't want to use
@@ -415,6 +826,26 @@ impl VectorTileLayerBuilder {
         self
     }
 
+    /// Sets the scale factor of the display the layer is rendered to. Font sizes of the labels given in points are
+    /// multiplied by it. This overrides [`VectorTileStyle::scale_factor`] of the layer style.
+    ///
+    /// If not set, the scale factor of the canvas the layer is drawn to is used.
+    ///
+    /// ```
+    /// use galileo::layer::vector_tile_layer::VectorTileLayerBuilder;
+    ///
+    /// let layer = VectorTileLayerBuilder::new_dynamic_url("https://tiles.example.com/{z}/{x}/{y}.pbf")
+    ///     .with_scale_factor(2.0)
+    ///     .build()?;
+    ///
+    /// assert_eq!(layer.style().scale_factor, 2.0);
+    /// # Ok::<(), galileo::error::GalileoError>(())
+    /// ```
+    pub fn with_scale_factor(mut self, scale_factor: f32) -> Self {
+        self.scale_factor = Some(scale_factor);
+        self
+    }
+
     /// Consumes the builder and constructs the vector tile layer.
     ///
     /// Will return an error if the layer is configured incorrectly or if the cache controller
@@ -426,15 +857,34 @@ impl VectorTileLayerBuilder {
             tile_schema,
             messenger,
             cache,
+            cache_ttl,
             offline_mode,
+            retry_policy,
+            headers,
+            timeout,
+            max_concurrent_requests,
+            subdomains,
+            pixel_ratio,
+            high_dpi_suffix,
+            zoom_range,
             attribution,
+            decode_error_fallback,
+            filter,
+            tessellation_threads,
+            scale_factor,
         } = self;
 
         let tile_schema = tile_schema.unwrap_or_else(|| TileSchema::web(18));
+        let tile_schema = match zoom_range {
+            Some((min_zoom, max_zoom)) => tile_schema.with_zoom_range(min_zoom, max_zoom)?,
+            None => tile_schema,
+        };
 
         let cache_controller: Option<Box<dyn PersistentCacheController<str, Bytes>>> = match cache {
             CacheType::None => None,
-            CacheType::File(path_buf) => Some(Box::new(FileCacheController::new(&path_buf)?)),
+            CacheType::File(path_buf) => Some(Box::new(
+                FileCacheController::new(&path_buf)?.with_ttl(cache_ttl),
+            )),
             CacheType::Custom(persistent_cache_controller) => Some(persistent_cache_controller),
         };
 
@@ -444,29 +894,147 @@ impl VectorTileLayerBuilder {
             ));
         }
 
-        let processor = Self::create_processor(tile_schema.clone());
+        let processor = Self::create_processor(tile_schema.clone(), tessellation_threads);
+
+        if let (Some(_), Some(provider_name)) = (&cache_controller, provider_type.custom_name()) {
+            return Err(GalileoError::Configuration(format!(
+                "{provider_name} cannot be used together with a cache controller"
+            )));
+        }
+
+        if !subdomains.is_empty() && !matches!(provider_type, ProviderType::DynamicUrl(_)) {
+            return Err(GalileoError::Configuration(
+                "subdomains can only be used with a dynamic URL tile loader".into(),
+            ));
+        }
+
+        if (pixel_ratio.is_some() || high_dpi_suffix.is_some())
+            && !matches!(provider_type, ProviderType::DynamicUrl(_))
+        {
+            return Err(GalileoError::Configuration(
+                "high DPI settings can only be used with a dynamic URL tile loader".into(),
+            ));
+        }
+
+        let is_custom = matches!(
+            provider_type,
+            ProviderType::Loader(_) | ProviderType::Custom(_)
+        );
+        if is_custom {
+            if retry_policy.is_some() {
+                return Err(GalileoError::Configuration(
+                    "custom tile provider cannot be used together with a retry policy".into(),
+                ));
+            }
+
+            if !headers.is_empty() {
+                return Err(GalileoError::Configuration(
+                    "custom tile provider cannot be used together with request headers".into(),
+                ));
+            }
+
+            if timeout.is_some() {
+                return Err(GalileoError::Configuration(
+                    "custom tile provider cannot be used together with a request timeout".into(),
+                ));
+            }
+
+            if max_concurrent_requests.is_some() {
+                return Err(GalileoError::Configuration(
+                    "custom tile provider cannot be used together with a request limit".into(),
+                ));
+            }
+        }
+
+        if tessellation_threads.is_some() && matches!(provider_type, ProviderType::Custom(_)) {
+            return Err(GalileoError::Configuration(
+                "custom tile provider cannot be used together with a tessellation thread count"
+                    .into(),
+            ));
+        }
 
         let provider = match provider_type {
             ProviderType::Rest(url_source) => {
-                let loader = WebVtLoader::new(cache_controller, url_source, offline_mode);
+                let loader = WebVtLoader::new(cache_controller, url_source, offline_mode)
+                    .with_headers(headers);
+                let loader = match retry_policy {
+                    Some(retry_policy) => loader.with_retry_policy(retry_policy),
+                    None => loader,
+                };
+                let loader = match timeout {
+                    Some(timeout) => loader.with_timeout(timeout),
+                    None => loader,
+                };
+                let loader = match max_concurrent_requests {
+                    Some(max_requests) => loader.with_max_concurrent_requests(max_requests),
+                    None => loader,
+                };
+                let loader = match zoom_range {
+                    Some((min_zoom, max_zoom)) => loader.with_zoom_range(min_zoom, max_zoom),
+                    None => loader,
+                };
 
                 VectorTileProvider::new(Arc::new(loader), Arc::new(processor))
             }
-            ProviderType::Custom(raster_tile_provider) => {
-                if cache_controller.is_some() {
-                    return Err(GalileoError::Configuration(
-                        "custom tile provider cannot be used together with a cache controller"
-                            .into(),
-                    ));
+            ProviderType::DynamicUrl(loader) => {
+                let loader = *loader;
+                loader.validate_url_template()?;
+                if !subdomains.is_empty() {
+                    loader.set_subdomains(subdomains);
+                }
+                loader.validate_subdomains()?;
+                if let Some(pixel_ratio) = pixel_ratio {
+                    loader.set_pixel_ratio(pixel_ratio);
                 }
+                let loader = match high_dpi_suffix {
+                    Some(suffix) => loader.with_high_dpi_suffix(suffix),
+                    None => loader,
+                };
+                let loader = loader.with_tile_schema(tile_schema.clone());
+
+                let loader = loader.with_headers(headers);
+                let loader = match retry_policy {
+                    Some(retry_policy) => loader.with_retry_policy(retry_policy),
+                    None => loader,
+                };
+                let loader = match timeout {
+                    Some(timeout) => loader.with_timeout(timeout),
+                    None => loader,
+                };
+                let loader = match max_concurrent_requests {
+                    Some(max_requests) => loader.with_max_concurrent_requests(max_requests),
+                    None => loader,
+                };
+                let loader = match zoom_range {
+                    Some((min_zoom, max_zoom)) => loader.with_zoom_range(min_zoom, max_zoom),
+                    None => loader,
+                };
 
-                raster_tile_provider
+                VectorTileProvider::new(Arc::new(loader), Arc::new(processor))
             }
+            ProviderType::Loader(loader) => VectorTileProvider::new(loader, Arc::new(processor)),
+            ProviderType::Custom(raster_tile_provider) => raster_tile_provider,
+        };
+
+        let provider = match decode_error_fallback {
+            Some(fallback) => provider.with_decode_error_fallback(fallback),
+            None => provider,
+        };
+
+        let provider = match filter {
+            Some(filter) => provider.with_filter(filter),
+            None => provider,
         };
 
-        let style = style.unwrap_or_else(Self::default_style);
+        let mut style = style.unwrap_or_else(Self::default_style);
+        if let Some(scale_factor) = scale_factor {
+            style.scale_factor = scale_factor;
+        }
 
         let mut layer = VectorTileLayer::new(provider, style, tile_schema, attribution);
+        if let Some(scale_factor) = scale_factor {
+            layer.set_scale_factor(scale_factor);
+        }
         if let Some(messenger) = messenger {
             layer.set_messenger(messenger);
         }
@@ -474,7 +1042,10 @@ impl VectorTileLayerBuilder {
         Ok(layer)
     }
 
-    fn create_processor(tile_schema: TileSchema) -> impl VectorTileProcessor {
+    fn create_processor(
+        tile_schema: TileSchema,
+        _thread_count: Option<usize>,
+    ) -> impl VectorTileProcessor {
         #[cfg(target_arch = "wasm32")]
         {
             crate::platform::web::vt_processor::WebWorkerVtProcessor::new(
@@ -485,7 +1056,12 @@ impl VectorTileLayerBuilder {
 
         #[cfg(not(target_arch = "wasm32"))]
         {
-            crate::platform::native::vt_processor::ThreadVtProcessor::new(tile_schema.clone())
+            let processor =
+                crate::platform::native::vt_processor::ThreadVtProcessor::new(tile_schema.clone());
+            match _thread_count {
+                Some(thread_count) => processor.with_thread_count(thread_count),
+                None => processor,
+            }
         }
     }
 
@@ -496,19 +1072,22 @@ impl VectorTileLayerBuilder {
                     layer_name: None,
                     properties: Default::default(),
                     symbol: VectorTileSymbol::Line(VectorTileLineSymbol {
-                        width: 1.0,
-                        stroke_color: Color::BLACK,
+                        width: 1.0.into(),
+                        stroke_color: Color::BLACK.into(),
+                        opacity: 1.0.into(),
                     }),
                 },
                 StyleRule {
                     layer_name: None,
                     properties: Default::default(),
                     symbol: VectorTileSymbol::Polygon(VectorTilePolygonSymbol {
-                        fill_color: Color::GRAY,
+                        fill_color: Color::GRAY.into(),
+                        opacity: 1.0.into(),
                     }),
                 },
             ],
             background: Color::WHITE,
+            ..Default::default()
         }
     }
 }
@@ -527,6 +1106,17 @@ mod tests {
             .clone()
     }
 
+    #[test]
+    fn with_decode_error_fallback_sets_provider_fallback() {
+        let fallback = DecodeErrorFallback::Parent;
+        let layer = VectorTileLayerBuilder::new_rest(|_| unimplemented!())
+            .with_decode_error_fallback(fallback)
+            .build()
+            .unwrap();
+
+        assert_eq!(layer.provider().decode_error_fallback(), fallback);
+    }
+
     #[test]
     fn with_file_cache_replaces_cache_controller() {
         let cache = FileCacheController::new("target").unwrap();
@@ -558,6 +1148,15 @@ mod tests {
         assert_compact_debug_snapshot!(result, @r#"Err(Configuration("custom tile provider cannot be used together with a cache controller"))"#);
     }
 
+    #[test]
+    fn with_file_cache_fails_build_if_dynamic_url_loader() {
+        let result = VectorTileLayerBuilder::new_dynamic_url("https://example.com/{z}/{x}/{y}.pbf")
+            .with_file_cache("target")
+            .build();
+
+        assert_compact_debug_snapshot!(result, @r#"Err(Configuration("dynamic URL tile loader (use `new_dynamic_url_with_cache` to set its cache) cannot be used together with a cache controller"))"#);
+    }
+
     #[test]
     fn with_cache_controller_replaces_file_cache() {
         let cache = FileCacheController::new("target").unwrap();
@@ -580,6 +1179,136 @@ mod tests {
         assert_compact_debug_snapshot!(result, @r#"Err(Configuration("custom tile provider cannot be used together with a cache controller"))"#);
     }
 
+    #[test]
+    fn with_retry_policy_fails_build_if_custom_provider() {
+        let provider = custom_provider();
+        let result = VectorTileLayerBuilder::new_with_provider(provider)
+            .with_retry_policy(RetryPolicy::new(3, Duration::from_millis(100)))
+            .build();
+
+        assert!(result.is_err());
+        assert_compact_debug_snapshot!(result, @r#"Err(Configuration("custom tile provider cannot be used together with a retry policy"))"#);
+    }
+
+    #[test]
+    fn with_header_fails_build_if_custom_provider() {
+        let provider = custom_provider();
+        let result = VectorTileLayerBuilder::new_with_provider(provider)
+            .with_header("X-Api-Key", "secret")
+            .build();
+
+        assert!(result.is_err());
+        assert_compact_debug_snapshot!(result, @r#"Err(Configuration("custom tile provider cannot be used together with request headers"))"#);
+    }
+
+    #[test]
+    fn with_timeout_fails_build_if_custom_provider() {
+        let provider = custom_provider();
+        let result = VectorTileLayerBuilder::new_with_provider(provider)
+            .with_timeout(Duration::from_secs(1))
+            .build();
+
+        assert!(result.is_err());
+        assert_compact_debug_snapshot!(result, @r#"Err(Configuration("custom tile provider cannot be used together with a request timeout"))"#);
+    }
+
+    #[test]
+    fn with_max_concurrent_requests_fails_build_if_custom_provider() {
+        let provider = custom_provider();
+        let result = VectorTileLayerBuilder::new_with_provider(provider)
+            .with_max_concurrent_requests(4)
+            .build();
+
+        assert!(result.is_err());
+        assert_compact_debug_snapshot!(result, @r#"Err(Configuration("custom tile provider cannot be used together with a request limit"))"#);
+    }
+
+    #[test]
+    fn with_tessellation_threads_fails_build_if_custom_provider() {
+        let provider = custom_provider();
+        let result = VectorTileLayerBuilder::new_with_provider(provider)
+            .with_tessellation_threads(2)
+            .build();
+
+        assert!(result.is_err());
+        assert_compact_debug_snapshot!(result, @r#"Err(Configuration("custom tile provider cannot be used together with a tessellation thread count"))"#);
+    }
+
+    #[test]
+    fn with_tessellation_threads_builds_with_custom_loader() {
+        let loader = WebVtLoader::new(None, |_| unimplemented!(), false);
+        let result = VectorTileLayerBuilder::new_with_loader(loader)
+            .with_tessellation_threads(1)
+            .build();
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn with_scale_factor_overrides_style_scale_factor() {
+        let layer = VectorTileLayerBuilder::new_rest(|_| unimplemented!())
+            .with_style(VectorTileStyle {
+                scale_factor: 3.0,
+                ..VectorTileStyle::default()
+            })
+            .with_scale_factor(2.0)
+            .build()
+            .expect("failed to build layer");
+
+        assert_eq!(layer.style().scale_factor, 2.0);
+    }
+
+    #[test]
+    fn with_retry_policy_fails_build_if_custom_loader() {
+        let loader = WebVtLoader::new(None, |_| unimplemented!(), false);
+        let result = VectorTileLayerBuilder::new_with_loader(loader)
+            .with_retry_policy(RetryPolicy::default())
+            .build();
+
+        assert!(result.is_err());
+        assert_compact_debug_snapshot!(result, @r#"Err(Configuration("custom tile provider cannot be used together with a retry policy"))"#);
+    }
+
+    #[test]
+    fn with_subdomains_fails_build_if_not_dynamic_url() {
+        let result = VectorTileLayerBuilder::new_rest(|_| unimplemented!())
+            .with_subdomains(["a", "b"])
+            .build();
+
+        assert!(result.is_err());
+        assert_compact_debug_snapshot!(result, @r#"Err(Configuration("subdomains can only be used with a dynamic URL tile loader"))"#);
+    }
+
+    #[test]
+    fn with_pixel_ratio_fails_build_if_not_dynamic_url() {
+        let result = VectorTileLayerBuilder::new_rest(|_| unimplemented!())
+            .with_pixel_ratio(2.0)
+            .build();
+
+        assert!(result.is_err());
+        assert_compact_debug_snapshot!(result, @r#"Err(Configuration("high DPI settings can only be used with a dynamic URL tile loader"))"#);
+    }
+
+    #[test]
+    fn invalid_url_template_fails_build() {
+        let result =
+            VectorTileLayerBuilder::new_dynamic_url("https://tiles.example.com/{z}/{x}.pbf")
+                .build();
+        assert!(matches!(result, Err(GalileoError::Configuration(_))));
+    }
+
+    #[test]
+    fn subdomain_placeholder_requires_subdomains() {
+        let template = "https://{s}.tiles.example.com/{z}/{x}/{y}.pbf";
+        assert!(VectorTileLayerBuilder::new_dynamic_url(template)
+            .build()
+            .is_err());
+        assert!(VectorTileLayerBuilder::new_dynamic_url(template)
+            .with_subdomains(["a", "b", "c"])
+            .build()
+            .is_ok());
+    }
+
     #[test]
     fn with_offline_mode_incompatible_with_custom_provider() {
         let provider = custom_provider();
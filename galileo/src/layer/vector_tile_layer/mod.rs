@@ -15,13 +15,13 @@ use parking_lot::Mutex;
 pub use vector_tile::VectorTile;
 
 use crate::layer::attribution::Attribution;
-use crate::layer::vector_tile_layer::style::VectorTileStyle;
+use crate::layer::vector_tile_layer::style::{VectorTileHighlight, VectorTileStyle};
 use crate::layer::vector_tile_layer::tile_provider::{VectorTileProvider, VtStyleId};
 use crate::layer::Layer;
 use crate::messenger::Messenger;
 use crate::render::render_bundle::RenderBundle;
 use crate::render::{Canvas, PackedBundle, PolygonPaint, RenderOptions};
-use crate::tile_schema::TileSchema;
+use crate::tile_schema::{TileIndex, TileSchema};
 use crate::view::MapView;
 use crate::Color;
 
@@ -38,17 +38,19 @@ use super::tiles::TilesContainer;
 pub struct VectorTileLayer {
     tile_provider: VectorTileProvider,
     tile_schema: TileSchema,
-    style_id: VtStyleId,
+    style_ids: Mutex<StyleIds>,
     displayed_tiles: TilesContainer<VtStyleId, VectorTileProvider>,
     prev_background: Mutex<Option<PreviousBackground>>,
     attribution: Option<Attribution>,
+    highlight: Option<VectorTileHighlight>,
+    scale_factor: Option<f32>,
 }
 
 impl std::fmt::Debug for VectorTileLayer {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("RasterTileLayer")
             .field("tile_schema", &self.tile_schema)
-            .field("style_id", &self.style_id)
+            .field("style_id", &self.style_id())
             .finish()
     }
 }
@@ -59,9 +61,21 @@ struct PreviousBackground {
     replaced_at: web_time::Instant,
 }
 
+/// Ids of the styles of the layer registered in the tile provider.
+///
+/// The styles are re-registered when the scale factor of the canvas changes, which happens during rendering, so the ids
+/// are stored behind a mutex.
+#[derive(Debug, Copy, Clone)]
+struct StyleIds {
+    layer: VtStyleId,
+    highlight: Option<VtStyleId>,
+}
+
 impl Layer for VectorTileLayer {
     fn render(&self, view: &MapView, canvas: &mut dyn Canvas) {
+        self.apply_scale_factor(self.scale_factor.unwrap_or_else(|| canvas.scale_factor()));
         self.update_displayed_tiles(view, canvas);
+        let highlight_bundles = self.highlight_bundles(view, canvas);
 
         let Some(background_bundle) = self.create_background_bundle(view, canvas) else {
             // View is impossible to render
@@ -71,15 +85,22 @@ impl Layer for VectorTileLayer {
         let displayed_tiles = self.displayed_tiles.tiles.lock();
         let to_render: Vec<(&dyn PackedBundle, f32)> = std::iter::once((&*background_bundle, 1.0))
             .chain(displayed_tiles.iter().map(|v| (&*v.bundle, v.opacity)))
+            .chain(highlight_bundles.iter().map(|bundle| (&**bundle, 1.0)))
             .collect();
 
         canvas.draw_bundles_with_opacity(&to_render, RenderOptions::default());
     }
 
     fn prepare(&self, view: &MapView) {
+        self.tile_provider.free_evicted_tiles();
+
+        let style_ids = *self.style_ids.lock();
         if let Some(iter) = self.tile_schema.iter_tiles(view) {
             for index in iter {
-                self.tile_provider.load_tile(index, self.style_id);
+                self.tile_provider.load_tile(index, style_ids.layer);
+                if let Some(highlight_style_id) = style_ids.highlight {
+                    self.tile_provider.load_tile(index, highlight_style_id);
+                }
             }
         }
     }
@@ -103,19 +124,32 @@ impl Layer for VectorTileLayer {
     fn attribution(&self) -> Option<Attribution> {
         self.attribution.clone()
     }
+
+    fn is_ready(&self, view: &MapView) -> bool {
+        let Some(mut indices) = self.tile_schema.iter_tiles(view) else {
+            return true;
+        };
+
+        let style_id = self.style_id();
+        indices.all(|index| self.tile_provider.is_tile_ready(index, style_id))
+    }
 }
 
 impl VectorTileLayer {
     /// Style of the layer.
     pub fn style(&self) -> Arc<VectorTileStyle> {
         self.tile_provider
-            .get_style(self.style_id)
+            .get_style(self.style_id())
             .unwrap_or_default()
     }
 
+    fn style_id(&self) -> VtStyleId {
+        self.style_ids.lock().layer
+    }
+
     /// Creates a new layer with the given url source.
     pub fn new(
-        mut tile_provider: VectorTileProvider,
+        tile_provider: VectorTileProvider,
         style: VectorTileStyle,
         tile_schema: TileSchema,
         attribution: Option<Attribution>,
@@ -124,24 +158,29 @@ impl VectorTileLayer {
         Self {
             tile_provider: tile_provider.clone(),
             tile_schema: tile_schema.clone(),
-            style_id,
+            style_ids: Mutex::new(StyleIds {
+                layer: style_id,
+                highlight: None,
+            }),
             displayed_tiles: TilesContainer::new(tile_schema, tile_provider),
             prev_background: Default::default(),
             attribution,
+            highlight: None,
+            scale_factor: None,
         }
     }
 
     fn update_displayed_tiles(&self, view: &MapView, canvas: &dyn Canvas) {
-        let Some(tile_iter) = self.tile_schema.iter_tiles(view) else {
+        let Some(needed_indices) = self.displayed_tiles.visible_tiles(view) else {
             return;
         };
 
-        let needed_indices: Vec<_> = tile_iter.collect();
+        let style_id = self.style_id();
         self.tile_provider
-            .pack_tiles(&needed_indices, self.style_id, canvas);
+            .pack_tiles(&needed_indices, style_id, canvas);
         let requires_redraw = self
             .displayed_tiles
-            .update_displayed_tiles(needed_indices, self.style_id);
+            .update_displayed_tiles(needed_indices, style_id);
 
         if requires_redraw {
             self.tile_provider.request_redraw();
@@ -153,16 +192,129 @@ impl VectorTileLayer {
     }
 
     /// Change style of the layer and redraw it.
+    ///
+    /// The scale factor of the new style is replaced with the one the layer is currently rendered with.
     pub fn update_style(&mut self, style: VectorTileStyle) {
-        let new_style_id = self.tile_provider.add_style(style);
-        if let Some(curr_style) = self.tile_provider.get_style(self.style_id) {
-            *self.prev_background.lock() = Some(PreviousBackground {
+        let style_ids = self.style_ids.get_mut();
+        let scale_factor = self
+            .tile_provider
+            .get_style(style_ids.layer)
+            .map_or(style.scale_factor, |style| style.scale_factor);
+        Self::replace_layer_style(
+            &self.tile_provider,
+            &self.prev_background,
+            style_ids,
+            VectorTileStyle {
+                scale_factor,
+                ..style
+            },
+        );
+    }
+
+    /// Sets the scale factor of the display the layer is rendered to and redraws the layer. Font sizes of the labels
+    /// given in points are multiplied by it. See [`VectorTileStyle::scale_factor`].
+    ///
+    /// Once set, the scale factor of the canvas the layer is drawn to is ignored.
+    pub fn set_scale_factor(&mut self, scale_factor: f32) {
+        self.scale_factor = Some(scale_factor);
+        self.apply_scale_factor(scale_factor);
+    }
+
+    /// Re-registers the layer and highlight styles with the given scale factor if it differs from the current one.
+    fn apply_scale_factor(&self, scale_factor: f32) {
+        let mut style_ids = self.style_ids.lock();
+        let Some(style) = self.tile_provider.get_style(style_ids.layer) else {
+            return;
+        };
+        if style.scale_factor == scale_factor {
+            return;
+        }
+
+        Self::replace_layer_style(
+            &self.tile_provider,
+            &self.prev_background,
+            &mut style_ids,
+            VectorTileStyle {
+                scale_factor,
+                ..(*style).clone()
+            },
+        );
+
+        if let Some(prev) = style_ids.highlight.take() {
+            self.tile_provider.drop_style(prev);
+        }
+        style_ids.highlight = self
+            .highlight
+            .as_ref()
+            .map(|highlight| self.add_highlight_style(highlight, scale_factor));
+
+        self.tile_provider.request_redraw();
+    }
+
+    fn replace_layer_style(
+        tile_provider: &VectorTileProvider,
+        prev_background: &Mutex<Option<PreviousBackground>>,
+        style_ids: &mut StyleIds,
+        style: VectorTileStyle,
+    ) {
+        let new_style_id = tile_provider.add_style(style);
+        if let Some(curr_style) = tile_provider.get_style(style_ids.layer) {
+            *prev_background.lock() = Some(PreviousBackground {
                 color: curr_style.background,
                 replaced_at: web_time::Instant::now(),
             });
         }
-        self.tile_provider.drop_style(self.style_id);
-        self.style_id = new_style_id;
+        tile_provider.drop_style(style_ids.layer);
+        style_ids.layer = new_style_id;
+    }
+
+    fn add_highlight_style(&self, highlight: &VectorTileHighlight, scale_factor: f32) -> VtStyleId {
+        self.tile_provider.add_style(VectorTileStyle {
+            scale_factor,
+            ..highlight.to_style()
+        })
+    }
+
+    /// Highlights features matching the given highlight on top of the layer, or removes the highlight if `None`
+    /// is given.
+    ///
+    /// The highlight is registered in the tile provider as a separate style, so it is prepared in background from the
+    /// already loaded tile data the same way as the layer style, and the tiles are not downloaded again.
+    pub fn set_highlight(&mut self, highlight: Option<VectorTileHighlight>) {
+        if let Some(prev) = self.style_ids.get_mut().highlight.take() {
+            self.tile_provider.drop_style(prev);
+        }
+
+        let scale_factor = self.style().scale_factor;
+        let highlight_style_id = highlight
+            .as_ref()
+            .map(|highlight| self.add_highlight_style(highlight, scale_factor));
+        self.style_ids.get_mut().highlight = highlight_style_id;
+        self.highlight = highlight;
+        self.tile_provider.request_redraw();
+    }
+
+    /// Currently set highlight of the layer.
+    pub fn highlight(&self) -> Option<&VectorTileHighlight> {
+        self.highlight.as_ref()
+    }
+
+    /// Returns the packed highlight bundles of the visible tiles that are already prepared. The tiles that are not
+    /// prepared yet are highlighted on one of the next redraws.
+    fn highlight_bundles(&self, view: &MapView, canvas: &dyn Canvas) -> Vec<Arc<dyn PackedBundle>> {
+        let Some(style_id) = self.style_ids.lock().highlight else {
+            return vec![];
+        };
+        let Some(indices) = self.displayed_tiles.visible_tiles(view) else {
+            return vec![];
+        };
+
+        self.tile_provider.pack_tiles(&indices, style_id, canvas);
+
+        indices
+            .into_iter()
+            .filter_map(|index| self.tile_provider.get_tile(index, style_id))
+            .collect()
     }
 
     /// Returns features, visible in the layer at the given point with the given map view.
@@ -250,7 +402,7 @@ impl VectorTileLayer {
             ]),
             vec![],
         );
-        let style = self.tile_provider.get_style(self.style_id)?;
+        let style = self.tile_provider.get_style(self.style_id())?;
 
         let mut prev_background = self.prev_background.lock();
         let color = match *prev_background {
@@ -274,11 +426,17 @@ impl VectorTileLayer {
             None => style.background,
         };
 
-        bundle.add_polygon(&bounds, &PolygonPaint { color }, view.resolution());
+        bundle.add_polygon(&bounds, &PolygonPaint::new(color), view.resolution());
 
         Some(canvas.pack_bundle(&bundle))
     }
 
+    /// Loads the tiles with the given indices into the cache of the tile loader without rendering
+    /// them. See [`VectorTileProvider::prefetch`].
+    pub fn prefetch(&self, indices: impl IntoIterator<Item = TileIndex>) {
+        self.tile_provider.prefetch(indices);
+    }
+
     /// Returns the reference to the layer's tile provider.
     pub fn provider(&self) -> &VectorTileProvider {
         &self.tile_provider
@@ -287,37 +445,155 @@ impl VectorTileLayer {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
+    use galileo_mvt::MvtTile;
+
     use super::*;
+    use crate::layer::vector_tile_layer::tile_provider::VtProcessor;
     use crate::platform::native::vt_processor::ThreadVtProcessor;
     use crate::tests::TestTileLoader;
 
     fn test_layer() -> VectorTileLayer {
         let tile_schema = TileSchema::web(18);
-        let mut provider = VectorTileProvider::new(
+        let provider = VectorTileProvider::new(
             Arc::new(TestTileLoader {}),
             Arc::new(ThreadVtProcessor::new(tile_schema.clone())),
         );
 
-        let style_id = provider.add_style(VectorTileStyle::default());
-        VectorTileLayer {
-            tile_provider: provider.clone(),
-            tile_schema: TileSchema::web(18),
-            style_id,
-            displayed_tiles: TilesContainer::new(tile_schema, provider),
-            prev_background: Default::default(),
-            attribution: None,
-        }
+        VectorTileLayer::new(provider, VectorTileStyle::default(), tile_schema, None)
+    }
+
+    fn highlight_style(layer: &VectorTileLayer) -> Option<Arc<VectorTileStyle>> {
+        let style_id = layer.style_ids.lock().highlight?;
+        layer.tile_provider.get_style(style_id)
+    }
+
+    #[test]
+    fn set_scale_factor_updates_layer_and_highlight_styles() {
+        let mut layer = test_layer();
+        layer.set_highlight(Some(VectorTileHighlight::property_match(
+            "name",
+            "a",
+            Color::RED,
+        )));
+
+        layer.set_scale_factor(2.0);
+        assert_eq!(layer.style().scale_factor, 2.0);
+        let highlight_style = highlight_style(&layer).expect("highlight style is registered");
+        assert_eq!(highlight_style.scale_factor, 2.0);
+    }
+
+    #[test]
+    fn apply_scale_factor_reregisters_styles() {
+        let mut layer = test_layer();
+        layer.set_highlight(Some(VectorTileHighlight::property_match(
+            "name",
+            "a",
+            Color::RED,
+        )));
+        let prev_style_id = layer.style_id();
+
+        layer.apply_scale_factor(1.5);
+        assert_eq!(layer.style().scale_factor, 1.5);
+        assert_eq!(
+            highlight_style(&layer).map(|style| style.scale_factor),
+            Some(1.5)
+        );
+        assert!(layer.tile_provider.get_style(prev_style_id).is_none());
+        assert!(layer.scale_factor.is_none());
+    }
+
+    #[test]
+    fn update_style_keeps_scale_factor() {
+        let mut layer = test_layer();
+        layer.set_scale_factor(2.0);
+
+        layer.update_style(VectorTileStyle::default());
+        assert_eq!(layer.style().scale_factor, 2.0);
     }
 
     #[test]
     fn update_style_drops_previous_style() {
         let mut layer = test_layer();
-        let style_id = layer.style_id;
+        let style_id = layer.style_id();
         assert!(layer.tile_provider.get_style(style_id).is_some());
 
         layer.update_style(VectorTileStyle::default());
-        let new_style_id = layer.style_id;
+        let new_style_id = layer.style_id();
         assert!(layer.tile_provider.get_style(new_style_id).is_some());
         assert!(layer.tile_provider.get_style(style_id).is_none());
     }
+
+    fn point_feature(name: &str) -> MvtFeature {
+        MvtFeature {
+            id: None,
+            properties: HashMap::from([(
+                "name".to_string(),
+                galileo_mvt::MvtValue::String(name.to_string()),
+            )]),
+            geometry: MvtGeometry::Point(vec![Point2::new(0.5, 0.5)]),
+        }
+    }
+
+    fn tile_with(names: &[&str]) -> MvtTile {
+        MvtTile {
+            layers: vec![galileo_mvt::MvtLayer {
+                name: "roads".to_string(),
+                features: names.iter().map(|name| point_feature(name)).collect(),
+                properties: vec!["name".to_string()],
+                size: 4096,
+            }],
+        }
+    }
+
+    fn highlighted_vertices(layer: &VectorTileLayer, names: &[&str]) -> usize {
+        let style = highlight_style(layer).expect("highlight style is registered");
+        let mut bundle = RenderBundle::default();
+        VtProcessor::prepare(
+            &tile_with(names),
+            &mut bundle,
+            TileIndex::new(0, 0, 0),
+            &style,
+            &layer.tile_schema,
+        )
+        .expect("highlight is prepared");
+
+        bundle.world_set.poly_tessellation.vertices.len()
+    }
+
+    #[test]
+    fn highlight_adds_geometry_only_for_matching_features() {
+        let mut layer = test_layer();
+        layer.set_highlight(Some(VectorTileHighlight::property_match(
+            "name",
+            "Main",
+            Color::RED,
+        )));
+
+        let single = highlighted_vertices(&layer, &["Main"]);
+        assert!(single > 0);
+        assert_eq!(highlighted_vertices(&layer, &["Other"]), 0);
+        assert_eq!(
+            highlighted_vertices(&layer, &["Main", "Other", "Main", "Side"]),
+            single * 2
+        );
+    }
+
+    #[test]
+    fn highlight_can_be_removed() {
+        let mut layer = test_layer();
+        layer.set_highlight(Some(VectorTileHighlight::property_match(
+            "name",
+            "Main",
+            Color::RED,
+        )));
+        assert!(layer.highlight().is_some());
+        let style_id = layer.style_ids.lock().highlight.expect("highlight is set");
+        assert!(layer.tile_provider.get_style(style_id).is_some());
+
+        layer.set_highlight(None);
+        assert!(layer.highlight().is_none());
+        assert!(layer.tile_provider.get_style(style_id).is_none());
+    }
 }
@@ -2,6 +2,7 @@
 
 use std::collections::HashMap;
 
+pub use expression::{Interpolatable, StyleExpression, StyleValue};
 use galileo_mvt::{MvtFeature, MvtGeometry};
 use serde::{Deserialize, Serialize};
 
@@ -10,10 +11,12 @@ use crate::render::text::TextStyle;
 use crate::render::{LineCap, LinePaint, PolygonPaint};
 use crate::Color;
 
+mod expression;
+
 /// Style of a vector tile layer. This specifies how each feature in a tile should be rendered.
 ///
 /// <div class="warning">This exact type is experimental and is likely to change in near future.</div>
-#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct VectorTileStyle {
     /// Rules for feature to be drawn. Rules are traversed in sequence until a rule that corresponds to a current feature
     /// is found, and that rule is used for drawing. If no rule corresponds to the feature, default symbol is used.
@@ -21,6 +24,30 @@ pub struct VectorTileStyle {
 
     /// Background color of tiles.
     pub background: Color,
+
+    /// Scale factor of the display the tiles are rendered to. Font sizes of the labels given in points are multiplied
+    /// by it. See [`RenderBundle::with_scale_factor`].
+    ///
+    /// A [`VectorTileLayer`](super::VectorTileLayer) replaces this value with the scale factor of the canvas it is
+    /// drawn to, unless one is fixed with [`VectorTileLayer::set_scale_factor`](super::VectorTileLayer::set_scale_factor).
+    ///
+    /// [`RenderBundle::with_scale_factor`]: crate::render::render_bundle::RenderBundle::with_scale_factor
+    #[serde(default = "default_scale_factor")]
+    pub scale_factor: f32,
+}
+
+impl Default for VectorTileStyle {
+    fn default() -> Self {
+        Self {
+            rules: vec![],
+            background: Color::default(),
+            scale_factor: default_scale_factor(),
+        }
+    }
+}
+
+fn default_scale_factor() -> f32 {
+    1.0
 }
 
 impl VectorTileStyle {
@@ -79,9 +106,10 @@ pub struct StyleRule {
 /// Symbol of an object in a vector tile.
 ///
 /// An the object has incompatible type with the symbol, the object is not renderred.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
 pub enum VectorTileSymbol {
     /// Do not render object.
+    #[default]
     None,
     /// Symbol for a point object.
     #[serde(rename = "point")]
@@ -97,12 +125,6 @@ pub enum VectorTileSymbol {
     Label(VectorTileLabelSymbol),
 }
 
-impl Default for VectorTileSymbol {
-    fn default() -> Self {
-        Self::None
-    }
-}
-
 impl VectorTileSymbol {
     pub(crate) fn line(&self) -> Option<&VectorTileLineSymbol> {
         match self {
@@ -134,55 +156,81 @@ impl VectorTileSymbol {
 }
 
 /// Symbol for point geometries.
-#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct VectorTilePointSymbol {
     /// Size of the point.
-    pub size: f64,
+    pub size: StyleValue<f64>,
     /// Color of the point.
-    pub color: Color,
+    pub color: StyleValue<Color>,
+    /// Opacity of the point from 0 to 1, multiplied with the alpha channel of the color.
+    #[serde(default = "default_opacity")]
+    pub opacity: StyleValue<f64>,
 }
 
-impl From<VectorTilePointSymbol> for PointPaint<'_> {
-    fn from(value: VectorTilePointSymbol) -> Self {
-        PointPaint::circle(value.color, value.size as f32)
+impl VectorTilePointSymbol {
+    /// Paint of the feature in a tile of the given zoom level.
+    pub fn paint(&self, zoom: f64, feature: &MvtFeature) -> PointPaint<'static> {
+        let color = self.color.evaluate(zoom, feature);
+        PointPaint::circle(
+            apply_opacity(color, self.opacity.evaluate(zoom, feature)),
+            self.size.evaluate(zoom, feature) as f32,
+        )
     }
 }
 
 /// Symbol for line geometries.
-#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct VectorTileLineSymbol {
     /// Width of the line in pixels.
-    pub width: f64,
+    pub width: StyleValue<f64>,
     /// Color of the line in pixels.
-    pub stroke_color: Color,
+    pub stroke_color: StyleValue<Color>,
+    /// Opacity of the line from 0 to 1, multiplied with the alpha channel of the color.
+    #[serde(default = "default_opacity")]
+    pub opacity: StyleValue<f64>,
 }
 
-impl From<VectorTileLineSymbol> for LinePaint {
-    fn from(value: VectorTileLineSymbol) -> Self {
-        Self {
-            color: value.stroke_color,
-            width: value.width,
+impl VectorTileLineSymbol {
+    /// Paint of the feature in a tile of the given zoom level.
+    pub fn paint(&self, zoom: f64, feature: &MvtFeature) -> LinePaint {
+        let color = self.stroke_color.evaluate(zoom, feature);
+        LinePaint {
+            color: apply_opacity(color, self.opacity.evaluate(zoom, feature)),
+            width: self.width.evaluate(zoom, feature),
             offset: 0.0,
             line_cap: LineCap::Butt,
+            dash_pattern: None,
+            dash_offset: 0.0,
         }
     }
 }
 
 /// Symbol for polygon geometries.
-#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct VectorTilePolygonSymbol {
     /// Color of the fill of polygon.
-    pub fill_color: Color,
+    pub fill_color: StyleValue<Color>,
+    /// Opacity of the fill from 0 to 1, multiplied with the alpha channel of the color.
+    #[serde(default = "default_opacity")]
+    pub opacity: StyleValue<f64>,
 }
 
-impl From<VectorTilePolygonSymbol> for PolygonPaint {
-    fn from(value: VectorTilePolygonSymbol) -> Self {
-        Self {
-            color: value.fill_color,
-        }
+impl VectorTilePolygonSymbol {
+    /// Paint of the feature in a tile of the given zoom level.
+    pub fn paint(&self, zoom: f64, feature: &MvtFeature) -> PolygonPaint {
+        let color = self.fill_color.evaluate(zoom, feature);
+        PolygonPaint::new(apply_opacity(color, self.opacity.evaluate(zoom, feature)))
     }
 }
 
+fn default_opacity() -> StyleValue<f64> {
+    StyleValue::Constant(1.0)
+}
+
+fn apply_opacity(color: Color, opacity: f64) -> Color {
+    color.with_alpha((color.a() as f64 * opacity.clamp(0.0, 1.0)).round() as u8)
+}
+
 /// Symbol of a point geometry that is renderred as text label on the map.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct VectorTileLabelSymbol {
@@ -192,6 +240,73 @@ pub struct VectorTileLabelSymbol {
     pub text_style: TextStyle,
 }
 
+/// Highlight of the features of a vector tile layer that have the given property values.
+///
+/// Highlighted features are drawn with the highlight symbols on top of the layer, in addition to
+/// their normal style. See [`VectorTileLayer::set_highlight`](super::VectorTileLayer::set_highlight).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct VectorTileHighlight {
+    /// If set, only features from the given layer are highlighted.
+    pub layer_name: Option<String>,
+    /// Attributes a feature must have with the given values to be highlighted.
+    pub properties: HashMap<String, String>,
+    /// Symbol for highlighted point features.
+    pub point: VectorTilePointSymbol,
+    /// Symbol for highlighted line features.
+    pub line: VectorTileLineSymbol,
+    /// Symbol for highlighted polygon features.
+    pub polygon: VectorTilePolygonSymbol,
+}
+
+impl VectorTileHighlight {
+    /// Highlight of all features with the property `key` equal to `value`, drawn with the given color.
+    pub fn property_match(key: impl Into<String>, value: impl Into<String>, color: Color) -> Self {
+        Self {
+            layer_name: None,
+            properties: HashMap::from([(key.into(), value.into())]),
+            point: VectorTilePointSymbol {
+                size: 8.0.into(),
+                color: color.into(),
+                opacity: default_opacity(),
+            },
+            line: VectorTileLineSymbol {
+                width: 4.0.into(),
+                stroke_color: color.into(),
+                opacity: default_opacity(),
+            },
+            polygon: VectorTilePolygonSymbol {
+                fill_color: color.into(),
+                opacity: default_opacity(),
+            },
+        }
+    }
+
+    /// Limits the highlight to the features of the given layer.
+    pub fn with_layer_name(mut self, layer_name: impl Into<String>) -> Self {
+        self.layer_name = Some(layer_name.into());
+        self
+    }
+
+    /// Style that draws only the highlighted features.
+    pub fn to_style(&self) -> VectorTileStyle {
+        let rule = |symbol| StyleRule {
+            layer_name: self.layer_name.clone(),
+            properties: self.properties.clone(),
+            symbol,
+        };
+
+        VectorTileStyle {
+            rules: vec![
+                rule(VectorTileSymbol::Point(self.point.clone())),
+                rule(VectorTileSymbol::Line(self.line.clone())),
+                rule(VectorTileSymbol::Polygon(self.polygon.clone())),
+            ],
+            background: Color::TRANSPARENT,
+            ..Default::default()
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -199,8 +314,9 @@ mod tests {
     #[test]
     fn symbol_serialization_point() {
         let symbol = VectorTileSymbol::Point(VectorTilePointSymbol {
-            size: 10.0,
-            color: Color::BLACK,
+            size: 10.0.into(),
+            color: Color::BLACK.into(),
+            opacity: default_opacity(),
         });
 
         let json = serde_json::to_string_pretty(&symbol).unwrap();
@@ -212,6 +328,56 @@ mod tests {
     }
 
     #[test]
+    fn symbol_without_expressions_deserializes_from_json() {
+        let symbol: VectorTileSymbol =
+            serde_json::from_str(r##"{ "line": { "stroke_color": "#e1cec1", "width": 1.6 } }"##)
+                .unwrap();
+        assert_eq!(
+            symbol,
+            VectorTileSymbol::Line(VectorTileLineSymbol {
+                width: 1.6.into(),
+                stroke_color: Color::from_hex("#e1cec1").into(),
+                opacity: default_opacity(),
+            })
+        );
+    }
+
+    #[test]
+    fn line_paint_evaluates_expressions() {
+        let symbol: VectorTileLineSymbol = serde_json::from_str(
+            r##"{
+                "width": { "interpolate": { "stops": [[10, 1.0], [14, 5.0]] } },
+                "stroke_color": {
+                    "match": {
+                        "property": "class",
+                        "cases": { "motorway": "#ff0000" },
+                        "default": "#000000"
+                    }
+                },
+                "opacity": { "step": { "stops": [[0, 0.5], [12, 1.0]] } }
+            }"##,
+        )
+        .unwrap();
+        let feature = MvtFeature {
+            id: None,
+            properties: HashMap::from([(
+                "class".to_string(),
+                galileo_mvt::MvtValue::String("motorway".to_string()),
+            )]),
+            geometry: MvtGeometry::Point(vec![]),
+        };
+
+        let paint = symbol.paint(11.0, &feature);
+        assert_eq!(paint.width, 2.0);
+        assert_eq!(paint.color, Color::RED.with_alpha(128));
+
+        let paint = symbol.paint(14.0, &feature);
+        assert_eq!(paint.width, 5.0);
+        assert_eq!(paint.color, Color::RED);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
     fn serialize_with_bincode() {
         let rule = StyleRule {
             layer_name: None,
@@ -0,0 +1,327 @@
+use std::collections::HashMap;
+
+use galileo_mvt::MvtFeature;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::Color;
+
+/// Value of a vector tile symbol parameter that can depend on the zoom level of the tile and on
+/// the properties of the feature.
+///
+/// In JSON styles a constant is written as a plain value, and an expression as an object with
+/// a single key naming the expression:
+///
+/// ```
+/// use galileo::layer::vector_tile_layer::style::StyleValue;
+/// use galileo::Color;
+/// use galileo_mvt::MvtFeature;
+///
+/// let width: StyleValue<f64> =
+///     serde_json::from_str(r#"{ "interpolate": { "stops": [[5, 1.0], [15, 6.0]] } }"#).unwrap();
+/// let color: StyleValue<Color> = serde_json::from_str(
+///     r##"{ "match": { "property": "class", "cases": { "motorway": "#e892a2" }, "default": "#ffffff" } }"##,
+/// )
+/// .unwrap();
+/// let constant: StyleValue<f64> = serde_json::from_str("2.5").unwrap();
+///
+/// let feature = MvtFeature {
+///     id: None,
+///     properties: Default::default(),
+///     geometry: galileo_mvt::MvtGeometry::Point(vec![]),
+/// };
+/// assert_eq!(width.evaluate(10.0, &feature), 3.5);
+/// assert_eq!(color.evaluate(10.0, &feature), Color::WHITE);
+/// assert_eq!(constant.evaluate(10.0, &feature), 2.5);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum StyleValue<T> {
+    /// The same value for all features.
+    Constant(T),
+    /// Value calculated for each feature.
+    Expression(StyleExpression<T>),
+}
+
+/// Expression that calculates a value of a vector tile symbol parameter. See [`StyleValue`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StyleExpression<T> {
+    /// Value of the last stop with the zoom level not greater than the zoom level of the tile, or
+    /// of the first stop if the tile zoom level is less than all of them.
+    Step {
+        /// Pairs of zoom levels and values, sorted by zoom level.
+        stops: Vec<(f64, T)>,
+    },
+    /// Value linearly interpolated between the two stops around the zoom level of the tile. Outside
+    /// of the stops range the value of the closest stop is used.
+    Interpolate {
+        /// Pairs of zoom levels and values, sorted by zoom level.
+        stops: Vec<(f64, T)>,
+    },
+    /// Value selected by the value of a feature property.
+    Match {
+        /// Name of the property.
+        property: String,
+        /// Values for the property values.
+        cases: HashMap<String, T>,
+        /// Value for the features with the property not set or not present in `cases`.
+        default: T,
+    },
+}
+
+/// Types of values that can be interpolated by [`StyleExpression::Interpolate`].
+pub trait Interpolatable: Clone {
+    /// Returns the value at the position `t` (from 0 to 1) between `self` and `other`.
+    fn interpolate(&self, other: &Self, t: f64) -> Self;
+}
+
+impl Interpolatable for f64 {
+    fn interpolate(&self, other: &Self, t: f64) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Interpolatable for Color {
+    fn interpolate(&self, other: &Self, t: f64) -> Self {
+        let channel = |from: u8, to: u8| (from as f64).interpolate(&(to as f64), t).round() as u8;
+        Color::rgba(
+            channel(self.r(), other.r()),
+            channel(self.g(), other.g()),
+            channel(self.b(), other.b()),
+            channel(self.a(), other.a()),
+        )
+    }
+}
+
+impl<T: Interpolatable + Default> StyleValue<T> {
+    /// Calculates the value for the feature in a tile of the given zoom level.
+    ///
+    /// Zoom expressions without stops are evaluated to the default value of the type.
+    pub fn evaluate(&self, zoom: f64, feature: &MvtFeature) -> T {
+        match self {
+            Self::Constant(value) => value.clone(),
+            Self::Expression(expression) => expression.evaluate(zoom, feature),
+        }
+    }
+}
+
+impl<T: Interpolatable + Default> StyleExpression<T> {
+    fn evaluate(&self, zoom: f64, feature: &MvtFeature) -> T {
+        match self {
+            Self::Step { stops } => stops
+                .iter()
+                .take_while(|(stop_zoom, _)| *stop_zoom <= zoom)
+                .last()
+                .or(stops.first())
+                .map(|(_, value)| value.clone())
+                .unwrap_or_default(),
+            Self::Interpolate { stops } => {
+                let next = stops.iter().position(|(stop_zoom, _)| *stop_zoom > zoom);
+                match next {
+                    None => stops.last().map(|(_, value)| value.clone()),
+                    Some(0) => stops.first().map(|(_, value)| value.clone()),
+                    Some(next) => {
+                        let (from_zoom, from) = &stops[next - 1];
+                        let (to_zoom, to) = &stops[next];
+                        Some(from.interpolate(to, (zoom - from_zoom) / (to_zoom - from_zoom)))
+                    }
+                }
+                .unwrap_or_default()
+            }
+            Self::Match {
+                property,
+                cases,
+                default,
+            } => feature
+                .properties
+                .get(property)
+                .and_then(|value| cases.get(&value.to_string()))
+                .unwrap_or(default)
+                .clone(),
+        }
+    }
+}
+
+impl<T> From<T> for StyleValue<T> {
+    fn from(value: T) -> Self {
+        Self::Constant(value)
+    }
+}
+
+impl<T> From<StyleExpression<T>> for StyleValue<T> {
+    fn from(value: StyleExpression<T>) -> Self {
+        Self::Expression(value)
+    }
+}
+
+// Human-readable formats (JSON) write constants as plain values. Other formats (e.g. bincode
+// used to send styles to web workers) cannot deserialize untagged enums, so the variant is
+// written explicitly.
+
+impl<T: Serialize> Serialize for StyleValue<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Constant(value) if serializer.is_human_readable() => value.serialize(serializer),
+            Self::Expression(expression) if serializer.is_human_readable() => {
+                expression.serialize(serializer)
+            }
+            Self::Constant(value) => {
+                serializer.serialize_newtype_variant("StyleValue", 0, "constant", value)
+            }
+            Self::Expression(expression) => {
+                serializer.serialize_newtype_variant("StyleValue", 1, "expression", expression)
+            }
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for StyleValue<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            Ok(UntaggedStyleValue::deserialize(deserializer)?.into())
+        } else {
+            Ok(TaggedStyleValue::deserialize(deserializer)?.into())
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum UntaggedStyleValue<T> {
+    Constant(T),
+    Expression(StyleExpression<T>),
+}
+
+impl<T> From<UntaggedStyleValue<T>> for StyleValue<T> {
+    fn from(value: UntaggedStyleValue<T>) -> Self {
+        match value {
+            UntaggedStyleValue::Constant(value) => Self::Constant(value),
+            UntaggedStyleValue::Expression(expression) => Self::Expression(expression),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "StyleValue", rename_all = "snake_case")]
+enum TaggedStyleValue<T> {
+    Constant(T),
+    Expression(StyleExpression<T>),
+}
+
+impl<T> From<TaggedStyleValue<T>> for StyleValue<T> {
+    fn from(value: TaggedStyleValue<T>) -> Self {
+        match value {
+            TaggedStyleValue::Constant(value) => Self::Constant(value),
+            TaggedStyleValue::Expression(expression) => Self::Expression(expression),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use galileo_mvt::{MvtGeometry, MvtValue};
+
+    use super::*;
+
+    fn feature(class: &str) -> MvtFeature {
+        MvtFeature {
+            id: None,
+            properties: HashMap::from([("class".to_string(), MvtValue::String(class.into()))]),
+            geometry: MvtGeometry::Point(vec![]),
+        }
+    }
+
+    fn stops() -> Vec<(f64, f64)> {
+        vec![(5.0, 1.0), (10.0, 2.0), (15.0, 12.0)]
+    }
+
+    #[test]
+    fn step_selects_last_passed_stop() {
+        let value = StyleValue::Expression(StyleExpression::Step { stops: stops() });
+        let feature = feature("a");
+
+        assert_eq!(value.evaluate(0.0, &feature), 1.0);
+        assert_eq!(value.evaluate(5.0, &feature), 1.0);
+        assert_eq!(value.evaluate(12.0, &feature), 2.0);
+        assert_eq!(value.evaluate(20.0, &feature), 12.0);
+    }
+
+    #[test]
+    fn interpolate_between_stops() {
+        let value = StyleValue::Expression(StyleExpression::Interpolate { stops: stops() });
+        let feature = feature("a");
+
+        assert_eq!(value.evaluate(0.0, &feature), 1.0);
+        assert_eq!(value.evaluate(7.5, &feature), 1.5);
+        assert_eq!(value.evaluate(10.0, &feature), 2.0);
+        assert_eq!(value.evaluate(11.0, &feature), 4.0);
+        assert_eq!(value.evaluate(20.0, &feature), 12.0);
+        assert_eq!(
+            StyleValue::<f64>::Expression(StyleExpression::Interpolate { stops: vec![] })
+                .evaluate(5.0, &feature),
+            0.0
+        );
+    }
+
+    #[test]
+    fn interpolate_colors() {
+        let value = StyleValue::Expression(StyleExpression::Interpolate {
+            stops: vec![
+                (0.0, Color::rgba(0, 0, 0, 0)),
+                (10.0, Color::rgba(100, 200, 50, 255)),
+            ],
+        });
+
+        assert_eq!(
+            value.evaluate(5.0, &feature("a")),
+            Color::rgba(50, 100, 25, 128)
+        );
+    }
+
+    #[test]
+    fn match_property_value() {
+        let value = StyleValue::Expression(StyleExpression::Match {
+            property: "class".to_string(),
+            cases: HashMap::from([("motorway".to_string(), Color::RED)]),
+            default: Color::BLACK,
+        });
+
+        assert_eq!(value.evaluate(0.0, &feature("motorway")), Color::RED);
+        assert_eq!(value.evaluate(0.0, &feature("path")), Color::BLACK);
+    }
+
+    #[test]
+    fn json_constant_is_plain_value() {
+        let value: StyleValue<f64> = serde_json::from_str("2").unwrap();
+        assert_eq!(value, StyleValue::Constant(2.0));
+        assert_eq!(serde_json::to_string(&value).unwrap(), "2.0");
+
+        let value: StyleValue<Color> = serde_json::from_str("\"#ff0000ff\"").unwrap();
+        assert_eq!(value, StyleValue::Constant(Color::RED));
+    }
+
+    #[test]
+    fn json_expression_roundtrip() {
+        let json = r#"{"step":{"stops":[[5.0,1.0],[10.0,2.0],[15.0,12.0]]}}"#;
+        let value: StyleValue<f64> = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            value,
+            StyleValue::Expression(StyleExpression::Step { stops: stops() })
+        );
+        assert_eq!(serde_json::to_string(&value).unwrap(), json);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn bincode_roundtrip() {
+        for value in [
+            StyleValue::Constant(3.0),
+            StyleValue::Expression(StyleExpression::Interpolate { stops: stops() }),
+        ] {
+            let encoded =
+                bincode::serde::encode_to_vec(&value, bincode::config::standard()).unwrap();
+            let (decoded, _): (StyleValue<f64>, _) =
+                bincode::serde::decode_from_slice(&encoded, bincode::config::standard()).unwrap();
+            assert_eq!(decoded, value);
+        }
+    }
+}
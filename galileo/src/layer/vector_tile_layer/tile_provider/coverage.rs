@@ -0,0 +1,250 @@
+//! Checking which tiles of a tile set are available.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use galileo_types::cartesian::Rect;
+use tokio::sync::Semaphore;
+
+use super::loader::{TileLoadError, VectorTileLoader};
+use crate::error::GalileoError;
+use crate::tile_schema::TileIndex;
+use crate::TileSchema;
+
+/// Parameters of [`check_coverage`].
+#[derive(Debug, Clone, Copy)]
+pub struct CoverageOptions {
+    /// Maximum number of tiles requested at the same time.
+    pub concurrency: usize,
+    /// Minimum time between starting two consecutive requests. Use it to stay within the rate limits of the tile
+    /// server.
+    pub min_request_interval: Duration,
+}
+
+impl Default for CoverageOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 8,
+            min_request_interval: Duration::ZERO,
+        }
+    }
+}
+
+/// Availability of a single tile.
+#[derive(Debug, Clone)]
+pub enum TileStatus {
+    /// The tile was loaded successfully.
+    Present,
+    /// The loader reported that the tile does not exist, or the server responded to the tile request with the
+    /// `404 Not Found` status.
+    Absent,
+    /// The tile could not be loaded for another reason.
+    Error(TileLoadError),
+}
+
+impl From<Result<(), TileLoadError>> for TileStatus {
+    fn from(value: Result<(), TileLoadError>) -> Self {
+        match value {
+            Ok(()) => Self::Present,
+            Err(TileLoadError::DoesNotExist)
+            | Err(TileLoadError::Network(GalileoError::HttpStatus(404))) => Self::Absent,
+            Err(err) => Self::Error(err),
+        }
+    }
+}
+
+/// Result of [`check_coverage`]: statuses of all the tiles of a region at one z-level.
+#[derive(Debug, Clone)]
+pub struct TileCoverage {
+    z: u32,
+    // Keyed by `(y, x)` so that iteration goes row by row.
+    statuses: BTreeMap<(i32, i32), TileStatus>,
+}
+
+impl TileCoverage {
+    /// Z-level of the checked tiles.
+    pub fn z(&self) -> u32 {
+        self.z
+    }
+
+    /// Status of the tile with the given indices, or `None` if the tile is outside of the checked region.
+    pub fn status(&self, x: i32, y: i32) -> Option<&TileStatus> {
+        self.statuses.get(&(y, x))
+    }
+
+    /// Iterates over all checked tiles row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (TileIndex, &TileStatus)> + '_ {
+        self.statuses
+            .iter()
+            .map(|(&(y, x), status)| (TileIndex::new(x, y, self.z), status))
+    }
+
+    /// Iterates over the tiles that are not [present](TileStatus::Present).
+    pub fn gaps(&self) -> impl Iterator<Item = (TileIndex, &TileStatus)> + '_ {
+        self.iter()
+            .filter(|(_, status)| !matches!(status, TileStatus::Present))
+    }
+
+    /// Returns `true` if all the tiles of the region are present.
+    pub fn is_complete(&self) -> bool {
+        self.gaps().next().is_none()
+    }
+}
+
+/// Requests all the tiles of `tile_schema` at z-level `z` that intersect `bbox` from the `loader`, and reports
+/// which of them are available.
+///
+/// This is a tool for tile set authors to find gaps in a tile set. Note that the tiles are loaded completely, so
+/// checking large regions can produce a lot of traffic.
+///
+/// Returns an error if the tile schema does not have the given z-level.
+pub async fn check_coverage(
+    loader: Arc<dyn VectorTileLoader>,
+    tile_schema: &TileSchema,
+    bbox: Rect,
+    z: u32,
+    options: CoverageOptions,
+) -> Result<TileCoverage, GalileoError> {
+    let resolution = tile_schema.lod_resolution(z).ok_or_else(|| {
+        GalileoError::Configuration(format!("tile schema does not have z-level {z}"))
+    })?;
+    let tiles: Vec<_> = tile_schema
+        .iter_tiles_over_bbox(resolution, bbox)
+        .into_iter()
+        .flatten()
+        .collect();
+
+    let semaphore = Arc::new(Semaphore::new(options.concurrency.max(1)));
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+
+    for (i, index) in tiles.into_iter().enumerate() {
+        if i > 0 && !options.min_request_interval.is_zero() {
+            crate::async_runtime::sleep(options.min_request_interval).await;
+        }
+
+        let permit = semaphore
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|err| GalileoError::Generic(err.to_string()))?;
+        let loader = loader.clone();
+        let sender = sender.clone();
+        crate::async_runtime::spawn(async move {
+            let status = TileStatus::from(loader.load_shared(index).await.map(|_| ()));
+            drop(permit);
+            let _ = sender.send((index, status));
+        });
+    }
+
+    drop(sender);
+
+    let mut statuses = BTreeMap::new();
+    while let Some((index, status)) = receiver.recv().await {
+        statuses.insert((index.y, index.x), status);
+    }
+
+    Ok(TileCoverage { z, statuses })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use galileo_mvt::MvtTile;
+
+    use super::*;
+
+    struct MockLoader {
+        missing: HashSet<(i32, i32)>,
+        not_found: HashSet<(i32, i32)>,
+        broken: HashSet<(i32, i32)>,
+        running: AtomicUsize,
+        max_running: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl VectorTileLoader for MockLoader {
+        async fn load(&self, index: TileIndex) -> Result<MvtTile, TileLoadError> {
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_running.fetch_max(running, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            self.running.fetch_sub(1, Ordering::SeqCst);
+
+            if self.missing.contains(&(index.x, index.y)) {
+                Err(TileLoadError::DoesNotExist)
+            } else if self.not_found.contains(&(index.x, index.y)) {
+                Err(TileLoadError::Network(GalileoError::HttpStatus(404)))
+            } else if self.broken.contains(&(index.x, index.y)) {
+                Err(TileLoadError::Network(GalileoError::HttpStatus(500)))
+            } else {
+                Ok(MvtTile { layers: vec![] })
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_missing_tiles() {
+        let loader = Arc::new(MockLoader {
+            missing: HashSet::from([(0, 0), (2, 1)]),
+            not_found: HashSet::from([(3, 3)]),
+            broken: HashSet::from([(1, 2)]),
+            running: AtomicUsize::new(0),
+            max_running: AtomicUsize::new(0),
+        });
+        let schema = TileSchema::web(18);
+        let bbox = Rect::new(-20_000_000.0, -20_000_000.0, 20_000_000.0, 20_000_000.0);
+
+        let coverage = check_coverage(
+            loader.clone(),
+            &schema,
+            bbox,
+            2,
+            CoverageOptions {
+                concurrency: 3,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("z-level exists");
+
+        let grid: Vec<String> = (0..4)
+            .map(|y| {
+                (0..4)
+                    .map(|x| match coverage.status(x, y).expect("tile is checked") {
+                        TileStatus::Present => '+',
+                        TileStatus::Absent => '.',
+                        TileStatus::Error(_) => '!',
+                    })
+                    .collect()
+            })
+            .collect();
+        assert_eq!(grid, vec![".+++", "++.+", "+!++", "+++."]);
+        assert_eq!(coverage.gaps().count(), 4);
+        assert!(!coverage.is_complete());
+        assert!(loader.max_running.load(Ordering::SeqCst) <= 3);
+    }
+
+    #[tokio::test]
+    async fn unknown_z_level_is_an_error() {
+        let loader = Arc::new(MockLoader {
+            missing: HashSet::new(),
+            not_found: HashSet::new(),
+            broken: HashSet::new(),
+            running: AtomicUsize::new(0),
+            max_running: AtomicUsize::new(0),
+        });
+        let bbox = Rect::new(0.0, 0.0, 1.0, 1.0);
+
+        let result = check_coverage(
+            loader,
+            &TileSchema::web(5),
+            bbox,
+            10,
+            CoverageOptions::default(),
+        )
+        .await;
+        assert!(result.is_err());
+    }
+}
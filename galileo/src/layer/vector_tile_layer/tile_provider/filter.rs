@@ -0,0 +1,186 @@
+use std::collections::HashSet;
+
+use galileo_mvt::{MvtFeature, MvtLayer, MvtTile};
+use maybe_sync::{MaybeSend, MaybeSync};
+
+/// Selects the source layers and features of vector tiles that are rendered by a vector tile
+/// layer.
+///
+/// The filter is applied to each tile right after it is decoded, so the features it rejects are
+/// never styled or tessellated, and are not returned by the feature queries of the layer.
+///
+/// ```
+/// use galileo::layer::vector_tile_layer::tile_provider::VectorTileFilter;
+///
+/// let filter = VectorTileFilter::new()
+///     .with_layers(["water", "transportation"])
+///     .with_feature_predicate(|layer_name, feature| {
+///         layer_name != "transportation"
+///             || feature
+///                 .properties
+///                 .get("class")
+///                 .is_some_and(|class| class.eq_str("motorway"))
+///     });
+///
+/// assert!(filter.accepts_layer("water"));
+/// assert!(!filter.accepts_layer("buildings"));
+/// ```
+#[derive(Default)]
+pub struct VectorTileFilter {
+    layers: Option<HashSet<String>>,
+    feature_predicate: Option<Box<dyn FeaturePredicate>>,
+}
+
+/// Predicate that selects features of a vector tile by the name of their layer and their
+/// attributes. See [`VectorTileFilter::with_feature_predicate`].
+pub trait FeaturePredicate: Fn(&str, &MvtFeature) -> bool + MaybeSend + MaybeSync {}
+impl<T: Fn(&str, &MvtFeature) -> bool + MaybeSend + MaybeSync> FeaturePredicate for T {}
+
+impl std::fmt::Debug for VectorTileFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VectorTileFilter")
+            .field("layers", &self.layers)
+            .field("feature_predicate", &self.feature_predicate.is_some())
+            .finish()
+    }
+}
+
+impl VectorTileFilter {
+    /// Creates a filter that accepts all layers and features.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only the source layers with the given names are rendered.
+    pub fn with_layers(mut self, layers: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.layers = Some(layers.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Only the features for which the predicate returns `true` are rendered. The predicate is
+    /// called with the name of the source layer of the feature and the feature itself.
+    pub fn with_feature_predicate(mut self, predicate: impl FeaturePredicate + 'static) -> Self {
+        self.feature_predicate = Some(Box::new(predicate));
+        self
+    }
+
+    /// Returns true if the source layer with the given name passes the filter.
+    pub fn accepts_layer(&self, layer_name: &str) -> bool {
+        self.layers
+            .as_ref()
+            .is_none_or(|layers| layers.contains(layer_name))
+    }
+
+    /// Returns true if the feature of the given source layer passes the filter.
+    pub fn accepts_feature(&self, layer_name: &str, feature: &MvtFeature) -> bool {
+        self.accepts_layer(layer_name)
+            && self
+                .feature_predicate
+                .as_ref()
+                .is_none_or(|predicate| predicate(layer_name, feature))
+    }
+
+    /// Returns a copy of the tile containing only the layers and features that pass the filter.
+    pub fn apply(&self, tile: &MvtTile) -> MvtTile {
+        let layers = tile
+            .layers
+            .iter()
+            .filter(|layer| self.accepts_layer(&layer.name))
+            .map(|layer| MvtLayer {
+                name: layer.name.clone(),
+                features: layer
+                    .features
+                    .iter()
+                    .filter(|feature| self.accepts_feature(&layer.name, feature))
+                    .cloned()
+                    .collect(),
+                properties: layer.properties.clone(),
+                size: layer.size,
+            })
+            .collect();
+
+        MvtTile { layers }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use galileo_mvt::{MvtGeometry, MvtValue};
+
+    use super::*;
+
+    fn layer(name: &str, classes: &[&str]) -> MvtLayer {
+        MvtLayer {
+            name: name.to_string(),
+            features: classes
+                .iter()
+                .map(|class| MvtFeature {
+                    id: None,
+                    properties: HashMap::from([(
+                        "class".to_string(),
+                        MvtValue::String(class.to_string()),
+                    )]),
+                    geometry: MvtGeometry::Point(vec![]),
+                })
+                .collect(),
+            properties: vec!["class".to_string()],
+            size: 4096,
+        }
+    }
+
+    fn tile() -> MvtTile {
+        MvtTile {
+            layers: vec![
+                layer("water", &["lake", "river"]),
+                layer("roads", &["motorway", "path"]),
+                layer("buildings", &["house"]),
+            ],
+        }
+    }
+
+    fn summary(tile: &MvtTile) -> Vec<(String, usize)> {
+        tile.layers
+            .iter()
+            .map(|layer| (layer.name.clone(), layer.features.len()))
+            .collect()
+    }
+
+    #[test]
+    fn default_filter_accepts_everything() {
+        let tile = tile();
+        assert_eq!(
+            summary(&VectorTileFilter::new().apply(&tile)),
+            summary(&tile)
+        );
+    }
+
+    #[test]
+    fn filter_by_layers() {
+        let filter = VectorTileFilter::new().with_layers(["water", "roads"]);
+        assert_eq!(
+            summary(&filter.apply(&tile())),
+            vec![("water".to_string(), 2), ("roads".to_string(), 2)]
+        );
+    }
+
+    #[test]
+    fn filter_by_feature_predicate() {
+        let filter = VectorTileFilter::new().with_feature_predicate(|layer_name, feature| {
+            layer_name != "roads"
+                || feature
+                    .properties
+                    .get("class")
+                    .is_some_and(|class| class.eq_str("motorway"))
+        });
+        assert_eq!(
+            summary(&filter.apply(&tile())),
+            vec![
+                ("water".to_string(), 2),
+                ("roads".to_string(), 1),
+                ("buildings".to_string(), 1)
+            ]
+        );
+    }
+}
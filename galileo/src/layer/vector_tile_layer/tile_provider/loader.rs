@@ -3,21 +3,111 @@
 use bytes::Bytes;
 use galileo_mvt::MvtTile;
 use maybe_sync::{MaybeSend, MaybeSync};
+use std::collections::HashMap;
+use std::future::Future;
+use std::ops::RangeInclusive;
 use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::OnceCell;
+use web_time::Instant;
 
 use crate::error::GalileoError;
-use crate::layer::data_provider::{PersistentCacheController, UrlSource};
-use crate::platform::PlatformService;
+#[cfg(all(feature = "mbtiles", not(target_arch = "wasm32")))]
+use crate::layer::data_provider::MbTiles;
+use crate::layer::data_provider::{
+    expand_url_template, high_dpi_suffix, opposite_y, validate_flipped_rows, validate_subdomains,
+    validate_url_template, PersistentCacheController, RetryPolicy, TileFetcher, UrlSource,
+    DEFAULT_HIGH_DPI_SUFFIX,
+};
 use crate::tile_schema::TileIndex;
+use crate::TileSchema;
 
 /// Error that can occur when trying to load a vector tile.
+#[derive(Debug, Clone, Error)]
 pub enum TileLoadError {
     /// Could not connect to the remote server.
-    Network,
+    #[error("failed to load tile: {0}")]
+    Network(#[source] GalileoError),
     /// Tile with the given index does not exist.
+    #[error("tile does not exist")]
     DoesNotExist,
     /// Failed to decode vector tile from the binary data.
-    Decoding,
+    #[error(
+        "failed to decode tile{}: {source}{}",
+        optional_part(" from ", .url),
+        optional_part("; data starts with ", .data_preview)
+    )]
+    Decoding {
+        /// Error returned by the decoder.
+        #[source]
+        source: GalileoError,
+        /// URL the data was loaded from, if known.
+        url: Option<String>,
+        /// Beginning of the data that could not be decoded, with a guess of its content type if
+        /// it is not a vector tile, e.g. an HTML error page served with a `200` status.
+        data_preview: Option<String>,
+    },
+}
+
+impl TileLoadError {
+    /// Creates a [`TileLoadError::Decoding`] error without the information about the data source.
+    pub fn decoding(source: GalileoError) -> Self {
+        Self::Decoding {
+            source,
+            url: None,
+            data_preview: None,
+        }
+    }
+
+    /// Adds the `url` and the beginning of the `data` to a [`TileLoadError::Decoding`] error.
+    /// Other errors are returned unchanged.
+    fn with_source_data(self, url: &str, data: &[u8]) -> Self {
+        match self {
+            Self::Decoding { source, .. } => Self::Decoding {
+                source,
+                url: Some(url.to_string()),
+                data_preview: Some(data_preview(data)),
+            },
+            err => err,
+        }
+    }
+}
+
+fn optional_part(prefix: &str, value: &Option<String>) -> String {
+    value
+        .as_ref()
+        .map(|value| format!("{prefix}{value}"))
+        .unwrap_or_default()
+}
+
+/// Maximum number of bytes of the undecodable data included into [`TileLoadError::Decoding`].
+const DATA_PREVIEW_LENGTH: usize = 32;
+
+/// Describes the beginning of the data: text is quoted, binary data is written as hex bytes.
+fn data_preview(data: &[u8]) -> String {
+    let head = &data[..data.len().min(DATA_PREVIEW_LENGTH)];
+    let ellipsis = if data.len() > head.len() { "..." } else { "" };
+    let is_text = !head.is_empty()
+        && head
+            .iter()
+            .all(|byte| byte.is_ascii_graphic() || byte.is_ascii_whitespace());
+    if !is_text {
+        let hex: Vec<_> = head.iter().map(|byte| format!("{byte:02x}")).collect();
+        return format!("[{}{ellipsis}]", hex.join(" "));
+    }
+
+    let text = String::from_utf8_lossy(head);
+    let trimmed = text.trim_start();
+    let content_type = if trimmed.starts_with('<') {
+        " (looks like HTML or XML, the server may have returned an error page)"
+    } else if trimmed.starts_with('{') || trimmed.starts_with('[') {
+        " (looks like JSON)"
+    } else {
+        " (looks like text)"
+    };
+
+    format!("{:?}{ellipsis}{content_type}", text)
 }
 
 /// Loader for vector tiles.
@@ -26,56 +116,177 @@ pub enum TileLoadError {
 pub trait VectorTileLoader: MaybeSend + MaybeSync {
     /// Load tile with the given index.
     async fn load(&self, index: TileIndex) -> Result<MvtTile, TileLoadError>;
+
+    /// Load tile with the given index wrapped into `Arc`. Vector tile layers load the tiles with
+    /// this method.
+    ///
+    /// Loaders that share one loaded tile between several concurrent calls for the same tile
+    /// override this method to return the shared tile without copying it. The default
+    /// implementation wraps the tile returned by [`VectorTileLoader::load`].
+    async fn load_shared(&self, index: TileIndex) -> Result<Arc<MvtTile>, TileLoadError> {
+        self.load(index).await.map(Arc::new)
+    }
+}
+
+/// Observer of the tile loading lifecycle, e.g. to show a loading indicator or collect server
+/// latency metrics.
+///
+/// All methods do nothing by default. They are called from the loading tasks, so they should
+/// return quickly.
+pub trait TileLoadObserver: MaybeSend + MaybeSync {
+    /// Loading of the tile started. Concurrent requests for the same tile are loaded once and
+    /// reported once.
+    fn on_load_start(&self, _index: TileIndex) {}
+    /// The tile was found in the persistent cache, so no request to the server was sent.
+    fn on_cache_hit(&self, _index: TileIndex) {}
+    /// The tile was loaded and decoded. `byte_size` is the size of the loaded data before
+    /// decompression, and `elapsed` is the time since the loading started.
+    fn on_loaded(&self, _index: TileIndex, _byte_size: usize, _elapsed: Duration) {}
+    /// Loading of the tile failed.
+    fn on_error(&self, _index: TileIndex, _error: &TileLoadError) {}
+}
+
+/// Function that decodes a vector tile from the decompressed tile data.
+///
+/// By default the loaders use [`decode_mvt`]. A custom decoder can be set e.g. to skip broken
+/// features with [`skip_recoverable_errors_decoder`] or to use another MVT parser.
+pub trait MvtDecoder: (Fn(Bytes) -> Result<MvtTile, GalileoError>) + MaybeSend + MaybeSync {}
+impl<T> MvtDecoder for T where
+    T: (Fn(Bytes) -> Result<MvtTile, GalileoError>) + MaybeSend + MaybeSync
+{
+}
+
+/// Default decoder of the vector tiles. Fails if any feature of the tile cannot be decoded.
+pub fn decode_mvt(bytes: Bytes) -> Result<MvtTile, GalileoError> {
+    Ok(MvtTile::decode(bytes, false)?)
+}
+
+/// Decoder that skips the features and layers of the tile that cannot be decoded instead of
+/// failing the whole tile.
+pub fn skip_recoverable_errors_decoder(bytes: Bytes) -> Result<MvtTile, GalileoError> {
+    Ok(MvtTile::decode(bytes, true)?)
 }
 
 /// Load the tile from the Web.
 pub struct WebVtLoader {
-    cache: Option<Box<dyn PersistentCacheController<str, Bytes>>>,
     url_source: Box<dyn UrlSource<TileIndex>>,
-    offline_mode: bool,
+    fetcher: TileFetcher,
+    zoom_range: Option<RangeInclusive<u32>>,
+    in_flight: InFlightLoads,
+    observer: Option<Arc<dyn TileLoadObserver>>,
+    decoder: Box<dyn MvtDecoder>,
 }
 
 impl WebVtLoader {
     /// Create a new instance.
+    ///
+    /// Failed tile requests are not repeated unless a policy is set with
+    /// [`with_retry_policy`](Self::with_retry_policy).
     pub fn new(
         cache: Option<Box<dyn PersistentCacheController<str, Bytes>>>,
         url_source: impl UrlSource<TileIndex> + 'static,
         offline_mode: bool,
     ) -> Self {
         Self {
-            cache,
             url_source: Box::new(url_source),
-            offline_mode,
+            fetcher: TileFetcher::new(cache, offline_mode),
+            zoom_range: None,
+            in_flight: InFlightLoads::default(),
+            observer: None,
+            decoder: Box::new(decode_mvt),
         }
     }
 
-    async fn load_raw(&self, url: &str) -> Result<Bytes, TileLoadError> {
-        if let Some(data) = self.cache.as_ref().and_then(|cache| cache.get(url)) {
-            log::trace!("Cache hit for url {url}");
-            return Ok(data);
-        }
+    /// Switches the offline mode. In offline mode only the tiles from the cache are loaded, and no
+    /// requests are sent to the server.
+    pub fn set_offline_mode(&self, offline_mode: bool) {
+        self.fetcher.set_offline_mode(offline_mode);
+    }
 
-        if self.offline_mode {
-            return Err(TileLoadError::DoesNotExist);
-        }
+    /// Returns true if the loader is in offline mode.
+    pub fn is_offline_mode(&self) -> bool {
+        self.fetcher.is_offline_mode()
+    }
 
-        let bytes = crate::platform::instance()
-            .load_bytes_from_url(url)
-            .await
-            .map_err(|err| match err {
-                GalileoError::NotFound => TileLoadError::DoesNotExist,
-                _ => TileLoadError::Network,
-            })?;
+    /// Replaces the policy of repeating tile requests that failed because of network errors.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.fetcher = self.fetcher.with_retry_policy(retry_policy);
+        self
+    }
 
-        log::info!("Loaded tile from url: {url}");
+    /// Adds an HTTP header that is sent with every tile request, e.g. to authenticate with the
+    /// tile server.
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.fetcher = self.fetcher.with_header(name, value);
+        self
+    }
 
-        if let Some(cache) = &self.cache {
-            if let Err(error) = cache.insert(url, &bytes) {
-                log::warn!("Failed to write persistent cache entry: {error:?}");
-            }
-        }
+    /// Adds HTTP headers that are sent with every tile request.
+    pub fn with_headers(mut self, headers: impl IntoIterator<Item = (String, String)>) -> Self {
+        self.fetcher = self.fetcher.with_headers(headers);
+        self
+    }
 
-        Ok(bytes)
+    /// Sets the maximum time to wait for a response to a tile request. If the server does not
+    /// respond in time, the request fails with [`TileLoadError::Network`].
+    ///
+    /// By default there is no timeout.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.fetcher = self.fetcher.with_timeout(timeout);
+        self
+    }
+
+    /// Limits the number of tile requests sent to the server at the same time. Tiles loaded from
+    /// the cache do not count towards the limit.
+    ///
+    /// By default the number of requests is not limited.
+    pub fn with_max_concurrent_requests(mut self, max_requests: usize) -> Self {
+        self.fetcher = self.fetcher.with_max_concurrent_requests(max_requests);
+        self
+    }
+
+    /// Enables revalidation of the cached tiles. Tiles that were received from the server more than
+    /// `max_age` ago are requested again. See [`TileFetcher::with_max_age`] for details.
+    ///
+    /// By default cached tiles are never revalidated.
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.fetcher = self.fetcher.with_max_age(max_age);
+        self
+    }
+
+    /// Limits the z-levels of the tiles provided by the server. Tiles outside of the range fail
+    /// with [`TileLoadError::DoesNotExist`] without sending a request.
+    pub fn with_zoom_range(mut self, min_zoom: u32, max_zoom: u32) -> Self {
+        self.zoom_range = Some(min_zoom..=max_zoom);
+        self
+    }
+
+    /// Sets the observer notified about the loading of every tile. The same observer can be
+    /// shared by several loaders.
+    pub fn with_observer(mut self, observer: Arc<dyn TileLoadObserver>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Sets the function used to decode the loaded tiles. See [`MvtDecoder`].
+    pub fn with_decoder(mut self, decoder: impl MvtDecoder + 'static) -> Self {
+        self.decoder = Box::new(decoder);
+        self
+    }
+
+    /// Loads the data of the tile with the given index without decoding it.
+    ///
+    /// The data is taken from the cache or requested from the server the same way as by
+    /// [`VectorTileLoader::load`], and is returned as served by the server, so it may be
+    /// compressed with gzip.
+    pub async fn load_bytes(&self, index: TileIndex) -> Result<Bytes, TileLoadError> {
+        check_zoom_range(self.zoom_range.as_ref(), index)?;
+        let url = (self.url_source)(&index);
+        self.load_raw(index, &url).await
+    }
+
+    async fn load_raw(&self, index: TileIndex, url: &str) -> Result<Bytes, TileLoadError> {
+        fetch_tile(&self.fetcher, self.observer.as_deref(), index, url).await
     }
 }
 
@@ -83,18 +294,322 @@ impl WebVtLoader {
 #[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
 impl VectorTileLoader for WebVtLoader {
     async fn load(&self, index: TileIndex) -> Result<MvtTile, TileLoadError> {
-        let url = (self.url_source)(&index);
+        self.load_shared(index).await.map(Arc::unwrap_or_clone)
+    }
 
-        log::trace!("Loading tile {index:?} from url {url}");
-        let bytes = self.load_raw(&url).await?;
+    async fn load_shared(&self, index: TileIndex) -> Result<Arc<MvtTile>, TileLoadError> {
+        check_zoom_range(self.zoom_range.as_ref(), index)?;
+        let url = &(self.url_source)(&index);
+        self.in_flight
+            .load(url, || {
+                log::trace!("Loading tile {index:?} from url {url}");
+                load_and_decode(
+                    index,
+                    url,
+                    &*self.decoder,
+                    self.observer.as_deref(),
+                    self.load_raw(index, url),
+                )
+            })
+            .await
+    }
+}
+
+/// Loader that tries several loaders in order, e.g. a primary tile server, its mirror and an
+/// offline tile pack.
+///
+/// A tile is requested from the next loader only if the previous one failed with
+/// [`TileLoadError::Network`] or [`TileLoadError::DoesNotExist`]. [`TileLoadError::Decoding`]
+/// errors are returned immediately, as the data was served but is broken. If all loaders fail,
+/// the error of the last one is returned.
+pub struct ChainedVtLoader {
+    loaders: Vec<Box<dyn VectorTileLoader>>,
+}
 
-        log::trace!("Tile {index:?} loaded. Byte size: {}", bytes.len());
+impl ChainedVtLoader {
+    /// Creates a new instance trying the `loaders` in the given order.
+    pub fn new(loaders: Vec<Box<dyn VectorTileLoader>>) -> Self {
+        Self { loaders }
+    }
 
-        let mvt = MvtTile::decode(bytes, false).map_err(|_| TileLoadError::Decoding)?;
+    /// Adds a loader to the end of the chain.
+    pub fn with_loader(mut self, loader: impl VectorTileLoader + 'static) -> Self {
+        self.loaders.push(Box::new(loader));
+        self
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+impl VectorTileLoader for ChainedVtLoader {
+    async fn load(&self, index: TileIndex) -> Result<MvtTile, TileLoadError> {
+        self.load_shared(index).await.map(Arc::unwrap_or_clone)
+    }
+
+    async fn load_shared(&self, index: TileIndex) -> Result<Arc<MvtTile>, TileLoadError> {
+        let mut last_error = TileLoadError::DoesNotExist;
+        for (position, loader) in self.loaders.iter().enumerate() {
+            match loader.load_shared(index).await {
+                Ok(tile) => return Ok(tile),
+                Err(err @ TileLoadError::Decoding { .. }) => return Err(err),
+                Err(err) => {
+                    log::debug!("Loader {position} failed to load tile {index:?}: {err}");
+                    last_error = err;
+                }
+            }
+        }
+
+        Err(last_error)
+    }
+}
+
+/// Loads vector tiles from an [`MbTiles`] file, e.g. for applications that must work fully
+/// offline.
+///
+/// Tiles outside of the zoom range given in the file metadata fail with
+/// [`TileLoadError::DoesNotExist`] without querying the file.
+///
+/// ```no_run
+/// use galileo::layer::vector_tile_layer::tile_provider::loader::MbTilesLoader;
+/// use galileo::layer::vector_tile_layer::VectorTileLayerBuilder;
+///
+/// let loader = MbTilesLoader::open("data/tiles.mbtiles")?;
+/// let layer = VectorTileLayerBuilder::new_with_loader(loader).build()?;
+/// # Ok::<(), galileo::error::GalileoError>(())
+/// ```
+#[cfg(all(feature = "mbtiles", not(target_arch = "wasm32")))]
+pub struct MbTilesLoader {
+    mbtiles: MbTiles,
+    decoder: Box<dyn MvtDecoder>,
+}
+
+#[cfg(all(feature = "mbtiles", not(target_arch = "wasm32")))]
+impl MbTilesLoader {
+    /// Opens the MBTiles file at `path`. See [`MbTilesLoader::new`].
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self, GalileoError> {
+        Self::new(MbTiles::open(path)?)
+    }
+
+    /// Creates a loader that reads the tiles from the given file.
+    ///
+    /// Returns an error if the metadata of the file specifies a format other than `pbf`.
+    pub fn new(mbtiles: MbTiles) -> Result<Self, GalileoError> {
+        if let Some(format) = mbtiles.metadata().format() {
+            if !mbtiles.metadata().is_vector() {
+                return Err(GalileoError::Configuration(format!(
+                    "MBTiles file contains tiles in '{format}' format, but vector tiles are expected"
+                )));
+            }
+        }
+
+        Ok(Self {
+            mbtiles,
+            decoder: Box::new(decode_mvt),
+        })
+    }
+
+    /// Sets the function used to decode the loaded tiles. See [`MvtDecoder`].
+    pub fn with_decoder(mut self, decoder: impl MvtDecoder + 'static) -> Self {
+        self.decoder = Box::new(decoder);
+        self
+    }
+
+    /// The file the tiles are read from.
+    pub fn mbtiles(&self) -> &MbTiles {
+        &self.mbtiles
+    }
+}
+
+#[cfg(all(feature = "mbtiles", not(target_arch = "wasm32")))]
+#[async_trait::async_trait]
+impl VectorTileLoader for MbTilesLoader {
+    async fn load(&self, index: TileIndex) -> Result<MvtTile, TileLoadError> {
+        check_zoom_range(self.mbtiles.metadata().zoom_range().as_ref(), index)?;
+
+        let bytes = self
+            .mbtiles
+            .load_tile_data(index)
+            .await
+            .map_err(TileLoadError::Network)?
+            .ok_or(TileLoadError::DoesNotExist)?;
+        let tile = (self.decoder)(bytes.clone()).map_err(|err| {
+            TileLoadError::decoding(err).with_source_data(&format!("mbtiles {index:?}"), &bytes)
+        })?;
+
+        Ok(tile)
+    }
+}
+
+/// Decodes the tile data returned by the `load_raw` future with the `decoder`, notifying the
+/// `observer` about the progress.
+async fn load_and_decode(
+    index: TileIndex,
+    url: &str,
+    decoder: &dyn MvtDecoder,
+    observer: Option<&dyn TileLoadObserver>,
+    load_raw: impl Future<Output = Result<Bytes, TileLoadError>>,
+) -> Result<Arc<MvtTile>, TileLoadError> {
+    let started = Instant::now();
+    if let Some(observer) = observer {
+        observer.on_load_start(index);
+    }
+
+    let result = async {
+        let bytes = load_raw.await?;
+        let byte_size = bytes.len();
+
+        log::trace!("Tile {index:?} loaded. Byte size: {byte_size}");
+
+        let decoded = decompress(bytes.clone())
+            .map_err(TileLoadError::decoding)
+            .and_then(|data| decoder(data).map_err(TileLoadError::decoding));
+        let mvt = decoded.map_err(|err| err.with_source_data(url, &bytes))?;
 
         log::trace!("Tile {index:?} successfully decoded");
 
-        Ok(mvt)
+        Ok((Arc::new(mvt), byte_size))
+    }
+    .await;
+
+    match (result, observer) {
+        (Ok((tile, byte_size)), Some(observer)) => {
+            observer.on_loaded(index, byte_size, started.elapsed());
+            Ok(tile)
+        }
+        (Err(err), Some(observer)) => {
+            observer.on_error(index, &err);
+            Err(err)
+        }
+        (result, None) => result.map(|(tile, _)| tile),
+    }
+}
+
+/// Decompresses the tile data if it is compressed with gzip or zlib.
+///
+/// Compression is detected by the leading bytes of the data, as some servers send compressed tiles
+/// without setting the `Content-Encoding` header.
+#[cfg(feature = "gzip")]
+pub(crate) fn decompress(bytes: Bytes) -> Result<Bytes, GalileoError> {
+    use std::io::Read;
+
+    let mut decompressed = vec![];
+    let result = match bytes.as_ref() {
+        [0x1f, 0x8b, ..] => flate2::read::GzDecoder::new(&bytes[..]).read_to_end(&mut decompressed),
+        // Zlib header with deflate compression, see RFC 1950.
+        [cmf, flg, ..]
+            if cmf & 0x0f == 8 && (*cmf as u16 * 256 + *flg as u16).is_multiple_of(31) =>
+        {
+            flate2::read::ZlibDecoder::new(&bytes[..]).read_to_end(&mut decompressed)
+        }
+        _ => return Ok(bytes),
+    };
+
+    result.map_err(|err| GalileoError::Generic(format!("failed to decompress tile: {err}")))?;
+    Ok(decompressed.into())
+}
+
+#[cfg(not(feature = "gzip"))]
+pub(crate) fn decompress(bytes: Bytes) -> Result<Bytes, GalileoError> {
+    Ok(bytes)
+}
+
+type SharedLoad = Arc<OnceCell<Result<Arc<MvtTile>, TileLoadError>>>;
+
+/// Tiles that are currently being loaded, keyed by their URL.
+///
+/// Concurrent loads of the same tile wait for the first one to complete instead of requesting and
+/// decoding the tile again.
+#[derive(Default)]
+struct InFlightLoads {
+    loads: parking_lot::Mutex<HashMap<String, SharedLoad>>,
+}
+
+impl InFlightLoads {
+    async fn load<Fut>(
+        &self,
+        url: &str,
+        load: impl FnOnce() -> Fut,
+    ) -> Result<Arc<MvtTile>, TileLoadError>
+    where
+        Fut: Future<Output = Result<Arc<MvtTile>, TileLoadError>>,
+    {
+        let shared = self
+            .loads
+            .lock()
+            .entry(url.to_string())
+            .or_default()
+            .clone();
+        let result = shared.get_or_init(load).await.clone();
+
+        let mut loads = self.loads.lock();
+        if loads
+            .get(url)
+            .is_some_and(|current| Arc::ptr_eq(current, &shared))
+        {
+            loads.remove(url);
+        }
+
+        result
+    }
+}
+
+/// Fails with [`TileLoadError::DoesNotExist`] if the z-level of the tile is outside of the `zoom_range`.
+fn check_zoom_range(
+    zoom_range: Option<&RangeInclusive<u32>>,
+    index: TileIndex,
+) -> Result<(), TileLoadError> {
+    match zoom_range {
+        Some(range) if !range.contains(&index.z) => Err(TileLoadError::DoesNotExist),
+        _ => Ok(()),
+    }
+}
+
+/// Loads the tile data from the `url` with the `fetcher`, notifying the `observer` about cache hits.
+async fn fetch_tile(
+    fetcher: &TileFetcher,
+    observer: Option<&dyn TileLoadObserver>,
+    index: TileIndex,
+    url: &str,
+) -> Result<Bytes, TileLoadError> {
+    let fetched = fetcher.fetch(url).await.map_err(|err| match err {
+        GalileoError::NotFound => TileLoadError::DoesNotExist,
+        err => TileLoadError::Network(err),
+    })?;
+
+    if let (true, Some(observer)) = (fetched.from_cache, observer) {
+        observer.on_cache_hit(index);
+    }
+
+    Ok(fetched.bytes)
+}
+
+/// Convention of numbering tile rows in tile URLs.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TileScheme {
+    /// Rows are numbered from the top of the map (XYZ or "slippy map" convention).
+    #[default]
+    Xyz,
+    /// Rows are numbered from the bottom of the map, as in the Tile Map Service specification.
+    Tms,
+}
+
+impl TileScheme {
+    /// Returns the row number of the tile of [`TileSchema::web`] to be used in the URL. For other
+    /// schemas the TMS rows are counted as for the `{-y}` placeholder, see
+    /// [`DynamicUrlVtLoader::with_tile_schema`].
+    ///
+    /// ```
+    /// use galileo::layer::vector_tile_layer::tile_provider::loader::TileScheme;
+    /// use galileo::tile_schema::TileIndex;
+    ///
+    /// assert_eq!(TileScheme::Xyz.url_y(&TileIndex::new(5, 2, 3)), 2);
+    /// assert_eq!(TileScheme::Tms.url_y(&TileIndex::new(5, 2, 3)), 5);
+    /// ```
+    pub fn url_y(&self, index: &TileIndex) -> i64 {
+        match self {
+            TileScheme::Xyz => index.y as i64,
+            TileScheme::Tms => opposite_y(index, None),
+        }
     }
 }
 
@@ -107,6 +622,9 @@ impl VectorTileLoader for WebVtLoader {
 /// # Example
 ///
 /// ```no_run
+/// use std::time::Duration;
+///
+/// use galileo::layer::data_provider::RetryPolicy;
 /// use galileo::layer::vector_tile_layer::tile_provider::loader::{VectorTileLoader, DynamicUrlVtLoader};
 /// use galileo::tile_schema::TileIndex;
 /// use std::sync::Arc;
@@ -115,11 +633,13 @@ impl VectorTileLoader for WebVtLoader {
 /// let loader = DynamicUrlVtLoader::new(
 ///     "https://vector.tiles.com/{z}/{x}/{y}.pbf",
 ///     None,
-///     false
-/// );
+///     false,
+/// )
+/// .with_header("Authorization", "Bearer <token>")
+/// .with_retry_policy(RetryPolicy::new(3, Duration::from_millis(200)));
 ///
 /// // Update the URL template and parameters
-/// loader.update_url_template("https://custom.vector.tiles.com/{z}/{x}/{y}.pbf");
+/// loader.update_url_template("https://custom.vector.tiles.com/{z}/{x}/{y}.pbf").expect("valid template");
 /// loader.update_parameters(vec![("api_key", "your_api_key"), ("style", "dark")]);
 ///
 /// # tokio_test::block_on(async {
@@ -129,31 +649,242 @@ impl VectorTileLoader for WebVtLoader {
 pub struct DynamicUrlVtLoader {
     url_template: Arc<parking_lot::RwLock<String>>,
     parameters: Arc<parking_lot::RwLock<Vec<(String, String)>>>,
-    cache: Option<Box<dyn PersistentCacheController<str, Bytes>>>,
-    offline_mode: bool,
+    subdomains: Arc<parking_lot::RwLock<Vec<String>>>,
+    pixel_ratio: Arc<parking_lot::RwLock<f32>>,
+    high_dpi_suffix: String,
+    tile_schema: Option<TileSchema>,
+    tile_scheme: Arc<parking_lot::RwLock<TileScheme>>,
+    url_source: Arc<parking_lot::RwLock<Option<Box<dyn UrlSource<TileIndex>>>>>,
+    fetcher: TileFetcher,
+    zoom_range: Option<RangeInclusive<u32>>,
+    in_flight: InFlightLoads,
+    observer: Option<Arc<dyn TileLoadObserver>>,
+    decoder: Box<dyn MvtDecoder>,
 }
 
 impl DynamicUrlVtLoader {
     /// Creates a new instance of the dynamic URL vector tile loader.
+    ///
+    /// Failed tile requests are not repeated unless a policy is set with
+    /// [`with_retry_policy`](Self::with_retry_policy).
+    ///
+    /// An invalid URL template is only reported in the log, and no tiles can be loaded with it. Use
+    /// [`try_new`](Self::try_new) to get an error instead.
     pub fn new(
         url_template: impl Into<String>,
         cache: Option<Box<dyn PersistentCacheController<str, Bytes>>>,
         offline_mode: bool,
     ) -> Self {
+        let url_template = url_template.into();
+        if let Err(err) = validate_url_template(&url_template) {
+            log::warn!("{err}");
+        }
+
         Self {
-            url_template: Arc::new(parking_lot::RwLock::new(url_template.into())),
+            url_template: Arc::new(parking_lot::RwLock::new(url_template)),
             parameters: Arc::new(parking_lot::RwLock::new(Vec::new())),
-            cache,
-            offline_mode,
+            subdomains: Arc::new(parking_lot::RwLock::new(Vec::new())),
+            pixel_ratio: Arc::new(parking_lot::RwLock::new(1.0)),
+            high_dpi_suffix: DEFAULT_HIGH_DPI_SUFFIX.to_string(),
+            tile_schema: None,
+            tile_scheme: Arc::new(parking_lot::RwLock::new(TileScheme::default())),
+            url_source: Arc::new(parking_lot::RwLock::new(None)),
+            fetcher: TileFetcher::new(cache, offline_mode),
+            zoom_range: None,
+            in_flight: InFlightLoads::default(),
+            observer: None,
+            decoder: Box::new(decode_mvt),
         }
     }
 
+    /// Creates a new instance of the dynamic URL vector tile loader, returning an error if the URL
+    /// template is invalid (see [`validate_url_template`]).
+    pub fn try_new(
+        url_template: impl Into<String>,
+        cache: Option<Box<dyn PersistentCacheController<str, Bytes>>>,
+        offline_mode: bool,
+    ) -> Result<Self, GalileoError> {
+        let url_template = url_template.into();
+        validate_url_template(&url_template)?;
+
+        Ok(Self::new(url_template, cache, offline_mode))
+    }
+
+    /// Switches the offline mode. In offline mode only the tiles from the cache are loaded, and no
+    /// requests are sent to the server.
+    pub fn set_offline_mode(&self, offline_mode: bool) {
+        self.fetcher.set_offline_mode(offline_mode);
+    }
+
+    /// Returns true if the loader is in offline mode.
+    pub fn is_offline_mode(&self) -> bool {
+        self.fetcher.is_offline_mode()
+    }
+
+    /// Replaces the policy of repeating tile requests that failed because of network errors.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.fetcher = self.fetcher.with_retry_policy(retry_policy);
+        self
+    }
+
+    /// Adds an HTTP header that is sent with every tile request, e.g. to authenticate with the
+    /// tile server.
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.fetcher = self.fetcher.with_header(name, value);
+        self
+    }
+
+    /// Adds HTTP headers that are sent with every tile request.
+    pub fn with_headers(mut self, headers: impl IntoIterator<Item = (String, String)>) -> Self {
+        self.fetcher = self.fetcher.with_headers(headers);
+        self
+    }
+
+    /// Sets the maximum time to wait for a response to a tile request. If the server does not
+    /// respond in time, the request fails with [`TileLoadError::Network`].
+    ///
+    /// By default there is no timeout.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.fetcher = self.fetcher.with_timeout(timeout);
+        self
+    }
+
+    /// Limits the number of tile requests sent to the server at the same time. Tiles loaded from
+    /// the cache do not count towards the limit.
+    ///
+    /// By default the number of requests is not limited.
+    pub fn with_max_concurrent_requests(mut self, max_requests: usize) -> Self {
+        self.fetcher = self.fetcher.with_max_concurrent_requests(max_requests);
+        self
+    }
+
+    /// Enables revalidation of the cached tiles. Tiles that were received from the server more than
+    /// `max_age` ago are requested again. See [`TileFetcher::with_max_age`] for details.
+    ///
+    /// By default cached tiles are never revalidated.
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.fetcher = self.fetcher.with_max_age(max_age);
+        self
+    }
+
+    /// Limits the z-levels of the tiles provided by the server. Tiles outside of the range fail
+    /// with [`TileLoadError::DoesNotExist`] without sending a request.
+    pub fn with_zoom_range(mut self, min_zoom: u32, max_zoom: u32) -> Self {
+        self.zoom_range = Some(min_zoom..=max_zoom);
+        self
+    }
+
+    /// Sets the suffix substituted for the `{r}` placeholder of the URL template on high-DPI
+    /// displays, e.g. `@2x` or `_hd`. See [`set_pixel_ratio`](Self::set_pixel_ratio).
+    ///
+    /// Defaults to [`DEFAULT_HIGH_DPI_SUFFIX`].
+    pub fn with_high_dpi_suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.high_dpi_suffix = suffix.into();
+        self
+    }
+
+    /// Sets the tile schema of the tileset. It is used to calculate the row number for the `{-y}`
+    /// placeholder of the URL template and for the `{y}` placeholder with [`TileScheme::Tms`] (see
+    /// [`TileSchema::opposite_y`]).
+    ///
+    /// Without the schema, the rows are counted as in [`TileSchema::web`].
+    pub fn with_tile_schema(mut self, tile_schema: TileSchema) -> Self {
+        self.tile_schema = Some(tile_schema);
+        self
+    }
+
+    /// Sets the observer notified about the loading of every tile. The same observer can be
+    /// shared by several loaders.
+    pub fn with_observer(mut self, observer: Arc<dyn TileLoadObserver>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Sets the function used to decode the loaded tiles. See [`MvtDecoder`].
+    pub fn with_decoder(mut self, decoder: impl MvtDecoder + 'static) -> Self {
+        self.decoder = Box::new(decoder);
+        self
+    }
+
     /// Updates the URL template used for generating tile URLs.
     ///
-    /// The template should contain placeholders for {z}, {x}, and {y} coordinates.
+    /// The template must contain placeholders for {z}, {x}, and {y} coordinates, or a {q}
+    /// placeholder for the Bing-style quadkey of the tile (see
+    /// [`quadkey`](crate::layer::data_provider::quadkey)).
     /// Example: "https://vector.tiles.com/{z}/{x}/{y}.pbf"
-    pub fn update_url_template(&self, new_template: impl Into<String>) {
-        *self.url_template.write() = new_template.into();
+    ///
+    /// If the template is invalid (see [`validate_url_template`]), contains `{s}` placeholder but no
+    /// subdomains are set, or contains `{-y}` placeholder while the tile scheme is
+    /// [`TileScheme::Tms`], an error is returned and the previous template is kept.
+    pub fn update_url_template(&self, new_template: impl Into<String>) -> Result<(), GalileoError> {
+        let new_template = new_template.into();
+        validate_url_template(&new_template)?;
+        validate_subdomains(&new_template, &self.subdomains.read())?;
+        validate_flipped_rows(&new_template, *self.tile_scheme.read() == TileScheme::Tms)?;
+        *self.url_template.write() = new_template;
+
+        Ok(())
+    }
+
+    /// Sets a custom function to build tile URLs, e.g. to sign the requests.
+    ///
+    /// While the custom URL source is set, the URL template, parameters, subdomains and tile scheme
+    /// of the loader are not used.
+    pub fn set_url_source(&self, url_source: impl UrlSource<TileIndex> + 'static) {
+        *self.url_source.write() = Some(Box::new(url_source));
+    }
+
+    /// Removes the custom URL source set by [`DynamicUrlVtLoader::set_url_source()`], so that the
+    /// URLs are built from the URL template again.
+    pub fn clear_url_source(&self) {
+        *self.url_source.write() = None;
+    }
+
+    /// Sets the convention of numbering tile rows used to substitute the `{y}` placeholder.
+    ///
+    /// Defaults to [`TileScheme::Xyz`]. [`TileScheme::Tms`] cannot be used with a URL template that
+    /// contains the `{-y}` placeholder, as both flip the rows. In this case an error is returned
+    /// and the previous scheme is kept.
+    pub fn set_tile_scheme(&self, tile_scheme: TileScheme) -> Result<(), GalileoError> {
+        validate_flipped_rows(&self.url_template.read(), tile_scheme == TileScheme::Tms)?;
+        *self.tile_scheme.write() = tile_scheme;
+
+        Ok(())
+    }
+
+    /// Sets the subdomains substituted for the `{s}` placeholder of the URL template, e.g.
+    /// `["a", "b", "c"]` for `"https://{s}.tiles.example.com/{z}/{x}/{y}.pbf"`.
+    ///
+    /// Requests are spread between the subdomains, but the same tile is always requested from the
+    /// same subdomain.
+    pub fn set_subdomains(&self, subdomains: impl IntoIterator<Item = impl Into<String>>) {
+        *self.subdomains.write() = subdomains.into_iter().map(Into::into).collect();
+    }
+
+    /// Checks that the URL template is valid (see [`validate_url_template`]).
+    pub fn validate_url_template(&self) -> Result<(), GalileoError> {
+        validate_url_template(&self.url_template.read())
+    }
+
+    /// Checks that subdomains are set if the URL template contains `{s}` placeholder.
+    pub fn validate_subdomains(&self) -> Result<(), GalileoError> {
+        validate_subdomains(&self.url_template.read(), &self.subdomains.read())
+    }
+
+    /// Sets the number of physical pixels in a logical pixel of the display.
+    ///
+    /// On high-DPI displays (pixel ratio above 1) the high-DPI suffix is substituted for the `{r}`
+    /// placeholder of the URL template, so that the server returns tiles of double resolution.
+    /// Such tiles cover the same area as the standard ones, so the tile schema stays the same.
+    ///
+    /// Defaults to 1.
+    pub fn set_pixel_ratio(&self, pixel_ratio: f32) {
+        *self.pixel_ratio.write() = pixel_ratio;
+    }
+
+    /// Returns the pixel ratio of the display the tiles are requested for.
+    pub fn pixel_ratio(&self) -> f32 {
+        *self.pixel_ratio.read()
     }
 
     /// Updates the parameters that will be appended to the URL as query parameters.
@@ -182,72 +913,525 @@ impl DynamicUrlVtLoader {
 
     /// Generates the URL for a given tile index using the current template and parameters.
     fn generate_url(&self, index: &TileIndex) -> String {
-        let template = self.url_template.read();
-        let params = self.parameters.read();
+        if let Some(url_source) = &*self.url_source.read() {
+            return url_source(index);
+        }
+
+        let y = match *self.tile_scheme.read() {
+            TileScheme::Xyz => index.y as i64,
+            TileScheme::Tms => opposite_y(index, self.tile_schema.as_ref()),
+        };
+        expand_url_template(
+            &self.url_template.read(),
+            index,
+            y,
+            &self.subdomains.read(),
+            &self.parameters.read(),
+            high_dpi_suffix(*self.pixel_ratio.read(), &self.high_dpi_suffix),
+            self.tile_schema.as_ref(),
+        )
+    }
+
+    /// Loads the data of the tile with the given index without decoding it. See
+    /// [`WebVtLoader::load_bytes`] for details.
+    pub async fn load_bytes(&self, index: TileIndex) -> Result<Bytes, TileLoadError> {
+        check_zoom_range(self.zoom_range.as_ref(), index)?;
+        let url = self.generate_url(&index);
+        self.load_raw(index, &url).await
+    }
+
+    async fn load_raw(&self, index: TileIndex, url: &str) -> Result<Bytes, TileLoadError> {
+        fetch_tile(&self.fetcher, self.observer.as_deref(), index, url).await
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+impl VectorTileLoader for DynamicUrlVtLoader {
+    async fn load(&self, index: TileIndex) -> Result<MvtTile, TileLoadError> {
+        self.load_shared(index).await.map(Arc::unwrap_or_clone)
+    }
+
+    async fn load_shared(&self, index: TileIndex) -> Result<Arc<MvtTile>, TileLoadError> {
+        check_zoom_range(self.zoom_range.as_ref(), index)?;
+        let url = &self.generate_url(&index);
+        self.in_flight
+            .load(url, || {
+                log::trace!("Loading tile {index:?} from url {url}");
+                load_and_decode(
+                    index,
+                    url,
+                    &*self.decoder,
+                    self.observer.as_deref(),
+                    self.load_raw(index, url),
+                )
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use galileo_types::cartesian::Rect;
 
-        let mut url = template
-            .replace("{z}", &index.z.to_string())
-            .replace("{x}", &index.x.to_string())
-            .replace("{y}", &index.y.to_string());
+    use super::*;
+    use crate::layer::data_provider::MemoryCacheController;
 
-        if !params.is_empty() {
-            let query_string: String = params
-                .iter()
-                .map(|(k, v)| format!("{}={}", urlencoding::encode(k), urlencoding::encode(v)))
-                .collect::<Vec<_>>()
-                .join("&");
-            url.push_str("?");
-            url.push_str(&query_string);
+    struct StubLoader(Result<Arc<MvtTile>, TileLoadError>);
+
+    #[async_trait::async_trait]
+    impl VectorTileLoader for StubLoader {
+        async fn load(&self, _index: TileIndex) -> Result<MvtTile, TileLoadError> {
+            self.0.clone().map(Arc::unwrap_or_clone)
         }
 
-        url
+        async fn load_shared(&self, _index: TileIndex) -> Result<Arc<MvtTile>, TileLoadError> {
+            self.0.clone()
+        }
     }
 
-    async fn load_raw(&self, url: &str) -> Result<Bytes, TileLoadError> {
-        if let Some(data) = self.cache.as_ref().and_then(|cache| cache.get(url)) {
-            log::trace!("Cache hit for url {url}");
-            return Ok(data);
+    #[derive(Default)]
+    struct CountingObserver {
+        events: parking_lot::Mutex<Vec<&'static str>>,
+    }
+
+    impl TileLoadObserver for CountingObserver {
+        fn on_load_start(&self, _index: TileIndex) {
+            self.events.lock().push("start");
         }
 
-        if self.offline_mode {
-            return Err(TileLoadError::DoesNotExist);
+        fn on_loaded(&self, _index: TileIndex, byte_size: usize, _elapsed: Duration) {
+            assert_eq!(byte_size, 0);
+            self.events.lock().push("loaded");
         }
 
-        let bytes = crate::platform::instance()
-            .load_bytes_from_url(url)
-            .await
-            .map_err(|err| match err {
-                crate::error::GalileoError::NotFound => TileLoadError::DoesNotExist,
-                _ => TileLoadError::Network,
-            })?;
+        fn on_error(&self, _index: TileIndex, _error: &TileLoadError) {
+            self.events.lock().push("error");
+        }
+    }
 
-        log::info!("Loaded tile from url: {url}");
+    #[tokio::test]
+    async fn observer_is_notified_about_load_result() {
+        let observer = CountingObserver::default();
+        let index = TileIndex::new(0, 0, 0);
+        let decoder =
+            |_: Bytes| -> Result<MvtTile, GalileoError> { Ok(MvtTile { layers: vec![] }) };
 
-        if let Some(cache) = &self.cache {
-            if let Err(error) = cache.insert(url, &bytes) {
-                log::warn!("Failed to write persistent cache entry: {error:?}");
-            }
+        load_and_decode(index, "", &decoder, Some(&observer), async {
+            Ok(Bytes::new())
+        })
+        .await
+        .unwrap();
+        let failed = load_and_decode(index, "", &decoder, Some(&observer), async {
+            Err(network_error())
+        })
+        .await;
+        assert!(failed.is_err());
+
+        assert_eq!(
+            *observer.events.lock(),
+            vec!["start", "loaded", "start", "error"]
+        );
+    }
+
+    fn network_error() -> TileLoadError {
+        TileLoadError::Network(GalileoError::IO)
+    }
+
+    #[tokio::test]
+    async fn decoding_error_contains_url_and_data() {
+        let url = "https://tiles.example.com/1/0/0.pbf";
+        let result = load_and_decode(TileIndex::new(0, 0, 1), url, &decode_mvt, None, async {
+            Ok(Bytes::from_static(
+                b"<html><body>Quota exceeded</body></html>",
+            ))
+        })
+        .await;
+
+        let Err(err @ TileLoadError::Decoding { .. }) = result else {
+            panic!("decoding error expected");
+        };
+        let message = err.to_string();
+        assert!(message.contains(url), "{message}");
+        assert!(message.contains("<html><body>Quota"), "{message}");
+        assert!(message.contains("HTML"), "{message}");
+    }
+
+    #[tokio::test]
+    async fn custom_decoder_is_used() {
+        let decoder = |bytes: Bytes| -> Result<MvtTile, GalileoError> {
+            assert_eq!(bytes, Bytes::from_static(b"custom"));
+            Ok(MvtTile { layers: vec![] })
+        };
+        let result = load_and_decode(TileIndex::new(0, 0, 0), "", &decoder, None, async {
+            Ok(Bytes::from_static(b"custom"))
+        })
+        .await;
+        assert!(result.is_ok());
+
+        let failing = |_: Bytes| -> Result<MvtTile, GalileoError> {
+            Err(GalileoError::Generic("unsupported".into()))
+        };
+        let result = load_and_decode(TileIndex::new(0, 0, 0), "", &failing, None, async {
+            Ok(Bytes::from_static(b"custom"))
+        })
+        .await;
+        assert!(matches!(result, Err(TileLoadError::Decoding { .. })));
+    }
+
+    #[test]
+    fn binary_data_preview_is_hex() {
+        assert_eq!(data_preview(&[0x1a, 0xff]), "[1a ff]");
+        assert_eq!(
+            data_preview(&[0; 40]).matches("00").count(),
+            DATA_PREVIEW_LENGTH
+        );
+    }
+
+    #[tokio::test]
+    async fn chained_loader_falls_back_on_missing_tiles() {
+        let tile = Arc::new(MvtTile { layers: vec![] });
+        let loader = ChainedVtLoader::new(vec![
+            Box::new(StubLoader(Err(network_error()))),
+            Box::new(StubLoader(Err(TileLoadError::DoesNotExist))),
+        ])
+        .with_loader(StubLoader(Ok(tile.clone())));
+
+        let loaded = loader.load_shared(TileIndex::new(0, 0, 0)).await.unwrap();
+        assert!(Arc::ptr_eq(&loaded, &tile));
+    }
+
+    #[tokio::test]
+    async fn chained_loader_stops_on_decoding_error() {
+        let loader = ChainedVtLoader::new(vec![])
+            .with_loader(StubLoader(Err(TileLoadError::decoding(GalileoError::IO))))
+            .with_loader(StubLoader(Ok(Arc::new(MvtTile { layers: vec![] }))));
+        assert!(matches!(
+            loader.load(TileIndex::new(0, 0, 0)).await,
+            Err(TileLoadError::Decoding { .. })
+        ));
+
+        let loader = ChainedVtLoader::new(vec![])
+            .with_loader(StubLoader(Err(TileLoadError::DoesNotExist)))
+            .with_loader(StubLoader(Err(network_error())));
+        assert!(matches!(
+            loader.load(TileIndex::new(0, 0, 0)).await,
+            Err(TileLoadError::Network(_))
+        ));
+    }
+
+    #[test]
+    fn invalid_template_update_keeps_previous_template() {
+        let loader =
+            DynamicUrlVtLoader::new("https://tiles.example.com/{z}/{x}/{y}.pbf", None, false);
+        let index = TileIndex::new(1, 2, 3);
+
+        assert!(loader
+            .update_url_template("https://other.example.com/{z}/{x}.pbf")
+            .is_err());
+        assert_eq!(
+            loader.generate_url(&index),
+            "https://tiles.example.com/3/1/2.pbf"
+        );
+
+        assert!(loader
+            .update_url_template("https://other.example.com/{z}/{x}/{y}.pbf")
+            .is_ok());
+        assert_eq!(
+            loader.generate_url(&index),
+            "https://other.example.com/3/1/2.pbf"
+        );
+    }
+
+    #[test]
+    fn subdomain_placeholder_is_substituted() {
+        let loader =
+            DynamicUrlVtLoader::new("https://tiles.example.com/{z}/{x}/{y}.pbf", None, false);
+
+        assert!(loader
+            .update_url_template("https://{s}.tiles.example.com/{z}/{x}/{y}.pbf")
+            .is_err());
+
+        loader.set_subdomains(["a", "b", "c"]);
+        assert!(loader
+            .update_url_template("https://{s}.tiles.example.com/{z}/{x}/{y}.pbf")
+            .is_ok());
+        assert!(loader.validate_subdomains().is_ok());
+        assert_eq!(
+            loader.generate_url(&TileIndex::new(1, 2, 3)),
+            "https://a.tiles.example.com/3/1/2.pbf"
+        );
+        assert_eq!(
+            loader.generate_url(&TileIndex::new(2, 2, 3)),
+            "https://b.tiles.example.com/3/2/2.pbf"
+        );
+    }
+
+    #[test]
+    fn high_dpi_suffix_depends_on_pixel_ratio() {
+        let loader =
+            DynamicUrlVtLoader::new("https://tiles.example.com/{z}/{x}/{y}{r}.pbf", None, false);
+        let index = TileIndex::new(1, 2, 3);
+
+        assert_eq!(
+            loader.generate_url(&index),
+            "https://tiles.example.com/3/1/2.pbf"
+        );
+
+        loader.set_pixel_ratio(2.0);
+        assert_eq!(
+            loader.generate_url(&index),
+            "https://tiles.example.com/3/1/2@2x.pbf"
+        );
+
+        let loader = loader.with_high_dpi_suffix("_hd");
+        assert_eq!(
+            loader.generate_url(&index),
+            "https://tiles.example.com/3/1/2_hd.pbf"
+        );
+    }
+
+    #[test]
+    fn opposite_y_placeholder_uses_tile_schema() {
+        let loader =
+            DynamicUrlVtLoader::new("https://tiles.example.com/{z}/{x}/{-y}.pbf", None, false);
+        let index = TileIndex::new(5, 2, 3);
+        assert_eq!(
+            loader.generate_url(&index),
+            "https://tiles.example.com/3/5/5.pbf"
+        );
+
+        // The tileset covers only the northern hemisphere, so it has half as many rows.
+        let mut tile_schema = TileSchema::web(18);
+        tile_schema.bounds = Rect::new(
+            tile_schema.bounds.x_min(),
+            0.0,
+            tile_schema.bounds.x_max(),
+            tile_schema.bounds.y_max(),
+        );
+        let loader = loader.with_tile_schema(tile_schema);
+        assert_eq!(
+            loader.generate_url(&index),
+            "https://tiles.example.com/3/5/1.pbf"
+        );
+
+        loader
+            .update_url_template("https://tiles.example.com/{z}/{x}/{y}.pbf")
+            .unwrap();
+        loader.set_tile_scheme(TileScheme::Tms).unwrap();
+        assert_eq!(
+            loader.generate_url(&index),
+            "https://tiles.example.com/3/5/1.pbf"
+        );
+    }
+
+    #[test]
+    fn opposite_y_placeholder_is_rejected_with_tms_scheme() {
+        let loader =
+            DynamicUrlVtLoader::new("https://tiles.example.com/{z}/{x}/{-y}.pbf", None, false);
+        assert!(loader.set_tile_scheme(TileScheme::Tms).is_err());
+        assert_eq!(*loader.tile_scheme.read(), TileScheme::Xyz);
+
+        loader
+            .update_url_template("https://tiles.example.com/{z}/{x}/{y}.pbf")
+            .unwrap();
+        loader.set_tile_scheme(TileScheme::Tms).unwrap();
+        assert!(loader
+            .update_url_template("https://tiles.example.com/{z}/{x}/{-y}.pbf")
+            .is_err());
+        assert_eq!(
+            loader.generate_url(&TileIndex::new(5, 2, 3)),
+            "https://tiles.example.com/3/5/5.pbf"
+        );
+    }
+
+    #[test]
+    fn quadkey_placeholder_is_substituted() {
+        let loader =
+            DynamicUrlVtLoader::new("https://tiles.example.com/tiles/{q}.pbf", None, false);
+        loader.add_parameter("g", "1");
+
+        assert_eq!(
+            loader.generate_url(&TileIndex::new(3, 5, 3)),
+            "https://tiles.example.com/tiles/213.pbf?g=1"
+        );
+    }
+
+    #[tokio::test]
+    async fn offline_mode_can_be_switched() {
+        let cache = MemoryCacheController::with_max_entries(10);
+        cache
+            .insert(
+                "https://tiles.example.com/3/1/2.pbf",
+                &Bytes::from_static(b"tile"),
+            )
+            .unwrap();
+        let loader = DynamicUrlVtLoader::new(
+            "https://tiles.example.com/{z}/{x}/{y}.pbf",
+            Some(Box::new(cache)),
+            true,
+        );
+        assert!(loader.is_offline_mode());
+
+        assert_eq!(
+            loader.load_bytes(TileIndex::new(1, 2, 3)).await.unwrap(),
+            Bytes::from_static(b"tile")
+        );
+        assert!(matches!(
+            loader.load_bytes(TileIndex::new(1, 3, 3)).await,
+            Err(TileLoadError::DoesNotExist)
+        ));
+
+        loader.set_offline_mode(false);
+        assert!(!loader.is_offline_mode());
+    }
+
+    #[tokio::test]
+    async fn tiles_outside_of_zoom_range_do_not_exist() {
+        let cache = MemoryCacheController::with_max_entries(10);
+        cache
+            .insert(
+                "https://tiles.example.com/3/1/2.pbf",
+                &Bytes::from_static(b"tile"),
+            )
+            .unwrap();
+        let loader = DynamicUrlVtLoader::new(
+            "https://tiles.example.com/{z}/{x}/{y}.pbf",
+            Some(Box::new(cache)),
+            false,
+        )
+        .with_zoom_range(0, 2);
+
+        assert!(matches!(
+            loader.load_bytes(TileIndex::new(1, 2, 3)).await,
+            Err(TileLoadError::DoesNotExist)
+        ));
+        assert!(matches!(
+            loader.load(TileIndex::new(1, 2, 3)).await,
+            Err(TileLoadError::DoesNotExist)
+        ));
+    }
+
+    #[tokio::test]
+    async fn concurrent_loads_of_same_tile_are_merged() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let in_flight = Arc::new(InFlightLoads::default());
+        let fetches = Arc::new(AtomicUsize::new(0));
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let in_flight = in_flight.clone();
+                let fetches = fetches.clone();
+                tokio::spawn(async move {
+                    in_flight
+                        .load("https://tiles.example.com/1/0/0.pbf", || async move {
+                            fetches.fetch_add(1, Ordering::SeqCst);
+                            tokio::time::sleep(Duration::from_millis(50)).await;
+                            Ok(Arc::new(MvtTile { layers: vec![] }))
+                        })
+                        .await
+                })
+            })
+            .collect();
+
+        let mut tiles = vec![];
+        for handle in handles {
+            tiles.push(handle.await.unwrap().unwrap());
         }
 
-        Ok(bytes)
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+        assert!(tiles.iter().all(|tile| Arc::ptr_eq(tile, &tiles[0])));
+        assert!(in_flight.loads.lock().is_empty());
     }
-}
 
-#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
-#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
-impl VectorTileLoader for DynamicUrlVtLoader {
-    async fn load(&self, index: TileIndex) -> Result<MvtTile, TileLoadError> {
-        let url = self.generate_url(&index);
+    #[tokio::test]
+    async fn errors_are_shared_by_merged_loads() {
+        let in_flight = InFlightLoads::default();
+        let url = "https://tiles.example.com/1/0/0.pbf";
+        let slow = in_flight.load(url, || async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Err(TileLoadError::Network(GalileoError::IO))
+        });
+        let fast = in_flight.load(url, || async { Ok(Arc::new(MvtTile { layers: vec![] })) });
 
-        log::trace!("Loading tile {index:?} from url {url}");
-        let bytes = self.load_raw(&url).await?;
+        let (slow, fast) = tokio::join!(slow, fast);
+        assert!(matches!(
+            slow,
+            Err(TileLoadError::Network(GalileoError::IO))
+        ));
+        assert!(matches!(
+            fast,
+            Err(TileLoadError::Network(GalileoError::IO))
+        ));
+    }
 
-        log::trace!("Tile {index:?} loaded. Byte size: {}", bytes.len());
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn compressed_tiles_are_decompressed() {
+        use std::io::Write;
 
-        let mvt = MvtTile::decode(bytes, false).map_err(|_| TileLoadError::Decoding)?;
+        let data = b"\x1a\x02tile data";
 
-        log::trace!("Tile {index:?} successfully decoded");
+        let mut gzip = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        gzip.write_all(data).unwrap();
+        let gzip = Bytes::from(gzip.finish().unwrap());
+        assert_eq!(decompress(gzip).unwrap(), &data[..]);
+
+        let mut zlib = flate2::write::ZlibEncoder::new(vec![], flate2::Compression::default());
+        zlib.write_all(data).unwrap();
+        let zlib = Bytes::from(zlib.finish().unwrap());
+        assert_eq!(decompress(zlib).unwrap(), &data[..]);
+
+        let plain = Bytes::from_static(data);
+        assert_eq!(decompress(plain).unwrap(), &data[..]);
+    }
+
+    #[test]
+    fn custom_url_source_overrides_template() {
+        let loader =
+            DynamicUrlVtLoader::new("https://tiles.example.com/{z}/{x}/{y}.pbf", None, false);
+        loader.add_parameter("key", "value");
+        let index = TileIndex::new(1, 2, 3);
+
+        loader.set_url_source(|index: &TileIndex| {
+            format!(
+                "https://signed.example.com/{}-{}-{}?sig=abc",
+                index.z, index.x, index.y
+            )
+        });
+        assert_eq!(
+            loader.generate_url(&index),
+            "https://signed.example.com/3-1-2?sig=abc"
+        );
+
+        loader.clear_url_source();
+        assert_eq!(
+            loader.generate_url(&index),
+            "https://tiles.example.com/3/1/2.pbf?key=value"
+        );
+    }
+
+    #[test]
+    fn tms_scheme_flips_y() {
+        let loader =
+            DynamicUrlVtLoader::new("https://tiles.example.com/{z}/{x}/{y}.pbf", None, false);
+        loader.add_parameter("key", "value");
+        loader.set_tile_scheme(TileScheme::Tms).unwrap();
+
+        let index = TileIndex::new(5, 2, 3);
+        assert_eq!(
+            loader.generate_url(&index),
+            "https://tiles.example.com/3/5/5.pbf?key=value"
+        );
+
+        let flipped = TileIndex::new(5, TileScheme::Tms.url_y(&index) as i32, 3);
+        assert_eq!(TileScheme::Tms.url_y(&flipped), index.y as i64);
 
-        Ok(mvt)
+        loader.set_tile_scheme(TileScheme::Xyz).unwrap();
+        assert_eq!(
+            loader.generate_url(&index),
+            "https://tiles.example.com/3/5/2.pbf?key=value"
+        );
     }
 }
@@ -2,9 +2,10 @@
 
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
 use galileo_mvt::MvtTile;
-use loader::VectorTileLoader;
+use loader::{TileLoadError, VectorTileLoader};
 use parking_lot::RwLock;
 use processor::VectorTileProcessor;
 
@@ -14,17 +15,25 @@ use crate::messenger::Messenger;
 use crate::render::{Canvas, PackedBundle};
 use crate::tile_schema::TileIndex;
 
+#[cfg(not(target_arch = "wasm32"))]
+pub mod coverage;
+mod filter;
 pub mod loader;
 pub mod processor;
 mod tile_store;
 mod vt_processor;
 
+pub use filter::{FeaturePredicate, VectorTileFilter};
 pub use vt_processor::{VectorTileDecodeContext, VtProcessor};
 
 use crate::layer::vector_tile_layer::tile_provider::tile_store::{
     MvtTileState, PreparedTileState, TileStore,
 };
 
+/// Maximum number of evicted tiles freed by one background task, to spread the cost of freeing GPU resources
+/// over several frames.
+const MAX_EVICTIONS_PER_TICK: usize = 16;
+
 /// Identifier of a vector tile style.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct VtStyleId(u32);
@@ -36,12 +45,30 @@ impl VtStyleId {
     }
 }
 
+/// Defines what a [`VectorTileProvider`] does with a tile that was loaded, but could not be decoded.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum DecodeErrorFallback {
+    /// The tile is rendered empty.
+    #[default]
+    Blank,
+    /// The tile is rendered empty, and is requested from the loader again when it is needed and at
+    /// least `delay` time passed since the failure.
+    Retry {
+        /// Minimum time between two attempts to load the tile.
+        delay: Duration,
+    },
+    /// The closest ancestor of the tile that is available is rendered in place of the tile.
+    Parent,
+}
+
 /// Provider of vector tiles for a vector tile layer.
 pub struct VectorTileProvider {
     tiles: Arc<RwLock<TileStore>>,
     loader: Arc<dyn VectorTileLoader>,
     processor: Arc<dyn VectorTileProcessor>,
     messenger: Option<Arc<dyn Messenger>>,
+    decode_error_fallback: DecodeErrorFallback,
+    filter: Option<Arc<VectorTileFilter>>,
 }
 
 impl Clone for VectorTileProvider {
@@ -51,6 +78,8 @@ impl Clone for VectorTileProvider {
             loader: self.loader.clone(),
             processor: self.processor.clone(),
             messenger: self.messenger.clone(),
+            decode_error_fallback: self.decode_error_fallback,
+            filter: self.filter.clone(),
         }
     }
 }
@@ -69,16 +98,48 @@ impl VectorTileProvider {
             loader,
             processor,
             messenger: None,
+            decode_error_fallback: DecodeErrorFallback::default(),
+            filter: None,
         }
     }
 
+    /// Sets the filter that selects the layers and features of the loaded tiles to be rendered.
+    ///
+    /// The filter is applied to the tiles loaded after this call.
+    pub fn with_filter(mut self, filter: VectorTileFilter) -> Self {
+        self.filter = Some(Arc::new(filter));
+        self
+    }
+
+    /// Filter of the loaded tiles, if set.
+    pub fn filter(&self) -> Option<&VectorTileFilter> {
+        self.filter.as_deref()
+    }
+
+    /// Sets the behaviour of the provider for tiles that cannot be decoded. Defaults to
+    /// [`DecodeErrorFallback::Blank`].
+    pub fn with_decode_error_fallback(mut self, fallback: DecodeErrorFallback) -> Self {
+        self.decode_error_fallback = fallback;
+        self
+    }
+
+    /// Sets the behaviour of the provider for tiles that cannot be decoded.
+    pub fn set_decode_error_fallback(&mut self, fallback: DecodeErrorFallback) {
+        self.decode_error_fallback = fallback;
+    }
+
+    /// Behaviour of the provider for tiles that cannot be decoded.
+    pub fn decode_error_fallback(&self) -> DecodeErrorFallback {
+        self.decode_error_fallback
+    }
+
     /// Return the style with the given id.
     pub fn get_style(&self, style_id: VtStyleId) -> Option<Arc<VectorTileStyle>> {
         self.processor.get_style(style_id)
     }
 
     /// Register a new style in the provider.
-    pub fn add_style(&mut self, style: VectorTileStyle) -> VtStyleId {
+    pub fn add_style(&self, style: VectorTileStyle) -> VtStyleId {
         let id = VtStyleId::next_id();
         self.processor.add_style(id, style);
 
@@ -86,7 +147,7 @@ impl VectorTileProvider {
     }
 
     /// Removes the style from the list of registered styles.
-    pub fn drop_style(&mut self, style_id: VtStyleId) {
+    pub fn drop_style(&self, style_id: VtStyleId) {
         self.processor.drop_style(style_id);
     }
 
@@ -100,7 +161,8 @@ impl VectorTileProvider {
         }
 
         let tile_store = self.tiles.clone();
-        if tile_store.read().contains(index, style_id) {
+        let fallback = self.decode_error_fallback;
+        if !Self::needs_loading(&tile_store.read(), index, style_id, fallback) {
             return;
         }
 
@@ -109,11 +171,12 @@ impl VectorTileProvider {
         let processor = self.processor.clone();
         let data_provider = self.loader.clone();
         let messenger = self.messenger.clone();
+        let filter = self.filter.clone();
 
         crate::async_runtime::spawn(async move {
             let cell = {
                 let mut store = tile_store.write();
-                if store.contains(index, style_id) {
+                if !Self::needs_loading(&store, index, style_id, fallback) {
                     return;
                 }
 
@@ -121,7 +184,7 @@ impl VectorTileProvider {
             };
 
             let tile_state = cell
-                .get_or_init(|| async { Self::download(index, data_provider).await })
+                .get_or_init(|| async { Self::download(index, data_provider, filter).await })
                 .await;
 
             log::debug!("Tile {index:?} is loaded. Preparing.");
@@ -140,30 +203,135 @@ impl VectorTileProvider {
         });
     }
 
+    /// Loads the tiles with the given indices in the background without preparing them for
+    /// rendering, e.g. to fill the persistent cache of the loader with the tiles of the next zoom
+    /// level or along a route.
+    ///
+    /// The tiles are requested through the loader, so its concurrency limit and offline mode
+    /// apply. Failed loads are only logged.
+    pub fn prefetch(&self, indices: impl IntoIterator<Item = TileIndex>) {
+        for index in indices {
+            let loader = self.loader.clone();
+            crate::async_runtime::spawn(async move {
+                match loader.load_shared(index).await {
+                    Ok(_) => log::trace!("Prefetched vector tile {index:?}"),
+                    Err(err) => log::debug!("Failed to prefetch vector tile {index:?}: {err}"),
+                }
+            });
+        }
+    }
+
     /// Move the pre-renderred tile data into GPU memory.
     ///
     /// If any of the tiles with the given indices was not pre-renderred with the given style id,
     /// it is just skipped.
     pub fn pack_tiles(&self, indices: &[TileIndex], style_id: VtStyleId, canvas: &dyn Canvas) {
-        let mut store = self.tiles.write();
-        for index in indices {
-            if let Some((tile, mvt_tile)) = store.get_prepared(*index, style_id) {
-                let packed = canvas.pack_bundle(&tile);
-                store.store_tile(
-                    *index,
-                    style_id,
-                    mvt_tile,
-                    PreparedTileState::Packed(packed.into()),
-                );
+        let mut to_load = vec![];
+        {
+            let mut store = self.tiles.write();
+            for requested in indices {
+                let Some(index) = self.substitute_index(&store, *requested, style_id) else {
+                    continue;
+                };
+
+                if index != *requested && !store.contains(index, style_id) {
+                    to_load.push(index);
+                } else if let Some((tile, mvt_tile)) = store.get_prepared(index, style_id) {
+                    let packed = canvas.pack_bundle(&tile);
+                    store.store_tile(
+                        index,
+                        style_id,
+                        mvt_tile,
+                        PreparedTileState::Packed(packed.into()),
+                    );
+                }
             }
         }
+
+        for index in to_load {
+            self.load_tile(index, style_id);
+        }
+    }
+
+    /// Drops a batch of the tiles evicted from the cache in a background task. Should be called on every frame,
+    /// e.g. from [`Layer::prepare`](crate::layer::Layer::prepare).
+    ///
+    /// If more tiles wait to be freed, a redraw is requested so that the next batch is freed on the next frame.
+    pub fn free_evicted_tiles(&self) {
+        let (batch, remaining) = {
+            let mut store = self.tiles.write();
+            let batch = store.take_evicted(MAX_EVICTIONS_PER_TICK);
+            (batch, store.pending_evictions())
+        };
+
+        if batch.is_empty() {
+            return;
+        }
+
+        log::trace!(
+            "Freeing {} evicted vector tiles, {remaining} remaining",
+            batch.len()
+        );
+        crate::async_runtime::spawn(async move {
+            drop(batch);
+        });
+
+        if remaining > 0 {
+            self.request_redraw();
+        }
+    }
+
+    /// Returns `true` if the tile with the given index was loaded and prepared with the given
+    /// style, or failed to load.
+    pub fn is_tile_ready(&self, index: TileIndex, style_id: VtStyleId) -> bool {
+        self.tiles.read().is_settled(index, style_id)
     }
 
     /// Return render bundle for given tile.
     ///
     /// The tile must be packed before calling this method.
+    ///
+    /// If the tile could not be decoded and the provider is configured with
+    /// [`DecodeErrorFallback::Parent`], the bundle of the closest loaded ancestor is returned.
     pub fn get_tile(&self, index: TileIndex, style_id: VtStyleId) -> Option<Arc<dyn PackedBundle>> {
-        self.tiles.read().get_packed(index, style_id)
+        let store = self.tiles.read();
+        let index = self.substitute_index(&store, index, style_id)?;
+        store.get_packed(index, style_id)
+    }
+
+    /// Returns the index of the tile that should be displayed instead of the tile `index`.
+    ///
+    /// Returns `None` if neither the tile nor any of its ancestors can be decoded.
+    fn substitute_index(
+        &self,
+        store: &TileStore,
+        mut index: TileIndex,
+        style_id: VtStyleId,
+    ) -> Option<TileIndex> {
+        if self.decode_error_fallback != DecodeErrorFallback::Parent {
+            return Some(index);
+        }
+
+        while store.decoding_failed_at(index, style_id).is_some() {
+            index = index.parent()?;
+        }
+
+        Some(index)
+    }
+
+    fn needs_loading(
+        store: &TileStore,
+        index: TileIndex,
+        style_id: VtStyleId,
+        fallback: DecodeErrorFallback,
+    ) -> bool {
+        match store.decoding_failed_at(index, style_id) {
+            Some(failed_at) => match fallback {
+                DecodeErrorFallback::Retry { delay } => failed_at.elapsed() >= delay,
+                _ => false,
+            },
+            None => !store.contains(index, style_id),
+        }
     }
 
     /// Returns raw tile data for the given index.
@@ -184,10 +352,24 @@ impl VectorTileProvider {
         }
     }
 
-    async fn download(tile_index: TileIndex, loader: Arc<dyn VectorTileLoader>) -> MvtTileState {
-        match loader.load(tile_index).await {
-            Ok(mvt_tile) => MvtTileState::Loaded(Arc::new(mvt_tile)),
-            Err(_) => MvtTileState::Error(),
+    async fn download(
+        tile_index: TileIndex,
+        loader: Arc<dyn VectorTileLoader>,
+        filter: Option<Arc<VectorTileFilter>>,
+    ) -> MvtTileState {
+        match loader.load_shared(tile_index).await {
+            Ok(mvt_tile) => match filter {
+                Some(filter) => MvtTileState::Loaded(Arc::new(filter.apply(&mvt_tile))),
+                None => MvtTileState::Loaded(mvt_tile),
+            },
+            Err(err @ TileLoadError::Decoding { .. }) => {
+                log::warn!("Vector tile {tile_index:?} is broken: {err}");
+                MvtTileState::DecodingError(web_time::Instant::now())
+            }
+            Err(err) => {
+                log::debug!("Failed to load vector tile {tile_index:?}: {err}");
+                MvtTileState::Error()
+            }
         }
     }
 
@@ -207,6 +389,7 @@ impl VectorTileProvider {
                     Err(_) => PreparedTileState::Error,
                 }
             }
+            MvtTileState::DecodingError(failed_at) => PreparedTileState::DecodingError(*failed_at),
             MvtTileState::Error() => PreparedTileState::Error,
         }
     }
@@ -214,7 +397,196 @@ impl VectorTileProvider {
 
 #[cfg(test)]
 mod tests {
+    use std::any::Any;
+
     use super::*;
+    use crate::platform::native::vt_processor::ThreadVtProcessor;
+    use crate::tests::TestTileLoader;
+    use crate::TileSchema;
+
+    struct TestPackedBundle;
+    impl PackedBundle for TestPackedBundle {
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    struct RecordingLoader(tokio::sync::mpsc::UnboundedSender<TileIndex>);
+
+    #[async_trait::async_trait]
+    impl VectorTileLoader for RecordingLoader {
+        async fn load(&self, index: TileIndex) -> Result<MvtTile, TileLoadError> {
+            let _ = self.0.send(index);
+            Err(TileLoadError::DoesNotExist)
+        }
+    }
+
+    #[tokio::test]
+    async fn prefetch_loads_tiles_without_storing_them() {
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let provider = VectorTileProvider::new(
+            Arc::new(RecordingLoader(sender)),
+            Arc::new(ThreadVtProcessor::new(TileSchema::web(18))),
+        );
+        let style_id = provider.add_style(VectorTileStyle::default());
+
+        let indices = [TileIndex::new(0, 0, 1), TileIndex::new(1, 0, 1)];
+        provider.prefetch(indices);
+
+        let mut loaded = vec![];
+        for _ in 0..indices.len() {
+            loaded.push(receiver.recv().await.expect("loader is alive"));
+        }
+        loaded.sort_by_key(|index| index.x);
+        assert_eq!(loaded, indices);
+
+        for index in indices {
+            assert!(!provider.is_tile_ready(index, style_id));
+            assert!(provider.get_mvt_tile(index).is_none());
+        }
+    }
+
+    struct StaticLoader(Arc<MvtTile>);
+
+    #[async_trait::async_trait]
+    impl VectorTileLoader for StaticLoader {
+        async fn load(&self, _index: TileIndex) -> Result<MvtTile, TileLoadError> {
+            Ok((*self.0).clone())
+        }
+
+        async fn load_shared(&self, _index: TileIndex) -> Result<Arc<MvtTile>, TileLoadError> {
+            Ok(self.0.clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn download_applies_filter() {
+        let layer = |name: &str| galileo_mvt::MvtLayer {
+            name: name.to_string(),
+            features: vec![],
+            properties: vec![],
+            size: 4096,
+        };
+        let loader = Arc::new(StaticLoader(Arc::new(MvtTile {
+            layers: vec![layer("water"), layer("roads")],
+        })));
+        let filter = Arc::new(VectorTileFilter::new().with_layers(["roads"]));
+
+        let state =
+            VectorTileProvider::download(TileIndex::new(0, 0, 0), loader, Some(filter)).await;
+        let MvtTileState::Loaded(tile) = state else {
+            panic!("tile is not loaded");
+        };
+        let names: Vec<_> = tile
+            .layers
+            .iter()
+            .map(|layer| layer.name.as_str())
+            .collect();
+        assert_eq!(names, ["roads"]);
+    }
+
+    fn test_provider(fallback: DecodeErrorFallback) -> (VectorTileProvider, VtStyleId) {
+        let provider = VectorTileProvider::new(
+            Arc::new(TestTileLoader {}),
+            Arc::new(ThreadVtProcessor::new(TileSchema::web(18))),
+        )
+        .with_decode_error_fallback(fallback);
+        let style_id = provider.add_style(VectorTileStyle::default());
+
+        (provider, style_id)
+    }
+
+    fn store_packed(
+        provider: &VectorTileProvider,
+        index: TileIndex,
+        style_id: VtStyleId,
+    ) -> Arc<dyn PackedBundle> {
+        let bundle: Arc<dyn PackedBundle> = Arc::new(TestPackedBundle);
+        provider.tiles.write().store_tile(
+            index,
+            style_id,
+            Arc::default(),
+            PreparedTileState::Packed(bundle.clone()),
+        );
+        bundle
+    }
+
+    fn store_decoding_error(provider: &VectorTileProvider, index: TileIndex, style_id: VtStyleId) {
+        provider.tiles.write().store_tile(
+            index,
+            style_id,
+            Arc::default(),
+            PreparedTileState::DecodingError(web_time::Instant::now()),
+        );
+    }
+
+    #[test]
+    fn parent_fallback_substitutes_tile_that_failed_to_decode() {
+        let (provider, style_id) = test_provider(DecodeErrorFallback::Parent);
+        let parent = store_packed(&provider, TileIndex::new(1, 1, 1), style_id);
+        store_decoding_error(&provider, TileIndex::new(2, 3, 2), style_id);
+
+        let tile = provider
+            .get_tile(TileIndex::new(2, 3, 2), style_id)
+            .expect("parent tile is returned");
+        assert!(Arc::ptr_eq(&tile, &parent));
+    }
+
+    #[test]
+    fn parent_fallback_skips_ancestors_that_failed_to_decode() {
+        let (provider, style_id) = test_provider(DecodeErrorFallback::Parent);
+        let root = store_packed(&provider, TileIndex::new(0, 0, 0), style_id);
+        store_decoding_error(&provider, TileIndex::new(1, 1, 1), style_id);
+        store_decoding_error(&provider, TileIndex::new(2, 3, 2), style_id);
+
+        let tile = provider
+            .get_tile(TileIndex::new(2, 3, 2), style_id)
+            .expect("root tile is returned");
+        assert!(Arc::ptr_eq(&tile, &root));
+    }
+
+    #[test]
+    fn blank_fallback_does_not_substitute_tile() {
+        let (provider, style_id) = test_provider(DecodeErrorFallback::Blank);
+        store_packed(&provider, TileIndex::new(1, 1, 1), style_id);
+        store_decoding_error(&provider, TileIndex::new(2, 3, 2), style_id);
+
+        assert!(provider
+            .get_tile(TileIndex::new(2, 3, 2), style_id)
+            .is_none());
+    }
+
+    #[test]
+    fn failed_tiles_are_blank_by_default() {
+        let provider = VectorTileProvider::new(
+            Arc::new(TestTileLoader {}),
+            Arc::new(ThreadVtProcessor::new(TileSchema::web(18))),
+        );
+        assert_eq!(provider.decode_error_fallback(), DecodeErrorFallback::Blank);
+    }
+
+    #[test]
+    fn retry_fallback_reloads_tile_after_delay() {
+        let index = TileIndex::new(2, 3, 2);
+        let (provider, style_id) = test_provider(DecodeErrorFallback::Retry {
+            delay: Duration::ZERO,
+        });
+        store_decoding_error(&provider, index, style_id);
+
+        let store = provider.tiles.read();
+        assert!(VectorTileProvider::needs_loading(
+            &store,
+            index,
+            style_id,
+            provider.decode_error_fallback()
+        ));
+        assert!(!VectorTileProvider::needs_loading(
+            &store,
+            index,
+            style_id,
+            DecodeErrorFallback::Blank
+        ));
+    }
 
     #[test]
     fn ids_are_unique() {
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::fmt::{Debug, Formatter};
 use std::sync::{Arc, Weak};
 
@@ -6,6 +6,7 @@ use galileo_mvt::MvtTile;
 use quick_cache::unsync::Cache;
 use quick_cache::{DefaultHashBuilder, Lifecycle, Weighter};
 use tokio::sync::OnceCell;
+use web_time::Instant;
 
 use crate::layer::vector_tile_layer::tile_provider::VtStyleId;
 use crate::render::render_bundle::RenderBundle;
@@ -14,10 +15,14 @@ use crate::tile_schema::TileIndex;
 
 const DEFAULT_CACHE_CAPACITY: usize = 50 * 2usize.pow(20);
 const AVG_TILE_SIZE: usize = 2 * 2usize.pow(20);
+/// Maximum number of evicted tiles waiting to be freed. If the queue is not drained fast enough, the oldest tiles
+/// are dropped right away to keep the memory usage bounded.
+const MAX_PENDING_EVICTIONS: usize = 64;
 
 #[derive(Debug, Clone)]
 pub enum MvtTileState {
     Loaded(Arc<MvtTile>),
+    DecodingError(Instant),
     Error(),
 }
 
@@ -26,6 +31,7 @@ pub enum PreparedTileState {
     Loading,
     Loaded(Arc<RenderBundle>),
     Packed(Arc<dyn PackedBundle>),
+    DecodingError(Instant),
     Error,
 }
 
@@ -35,6 +41,7 @@ impl Debug for PreparedTileState {
             PreparedTileState::Loading => write!(f, "PreparedTileState::Loading"),
             PreparedTileState::Loaded(_) => write!(f, "PreparedTileState::Loaded"),
             PreparedTileState::Packed(_) => write!(f, "PreparedTileState::Packed"),
+            PreparedTileState::DecodingError(_) => write!(f, "PreparedTileState::DecodingError"),
             PreparedTileState::Error => write!(f, "PreparedTileState::Error"),
         }
     }
@@ -54,12 +61,15 @@ pub(super) struct TileStore {
         DefaultHashBuilder,
         TileStoreLc,
     >,
+    /// Tiles removed from the cache, that are waiting to be freed in background.
+    evicted: VecDeque<PreparedTileState>,
 }
 
 impl Default for TileStore {
     fn default() -> Self {
         Self {
             mvt_tiles: HashMap::default(),
+            evicted: VecDeque::new(),
             processed: Cache::with(
                 DEFAULT_CACHE_CAPACITY / AVG_TILE_SIZE,
                 DEFAULT_CACHE_CAPACITY as u64,
@@ -84,9 +94,9 @@ impl Weighter<(TileIndex, VtStyleId), TileStoreEntry> for TileWeighter {
 
 struct TileStoreLc;
 
-#[derive(Debug, Default, Clone)]
+#[derive(Default)]
 struct TileStoreLcState {
-    evicted: Vec<TileIndex>,
+    evicted: Vec<(TileIndex, TileStoreEntry)>,
 }
 
 impl Lifecycle<(TileIndex, VtStyleId), TileStoreEntry> for TileStoreLc {
@@ -100,9 +110,9 @@ impl Lifecycle<(TileIndex, VtStyleId), TileStoreEntry> for TileStoreLc {
         &self,
         state: &mut Self::RequestState,
         key: (TileIndex, VtStyleId),
-        _val: TileStoreEntry,
+        val: TileStoreEntry,
     ) {
-        state.evicted.push(key.0)
+        state.evicted.push((key.0, val))
     }
 }
 
@@ -134,6 +144,8 @@ impl TileStore {
             .mvt_tiles
             .get(&index)
             .and_then(|v| v.upgrade())
+            // Tiles that failed to decode are requested again, so they need a new cell
+            .filter(|cell| !matches!(cell.get(), Some(MvtTileState::DecodingError(_))))
             .unwrap_or_default();
         self.mvt_tiles.insert(index, Arc::downgrade(&tile_cell));
 
@@ -162,6 +174,22 @@ impl TileStore {
         self.insert_entry(tile_index, style_id, entry);
     }
 
+    /// Returns `true` if the tile was requested and is not being loaded at the moment.
+    pub fn is_settled(&self, index: TileIndex, style_id: VtStyleId) -> bool {
+        self.processed
+            .peek(&(index, style_id))
+            .is_some_and(|entry| !matches!(entry.prepared_tile, PreparedTileState::Loading))
+    }
+
+    /// Returns the time when the tile failed to decode, or `None` if the tile was not decoded with
+    /// an error.
+    pub fn decoding_failed_at(&self, index: TileIndex, style_id: VtStyleId) -> Option<Instant> {
+        match self.processed.peek(&(index, style_id))?.prepared_tile {
+            PreparedTileState::DecodingError(failed_at) => Some(failed_at),
+            _ => None,
+        }
+    }
+
     pub fn get_prepared(
         &self,
         index: TileIndex,
@@ -207,9 +235,37 @@ impl TileStore {
             .processed
             .insert_with_lifecycle((index, style_id), entry);
 
-        for index in lc.evicted {
+        for (index, entry) in lc.evicted {
+            // Freeing GPU buffers of many tiles at once can take a noticeable time, so the prepared tiles are
+            // only queued here and dropped by `take_evicted` callers off the render thread. The reference to the mvt
+            // tile is released right away, so the tile can be removed from `mvt_tiles`.
+            let TileStoreEntry {
+                mvt_tile,
+                prepared_tile,
+            } = entry;
+            drop(mvt_tile);
+
+            self.evicted.push_back(prepared_tile);
             self.on_bundle_evicted(index)
         }
+
+        if self.evicted.len() > MAX_PENDING_EVICTIONS {
+            let overflow = self.evicted.len() - MAX_PENDING_EVICTIONS;
+            log::debug!("Eviction queue is full, dropping {overflow} evicted vector tiles");
+            self.evicted.drain(..overflow);
+        }
+    }
+
+    /// Removes up to `max_count` evicted tiles from the eviction queue and returns them, so they can be dropped
+    /// in background.
+    pub fn take_evicted(&mut self, max_count: usize) -> Vec<PreparedTileState> {
+        let count = max_count.min(self.evicted.len());
+        self.evicted.drain(..count).collect()
+    }
+
+    /// Number of evicted tiles that were not freed yet.
+    pub fn pending_evictions(&self) -> usize {
+        self.evicted.len()
     }
 
     fn on_bundle_evicted(&mut self, tile_index: TileIndex) {
@@ -225,6 +281,8 @@ impl TileStore {
 
 #[cfg(test)]
 mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
     use super::*;
 
     fn render_bundle(size: usize) -> RenderBundle {
@@ -238,6 +296,72 @@ mod tests {
         PreparedTileState::Loaded(Arc::new(render_bundle(size as usize)))
     }
 
+    struct DropCountingBundle(Arc<AtomicUsize>);
+
+    impl PackedBundle for DropCountingBundle {
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+    }
+
+    impl Drop for DropCountingBundle {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn evicted_tiles_are_queued_instead_of_dropped() {
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let mut store = TileStore::with_capacity(5 * AVG_TILE_SIZE);
+        let style_id = VtStyleId::next_id();
+        for i in 0..20 {
+            let bundle = DropCountingBundle(dropped.clone());
+            store.store_tile(
+                TileIndex::new(i, i, 10),
+                style_id,
+                Arc::default(),
+                PreparedTileState::Packed(Arc::new(bundle)),
+            );
+            let _ = store.get_packed(TileIndex::new(i, i, 10), style_id);
+        }
+
+        let pending = store.pending_evictions();
+        assert!(pending >= 10, "only {pending} tiles were evicted");
+        assert_eq!(dropped.load(Ordering::Relaxed), 0);
+
+        let batch = store.take_evicted(4);
+        assert_eq!(batch.len(), 4);
+        assert_eq!(store.pending_evictions(), pending - 4);
+
+        drop(batch);
+        assert_eq!(dropped.load(Ordering::Relaxed), 4);
+    }
+
+    #[test]
+    fn eviction_queue_is_capped() {
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let mut store = TileStore::with_capacity(5 * AVG_TILE_SIZE);
+        let style_id = VtStyleId::next_id();
+        let tile_count = MAX_PENDING_EVICTIONS as i32 * 2;
+        for i in 0..tile_count {
+            let bundle = DropCountingBundle(dropped.clone());
+            store.store_tile(
+                TileIndex::new(i, i, 10),
+                style_id,
+                Arc::default(),
+                PreparedTileState::Packed(Arc::new(bundle)),
+            );
+        }
+
+        assert_eq!(store.pending_evictions(), MAX_PENDING_EVICTIONS);
+        let alive = tile_count as usize - dropped.load(Ordering::Relaxed);
+        assert!(
+            alive <= MAX_PENDING_EVICTIONS + 5,
+            "{alive} tiles are alive"
+        );
+    }
+
     #[test]
     fn returns_same_mvt_tile_for_different_styles() {
         let mut store = TileStore::with_capacity(1_000_000);
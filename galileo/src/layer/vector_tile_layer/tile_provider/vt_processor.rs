@@ -30,6 +30,8 @@ pub struct VectorTileDecodeContext {
 
 impl VtProcessor {
     /// Pre-render the given tile into the given `bundle`.
+    ///
+    /// The scale factor of the bundle is set to the scale factor of the `style`.
     pub fn prepare(
         mvt_tile: &MvtTile,
         bundle: &mut RenderBundle,
@@ -44,6 +46,8 @@ impl VtProcessor {
             GalileoError::Generic(format!("cannot get lod resolution for lod {}", index.z))
         })?;
         let tile_resolution = lod_resolution * tile_schema.tile_width() as f64;
+        let zoom = index.z as f64;
+        bundle.set_scale_factor(style.scale_factor);
 
         let bounds = Polygon::new(
             ClosedContour::new(vec![
@@ -64,7 +68,7 @@ impl VtProcessor {
 
                 match &feature.geometry {
                     MvtGeometry::Point(points) => {
-                        let Some(paint) = Self::get_point_symbol(rule, feature) else {
+                        let Some(paint) = Self::get_point_symbol(rule, zoom, feature) else {
                             continue;
                         };
 
@@ -96,7 +100,7 @@ impl VtProcessor {
                         }
                     }
                     MvtGeometry::LineString(contours) => {
-                        if let Some(paint) = Self::get_line_symbol(rule, feature) {
+                        if let Some(paint) = Self::get_line_symbol(rule, zoom, feature) {
                             for contour in contours.contours() {
                                 bundle.add_line(
                                     &galileo_types::impls::Contour::new(
@@ -115,7 +119,7 @@ impl VtProcessor {
                         }
                     }
                     MvtGeometry::Polygon(polygons) => {
-                        if let Some(paint) = Self::get_polygon_symbol(rule, feature) {
+                        if let Some(paint) = Self::get_polygon_symbol(rule, zoom, feature) {
                             for polygon in polygons.polygons() {
                                 bundle.add_polygon(
                                     &Self::transform_polygon(polygon, bbox, tile_resolution),
@@ -132,11 +136,14 @@ impl VtProcessor {
         Ok(())
     }
 
-    fn get_point_symbol<'a>(rule: &'a StyleRule, feature: &MvtFeature) -> Option<PointPaint<'a>> {
+    fn get_point_symbol<'a>(
+        rule: &'a StyleRule,
+        zoom: f64,
+        feature: &MvtFeature,
+    ) -> Option<PointPaint<'a>> {
         rule.symbol
             .point()
-            .copied()
-            .map(|symbol| symbol.into())
+            .map(|symbol| symbol.paint(zoom, feature))
             .or_else(|| {
                 rule.symbol
                     .label()
@@ -166,12 +173,16 @@ impl VtProcessor {
         ))
     }
 
-    fn get_line_symbol(rule: &StyleRule, _feature: &MvtFeature) -> Option<LinePaint> {
-        rule.symbol.line().map(|&s| s.into())
+    fn get_line_symbol(rule: &StyleRule, zoom: f64, feature: &MvtFeature) -> Option<LinePaint> {
+        rule.symbol.line().map(|s| s.paint(zoom, feature))
     }
 
-    fn get_polygon_symbol(rule: &StyleRule, _feature: &MvtFeature) -> Option<PolygonPaint> {
-        rule.symbol.polygon().map(|&s| s.into())
+    fn get_polygon_symbol(
+        rule: &StyleRule,
+        zoom: f64,
+        feature: &MvtFeature,
+    ) -> Option<PolygonPaint> {
+        rule.symbol.polygon().map(|s| s.paint(zoom, feature))
     }
 
     fn transform_polygon(
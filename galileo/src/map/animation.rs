@@ -0,0 +1,158 @@
+use std::time::Duration;
+
+use galileo_types::cartesian::{CartesianPoint3d, Vector2};
+use web_time::SystemTime;
+
+use crate::view::MapView;
+
+/// Rate of change of the animated view parameters, in projected units (or resolution units) per second.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(super) struct ViewVelocity {
+    x: f64,
+    y: f64,
+    resolution: f64,
+}
+
+/// Gradual change of the map view.
+///
+/// An animation started from rest moves the view to the target with constant speed. An animation that replaces a
+/// running one moves along a cubic curve that starts with the velocity the view had at that moment and blends into
+/// the constant speed by the end, so the camera does not jerk when the target is changed mid-flight.
+///
+/// The resolution never leaves the range between the start and the target resolutions, so the curve cannot make it
+/// negative.
+pub(super) struct Animation {
+    start_view: MapView,
+    pub(super) end_view: MapView,
+    start_velocity: ViewVelocity,
+    pub(super) start_time: SystemTime,
+    duration: Duration,
+    pub(super) stepped: Duration,
+    last_elapsed: Duration,
+}
+
+impl Animation {
+    pub(super) fn new(
+        start_view: MapView,
+        end_view: MapView,
+        start_velocity: ViewVelocity,
+        start_time: SystemTime,
+        duration: Duration,
+    ) -> Self {
+        Self {
+            start_view,
+            end_view,
+            start_velocity,
+            start_time,
+            duration,
+            stepped: Duration::ZERO,
+            last_elapsed: Duration::ZERO,
+        }
+    }
+
+    /// Velocity of the view at the last applied frame of the animation.
+    pub(super) fn current_velocity(&self) -> ViewVelocity {
+        self.velocity_at(self.last_elapsed)
+    }
+
+    /// Returns the view at `elapsed` time since the start of the animation, or `None` if the animation is finished.
+    pub(super) fn view_at(&mut self, elapsed: Duration) -> Option<MapView> {
+        let k = self.progress(elapsed);
+        if k >= 1.0 {
+            return None;
+        }
+
+        self.last_elapsed = elapsed;
+
+        let (Some(start), Some(end)) = (
+            self.start_view.projected_position(),
+            self.end_view.projected_position(),
+        ) else {
+            return Some(self.start_view.clone());
+        };
+
+        let (h00, h10, h01, h11) = (
+            2.0 * k.powi(3) - 3.0 * k.powi(2) + 1.0,
+            k.powi(3) - 2.0 * k.powi(2) + k,
+            -2.0 * k.powi(3) + 3.0 * k.powi(2),
+            k.powi(3) - k.powi(2),
+        );
+        let value = |from: f64, to: f64, velocity: f64| {
+            h00 * from + h10 * self.start_tangent(from, to, velocity) + h01 * to + h11 * (to - from)
+        };
+
+        let v = self.start_velocity;
+        let x = value(start.x(), end.x(), v.x);
+        let y = value(start.y(), end.y(), v.y);
+        let (start_resolution, end_resolution) =
+            (self.start_view.resolution(), self.end_view.resolution());
+        let resolution = value(start_resolution, end_resolution, v.resolution).clamp(
+            start_resolution.min(end_resolution),
+            start_resolution.max(end_resolution),
+        );
+
+        Some(
+            self.start_view
+                .translate(Vector2::new(start.x() - x, start.y() - y))
+                .with_resolution(resolution),
+        )
+    }
+
+    fn velocity_at(&self, elapsed: Duration) -> ViewVelocity {
+        let k = self.progress(elapsed);
+        let t = self.duration.as_secs_f64();
+        if k >= 1.0 || t <= 0.0 {
+            return ViewVelocity::default();
+        }
+
+        let (Some(start), Some(end)) = (
+            self.start_view.projected_position(),
+            self.end_view.projected_position(),
+        ) else {
+            return ViewVelocity::default();
+        };
+
+        let (d00, d10, d01, d11) = (
+            6.0 * k.powi(2) - 6.0 * k,
+            3.0 * k.powi(2) - 4.0 * k + 1.0,
+            -6.0 * k.powi(2) + 6.0 * k,
+            3.0 * k.powi(2) - 2.0 * k,
+        );
+        let v = self.start_velocity;
+        let rate = |from: f64, to: f64, velocity: f64| {
+            (d00 * from
+                + d10 * self.start_tangent(from, to, velocity)
+                + d01 * to
+                + d11 * (to - from))
+                / t
+        };
+
+        ViewVelocity {
+            x: rate(start.x(), end.x(), v.x),
+            y: rate(start.y(), end.y(), v.y),
+            resolution: rate(
+                self.start_view.resolution(),
+                self.end_view.resolution(),
+                v.resolution,
+            ),
+        }
+    }
+
+    /// Tangent of the curve of a view parameter at the start of the animation. For an animation started from rest
+    /// it is the same as the tangent at the end, so the curve is a straight line passed with constant speed.
+    fn start_tangent(&self, from: f64, to: f64, velocity: f64) -> f64 {
+        if self.start_velocity == ViewVelocity::default() {
+            to - from
+        } else {
+            velocity * self.duration.as_secs_f64()
+        }
+    }
+
+    fn progress(&self, elapsed: Duration) -> f64 {
+        if self.duration.is_zero() {
+            return 1.0;
+        }
+
+        elapsed.as_secs_f64() / self.duration.as_secs_f64()
+    }
+}
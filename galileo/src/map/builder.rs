@@ -4,7 +4,8 @@ use galileo_types::geo::{Crs, GeoPoint};
 use galileo_types::latlon;
 
 use super::Map;
-use crate::layer::Layer;
+use crate::layer::raster_tile_layer::Basemap;
+use crate::layer::{Layer, RasterTileLayer};
 use crate::{MapView, Messenger};
 
 // z-level 4 on the standard web tile scheme
@@ -197,6 +198,24 @@ impl MapBuilder {
         self
     }
 
+    /// Adds a raster tile layer with the given [basemap](Basemap) at the top of the map.
+    ///
+    /// The layer is created without a tile cache. To configure the layer further, create it with
+    /// [`RasterTileLayerBuilder::new_basemap()`](crate::layer::raster_tile_layer::RasterTileLayerBuilder::new_basemap)
+    /// and add it with [`MapBuilder::with_layer()`] instead.
+    ///
+    /// ```
+    /// use galileo::MapBuilder;
+    /// use galileo::layer::raster_tile_layer::Basemap;
+    ///
+    /// let map = MapBuilder::default().with_basemap(Basemap::OpenStreetMap).build();
+    ///
+    /// assert_eq!(map.layers().len(), 1);
+    /// ```
+    pub fn with_basemap(self, basemap: Basemap) -> Self {
+        self.with_layer(RasterTileLayer::from_basemap(basemap))
+    }
+
     /// Sets a [messenger](Messenger) implementation to the map.
     pub fn with_messenger(mut self, messenger: impl Messenger + 'static) -> Self {
         self.messenger = Some(Box::new(messenger));
@@ -247,7 +266,7 @@ mod tests {
 
     use super::*;
     use crate::layer::raster_tile_layer::RestTileLoader;
-    use crate::layer::RasterTileLayer;
+    use crate::tile_schema::TileIndex;
     use crate::TileSchema;
 
     fn test_tile_schema() -> TileSchema {
@@ -457,6 +476,31 @@ mod tests {
         assert_eq!(map.layers.len(), 2);
     }
 
+    #[test]
+    fn with_basemap_adds_osm_layer() {
+        let map = MapBuilder::default()
+            .with_basemap(Basemap::OpenStreetMap)
+            .build();
+
+        let layer = map
+            .layers()
+            .get_typed::<RasterTileLayer>(0)
+            .expect("basemap layer is a raster tile layer");
+        assert_eq!(*layer.tile_schema(), TileSchema::web(20));
+
+        let attribution = layer.attribution().expect("basemap has attribution");
+        assert_eq!(attribution.get_text(), "© OpenStreetMap contributors");
+        assert_eq!(
+            attribution.get_url(),
+            Some("https://www.openstreetmap.org/copyright")
+        );
+
+        assert_eq!(
+            Basemap::OpenStreetMap.tile_url(&TileIndex::new(1, 2, 3)),
+            "https://tile.openstreetmap.org/3/1/2.png"
+        );
+    }
+
     #[test]
     fn with_crs_sets_crs() {
         let crs1 = Crs::new(
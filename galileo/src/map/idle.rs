@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+use maybe_sync::{MaybeSend, MaybeSync};
+use web_time::SystemTime;
+
+use crate::view::MapView;
+
+/// Callback called when the map becomes idle. See [`Map::on_idle`](super::Map::on_idle).
+pub trait IdleCallback: Fn(&MapView) + MaybeSend + MaybeSync {}
+impl<T: Fn(&MapView) + MaybeSend + MaybeSync> IdleCallback for T {}
+
+pub(super) struct IdleWatcher {
+    quiet_interval: Duration,
+    callback: Box<dyn IdleCallback>,
+    last_interaction: SystemTime,
+    fired: bool,
+    /// Time when the scheduled wake-up is due, if one is scheduled.
+    wake_up: Option<SystemTime>,
+}
+
+impl IdleWatcher {
+    pub(super) fn new(quiet_interval: Duration, callback: Box<dyn IdleCallback>) -> Self {
+        Self {
+            quiet_interval,
+            callback,
+            last_interaction: SystemTime::now(),
+            fired: false,
+            wake_up: None,
+        }
+    }
+
+    /// Restarts the quiet interval and re-arms the callback.
+    pub(super) fn register_interaction(&mut self, now: SystemTime) {
+        self.last_interaction = now;
+        self.fired = false;
+    }
+
+    /// Returns the time to wait until the quiet interval passes, if the watcher waits for it and no wake-up is
+    /// scheduled for that time yet. The caller is expected to check the watcher again after this time.
+    ///
+    /// Only one wake-up is pending at a time. If the interval is restarted before it is due, a new one is returned by
+    /// the first call after the previous wake-up.
+    pub(super) fn schedule_wake_up(&mut self, now: SystemTime) -> Option<Duration> {
+        if self.fired || self.is_quiet(now) {
+            return None;
+        }
+
+        if self.wake_up.is_some_and(|wake_up| wake_up > now) {
+            return None;
+        }
+
+        let deadline = self.last_interaction + self.quiet_interval;
+        self.wake_up = Some(deadline);
+        Some(deadline.duration_since(now).unwrap_or_default())
+    }
+
+    /// Calls the callback if the input has been quiet long enough and the map is `ready`, unless it was already
+    /// called since the last interaction.
+    pub(super) fn check(&mut self, now: SystemTime, ready: bool, view: &MapView) {
+        if self.fired || !ready || !self.is_quiet(now) {
+            return;
+        }
+
+        self.fired = true;
+        (self.callback)(view);
+    }
+
+    fn is_quiet(&self, now: SystemTime) -> bool {
+        now.duration_since(self.last_interaction)
+            .unwrap_or_default()
+            >= self.quiet_interval
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schedules_single_wake_up_per_quiet_interval() {
+        let mut watcher = IdleWatcher::new(Duration::from_millis(100), Box::new(|_: &MapView| {}));
+        let start = SystemTime::now();
+        watcher.register_interaction(start);
+
+        assert_eq!(
+            watcher.schedule_wake_up(start + Duration::from_millis(10)),
+            Some(Duration::from_millis(90))
+        );
+        assert_eq!(
+            watcher.schedule_wake_up(start + Duration::from_millis(20)),
+            None
+        );
+
+        // The interval is restarted, but the pending wake-up is still due.
+        watcher.register_interaction(start + Duration::from_millis(50));
+        assert_eq!(
+            watcher.schedule_wake_up(start + Duration::from_millis(60)),
+            None
+        );
+
+        // The wake-up came before the restarted interval passed.
+        assert_eq!(
+            watcher.schedule_wake_up(start + Duration::from_millis(100)),
+            Some(Duration::from_millis(50))
+        );
+        assert_eq!(
+            watcher.schedule_wake_up(start + Duration::from_millis(150)),
+            None
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use std::sync::Arc;
 use std::time::Duration;
 
 use galileo_types::cartesian::Size;
@@ -7,10 +8,15 @@ use crate::layer::Layer;
 use crate::messenger::Messenger;
 use crate::view::MapView;
 
+mod animation;
 mod builder;
+mod idle;
 mod layer_collection;
 
+use animation::Animation;
 pub use builder::MapBuilder;
+pub use idle::IdleCallback;
+use idle::IdleWatcher;
 pub use layer_collection::LayerCollection;
 
 const FRAME_DURATION: Duration = Duration::from_millis(16);
@@ -19,15 +25,9 @@ const FRAME_DURATION: Duration = Duration::from_millis(16);
 pub struct Map {
     view: MapView,
     layers: LayerCollection,
-    messenger: Option<Box<dyn Messenger>>,
-    animation: Option<AnimationParameters>,
-}
-
-struct AnimationParameters {
-    start_view: MapView,
-    end_view: MapView,
-    start_time: SystemTime,
-    duration: Duration,
+    messenger: Option<Arc<dyn Messenger>>,
+    animation: Option<Animation>,
+    idle: Option<IdleWatcher>,
 }
 
 impl Map {
@@ -40,8 +40,9 @@ impl Map {
         Self {
             view,
             layers: layers.into(),
-            messenger,
+            messenger: messenger.map(Arc::from),
             animation: None,
+            idle: None,
         }
     }
 
@@ -63,6 +64,7 @@ impl Map {
     /// Changes the view of the map to the given one.
     pub fn set_view(&mut self, view: MapView) {
         self.view = view;
+        self.register_interaction();
         if let Some(messenger) = &self.messenger {
             messenger.request_redraw();
         }
@@ -76,6 +78,14 @@ impl Map {
         }
     }
 
+    /// Returns `true` if all visible layers have loaded the data needed to render the current
+    /// view. See [`Layer::is_ready`].
+    pub fn is_ready(&self) -> bool {
+        self.layers
+            .iter_visible()
+            .all(|layer| layer.is_ready(&self.view))
+    }
+
     /// Request redraw of the map.
     pub fn redraw(&self) {
         if let Some(messenger) = &self.messenger {
@@ -84,26 +94,106 @@ impl Map {
     }
 
     /// Update the view of the map before the rendering in case [`Map::animate_to`] was called.
+    ///
+    /// This also checks whether the map has become idle and calls the [`Map::on_idle`] callback.
     pub fn animate(&mut self) {
-        let Some(animation) = &self.animation else {
+        if let Some(animation) = &self.animation {
+            let elapsed = SystemTime::now()
+                .duration_since(animation.start_time)
+                .unwrap_or_default();
+            self.apply_animation(elapsed);
+        }
+
+        self.check_idle(SystemTime::now());
+    }
+
+    /// Sets the callback that is called once the map becomes idle: there was no user input and no view changes for
+    /// `quiet_interval`, no animation is running and all visible layers have loaded the data for the current view
+    /// (see [`Map::is_ready`]).
+    ///
+    /// The callback is called only once per idle period. It is armed again by the next user interaction or view
+    /// change. This is useful to start expensive work, like loading detailed data for the area the user settled on.
+    ///
+    /// The idle state is checked in [`Map::animate`], so it must be called on every frame, as is done by the
+    /// integrations provided with the crate.
+    pub fn on_idle(&mut self, quiet_interval: Duration, callback: impl IdleCallback + 'static) {
+        self.idle = Some(IdleWatcher::new(quiet_interval, Box::new(callback)));
+    }
+
+    /// Removes the callback set by [`Map::on_idle`].
+    pub fn clear_on_idle(&mut self) {
+        self.idle = None;
+    }
+
+    /// Notifies the map that the user interacted with it, restarting the quiet interval of the [`Map::on_idle`]
+    /// callback. [`EventProcessor`](crate::control::EventProcessor) calls it for every input event except moving the
+    /// pointer without pressed buttons.
+    pub fn register_interaction(&mut self) {
+        if let Some(idle) = &mut self.idle {
+            idle.register_interaction(SystemTime::now());
+        }
+    }
+
+    fn check_idle(&mut self, now: SystemTime) {
+        let ready = !self.is_animating() && self.is_ready();
+        let Some(idle) = &mut self.idle else {
             return;
         };
 
-        let now = SystemTime::now();
-        let k = now
-            .duration_since(animation.start_time)
-            .unwrap_or_default()
-            .as_millis() as f64
-            / animation.duration.as_millis() as f64;
-
-        if k >= 1.0 {
-            let animation = self
-                .animation
-                .take()
-                .expect("the value was removed unexpectedly");
-            self.view = animation.end_view;
-        } else {
-            self.view = animation.start_view.interpolate(&animation.end_view, k);
+        idle.check(now, ready, &self.view);
+
+        // Nothing else may trigger a redraw when the quiet interval ends, so wake up the map once it is due. If the
+        // layers are not loaded by then, they request a redraw themselves when they are.
+        if let Some(delay) = idle.schedule_wake_up(now) {
+            if let Some(messenger) = self.messenger.clone() {
+                crate::async_runtime::spawn(async move {
+                    crate::async_runtime::sleep(delay).await;
+                    messenger.request_redraw();
+                });
+            }
+        }
+    }
+
+    /// Advances the current animation by a fixed time step `dt` instead of the wall clock time.
+    ///
+    /// This is useful for rendering an animation frame by frame, e.g. when recording it to a
+    /// video, where the frames are produced at a rate unrelated to the display refresh rate.
+    /// Returns `true` if the animation is still running after the step.
+    pub fn advance_animation(&mut self, dt: Duration) -> bool {
+        let Some(animation) = &mut self.animation else {
+            return false;
+        };
+
+        animation.stepped += dt;
+        let elapsed = animation.stepped;
+        self.apply_animation(elapsed);
+
+        self.is_animating()
+    }
+
+    /// Returns `true` if an animation started with [`Map::animate_to`] is not finished yet.
+    pub fn is_animating(&self) -> bool {
+        self.animation.is_some()
+    }
+
+    fn apply_animation(&mut self, elapsed: Duration) {
+        let Some(animation) = &mut self.animation else {
+            return;
+        };
+
+        if let Some(idle) = &mut self.idle {
+            idle.register_interaction(SystemTime::now());
+        }
+
+        match animation.view_at(elapsed) {
+            Some(view) => self.view = view,
+            None => {
+                let animation = self
+                    .animation
+                    .take()
+                    .expect("the value was removed unexpectedly");
+                self.view = animation.end_view;
+            }
         }
 
         self.redraw();
@@ -118,13 +208,24 @@ impl Map {
     }
 
     /// Request a gradual change of the map view to the specified view.
+    ///
+    /// If another animation is running, it is replaced by the new one. The new animation starts from the current
+    /// view of the map and keeps the velocity with which the view was changing, so the transition to the new target
+    /// is smooth.
     pub fn animate_to(&mut self, target: MapView, duration: Duration) {
-        self.animation = Some(AnimationParameters {
-            start_view: self.view.clone(),
-            end_view: target,
-            start_time: SystemTime::now() - FRAME_DURATION,
+        let start_velocity = self
+            .animation
+            .as_ref()
+            .map(Animation::current_velocity)
+            .unwrap_or_default();
+
+        self.animation = Some(Animation::new(
+            self.view.clone(),
+            target,
+            start_velocity,
+            SystemTime::now() - FRAME_DURATION,
             duration,
-        });
+        ));
     }
 
     /// Set the size of the map.
@@ -134,8 +235,8 @@ impl Map {
 
     /// Sets the new event messenger for the map.
     pub fn set_messenger(&mut self, messenger: Option<impl Messenger + 'static>) {
-        let messenger: Option<Box<dyn Messenger>> = if let Some(m) = messenger {
-            Some(Box::new(m))
+        let messenger: Option<Arc<dyn Messenger>> = if let Some(m) = messenger {
+            Some(Arc::new(m))
         } else {
             None
         };
@@ -143,3 +244,155 @@ impl Map {
         self.messenger = messenger;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use galileo_types::cartesian::{CartesianPoint3d, Point2};
+
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::MapBuilder;
+
+    #[test]
+    fn advance_animation_steps_by_fixed_time() {
+        let mut map = MapBuilder::default()
+            .with_projected_position(Point2::new(0.0, 0.0))
+            .with_resolution(1.0)
+            .build();
+        let target = map.view().with_resolution(2.0);
+        map.animate_to(target, Duration::from_millis(100));
+
+        assert!(map.advance_animation(Duration::from_millis(50)));
+        assert!((map.view().resolution() - 1.5).abs() < 1e-9);
+
+        assert!(!map.advance_animation(Duration::from_millis(50)));
+        assert_eq!(map.view().resolution(), 2.0);
+        assert!(!map.is_animating());
+    }
+
+    #[test]
+    fn retargeted_zoom_stays_between_resolutions() {
+        let mut map = MapBuilder::default()
+            .with_projected_position(Point2::new(0.0, 0.0))
+            .with_resolution(1.0)
+            .build();
+
+        map.animate_to(
+            MapView::new_projected(&Point2::new(0.0, 0.0), 100.0),
+            Duration::from_millis(100),
+        );
+        map.advance_animation(Duration::from_millis(50));
+        let start_resolution = map.view().resolution();
+
+        map.animate_to(
+            MapView::new_projected(&Point2::new(0.0, 0.0), 1.0),
+            Duration::from_millis(100),
+        );
+        while map.advance_animation(Duration::from_millis(1)) {
+            let resolution = map.view().resolution();
+            assert!((1.0..=start_resolution).contains(&resolution));
+        }
+        assert_eq!(map.view().resolution(), 1.0);
+    }
+
+    #[test]
+    fn retargeted_animation_is_continuous() {
+        let mut map = MapBuilder::default()
+            .with_projected_position(Point2::new(0.0, 0.0))
+            .with_resolution(1.0)
+            .build();
+        let position = |map: &Map| {
+            let p = map.view().projected_position().expect("position is valid");
+            Point2::new(p.x(), p.y())
+        };
+
+        map.animate_to(
+            MapView::new_projected(&Point2::new(100.0, 0.0), 1.0),
+            Duration::from_millis(100),
+        );
+        map.advance_animation(Duration::from_millis(49));
+        let before = position(&map);
+        map.advance_animation(Duration::from_millis(1));
+        let at_retarget = position(&map);
+
+        map.animate_to(
+            MapView::new_projected(&Point2::new(0.0, 100.0), 1.0),
+            Duration::from_millis(100),
+        );
+        map.advance_animation(Duration::from_millis(1));
+        let after = position(&map);
+
+        let step_before = at_retarget - before;
+        let step_after = after - at_retarget;
+        assert!(step_before.dx() > 0.5);
+        assert!((step_after.dx() - step_before.dx()).abs() < 0.1);
+        assert!(step_after.dy().abs() < 0.1);
+
+        assert!(map.advance_animation(Duration::from_millis(98)));
+        assert!(!map.advance_animation(Duration::from_millis(1)));
+        assert_eq!(position(&map), Point2::new(0.0, 100.0));
+    }
+
+    struct ReadinessLayer(Arc<AtomicBool>);
+
+    impl Layer for ReadinessLayer {
+        fn render(&self, _view: &MapView, _canvas: &mut dyn crate::render::Canvas) {}
+
+        fn prepare(&self, _view: &MapView) {}
+
+        fn set_messenger(&mut self, _messenger: Box<dyn Messenger>) {}
+
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+
+        fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+            self
+        }
+
+        fn attribution(&self) -> Option<crate::layer::attribution::Attribution> {
+            None
+        }
+
+        fn is_ready(&self, _view: &MapView) -> bool {
+            self.0.load(Ordering::Relaxed)
+        }
+    }
+
+    #[test]
+    fn on_idle_waits_for_quiet_input_and_loaded_layers() {
+        let ready = Arc::new(AtomicBool::new(false));
+        let mut map = MapBuilder::default()
+            .with_layer(ReadinessLayer(ready.clone()))
+            .build();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let calls_clone = calls.clone();
+        map.on_idle(Duration::from_millis(100), move |_: &MapView| {
+            calls_clone.fetch_add(1, Ordering::Relaxed);
+        });
+
+        map.register_interaction();
+        let start = SystemTime::now();
+
+        // Layers are not loaded and the input is not quiet yet.
+        map.check_idle(start + Duration::from_millis(50));
+        // The input is quiet, but the layers are still loading.
+        map.check_idle(start + Duration::from_millis(150));
+        assert_eq!(calls.load(Ordering::Relaxed), 0);
+
+        // The layers are loaded, but the input is not quiet.
+        ready.store(true, Ordering::Relaxed);
+        map.check_idle(start + Duration::from_millis(50));
+        assert_eq!(calls.load(Ordering::Relaxed), 0);
+
+        map.check_idle(start + Duration::from_millis(150));
+        map.check_idle(start + Duration::from_millis(200));
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+
+        map.register_interaction();
+        map.check_idle(SystemTime::now() + Duration::from_millis(150));
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+    }
+}
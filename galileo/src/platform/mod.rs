@@ -19,6 +19,37 @@ pub trait PlatformService {
     async fn load_image_url(&self, url: &str) -> Result<DecodedImage, GalileoError>;
     /// Loads a byte array from the given url.
     async fn load_bytes_from_url(&self, url: &str) -> Result<bytes::Bytes, GalileoError>;
+    /// Loads a byte array from the given url, adding the given `(name, value)` headers to the
+    /// request.
+    ///
+    /// The headers may contain secrets like API keys, so implementations must not log them.
+    async fn load_bytes_from_url_with_headers(
+        &self,
+        url: &str,
+        headers: &[(String, String)],
+    ) -> Result<bytes::Bytes, GalileoError>;
+
+    /// Sends a GET request to the given url with the given `(name, value)` headers and returns the status, headers
+    /// and body of the response.
+    ///
+    /// Unlike [`PlatformService::load_bytes_from_url_with_headers`], a `304 Not Modified` response is not an error,
+    /// so this method can be used for conditional requests. Other unsuccessful responses result in the same errors
+    /// as with `load_bytes_from_url_with_headers`.
+    ///
+    /// The default implementation calls `load_bytes_from_url_with_headers` and returns a `200` response without
+    /// headers.
+    async fn load_response_from_url(
+        &self,
+        url: &str,
+        headers: &[(String, String)],
+    ) -> Result<HttpResponse, GalileoError> {
+        let body = self.load_bytes_from_url_with_headers(url, headers).await?;
+        Ok(HttpResponse {
+            status: 200,
+            headers: vec![],
+            body,
+        })
+    }
 
     /// Decodes an image from raw byte data
     ///
@@ -27,6 +58,35 @@ pub trait PlatformService {
     async fn decode_image(&self, imaage_data: Bytes) -> Result<DecodedImage, GalileoError>;
 }
 
+/// Response to a request sent with [`PlatformService::load_response_from_url`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    /// HTTP status code.
+    pub status: u16,
+    /// Response headers as `(name, value)` pairs.
+    pub headers: Vec<(String, String)>,
+    /// Response body. Empty for `304 Not Modified` responses.
+    pub body: Bytes,
+}
+
+impl HttpResponse {
+    /// Status code of the `304 Not Modified` response.
+    pub const NOT_MODIFIED: u16 = 304;
+
+    /// Returns the value of the header with the given name. Header names are compared case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns true if the server responded that the requested resource was not modified.
+    pub fn is_not_modified(&self) -> bool {
+        self.status == Self::NOT_MODIFIED
+    }
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 pub mod native;
 
@@ -6,7 +6,7 @@ use log::info;
 
 use crate::decoded_image::DecodedImage;
 use crate::error::GalileoError;
-use crate::platform::PlatformService;
+use crate::platform::{HttpResponse, PlatformService};
 
 pub mod vt_processor;
 
@@ -28,12 +28,28 @@ impl PlatformService for NativePlatformService {
     }
 
     async fn load_image_url(&self, url: &str) -> Result<DecodedImage, GalileoError> {
-        let image_source = self.load_from_web(url).await?;
+        let image_source = self.load_from_web(url, &[]).await?;
         DecodedImage::decode(&image_source)
     }
 
     async fn load_bytes_from_url(&self, url: &str) -> Result<Bytes, GalileoError> {
-        self.load_from_web(url).await
+        self.load_from_web(url, &[]).await
+    }
+
+    async fn load_bytes_from_url_with_headers(
+        &self,
+        url: &str,
+        headers: &[(String, String)],
+    ) -> Result<Bytes, GalileoError> {
+        self.load_from_web(url, headers).await
+    }
+
+    async fn load_response_from_url(
+        &self,
+        url: &str,
+        headers: &[(String, String)],
+    ) -> Result<HttpResponse, GalileoError> {
+        self.load_response(url, headers).await
     }
 
     async fn decode_image(&self, image_data: Bytes) -> Result<DecodedImage, GalileoError> {
@@ -42,17 +58,53 @@ impl PlatformService for NativePlatformService {
 }
 
 impl NativePlatformService {
-    async fn load_from_web(&self, url: &str) -> Result<Bytes, GalileoError> {
-        let response = self.http_client.get(url).send().await?;
+    async fn load_from_web(
+        &self,
+        url: &str,
+        headers: &[(String, String)],
+    ) -> Result<Bytes, GalileoError> {
+        Ok(self.load_response(url, headers).await?.body)
+    }
+
+    async fn load_response(
+        &self,
+        url: &str,
+        headers: &[(String, String)],
+    ) -> Result<HttpResponse, GalileoError> {
+        let request = headers
+            .iter()
+            .fold(self.http_client.get(url), |request, (name, value)| {
+                request.header(name, value)
+            });
+        let response = request.send().await?;
+        let status = response.status().as_u16();
+        let response_headers = response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+            return Ok(HttpResponse {
+                status,
+                headers: response_headers,
+                body: Bytes::new(),
+            });
+        }
+
         if !response.status().is_success() {
             info!(
                 "Failed to load {url}: {}, {:?}",
                 response.status(),
                 response.text().await
             );
-            return Err(GalileoError::IO);
+            return Err(GalileoError::HttpStatus(status));
         }
 
-        Ok(response.bytes().await?)
+        Ok(HttpResponse {
+            status,
+            headers: response_headers,
+            body: response.bytes().await?,
+        })
     }
 }
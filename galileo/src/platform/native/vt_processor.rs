@@ -6,6 +6,7 @@ use std::sync::Arc;
 
 use galileo_mvt::MvtTile;
 use parking_lot::RwLock;
+use tokio::sync::Semaphore;
 
 use crate::layer::vector_tile_layer::style::VectorTileStyle;
 use crate::layer::vector_tile_layer::tile_provider::processor::{
@@ -17,19 +18,48 @@ use crate::tile_schema::TileIndex;
 use crate::TileSchema;
 
 /// Vector tile processor that uses a thread pool to run vector tile tessellation in parallel.
+///
+/// By default, the number of tiles tessellated at the same time is one less than the number of
+/// available cores (but at least one), so that the main thread is not starved. Use
+/// [`ThreadVtProcessor::with_thread_count`] to change it.
 pub struct ThreadVtProcessor {
     tile_schema: TileSchema,
     styles: RwLock<HashMap<VtStyleId, Arc<VectorTileStyle>>>,
+    thread_count: usize,
+    workers: Semaphore,
 }
 
 impl ThreadVtProcessor {
     /// Create a new instance of the processor.
     pub fn new(tile_schema: TileSchema) -> Self {
+        let thread_count = default_thread_count();
         Self {
             tile_schema,
             styles: Default::default(),
+            thread_count,
+            workers: Semaphore::new(thread_count),
         }
     }
+
+    /// Sets the maximum number of threads that tessellate tiles at the same time. Values less
+    /// than `1` are treated as `1`.
+    pub fn with_thread_count(mut self, thread_count: usize) -> Self {
+        self.thread_count = thread_count.max(1);
+        self.workers = Semaphore::new(self.thread_count);
+        self
+    }
+
+    /// Maximum number of threads that tessellate tiles at the same time.
+    pub fn thread_count(&self) -> usize {
+        self.thread_count
+    }
+}
+
+fn default_thread_count() -> usize {
+    std::thread::available_parallelism()
+        .map(|count| count.get().saturating_sub(1))
+        .unwrap_or(1)
+        .max(1)
 }
 
 #[async_trait::async_trait]
@@ -61,6 +91,12 @@ impl VectorTileProcessor for ThreadVtProcessor {
             return Err(TileProcessingError::InvalidStyle);
         };
 
+        let _permit = self
+            .workers
+            .acquire()
+            .await
+            .map_err(|_| TileProcessingError::Rendering)?;
+
         let mut bundle = RenderBundle::default();
         let tile_schema = self.tile_schema.clone();
 
@@ -86,3 +122,34 @@ impl VectorTileProcessor for ThreadVtProcessor {
         .map_err(|_| TileProcessingError::Rendering)?
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layer::vector_tile_layer::tile_provider::VectorTileProvider;
+    use crate::tests::TestTileLoader;
+
+    #[tokio::test]
+    async fn single_thread_processes_all_tiles() {
+        let processor = Arc::new(ThreadVtProcessor::new(TileSchema::web(18)).with_thread_count(1));
+        assert_eq!(processor.thread_count(), 1);
+
+        let provider = VectorTileProvider::new(Arc::new(TestTileLoader {}), processor.clone());
+        let style_id = provider.add_style(VectorTileStyle::default());
+
+        let tile = Arc::new(MvtTile { layers: vec![] });
+        let process = |x| processor.process_tile(tile.clone(), TileIndex::new(x, 0, 2), style_id);
+        let results = tokio::join!(process(0), process(1), process(2), process(3));
+        let results = [results.0, results.1, results.2, results.3];
+
+        assert!(results.iter().all(|result| result.is_ok()));
+        assert_eq!(processor.workers.available_permits(), 1);
+    }
+
+    #[test]
+    fn thread_count_is_at_least_one() {
+        let processor = ThreadVtProcessor::new(TileSchema::web(18)).with_thread_count(0);
+        assert_eq!(processor.thread_count(), 1);
+        assert!(default_thread_count() >= 1);
+    }
+}
@@ -19,7 +19,7 @@ use web_sys::{
 
 use crate::decoded_image::{DecodedImage, DecodedImageType};
 use crate::error::GalileoError;
-use crate::platform::PlatformService;
+use crate::platform::{HttpResponse, PlatformService};
 
 pub mod vt_processor;
 pub mod web_workers;
@@ -63,36 +63,95 @@ impl PlatformService for WebPlatformService {
     }
 
     async fn load_bytes_from_url(&self, url: &str) -> Result<bytes::Bytes, GalileoError> {
-        let opts = RequestInit::new();
-        opts.set_method("GET");
-        opts.set_mode(RequestMode::Cors);
-
-        let request =
-            Request::new_with_str_and_init(url, &opts).expect("failed to create a request object");
-        request
-            .headers()
-            .set("Accept", "application/vnd.mapbox-vector-tile")?;
-
-        use wasm_bindgen::JsCast;
-        let resp_value = {
-            if let Some(window) = web_sys::window() {
-                JsFuture::from(window.fetch_with_request(&request)).await?
-            } else if let Ok(global) = js_sys::global().dyn_into::<WorkerGlobalScope>() {
-                JsFuture::from(global.fetch_with_request(&request)).await?
-            } else {
-                return Err(GalileoError::Wasm(Some(
-                    "Global object is not available".into(),
-                )));
+        self.load_bytes_from_url_with_headers(url, &[]).await
+    }
+
+    async fn load_bytes_from_url_with_headers(
+        &self,
+        url: &str,
+        headers: &[(String, String)],
+    ) -> Result<bytes::Bytes, GalileoError> {
+        let resp = fetch(url, headers).await?;
+        if !resp.ok() {
+            log::info!("Failed to load {url}: {}", resp.status());
+            return Err(GalileoError::HttpStatus(resp.status()));
+        }
+
+        read_body(&resp).await
+    }
+
+    async fn load_response_from_url(
+        &self,
+        url: &str,
+        headers: &[(String, String)],
+    ) -> Result<HttpResponse, GalileoError> {
+        let resp = fetch(url, headers).await?;
+        let status = resp.status();
+
+        // Only the headers exposed by the server with `Access-Control-Expose-Headers` are visible for CORS
+        // requests.
+        let mut response_headers = vec![];
+        if let Some(entries) = js_sys::try_iter(&resp.headers())? {
+            for entry in entries {
+                let entry: js_sys::Array = entry?.dyn_into()?;
+                if let (Some(name), Some(value)) =
+                    (entry.get(0).as_string(), entry.get(1).as_string())
+                {
+                    response_headers.push((name, value));
+                }
             }
+        }
+
+        let body = match status {
+            HttpResponse::NOT_MODIFIED => Bytes::new(),
+            200..=299 => read_body(&resp).await?,
+            _ => return Err(GalileoError::HttpStatus(status)),
         };
 
-        assert!(resp_value.is_instance_of::<Response>());
-        let resp: Response = resp_value.dyn_into()?;
+        Ok(HttpResponse {
+            status,
+            headers: response_headers,
+            body,
+        })
+    }
+}
 
-        let bytes_val = JsFuture::from(resp.array_buffer()?).await?;
-        let array = Uint8Array::new(&bytes_val);
-        Ok(array.to_vec().into())
+/// Sends a GET request with the given headers.
+async fn fetch(url: &str, headers: &[(String, String)]) -> Result<Response, GalileoError> {
+    let opts = RequestInit::new();
+    opts.set_method("GET");
+    opts.set_mode(RequestMode::Cors);
+
+    let request =
+        Request::new_with_str_and_init(url, &opts).expect("failed to create a request object");
+    request
+        .headers()
+        .set("Accept", "application/vnd.mapbox-vector-tile")?;
+    for (name, value) in headers {
+        request.headers().set(name, value)?;
     }
+
+    use wasm_bindgen::JsCast;
+    let resp_value = {
+        if let Some(window) = web_sys::window() {
+            JsFuture::from(window.fetch_with_request(&request)).await?
+        } else if let Ok(global) = js_sys::global().dyn_into::<WorkerGlobalScope>() {
+            JsFuture::from(global.fetch_with_request(&request)).await?
+        } else {
+            return Err(GalileoError::Wasm(Some(
+                "Global object is not available".into(),
+            )));
+        }
+    };
+
+    assert!(resp_value.is_instance_of::<Response>());
+    Ok(resp_value.dyn_into()?)
+}
+
+async fn read_body(resp: &Response) -> Result<Bytes, GalileoError> {
+    let bytes_val = JsFuture::from(resp.array_buffer()?).await?;
+    let array = Uint8Array::new(&bytes_val);
+    Ok(array.to_vec().into())
 }
 
 /// Future for getting image with browser API
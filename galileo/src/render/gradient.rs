@@ -0,0 +1,218 @@
+use galileo_types::cartesian::{CartesianPoint2d, CartesianPoint2dFloat, Point2, Rect};
+use serde::{Deserialize, Serialize};
+
+use crate::Color;
+
+/// Color of a gradient at the given position.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GradientStop {
+    /// Position of the stop along the gradient, from `0.0` (start) to `1.0` (end).
+    pub offset: f32,
+    /// Color at the stop.
+    pub color: Color,
+}
+
+impl GradientStop {
+    /// Creates a new gradient stop.
+    pub fn new(offset: f32, color: Color) -> Self {
+        Self { offset, color }
+    }
+}
+
+/// Gradient fill of a polygon.
+///
+/// The gradient is stretched over the bounding rectangle of the polygon in map coordinates, so it
+/// stays attached to the polygon when the map is panned, zoomed or rotated.
+///
+/// Colors are calculated at the vertices of the polygon tessellation and interpolated linearly
+/// between them. Where this differs from the gradient, e.g. around the stops or the center of a
+/// radial gradient, the tessellation is subdivided until the difference is not visible.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum GradientFill {
+    /// Gradient that changes color along a direction.
+    Linear {
+        /// Direction of the gradient in radians, counterclockwise from the x axis. With `0.0`
+        /// the gradient goes from the left (offset `0.0`) to the right (offset `1.0`) edge of
+        /// the polygon.
+        angle: f64,
+        /// Color stops sorted by their offsets.
+        stops: Vec<GradientStop>,
+    },
+    /// Gradient that changes color with the distance from a center point.
+    Radial {
+        /// Center of the gradient relative to the bounding rectangle of the polygon: `(0.0, 0.0)`
+        /// is the bottom left corner and `(1.0, 1.0)` is the top right corner.
+        center: Point2,
+        /// Color stops sorted by their offsets. Offset `1.0` corresponds to the polygon vertex
+        /// farthest from the center.
+        stops: Vec<GradientStop>,
+    },
+}
+
+impl GradientFill {
+    /// Creates a linear gradient with the given direction in radians.
+    pub fn linear(angle: f64, stops: Vec<GradientStop>) -> Self {
+        Self::Linear { angle, stops }
+    }
+
+    /// Creates a radial gradient with the center relative to the polygon's bounding rectangle.
+    pub fn radial(center: Point2, stops: Vec<GradientStop>) -> Self {
+        Self::Radial { center, stops }
+    }
+
+    fn stops(&self) -> &[GradientStop] {
+        match self {
+            Self::Linear { stops, .. } | Self::Radial { stops, .. } => stops,
+        }
+    }
+
+    /// Color of the gradient at the given offset as used by wgpu.
+    pub(crate) fn color_at(&self, offset: f32) -> [f32; 4] {
+        color_at_offset(self.stops(), offset)
+    }
+
+    /// Prepares the gradient to calculate colors of the polygon with the given vertices.
+    pub(crate) fn resolve(&self, vertices: &[Point2]) -> Option<ResolvedGradient<'_>> {
+        let bbox = Rect::from_points(vertices.iter().copied())?;
+        let mapping = match self {
+            Self::Linear { angle, .. } => {
+                let direction = Point2::new(angle.cos(), angle.sin());
+                let project = |p: &Point2| p.x() * direction.x() + p.y() * direction.y();
+                let (min, max) = vertices
+                    .iter()
+                    .map(project)
+                    .fold((f64::MAX, f64::MIN), |(min, max), v| {
+                        (min.min(v), max.max(v))
+                    });
+                OffsetMapping::Linear {
+                    direction,
+                    min,
+                    length: max - min,
+                }
+            }
+            Self::Radial { center, .. } => {
+                let center = Point2::new(
+                    bbox.x_min() + bbox.width() * center.x(),
+                    bbox.y_min() + bbox.height() * center.y(),
+                );
+                let radius = vertices
+                    .iter()
+                    .map(|p| p.distance(&center))
+                    .fold(0.0, f64::max);
+                OffsetMapping::Radial { center, radius }
+            }
+        };
+
+        Some(ResolvedGradient {
+            gradient: self,
+            mapping,
+        })
+    }
+}
+
+/// Color at the given offset of the color stops sorted by their offsets. Colors between the stops
+/// are interpolated linearly.
+pub(crate) fn color_at_offset(stops: &[GradientStop], offset: f32) -> [f32; 4] {
+    let Some(first) = stops.first() else {
+        return Color::TRANSPARENT.to_f32_array();
+    };
+
+    if offset <= first.offset {
+        return first.color.to_f32_array();
+    }
+
+    for pair in stops.windows(2) {
+        let (from, to) = (pair[0], pair[1]);
+        if offset <= to.offset {
+            let span = to.offset - from.offset;
+            let k = if span > 0.0 {
+                (offset - from.offset) / span
+            } else {
+                1.0
+            };
+            let (from, to) = (from.color.to_f32_array(), to.color.to_f32_array());
+            return [0, 1, 2, 3].map(|i| from[i] + (to[i] - from[i]) * k);
+        }
+    }
+
+    stops[stops.len() - 1].color.to_f32_array()
+}
+
+enum OffsetMapping {
+    Linear {
+        direction: Point2,
+        min: f64,
+        length: f64,
+    },
+    Radial {
+        center: Point2,
+        radius: f64,
+    },
+}
+
+/// Gradient fitted to a specific polygon.
+pub(crate) struct ResolvedGradient<'a> {
+    gradient: &'a GradientFill,
+    mapping: OffsetMapping,
+}
+
+impl ResolvedGradient<'_> {
+    /// Color of the polygon at the given point.
+    pub(crate) fn color_at(&self, point: &impl CartesianPoint2d<Num = f64>) -> [f32; 4] {
+        let (value, length) = match &self.mapping {
+            OffsetMapping::Linear {
+                direction,
+                min,
+                length,
+            } => (
+                point.x() * direction.x() + point.y() * direction.y() - min,
+                *length,
+            ),
+            OffsetMapping::Radial { center, radius } => (point.distance(center), *radius),
+        };
+
+        let offset = if length > 0.0 { value / length } else { 0.0 };
+        self.gradient.color_at(offset as f32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn two_stops() -> Vec<GradientStop> {
+        vec![
+            GradientStop::new(0.0, Color::RED),
+            GradientStop::new(1.0, Color::BLUE),
+        ]
+    }
+
+    #[test]
+    fn color_at_interpolates_between_stops() {
+        let gradient = GradientFill::linear(0.0, two_stops());
+        assert_eq!(gradient.color_at(-1.0), Color::RED.to_f32_array());
+        assert_eq!(gradient.color_at(0.5), [0.5, 0.0, 0.5, 1.0]);
+        assert_eq!(gradient.color_at(2.0), Color::BLUE.to_f32_array());
+    }
+
+    #[test]
+    fn radial_gradient_grows_from_center() {
+        let gradient = GradientFill::radial(Point2::new(0.5, 0.5), two_stops());
+        let square = [
+            Point2::new(0.0, 0.0),
+            Point2::new(0.0, 2.0),
+            Point2::new(2.0, 2.0),
+            Point2::new(2.0, 0.0),
+        ];
+        let resolved = gradient.resolve(&square).expect("polygon is not empty");
+
+        assert_eq!(
+            resolved.color_at(&Point2::new(1.0, 1.0)),
+            Color::RED.to_f32_array()
+        );
+        assert_eq!(
+            resolved.color_at(&Point2::new(2.0, 2.0)),
+            Color::BLUE.to_f32_array()
+        );
+    }
+}
@@ -5,19 +5,27 @@
 //! At this point only [`WgpuRenderer`] is implemented.
 
 use std::any::Any;
+use std::sync::Arc;
 
 use galileo_types::cartesian::Size;
 use maybe_sync::{MaybeSend, MaybeSync};
 use render_bundle::RenderBundle;
 use serde::{Deserialize, Serialize};
 
+use crate::decoded_image::DecodedImage;
 use crate::Color;
 
 #[cfg(feature = "wgpu")]
 mod wgpu;
+#[cfg(all(feature = "wgpu", not(target_arch = "wasm32")))]
+pub use wgpu::FrameRecorder;
 #[cfg(feature = "wgpu")]
 pub use wgpu::{HorizonOptions, WgpuRenderer};
 
+mod gradient;
+pub(crate) use gradient::color_at_offset;
+pub use gradient::{GradientFill, GradientStop};
+
 pub mod point_paint;
 pub mod render_bundle;
 pub mod text;
@@ -37,6 +45,12 @@ pub mod text;
 pub trait Canvas {
     /// Size of the drawing area.
     fn size(&self) -> Size;
+    /// Number of physical pixels per logical pixel of the drawing area.
+    ///
+    /// Layers use it to scale sizes given in logical pixels (line widths, font sizes etc.) on HiDPI displays.
+    fn scale_factor(&self) -> f32 {
+        1.0
+    }
     /// Packs a bundle to make it ready for be rendered with [`Canvas::draw_bundles`] method.
     fn pack_bundle(&self, bundle: &RenderBundle) -> Box<dyn PackedBundle>;
     /// Render the bundles.
@@ -73,14 +87,147 @@ impl Default for RenderOptions {
 }
 
 /// Parameters to draw a polygon primitive with.
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PolygonPaint {
     /// Fill color of the polygon.
     pub color: Color,
+    /// If set, the polygon is filled with the gradient instead of the `color`.
+    #[serde(default)]
+    pub gradient: Option<GradientFill>,
+    /// Rule that decides which areas bounded by the polygon contours are filled.
+    #[serde(default)]
+    pub fill_rule: FillRule,
+    /// If set, the polygon is filled with a repeating image. The `color` (or the `gradient`) is then used as the
+    /// background visible through the transparent pixels of the pattern.
+    #[serde(default)]
+    pub pattern: Option<FillPattern>,
+    /// If set, the outer and inner rings of the polygon are outlined with this paint.
+    #[serde(default)]
+    pub stroke: Option<LinePaint>,
+}
+
+impl PolygonPaint {
+    /// Creates a paint with a solid fill color.
+    pub fn new(color: Color) -> Self {
+        Self {
+            color,
+            gradient: None,
+            fill_rule: FillRule::default(),
+            pattern: None,
+            stroke: None,
+        }
+    }
+
+    /// Sets the gradient fill of the polygon.
+    pub fn with_gradient(mut self, gradient: GradientFill) -> Self {
+        self.gradient = Some(gradient);
+        self
+    }
+
+    /// Sets the fill rule of the polygon.
+    pub fn with_fill_rule(mut self, fill_rule: FillRule) -> Self {
+        self.fill_rule = fill_rule;
+        self
+    }
+
+    /// Sets the pattern the polygon is filled with.
+    ///
+    /// The pattern is drawn over the fill color, so to get only the pattern with transparent gaps between its
+    /// elements, set the color to [`Color::TRANSPARENT`].
+    pub fn with_pattern(mut self, pattern: FillPattern) -> Self {
+        self.pattern = Some(pattern);
+        self
+    }
+
+    /// Sets the paint of the polygon outline.
+    ///
+    /// The outline is drawn over the fill of the polygon. Its [`LinePaint::offset`] can be used to move the outline
+    /// outside or inside of the polygon.
+    pub fn with_stroke(mut self, stroke: LinePaint) -> Self {
+        self.stroke = Some(stroke);
+        self
+    }
+}
+
+/// Tileable image repeated over the area of a polygon.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FillPattern {
+    /// Image of one tile of the pattern. The image must be seamless for the pattern to look continuous.
+    pub image: Arc<DecodedImage>,
+    /// Space in which the size of the pattern is set.
+    pub space: PatternSpace,
+    /// Number of pixels one pixel of the image covers.
+    pub scale: f32,
+    /// Rotation of the pattern counterclockwise in radians.
+    pub rotation: f32,
+}
+
+impl FillPattern {
+    /// Creates a pattern drawn in screen space with the original size of the image and no rotation.
+    pub fn new(image: Arc<DecodedImage>) -> Self {
+        Self {
+            image,
+            space: PatternSpace::Screen,
+            scale: 1.0,
+            rotation: 0.0,
+        }
+    }
+
+    /// Sets the space in which the size of the pattern is set.
+    pub fn with_space(mut self, space: PatternSpace) -> Self {
+        self.space = space;
+        self
+    }
+
+    /// Sets the number of pixels one pixel of the image covers.
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Sets the rotation of the pattern counterclockwise in radians.
+    pub fn with_rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+}
+
+/// Space in which the size of a [`FillPattern`] is set.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PatternSpace {
+    /// The pattern keeps its size on the screen, so its pixels stay crisp at any zoom level. This suits hatching
+    /// and other symbolic fills.
+    #[default]
+    Screen,
+    /// The pattern is attached to the map and is scaled together with it. The scale of the pattern is set for the
+    /// resolution at which the polygon is added to the bundle.
+    World,
+}
+
+/// Rule that decides which parts of a polygon are inside of it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FillRule {
+    /// A point is inside the polygon if a ray from it crosses the contours an odd number of times. Rings nested
+    /// into each other alternate between fills and holes independently of their orientation, so a ring inside a
+    /// hole is filled again. This matches the interpretation of polygons in GeoJSON and OGC Simple Features.
+    #[default]
+    EvenOdd,
+    /// A point is inside the polygon if the contours wind around it a non-zero number of times. With this rule, a
+    /// ring is a hole only if it is oriented opposite to the ring that contains it.
+    NonZero,
+}
+
+impl From<FillRule> for lyon::path::FillRule {
+    fn from(val: FillRule) -> Self {
+        match val {
+            FillRule::EvenOdd => lyon::path::FillRule::EvenOdd,
+            FillRule::NonZero => lyon::path::FillRule::NonZero,
+        }
+    }
 }
 
 /// Parameter to draw a line primitive with.
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LinePaint {
     /// Color of the line.
     pub color: Color,
@@ -91,6 +238,15 @@ pub struct LinePaint {
     pub offset: f64,
     /// Type of the cap of the line.
     pub line_cap: LineCap,
+    /// Lengths of alternating dashes and gaps in pixels, starting with a dash. If the pattern has an odd number of
+    /// elements, it is repeated twice, so `[5.0]` is the same as `[5.0, 5.0]`.
+    ///
+    /// If not set, the line is drawn solid. Combine a zero-length dash with [`LineCap::Round`] to draw a dotted line.
+    #[serde(default)]
+    pub dash_pattern: Option<Vec<f32>>,
+    /// Distance in pixels into the dash pattern at which the line starts.
+    #[serde(default)]
+    pub dash_offset: f32,
 }
 
 /// Cap (end point) style of the line.
@@ -111,6 +267,28 @@ impl From<LineCap> for lyon::path::LineCap {
     }
 }
 
+/// Arrows drawn along a line to show its direction.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ArrowPaint {
+    /// Color of the arrows.
+    pub color: Color,
+    /// Length and width of an arrow in pixels.
+    pub size: f32,
+    /// Distance between the arrows along the line in pixels.
+    pub spacing: f32,
+}
+
+impl ArrowPaint {
+    /// Creates a new instance.
+    pub fn new(color: Color, size: f32, spacing: f32) -> Self {
+        Self {
+            color,
+            size,
+            spacing,
+        }
+    }
+}
+
 /// Parameter to render an image with.
 pub struct ImagePaint {
     /// Opacity of the image. The value of 255 means fully opaque image.
@@ -118,4 +296,17 @@ pub struct ImagePaint {
     /// If an image contains non-opaque pixels, the resulting opacity of those pixels is the product of the pixel
     /// opacity and this value represented in percents.
     pub opacity: u8,
+    /// How the image pixels are sampled when the image is scaled.
+    pub sampling: ImageSampling,
+}
+
+/// Method of calculating the color of a scaled image at a screen pixel.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ImageSampling {
+    /// Color of the closest image pixel is used. Scaled images look blocky, but the colors of the image are
+    /// preserved exactly, which is needed for classified rasters like land cover maps.
+    Nearest,
+    /// Colors of the neighbouring image pixels are interpolated, so scaled images look smooth.
+    #[default]
+    Linear,
 }
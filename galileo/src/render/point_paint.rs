@@ -3,7 +3,7 @@
 use std::borrow::Cow;
 use std::sync::Arc;
 
-use galileo_types::cartesian::{Point2, Size, Vector2};
+use galileo_types::cartesian::{CartesianPoint2d, Point2, Rect, Size, Vector2};
 use galileo_types::impls::ClosedContour;
 use serde::{Deserialize, Serialize};
 
@@ -112,6 +112,8 @@ impl<'a> PointPaint<'a> {
                     width: width as f64,
                     offset: 0.0,
                     line_cap: LineCap::Round,
+                    dash_pattern: None,
+                    dash_offset: 0.0,
                 })
             }
             _ => {}
@@ -131,6 +133,39 @@ impl<'a> PointPaint<'a> {
         self.offset = offset;
         self
     }
+
+    /// Radius in pixels of the circle around the point that covers the drawn shape, or `None` if the size of the
+    /// shape is not known in advance (e.g. for labels).
+    pub(crate) fn pick_radius(&self) -> Option<f32> {
+        let outline_width =
+            |outline: &Option<LinePaint>| outline.as_ref().map_or(0.0, |o| o.width as f32);
+        match &self.shape {
+            PointShape::Dot { .. } => Some(1.0),
+            PointShape::Circle {
+                radius, outline, ..
+            } => Some(radius + outline_width(outline)),
+            PointShape::Sector(parameters) => {
+                Some(parameters.radius + outline_width(&parameters.outline))
+            }
+            PointShape::Square { size, outline, .. } => {
+                Some(size * std::f32::consts::FRAC_1_SQRT_2 + outline_width(outline))
+            }
+            PointShape::FreeShape {
+                scale,
+                outline,
+                shape,
+                ..
+            } => {
+                let radius = shape
+                    .points
+                    .iter()
+                    .map(|p| p.x().hypot(p.y()))
+                    .fold(0.0, f32::max);
+                Some(radius * scale + outline_width(outline))
+            }
+            PointShape::Label { .. } => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -171,12 +206,23 @@ pub enum MarkerStyle {
         image: Arc<DecodedImage>,
         /// Anchor point.
         anchor: Vector2<f32>,
-        /// Size of the marker image in pixels. If not set, the size of the bitmap will be used.
+        /// Size of the marker image in pixels. If not set, the size of the source rectangle (or of the whole
+        /// bitmap) will be used.
         size: Option<Size<u32>>,
+        /// Part of the bitmap to draw in pixels of the bitmap with the origin in the top left corner. If not set,
+        /// the whole bitmap is drawn.
+        ///
+        /// This allows many different markers to share one texture atlas.
+        #[serde(default)]
+        source_rect: Option<Rect<u32>>,
+        /// Rotation of the image around the anchor point in radians. Positive values rotate the image clockwise on
+        /// the screen, so a bearing or a heading can be used directly.
+        #[serde(default)]
+        rotation: f32,
     },
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct SectorParameters {
     pub fill: CircleFill,
     pub radius: f32,
@@ -0,0 +1,138 @@
+use serde::{Deserialize, Serialize};
+
+/// Handle of a primitive added to a [`RenderBundle`](super::RenderBundle).
+///
+/// A handle stays valid until the primitive is removed or the bundle is cleared. After that the index of the handle
+/// can be given to another primitive, but the handle itself will never refer to it, since the generation of the index
+/// is increased.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PrimitiveHandle {
+    index: u32,
+    generation: u32,
+}
+
+impl PrimitiveHandle {
+    /// Index of the primitive in the bundle.
+    ///
+    /// Indices are given out in the order the primitives are added, starting from `0`, so they can be used as
+    /// indices into a list of the features the primitives were created for.
+    pub fn index(&self) -> usize {
+        self.index as usize
+    }
+}
+
+/// Allocates handles of the bundle primitives.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct HandleAllocator {
+    /// Current generation of every index.
+    generations: Vec<u32>,
+    /// Indices that are not used by any primitive, in reverse order of reuse.
+    free: Vec<u32>,
+}
+
+impl HandleAllocator {
+    pub fn allocate(&mut self) -> PrimitiveHandle {
+        let index = match self.free.pop() {
+            Some(index) => index,
+            None => {
+                self.generations.push(0);
+                (self.generations.len() - 1) as u32
+            }
+        };
+
+        PrimitiveHandle {
+            index,
+            generation: self.generations[index as usize],
+        }
+    }
+
+    /// Free indices always have a generation that no handle was given out with, so only the generation is checked.
+    pub fn is_valid(&self, handle: PrimitiveHandle) -> bool {
+        self.generations.get(handle.index()) == Some(&handle.generation)
+    }
+
+    /// Invalidates the handle and makes its index available again. Returns `false` if the handle was not valid.
+    pub fn free(&mut self, handle: PrimitiveHandle) -> bool {
+        if !self.is_valid(handle) {
+            return false;
+        }
+
+        let generation = &mut self.generations[handle.index()];
+        *generation = generation.wrapping_add(1);
+        self.free.push(handle.index);
+        true
+    }
+
+    /// Invalidates all handles and makes all indices available again.
+    pub fn clear(&mut self) {
+        for generation in &mut self.generations {
+            *generation = generation.wrapping_add(1);
+        }
+
+        self.free.clear();
+        self.free.extend((0..self.generations.len() as u32).rev());
+    }
+
+    /// Number of indices reserved by the allocator, including the free ones.
+    pub fn len(&self) -> usize {
+        self.generations.len()
+    }
+
+    /// Adds the indices of `other` after the indices of this allocator. Handles of `other` are converted with
+    /// [`HandleAllocator::shift`].
+    pub fn append(&mut self, other: HandleAllocator) {
+        let offset = self.generations.len() as u32;
+        self.generations.extend(other.generations);
+        self.free
+            .extend(other.free.into_iter().map(|index| index + offset));
+    }
+
+    /// Converts a handle of the allocator appended after `offset` indices into a handle of the combined allocator.
+    pub fn shift(handle: PrimitiveHandle, offset: usize) -> PrimitiveHandle {
+        PrimitiveHandle {
+            index: handle.index + offset as u32,
+            ..handle
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handles_are_invalidated_by_clear() {
+        let mut allocator = HandleAllocator::default();
+        let first = allocator.allocate();
+        let second = allocator.allocate();
+        assert_eq!((first.index(), second.index()), (0, 1));
+        assert!(allocator.is_valid(first));
+
+        allocator.clear();
+        assert!(!allocator.is_valid(first));
+        assert!(!allocator.is_valid(second));
+
+        let reused = allocator.allocate();
+        assert_eq!(reused.index(), 0);
+        assert_ne!(reused, first);
+        assert!(allocator.is_valid(reused));
+        assert!(!allocator.is_valid(second));
+    }
+
+    #[test]
+    fn freed_index_is_reused_with_new_generation() {
+        let mut allocator = HandleAllocator::default();
+        let first = allocator.allocate();
+        let second = allocator.allocate();
+
+        assert!(allocator.free(first));
+        assert!(!allocator.free(first));
+        assert!(!allocator.is_valid(first));
+        assert!(allocator.is_valid(second));
+
+        let reused = allocator.allocate();
+        assert_eq!(reused.index(), first.index());
+        assert_ne!(reused, first);
+        assert_eq!(allocator.allocate().index(), 2);
+    }
+}
@@ -1,84 +1,598 @@
 //! [`RenderBundle`] is used to store primitives and prepare them for rendering with the rendering backend.
 
+#[cfg(feature = "serde")]
+use std::mem::size_of;
 use std::sync::Arc;
 
-use galileo_types::cartesian::{CartesianPoint3d, Point2, Vector2};
+use galileo_types::cartesian::{CartesianPoint3d, Point2, Rect, Vector2};
 use galileo_types::contour::Contour;
-use galileo_types::Polygon;
+use galileo_types::{MultiPolygon, Polygon};
+use handle::HandleAllocator;
+pub use handle::PrimitiveHandle;
 use num_traits::AsPrimitive;
+use pick::PickSet;
 use screen_set::ScreenRenderSet;
 use serde::{Deserialize, Serialize};
 
 use super::point_paint::MarkerStyle;
-use super::text::TextStyle;
+use super::text::{LabelBackground, LabelPlacement, TextStyle};
 use crate::decoded_image::DecodedImage;
+#[cfg(feature = "serde")]
+use crate::error::GalileoError;
 use crate::render::point_paint::PointPaint;
-use crate::render::render_bundle::world_set::WorldRenderSet;
-use crate::render::{ImagePaint, LinePaint, PolygonPaint};
+use crate::render::render_bundle::world_set::{PrimitiveKind, WorldRenderSet, WorldSetMark};
+use crate::render::{ArrowPaint, ImagePaint, LinePaint, PolygonPaint};
+use crate::Color;
 
+mod handle;
+mod pick;
 pub(crate) mod screen_set;
 pub(crate) mod world_set;
 
+/// First bytes of a serialized render bundle.
+#[cfg(feature = "serde")]
+const FORMAT_MAGIC: &[u8; 4] = b"GLRB";
+/// Version of the binary format of the render bundle. Must be increased on every change of the bundle layout.
+#[cfg(feature = "serde")]
+const FORMAT_VERSION: u16 = 8;
+#[cfg(feature = "serde")]
+const HEADER_SIZE: usize = FORMAT_MAGIC.len() + size_of::<u16>();
+
 /// Render bundle is used to store render primitives and prepare them to be rendered with the rendering backend.
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct RenderBundle {
     pub(crate) world_set: WorldRenderSet,
     pub(crate) screen_sets: Vec<ScreenRenderSet>,
+    #[serde(default)]
+    pub(crate) handles: HandleAllocator,
+    #[serde(default)]
+    pub(crate) pick_set: PickSet,
 }
 
 impl RenderBundle {
+    /// Sets the maximum distance in pixels between curved shapes (round line caps, circles etc.) and
+    /// the triangles they are tessellated into.
+    ///
+    /// Smaller values make curves smoother at the cost of more vertices. Polygons are tessellated in map coordinates,
+    /// so for them the tolerance is converted into map units using the `min_resolution` they are added with. The
+    /// default value is `0.1`.
+    ///
+    /// The tolerance applies to the primitives added to the bundle after this call.
+    pub fn with_tessellation_tolerance(mut self, tolerance: f32) -> Self {
+        self.set_tessellation_tolerance(tolerance);
+        self
+    }
+
+    /// Sets the tessellation tolerance. See [`RenderBundle::with_tessellation_tolerance`].
+    pub fn set_tessellation_tolerance(&mut self, tolerance: f32) {
+        self.world_set.tessellation_tolerance = tolerance;
+    }
+
+    /// Maximum distance in pixels between curved shapes and their tessellation.
+    pub fn tessellation_tolerance(&self) -> f32 {
+        self.world_set.tessellation_tolerance
+    }
+
+    /// Sets the scale factor of the display the bundle is rendered to, i.e. the number of physical pixels in a
+    /// logical pixel. It is used to convert the font sizes given in points into pixels (see
+    /// [`FontSizeUnit`](crate::render::text::FontSizeUnit)). The default value is `1.0`.
+    ///
+    /// The scale factor applies to the labels added to the bundle after this call.
+    pub fn with_scale_factor(mut self, scale_factor: f32) -> Self {
+        self.set_scale_factor(scale_factor);
+        self
+    }
+
+    /// Sets the display scale factor. See [`RenderBundle::with_scale_factor`].
+    pub fn set_scale_factor(&mut self, scale_factor: f32) {
+        self.world_set.scale_factor = scale_factor;
+    }
+
+    /// Scale factor of the display the bundle is rendered to.
+    pub fn scale_factor(&self) -> f32 {
+        self.world_set.scale_factor
+    }
+
+    /// Enables hit-testing of the primitives with [`RenderBundle::pick`].
+    ///
+    /// To be picked, the geometry of every point, line and polygon is stored in the bundle in addition to its
+    /// tessellation, so picking is disabled by default. It applies to the primitives added after this call.
+    pub fn with_picking(mut self, enabled: bool) -> Self {
+        self.set_picking(enabled);
+        self
+    }
+
+    /// Enables or disables hit-testing. See [`RenderBundle::with_picking`].
+    pub fn set_picking(&mut self, enabled: bool) {
+        self.pick_set.enabled = enabled;
+    }
+
+    /// Enables updating points and lines in place with [`RenderBundle::update_point_position`] and
+    /// [`RenderBundle::update_line`].
+    ///
+    /// To be updated, the source geometry of every point and line (with the line's vertex colors and arrows) is
+    /// stored in the bundle in addition to its tessellation, so updates are disabled by default. It applies to the
+    /// primitives added after this call.
+    pub fn with_updates(mut self, enabled: bool) -> Self {
+        self.set_updates(enabled);
+        self
+    }
+
+    /// Enables or disables in-place updates. See [`RenderBundle::with_updates`].
+    pub fn set_updates(&mut self, enabled: bool) {
+        self.world_set.updates_enabled = enabled;
+    }
+
+    /// Returns the topmost point, line or polygon of the bundle under the given `point`, or `None` if there is no
+    /// primitive there or picking is not enabled with [`RenderBundle::with_picking`].
+    ///
+    /// The `point` is given in map coordinates (a screen position can be converted with
+    /// [`MapView::screen_to_map`](crate::MapView::screen_to_map)), and `resolution` is used to convert point sizes
+    /// and line widths from pixels into map units. Points are hit within their radius, lines within half of their
+    /// width, and polygons by their area excluding holes. Primitives with higher z-index are on top, and among the
+    /// primitives with the same z-index the last added one is on top. Labels, markers and images cannot be picked.
+    pub fn pick(&self, point: &Point2, resolution: f64) -> Option<PrimitiveHandle> {
+        self.pick_set.pick(point, resolution)
+    }
+
+    /// Dumps the tessellated polygons and lines of the bundle in [Wavefront OBJ](https://en.wikipedia.org/wiki/Wavefront_.obj_file)
+    /// format for debugging. The result can be opened with any 3D model viewer to inspect the generated triangles.
+    ///
+    /// Vertices are written in map coordinates. Lines and other screen-referenced primitives (like circles) are
+    /// widened to their pixel size only when rendering, so their triangles appear collapsed to the line itself in
+    /// the dump. Points, images and screen sets are not included.
+    pub fn tessellation_to_obj(&self) -> String {
+        self.world_set.tessellation_to_obj()
+    }
+
+    /// Removes all primitives from the bundle while retaining the allocated memory, so that the bundle can be
+    /// reused for the next frame without reallocating its buffers.
+    ///
+    /// All [`PrimitiveHandle`]s given out by the bundle become invalid. Tessellation tolerance and scale factor of
+    /// the bundle are not changed.
+    pub fn clear(&mut self) {
+        self.world_set.clear();
+        self.screen_sets.clear();
+        self.handles.clear();
+        self.pick_set.clear();
+    }
+
+    /// Returns true if the `handle` was given out by this bundle, and neither the primitive was removed nor the
+    /// bundle was cleared since then.
+    pub fn is_valid(&self, handle: PrimitiveHandle) -> bool {
+        self.handles.is_valid(handle)
+    }
+
+    /// Removes the primitive with the given handle from the bundle. Returns `false` if the handle is not valid.
+    ///
+    /// The primitive stops being drawn and picked, and the handle becomes invalid. Its data is removed from the
+    /// buffers of the bundle, which takes time linear in the size of the bundle. Images stay in the bundle until it
+    /// is cleared, as they can be shared by several primitives. The index of the handle is reused by the next added
+    /// primitive.
+    pub fn remove(&mut self, handle: PrimitiveHandle) -> bool {
+        if !self.handles.free(handle) {
+            return false;
+        }
+
+        self.world_set.remove_primitive(handle.index());
+        self.pick_set.remove(handle);
+        true
+    }
+
+    /// Redraws the line with the given handle with a new paint. Returns `false` if the handle is not valid, it is
+    /// not a handle of a line, or the line was added with updates disabled (see [`RenderBundle::with_updates`]).
+    ///
+    /// The line keeps its handle, z-index, vertex colors and arrows. Only the line itself is tessellated again.
+    pub fn update_line(&mut self, handle: PrimitiveHandle, paint: &LinePaint) -> bool {
+        if !self.handles.is_valid(handle) || !self.world_set.update_line(handle.index(), paint) {
+            return false;
+        }
+
+        self.pick_set.update_line(handle, paint);
+        true
+    }
+
+    /// Moves the point with the given handle to a new position. Returns `false` if the handle is not valid, it is
+    /// not a handle of a point, or the point was added with updates disabled (see [`RenderBundle::with_updates`]).
+    ///
+    /// Unlike [`RenderBundle::update_line`], the point is not tessellated again, only the positions of its vertices
+    /// are changed, so this is cheap enough to be done every frame for moving objects.
+    pub fn update_point_position<N, P>(&mut self, handle: PrimitiveHandle, position: &P) -> bool
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+    {
+        let position = [position.x().as_(), position.y().as_(), position.z().as_()];
+        if !self.handles.is_valid(handle) || !self.world_set.move_point(handle.index(), position) {
+            return false;
+        }
+
+        self.pick_set.move_point(handle, [position[0], position[1]]);
+        true
+    }
+
+    /// Returns the bounding rectangle of all points, lines, polygons and images of the bundle in map coordinates,
+    /// or `None` if the bundle contains no such primitives.
+    ///
+    /// Only the anchor positions of the primitives are taken into account: line widths, point sizes and other
+    /// dimensions specified in pixels are not included. Screen-referenced primitives (labels, markers) are ignored.
+    pub fn world_bounds(&self) -> Option<Rect> {
+        self.world_set.world_bounds()
+    }
+
+    /// Serializes the bundle into a compact binary format, which can be read back with [`RenderBundle::from_bytes`].
+    ///
+    /// The data starts with a header containing the version of the format, so bundles stored by an incompatible
+    /// version of the library are rejected when read.
+    #[cfg(feature = "serde")]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let payload = bincode::serde::encode_to_vec(self, bincode::config::standard())
+            .expect("render bundle is always serializable");
+
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + payload.len());
+        bytes.extend_from_slice(FORMAT_MAGIC);
+        bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&payload);
+
+        bytes
+    }
+
+    /// Reads a bundle serialized with [`RenderBundle::to_bytes`].
+    ///
+    /// Returns [`GalileoError::Deserialization`] if the data is not a serialized bundle, or it was written with a
+    /// different version of the format.
+    #[cfg(feature = "serde")]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, GalileoError> {
+        if bytes.len() < HEADER_SIZE || !bytes.starts_with(FORMAT_MAGIC) {
+            return Err(GalileoError::Deserialization(
+                "data is not a serialized render bundle".into(),
+            ));
+        }
+
+        let version =
+            u16::from_le_bytes([bytes[FORMAT_MAGIC.len()], bytes[FORMAT_MAGIC.len() + 1]]);
+        if version != FORMAT_VERSION {
+            return Err(GalileoError::Deserialization(format!(
+                "unsupported render bundle format version {version}, expected {FORMAT_VERSION}"
+            )));
+        }
+
+        let payload = &bytes[HEADER_SIZE..];
+        let (bundle, read) =
+            bincode::serde::decode_from_slice(payload, bincode::config::standard())
+                .map_err(|err| GalileoError::Deserialization(err.to_string()))?;
+        if read != payload.len() {
+            return Err(GalileoError::Deserialization(
+                "unexpected data after the render bundle".into(),
+            ));
+        }
+
+        Ok(bundle)
+    }
+
+    /// Moves all primitives of the `other` bundle into this one.
+    ///
+    /// This can be used to combine bundles prepared in parallel into one bundle before rendering. The cost of the
+    /// operation is linear in the number of primitives in `other`. If both bundles have a clip area set, the clip
+    /// area of this bundle is kept. Tessellation tolerance and scale factor of `other` are ignored.
+    pub fn append(&mut self, mut other: RenderBundle) {
+        // Primitive records are indexed by handle index, so the records of `other` must start at the handle offset.
+        self.world_set
+            .primitives
+            .resize_with(self.handles.len(), Default::default);
+        other
+            .world_set
+            .primitives
+            .resize_with(other.handles.len(), Default::default);
+        self.world_set.append(other.world_set);
+        self.screen_sets.extend(other.screen_sets);
+
+        let handle_offset = self.handles.len();
+        self.handles.append(other.handles);
+        self.pick_set.append(other.pick_set, handle_offset);
+    }
+
+    /// Reserves capacity for at least `vertices` more polygon and line vertices, `indices` more triangle indices
+    /// and `points` more points.
+    ///
+    /// Use it when the approximate size of the bundle is known in advance to avoid repeated reallocations while
+    /// adding primitives.
+    pub fn reserve(&mut self, vertices: usize, indices: usize, points: usize) {
+        self.world_set.reserve(vertices, indices, points);
+    }
+
+    fn set_z_index(&mut self, z_index: i32) {
+        self.world_set.set_z_index(z_index);
+        self.pick_set.z_index = z_index;
+    }
+
+    /// Returns the source data of a primitive if updates are enabled, or [`PrimitiveKind::Other`] otherwise.
+    fn updatable_kind(&self, kind: impl FnOnce() -> PrimitiveKind) -> PrimitiveKind {
+        if self.world_set.updates_enabled {
+            kind()
+        } else {
+            PrimitiveKind::Other
+        }
+    }
+
+    /// Gives out a handle for the primitive added to the world set since the `mark`.
+    fn add_primitive(&mut self, mark: WorldSetMark, kind: PrimitiveKind) -> PrimitiveHandle {
+        let handle = self.handles.allocate();
+        let record = self.world_set.record_since(mark, kind);
+        self.world_set.set_primitive(handle.index(), record);
+        handle
+    }
+
     /// Adds an image to the bundle.
     pub fn add_image(
         &mut self,
         image: Arc<DecodedImage>,
         vertices: [Point2; 4],
         paint: ImagePaint,
-    ) {
+    ) -> PrimitiveHandle {
+        let mark = self.world_set.mark();
         self.world_set.add_image(image, vertices, paint);
+        self.add_primitive(mark, PrimitiveKind::Other)
     }
+
+    /// Adds a part of an image to the bundle, stretched to the given `vertices`.
     ///
+    /// The `source` region is given in the fractions of the image size, with the origin in the top
+    /// left corner of the image, so `Rect::new(0.0, 0.0, 0.5, 0.5)` is the top left quarter of
+    /// the image.
+    pub fn add_image_region(
+        &mut self,
+        image: Arc<DecodedImage>,
+        source: Rect,
+        vertices: [Point2; 4],
+        paint: ImagePaint,
+    ) -> PrimitiveHandle {
+        let mark = self.world_set.mark();
+        self.world_set
+            .add_image_region(image, source, vertices, paint);
+        self.add_primitive(mark, PrimitiveKind::Other)
+    }
+
     /// Adds an image to the bundle.
     pub fn add_image_owned(
         &mut self,
         image: DecodedImage,
         vertices: [Point2; 4],
         paint: ImagePaint,
-    ) {
+    ) -> PrimitiveHandle {
+        let mark = self.world_set.mark();
         self.world_set.add_image_owned(image, vertices, paint);
+        self.add_primitive(mark, PrimitiveKind::Other)
     }
 
     /// Adds a point to the bundle.
-    pub fn add_point<N, P>(&mut self, point: &P, paint: &PointPaint, _min_resolution: f64)
+    ///
+    /// Returns the handle of the point, which is returned by [`RenderBundle::pick`] when the point is picked.
+    pub fn add_point<N, P>(
+        &mut self,
+        point: &P,
+        paint: &PointPaint,
+        _min_resolution: f64,
+    ) -> PrimitiveHandle
     where
         N: AsPrimitive<f32>,
         P: CartesianPoint3d<Num = N>,
     {
+        let mark = self.world_set.mark();
         self.world_set.add_point(point, paint);
+
+        let kind = self.updatable_kind(|| PrimitiveKind::point(point));
+        let handle = self.add_primitive(mark, kind);
+        self.pick_set.add_point(handle, point, paint);
+        handle
+    }
+
+    /// Adds a point to the bundle with the given z-index.
+    ///
+    /// Primitives of the bundle with higher z-index are drawn over the ones with lower z-index, whatever their type
+    /// is, e.g. a point can be drawn under a polygon. Primitives with the same z-index are drawn in the order they
+    /// were added. Primitives added without z-index have z-index of `0`. Labels and markers drawn in screen space are
+    /// not affected, as they are always drawn over the map.
+    pub fn add_point_with_z_index<N, P>(
+        &mut self,
+        point: &P,
+        paint: &PointPaint,
+        min_resolution: f64,
+        z_index: i32,
+    ) -> PrimitiveHandle
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+    {
+        self.set_z_index(z_index);
+        let handle = self.add_point(point, paint, min_resolution);
+        self.set_z_index(0);
+        handle
+    }
+
+    /// Adds a line to the bundle and returns its handle.
+    pub fn add_line<N, P, C>(
+        &mut self,
+        line: &C,
+        paint: &LinePaint,
+        min_resolution: f64,
+    ) -> PrimitiveHandle
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+        C: Contour<Point = P>,
+    {
+        let mark = self.world_set.mark();
+        self.world_set.add_line(line, paint, min_resolution);
+
+        let kind = self.updatable_kind(|| PrimitiveKind::line(line, None, None, min_resolution));
+        let handle = self.add_primitive(mark, kind);
+        self.pick_set.add_line(handle, line, paint);
+        handle
+    }
+
+    /// Adds a line to the bundle with the given z-index. See [`RenderBundle::add_point_with_z_index`] for details.
+    pub fn add_line_with_z_index<N, P, C>(
+        &mut self,
+        line: &C,
+        paint: &LinePaint,
+        min_resolution: f64,
+        z_index: i32,
+    ) -> PrimitiveHandle
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+        C: Contour<Point = P>,
+    {
+        self.set_z_index(z_index);
+        let handle = self.add_line(line, paint, min_resolution);
+        self.set_z_index(0);
+        handle
     }
 
-    /// Adds a line to the bundle.
-    pub fn add_line<N, P, C>(&mut self, line: &C, paint: &LinePaint, min_resolution: f64)
+    /// Adds a line with arrows along it showing the direction of the line.
+    ///
+    /// Arrows are placed every `arrows.spacing` pixels, starting at half of the spacing from the start of the line,
+    /// and point from the start of the line to its end. Arrows that would stick out beyond the ends of the line
+    /// (including its cap) are not drawn. The arrows are drawn on the center line of the line, ignoring its offset.
+    ///
+    /// Like the line width, the arrows keep their size in pixels, but their positions along the line are calculated
+    /// for the `min_resolution`.
+    pub fn add_line_with_arrows<N, P, C>(
+        &mut self,
+        line: &C,
+        paint: &LinePaint,
+        arrows: &ArrowPaint,
+        min_resolution: f64,
+    ) -> PrimitiveHandle
     where
         N: AsPrimitive<f32>,
         P: CartesianPoint3d<Num = N>,
         C: Contour<Point = P>,
     {
+        let mark = self.world_set.mark();
         self.world_set.add_line(line, paint, min_resolution);
+        self.world_set
+            .add_line_arrows(line, paint, arrows, min_resolution);
+
+        let kind =
+            self.updatable_kind(|| PrimitiveKind::line(line, None, Some(arrows), min_resolution));
+        let handle = self.add_primitive(mark, kind);
+        self.pick_set.add_line(handle, line, paint);
+        handle
+    }
+
+    /// Adds a line with a separate color for every vertex of the line. The colors are interpolated along the line
+    /// between the vertices, and `paint.color` is ignored.
+    ///
+    /// The number of `colors` must be equal to the number of points in the `line`. Otherwise, a warning is logged and
+    /// the line is drawn with `paint.color`.
+    pub fn add_line_with_colors<N, P, C>(
+        &mut self,
+        line: &C,
+        paint: &LinePaint,
+        colors: &[Color],
+        min_resolution: f64,
+    ) -> PrimitiveHandle
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+        C: Contour<Point = P>,
+    {
+        let mark = self.world_set.mark();
+        self.world_set
+            .add_line_with_colors(line, paint, colors, min_resolution);
+
+        let kind =
+            self.updatable_kind(|| PrimitiveKind::line(line, Some(colors), None, min_resolution));
+        let handle = self.add_primitive(mark, kind);
+        self.pick_set.add_line(handle, line, paint);
+        handle
     }
 
-    /// Adds a polygon to the bundle.
+    /// Adds a polygon to the bundle and returns its handle.
     pub fn add_polygon<N, P, Poly>(
         &mut self,
         polygon: &Poly,
         paint: &PolygonPaint,
         min_resolution: f64,
-    ) where
+    ) -> PrimitiveHandle
+    where
         N: AsPrimitive<f32>,
         P: CartesianPoint3d<Num = N>,
         Poly: Polygon,
         Poly::Contour: Contour<Point = P>,
     {
+        let mark = self.world_set.mark();
         self.world_set.add_polygon(polygon, paint, min_resolution);
+
+        let handle = self.add_primitive(mark, PrimitiveKind::Other);
+        self.pick_set
+            .add_polygons(handle, std::iter::once(polygon), paint);
+        handle
+    }
+
+    /// Adds a polygon to the bundle with the given z-index. See [`RenderBundle::add_point_with_z_index`] for details.
+    pub fn add_polygon_with_z_index<N, P, Poly>(
+        &mut self,
+        polygon: &Poly,
+        paint: &PolygonPaint,
+        min_resolution: f64,
+        z_index: i32,
+    ) -> PrimitiveHandle
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+        Poly: Polygon,
+        Poly::Contour: Contour<Point = P>,
+    {
+        self.set_z_index(z_index);
+        let handle = self.add_polygon(polygon, paint, min_resolution);
+        self.set_z_index(0);
+        handle
+    }
+
+    /// Adds all parts of a multipolygon to the bundle with the same paint.
+    ///
+    /// Each part is tessellated the same way as with [`RenderBundle::add_polygon`]. All the parts share one
+    /// handle.
+    pub fn add_multi_polygon<N, P, MPoly>(
+        &mut self,
+        polygons: &MPoly,
+        paint: &PolygonPaint,
+        min_resolution: f64,
+    ) -> PrimitiveHandle
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+        MPoly: MultiPolygon,
+        <MPoly::Polygon as Polygon>::Contour: Contour<Point = P>,
+    {
+        let mark = self.world_set.mark();
+        self.world_set
+            .add_multi_polygon(polygons, paint, min_resolution);
+
+        let handle = self.add_primitive(mark, PrimitiveKind::Other);
+        self.pick_set
+            .add_polygons(handle, polygons.polygons(), paint);
+        handle
+    }
+
+    /// Adds a flat quadrilateral with the given corners in map coordinates.
+    ///
+    /// Unlike [`RenderBundle::add_polygon`], the corners are used as is without tessellation in the XY plane, so the
+    /// quad may be vertical, e.g. a wall of an extruded building. The corners must be given in order along the quad's
+    /// edge.
+    ///
+    /// Returns the handle of the quad, which can be used to remove it from the bundle.
+    pub fn add_quad<N, P>(&mut self, corners: [&P; 4], color: Color) -> PrimitiveHandle
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+    {
+        let mark = self.world_set.mark();
+        self.world_set.add_quad(corners, color);
+        self.add_primitive(mark, PrimitiveKind::Other)
     }
 
     /// Adds a label to the bundle.
@@ -95,7 +609,138 @@ impl RenderBundle {
     {
         if attach_to_map {
             self.world_set.add_label(position, text, style, offset);
-        } else if let Some(set) = ScreenRenderSet::new_from_label(position, text, style, offset) {
+        } else if let Some(set) = ScreenRenderSet::new_from_label(
+            position,
+            text,
+            style,
+            offset,
+            None,
+            None,
+            self.world_set.scale_factor,
+        ) {
+            self.screen_sets.push(set);
+        }
+    }
+
+    /// Adds a label that follows the line, e.g. the name of a river or a road.
+    ///
+    /// The glyphs are placed along the line centered on its length, and each glyph is rotated to be tangent to the
+    /// line. If the line runs from right to left, the text is laid out in reverse direction so that it is not upside
+    /// down. The text is not drawn if the line is shorter than the text.
+    ///
+    /// The label is attached to the map. The glyphs keep their size in pixels, but their positions along the line
+    /// are calculated for the `min_resolution`.
+    pub fn add_line_label<N, P, C>(
+        &mut self,
+        line: &C,
+        text: &str,
+        style: &TextStyle,
+        min_resolution: f64,
+    ) where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+        C: Contour<Point = P>,
+    {
+        self.world_set
+            .add_line_label(line, text, style, min_resolution);
+    }
+
+    /// Adds a label with a background shape behind it to the bundle. The label and its background are added as a
+    /// single primitive, so they are always shown or hidden together.
+    ///
+    /// The size of the background is calculated from the size of the text measured with the
+    /// [`TextService`](crate::render::text::text_service::TextService).
+    pub fn add_label_with_background<N, P>(
+        &mut self,
+        position: &P,
+        text: &str,
+        style: &TextStyle,
+        offset: Vector2<f32>,
+        background: &LabelBackground,
+        attach_to_map: bool,
+    ) where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+    {
+        if attach_to_map {
+            self.world_set
+                .add_label_with_background(position, text, style, offset, background);
+        } else if let Some(set) = ScreenRenderSet::new_from_label(
+            position,
+            text,
+            style,
+            offset,
+            Some(background),
+            None,
+            self.world_set.scale_factor,
+        ) {
+            self.screen_sets.push(set);
+        }
+    }
+
+    /// Adds a label placed relative to its anchor point according to the `placement`.
+    ///
+    /// If the placement has a [`LeaderLine`], the line is drawn from the `position` to the nearest point of the
+    /// label (or its background, if given), unless the offset is shorter than [`LeaderLine::min_offset`]. The leader
+    /// line is drawn beneath the label and is not taken into account when overlapping labels are hidden.
+    pub fn add_label_with_placement<N, P>(
+        &mut self,
+        position: &P,
+        text: &str,
+        style: &TextStyle,
+        placement: &LabelPlacement,
+        background: Option<&LabelBackground>,
+        attach_to_map: bool,
+    ) where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+    {
+        if attach_to_map {
+            self.world_set
+                .add_label_with_placement(position, text, style, placement, background);
+        } else if let Some(set) = ScreenRenderSet::new_from_label(
+            position,
+            text,
+            style,
+            placement.offset,
+            background,
+            placement.leader_line.as_ref(),
+            self.world_set.scale_factor,
+        ) {
+            self.screen_sets.push(set);
+        }
+    }
+
+    /// Adds a label that is hidden when it overlaps a label with higher priority.
+    ///
+    /// The overlapping labels are resolved on every frame using the screen-space bounding boxes of the labels,
+    /// including their backgrounds. Of two overlapping labels the one with the higher `priority` is shown. If the
+    /// priorities are equal, the label that is already displayed stays on the screen, so that labels do not flicker
+    /// while the map is moved.
+    ///
+    /// The label is always drawn in screen space, as collisions are not resolved for the labels attached to the map.
+    pub fn add_label_with_priority<N, P>(
+        &mut self,
+        position: &P,
+        text: &str,
+        style: &TextStyle,
+        offset: Vector2<f32>,
+        background: Option<&LabelBackground>,
+        priority: f32,
+    ) where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+    {
+        if let Some(mut set) = ScreenRenderSet::new_from_label(
+            position,
+            text,
+            style,
+            offset,
+            background,
+            None,
+            self.world_set.scale_factor,
+        ) {
+            set.priority = priority;
             self.screen_sets.push(set);
         }
     }
@@ -106,8 +751,323 @@ impl RenderBundle {
         N: AsPrimitive<f32>,
         P: CartesianPoint3d<Num = N>,
     {
-        if let Some(set) = ScreenRenderSet::new_from_marker(position, style) {
+        self.add_marker_with_z_index(position, style, 0);
+    }
+
+    /// Adds a marker to the bundle with the given z-index. When markers overlap on the screen,
+    /// markers with higher z-index are drawn over the ones with lower z-index.
+    ///
+    /// Markers with the same z-index are drawn from the top of the screen to the bottom.
+    pub fn add_marker_with_z_index<N, P>(&mut self, position: &P, style: &MarkerStyle, z_index: i32)
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+    {
+        if let Some(set) = ScreenRenderSet::new_from_marker(position, style, z_index) {
             self.screen_sets.push(set);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use galileo_types::cartesian::{Point3, Size};
+    use galileo_types::impls::{ClosedContour, Contour};
+    use lyon::tessellation::VertexBuffers;
+
+    use super::*;
+    use crate::render::render_bundle::screen_set::{ScreenSetData, ScreenSetVertex};
+    use crate::render::{ImageSampling, LineCap};
+
+    fn image(size: u32) -> DecodedImage {
+        DecodedImage::from_raw(vec![128; (size * size * 4) as usize], Size::new(size, size))
+            .expect("valid image")
+    }
+
+    fn bundle_with_all_primitives() -> RenderBundle {
+        let mut bundle = RenderBundle::default().with_picking(true);
+
+        bundle.add_image_owned(
+            image(2),
+            [
+                Point2::new(0.0, 0.0),
+                Point2::new(0.0, 1.0),
+                Point2::new(1.0, 1.0),
+                Point2::new(1.0, 0.0),
+            ],
+            ImagePaint {
+                opacity: 200,
+                sampling: ImageSampling::Nearest,
+            },
+        );
+        bundle.add_point(
+            &Point3::new(1.0, 2.0, 3.0),
+            &PointPaint::circle(Color::RED, 5.0),
+            1.0,
+        );
+        bundle.add_line(
+            &Contour::open(vec![
+                Point3::new(0.0, 0.0, 0.0),
+                Point3::new(10.0, 5.0, 0.0),
+            ]),
+            &LinePaint {
+                color: Color::GREEN,
+                width: 2.0,
+                offset: 0.0,
+                line_cap: LineCap::Round,
+                dash_pattern: None,
+                dash_offset: 0.0,
+            },
+            1.0,
+        );
+        bundle.add_polygon_with_z_index(
+            &galileo_types::impls::Polygon::from(ClosedContour::new(vec![
+                Point3::new(0.0, 0.0, 0.0),
+                Point3::new(0.0, 10.0, 0.0),
+                Point3::new(10.0, 10.0, 0.0),
+            ])),
+            &PolygonPaint::new(Color::BLUE),
+            1.0,
+            1,
+        );
+        bundle.add_marker(
+            &Point3::new(5.0, 5.0, 0.0),
+            &MarkerStyle::Image {
+                image: Arc::new(image(1)),
+                anchor: Vector2::new(0.5, 1.0),
+                size: None,
+                source_rect: None,
+                rotation: 0.0,
+            },
+        );
+
+        // Labels are shaped by the global text service, so a tessellated label is added directly.
+        let mut label = VertexBuffers::new();
+        label.vertices = [[0.0, 0.0], [4.0, 0.0], [4.0, 4.0]]
+            .map(|position| ScreenSetVertex {
+                position,
+                color: Color::BLACK.to_u8_array(),
+            })
+            .to_vec();
+        label.indices = vec![0, 1, 2];
+        bundle.screen_sets.push(ScreenRenderSet {
+            animation_duration: Default::default(),
+            anchor_point: [1.0, 1.0, 0.0],
+            bbox: Rect::new(0.0, 0.0, 4.0, 4.0),
+            hide_on_overlay: true,
+            z_index: 0,
+            priority: 0.0,
+            data: ScreenSetData::Vertices(label),
+        });
+
+        bundle
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn binary_round_trip() {
+        let bundle = bundle_with_all_primitives();
+        let bytes = bundle.to_bytes();
+        let restored = RenderBundle::from_bytes(&bytes).expect("valid bundle");
+
+        assert_eq!(format!("{restored:?}"), format!("{bundle:?}"));
+    }
+
+    #[test]
+    fn picked_handles_of_appended_bundle_are_shifted() {
+        let mut bundle = bundle_with_all_primitives();
+        let mut other = RenderBundle::default().with_picking(true);
+        let handle = other.add_point(
+            &Point3::new(20.0, 20.0, 0.0),
+            &PointPaint::circle(Color::RED, 2.0),
+            1.0,
+        );
+        assert_eq!(other.pick(&Point2::new(20.0, 20.0), 1.0), Some(handle));
+
+        bundle.append(other);
+        let picked = bundle
+            .pick(&Point2::new(20.0, 20.0), 1.0)
+            .expect("point is picked");
+        assert_eq!(picked.index(), 4);
+        assert!(bundle.is_valid(picked));
+
+        // The polygon has higher z-index than the line and the point drawn over it.
+        let picked = bundle
+            .pick(&Point2::new(1.0, 2.0), 1.0)
+            .expect("polygon is picked");
+        assert_eq!(picked.index(), 3);
+    }
+
+    #[test]
+    fn clear_invalidates_handles() {
+        let mut bundle = bundle_with_all_primitives();
+        let handle = bundle
+            .pick(&Point2::new(1.0, 2.0), 1.0)
+            .expect("polygon is picked");
+
+        bundle.clear();
+        assert!(!bundle.is_valid(handle));
+        assert_eq!(bundle.pick(&Point2::new(1.0, 2.0), 1.0), None);
+
+        let new_handle = bundle.add_point(
+            &Point3::new(1.0, 2.0, 0.0),
+            &PointPaint::circle(Color::RED, 2.0),
+            1.0,
+        );
+        assert!(bundle.is_valid(new_handle));
+        assert!(!bundle.is_valid(handle));
+    }
+
+    fn line_paint(color: Color, width: f64) -> LinePaint {
+        LinePaint {
+            color,
+            width,
+            offset: 0.0,
+            line_cap: LineCap::Butt,
+            dash_pattern: None,
+            dash_offset: 0.0,
+        }
+    }
+
+    #[test]
+    fn removed_primitive_is_not_drawn() {
+        let mut bundle = RenderBundle::default().with_picking(true);
+        let polygon = galileo_types::impls::Polygon::from(ClosedContour::new(vec![
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(0.0, 10.0, 0.0),
+            Point3::new(10.0, 10.0, 0.0),
+        ]));
+        let handle = bundle.add_polygon(&polygon, &PolygonPaint::new(Color::BLUE), 1.0);
+        assert!(bundle
+            .world_set
+            .draw_order()
+            .indices
+            .iter()
+            .any(|&i| i != 0));
+
+        assert!(bundle.remove(handle));
+        assert!(!bundle.is_valid(handle));
+        assert!(!bundle.remove(handle));
+        assert!(bundle.world_set.draw_order().indices.is_empty());
+        assert!(bundle.world_bounds().is_none());
+        assert_eq!(bundle.pick(&Point2::new(1.0, 2.0), 1.0), None);
+
+        let new_handle = bundle.add_polygon(&polygon, &PolygonPaint::new(Color::RED), 1.0);
+        assert_eq!(new_handle.index(), handle.index());
+        assert_ne!(new_handle, handle);
+        assert_eq!(bundle.pick(&Point2::new(1.0, 2.0), 1.0), Some(new_handle));
+        assert!(!bundle.update_line(new_handle, &line_paint(Color::RED, 1.0)));
+    }
+
+    #[test]
+    fn removed_quad_is_not_drawn() {
+        let mut bundle = RenderBundle::default();
+        let corners = [
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(10.0, 0.0, 0.0),
+            Point3::new(10.0, 0.0, 5.0),
+            Point3::new(0.0, 0.0, 5.0),
+        ];
+        let handle = bundle.add_quad(
+            [&corners[0], &corners[1], &corners[2], &corners[3]],
+            Color::BLUE,
+        );
+        assert!(bundle
+            .world_set
+            .draw_order()
+            .indices
+            .iter()
+            .any(|&i| i != 0));
+
+        assert!(bundle.remove(handle));
+        assert!(!bundle.is_valid(handle));
+        assert!(bundle.world_set.draw_order().indices.is_empty());
+        assert!(bundle.world_set.poly_tessellation.vertices.is_empty());
+    }
+
+    #[test]
+    fn point_is_moved_by_handle() {
+        let mut bundle = RenderBundle::default()
+            .with_picking(true)
+            .with_updates(true);
+        let handle = bundle.add_point(
+            &Point3::new(1.0, 2.0, 0.0),
+            &PointPaint::circle(Color::RED, 2.0),
+            1.0,
+        );
+
+        assert!(bundle.update_point_position(handle, &Point3::new(11.0, 12.0, 0.0)));
+        assert!(bundle
+            .world_set
+            .poly_tessellation
+            .vertices
+            .iter()
+            .all(|vertex| vertex.position == [11.0, 12.0, 0.0]));
+        assert_eq!(bundle.pick(&Point2::new(1.0, 2.0), 1.0), None);
+        assert_eq!(bundle.pick(&Point2::new(11.0, 12.0), 1.0), Some(handle));
+
+        bundle.remove(handle);
+        assert!(!bundle.update_point_position(handle, &Point3::new(1.0, 2.0, 0.0)));
+    }
+
+    #[test]
+    fn line_is_updated_by_handle() {
+        let mut bundle = RenderBundle::default()
+            .with_picking(true)
+            .with_updates(true);
+        let line = Contour::open(vec![
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(10.0, 0.0, 0.0),
+        ]);
+        let handle = bundle.add_line(&line, &line_paint(Color::GREEN, 2.0), 1.0);
+        assert_eq!(bundle.pick(&Point2::new(5.0, 2.0), 1.0), None);
+
+        assert!(bundle.update_line(handle, &line_paint(Color::RED, 6.0)));
+        assert!(bundle.is_valid(handle));
+        assert_eq!(bundle.pick(&Point2::new(5.0, 2.0), 1.0), Some(handle));
+
+        // The old tessellation is removed, so only the new one is left.
+        let tessellation = &bundle.world_set.poly_tessellation;
+        assert!(!tessellation.indices.is_empty());
+        assert!(tessellation
+            .vertices
+            .iter()
+            .all(|vertex| vertex.color == Color::RED.to_f32_array()));
+    }
+
+    #[test]
+    fn updates_are_disabled_by_default() {
+        let mut bundle = RenderBundle::default();
+        let line = Contour::open(vec![
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(10.0, 0.0, 0.0),
+        ]);
+        let line_handle = bundle.add_line(&line, &line_paint(Color::GREEN, 2.0), 1.0);
+        let point_handle = bundle.add_point(
+            &Point3::new(1.0, 2.0, 0.0),
+            &PointPaint::circle(Color::RED, 2.0),
+            1.0,
+        );
+
+        assert!(!bundle.update_line(line_handle, &line_paint(Color::RED, 6.0)));
+        assert!(!bundle.update_point_position(point_handle, &Point3::new(11.0, 12.0, 0.0)));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn rejects_invalid_data() {
+        let mut bytes = bundle_with_all_primitives().to_bytes();
+
+        assert!(RenderBundle::from_bytes(&bytes[..3]).is_err());
+        assert!(RenderBundle::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+
+        bytes[FORMAT_MAGIC.len()] += 1;
+        assert!(matches!(
+            RenderBundle::from_bytes(&bytes),
+            Err(GalileoError::Deserialization(message)) if message.contains("version")
+        ));
+
+        bytes[0] = b'X';
+        assert!(RenderBundle::from_bytes(&bytes).is_err());
+    }
+}
@@ -0,0 +1,394 @@
+use galileo_types::cartesian::{CartesianPoint2d, CartesianPoint3d, Point2};
+use galileo_types::contour::Contour;
+use galileo_types::Polygon;
+use num_traits::AsPrimitive;
+use serde::{Deserialize, Serialize};
+
+use crate::render::point_paint::PointPaint;
+use crate::render::render_bundle::handle::{HandleAllocator, PrimitiveHandle};
+use crate::render::{FillRule, LinePaint, PolygonPaint};
+
+/// Geometries of the bundle primitives used for hit-testing.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct PickSet {
+    pub enabled: bool,
+    pub z_index: i32,
+    shapes: Vec<PickShape>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PickShape {
+    handle: PrimitiveHandle,
+    z_index: i32,
+    geometry: PickGeometry,
+}
+
+/// Coordinates are in map units, sizes are in pixels.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum PickGeometry {
+    Point {
+        position: [f32; 2],
+        offset: [f32; 2],
+        radius: f32,
+    },
+    Line {
+        points: Vec<[f32; 2]>,
+        closed: bool,
+        half_width: f32,
+    },
+    Polygon {
+        rings: Vec<Vec<[f32; 2]>>,
+        fill_rule: FillRule,
+    },
+}
+
+impl PickSet {
+    pub fn clear(&mut self) {
+        self.shapes.clear();
+    }
+
+    /// Moves the shapes of `other` into this set. Handles of the moved primitives are shifted by `handle_offset`
+    /// indices.
+    pub fn append(&mut self, other: PickSet, handle_offset: usize) {
+        self.shapes
+            .extend(other.shapes.into_iter().map(|shape| PickShape {
+                handle: HandleAllocator::shift(shape.handle, handle_offset),
+                ..shape
+            }));
+    }
+
+    pub fn remove(&mut self, handle: PrimitiveHandle) {
+        self.shapes.retain(|shape| shape.handle != handle);
+    }
+
+    pub fn move_point(&mut self, handle: PrimitiveHandle, new_position: [f32; 2]) {
+        for shape in self
+            .shapes
+            .iter_mut()
+            .filter(|shape| shape.handle == handle)
+        {
+            if let PickGeometry::Point { position, .. } = &mut shape.geometry {
+                *position = new_position;
+            }
+        }
+    }
+
+    pub fn update_line(&mut self, handle: PrimitiveHandle, paint: &LinePaint) {
+        for shape in self
+            .shapes
+            .iter_mut()
+            .filter(|shape| shape.handle == handle)
+        {
+            if let PickGeometry::Line { half_width, .. } = &mut shape.geometry {
+                *half_width = line_half_width(paint);
+            }
+        }
+    }
+
+    pub fn add_point<N, P>(&mut self, handle: PrimitiveHandle, point: &P, paint: &PointPaint)
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+    {
+        if let Some(radius) = paint.pick_radius().filter(|_| self.enabled) {
+            self.push(
+                handle,
+                PickGeometry::Point {
+                    position: [point.x().as_(), point.y().as_()],
+                    offset: [paint.offset.dx(), paint.offset.dy()],
+                    radius,
+                },
+            );
+        }
+    }
+
+    pub fn add_line<N, P, C>(&mut self, handle: PrimitiveHandle, line: &C, paint: &LinePaint)
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+        C: Contour<Point = P>,
+    {
+        if self.enabled {
+            self.push_line(handle, line, paint);
+        }
+    }
+
+    pub fn add_polygons<'a, N, P, Poly>(
+        &mut self,
+        handle: PrimitiveHandle,
+        polygons: impl Iterator<Item = &'a Poly>,
+        paint: &PolygonPaint,
+    ) where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+        Poly: Polygon + 'a,
+        Poly::Contour: Contour<Point = P>,
+    {
+        if !self.enabled {
+            return;
+        }
+
+        for polygon in polygons {
+            let rings = polygon
+                .iter_contours()
+                .map(|contour| {
+                    contour
+                        .iter_points()
+                        .map(|p| [p.x().as_(), p.y().as_()])
+                        .collect()
+                })
+                .collect();
+            self.push(
+                handle,
+                PickGeometry::Polygon {
+                    rings,
+                    fill_rule: paint.fill_rule,
+                },
+            );
+
+            if let Some(stroke) = &paint.stroke {
+                for contour in polygon.iter_contours() {
+                    self.push_line(handle, contour, stroke);
+                }
+            }
+        }
+    }
+
+    /// Returns the topmost primitive under the `point` given in map coordinates. Primitives with higher z-index are
+    /// considered to be on top, and among the primitives with the same z-index the last added one is on top.
+    pub fn pick(&self, point: &Point2, resolution: f64) -> Option<PrimitiveHandle> {
+        let point = [point.x() as f32, point.y() as f32];
+        let resolution = resolution as f32;
+
+        // `max_by_key` returns the last of the equal elements, i.e. the last added one.
+        self.shapes
+            .iter()
+            .filter(|shape| shape.geometry.contains(point, resolution))
+            .max_by_key(|shape| shape.z_index)
+            .map(|shape| shape.handle)
+    }
+
+    fn push(&mut self, handle: PrimitiveHandle, geometry: PickGeometry) {
+        self.shapes.push(PickShape {
+            handle,
+            z_index: self.z_index,
+            geometry,
+        });
+    }
+
+    fn push_line<N, P, C>(&mut self, handle: PrimitiveHandle, line: &C, paint: &LinePaint)
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+        C: Contour<Point = P>,
+    {
+        self.push(
+            handle,
+            PickGeometry::Line {
+                points: line
+                    .iter_points()
+                    .map(|p| [p.x().as_(), p.y().as_()])
+                    .collect(),
+                closed: line.is_closed(),
+                half_width: line_half_width(paint),
+            },
+        );
+    }
+}
+
+/// Offset lines are picked by the band that covers both the base line and the drawn line.
+fn line_half_width(paint: &LinePaint) -> f32 {
+    (paint.width / 2.0 + paint.offset.abs()) as f32
+}
+
+impl PickGeometry {
+    fn contains(&self, point: [f32; 2], resolution: f32) -> bool {
+        match self {
+            PickGeometry::Point {
+                position,
+                offset,
+                radius,
+            } => {
+                let dx = point[0] - (position[0] + offset[0] * resolution);
+                let dy = point[1] - (position[1] + offset[1] * resolution);
+                dx.hypot(dy) <= radius * resolution
+            }
+            PickGeometry::Line {
+                points,
+                closed,
+                half_width,
+            } => {
+                let max_distance = half_width * resolution;
+                let closing = if *closed {
+                    points.last().copied().zip(points.first().copied())
+                } else {
+                    None
+                };
+                points
+                    .windows(2)
+                    .map(|segment| (segment[0], segment[1]))
+                    .chain(closing)
+                    .any(|(a, b)| segment_distance(point, a, b) <= max_distance)
+            }
+            PickGeometry::Polygon { rings, fill_rule } => {
+                let winding: i32 = rings.iter().map(|ring| winding_number(point, ring)).sum();
+                match fill_rule {
+                    FillRule::EvenOdd => winding % 2 != 0,
+                    FillRule::NonZero => winding != 0,
+                }
+            }
+        }
+    }
+}
+
+fn segment_distance(point: [f32; 2], a: [f32; 2], b: [f32; 2]) -> f32 {
+    let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+    let length_sq = dx * dx + dy * dy;
+    let t = if length_sq > 0.0 {
+        (((point[0] - a[0]) * dx + (point[1] - a[1]) * dy) / length_sq).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+
+    (point[0] - a[0] - t * dx).hypot(point[1] - a[1] - t * dy)
+}
+
+/// Number of times the closed `ring` winds around the `point` counterclockwise.
+fn winding_number(point: [f32; 2], ring: &[[f32; 2]]) -> i32 {
+    let mut winding = 0;
+    for (i, &a) in ring.iter().enumerate() {
+        let b = ring[(i + 1) % ring.len()];
+        let side = (b[0] - a[0]) * (point[1] - a[1]) - (point[0] - a[0]) * (b[1] - a[1]);
+        if a[1] <= point[1] {
+            if b[1] > point[1] && side > 0.0 {
+                winding += 1;
+            }
+        } else if b[1] <= point[1] && side < 0.0 {
+            winding -= 1;
+        }
+    }
+
+    winding
+}
+
+#[cfg(test)]
+mod tests {
+    use galileo_types::cartesian::Point3;
+    use galileo_types::impls::{ClosedContour, Contour as LineContour};
+
+    use super::*;
+    use crate::render::LineCap;
+    use crate::Color;
+
+    fn square(min: f64, max: f64) -> ClosedContour<Point3> {
+        ClosedContour::new(vec![
+            Point3::new(min, min, 0.0),
+            Point3::new(max, min, 0.0),
+            Point3::new(max, max, 0.0),
+            Point3::new(min, max, 0.0),
+        ])
+    }
+
+    fn line_paint(width: f64) -> LinePaint {
+        LinePaint {
+            color: Color::BLACK,
+            width,
+            offset: 0.0,
+            line_cap: LineCap::Butt,
+            dash_pattern: None,
+            dash_offset: 0.0,
+        }
+    }
+
+    fn pick_set() -> (PickSet, HandleAllocator) {
+        let set = PickSet {
+            enabled: true,
+            ..Default::default()
+        };
+        (set, HandleAllocator::default())
+    }
+
+    fn add_square(
+        set: &mut PickSet,
+        handles: &mut HandleAllocator,
+        polygon: &galileo_types::impls::Polygon<Point3>,
+    ) -> PrimitiveHandle {
+        let handle = handles.allocate();
+        set.add_polygons(
+            handle,
+            std::iter::once(polygon),
+            &PolygonPaint::new(Color::BLACK),
+        );
+        handle
+    }
+
+    #[test]
+    fn points_are_picked_within_radius() {
+        let (mut set, mut handles) = pick_set();
+        let handle = handles.allocate();
+        set.add_point(
+            handle,
+            &Point3::new(10.0, 10.0, 0.0),
+            &PointPaint::circle(Color::BLACK, 10.0),
+        );
+
+        assert_eq!(set.pick(&Point2::new(14.0, 10.0), 1.0), Some(handle));
+        assert_eq!(set.pick(&Point2::new(16.0, 10.0), 1.0), None);
+        assert_eq!(set.pick(&Point2::new(16.0, 10.0), 2.0), Some(handle));
+    }
+
+    #[test]
+    fn lines_are_picked_within_stroke_width() {
+        let (mut set, mut handles) = pick_set();
+        let line = LineContour::open(vec![
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(10.0, 0.0, 0.0),
+        ]);
+        let handle = handles.allocate();
+        set.add_line(handle, &line, &line_paint(4.0));
+
+        assert_eq!(set.pick(&Point2::new(5.0, 1.5), 1.0), Some(handle));
+        assert_eq!(set.pick(&Point2::new(5.0, 2.5), 1.0), None);
+        assert_eq!(set.pick(&Point2::new(12.5, 0.0), 1.0), None);
+    }
+
+    #[test]
+    fn polygon_holes_are_not_picked() {
+        let (mut set, mut handles) = pick_set();
+        let polygon = galileo_types::impls::Polygon::new(square(0.0, 10.0), vec![square(4.0, 6.0)]);
+        let handle = add_square(&mut set, &mut handles, &polygon);
+
+        assert_eq!(set.pick(&Point2::new(2.0, 2.0), 1.0), Some(handle));
+        assert_eq!(set.pick(&Point2::new(5.0, 5.0), 1.0), None);
+        assert_eq!(set.pick(&Point2::new(11.0, 5.0), 1.0), None);
+    }
+
+    #[test]
+    fn topmost_primitive_is_picked() {
+        let (mut set, mut handles) = pick_set();
+        let polygon = galileo_types::impls::Polygon::from(square(0.0, 10.0));
+        set.z_index = 1;
+        let top = add_square(&mut set, &mut handles, &polygon);
+        set.z_index = 0;
+        add_square(&mut set, &mut handles, &polygon);
+        let last = add_square(&mut set, &mut handles, &polygon);
+
+        assert_eq!(set.pick(&Point2::new(5.0, 5.0), 1.0), Some(top));
+
+        set.shapes.remove(0);
+        assert_eq!(set.pick(&Point2::new(5.0, 5.0), 1.0), Some(last));
+    }
+
+    #[test]
+    fn primitives_are_not_stored_when_disabled() {
+        let mut set = PickSet::default();
+        set.add_point(
+            HandleAllocator::default().allocate(),
+            &Point3::new(0.0, 0.0, 0.0),
+            &PointPaint::circle(Color::BLACK, 10.0),
+        );
+
+        assert!(set.shapes.is_empty());
+        assert_eq!(set.pick(&Point2::new(0.0, 0.0), 1.0), None);
+    }
+}
@@ -1,14 +1,22 @@
+use std::cmp::Ordering;
 use std::sync::Arc;
 
-use galileo_types::cartesian::{CartesianPoint3d, Point2, Rect, Vector2};
-use lyon::tessellation::VertexBuffers;
+use galileo_types::cartesian::{CartesianPoint2d, CartesianPoint3d, Point2, Rect, Size, Vector2};
+use galileo_types::contour::Contour;
+use lyon::math::point;
+use lyon::path::Path;
+use lyon::tessellation::{
+    BuffersBuilder, FillOptions, FillTessellator, FillVertex, FillVertexConstructor, StrokeOptions,
+    StrokeTessellator, StrokeVertex, StrokeVertexConstructor, VertexBuffers,
+};
 use num_traits::AsPrimitive;
 use serde::{Deserialize, Serialize};
 use web_time::{Duration, Instant};
 
 use crate::decoded_image::DecodedImage;
 use crate::render::point_paint::MarkerStyle;
-use crate::render::text::{TextService, TextShaping, TextStyle};
+use crate::render::text::label_background::label_rect;
+use crate::render::text::{LabelBackground, LeaderLine, TextService, TextShaping, TextStyle};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct ScreenRenderSet {
@@ -16,6 +24,11 @@ pub(crate) struct ScreenRenderSet {
     pub(crate) anchor_point: [f32; 3],
     pub(crate) bbox: Rect<f32>,
     pub(crate) hide_on_overlay: bool,
+    #[serde(default)]
+    pub(crate) z_index: i32,
+    /// Priority of the set when it overlaps other sets. See [`ScreenSetOrder::collision_cmp`].
+    #[serde(default)]
+    pub(crate) priority: f32,
     pub(crate) data: ScreenSetData,
 }
 
@@ -56,24 +69,140 @@ pub(crate) struct ScreenSetImageVertex {
     pub(crate) tex_coords: [f32; 2],
 }
 
+/// Properties of a screen set that define the order in which it is drawn.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct ScreenSetOrder {
+    /// Whether the set is currently displayed.
+    pub(crate) displayed: bool,
+    /// Explicit z-index of the set.
+    pub(crate) z_index: i32,
+    /// Priority of the set when it overlaps other sets.
+    pub(crate) priority: f32,
+    /// Depth of the anchor point in normalized device coordinates.
+    pub(crate) depth: f64,
+    /// Vertical position of the anchor point in normalized device coordinates.
+    pub(crate) screen_y: f64,
+}
+
+impl ScreenSetOrder {
+    /// Compares two sets by the order they should be drawn in. Sets that are drawn later are
+    /// displayed on top of the sets drawn before them.
+    ///
+    /// Displayed sets go first, so that they take precedence over hidden ones when overlapping
+    /// sets are hidden. Then sets with lower z-index are drawn behind the sets with higher one.
+    /// Sets with the same z-index are ordered by depth, and then from the top of the screen to
+    /// the bottom, so that the order of overlapping markers does not change between frames.
+    pub(crate) fn draw_cmp(&self, other: &Self) -> Ordering {
+        other
+            .displayed
+            .cmp(&self.displayed)
+            .then(self.z_index.cmp(&other.z_index))
+            .then(
+                self.depth
+                    .partial_cmp(&other.depth)
+                    .unwrap_or(Ordering::Equal),
+            )
+            .then(
+                other
+                    .screen_y
+                    .partial_cmp(&self.screen_y)
+                    .unwrap_or(Ordering::Equal),
+            )
+    }
+
+    /// Compares two sets by the order in which their collisions are resolved. When two sets
+    /// overlap, the one that goes first is kept.
+    ///
+    /// Sets with higher priority go first. Sets with the same priority are compared as in
+    /// [`draw_cmp`](Self::draw_cmp), so the sets that are already displayed keep their place and
+    /// labels do not flicker between frames.
+    pub(crate) fn collision_cmp(&self, other: &Self) -> Ordering {
+        other
+            .priority
+            .total_cmp(&self.priority)
+            .then_with(|| self.draw_cmp(other))
+    }
+}
+
+/// Decides which screen sets are displayed in the current frame.
+///
+/// `bboxes` are the screen-space bounding boxes of the sets together with their `hide_on_overlay`
+/// flag, sorted with [`ScreenSetOrder::collision_cmp`]. A set that has `hide_on_overlay` is hidden
+/// if its box intersects the box of any displayed set before it. Returns whether each set is
+/// displayed.
+pub(crate) fn resolve_collisions(bboxes: impl IntoIterator<Item = (Rect<f32>, bool)>) -> Vec<bool> {
+    let mut displayed: Vec<Rect<f32>> = vec![];
+    bboxes
+        .into_iter()
+        .map(|(bbox, hide_on_overlay)| {
+            if hide_on_overlay && displayed.iter().any(|other| other.intersects(bbox)) {
+                false
+            } else {
+                displayed.push(bbox);
+                true
+            }
+        })
+        .collect()
+}
+
 impl ScreenRenderSet {
     pub(crate) fn new_from_label<N, P>(
         position: &P,
         text: &str,
         style: &TextStyle,
         offset: Vector2<f32>,
+        background: Option<&LabelBackground>,
+        leader_line: Option<&LeaderLine>,
+        scale_factor: f32,
     ) -> Option<Self>
     where
         N: AsPrimitive<f32>,
         P: CartesianPoint3d<Num = N>,
     {
-        match TextService::shape(text, style, offset) {
+        match TextService::shape_scaled(text, style, offset, scale_factor) {
             Ok(TextShaping::Tessellation { glyphs, .. }) => {
                 let mut vertices = vec![];
                 let mut indices = vec![];
 
                 let mut bbox: Option<Rect<f32>> = None;
 
+                if background.is_some() || leader_line.is_some() {
+                    match TextService::measure(text, style, scale_factor) {
+                        Ok(size) => {
+                            let padding = background.map_or(0.0, |background| background.padding);
+                            let rect = label_rect(size, style, offset, padding);
+
+                            // The leader line is not included into the bbox, so that it does not
+                            // hide other labels.
+                            if let Some(leader) = leader_line {
+                                if let Some(contour) = leader.contour(rect, offset) {
+                                    let color = leader.color.to_u8_array();
+                                    vertices.extend(contour.iter_points().map(|p| {
+                                        ScreenSetVertex {
+                                            position: [p.x(), p.y()],
+                                            color,
+                                        }
+                                    }));
+                                    indices.extend([0, 1, 2, 0, 2, 3]);
+                                }
+                            }
+
+                            if let Some(background) = background {
+                                let contour = background.contour(size, style, offset);
+                                bbox = Rect::from_points(contour.iter_points());
+
+                                let vertices_start = vertices.len() as u32;
+                                let tessellation = tessellate_background(&contour, background);
+                                vertices.extend(tessellation.vertices);
+                                indices.extend(
+                                    tessellation.indices.iter().map(|i| i + vertices_start),
+                                );
+                            }
+                        }
+                        Err(err) => log::error!("Error measuring text label: {err:?}"),
+                    }
+                }
+
                 for glyph in glyphs {
                     let vertices_start = vertices.len() as u32;
 
@@ -107,6 +236,8 @@ impl ScreenRenderSet {
                     anchor_point: [position.x().as_(), position.y().as_(), position.z().as_()],
                     bbox,
                     hide_on_overlay: true,
+                    z_index: 0,
+                    priority: 0.0,
                     data: ScreenSetData::Vertices(VertexBuffers { vertices, indices }),
                 })
             }
@@ -121,7 +252,11 @@ impl ScreenRenderSet {
         }
     }
 
-    pub(crate) fn new_from_marker<N, P>(position: &P, style: &MarkerStyle) -> Option<Self>
+    pub(crate) fn new_from_marker<N, P>(
+        position: &P,
+        style: &MarkerStyle,
+        z_index: i32,
+    ) -> Option<Self>
     where
         N: AsPrimitive<f32>,
         P: CartesianPoint3d<Num = N>,
@@ -131,40 +266,74 @@ impl ScreenRenderSet {
                 image,
                 anchor,
                 size,
+                source_rect,
+                rotation,
             } => {
-                let size = size.unwrap_or(image.size()).cast::<f32>();
+                let image_size = image.size();
+                let size = size
+                    .or_else(|| source_rect.map(|rect| Size::new(rect.width(), rect.height())))
+                    .unwrap_or(image_size)
+                    .cast::<f32>();
                 let anchor_px = *anchor * size;
-                let bbox = Rect::new(
+                let image_rect = Rect::new(
                     -anchor_px.dx(),
                     anchor_px.dy(),
                     size.width() - anchor_px.dx(),
                     anchor_px.dy() - size.height(),
                 );
 
+                // The image is rotated around the anchor point, which is the origin of the vertex coordinates.
+                let (sin, cos) = rotation.sin_cos();
+                let rotate = |x: f32, y: f32| [x * cos + y * sin, y * cos - x * sin];
+
+                // Texture coordinates have the origin in the top left corner of the bitmap, while the
+                // screen coordinates of the vertices are directed up.
+                let tex_rect = match source_rect {
+                    Some(rect) => {
+                        let width = image_size.width().max(1) as f32;
+                        let height = image_size.height().max(1) as f32;
+                        Rect::new(
+                            rect.x_min() as f32 / width,
+                            rect.y_min() as f32 / height,
+                            rect.x_max() as f32 / width,
+                            rect.y_max() as f32 / height,
+                        )
+                    }
+                    None => Rect::new(0.0, 0.0, 1.0, 1.0),
+                };
+
                 let vertices = [
                     ScreenSetImageVertex {
-                        position: [bbox.x_min(), bbox.y_min()],
-                        tex_coords: [0.0, 1.0],
+                        position: rotate(image_rect.x_min(), image_rect.y_min()),
+                        tex_coords: [tex_rect.x_min(), tex_rect.y_max()],
                     },
                     ScreenSetImageVertex {
-                        position: [bbox.x_min(), bbox.y_max()],
-                        tex_coords: [0.0, 0.0],
+                        position: rotate(image_rect.x_min(), image_rect.y_max()),
+                        tex_coords: [tex_rect.x_min(), tex_rect.y_min()],
                     },
                     ScreenSetImageVertex {
-                        position: [bbox.x_max(), bbox.y_min()],
-                        tex_coords: [1.0, 1.0],
+                        position: rotate(image_rect.x_max(), image_rect.y_min()),
+                        tex_coords: [tex_rect.x_max(), tex_rect.y_max()],
                     },
                     ScreenSetImageVertex {
-                        position: [bbox.x_max(), bbox.y_max()],
-                        tex_coords: [1.0, 0.0],
+                        position: rotate(image_rect.x_max(), image_rect.y_max()),
+                        tex_coords: [tex_rect.x_max(), tex_rect.y_min()],
                     },
                 ];
+                let bbox = Rect::from_points(
+                    vertices
+                        .iter()
+                        .map(|v| Point2::new(v.position[0], v.position[1])),
+                )
+                .unwrap_or(image_rect);
 
                 Some(Self {
                     animation_duration: Duration::from_millis(0),
                     anchor_point: [position.x().as_(), position.y().as_(), position.z().as_()],
                     bbox,
                     hide_on_overlay: false,
+                    z_index,
+                    priority: 0.0,
                     data: ScreenSetData::Image {
                         vertices,
                         bitmap: image.clone(),
@@ -174,3 +343,231 @@ impl ScreenRenderSet {
         }
     }
 }
+
+/// Tessellates the fill and the border of a label background. The border is drawn over the fill.
+fn tessellate_background(
+    contour: &galileo_types::impls::ClosedContour<Point2<f32>>,
+    background: &LabelBackground,
+) -> VertexBuffers<ScreenSetVertex, u32> {
+    let mut builder = Path::builder();
+    let mut points = contour.iter_points();
+    if let Some(first) = points.next() {
+        let _ = builder.begin(point(first.x(), first.y()));
+        for p in points {
+            let _ = builder.line_to(point(p.x(), p.y()));
+        }
+        builder.end(true);
+    }
+    let path = builder.build();
+
+    let mut buffers = VertexBuffers::new();
+    if !background.fill.is_transparent() {
+        let constructor = BackgroundVertexConstructor(background.fill.to_u8_array());
+        if let Err(err) = FillTessellator::new().tessellate_path(
+            &path,
+            &FillOptions::default(),
+            &mut BuffersBuilder::new(&mut buffers, constructor),
+        ) {
+            log::warn!("Label background tessellation failed: {err:?}");
+        }
+    }
+
+    if background.has_border() {
+        let constructor = BackgroundVertexConstructor(background.border_color.to_u8_array());
+        if let Err(err) = StrokeTessellator::new().tessellate_path(
+            &path,
+            &StrokeOptions::default().with_line_width(background.border_width),
+            &mut BuffersBuilder::new(&mut buffers, constructor),
+        ) {
+            log::warn!("Label background tessellation failed: {err:?}");
+        }
+    }
+
+    buffers
+}
+
+struct BackgroundVertexConstructor([u8; 4]);
+
+impl FillVertexConstructor<ScreenSetVertex> for BackgroundVertexConstructor {
+    fn new_vertex(&mut self, vertex: FillVertex) -> ScreenSetVertex {
+        ScreenSetVertex {
+            position: vertex.position().to_array(),
+            color: self.0,
+        }
+    }
+}
+
+impl StrokeVertexConstructor<ScreenSetVertex> for BackgroundVertexConstructor {
+    fn new_vertex(&mut self, vertex: StrokeVertex) -> ScreenSetVertex {
+        ScreenSetVertex {
+            position: vertex.position().to_array(),
+            color: self.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use galileo_types::cartesian::Point3;
+
+    use super::*;
+    use crate::Color;
+
+    fn order(z_index: i32, screen_y: f64) -> ScreenSetOrder {
+        ScreenSetOrder {
+            displayed: false,
+            z_index,
+            priority: 0.0,
+            depth: 0.0,
+            screen_y,
+        }
+    }
+
+    fn prioritized(priority: f32, displayed: bool) -> ScreenSetOrder {
+        ScreenSetOrder {
+            priority,
+            displayed,
+            ..order(0, 0.0)
+        }
+    }
+
+    #[test]
+    fn higher_priority_wins_collision() {
+        let mut sets = [prioritized(1.0, true), prioritized(2.0, false)];
+        sets.sort_by(ScreenSetOrder::collision_cmp);
+        assert_eq!(sets[0].priority, 2.0);
+
+        // With equal priority the displayed set is kept.
+        let mut sets = [prioritized(1.0, false), prioritized(1.0, true)];
+        sets.sort_by(ScreenSetOrder::collision_cmp);
+        assert!(sets[0].displayed);
+    }
+
+    #[test]
+    fn overlapping_sets_are_hidden() {
+        let visible = resolve_collisions([
+            (Rect::new(0.0, 0.0, 10.0, 10.0), true),
+            (Rect::new(5.0, 5.0, 15.0, 15.0), true),
+            (Rect::new(20.0, 0.0, 30.0, 10.0), true),
+            (Rect::new(8.0, 8.0, 22.0, 9.0), false),
+        ]);
+        assert_eq!(visible, vec![true, false, true, true]);
+    }
+
+    #[test]
+    fn collisions_are_resolved_deterministically() {
+        let bboxes = [
+            (Rect::new(0.0, 0.0, 10.0, 10.0), true),
+            (Rect::new(5.0, 0.0, 15.0, 10.0), true),
+            (Rect::new(12.0, 0.0, 22.0, 10.0), true),
+        ];
+        let first = resolve_collisions(bboxes);
+        for _ in 0..10 {
+            assert_eq!(resolve_collisions(bboxes), first);
+        }
+        assert_eq!(first, vec![true, false, true]);
+    }
+
+    #[test]
+    fn higher_z_index_is_drawn_last() {
+        let mut sets = [order(2, 0.0), order(1, 0.0)];
+        sets.sort_by(ScreenSetOrder::draw_cmp);
+        assert_eq!(sets[0].z_index, 1);
+        assert_eq!(sets[1].z_index, 2);
+
+        // The z-index takes precedence over the position on the screen.
+        let mut sets = [order(1, 0.5), order(0, -0.5)];
+        sets.sort_by(ScreenSetOrder::draw_cmp);
+        assert_eq!(sets[1].z_index, 1);
+    }
+
+    #[test]
+    fn label_background_has_fill_and_border() {
+        let contour = galileo_types::impls::ClosedContour::new(vec![
+            Point2::new(0.0, 0.0),
+            Point2::new(20.0, 0.0),
+            Point2::new(20.0, 10.0),
+            Point2::new(0.0, 10.0),
+        ]);
+
+        let fill_only = tessellate_background(&contour, &LabelBackground::new(Color::WHITE));
+        assert_eq!(fill_only.indices.len(), 6);
+        assert!(fill_only
+            .vertices
+            .iter()
+            .all(|v| v.color == Color::WHITE.to_u8_array()));
+
+        let with_border = tessellate_background(
+            &contour,
+            &LabelBackground::new(Color::WHITE).with_border(Color::RED, 2.0),
+        );
+        assert!(with_border.indices.len() > fill_only.indices.len());
+        assert!(with_border
+            .vertices
+            .iter()
+            .any(|v| v.color == Color::RED.to_u8_array()));
+    }
+
+    #[test]
+    fn marker_image_is_rotated_around_anchor() {
+        let image =
+            DecodedImage::from_raw(vec![0; 4 * 20 * 10], Size::new(20, 10)).expect("valid image");
+        let style = MarkerStyle::Image {
+            image: Arc::new(image),
+            anchor: Vector2::new(0.5, 1.0),
+            size: None,
+            source_rect: None,
+            rotation: std::f32::consts::FRAC_PI_2,
+        };
+
+        let set = ScreenRenderSet::new_from_marker(&Point3::new(0.0, 0.0, 0.0), &style, 0)
+            .expect("marker is created");
+        let ScreenSetData::Image { vertices, .. } = set.data else {
+            panic!("image marker expected");
+        };
+
+        // Without rotation the image is above the anchor. Rotated clockwise by 90 degrees it is
+        // to the right of the anchor.
+        for vertex in vertices {
+            let [x, y] = vertex.position;
+            assert!((-1e-4..=10.0 + 1e-4).contains(&x), "x: {x}");
+            assert!((-10.0 - 1e-4..=10.0 + 1e-4).contains(&y), "y: {y}");
+        }
+        assert!((set.bbox.width() - 10.0).abs() < 1e-4);
+        assert!((set.bbox.height() - 20.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn marker_samples_source_rect_of_image() {
+        let image =
+            DecodedImage::from_raw(vec![0; 4 * 40 * 20], Size::new(40, 20)).expect("valid image");
+        let style = MarkerStyle::Image {
+            image: Arc::new(image),
+            anchor: Vector2::new(0.0, 0.0),
+            size: None,
+            source_rect: Some(Rect::new(10, 0, 30, 10)),
+            rotation: 0.0,
+        };
+
+        let set = ScreenRenderSet::new_from_marker(&Point3::new(0.0, 0.0, 0.0), &style, 0)
+            .expect("marker is created");
+        let ScreenSetData::Image { vertices, .. } = set.data else {
+            panic!("image marker expected");
+        };
+
+        assert_eq!(set.bbox, Rect::new(0.0, -10.0, 20.0, 0.0));
+        let tex_coords: Vec<_> = vertices.iter().map(|v| v.tex_coords).collect();
+        assert_eq!(
+            tex_coords,
+            vec![[0.25, 0.5], [0.25, 0.0], [0.75, 0.5], [0.75, 0.0]]
+        );
+    }
+
+    #[test]
+    fn lower_sets_are_drawn_over_upper_ones() {
+        let mut sets = [order(0, -0.5), order(0, 0.5)];
+        sets.sort_by(ScreenSetOrder::draw_cmp);
+        assert_eq!(sets[0].screen_y, 0.5);
+        assert_eq!(sets[1].screen_y, -0.5);
+    }
+}
@@ -1,10 +1,14 @@
+use std::collections::HashMap;
 use std::mem::size_of;
+use std::ops::Range;
 use std::sync::Arc;
 
-use galileo_types::cartesian::{CartesianPoint2d, CartesianPoint3d, Point2, Vector2};
+use galileo_types::cartesian::{
+    CartesianPoint2d, CartesianPoint2dFloat, CartesianPoint3d, Point2, Point3, Rect, Size, Vector2,
+};
 use galileo_types::contour::Contour;
 use galileo_types::impls::ClosedContour;
-use galileo_types::Polygon;
+use galileo_types::{MultiPolygon, Polygon};
 use lyon::lyon_tessellation::{
     BuffersBuilder, FillOptions, FillTessellator, FillVertex, FillVertexConstructor, LineJoin,
     Side, StrokeOptions, StrokeTessellator, StrokeVertex, StrokeVertexConstructor, VertexBuffers,
@@ -12,15 +16,22 @@ use lyon::lyon_tessellation::{
 use lyon::math::point;
 use lyon::path::builder::PathBuilder;
 use lyon::path::path::BuilderWithAttributes;
-use lyon::path::{EndpointId, Path};
+use lyon::path::EndpointId;
 use lyon::tessellation::VertexSource;
 use num_traits::AsPrimitive;
 use serde::{Deserialize, Serialize};
 
 use crate::decoded_image::DecodedImage;
+use crate::render::gradient::ResolvedGradient;
 use crate::render::point_paint::{CircleFill, PointPaint, PointShape, SectorParameters};
-use crate::render::text::{TextService, TextShaping, TextStyle};
-use crate::render::{ImagePaint, LinePaint, PolygonPaint};
+use crate::render::text::label_background::label_rect;
+use crate::render::text::{
+    HorizontalAlignment, LabelBackground, LabelPlacement, TextService, TextShaping, TextStyle,
+};
+use crate::render::{
+    ArrowPaint, FillPattern, ImagePaint, ImageSampling, LineCap, LinePaint, PatternSpace,
+    PolygonPaint,
+};
 use crate::Color;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,13 +42,332 @@ pub(crate) struct WorldRenderSet {
     pub clip_area: Option<VertexBuffers<PolyVertex, u32>>,
     pub image_store: Vec<Arc<DecodedImage>>,
     pub buffer_size: usize,
+    #[serde(default = "default_tessellation_tolerance")]
+    pub tessellation_tolerance: f32,
+    #[serde(default = "default_scale_factor")]
+    pub scale_factor: f32,
+    /// Parts of the buffers with their z-indices, in the order the primitives were added. See
+    /// [`WorldRenderSet::draw_order`].
+    #[serde(default)]
+    pub draw_segments: Vec<DrawSegment>,
+    /// Lengths of the buffers when the draw segments were last updated.
+    #[serde(default)]
+    pub synced_lengths: BufferLengths,
+    /// Polygons filled with patterns, one entry per pattern image.
+    #[serde(default)]
+    pub pattern_fills: Vec<PatternFill>,
+    /// Parts of the buffers occupied by the primitives of the bundle, indexed by the index of the primitive handle.
+    #[serde(default)]
+    pub primitives: Vec<PrimitiveRecord>,
+    /// If true, the source geometry of points and lines is kept in their records, so that they can be updated.
+    #[serde(default)]
+    pub updates_enabled: bool,
 }
 
-#[repr(C)]
-#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable, Serialize, Deserialize)]
+/// Lengths of the world set buffers at some moment, used to find the parts of the buffers a primitive was added to.
+pub(crate) struct WorldSetMark {
+    vertices: usize,
+    indices: usize,
+    points: usize,
+    images: usize,
+    /// Lengths of the vertices and indices of every pattern fill.
+    patterns: Vec<(usize, usize)>,
+}
+
+/// Parts of the world set buffers occupied by one primitive.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct PrimitiveRecord {
+    vertices: Range<usize>,
+    indices: Range<usize>,
+    points: Range<usize>,
+    images: Range<usize>,
+    /// Parts of the tessellations of `pattern_fills`.
+    patterns: Vec<PatternRange>,
+    z_index: i32,
+    kind: PrimitiveKind,
+}
+
+impl PrimitiveRecord {
+    /// Shifts the ranges of the record to account for the parts of the buffers occupied by the `removed` primitive
+    /// being drained.
+    fn shift_for_removal(&mut self, removed: &PrimitiveRecord) {
+        self.vertices = range_after_removal(&self.vertices, &removed.vertices);
+        self.indices = range_after_removal(&self.indices, &removed.indices);
+        self.points = range_after_removal(&self.points, &removed.points);
+        self.images = range_after_removal(&self.images, &removed.images);
+        for pattern in &mut self.patterns {
+            for drained in removed.patterns.iter().filter(|p| p.fill == pattern.fill) {
+                pattern.vertices = range_after_removal(&pattern.vertices, &drained.vertices);
+                pattern.indices = range_after_removal(&pattern.indices, &drained.indices);
+            }
+        }
+    }
+
+    /// Approximate size of the buffer parts occupied by the primitive in bytes.
+    fn byte_size(&self) -> usize {
+        let patterns: usize = self
+            .patterns
+            .iter()
+            .map(|pattern| {
+                pattern.vertices.len() * size_of::<PatternVertex>()
+                    + pattern.indices.len() * size_of::<u32>()
+            })
+            .sum();
+
+        self.vertices.len() * size_of::<PolyVertex>()
+            + self.indices.len() * size_of::<u32>()
+            + self.points.len() * size_of::<PointInstance>()
+            + self.images.len() * size_of::<ImageVertex>() * 4
+            + patterns
+    }
+}
+
+/// Parts of the tessellation of a pattern fill occupied by a primitive.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct PatternRange {
+    fill: usize,
+    vertices: Range<usize>,
+    indices: Range<usize>,
+}
+
+/// Position in a buffer after the `removed` range is drained from it.
+fn position_after_removal(position: usize, removed: &Range<usize>) -> usize {
+    if position >= removed.end {
+        position - removed.len()
+    } else {
+        position.min(removed.start)
+    }
+}
+
+fn range_after_removal(range: &Range<usize>, removed: &Range<usize>) -> Range<usize> {
+    position_after_removal(range.start, removed)..position_after_removal(range.end, removed)
+}
+
+/// Drains the given ranges from the tessellation, and shifts the indices of the vertices after the drained ones.
+fn drain_tessellation<V>(
+    tessellation: &mut VertexBuffers<V, u32>,
+    vertices: &Range<usize>,
+    indices: &Range<usize>,
+) {
+    tessellation.vertices.drain(vertices.clone());
+    tessellation.indices.drain(indices.clone());
+
+    let (end, removed) = (vertices.end as u32, vertices.len() as u32);
+    for index in &mut tessellation.indices {
+        if *index >= end {
+            *index -= removed;
+        }
+    }
+}
+
+/// Source data of a primitive kept to be able to update it in place.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) enum PrimitiveKind {
+    Point {
+        position: [f32; 3],
+    },
+    Line {
+        points: Vec<Point3<f32>>,
+        closed: bool,
+        colors: Option<Vec<Color>>,
+        arrows: Option<ArrowPaint>,
+        min_resolution: f64,
+    },
+    #[default]
+    Other,
+}
+
+impl PrimitiveKind {
+    pub fn point<N, P>(point: &P) -> Self
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+    {
+        Self::Point {
+            position: [point.x().as_(), point.y().as_(), point.z().as_()],
+        }
+    }
+
+    pub fn line<N, P, C>(
+        line: &C,
+        colors: Option<&[Color]>,
+        arrows: Option<&ArrowPaint>,
+        min_resolution: f64,
+    ) -> Self
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+        C: Contour<Point = P>,
+    {
+        Self::Line {
+            points: line
+                .iter_points()
+                .map(|p| Point3::new(p.x().as_(), p.y().as_(), p.z().as_()))
+                .collect(),
+            closed: line.is_closed(),
+            colors: colors.map(|colors| colors.to_vec()),
+            arrows: arrows.copied(),
+            min_resolution,
+        }
+    }
+}
+
+/// Lengths of the buffers drawn with different pipelines.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct BufferLengths {
+    images: usize,
+    /// Lengths of the indices of every pattern fill. Fills added later are missing.
+    patterns: Vec<usize>,
+    indices: usize,
+    points: usize,
+}
+
+/// Pipelines used to draw the world set. Within one draw segment the primitives are drawn
+/// pipeline by pipeline in the order of this enum.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum DrawPipeline {
+    Image,
+    Pattern,
+    Polygon,
+    Point,
+}
+
+impl BufferLengths {
+    /// Pipelines, the buffers of which are longer in `other`.
+    fn grown_pipelines(&self, other: &BufferLengths) -> Vec<DrawPipeline> {
+        let mut pipelines = vec![];
+        if other.images > self.images {
+            pipelines.push(DrawPipeline::Image);
+        }
+        if (0..other.patterns.len()).any(|fill| other.pattern(fill) > self.pattern(fill)) {
+            pipelines.push(DrawPipeline::Pattern);
+        }
+        if other.indices > self.indices {
+            pipelines.push(DrawPipeline::Polygon);
+        }
+        if other.points > self.points {
+            pipelines.push(DrawPipeline::Point);
+        }
+
+        pipelines
+    }
+
+    /// Length of the indices of the pattern fill with the given index.
+    fn pattern(&self, fill: usize) -> usize {
+        self.patterns.get(fill).copied().unwrap_or(0)
+    }
+
+    /// Shifts the lengths to account for the parts of the buffers occupied by the `removed` primitive being drained.
+    fn shift_for_removal(&mut self, removed: &PrimitiveRecord) {
+        self.images = position_after_removal(self.images, &removed.images);
+        self.indices = position_after_removal(self.indices, &removed.indices);
+        self.points = position_after_removal(self.points, &removed.points);
+        for pattern in &removed.patterns {
+            if let Some(length) = self.patterns.get_mut(pattern.fill) {
+                *length = position_after_removal(*length, &pattern.indices);
+            }
+        }
+    }
+
+    /// Lengths of the buffers of an appended set, which pattern fills go after the fills of the `offset` set.
+    fn offset_by(&self, offset: &BufferLengths) -> BufferLengths {
+        BufferLengths {
+            images: self.images + offset.images,
+            patterns: offset
+                .patterns
+                .iter()
+                .chain(&self.patterns)
+                .copied()
+                .collect(),
+            indices: self.indices + offset.indices,
+            points: self.points + offset.points,
+        }
+    }
+}
+
+/// Marks the positions in the buffers, starting from which the primitives have the given
+/// z-index and are drawn after the primitives of the previous segments with the same z-index.
+/// Primitives before the first segment have z-index `0`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct DrawSegment {
+    pub start: BufferLengths,
+    pub z_index: i32,
+}
+
+/// Buffers of the world set reordered for drawing, and the draw calls to draw them with.
+#[derive(Debug, Default)]
+pub(crate) struct DrawOrder {
+    /// Indices of the tessellation of every pattern fill.
+    pub pattern_indices: Vec<Vec<u32>>,
+    pub indices: Vec<u32>,
+    pub points: Vec<PointInstance>,
+    pub commands: Vec<DrawCommand>,
+}
+
+/// A draw call of a part of the [`DrawOrder`] buffers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum DrawCommand {
+    /// Draws the image with the given index in `WorldRenderSet::images`.
+    Image(usize),
+    /// Draws the range of `DrawOrder::pattern_indices` of the pattern fill with the given index.
+    Pattern { fill: usize, indices: Range<u32> },
+    /// Draws the range of `DrawOrder::indices`.
+    Polygons(Range<u32>),
+    /// Draws the range of `DrawOrder::points`.
+    Points(Range<u32>),
+}
+
+impl DrawOrder {
+    /// Adds the command, merging it with the previous one if they draw adjacent ranges of the same buffer.
+    fn push(&mut self, command: DrawCommand) {
+        match (self.commands.last_mut(), &command) {
+            (Some(DrawCommand::Polygons(last)), DrawCommand::Polygons(next))
+            | (Some(DrawCommand::Points(last)), DrawCommand::Points(next))
+                if last.end == next.start =>
+            {
+                last.end = next.end;
+            }
+            (
+                Some(DrawCommand::Pattern {
+                    fill: last_fill,
+                    indices: last,
+                }),
+                DrawCommand::Pattern {
+                    fill,
+                    indices: next,
+                },
+            ) if last_fill == fill && last.end == next.start => {
+                last.end = next.end;
+            }
+            _ => self.commands.push(command),
+        }
+    }
+}
+
+/// Default maximum distance in pixels between a curve and its tessellation.
+pub(crate) const DEFAULT_TESSELLATION_TOLERANCE: f32 = 0.1;
+
+fn default_tessellation_tolerance() -> f32 {
+    DEFAULT_TESSELLATION_TOLERANCE
+}
+
+fn default_scale_factor() -> f32 {
+    1.0
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 pub(crate) struct ImageInfo {
     pub(crate) store_index: usize,
     pub(crate) vertices: [ImageVertex; 4],
+    #[serde(default)]
+    pub(crate) sampling: ImageSampling,
+}
+
+/// Tessellation of the polygons filled with the image from the image store.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct PatternFill {
+    pub(crate) store_index: usize,
+    pub(crate) tessellation: VertexBuffers<PatternVertex, u32>,
 }
 
 #[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable, Serialize, Deserialize)]
@@ -63,6 +393,13 @@ impl WorldRenderSet {
             clip_area: None,
             image_store: Vec::new(),
             buffer_size: 0,
+            tessellation_tolerance: DEFAULT_TESSELLATION_TOLERANCE,
+            scale_factor: default_scale_factor(),
+            draw_segments: Vec::new(),
+            synced_lengths: BufferLengths::default(),
+            pattern_fills: Vec::new(),
+            primitives: Vec::new(),
+            updates_enabled: false,
         }
     }
 
@@ -70,6 +407,450 @@ impl WorldRenderSet {
         self.buffer_size
     }
 
+    /// Removes all primitives from the set, keeping the allocated buffers and the set's parameters.
+    pub fn clear(&mut self) {
+        self.poly_tessellation.vertices.clear();
+        self.poly_tessellation.indices.clear();
+        self.points.clear();
+        self.images.clear();
+        self.clip_area = None;
+        self.image_store.clear();
+        self.buffer_size = 0;
+        self.draw_segments.clear();
+        self.synced_lengths = BufferLengths::default();
+        self.pattern_fills.clear();
+        self.primitives.clear();
+    }
+
+    /// Sets the z-index of the primitives added after this call.
+    pub fn set_z_index(&mut self, z_index: i32) {
+        self.sync_draw_segments();
+        self.pop_empty_segment();
+        if self.z_index() != z_index {
+            self.draw_segments.push(DrawSegment {
+                start: self.synced_lengths.clone(),
+                z_index,
+            });
+        }
+    }
+
+    /// Z-index of the primitives being added.
+    pub fn z_index(&self) -> i32 {
+        self.draw_segments
+            .last()
+            .map_or(0, |segment| segment.z_index)
+    }
+
+    fn buffer_lengths(&self) -> BufferLengths {
+        BufferLengths {
+            images: self.images.len(),
+            patterns: self
+                .pattern_fills
+                .iter()
+                .map(|fill| fill.tessellation.indices.len())
+                .collect(),
+            indices: self.poly_tessellation.indices.len(),
+            points: self.points.len(),
+        }
+    }
+
+    /// Removes the last segment if no primitives were added to it.
+    fn pop_empty_segment(&mut self) {
+        if self
+            .draw_segments
+            .last()
+            .is_some_and(|segment| segment.start == self.synced_lengths)
+        {
+            self.draw_segments.pop();
+        }
+    }
+
+    /// Updates the draw segments with the primitives added since the last call. Must be called before adding
+    /// every primitive, so that the primitives are split into segments at the primitive boundaries.
+    fn sync_draw_segments(&mut self) {
+        let lengths = self.buffer_lengths();
+        sync_segments(&mut self.draw_segments, &self.synced_lengths, &lengths);
+        self.synced_lengths = lengths;
+    }
+
+    /// Returns the buffers ordered for drawing, and the draw calls to draw them.
+    ///
+    /// All primitives are ordered by their z-index. Primitives with the same z-index are drawn in the order they
+    /// were added, regardless of the pipeline they are drawn with.
+    pub fn draw_order(&self) -> DrawOrder {
+        let lengths = self.buffer_lengths();
+        let mut segments = self.draw_segments.clone();
+        sync_segments(&mut segments, &self.synced_lengths, &lengths);
+        if segments
+            .first()
+            .is_none_or(|segment| segment.start != BufferLengths::default())
+        {
+            segments.insert(0, DrawSegment::default());
+        }
+
+        let ends = segments
+            .iter()
+            .skip(1)
+            .map(|segment| segment.start.clone())
+            .chain(std::iter::once(lengths));
+        let mut ranges: Vec<_> = segments
+            .iter()
+            .zip(ends)
+            .map(|(segment, end)| (segment.z_index, segment.start.clone(), end))
+            .collect();
+        ranges.sort_by_key(|(z_index, ..)| *z_index);
+
+        let mut order = DrawOrder {
+            pattern_indices: vec![vec![]; self.pattern_fills.len()],
+            ..Default::default()
+        };
+        for (_, start, end) in ranges {
+            for image in start.images..end.images {
+                order.push(DrawCommand::Image(image));
+            }
+
+            for (fill, pattern_fill) in self.pattern_fills.iter().enumerate() {
+                let (from, to) = (start.pattern(fill), end.pattern(fill));
+                if to > from {
+                    let indices = &mut order.pattern_indices[fill];
+                    let first = indices.len() as u32;
+                    indices.extend_from_slice(&pattern_fill.tessellation.indices[from..to]);
+                    let indices = first..indices.len() as u32;
+                    order.push(DrawCommand::Pattern { fill, indices });
+                }
+            }
+
+            if end.indices > start.indices {
+                let from = order.indices.len() as u32;
+                order
+                    .indices
+                    .extend_from_slice(&self.poly_tessellation.indices[start.indices..end.indices]);
+                order.push(DrawCommand::Polygons(from..order.indices.len() as u32));
+            }
+
+            if end.points > start.points {
+                let from = order.points.len() as u32;
+                order
+                    .points
+                    .extend_from_slice(&self.points[start.points..end.points]);
+                order.push(DrawCommand::Points(from..order.points.len() as u32));
+            }
+        }
+
+        order
+    }
+
+    /// Moves all primitives from `other` into this set. Vertex indices and image references of the
+    /// moved primitives are shifted to point into the combined buffers.
+    ///
+    /// If both sets have a clip area, the clip area of this set is kept.
+    pub fn append(&mut self, mut other: WorldRenderSet) {
+        self.sync_draw_segments();
+        other.sync_draw_segments();
+        self.pop_empty_segment();
+        let offset = self.synced_lengths.clone();
+        if other
+            .draw_segments
+            .first()
+            .is_none_or(|segment| segment.start != BufferLengths::default())
+        {
+            other.draw_segments.insert(0, DrawSegment::default());
+        }
+        self.draw_segments
+            .extend(other.draw_segments.iter().map(|segment| DrawSegment {
+                start: segment.start.offset_by(&offset),
+                z_index: segment.z_index,
+            }));
+
+        let index_offset = self.poly_tessellation.indices.len();
+        let point_offset = self.points.len();
+
+        let vertex_offset = self.poly_tessellation.vertices.len() as u32;
+        self.poly_tessellation
+            .vertices
+            .append(&mut other.poly_tessellation.vertices);
+        self.poly_tessellation.indices.extend(
+            other
+                .poly_tessellation
+                .indices
+                .iter()
+                .map(|index| index + vertex_offset),
+        );
+
+        let store_offset = self.image_store.len();
+        let image_offset = self.images.len();
+        self.image_store.append(&mut other.image_store);
+        self.images
+            .extend(other.images.iter().map(|image| ImageInfo {
+                store_index: image.store_index + store_offset,
+                ..*image
+            }));
+        let pattern_offset = self.pattern_fills.len();
+        self.pattern_fills
+            .extend(other.pattern_fills.into_iter().map(|fill| PatternFill {
+                store_index: fill.store_index + store_offset,
+                ..fill
+            }));
+
+        let shift = |range: &Range<usize>, offset: usize| range.start + offset..range.end + offset;
+        self.primitives
+            .extend(other.primitives.into_iter().map(|record| {
+                PrimitiveRecord {
+                    vertices: shift(&record.vertices, vertex_offset as usize),
+                    indices: shift(&record.indices, index_offset),
+                    points: shift(&record.points, point_offset),
+                    images: shift(&record.images, image_offset),
+                    patterns: record
+                        .patterns
+                        .iter()
+                        .map(|pattern| PatternRange {
+                            fill: pattern.fill + pattern_offset,
+                            ..pattern.clone()
+                        })
+                        .collect(),
+                    ..record
+                }
+            }));
+
+        self.points.append(&mut other.points);
+
+        if self.clip_area.is_none() {
+            self.clip_area = other.clip_area;
+        }
+
+        self.buffer_size += other.buffer_size;
+        self.synced_lengths = self.buffer_lengths();
+    }
+
+    /// Returns the current lengths of the buffers. Call [`WorldRenderSet::record_since`] after adding a primitive
+    /// to get the parts of the buffers the primitive was added to.
+    pub fn mark(&self) -> WorldSetMark {
+        WorldSetMark {
+            vertices: self.poly_tessellation.vertices.len(),
+            indices: self.poly_tessellation.indices.len(),
+            points: self.points.len(),
+            images: self.images.len(),
+            patterns: self
+                .pattern_fills
+                .iter()
+                .map(|fill| {
+                    (
+                        fill.tessellation.vertices.len(),
+                        fill.tessellation.indices.len(),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    pub fn record_since(&self, mark: WorldSetMark, kind: PrimitiveKind) -> PrimitiveRecord {
+        let patterns = self
+            .pattern_fills
+            .iter()
+            .enumerate()
+            .filter_map(|(fill, pattern_fill)| {
+                let (vertices, indices) = mark.patterns.get(fill).copied().unwrap_or((0, 0));
+                let tessellation = &pattern_fill.tessellation;
+                (tessellation.indices.len() > indices).then(|| PatternRange {
+                    fill,
+                    vertices: vertices..tessellation.vertices.len(),
+                    indices: indices..tessellation.indices.len(),
+                })
+            })
+            .collect();
+
+        PrimitiveRecord {
+            vertices: mark.vertices..self.poly_tessellation.vertices.len(),
+            indices: mark.indices..self.poly_tessellation.indices.len(),
+            points: mark.points..self.points.len(),
+            images: mark.images..self.images.len(),
+            patterns,
+            z_index: self.z_index(),
+            kind,
+        }
+    }
+
+    /// Stores the record of the primitive with the given handle index.
+    pub fn set_primitive(&mut self, index: usize, record: PrimitiveRecord) {
+        if index >= self.primitives.len() {
+            self.primitives.resize_with(index + 1, Default::default);
+        }
+
+        self.primitives[index] = record;
+    }
+
+    /// Removes the primitive with the given handle index from the buffers.
+    ///
+    /// The parts of the buffers after the primitive are moved to its place, and the records of other primitives
+    /// and the draw segments are shifted accordingly. Images stay in the image store until the set is cleared.
+    pub fn remove_primitive(&mut self, index: usize) {
+        let Some(record) = self.primitives.get_mut(index).map(std::mem::take) else {
+            return;
+        };
+
+        drain_tessellation(
+            &mut self.poly_tessellation,
+            &record.vertices,
+            &record.indices,
+        );
+        self.points.drain(record.points.clone());
+        self.images.drain(record.images.clone());
+        for pattern in &record.patterns {
+            drain_tessellation(
+                &mut self.pattern_fills[pattern.fill].tessellation,
+                &pattern.vertices,
+                &pattern.indices,
+            );
+        }
+
+        for other in &mut self.primitives {
+            other.shift_for_removal(&record);
+        }
+        for segment in &mut self.draw_segments {
+            segment.start.shift_for_removal(&record);
+        }
+        self.synced_lengths.shift_for_removal(&record);
+
+        self.buffer_size = self.buffer_size.saturating_sub(record.byte_size());
+    }
+
+    /// Moves the point primitive with the given handle index to the new position. Returns `false` if the primitive
+    /// is not a point, or its source geometry was not kept (see [`WorldRenderSet::updates_enabled`]).
+    pub fn move_point(&mut self, index: usize, position: [f32; 3]) -> bool {
+        let Some(PrimitiveRecord {
+            vertices,
+            points,
+            kind: PrimitiveKind::Point {
+                position: old_position,
+            },
+            ..
+        }) = self.primitives.get_mut(index)
+        else {
+            return false;
+        };
+
+        // Point shapes are drawn relative to the position of the point, so all their vertices are moved by the same
+        // distance.
+        let delta = [0, 1, 2].map(|i| position[i] - old_position[i]);
+        let shift = |vertex_position: &mut [f32; 3]| {
+            for i in 0..3 {
+                vertex_position[i] += delta[i];
+            }
+        };
+        for vertex in &mut self.poly_tessellation.vertices[vertices.clone()] {
+            shift(&mut vertex.position);
+        }
+        for point in &mut self.points[points.clone()] {
+            shift(&mut point.position);
+        }
+
+        *old_position = position;
+        true
+    }
+
+    /// Tessellates the line primitive with the given handle index again with the new paint. Returns `false` if the
+    /// primitive is not a line, or its source geometry was not kept (see [`WorldRenderSet::updates_enabled`]).
+    ///
+    /// The old tessellation is removed, and the new one is added to the end of the buffers with the z-index of the
+    /// primitive.
+    pub fn update_line(&mut self, index: usize, paint: &LinePaint) -> bool {
+        let Some(record) = self.primitives.get(index) else {
+            return false;
+        };
+        let PrimitiveKind::Line {
+            points,
+            closed,
+            colors,
+            arrows,
+            min_resolution,
+        } = record.kind.clone()
+        else {
+            return false;
+        };
+        let z_index = record.z_index;
+
+        self.remove_primitive(index);
+
+        let line = galileo_types::impls::Contour::new(points.clone(), closed);
+        let current_z_index = self.z_index();
+        self.set_z_index(z_index);
+
+        let mark = self.mark();
+        self.add_line_lod(&line, paint, colors.as_deref(), min_resolution);
+        if let Some(arrows) = &arrows {
+            self.add_line_arrows(&line, paint, arrows, min_resolution);
+        }
+        let kind = PrimitiveKind::Line {
+            points,
+            closed,
+            colors,
+            arrows,
+            min_resolution,
+        };
+        let record = self.record_since(mark, kind);
+        self.set_primitive(index, record);
+
+        self.set_z_index(current_z_index);
+        true
+    }
+
+    pub fn world_bounds(&self) -> Option<Rect> {
+        let polys = self
+            .poly_tessellation
+            .vertices
+            .iter()
+            .map(|v| [v.position[0], v.position[1]]);
+        let points = self.points.iter().map(|p| [p.position[0], p.position[1]]);
+        let images = self
+            .images
+            .iter()
+            .flat_map(|image| image.vertices.iter().map(|v| v.position));
+        let patterns = self.pattern_fills.iter().flat_map(|fill| {
+            fill.tessellation
+                .vertices
+                .iter()
+                .map(|v| [v.position[0], v.position[1]])
+        });
+
+        Rect::from_points(
+            polys
+                .chain(points)
+                .chain(images)
+                .chain(patterns)
+                .map(|[x, y]| Point2::new(x as f64, y as f64)),
+        )
+    }
+
+    pub fn reserve(&mut self, vertices: usize, indices: usize, points: usize) {
+        self.poly_tessellation.vertices.reserve(vertices);
+        self.poly_tessellation.indices.reserve(indices);
+        self.points.reserve(points);
+    }
+
+    /// Writes the polygon tessellation in Wavefront OBJ format.
+    pub fn tessellation_to_obj(&self) -> String {
+        use std::fmt::Write;
+
+        let mut obj = String::new();
+        for vertex in &self.poly_tessellation.vertices {
+            let [x, y, z] = vertex.position;
+            let _ = writeln!(obj, "v {x} {y} {z}");
+        }
+
+        // OBJ vertex indices start from 1.
+        for triangle in self.poly_tessellation.indices.chunks_exact(3) {
+            let _ = writeln!(
+                obj,
+                "f {} {} {}",
+                triangle[0] + 1,
+                triangle[1] + 1,
+                triangle[2] + 1
+            );
+        }
+
+        obj
+    }
+
     pub fn clip_area<N, P, Poly>(&mut self, polygon: &Poly)
     where
         N: AsPrimitive<f32>,
@@ -80,9 +861,8 @@ impl WorldRenderSet {
         let mut tessellation = VertexBuffers::new();
         Self::tessellate_polygon(
             polygon,
-            &PolygonPaint {
-                color: Color::BLACK,
-            },
+            &PolygonPaint::new(Color::BLACK),
+            FillOptions::DEFAULT_TOLERANCE,
             &mut tessellation,
         );
 
@@ -98,6 +878,7 @@ impl WorldRenderSet {
         vertices: [Point2; 4],
         paint: ImagePaint,
     ) {
+        self.sync_draw_segments();
         self.add_image(Arc::new(image), vertices, paint)
     }
 
@@ -107,46 +888,46 @@ impl WorldRenderSet {
         vertices: [Point2; 4],
         paint: ImagePaint,
     ) {
+        self.sync_draw_segments();
+        self.add_image_region(image, Rect::new(0.0, 0.0, 1.0, 1.0), vertices, paint)
+    }
+
+    pub fn add_image_region(
+        &mut self,
+        image: Arc<DecodedImage>,
+        source: Rect,
+        vertices: [Point2; 4],
+        paint: ImagePaint,
+    ) {
+        self.sync_draw_segments();
         let opacity = paint.opacity as f32 / 255.0;
 
         self.buffer_size += image.byte_size() + std::mem::size_of::<ImageVertex>() * 4;
 
         let index = self.add_image_to_store(image);
-        let vertices = [
-            ImageVertex {
-                position: [vertices[0].x() as f32, vertices[0].y() as f32],
-                opacity,
-                tex_coords: [0.0, 1.0],
-                offset: [0.0, 0.0],
-            },
-            ImageVertex {
-                position: [vertices[1].x() as f32, vertices[1].y() as f32],
-                opacity,
-                tex_coords: [0.0, 0.0],
-                offset: [0.0, 0.0],
-            },
-            ImageVertex {
-                position: [vertices[3].x() as f32, vertices[3].y() as f32],
-                opacity,
-                tex_coords: [1.0, 1.0],
-                offset: [0.0, 0.0],
-            },
-            ImageVertex {
-                position: [vertices[2].x() as f32, vertices[2].y() as f32],
-                opacity,
-                tex_coords: [1.0, 0.0],
-                offset: [0.0, 0.0],
-            },
-        ];
+        let tex_coords = region_tex_coords(projective_tex_coords(&vertices), source);
+        let vertex = |i: usize| ImageVertex {
+            position: [vertices[i].x() as f32, vertices[i].y() as f32],
+            opacity,
+            tex_coords: tex_coords[i],
+            offset: [0.0, 0.0],
+        };
+        let vertices = [vertex(0), vertex(1), vertex(3), vertex(2)];
 
-        self.add_image_info(index, vertices);
+        self.add_image_info(index, vertices, paint.sampling);
     }
 
-    fn add_image_info(&mut self, image_store_index: usize, vertices: [ImageVertex; 4]) -> usize {
+    fn add_image_info(
+        &mut self,
+        image_store_index: usize,
+        vertices: [ImageVertex; 4],
+        sampling: ImageSampling,
+    ) -> usize {
         let index = self.images.len();
         self.images.push(ImageInfo {
             store_index: image_store_index,
             vertices,
+            sampling,
         });
         index
     }
@@ -168,6 +949,7 @@ impl WorldRenderSet {
         N: AsPrimitive<f32>,
         P: CartesianPoint3d<Num = N>,
     {
+        self.sync_draw_segments();
         match &paint.shape {
             PointShape::Dot { color } => {
                 self.add_dot(point, *color, paint.offset);
@@ -177,17 +959,24 @@ impl WorldRenderSet {
                 radius,
                 outline,
             } => {
-                self.add_circle(point, *fill, *radius, *outline, paint.offset);
+                self.add_circle(point, *fill, *radius, outline.clone(), paint.offset);
             }
             PointShape::Sector(parameters) => {
-                self.add_circle_sector(point, *parameters, paint.offset);
+                self.add_circle_sector(point, parameters.clone(), paint.offset);
             }
             PointShape::Square {
                 fill,
                 size,
                 outline,
             } => {
-                self.add_shape(point, *fill, *size, *outline, &square_shape(), paint.offset);
+                self.add_shape(
+                    point,
+                    *fill,
+                    *size,
+                    outline.clone(),
+                    &square_shape(),
+                    paint.offset,
+                );
             }
             PointShape::FreeShape {
                 fill,
@@ -195,7 +984,7 @@ impl WorldRenderSet {
                 outline,
                 shape,
             } => {
-                self.add_shape(point, *fill, *scale, *outline, shape, paint.offset);
+                self.add_shape(point, *fill, *scale, outline.clone(), shape, paint.offset);
             }
             PointShape::Label { text, style } => self.add_label(point, text, style, paint.offset),
         };
@@ -207,50 +996,98 @@ impl WorldRenderSet {
         P: CartesianPoint3d<Num = N>,
         C: Contour<Point = P>,
     {
-        self.add_line_lod(line, *paint, min_resolution);
+        self.sync_draw_segments();
+        self.add_line_lod(line, paint, None, min_resolution);
     }
 
-    fn add_line_lod<N, P, C>(&mut self, line: &C, paint: LinePaint, min_resolution: f64)
-    where
+    pub fn add_line_with_colors<N, P, C>(
+        &mut self,
+        line: &C,
+        paint: &LinePaint,
+        colors: &[Color],
+        min_resolution: f64,
+    ) where
         N: AsPrimitive<f32>,
         P: CartesianPoint3d<Num = N>,
         C: Contour<Point = P>,
     {
-        let tessellation = &mut self.poly_tessellation;
-        let mut path_builder = BuilderWithAttributes::new(1);
-        let mut iterator = line.iter_points();
+        self.sync_draw_segments();
+        self.add_line_lod(line, paint, Some(colors), min_resolution);
+    }
 
-        let Some(first_point) = iterator.next() else {
-            return;
+    fn add_line_lod<N, P, C>(
+        &mut self,
+        line: &C,
+        paint: &LinePaint,
+        colors: Option<&[Color]>,
+        min_resolution: f64,
+    ) where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+        C: Contour<Point = P>,
+    {
+        let tessellation = &mut self.poly_tessellation;
+        let mut path_builder = BuilderWithAttributes::new(PathVertex::ATTRIBUTES_COUNT);
+        let mut segment_lengths = HashMap::new();
+
+        let colors = match colors {
+            Some(colors) if colors.len() != line.iter_points().count() => {
+                log::warn!(
+                    "Number of line vertex colors ({}) does not match the number of vertices, line color is used instead",
+                    colors.len()
+                );
+                None
+            }
+            colors => colors,
         };
 
-        let _ = path_builder.begin(
-            point(
-                first_point.x().as_() / min_resolution as f32,
-                first_point.y().as_() / min_resolution as f32,
-            ),
-            &[first_point.z().as_()],
-        );
-
-        for p in iterator {
-            let _ = path_builder.line_to(
-                point(
+        let mut points: Vec<_> = line
+            .iter_points()
+            .enumerate()
+            .map(|(i, p)| {
+                let color = colors.map_or(paint.color, |colors| colors[i]);
+                PathVertex::new(
                     p.x().as_() / min_resolution as f32,
                     p.y().as_() / min_resolution as f32,
-                ),
-                &[p.z().as_()],
-            );
+                    p.z().as_(),
+                    color.to_f32_array(),
+                )
+            })
+            .collect();
+
+        if points.is_empty() {
+            return;
+        }
+
+        match paint
+            .dash_pattern
+            .as_deref()
+            .and_then(|pattern| DashState::new(pattern, paint.dash_offset))
+        {
+            Some(dash_state) => {
+                if line.is_closed() {
+                    points.push(points[0]);
+                }
+
+                for dash in split_into_dashes(&points, dash_state) {
+                    add_path_part(&mut path_builder, &mut segment_lengths, &dash, false);
+                }
+            }
+            None => add_path_part(
+                &mut path_builder,
+                &mut segment_lengths,
+                &points,
+                line.is_closed(),
+            ),
         }
 
-        path_builder.end(line.is_closed());
         let path = path_builder.build();
 
         let vertex_constructor = LineVertexConstructor {
             width: paint.width as f32,
             offset: paint.offset as f32,
-            color: paint.color.to_f32_array(),
             resolution: min_resolution as f32,
-            path: &path,
+            segment_lengths: &segment_lengths,
         };
 
         let mut tesselator = StrokeTessellator::new();
@@ -263,7 +1100,7 @@ impl WorldRenderSet {
                 .with_line_cap(paint.line_cap.into())
                 .with_line_width(paint.width as f32)
                 .with_miter_limit(1.0)
-                .with_tolerance(0.1)
+                .with_tolerance(self.tessellation_tolerance)
                 .with_line_join(LineJoin::MiterClip),
             &mut BuffersBuilder::new(tessellation, vertex_constructor),
         ) {
@@ -288,25 +1125,75 @@ impl WorldRenderSet {
         Poly: Polygon,
         Poly::Contour: Contour<Point = P>,
     {
+        self.sync_draw_segments();
         self.add_polygon_lod(polygon, paint, min_resolution as f32);
+        self.add_polygon_stroke(polygon, paint, min_resolution);
     }
 
-    fn add_polygon_lod<N, P, Poly>(
+    pub fn add_multi_polygon<N, P, MPoly>(
+        &mut self,
+        polygons: &MPoly,
+        paint: &PolygonPaint,
+        min_resolution: f64,
+    ) where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+        MPoly: MultiPolygon,
+        <MPoly::Polygon as Polygon>::Contour: Contour<Point = P>,
+    {
+        self.sync_draw_segments();
+        for polygon in polygons.polygons() {
+            self.add_polygon_lod(polygon, paint, min_resolution as f32);
+        }
+        for polygon in polygons.polygons() {
+            self.add_polygon_stroke(polygon, paint, min_resolution);
+        }
+    }
+
+    fn add_polygon_stroke<N, P, Poly>(
         &mut self,
         polygon: &Poly,
         paint: &PolygonPaint,
-        _min_resolution: f32,
+        min_resolution: f64,
     ) where
         N: AsPrimitive<f32>,
         P: CartesianPoint3d<Num = N>,
         Poly: Polygon,
         Poly::Contour: Contour<Point = P>,
     {
-        let lod = &mut self.poly_tessellation;
-        let start_index = lod.vertices.len();
-        let start_index_count = lod.indices.len();
+        if let Some(stroke) = &paint.stroke {
+            for contour in polygon.iter_contours() {
+                self.add_line(contour, stroke, min_resolution);
+            }
+        }
+    }
+
+    fn add_polygon_lod<N, P, Poly>(
+        &mut self,
+        polygon: &Poly,
+        paint: &PolygonPaint,
+        min_resolution: f32,
+    ) where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+        Poly: Polygon,
+        Poly::Contour: Contour<Point = P>,
+    {
+        // Polygons are tessellated in map units, so the tolerance is converted from pixels.
+        let tolerance = self.tessellation_tolerance * min_resolution;
+
+        if let Some(pattern) = &paint.pattern {
+            let mut tessellation = VertexBuffers::new();
+            Self::tessellate_polygon(polygon, paint, tolerance, &mut tessellation);
+            self.add_pattern_fill(&tessellation, pattern, min_resolution as f64);
+            return;
+        }
+
+        let lod = &mut self.poly_tessellation;
+        let start_index = lod.vertices.len();
+        let start_index_count = lod.indices.len();
 
-        Self::tessellate_polygon(polygon, paint, lod);
+        Self::tessellate_polygon(polygon, paint, tolerance, lod);
 
         let end_index = self.poly_tessellation.vertices.len();
 
@@ -315,9 +1202,92 @@ impl WorldRenderSet {
             (self.poly_tessellation.indices.len() - start_index_count) * size_of::<u32>();
     }
 
+    fn add_pattern_fill(
+        &mut self,
+        tessellation: &VertexBuffers<PolyVertex, u32>,
+        pattern: &FillPattern,
+        min_resolution: f64,
+    ) {
+        let Some(first) = tessellation.vertices.first() else {
+            return;
+        };
+
+        let (sin, cos) = (-pattern.rotation as f64).sin_cos();
+        let rotate = |position: [f32; 3]| {
+            let (x, y) = (position[0] as f64, position[1] as f64);
+            [x * cos - y * sin, x * sin + y * cos]
+        };
+
+        let (texel_size, origin) = match pattern.space {
+            PatternSpace::World => {
+                // The origin is snapped to the pattern period, so the patterns of adjacent polygons line up.
+                let texel_size = pattern.scale as f64 * min_resolution;
+                let period = [
+                    pattern.image.width() as f64 * texel_size,
+                    pattern.image.height() as f64 * texel_size,
+                ];
+                let first = rotate(first.position);
+                let origin = [0, 1].map(|i| {
+                    if period[i] > 0.0 {
+                        (first[i] / period[i]).floor() * period[i]
+                    } else {
+                        first[i]
+                    }
+                });
+                (texel_size as f32, origin)
+            }
+            PatternSpace::Screen => (0.0, rotate(first.position)),
+        };
+
+        let vertices = tessellation.vertices.iter().map(|vertex| {
+            let position = rotate(vertex.position);
+            PatternVertex {
+                position: vertex.position,
+                color: vertex.color,
+                pattern_position: [
+                    (position[0] - origin[0]) as f32,
+                    (position[1] - origin[1]) as f32,
+                ],
+                texel_size,
+                scale: pattern.scale,
+            }
+        });
+
+        let store_index = self.add_image_to_store(pattern.image.clone());
+        let fill_index = match self
+            .pattern_fills
+            .iter()
+            .position(|fill| fill.store_index == store_index)
+        {
+            Some(index) => index,
+            None => {
+                self.buffer_size += pattern.image.byte_size();
+                self.pattern_fills.push(PatternFill {
+                    store_index,
+                    tessellation: VertexBuffers::new(),
+                });
+                self.pattern_fills.len() - 1
+            }
+        };
+
+        let fill = &mut self.pattern_fills[fill_index].tessellation;
+        let vertex_offset = fill.vertices.len() as u32;
+        fill.vertices.extend(vertices);
+        fill.indices.extend(
+            tessellation
+                .indices
+                .iter()
+                .map(|index| index + vertex_offset),
+        );
+
+        self.buffer_size += tessellation.vertices.len() * size_of::<PatternVertex>()
+            + tessellation.indices.len() * size_of::<u32>();
+    }
+
     fn tessellate_polygon<N, P, Poly>(
         polygon: &Poly,
         paint: &PolygonPaint,
+        tolerance: f32,
         tessellation: &mut VertexBuffers<PolyVertex, u32>,
     ) where
         N: AsPrimitive<f32>,
@@ -326,6 +1296,7 @@ impl WorldRenderSet {
         Poly::Contour: Contour<Point = P>,
     {
         let mut path_builder = BuilderWithAttributes::new(1);
+        let mut vertices = vec![];
         for contour in polygon.iter_contours() {
             let mut iterator = contour.iter_points();
 
@@ -334,12 +1305,14 @@ impl WorldRenderSet {
                     point(first_point.x().as_(), first_point.y().as_()),
                     &[first_point.z().as_()],
                 );
+                vertices.push(first_point);
             } else {
                 return;
             }
 
             for p in iterator {
                 let _ = path_builder.line_to(point(p.x().as_(), p.y().as_()), &[p.z().as_()]);
+                vertices.push(p);
             }
 
             path_builder.end(true);
@@ -347,18 +1320,56 @@ impl WorldRenderSet {
 
         let path = path_builder.build();
 
+        let gradient = paint.gradient.as_ref().and_then(|gradient| {
+            let vertices: Vec<_> = vertices
+                .iter()
+                .map(|p| Point2::new(p.x().as_() as f64, p.y().as_() as f64))
+                .collect();
+            gradient.resolve(&vertices)
+        });
+        let first_index = tessellation.indices.len();
         let vertex_constructor = PolygonVertexConstructor {
             color: paint.color.to_f32_array(),
+            gradient: gradient.as_ref(),
         };
         let mut tesselator = FillTessellator::new();
 
-        if let Err(err) = tesselator.tessellate(
+        if let Err(err) = tesselator.tessellate_path(
             &path,
-            &FillOptions::DEFAULT,
+            &FillOptions::DEFAULT
+                .with_tolerance(tolerance)
+                .with_fill_rule(paint.fill_rule.into()),
             &mut BuffersBuilder::new(tessellation, vertex_constructor),
         ) {
             log::error!("Tessellation failed: {err:?}");
         }
+
+        if let Some(gradient) = &gradient {
+            GradientSubdivision::new(gradient, tessellation, tolerance).run(first_index);
+        }
+    }
+
+    pub fn add_quad<N, P>(&mut self, corners: [&P; 4], color: Color)
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+    {
+        self.sync_draw_segments();
+        let first_index = self.poly_tessellation.vertices.len() as u32;
+        for corner in corners {
+            self.poly_tessellation.vertices.push(PolyVertex {
+                position: [corner.x().as_(), corner.y().as_(), corner.z().as_()],
+                color: color.to_f32_array(),
+                normal: Default::default(),
+                norm_limit: 1.0,
+            });
+        }
+
+        self.poly_tessellation
+            .indices
+            .extend([0, 1, 2, 0, 2, 3].map(|i| first_index + i));
+
+        self.buffer_size += 4 * size_of::<PolyVertex>() + 6 * size_of::<u32>();
     }
 
     pub fn add_shape<N, P>(
@@ -373,6 +1384,7 @@ impl WorldRenderSet {
         N: AsPrimitive<f32>,
         P: CartesianPoint3d<Num = N>,
     {
+        self.sync_draw_segments();
         let mut path_builder = BuilderWithAttributes::new(0);
         build_contour_path(&mut path_builder, shape, scale);
         let path = path_builder.build();
@@ -391,7 +1403,9 @@ impl WorldRenderSet {
 
             if let Err(err) = StrokeTessellator::new().tessellate(
                 &path,
-                &StrokeOptions::DEFAULT.with_line_width(outline.width as f32 * 2.0),
+                &StrokeOptions::DEFAULT
+                    .with_line_width(outline.width as f32 * 2.0)
+                    .with_tolerance(self.tessellation_tolerance),
                 &mut BuffersBuilder::new(tessellation, vertex_constructor),
             ) {
                 log::warn!("Shape tessellation failed: {err:?}");
@@ -408,7 +1422,7 @@ impl WorldRenderSet {
 
             if let Err(err) = FillTessellator::new().tessellate(
                 &path,
-                &FillOptions::DEFAULT,
+                &FillOptions::DEFAULT.with_tolerance(self.tessellation_tolerance),
                 &mut BuffersBuilder::new(tessellation, vertex_constructor),
             ) {
                 log::warn!("Shape tessellation failed: {err:?}");
@@ -475,7 +1489,8 @@ impl WorldRenderSet {
 
         let is_full_circle = (dr - std::f32::consts::PI * 2.0).abs() < TOLERANCE;
 
-        let mut contour = get_circle_sector(radius, start_angle, end_angle);
+        let mut contour =
+            get_circle_sector(radius, start_angle, end_angle, self.tessellation_tolerance);
         let first_index = self.poly_tessellation.vertices.len() as u32;
 
         let start_vertex_count = self.poly_tessellation.vertices.len();
@@ -544,6 +1559,100 @@ impl WorldRenderSet {
         self.buffer_size += size_of::<PointInstance>();
     }
 
+    pub fn add_label_with_background<N, P>(
+        &mut self,
+        position: &P,
+        text: &str,
+        style: &TextStyle,
+        offset: Vector2<f32>,
+        background: &LabelBackground,
+    ) where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+    {
+        self.sync_draw_segments();
+        match TextService::measure(text, style, self.scale_factor) {
+            Ok(size) => self.add_label_background(position, size, style, offset, background),
+            Err(err) => log::error!("Error measuring text label: {err:?}"),
+        }
+
+        self.add_label(position, text, style, offset);
+    }
+
+    pub fn add_label_with_placement<N, P>(
+        &mut self,
+        position: &P,
+        text: &str,
+        style: &TextStyle,
+        placement: &LabelPlacement,
+        background: Option<&LabelBackground>,
+    ) where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+    {
+        self.sync_draw_segments();
+        let offset = placement.offset;
+        if background.is_some() || placement.leader_line.is_some() {
+            match TextService::measure(text, style, self.scale_factor) {
+                Ok(size) => {
+                    let padding = background.map_or(0.0, |background| background.padding);
+                    let rect = label_rect(size, style, offset, padding);
+                    if let Some(leader_line) = &placement.leader_line {
+                        if let Some(contour) = leader_line.contour(rect, offset) {
+                            self.add_shape(
+                                position,
+                                leader_line.color,
+                                1.0,
+                                None,
+                                &contour,
+                                Vector2::default(),
+                            );
+                        }
+                    }
+
+                    if let Some(background) = background {
+                        self.add_label_background(position, size, style, offset, background);
+                    }
+                }
+                Err(err) => log::error!("Error measuring text label: {err:?}"),
+            }
+        }
+
+        self.add_label(position, text, style, offset);
+    }
+
+    fn add_label_background<N, P>(
+        &mut self,
+        position: &P,
+        text_size: Size<f32>,
+        style: &TextStyle,
+        offset: Vector2<f32>,
+        background: &LabelBackground,
+    ) where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+    {
+        let contour = background.contour(text_size, style, offset);
+        let outline = background.has_border().then(|| LinePaint {
+            color: background.border_color,
+            // Shape outlines are stroked with the doubled width.
+            width: background.border_width as f64 / 2.0,
+            offset: 0.0,
+            line_cap: LineCap::Butt,
+            dash_pattern: None,
+            dash_offset: 0.0,
+        });
+
+        self.add_shape(
+            position,
+            background.fill,
+            1.0,
+            outline,
+            &contour,
+            Vector2::default(),
+        );
+    }
+
     pub fn add_label<N, P>(
         &mut self,
         position: &P,
@@ -554,7 +1663,8 @@ impl WorldRenderSet {
         N: AsPrimitive<f32>,
         P: CartesianPoint3d<Num = N>,
     {
-        match TextService::shape(text, style, offset) {
+        self.sync_draw_segments();
+        match TextService::shape_scaled(text, style, offset, self.scale_factor) {
             Ok(TextShaping::Tessellation { glyphs, .. }) => {
                 for glyph in glyphs {
                     let vertices_start = self.poly_tessellation.vertices.len() as u32;
@@ -579,14 +1689,291 @@ impl WorldRenderSet {
             }
         }
     }
+
+    /// Adds a label with glyphs placed along the line. See [`RenderBundle::add_line_label`] for details.
+    ///
+    /// [`RenderBundle::add_line_label`]: super::RenderBundle::add_line_label
+    pub fn add_line_label<N, P, C>(
+        &mut self,
+        line: &C,
+        text: &str,
+        style: &TextStyle,
+        min_resolution: f64,
+    ) where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+        C: Contour<Point = P>,
+    {
+        self.sync_draw_segments();
+        let resolution = min_resolution as f32;
+        let (points, z) = screen_line_points(line, resolution);
+
+        let glyph_style = TextStyle {
+            horizontal_alignment: HorizontalAlignment::Center,
+            max_width: None,
+            ..style.clone()
+        };
+
+        let chars: Vec<String> = text
+            .chars()
+            .map(|c| if c.is_control() { ' ' } else { c })
+            .map(String::from)
+            .collect();
+        let mut advances = Vec::with_capacity(chars.len());
+        for c in &chars {
+            match TextService::measure(c, &glyph_style, self.scale_factor) {
+                Ok(size) => advances.push(size.width()),
+                Err(err) => {
+                    log::error!("Error measuring text label: {err:?}");
+                    return;
+                }
+            }
+        }
+
+        let Some(placements) = place_along_line(&points, &advances, style.letter_spacing) else {
+            return;
+        };
+
+        for (c, placement) in chars.iter().zip(placements) {
+            let glyphs = match TextService::shape_scaled(
+                c,
+                &glyph_style,
+                Vector2::default(),
+                self.scale_factor,
+            ) {
+                Ok(TextShaping::Tessellation { glyphs, .. }) => glyphs,
+                Err(err) => {
+                    log::error!("Error shaping text label: {err:?}");
+                    return;
+                }
+                _ => {
+                    log::error!("Not supported font type");
+                    return;
+                }
+            };
+
+            let position = [
+                placement.position.x() * resolution,
+                placement.position.y() * resolution,
+                z,
+            ];
+            let (sin, cos) = placement.angle.sin_cos();
+            for glyph in glyphs {
+                let vertices_start = self.poly_tessellation.vertices.len() as u32;
+                for vertex in glyph.vertices {
+                    let [x, y] = vertex.position;
+                    self.poly_tessellation.vertices.push(PolyVertex {
+                        position,
+                        normal: [x * cos - y * sin, x * sin + y * cos],
+                        color: vertex.color.to_f32_array(),
+                        norm_limit: f32::MAX,
+                    });
+                }
+                for index in glyph.indices {
+                    self.poly_tessellation.indices.push(index + vertices_start);
+                }
+            }
+        }
+    }
+
+    /// Adds arrows along the line. See [`RenderBundle::add_line_with_arrows`] for details.
+    ///
+    /// [`RenderBundle::add_line_with_arrows`]: super::RenderBundle::add_line_with_arrows
+    pub fn add_line_arrows<N, P, C>(
+        &mut self,
+        line: &C,
+        paint: &LinePaint,
+        arrows: &ArrowPaint,
+        min_resolution: f64,
+    ) where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+        C: Contour<Point = P>,
+    {
+        self.sync_draw_segments();
+        if arrows.size <= 0.0 || arrows.spacing <= 0.0 || arrows.color.is_transparent() {
+            return;
+        }
+
+        let resolution = min_resolution as f32;
+        let (points, z) = screen_line_points(line, resolution);
+
+        // A round cap extends the line beyond its end points, so arrows can reach closer to them.
+        let cap_extension = match paint.line_cap {
+            LineCap::Round => paint.width as f32 / 2.0,
+            LineCap::Butt => 0.0,
+        };
+        let half_size = arrows.size / 2.0;
+        let margin = (half_size - cap_extension).max(0.0);
+        let color = arrows.color.to_f32_array();
+
+        for placement in place_arrows(&points, arrows.spacing, margin) {
+            let position = [
+                placement.position.x() * resolution,
+                placement.position.y() * resolution,
+                z,
+            ];
+            let (sin, cos) = placement.angle.sin_cos();
+            let vertices_start = self.poly_tessellation.vertices.len() as u32;
+            for [x, y] in [
+                [half_size, 0.0],
+                [-half_size, half_size],
+                [-half_size, -half_size],
+            ] {
+                self.poly_tessellation.vertices.push(PolyVertex {
+                    position,
+                    normal: [x * cos - y * sin, x * sin + y * cos],
+                    color,
+                    norm_limit: f32::MAX,
+                });
+            }
+            self.poly_tessellation.indices.extend([
+                vertices_start,
+                vertices_start + 1,
+                vertices_start + 2,
+            ]);
+        }
+    }
+}
+
+/// Position and rotation of a glyph or a marker placed along a line.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct LinePlacement {
+    /// Center of the glyph's baseline or of the marker.
+    position: Point2<f32>,
+    /// Angle of the line at the glyph position in radians.
+    angle: f32,
+}
+
+/// Points of the line in screen pixels at the given resolution, with the first point repeated at the end for closed
+/// lines, and the z coordinate of the line.
+fn screen_line_points<N, P, C>(line: &C, resolution: f32) -> (Vec<Point2<f32>>, f32)
+where
+    N: AsPrimitive<f32>,
+    P: CartesianPoint3d<Num = N>,
+    C: Contour<Point = P>,
+{
+    let mut z = 0.0;
+    let mut points: Vec<Point2<f32>> = line
+        .iter_points()
+        .map(|p| {
+            z = p.z().as_();
+            Point2::new(p.x().as_() / resolution, p.y().as_() / resolution)
+        })
+        .collect();
+
+    if line.is_closed() {
+        if let Some(&first) = points.first() {
+            points.push(first);
+        }
+    }
+
+    (points, z)
+}
+
+/// Places glyphs with the given advances along the line, so that the text is centered on the line and each glyph
+/// is tangent to it. The `letter_spacing` is added between consecutive glyphs.
+///
+/// If the line runs from right to left, it is reversed so that the text is not upside down. Returns `None` if the
+/// line is shorter than the text.
+fn place_along_line(
+    points: &[Point2<f32>],
+    advances: &[f32],
+    letter_spacing: f32,
+) -> Option<Vec<LinePlacement>> {
+    let (first, last) = (points.first()?, points.last()?);
+    let reversed: Vec<Point2<f32>>;
+    let points = if last.x() < first.x() {
+        reversed = points.iter().rev().copied().collect();
+        &reversed[..]
+    } else {
+        points
+    };
+
+    let segment_lengths: Vec<f32> = points
+        .windows(2)
+        .map(|segment| (segment[1] - segment[0]).magnitude())
+        .collect();
+    let line_length: f32 = segment_lengths.iter().sum();
+    let text_length: f32 =
+        advances.iter().sum::<f32>() + advances.len().saturating_sub(1) as f32 * letter_spacing;
+    if text_length > line_length || line_length == 0.0 {
+        return None;
+    }
+
+    let mut placements = Vec::with_capacity(advances.len());
+    let mut segment_index = 0;
+    let mut segment_start = 0.0;
+    let mut cursor = (line_length - text_length) / 2.0;
+
+    for advance in advances {
+        let distance = cursor + advance / 2.0;
+        while segment_index < segment_lengths.len() - 1
+            && segment_start + segment_lengths[segment_index] < distance
+        {
+            segment_start += segment_lengths[segment_index];
+            segment_index += 1;
+        }
+
+        let from = points[segment_index];
+        let to = points[segment_index + 1];
+        let direction = to - from;
+        let length = segment_lengths[segment_index];
+        let t = if length > 0.0 {
+            (distance - segment_start) / length
+        } else {
+            0.0
+        };
+
+        placements.push(LinePlacement {
+            position: from + direction * t,
+            angle: direction.dy().atan2(direction.dx()),
+        });
+        cursor += advance + letter_spacing;
+    }
+
+    Some(placements)
 }
 
-fn get_circle_sector(radius: f32, start_angle: f32, end_angle: f32) -> Vec<Point2<f32>> {
-    const TOLERANCE: f32 = 0.1;
+/// Places markers along the line every `spacing` units, starting at half of the spacing from the start of the line.
+/// Markers closer than `margin` to the ends of the line are skipped. Markers are directed along the line from its
+/// start to its end.
+fn place_arrows(points: &[Point2<f32>], spacing: f32, margin: f32) -> Vec<LinePlacement> {
+    let line_length: f32 = points
+        .windows(2)
+        .map(|segment| (segment[1] - segment[0]).magnitude())
+        .sum();
+
+    let mut placements = vec![];
+    let mut distance = spacing / 2.0;
+    let mut segment_start = 0.0;
+    for segment in points.windows(2) {
+        let direction = segment[1] - segment[0];
+        let length = direction.magnitude();
+        while distance <= segment_start + length {
+            if distance >= margin && distance <= line_length - margin {
+                placements.push(LinePlacement {
+                    position: segment[0] + direction * ((distance - segment_start) / length),
+                    angle: direction.dy().atan2(direction.dx()),
+                });
+            }
+            distance += spacing;
+        }
+        segment_start += length;
+    }
+
+    placements
+}
 
+fn get_circle_sector(
+    radius: f32,
+    start_angle: f32,
+    end_angle: f32,
+    tolerance: f32,
+) -> Vec<Point2<f32>> {
     let mut contour = vec![];
 
-    if radius <= TOLERANCE {
+    if radius <= tolerance {
         return contour;
     }
 
@@ -595,7 +1982,7 @@ fn get_circle_sector(radius: f32, start_angle: f32, end_angle: f32) -> Vec<Point
         .min(std::f32::consts::PI * 2.0);
 
     let circle_steps_count =
-        std::f32::consts::PI / ((radius - TOLERANCE) / (radius + TOLERANCE)).acos();
+        std::f32::consts::PI / ((radius - tolerance) / (radius + tolerance)).acos();
 
     let segment_steps_count =
         ((dr / std::f32::consts::PI * 2.0) * circle_steps_count).ceil() as usize;
@@ -642,13 +2029,180 @@ fn build_contour_path(
     Some(())
 }
 
+/// Starts a new segment if the primitives added between `synced` and `current` lengths must be drawn with a
+/// pipeline that goes before the pipelines already used in the last segment.
+fn sync_segments(segments: &mut Vec<DrawSegment>, synced: &BufferLengths, current: &BufferLengths) {
+    let (start, z_index) = segments
+        .last()
+        .map_or((BufferLengths::default(), 0), |segment| {
+            (segment.start.clone(), segment.z_index)
+        });
+    let used = start.grown_pipelines(synced);
+    let added = synced.grown_pipelines(current);
+    if let (Some(used), Some(added)) = (used.iter().max(), added.iter().min()) {
+        if added < used {
+            segments.push(DrawSegment {
+                start: synced.clone(),
+                z_index,
+            });
+        }
+    }
+}
+
+/// Vertex of a line in pixel coordinates. Its z coordinate and color are stored as path
+/// attributes, so that they are interpolated along the line by the tessellator.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct PathVertex {
+    position: lyon::math::Point,
+    /// `[z, r, g, b, a]`
+    attributes: [f32; PathVertex::ATTRIBUTES_COUNT],
+}
+
+impl PathVertex {
+    const ATTRIBUTES_COUNT: usize = 5;
+
+    fn new(x: f32, y: f32, z: f32, color: [f32; 4]) -> Self {
+        Self {
+            position: point(x, y),
+            attributes: [z, color[0], color[1], color[2], color[3]],
+        }
+    }
+
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            position: self.position.lerp(other.position, t),
+            attributes: std::array::from_fn(|i| {
+                self.attributes[i] + (other.attributes[i] - self.attributes[i]) * t
+            }),
+        }
+    }
+}
+
+/// Adds the polyline to the path builder. The length of the segment ending at each endpoint
+/// (except the first one) is stored into `segment_lengths`.
+fn add_path_part(
+    path_builder: &mut BuilderWithAttributes,
+    segment_lengths: &mut HashMap<EndpointId, f32>,
+    vertices: &[PathVertex],
+    is_closed: bool,
+) {
+    let Some((first, rest)) = vertices.split_first() else {
+        return;
+    };
+
+    let _ = path_builder.begin(first.position, &first.attributes);
+    let mut prev = first.position;
+    for v in rest {
+        let id = path_builder.line_to(v.position, &v.attributes);
+        segment_lengths.insert(id, (v.position - prev).length());
+        prev = v.position;
+    }
+    path_builder.end(is_closed);
+}
+
+/// Position inside a dash pattern.
+#[derive(Debug, Clone)]
+struct DashState<'a> {
+    pattern: &'a [f32],
+    index: usize,
+    is_dash: bool,
+    remaining: f32,
+}
+
+impl<'a> DashState<'a> {
+    /// Creates a state at the `offset` distance from the start of the pattern. Returns `None` if
+    /// the pattern cannot be used for dashing (it is empty, has negative or non-finite elements or
+    /// its total length is zero).
+    fn new(pattern: &'a [f32], offset: f32) -> Option<Self> {
+        if pattern.iter().any(|v| !v.is_finite() || *v < 0.0) {
+            return None;
+        }
+
+        let length: f32 = pattern.iter().sum();
+        if length <= 0.0 {
+            return None;
+        }
+
+        // Odd patterns are repeated twice, so that dashes and gaps alternate.
+        let period = if pattern.len().is_multiple_of(2) {
+            length
+        } else {
+            length * 2.0
+        };
+
+        let mut state = Self {
+            pattern,
+            index: 0,
+            is_dash: true,
+            remaining: pattern[0],
+        };
+
+        let mut phase = if offset.is_finite() {
+            offset.rem_euclid(period)
+        } else {
+            0.0
+        };
+        while phase >= state.remaining {
+            phase -= state.remaining;
+            state.advance();
+        }
+        state.remaining -= phase;
+
+        Some(state)
+    }
+
+    fn advance(&mut self) {
+        self.index = (self.index + 1) % self.pattern.len();
+        self.is_dash = !self.is_dash;
+        self.remaining = self.pattern[self.index];
+    }
+}
+
+/// Splits the polyline into dashes. The pattern phase is carried over the polyline vertices, so a
+/// dash that covers a vertex goes around the corner without a break.
+fn split_into_dashes(vertices: &[PathVertex], mut state: DashState) -> Vec<Vec<PathVertex>> {
+    let mut dashes = vec![];
+    let mut current = vec![];
+
+    if state.is_dash {
+        current.extend(vertices.first());
+    }
+
+    for segment in vertices.windows(2) {
+        let (from, to) = (&segment[0], &segment[1]);
+        let length = (to.position - from.position).length();
+        let mut passed = 0.0;
+
+        while length - passed > state.remaining {
+            passed += state.remaining;
+            current.push(from.lerp(to, passed / length));
+            if state.is_dash {
+                dashes.push(std::mem::take(&mut current));
+            }
+
+            state.advance();
+        }
+
+        state.remaining -= length - passed;
+        if state.is_dash {
+            current.push(*to);
+        }
+    }
+
+    if current.len() > 1 {
+        dashes.push(current);
+    }
+
+    dashes
+}
+
 #[allow(dead_code)]
 struct LineVertexConstructor<'a> {
     width: f32,
     offset: f32,
-    color: [f32; 4],
     resolution: f32,
-    path: &'a Path,
+    /// Lengths of the segments ending at the path endpoints.
+    segment_lengths: &'a HashMap<EndpointId, f32>,
 }
 
 impl StrokeVertexConstructor<PolyVertex> for LineVertexConstructor<'_> {
@@ -664,73 +2218,247 @@ impl StrokeVertexConstructor<PolyVertex> for LineVertexConstructor<'_> {
             vertex.normal().y * (vertex.line_width() / 2.0 + offset),
         ];
 
-        let norm_limit = if let VertexSource::Endpoint { id } = vertex.source() {
-            let mut prev_id = id.0.saturating_sub(1);
-            while self.path[EndpointId(prev_id)] == Default::default() && prev_id > 0 {
-                prev_id -= 1;
-            }
-
-            if prev_id != 0 {
-                let prev_id = EndpointId(prev_id);
-                let from = self.path[prev_id];
-                let to = self.path[id];
-                let dx = from.x - to.x;
-                let dy = from.y - to.y;
-                (dx * dx + dy * dy).sqrt() * 2.0 * self.resolution
-            } else {
-                f32::MAX
-            }
-        } else {
-            f32::MAX
+        let norm_limit = match vertex.source() {
+            VertexSource::Endpoint { id } => self
+                .segment_lengths
+                .get(&id)
+                .map_or(f32::MAX, |length| length * 2.0 * self.resolution),
+            _ => f32::MAX,
         };
 
+        let attributes = vertex.interpolated_attributes();
         PolyVertex {
             position: [
                 position.x * self.resolution,
                 position.y * self.resolution,
-                vertex.interpolated_attributes()[0],
+                attributes[0],
             ],
-            color: self.color,
+            color: [attributes[1], attributes[2], attributes[3], attributes[4]],
             normal,
             norm_limit,
         }
     }
 }
 
-struct PolygonVertexConstructor {
+struct PolygonVertexConstructor<'a> {
     color: [f32; 4],
+    gradient: Option<&'a ResolvedGradient<'a>>,
 }
 
-impl FillVertexConstructor<PolyVertex> for PolygonVertexConstructor {
-    fn new_vertex(&mut self, vertex: FillVertex) -> PolyVertex {
+impl FillVertexConstructor<PolyVertex> for PolygonVertexConstructor<'_> {
+    fn new_vertex(&mut self, mut vertex: FillVertex) -> PolyVertex {
+        let position = vertex.position();
+        let z = vertex.interpolated_attributes()[0];
+        let color = match &self.gradient {
+            Some(gradient) => gradient.color_at(&Point2::new(position.x as f64, position.y as f64)),
+            None => self.color,
+        };
+
         PolyVertex {
-            position: [vertex.position().x, vertex.position().y, 0.0],
-            color: self.color,
+            position: [position.x, position.y, z],
+            color,
             normal: Default::default(),
             norm_limit: 1.0,
         }
     }
 }
 
-struct ScreenRefVertexConstructor {
-    color: [f32; 4],
-    position: [f32; 3],
-    offset: Vector2<f32>,
+/// Maximum difference of a color channel between the gradient and the colors interpolated between the vertices of
+/// the gradient fill tessellation.
+const GRADIENT_COLOR_TOLERANCE: f32 = 1.0 / 255.0;
+
+/// Maximum number of times the triangles of a gradient fill are subdivided.
+const MAX_GRADIENT_SUBDIVISION_DEPTH: u32 = 16;
+
+/// Maximum number of vertices added by the subdivision of a single gradient filled polygon.
+const MAX_GRADIENT_SUBDIVISION_VERTICES: usize = 1 << 16;
+
+/// Splits the triangles of a gradient filled polygon until the colors interpolated between the vertices match the
+/// gradient, as the GPU can only interpolate the colors linearly.
+///
+/// The triangles are subdivided in passes. In every pass the decision to split an edge is made once per edge, so all
+/// the triangles sharing the edge split it at the same vertex and no T-junctions are created, even when the depth
+/// limit or the vertex budget stops the subdivision. Triangles that do not need any of the edges split, but still
+/// differ from the gradient inside (e.g. around the center of a radial gradient) are split at their centroid. Edges
+/// and triangles not longer than `min_edge_length` are never split.
+struct GradientSubdivision<'a> {
+    gradient: &'a ResolvedGradient<'a>,
+    tessellation: &'a mut VertexBuffers<PolyVertex, u32>,
+    min_edge_length: f32,
+    added_vertices: usize,
 }
 
-impl ScreenRefVertexConstructor {
-    fn create_vertex(&self, position: lyon::math::Point) -> PolyVertex {
-        PolyVertex {
-            position: self.position,
-            normal: [position.x + self.offset.dx(), position.y + self.offset.dy()],
-            color: self.color,
-            norm_limit: f32::MAX,
+impl<'a> GradientSubdivision<'a> {
+    fn new(
+        gradient: &'a ResolvedGradient<'a>,
+        tessellation: &'a mut VertexBuffers<PolyVertex, u32>,
+        min_edge_length: f32,
+    ) -> Self {
+        Self {
+            gradient,
+            tessellation,
+            min_edge_length,
+            added_vertices: 0,
         }
     }
-}
 
-impl StrokeVertexConstructor<PolyVertex> for ScreenRefVertexConstructor {
-    fn new_vertex(&mut self, vertex: StrokeVertex) -> PolyVertex {
+    /// Subdivides the triangles starting from the given position in the index buffer.
+    fn run(mut self, first_index: usize) {
+        let mut triangles: Vec<u32> = self.tessellation.indices.drain(first_index..).collect();
+        for _ in 0..MAX_GRADIENT_SUBDIVISION_DEPTH {
+            let mut midpoints = HashMap::new();
+            let mut subdivided = Vec::with_capacity(triangles.len());
+            let mut is_changed = false;
+            for triangle in triangles.chunks_exact(3) {
+                is_changed |= self.subdivide(
+                    [triangle[0], triangle[1], triangle[2]],
+                    &mut midpoints,
+                    &mut subdivided,
+                );
+            }
+
+            triangles = subdivided;
+            if !is_changed {
+                break;
+            }
+        }
+
+        self.tessellation.indices.append(&mut triangles);
+    }
+
+    /// Adds the triangles the given one is split into in this pass to `output`. Returns false if the triangle is
+    /// not split.
+    fn subdivide(
+        &mut self,
+        [a, b, c]: [u32; 3],
+        midpoints: &mut HashMap<(u32, u32), Option<u32>>,
+        output: &mut Vec<u32>,
+    ) -> bool {
+        match (
+            self.split(a, b, midpoints),
+            self.split(b, c, midpoints),
+            self.split(c, a, midpoints),
+        ) {
+            (None, None, None) => match self.centroid([a, b, c]) {
+                Some(g) => output.extend([a, b, g, b, c, g, c, a, g]),
+                None => {
+                    output.extend([a, b, c]);
+                    return false;
+                }
+            },
+            (Some(ab), None, None) => output.extend([a, ab, c, ab, b, c]),
+            (None, Some(bc), None) => output.extend([b, bc, a, bc, c, a]),
+            (None, None, Some(ca)) => output.extend([c, ca, b, ca, a, b]),
+            (Some(ab), Some(bc), None) => output.extend([ab, b, bc, a, ab, bc, a, bc, c]),
+            (None, Some(bc), Some(ca)) => output.extend([bc, c, ca, b, bc, ca, b, ca, a]),
+            (Some(ab), None, Some(ca)) => output.extend([a, ab, ca, ab, b, c, ab, c, ca]),
+            (Some(ab), Some(bc), Some(ca)) => {
+                output.extend([a, ab, ca, ab, b, bc, ca, bc, c, ab, bc, ca])
+            }
+        }
+
+        true
+    }
+
+    /// Returns the vertex splitting the edge, if the edge must be split. The decision is stored in `midpoints`, so
+    /// that the other triangle sharing the edge gets the same result.
+    fn split(
+        &mut self,
+        a: u32,
+        b: u32,
+        midpoints: &mut HashMap<(u32, u32), Option<u32>>,
+    ) -> Option<u32> {
+        let key = (a.min(b), a.max(b));
+        if let Some(midpoint) = midpoints.get(&key) {
+            return *midpoint;
+        }
+
+        let vertices = [self.vertex(key.0), self.vertex(key.1)];
+        let midpoint = if Self::edge_length(&vertices[0], &vertices[1]) > self.min_edge_length {
+            self.add_vertex_if_inexact(&vertices)
+        } else {
+            None
+        };
+        midpoints.insert(key, midpoint);
+
+        midpoint
+    }
+
+    /// Returns the vertex at the centroid of the triangle, if the triangle must be split there.
+    fn centroid(&mut self, [a, b, c]: [u32; 3]) -> Option<u32> {
+        let vertices = [self.vertex(a), self.vertex(b), self.vertex(c)];
+        let max_edge_length = [(0, 1), (1, 2), (2, 0)]
+            .map(|(from, to)| Self::edge_length(&vertices[from], &vertices[to]))
+            .into_iter()
+            .fold(0.0, f32::max);
+        if max_edge_length <= self.min_edge_length {
+            return None;
+        }
+
+        self.add_vertex_if_inexact(&vertices)
+    }
+
+    fn edge_length(from: &PolyVertex, to: &PolyVertex) -> f32 {
+        (from.position[0] - to.position[0]).hypot(from.position[1] - to.position[1])
+    }
+
+    /// Adds a vertex at the average position of the given ones, if the gradient color there differs from the
+    /// average of their colors.
+    fn add_vertex_if_inexact(&mut self, vertices: &[PolyVertex]) -> Option<u32> {
+        let count = vertices.len() as f32;
+        let position =
+            [0, 1, 2].map(|i| vertices.iter().map(|v| v.position[i]).sum::<f32>() / count);
+        let interpolated =
+            [0, 1, 2, 3].map(|i| vertices.iter().map(|v| v.color[i]).sum::<f32>() / count);
+        let color = self
+            .gradient
+            .color_at(&Point2::new(position[0] as f64, position[1] as f64));
+
+        let error = (0..4)
+            .map(|i| (color[i] - interpolated[i]).abs())
+            .fold(0.0, f32::max);
+        if error <= GRADIENT_COLOR_TOLERANCE
+            || self.added_vertices >= MAX_GRADIENT_SUBDIVISION_VERTICES
+        {
+            return None;
+        }
+
+        self.added_vertices += 1;
+        let index = self.tessellation.vertices.len() as u32;
+        self.tessellation.vertices.push(PolyVertex {
+            position,
+            color,
+            normal: Default::default(),
+            norm_limit: 1.0,
+        });
+
+        Some(index)
+    }
+
+    fn vertex(&self, index: u32) -> PolyVertex {
+        self.tessellation.vertices[index as usize]
+    }
+}
+
+struct ScreenRefVertexConstructor {
+    color: [f32; 4],
+    position: [f32; 3],
+    offset: Vector2<f32>,
+}
+
+impl ScreenRefVertexConstructor {
+    fn create_vertex(&self, position: lyon::math::Point) -> PolyVertex {
+        PolyVertex {
+            position: self.position,
+            normal: [position.x + self.offset.dx(), position.y + self.offset.dy()],
+            color: self.color,
+            norm_limit: f32::MAX,
+        }
+    }
+}
+
+impl StrokeVertexConstructor<PolyVertex> for ScreenRefVertexConstructor {
+    fn new_vertex(&mut self, vertex: StrokeVertex) -> PolyVertex {
         self.create_vertex(vertex.position())
     }
 }
@@ -750,6 +2478,20 @@ pub(crate) struct PolyVertex {
     pub norm_limit: f32,
 }
 
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable, Serialize, Deserialize)]
+pub(crate) struct PatternVertex {
+    pub position: [f32; 3],
+    /// Background color drawn under the pattern.
+    pub color: [f32; 4],
+    /// Position of the vertex in the rotated pattern coordinates in map units, relative to the pattern origin.
+    pub pattern_position: [f32; 2],
+    /// Size of a pattern pixel in map units, or `0.0` if the pattern is drawn in screen space.
+    pub texel_size: f32,
+    /// Number of screen pixels a pattern pixel covers.
+    pub scale: f32,
+}
+
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable, Serialize, Deserialize)]
 pub(crate) struct PointInstance {
@@ -762,6 +2504,1210 @@ pub(crate) struct PointInstance {
 pub(crate) struct ImageVertex {
     pub position: [f32; 2],
     pub opacity: f32,
-    pub tex_coords: [f32; 2],
+    /// Homogeneous texture coordinates `[u * q, v * q, q]`.
+    pub tex_coords: [f32; 3],
     pub offset: [f32; 2],
 }
+
+/// Texture coordinates of the image corners that map the image onto an arbitrary convex quad
+/// without skewing it along the diagonal, where the quad is split into two triangles.
+///
+/// Vertices of the quad are given in the order: bottom left, top left, top right, bottom right.
+/// The texture coordinates are returned in the homogeneous form `[u * q, v * q, q]`, where `q`
+/// is calculated from the distances between the corners and the intersection of the diagonals.
+/// For parallelograms (including rotated rectangles) `q` is `1.0` for all corners.
+fn projective_tex_coords(vertices: &[Point2; 4]) -> [[f32; 3]; 4] {
+    const UV: [[f32; 2]; 4] = [[0.0, 1.0], [0.0, 0.0], [1.0, 0.0], [1.0, 1.0]];
+
+    let q = diagonals_intersection(vertices)
+        .map(|center| {
+            let d = vertices.map(|v| v.distance(&center));
+            [0, 1, 2, 3].map(|i| {
+                let opposite = d[(i + 2) % 4];
+                // Halved so that parallelograms, whose diagonals bisect each other, get `q == 1`.
+                if opposite > 0.0 {
+                    (d[i] + opposite) / (2.0 * opposite)
+                } else {
+                    1.0
+                }
+            })
+        })
+        .unwrap_or([1.0; 4]);
+
+    [0, 1, 2, 3].map(|i| {
+        let q = q[i] as f32;
+        [UV[i][0] * q, UV[i][1] * q, q]
+    })
+}
+
+/// Maps homogeneous texture coordinates of the whole image to the `source` region of it, given in
+/// the fractions of the image size with the origin in the top left corner.
+fn region_tex_coords(tex_coords: [[f32; 3]; 4], source: Rect) -> [[f32; 3]; 4] {
+    let (x_min, y_min) = (source.x_min() as f32, source.y_min() as f32);
+    let (width, height) = (source.width() as f32, source.height() as f32);
+
+    tex_coords.map(|[u, v, q]| [x_min * q + u * width, y_min * q + v * height, q])
+}
+
+fn diagonals_intersection(vertices: &[Point2; 4]) -> Option<Point2> {
+    let d1 = vertices[2] - vertices[0];
+    let d2 = vertices[3] - vertices[1];
+    let denominator = d1.dx() * d2.dy() - d1.dy() * d2.dx();
+    if denominator.abs() < f64::EPSILON {
+        return None;
+    }
+
+    let between = vertices[1] - vertices[0];
+    let t = (between.dx() * d2.dy() - between.dy() * d2.dx()) / denominator;
+    if !(0.0..=1.0).contains(&t) {
+        // The quad is not convex
+        return None;
+    }
+
+    Some(vertices[0] + d1 * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use galileo_types::cartesian::{Point3, Size};
+
+    use super::*;
+    use crate::render::{FillRule, GradientFill, GradientStop, LineCap};
+
+    /// Interpolates homogeneous texture coordinates linearly between two vertices, as the GPU
+    /// does, and returns the resulting `[u, v]`.
+    fn interpolate(a: [f32; 3], b: [f32; 3], t: f32) -> [f32; 2] {
+        let c = [0, 1, 2].map(|i| a[i] + (b[i] - a[i]) * t);
+        [c[0] / c[2], c[1] / c[2]]
+    }
+
+    fn assert_placement(placement: LinePlacement, x: f32, y: f32, angle: f32) {
+        assert_relative_eq!(placement.position.x(), x, epsilon = 1e-4);
+        assert_relative_eq!(placement.position.y(), y, epsilon = 1e-4);
+        assert_relative_eq!(placement.angle, angle, epsilon = 1e-4);
+    }
+
+    #[test]
+    fn glyphs_follow_line() {
+        let line = [
+            Point2::new(0.0, 0.0),
+            Point2::new(10.0, 0.0),
+            Point2::new(10.0, 10.0),
+        ];
+        let placements =
+            place_along_line(&line, &[4.0, 4.0, 4.0], 0.0).expect("line is long enough");
+
+        assert_eq!(placements.len(), 3);
+        assert_placement(placements[0], 6.0, 0.0, 0.0);
+        assert_placement(placements[1], 10.0, 0.0, 0.0);
+        assert_placement(placements[2], 10.0, 4.0, std::f32::consts::FRAC_PI_2);
+    }
+
+    #[test]
+    fn right_to_left_line_is_reversed() {
+        let line = [Point2::new(10.0, 0.0), Point2::new(0.0, 0.0)];
+        let placements = place_along_line(&line, &[2.0, 2.0], 0.0).expect("line is long enough");
+
+        assert_placement(placements[0], 4.0, 0.0, 0.0);
+        assert_placement(placements[1], 6.0, 0.0, 0.0);
+    }
+
+    #[test]
+    fn letter_spacing_separates_glyphs_on_line() {
+        let line = [Point2::new(0.0, 0.0), Point2::new(10.0, 0.0)];
+        let placements = place_along_line(&line, &[2.0, 2.0], 2.0).expect("line is long enough");
+
+        assert_placement(placements[0], 3.0, 0.0, 0.0);
+        assert_placement(placements[1], 7.0, 0.0, 0.0);
+
+        assert!(place_along_line(&line, &[4.0, 4.0], 3.0).is_none());
+    }
+
+    #[test]
+    fn text_longer_than_line_is_skipped() {
+        let line = [Point2::new(0.0, 0.0), Point2::new(5.0, 0.0)];
+        assert!(place_along_line(&line, &[2.0, 2.0, 2.0], 0.0).is_none());
+        assert!(place_along_line(&line[..1], &[], 0.0).is_none());
+    }
+
+    #[test]
+    fn arrows_are_placed_with_spacing_along_line() {
+        let line = [
+            Point2::new(0.0, 0.0),
+            Point2::new(10.0, 0.0),
+            Point2::new(10.0, 10.0),
+        ];
+        let placements = place_arrows(&line, 4.0, 0.0);
+
+        assert_eq!(placements.len(), 5);
+        assert_placement(placements[0], 2.0, 0.0, 0.0);
+        assert_placement(placements[2], 10.0, 0.0, 0.0);
+        assert_placement(placements[3], 10.0, 4.0, std::f32::consts::FRAC_PI_2);
+        assert_placement(placements[4], 10.0, 8.0, std::f32::consts::FRAC_PI_2);
+    }
+
+    #[test]
+    fn arrows_do_not_reach_past_line_ends() {
+        let line = [Point2::new(0.0, 0.0), Point2::new(10.0, 0.0)];
+        let placements = place_arrows(&line, 4.0, 3.0);
+
+        assert_eq!(placements.len(), 1);
+        assert_placement(placements[0], 6.0, 0.0, 0.0);
+    }
+
+    #[test]
+    fn round_cap_allows_arrows_closer_to_line_end() {
+        let line = galileo_types::impls::Contour::open(vec![
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(10.0, 0.0, 0.0),
+        ]);
+        let arrows = ArrowPaint::new(Color::RED, 6.0, 4.0);
+        let mut paint = LinePaint {
+            color: Color::BLACK,
+            width: 6.0,
+            offset: 0.0,
+            line_cap: LineCap::Butt,
+            dash_pattern: None,
+            dash_offset: 0.0,
+        };
+
+        let mut butt = WorldRenderSet::new();
+        butt.add_line_arrows(&line, &paint, &arrows, 1.0);
+        assert_eq!(butt.poly_tessellation.indices.len(), 3);
+
+        paint.line_cap = LineCap::Round;
+        let mut round = WorldRenderSet::new();
+        round.add_line_arrows(&line, &paint, &arrows, 1.0);
+        assert_eq!(round.poly_tessellation.indices.len(), 9);
+    }
+
+    #[test]
+    fn primitives_keep_z_coordinate() {
+        let z = 42.0;
+        let mut set = WorldRenderSet::new();
+
+        set.add_point(&Point3::new(1.0, 2.0, z), &PointPaint::dot(Color::BLACK));
+        assert_eq!(set.points[0].position[2], z as f32);
+
+        let line = galileo_types::impls::Contour::open(vec![
+            Point3::new(0.0, 0.0, z),
+            Point3::new(10.0, 10.0, z),
+        ]);
+        set.add_line(
+            &line,
+            &LinePaint {
+                color: Color::BLACK,
+                width: 2.0,
+                offset: 0.0,
+                line_cap: LineCap::Butt,
+                dash_pattern: None,
+                dash_offset: 0.0,
+            },
+            1.0,
+        );
+
+        let square = galileo_types::impls::Polygon::from(ClosedContour::new(vec![
+            Point3::new(0.0, 0.0, z),
+            Point3::new(0.0, 10.0, z),
+            Point3::new(10.0, 10.0, z),
+            Point3::new(10.0, 0.0, z),
+        ]));
+        set.add_polygon(&square, &PolygonPaint::new(Color::BLACK), 1.0);
+
+        assert!(!set.poly_tessellation.vertices.is_empty());
+        for vertex in &set.poly_tessellation.vertices {
+            assert_eq!(vertex.position[2], z as f32);
+        }
+    }
+
+    #[test]
+    fn clear_keeps_capacity() {
+        let mut set = WorldRenderSet::new();
+        let square = galileo_types::impls::Polygon::from(ClosedContour::new(vec![
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(0.0, 10.0, 0.0),
+            Point3::new(10.0, 10.0, 0.0),
+            Point3::new(10.0, 0.0, 0.0),
+        ]));
+        set.add_polygon(&square, &PolygonPaint::new(Color::BLACK), 1.0);
+        set.add_point(&Point3::new(1.0, 2.0, 0.0), &PointPaint::dot(Color::BLACK));
+
+        let vertex_capacity = set.poly_tessellation.vertices.capacity();
+        let index_capacity = set.poly_tessellation.indices.capacity();
+        let point_capacity = set.points.capacity();
+
+        set.clear();
+
+        assert!(set.poly_tessellation.vertices.is_empty());
+        assert!(set.poly_tessellation.indices.is_empty());
+        assert!(set.points.is_empty());
+        assert_eq!(set.approx_buffer_size(), 0);
+        assert_eq!(set.tessellation_to_obj(), "");
+
+        assert_eq!(set.poly_tessellation.vertices.capacity(), vertex_capacity);
+        assert_eq!(set.poly_tessellation.indices.capacity(), index_capacity);
+        assert_eq!(set.points.capacity(), point_capacity);
+    }
+
+    #[test]
+    fn append_shifts_indices() {
+        let square = |offset: f64| {
+            galileo_types::impls::Polygon::from(ClosedContour::new(vec![
+                Point3::new(offset, 0.0, 0.0),
+                Point3::new(offset, 10.0, 0.0),
+                Point3::new(offset + 10.0, 10.0, 0.0),
+                Point3::new(offset + 10.0, 0.0, 0.0),
+            ]))
+        };
+        let image = |size: u32| {
+            DecodedImage::from_raw(vec![0; (size * size * 4) as usize], Size::new(size, size))
+                .expect("valid image")
+        };
+        let image_vertices = [
+            Point2::new(0.0, 0.0),
+            Point2::new(0.0, 1.0),
+            Point2::new(1.0, 1.0),
+            Point2::new(1.0, 0.0),
+        ];
+
+        let mut first = WorldRenderSet::new();
+        first.add_polygon(&square(0.0), &PolygonPaint::new(Color::BLACK), 1.0);
+        first.add_image_owned(
+            image(1),
+            image_vertices,
+            ImagePaint {
+                opacity: 255,
+                sampling: ImageSampling::Linear,
+            },
+        );
+
+        let mut second = WorldRenderSet::new();
+        second.add_polygon(&square(100.0), &PolygonPaint::new(Color::BLACK), 1.0);
+        second.add_image_owned(
+            image(2),
+            image_vertices,
+            ImagePaint {
+                opacity: 255,
+                sampling: ImageSampling::Linear,
+            },
+        );
+        second.add_point(&Point3::new(1.0, 2.0, 0.0), &PointPaint::dot(Color::BLACK));
+
+        let vertex_count = first.poly_tessellation.vertices.len();
+        let index_count = first.poly_tessellation.indices.len();
+        let expected_size = first.approx_buffer_size() + second.approx_buffer_size();
+        let second_indices = second.poly_tessellation.indices.clone();
+
+        first.append(second);
+
+        assert_eq!(first.poly_tessellation.vertices.len(), vertex_count * 2);
+        assert_eq!(
+            &first.poly_tessellation.indices[index_count..],
+            &second_indices
+                .iter()
+                .map(|i| i + vertex_count as u32)
+                .collect::<Vec<_>>()[..]
+        );
+        for index in &first.poly_tessellation.indices[index_count..] {
+            let vertex = &first.poly_tessellation.vertices[*index as usize];
+            assert!(vertex.position[0] >= 100.0);
+        }
+
+        assert_eq!(first.image_store.len(), 2);
+        assert_eq!(first.images[1].store_index, 1);
+        assert_eq!(first.image_store[1].size().width(), 2);
+        assert_eq!(first.points.len(), 1);
+        assert_eq!(first.approx_buffer_size(), expected_size);
+    }
+
+    #[test]
+    fn world_bounds_include_all_primitives() {
+        let mut set = WorldRenderSet::new();
+        assert!(set.world_bounds().is_none());
+
+        let square = galileo_types::impls::Polygon::from(ClosedContour::new(vec![
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(0.0, 10.0, 0.0),
+            Point3::new(10.0, 10.0, 0.0),
+            Point3::new(10.0, 0.0, 0.0),
+        ]));
+        set.add_polygon(&square, &PolygonPaint::new(Color::BLACK), 1.0);
+        set.add_point(
+            &Point3::new(-5.0, 20.0, 0.0),
+            &PointPaint::dot(Color::BLACK),
+        );
+
+        let bounds = set.world_bounds().expect("set is not empty");
+        assert_eq!(bounds, Rect::new(-5.0, 0.0, 10.0, 20.0));
+    }
+
+    #[test]
+    fn removed_primitive_frees_buffers() {
+        let square = |offset: f64| {
+            galileo_types::impls::Polygon::from(ClosedContour::new(vec![
+                Point3::new(offset, 0.0, 0.0),
+                Point3::new(offset, 10.0, 0.0),
+                Point3::new(offset + 10.0, 10.0, 0.0),
+                Point3::new(offset + 10.0, 0.0, 0.0),
+            ]))
+        };
+        let paint = PolygonPaint::new(Color::BLACK);
+
+        let mut set = WorldRenderSet::new();
+        let add = |set: &mut WorldRenderSet, index: usize, offset: f64, z_index: i32| {
+            set.set_z_index(z_index);
+            let mark = set.mark();
+            set.add_polygon(&square(offset), &paint, 1.0);
+            let record = set.record_since(mark, PrimitiveKind::Other);
+            set.set_primitive(index, record);
+        };
+        add(&mut set, 0, 0.0, 1);
+        add(&mut set, 1, 100.0, 0);
+        add(&mut set, 2, 200.0, 1);
+        set.set_z_index(0);
+        let single_size = set.approx_buffer_size() / 3;
+
+        set.remove_primitive(0);
+        assert_eq!(set.world_bounds(), Some(Rect::new(100.0, 0.0, 210.0, 10.0)));
+        assert_eq!(set.approx_buffer_size(), single_size * 2);
+
+        let order = set.draw_order();
+        assert_eq!(order.indices.len(), set.poly_tessellation.indices.len());
+        let per_square = order.indices.len() / 2;
+        let min_x = |indices: &[u32]| {
+            indices
+                .iter()
+                .map(|i| set.poly_tessellation.vertices[*i as usize].position[0])
+                .fold(f32::MAX, f32::min)
+        };
+        assert_eq!(min_x(&order.indices[..per_square]), 100.0);
+        assert_eq!(min_x(&order.indices[per_square..]), 200.0);
+
+        set.remove_primitive(2);
+        assert_eq!(set.world_bounds(), Some(Rect::new(100.0, 0.0, 110.0, 10.0)));
+        set.remove_primitive(1);
+        assert!(set.world_bounds().is_none());
+        assert!(set.poly_tessellation.vertices.is_empty());
+        assert_eq!(set.approx_buffer_size(), 0);
+    }
+
+    #[test]
+    fn multi_polygon_matches_separate_polygons() {
+        let square = |offset: f64| {
+            galileo_types::impls::Polygon::from(ClosedContour::new(vec![
+                Point3::new(offset, 0.0, 0.0),
+                Point3::new(offset, 10.0, 0.0),
+                Point3::new(offset + 10.0, 10.0, 0.0),
+                Point3::new(offset + 10.0, 0.0, 0.0),
+            ]))
+        };
+        let paint = PolygonPaint::new(Color::BLACK);
+
+        let mut separate = WorldRenderSet::new();
+        separate.add_polygon(&square(0.0), &paint, 1.0);
+        separate.add_polygon(&square(100.0), &paint, 1.0);
+
+        let mut multi = WorldRenderSet::new();
+        let multi_polygon =
+            galileo_types::impls::MultiPolygon::from(vec![square(0.0), square(100.0)]);
+        multi.add_multi_polygon(&multi_polygon, &paint, 1.0);
+
+        assert_eq!(multi.tessellation_to_obj(), separate.tessellation_to_obj());
+        assert_eq!(multi.approx_buffer_size(), separate.approx_buffer_size());
+    }
+
+    /// Returns dash vertex positions rounded to whole pixels.
+    fn dash_positions(dashes: &[Vec<PathVertex>]) -> Vec<Vec<[f32; 2]>> {
+        dashes
+            .iter()
+            .map(|dash| {
+                dash.iter()
+                    .map(|v| [v.position.x.round(), v.position.y.round()])
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn dashes_split_straight_line() {
+        let line = [
+            PathVertex::new(0.0, 0.0, 0.0, [0.0; 4]),
+            PathVertex::new(35.0, 0.0, 0.0, [0.0; 4]),
+        ];
+        let state = DashState::new(&[10.0, 5.0], 0.0).expect("valid pattern");
+
+        assert_eq!(
+            dash_positions(&split_into_dashes(&line, state)),
+            vec![
+                vec![[0.0, 0.0], [10.0, 0.0]],
+                vec![[15.0, 0.0], [25.0, 0.0]],
+                vec![[30.0, 0.0], [35.0, 0.0]],
+            ]
+        );
+    }
+
+    #[test]
+    fn dash_continues_around_corner() {
+        let line = [
+            PathVertex::new(0.0, 0.0, 0.0, [0.0; 4]),
+            PathVertex::new(5.0, 0.0, 0.0, [0.0; 4]),
+            PathVertex::new(5.0, 20.0, 0.0, [0.0; 4]),
+        ];
+        let state = DashState::new(&[10.0, 5.0], 0.0).expect("valid pattern");
+
+        assert_eq!(
+            dash_positions(&split_into_dashes(&line, state)),
+            vec![
+                vec![[0.0, 0.0], [5.0, 0.0], [5.0, 5.0]],
+                vec![[5.0, 10.0], [5.0, 20.0]],
+            ]
+        );
+    }
+
+    #[test]
+    fn dash_offset_and_odd_patterns() {
+        let line = [
+            PathVertex::new(0.0, 0.0, 0.0, [0.0; 4]),
+            PathVertex::new(20.0, 0.0, 0.0, [0.0; 4]),
+        ];
+
+        let state = DashState::new(&[10.0, 5.0], 12.0).expect("valid pattern");
+        assert_eq!(
+            dash_positions(&split_into_dashes(&line, state)),
+            vec![
+                vec![[3.0, 0.0], [13.0, 0.0]],
+                vec![[18.0, 0.0], [20.0, 0.0]]
+            ]
+        );
+
+        let state = DashState::new(&[5.0], 0.0).expect("valid pattern");
+        assert_eq!(
+            dash_positions(&split_into_dashes(&line, state)),
+            vec![vec![[0.0, 0.0], [5.0, 0.0]], vec![[10.0, 0.0], [15.0, 0.0]]]
+        );
+
+        assert!(DashState::new(&[], 0.0).is_none());
+        assert!(DashState::new(&[0.0, 0.0], 0.0).is_none());
+        assert!(DashState::new(&[5.0, -1.0], 0.0).is_none());
+    }
+
+    #[test]
+    fn dashed_line_has_more_vertices_than_solid() {
+        let line = galileo_types::impls::Contour::open(vec![
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(100.0, 0.0, 0.0),
+        ]);
+        let mut paint = LinePaint {
+            color: Color::BLACK,
+            width: 2.0,
+            offset: 0.0,
+            line_cap: LineCap::Butt,
+            dash_pattern: None,
+            dash_offset: 0.0,
+        };
+
+        let mut solid = WorldRenderSet::new();
+        solid.add_line(&line, &paint, 1.0);
+
+        paint.dash_pattern = Some(vec![10.0, 10.0]);
+        let mut dashed = WorldRenderSet::new();
+        dashed.add_line(&line, &paint, 1.0);
+
+        assert_eq!(
+            dashed.poly_tessellation.vertices.len(),
+            solid.poly_tessellation.vertices.len() * 5
+        );
+    }
+
+    #[test]
+    fn line_norm_limit_is_twice_the_previous_segment() {
+        let line = galileo_types::impls::Contour::open(vec![
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(100.0, 0.0, 0.0),
+            Point3::new(100.0, 50.0, 0.0),
+        ]);
+        let paint = LinePaint {
+            color: Color::BLACK,
+            width: 2.0,
+            offset: 0.0,
+            line_cap: LineCap::Butt,
+            dash_pattern: None,
+            dash_offset: 0.0,
+        };
+
+        let mut set = WorldRenderSet::new();
+        set.add_line(&line, &paint, 2.0);
+
+        let norm_limits_at = |x: f32, y: f32| {
+            let limits: Vec<_> = set
+                .poly_tessellation
+                .vertices
+                .iter()
+                .filter(|v| (v.position[0] - x).abs() < 1e-3 && (v.position[1] - y).abs() < 1e-3)
+                .map(|v| v.norm_limit)
+                .collect();
+            assert!(!limits.is_empty());
+            limits
+        };
+
+        assert!(norm_limits_at(0.0, 0.0).iter().all(|v| *v == f32::MAX));
+        for limit in norm_limits_at(100.0, 0.0) {
+            assert_relative_eq!(limit, 200.0, epsilon = 1e-3);
+        }
+        for limit in norm_limits_at(100.0, 50.0) {
+            assert_relative_eq!(limit, 100.0, epsilon = 1e-3);
+        }
+    }
+
+    #[test]
+    fn line_vertex_colors_are_interpolated() {
+        let line = galileo_types::impls::Contour::open(vec![
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(100.0, 0.0, 0.0),
+        ]);
+        let paint = LinePaint {
+            color: Color::BLACK,
+            width: 2.0,
+            offset: 0.0,
+            line_cap: LineCap::Butt,
+            dash_pattern: Some(vec![20.0, 40.0]),
+            dash_offset: 0.0,
+        };
+
+        let mut set = WorldRenderSet::new();
+        set.add_line_with_colors(&line, &paint, &[Color::RED, Color::BLUE], 1.0);
+
+        let color_at = |x: f32| {
+            set.poly_tessellation
+                .vertices
+                .iter()
+                .find(|v| (v.position[0] - x).abs() < 1e-3)
+                .expect("vertex exists")
+                .color
+        };
+        assert_eq!(color_at(0.0), Color::RED.to_f32_array());
+        assert_relative_eq!(color_at(60.0)[0], 0.4, epsilon = 1e-5);
+        assert_relative_eq!(color_at(60.0)[2], 0.6, epsilon = 1e-5);
+
+        let mut set = WorldRenderSet::new();
+        set.add_line_with_colors(&line, &paint, &[Color::RED], 1.0);
+        for vertex in &set.poly_tessellation.vertices {
+            assert_eq!(vertex.color, Color::BLACK.to_f32_array());
+        }
+    }
+
+    #[test]
+    fn primitives_are_ordered_by_z_index() {
+        let square = |offset: f64| {
+            galileo_types::impls::Polygon::from(ClosedContour::new(vec![
+                Point3::new(offset, 0.0, 0.0),
+                Point3::new(offset, 10.0, 0.0),
+                Point3::new(offset + 10.0, 10.0, 0.0),
+                Point3::new(offset + 10.0, 0.0, 0.0),
+            ]))
+        };
+        let paint = PolygonPaint::new(Color::BLACK);
+        let min_x = |set: &WorldRenderSet, indices: &[u32]| {
+            indices
+                .iter()
+                .map(|i| set.poly_tessellation.vertices[*i as usize].position[0])
+                .fold(f32::MAX, f32::min)
+        };
+
+        let mut set = WorldRenderSet::new();
+        set.set_z_index(2);
+        set.add_polygon(&square(0.0), &paint, 1.0);
+        set.set_z_index(0);
+        set.add_polygon(&square(100.0), &paint, 1.0);
+        set.set_z_index(1);
+        set.add_polygon(&square(200.0), &paint, 1.0);
+        set.set_z_index(0);
+
+        let order = set.draw_order();
+        let indices = &order.indices;
+        assert_eq!(indices.len(), set.poly_tessellation.indices.len());
+        let per_square = indices.len() / 3;
+        assert_eq!(min_x(&set, &indices[..per_square]), 100.0);
+        assert_eq!(min_x(&set, &indices[per_square..per_square * 2]), 200.0);
+        assert_eq!(min_x(&set, &indices[per_square * 2..]), 0.0);
+
+        set.add_point(&Point3::new(1.0, 0.0, 0.0), &PointPaint::dot(Color::BLACK));
+        set.set_z_index(-1);
+        set.add_point(&Point3::new(2.0, 0.0, 0.0), &PointPaint::dot(Color::BLACK));
+        set.set_z_index(0);
+        let points = set.draw_order().points;
+        assert_eq!(points[0].position[0], 2.0);
+        assert_eq!(points[1].position[0], 1.0);
+    }
+
+    #[test]
+    fn z_index_orders_primitives_across_pipelines() {
+        let square = galileo_types::impls::Polygon::from(ClosedContour::new(vec![
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(0.0, 10.0, 0.0),
+            Point3::new(10.0, 10.0, 0.0),
+            Point3::new(10.0, 0.0, 0.0),
+        ]));
+        let paint = PolygonPaint::new(Color::BLACK);
+        let dot = PointPaint::dot(Color::BLACK);
+
+        let mut set = WorldRenderSet::new();
+        set.add_polygon(&square, &paint, 1.0);
+        set.add_point(&Point3::new(1.0, 0.0, 0.0), &dot);
+        set.add_polygon(&square, &paint, 1.0);
+        set.set_z_index(-1);
+        set.add_point(&Point3::new(2.0, 0.0, 0.0), &dot);
+        set.set_z_index(0);
+
+        let order = set.draw_order();
+        let per_square = set.poly_tessellation.indices.len() as u32 / 2;
+        assert_eq!(
+            order.commands,
+            vec![
+                DrawCommand::Points(0..1),
+                DrawCommand::Polygons(0..per_square),
+                DrawCommand::Points(1..2),
+                DrawCommand::Polygons(per_square..per_square * 2),
+            ]
+        );
+        assert_eq!(order.points[0].position[0], 2.0);
+    }
+
+    #[test]
+    fn z_index_without_primitives_is_ignored() {
+        let mut set = WorldRenderSet::new();
+        set.set_z_index(5);
+        set.set_z_index(0);
+        assert!(set.draw_segments.is_empty());
+    }
+
+    #[test]
+    fn label_background_is_screen_referenced() {
+        let mut set = WorldRenderSet::new();
+        let style = crate::layer::feature_layer::symbol::TextMarkerSymbol::new().text_style;
+        let position = Point3::new(100.0, 200.0, 0.0);
+        let background = LabelBackground::new(Color::WHITE).with_padding(2.0);
+
+        set.add_label_background(
+            &position,
+            Size::new(20.0, 10.0),
+            &style,
+            Vector2::default(),
+            &background,
+        );
+        let fill_vertices = set.poly_tessellation.vertices.len();
+        assert!(fill_vertices > 0);
+        for vertex in &set.poly_tessellation.vertices {
+            assert_eq!(vertex.position, [100.0, 200.0, 0.0]);
+            assert!(vertex.normal[0].abs() <= 12.0 && vertex.normal[1].abs() <= 7.0);
+        }
+
+        let mut set = WorldRenderSet::new();
+        set.add_label_background(
+            &position,
+            Size::new(20.0, 10.0),
+            &style,
+            Vector2::default(),
+            &background.with_border(Color::RED, 2.0),
+        );
+        assert!(set.poly_tessellation.vertices.len() > fill_vertices);
+    }
+
+    #[test]
+    fn square_tessellation_to_obj() {
+        let mut set = WorldRenderSet::new();
+        let square = galileo_types::impls::Polygon::from(ClosedContour::new(vec![
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(0.0, 10.0, 0.0),
+            Point3::new(10.0, 10.0, 0.0),
+            Point3::new(10.0, 0.0, 0.0),
+        ]));
+        set.add_polygon(&square, &PolygonPaint::new(Color::BLACK), 1.0);
+
+        let obj = set.tessellation_to_obj();
+        let vertices: Vec<&str> = obj.lines().filter(|l| l.starts_with("v ")).collect();
+        let faces: Vec<Vec<usize>> = obj
+            .lines()
+            .filter_map(|l| l.strip_prefix("f "))
+            .map(|f| {
+                f.split(' ')
+                    .map(|i| i.parse().expect("valid index"))
+                    .collect()
+            })
+            .collect();
+
+        assert_eq!(faces.len(), 2);
+        for corner in ["v 0 0 0", "v 0 10 0", "v 10 10 0", "v 10 0 0"] {
+            assert!(vertices.contains(&corner), "{corner} is missing");
+        }
+        for face in faces {
+            assert_eq!(face.len(), 3);
+            assert!(face.iter().all(|i| (1..=vertices.len()).contains(i)));
+        }
+    }
+
+    fn gradient_square(gradient: GradientFill) -> WorldRenderSet {
+        let mut set = WorldRenderSet::new();
+        let square = galileo_types::impls::Polygon::from(ClosedContour::new(vec![
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(0.0, 10.0, 0.0),
+            Point3::new(10.0, 10.0, 0.0),
+            Point3::new(10.0, 0.0, 0.0),
+        ]));
+        set.add_polygon(
+            &square,
+            &PolygonPaint::new(Color::BLACK).with_gradient(gradient),
+            1.0,
+        );
+
+        set
+    }
+
+    /// Interpolates the vertex colors of the triangle containing the point, as the GPU does.
+    fn rendered_color_at(set: &WorldRenderSet, x: f32, y: f32) -> [f32; 4] {
+        let tessellation = &set.poly_tessellation;
+        for triangle in tessellation.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| tessellation.vertices[triangle[i] as usize]);
+            let det = (b.position[1] - c.position[1]) * (a.position[0] - c.position[0])
+                + (c.position[0] - b.position[0]) * (a.position[1] - c.position[1]);
+            let wa = ((b.position[1] - c.position[1]) * (x - c.position[0])
+                + (c.position[0] - b.position[0]) * (y - c.position[1]))
+                / det;
+            let wb = ((c.position[1] - a.position[1]) * (x - c.position[0])
+                + (a.position[0] - c.position[0]) * (y - c.position[1]))
+                / det;
+            let wc = 1.0 - wa - wb;
+            if [wa, wb, wc].iter().all(|w| *w >= -1e-6) {
+                return [0, 1, 2, 3].map(|i| a.color[i] * wa + b.color[i] * wb + c.color[i] * wc);
+            }
+        }
+
+        panic!("point ({x}, {y}) is not covered by the tessellation");
+    }
+
+    fn assert_color_eq(actual: [f32; 4], expected: [f32; 4]) {
+        for i in 0..4 {
+            assert!(
+                (actual[i] - expected[i]).abs() <= GRADIENT_COLOR_TOLERANCE * 2.0,
+                "{actual:?} != {expected:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn radial_gradient_is_exact_inside_polygon() {
+        let gradient = GradientFill::radial(
+            Point2::new(0.5, 0.5),
+            vec![
+                GradientStop::new(0.0, Color::RED),
+                GradientStop::new(1.0, Color::BLUE),
+            ],
+        );
+        let set = gradient_square(gradient.clone());
+        let vertices = [
+            Point2::new(0.0, 0.0),
+            Point2::new(0.0, 10.0),
+            Point2::new(10.0, 10.0),
+            Point2::new(10.0, 0.0),
+        ];
+        let resolved = gradient.resolve(&vertices).expect("polygon is not empty");
+
+        for (x, y) in [(5.0, 5.0), (2.5, 3.0), (7.3, 1.1), (9.0, 6.0)] {
+            assert_color_eq(
+                rendered_color_at(&set, x, y),
+                resolved.color_at(&Point2::new(x as f64, y as f64)),
+            );
+        }
+    }
+
+    #[test]
+    fn gradient_subdivision_does_not_create_t_junctions() {
+        let set = gradient_square(GradientFill::radial(
+            Point2::new(0.3, 0.6),
+            vec![
+                GradientStop::new(0.0, Color::RED),
+                GradientStop::new(0.4, Color::GREEN),
+                GradientStop::new(1.0, Color::BLUE),
+            ],
+        ));
+        let tessellation = &set.poly_tessellation;
+        assert!(tessellation.vertices.len() > 4);
+
+        let mut edges = HashMap::new();
+        for triangle in tessellation.indices.chunks_exact(3) {
+            for (a, b) in [(0, 1), (1, 2), (2, 0)] {
+                let key = (triangle[a].min(triangle[b]), triangle[a].max(triangle[b]));
+                *edges.entry(key).or_insert(0) += 1;
+            }
+        }
+
+        // An edge that is used by only one triangle must be on the border of the square, otherwise
+        // the neighbouring triangle split it.
+        let position = |index: u32| tessellation.vertices[index as usize].position;
+        for ((a, b), count) in edges {
+            assert!(count <= 2);
+            if count == 1 {
+                let [a, b] = [position(a), position(b)];
+                let is_on_border = (0..2).any(|i| a[i] == b[i] && (a[i] == 0.0 || a[i] == 10.0));
+                assert!(
+                    is_on_border,
+                    "inner edge {a:?} - {b:?} is used by one triangle"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn linear_gradient_stops_are_exact_inside_polygon() {
+        let gradient = GradientFill::linear(
+            0.0,
+            vec![
+                GradientStop::new(0.0, Color::RED),
+                GradientStop::new(0.5, Color::GREEN),
+                GradientStop::new(1.0, Color::BLUE),
+            ],
+        );
+        let set = gradient_square(gradient);
+
+        assert_color_eq(
+            rendered_color_at(&set, 5.0, 4.0),
+            Color::GREEN.to_f32_array(),
+        );
+        assert_color_eq(rendered_color_at(&set, 2.5, 7.0), [0.5, 0.5, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn two_stop_gradient_colors_opposite_ends() {
+        let mut set = WorldRenderSet::new();
+        let square = galileo_types::impls::Polygon::from(ClosedContour::new(vec![
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(0.0, 10.0, 0.0),
+            Point3::new(10.0, 10.0, 0.0),
+            Point3::new(10.0, 0.0, 0.0),
+        ]));
+        let gradient = GradientFill::linear(
+            0.0,
+            vec![
+                GradientStop::new(0.0, Color::RED),
+                GradientStop::new(1.0, Color::BLUE),
+            ],
+        );
+        set.add_polygon(
+            &square,
+            &PolygonPaint::new(Color::BLACK).with_gradient(gradient),
+            1.0,
+        );
+
+        let vertices = &set.poly_tessellation.vertices;
+        assert!(!vertices.is_empty());
+        for vertex in vertices {
+            let expected = if vertex.position[0] == 0.0 {
+                Color::RED
+            } else {
+                Color::BLUE
+            };
+            assert_eq!(vertex.color, expected.to_f32_array());
+        }
+    }
+
+    fn pattern(space: PatternSpace) -> FillPattern {
+        let image =
+            DecodedImage::from_raw(vec![255; 4 * 4 * 2], Size::new(4, 2)).expect("valid image");
+        FillPattern::new(Arc::new(image))
+            .with_space(space)
+            .with_scale(2.0)
+    }
+
+    #[test]
+    fn pattern_polygons_are_grouped_by_image() {
+        let mut set = WorldRenderSet::new();
+        let pattern = pattern(PatternSpace::Screen);
+        let paint = PolygonPaint::new(Color::TRANSPARENT).with_pattern(pattern.clone());
+        set.add_polygon(
+            &galileo_types::impls::Polygon::from(ring(0.0, 10.0)),
+            &paint,
+            1.0,
+        );
+        set.add_polygon(
+            &galileo_types::impls::Polygon::from(ring(20.0, 30.0)),
+            &paint,
+            1.0,
+        );
+
+        assert!(set.poly_tessellation.vertices.is_empty());
+        assert_eq!(set.image_store.len(), 1);
+        assert_eq!(set.pattern_fills.len(), 1);
+
+        let tessellation = &set.pattern_fills[0].tessellation;
+        assert_eq!(tessellation.vertices.len(), 8);
+        assert_eq!(tessellation.indices.len(), 12);
+        assert!(tessellation.indices[6..].iter().all(|&i| i >= 4));
+        for vertex in &tessellation.vertices {
+            assert_eq!(vertex.texel_size, 0.0);
+            assert_eq!(vertex.scale, 2.0);
+        }
+    }
+
+    #[test]
+    fn pattern_polygons_are_drawn_in_order_with_other_polygons() {
+        let square = galileo_types::impls::Polygon::from(ring(0.0, 10.0));
+        let solid = PolygonPaint::new(Color::BLACK);
+        let patterned =
+            PolygonPaint::new(Color::TRANSPARENT).with_pattern(pattern(PatternSpace::Screen));
+
+        let mut set = WorldRenderSet::new();
+        set.add_polygon(&square, &solid, 1.0);
+        set.add_polygon(&square, &patterned, 1.0);
+        set.set_z_index(-1);
+        set.add_polygon(&square, &solid, 1.0);
+        set.set_z_index(0);
+        set.add_polygon(&square, &solid, 1.0);
+
+        let per_square = set.poly_tessellation.indices.len() as u32 / 3;
+        let pattern_indices = set.pattern_fills[0].tessellation.indices.len() as u32;
+        assert_eq!(
+            set.draw_order().commands,
+            vec![
+                DrawCommand::Polygons(0..per_square * 2),
+                DrawCommand::Pattern {
+                    fill: 0,
+                    indices: 0..pattern_indices,
+                },
+                DrawCommand::Polygons(per_square * 2..per_square * 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn world_pattern_is_aligned_to_period() {
+        let mut set = WorldRenderSet::new();
+        let paint =
+            PolygonPaint::new(Color::TRANSPARENT).with_pattern(pattern(PatternSpace::World));
+        // Pattern period is 4 * 2 * 0.5 = 4 map units along x and 2 map units along y.
+        set.add_polygon(
+            &galileo_types::impls::Polygon::from(ring(12.5, 13.5)),
+            &paint,
+            0.5,
+        );
+
+        for vertex in &set.pattern_fills[0].tessellation.vertices {
+            assert_eq!(vertex.texel_size, 1.0);
+            assert_relative_eq!(vertex.pattern_position[0], vertex.position[0] - 12.0);
+            assert_relative_eq!(vertex.pattern_position[1], vertex.position[1] - 12.0);
+        }
+    }
+
+    #[test]
+    fn pattern_position_is_rotated() {
+        let mut set = WorldRenderSet::new();
+        let paint = PolygonPaint::new(Color::TRANSPARENT)
+            .with_pattern(pattern(PatternSpace::Screen).with_rotation(std::f32::consts::FRAC_PI_2));
+        set.add_polygon(
+            &galileo_types::impls::Polygon::from(ring(0.0, 10.0)),
+            &paint,
+            1.0,
+        );
+
+        let vertices = &set.pattern_fills[0].tessellation.vertices;
+        let first = vertices[0];
+        for vertex in vertices {
+            let dx = vertex.position[0] - first.position[0];
+            let dy = vertex.position[1] - first.position[1];
+            assert_relative_eq!(vertex.pattern_position[0], dy, epsilon = 1e-4);
+            assert_relative_eq!(vertex.pattern_position[1], -dx, epsilon = 1e-4);
+        }
+    }
+
+    #[test]
+    fn polygon_stroke_outlines_all_rings() {
+        let polygon = galileo_types::impls::Polygon::new(ring(0.0, 10.0), vec![ring(2.0, 8.0)]);
+        let stroke = LinePaint {
+            color: Color::RED,
+            width: 1.0,
+            offset: 0.0,
+            line_cap: LineCap::Butt,
+            dash_pattern: None,
+            dash_offset: 0.0,
+        };
+
+        let mut expected = WorldRenderSet::new();
+        expected.add_polygon(&polygon, &PolygonPaint::new(Color::BLACK), 1.0);
+        for contour in polygon.iter_contours() {
+            expected.add_line(contour, &stroke, 1.0);
+        }
+
+        let mut set = WorldRenderSet::new();
+        set.add_polygon(
+            &polygon,
+            &PolygonPaint::new(Color::BLACK).with_stroke(stroke),
+            1.0,
+        );
+
+        let positions = |set: &WorldRenderSet| -> Vec<[f32; 3]> {
+            set.poly_tessellation
+                .vertices
+                .iter()
+                .map(|v| v.position)
+                .collect()
+        };
+        assert_eq!(positions(&set), positions(&expected));
+        assert_eq!(
+            set.poly_tessellation.indices,
+            expected.poly_tessellation.indices
+        );
+        assert!(set
+            .poly_tessellation
+            .vertices
+            .iter()
+            .any(|v| v.color == Color::RED.to_f32_array()));
+    }
+
+    fn ring(min: f64, max: f64) -> ClosedContour<Point3> {
+        ClosedContour::new(vec![
+            Point3::new(min, min, 0.0),
+            Point3::new(min, max, 0.0),
+            Point3::new(max, max, 0.0),
+            Point3::new(max, min, 0.0),
+        ])
+    }
+
+    fn is_filled(tessellation: &VertexBuffers<PolyVertex, u32>, x: f32, y: f32) -> bool {
+        tessellation.indices.chunks(3).any(|triangle| {
+            let [a, b, c] = [0, 1, 2].map(|i| tessellation.vertices[triangle[i] as usize].position);
+            let side =
+                |p: [f32; 3], q: [f32; 3]| (q[0] - p[0]) * (y - p[1]) - (q[1] - p[1]) * (x - p[0]);
+            let (ab, bc, ca) = (side(a, b), side(b, c), side(c, a));
+            (ab >= 0.0 && bc >= 0.0 && ca >= 0.0) || (ab <= 0.0 && bc <= 0.0 && ca <= 0.0)
+        })
+    }
+
+    #[test]
+    fn even_odd_fills_ring_inside_hole() {
+        let mut set = WorldRenderSet::new();
+        // All rings have the same orientation, so only the even-odd rule tells holes from fills.
+        let polygon = galileo_types::impls::Polygon::new(
+            ring(0.0, 10.0),
+            vec![ring(2.0, 8.0), ring(4.0, 6.0)],
+        );
+        set.add_polygon(
+            &polygon,
+            &PolygonPaint::new(Color::BLACK).with_fill_rule(FillRule::EvenOdd),
+            1.0,
+        );
+
+        let tessellation = &set.poly_tessellation;
+        assert!(is_filled(tessellation, 1.0, 1.0));
+        assert!(!is_filled(tessellation, 3.0, 3.0));
+        assert!(is_filled(tessellation, 5.0, 5.0));
+    }
+
+    fn round_line_vertices(tolerance: f32) -> usize {
+        let mut set = WorldRenderSet::new();
+        set.tessellation_tolerance = tolerance;
+        let line = galileo_types::impls::Contour::open(vec![
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(100.0, 50.0, 0.0),
+            Point3::new(200.0, 0.0, 0.0),
+        ]);
+        set.add_line(
+            &line,
+            &LinePaint {
+                color: Color::BLACK,
+                width: 20.0,
+                offset: 0.0,
+                line_cap: LineCap::Round,
+                dash_pattern: None,
+                dash_offset: 0.0,
+            },
+            1.0,
+        );
+
+        set.poly_tessellation.vertices.len()
+    }
+
+    #[test]
+    fn tessellation_tolerance_controls_vertex_count() {
+        let loose = round_line_vertices(1.0);
+        let default = round_line_vertices(DEFAULT_TESSELLATION_TOLERANCE);
+        let tight = round_line_vertices(0.01);
+
+        assert!(loose < default);
+        assert!(default < tight);
+    }
+
+    #[test]
+    fn rotated_rectangle_is_not_distorted() {
+        let rotate = |x: f64, y: f64| {
+            let (sin, cos) = 30f64.to_radians().sin_cos();
+            Point2::new(x * cos - y * sin, x * sin + y * cos)
+        };
+        let vertices = [
+            rotate(0.0, 0.0),
+            rotate(0.0, 10.0),
+            rotate(20.0, 10.0),
+            rotate(20.0, 0.0),
+        ];
+
+        let tex_coords = projective_tex_coords(&vertices);
+        for coords in tex_coords {
+            assert_relative_eq!(coords[2], 1.0, epsilon = 1e-5);
+        }
+    }
+
+    #[test]
+    fn corners_sample_texture_corners() {
+        let vertices = [
+            Point2::new(0.0, 0.0),
+            Point2::new(2.0, 4.0),
+            Point2::new(6.0, 4.0),
+            Point2::new(10.0, 0.0),
+        ];
+
+        let tex_coords = projective_tex_coords(&vertices);
+        let expected = [[0.0, 1.0], [0.0, 0.0], [1.0, 0.0], [1.0, 1.0]];
+        for (coords, expected) in tex_coords.iter().zip(expected) {
+            assert_relative_eq!(coords[0] / coords[2], expected[0]);
+            assert_relative_eq!(coords[1] / coords[2], expected[1]);
+        }
+    }
+
+    #[test]
+    fn region_corners_sample_region_corners() {
+        let vertices = [
+            Point2::new(0.0, 0.0),
+            Point2::new(2.0, 4.0),
+            Point2::new(6.0, 4.0),
+            Point2::new(10.0, 0.0),
+        ];
+
+        let source = Rect::new(0.25, 0.5, 0.5, 1.0);
+        let tex_coords = region_tex_coords(projective_tex_coords(&vertices), source);
+        let expected = [[0.25, 1.0], [0.25, 0.5], [0.5, 0.5], [0.5, 1.0]];
+        for (coords, expected) in tex_coords.iter().zip(expected) {
+            assert_relative_eq!(coords[0] / coords[2], expected[0]);
+            assert_relative_eq!(coords[1] / coords[2], expected[1]);
+        }
+    }
+
+    #[test]
+    fn trapezoid_center_samples_texture_center() {
+        let vertices = [
+            Point2::new(0.0, 0.0),
+            Point2::new(2.0, 4.0),
+            Point2::new(6.0, 4.0),
+            Point2::new(10.0, 0.0),
+        ];
+
+        let center = diagonals_intersection(&vertices).expect("quad is convex");
+        let tex_coords = projective_tex_coords(&vertices);
+
+        // The quad is split into triangles along the diagonal from the top left to the bottom
+        // right corner, so the center is interpolated between these two vertices.
+        let t = (vertices[1].distance(&center) / vertices[1].distance(&vertices[3])) as f32;
+        let uv = interpolate(tex_coords[1], tex_coords[3], t);
+
+        assert_relative_eq!(uv[0], 0.5, epsilon = 1e-5);
+        assert_relative_eq!(uv[1], 0.5, epsilon = 1e-5);
+    }
+}
@@ -0,0 +1,195 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use galileo_types::cartesian::{CartesianPoint2d, Point2, Rect, Size, Vector2};
+use galileo_types::impls::ClosedContour;
+use serde::{Deserialize, Serialize};
+
+use crate::render::text::{HorizontalAlignment, TextStyle, VerticalAlignment};
+use crate::Color;
+
+/// Background drawn behind a text label.
+///
+/// The size of the background is calculated from the measured size of the text, so the background always wraps the
+/// text with the given `padding`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LabelBackground {
+    /// Fill color of the background.
+    pub fill: Color,
+    /// Radius of the background corners in pixels. The radius is limited to half of the shorter side of the
+    /// background, so `f32::INFINITY` gives a pill shape.
+    pub corner_radius: f32,
+    /// Color of the border.
+    pub border_color: Color,
+    /// Width of the border in pixels. No border is drawn if the width is `0`.
+    pub border_width: f32,
+    /// Distance between the text and the edge of the background in pixels.
+    pub padding: f32,
+}
+
+impl LabelBackground {
+    /// Rectangular background of the given color without border.
+    pub fn new(fill: Color) -> Self {
+        Self {
+            fill,
+            corner_radius: 0.0,
+            border_color: Color::TRANSPARENT,
+            border_width: 0.0,
+            padding: 0.0,
+        }
+    }
+
+    /// Sets the radius of the corners.
+    pub fn with_corner_radius(mut self, corner_radius: f32) -> Self {
+        self.corner_radius = corner_radius;
+        self
+    }
+
+    /// Sets the border of the background.
+    pub fn with_border(mut self, color: Color, width: f32) -> Self {
+        self.border_color = color;
+        self.border_width = width;
+        self
+    }
+
+    /// Sets the padding around the text.
+    pub fn with_padding(mut self, padding: f32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Returns true if the background has a visible border.
+    pub(crate) fn has_border(&self) -> bool {
+        self.border_width > 0.0 && !self.border_color.is_transparent()
+    }
+
+    /// Builds the contour of the background in pixels for a text of the given size. The contour
+    /// is positioned relative to the anchor point according to the alignment of the `style`.
+    pub fn contour(
+        &self,
+        text_size: Size<f32>,
+        style: &TextStyle,
+        offset: Vector2<f32>,
+    ) -> ClosedContour<Point2<f32>> {
+        let rect = label_rect(text_size, style, offset, self.padding);
+        let (width, height) = (rect.width(), rect.height());
+
+        let radius = self.corner_radius.clamp(0.0, width.min(height) / 2.0);
+        rounded_rect(rect.x_min(), rect.y_min(), width, height, radius)
+    }
+}
+
+/// Rectangle in pixels occupied by a text of the given size with `padding` around it. The
+/// rectangle is positioned relative to the anchor point according to the alignment of the `style`.
+pub(crate) fn label_rect(
+    text_size: Size<f32>,
+    style: &TextStyle,
+    offset: Vector2<f32>,
+    padding: f32,
+) -> Rect<f32> {
+    let width = text_size.width() + padding * 2.0;
+    let height = text_size.height() + padding * 2.0;
+
+    let x_min = offset.dx()
+        + match style.horizontal_alignment {
+            HorizontalAlignment::Left => -padding,
+            HorizontalAlignment::Center => -width / 2.0,
+            HorizontalAlignment::Right => padding - width,
+        };
+    let y_min = offset.dy()
+        + match style.vertical_alignment {
+            VerticalAlignment::Top => padding - height,
+            VerticalAlignment::Middle => -height / 2.0,
+            VerticalAlignment::Bottom => -padding,
+        };
+
+    Rect::new(x_min, y_min, x_min + width, y_min + height)
+}
+
+const CORNER_SEGMENTS: usize = 8;
+
+/// Contour of a rectangle with rounded corners.
+pub(crate) fn rounded_rect(
+    x_min: f32,
+    y_min: f32,
+    width: f32,
+    height: f32,
+    radius: f32,
+) -> ClosedContour<Point2<f32>> {
+    let x_max = x_min + width;
+    let y_max = y_min + height;
+
+    if radius <= 0.0 {
+        return ClosedContour::new(vec![
+            Point2::new(x_min, y_min),
+            Point2::new(x_max, y_min),
+            Point2::new(x_max, y_max),
+            Point2::new(x_min, y_max),
+        ]);
+    }
+
+    let corners = [
+        (x_max - radius, y_min + radius, -FRAC_PI_2),
+        (x_max - radius, y_max - radius, 0.0),
+        (x_min + radius, y_max - radius, FRAC_PI_2),
+        (x_min + radius, y_min + radius, PI),
+    ];
+
+    let mut points = Vec::with_capacity(corners.len() * (CORNER_SEGMENTS + 1));
+    for (cx, cy, start_angle) in corners {
+        for i in 0..=CORNER_SEGMENTS {
+            let angle = start_angle + FRAC_PI_2 * i as f32 / CORNER_SEGMENTS as f32;
+            points.push(Point2::new(
+                cx + radius * angle.cos(),
+                cy + radius * angle.sin(),
+            ));
+        }
+    }
+
+    // Neighbouring arcs of a pill touch, so remove the duplicate points.
+    points.dedup_by(|a, b| {
+        (a.x() - b.x()).abs() < f32::EPSILON && (a.y() - b.y()).abs() < f32::EPSILON
+    });
+
+    ClosedContour::new(points)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn style() -> TextStyle {
+        TextStyle {
+            horizontal_alignment: HorizontalAlignment::Left,
+            vertical_alignment: VerticalAlignment::Bottom,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn background_wraps_text_with_padding() {
+        let background = LabelBackground::new(Color::WHITE).with_padding(2.0);
+        let contour = background.contour(Size::new(30.0, 10.0), &style(), Vector2::new(5.0, 0.0));
+
+        assert_eq!(
+            contour.points,
+            vec![
+                Point2::new(3.0, -2.0),
+                Point2::new(37.0, -2.0),
+                Point2::new(37.0, 12.0),
+                Point2::new(3.0, 12.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn corner_radius_is_limited_by_size() {
+        let pill = LabelBackground::new(Color::WHITE).with_corner_radius(f32::INFINITY);
+        let contour = pill.contour(Size::new(30.0, 10.0), &style(), Vector2::default());
+
+        assert!(contour.points.len() > 4);
+        for p in &contour.points {
+            assert!(p.x() >= -1e-4 && p.x() <= 30.0 + 1e-4);
+            assert!(p.y() >= -1e-4 && p.y() <= 10.0 + 1e-4);
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use galileo_types::cartesian::{CartesianPoint2d, Point2, Rect, Vector2};
+use galileo_types::impls::ClosedContour;
+use serde::{Deserialize, Serialize};
+
+use crate::Color;
+
+/// Position of a label relative to its anchor point.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct LabelPlacement {
+    /// Offset of the label from the anchor point in pixels.
+    pub offset: Vector2<f32>,
+    /// Line connecting the label to the anchor point.
+    pub leader_line: Option<LeaderLine>,
+}
+
+impl LabelPlacement {
+    /// Label placed with the given offset without leader line.
+    pub fn new(offset: Vector2<f32>) -> Self {
+        Self {
+            offset,
+            leader_line: None,
+        }
+    }
+
+    /// Sets the leader line of the label.
+    pub fn with_leader_line(mut self, leader_line: LeaderLine) -> Self {
+        self.leader_line = Some(leader_line);
+        self
+    }
+}
+
+/// Thin line connecting a label drawn with an offset to its anchor point.
+///
+/// The line goes from the anchor point to the nearest point of the label box (or of the label background, if
+/// the label has one).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LeaderLine {
+    /// Color of the line.
+    pub color: Color,
+    /// Width of the line in pixels.
+    pub width: f32,
+    /// The line is drawn only if the length of the label offset is not less than this value in pixels.
+    pub min_offset: f32,
+}
+
+impl LeaderLine {
+    /// Leader line of the given color and width that is drawn for any label offset.
+    pub fn new(color: Color, width: f32) -> Self {
+        Self {
+            color,
+            width,
+            min_offset: 0.0,
+        }
+    }
+
+    /// Sets the minimum label offset for which the line is drawn.
+    pub fn with_min_offset(mut self, min_offset: f32) -> Self {
+        self.min_offset = min_offset;
+        self
+    }
+
+    /// End points of the line in pixels relative to the anchor point for a label occupying
+    /// `label_rect`. Returns `None` if the line should not be drawn.
+    pub(crate) fn segment(
+        &self,
+        label_rect: Rect<f32>,
+        offset: Vector2<f32>,
+    ) -> Option<(Point2<f32>, Point2<f32>)> {
+        if self.width <= 0.0 || self.color.is_transparent() || offset.magnitude() < self.min_offset
+        {
+            return None;
+        }
+
+        let end = Point2::new(
+            0f32.clamp(label_rect.x_min(), label_rect.x_max()),
+            0f32.clamp(label_rect.y_min(), label_rect.y_max()),
+        );
+        if end.x() == 0.0 && end.y() == 0.0 {
+            // The anchor point is covered by the label.
+            return None;
+        }
+
+        Some((Point2::new(0.0, 0.0), end))
+    }
+
+    /// Contour of the line in pixels relative to the anchor point. See [`LeaderLine::segment`].
+    pub(crate) fn contour(
+        &self,
+        label_rect: Rect<f32>,
+        offset: Vector2<f32>,
+    ) -> Option<ClosedContour<Point2<f32>>> {
+        let (start, end) = self.segment(label_rect, offset)?;
+        let direction = end - start;
+        let length = direction.magnitude();
+        let normal = Vector2::new(-direction.dy(), direction.dx()) * (self.width / 2.0 / length);
+
+        Some(ClosedContour::new(vec![
+            start + normal,
+            end + normal,
+            end - normal,
+            start - normal,
+        ]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_ends_at_nearest_point_of_label() {
+        let leader = LeaderLine::new(Color::BLACK, 1.0);
+        let rect = Rect::new(10.0, -5.0, 30.0, 5.0);
+
+        let (start, end) = leader
+            .segment(rect, Vector2::new(20.0, 0.0))
+            .expect("line is drawn");
+        assert_eq!(start, Point2::new(0.0, 0.0));
+        assert_eq!(end, Point2::new(10.0, 0.0));
+
+        let rect = Rect::new(10.0, 10.0, 30.0, 20.0);
+        let (_, end) = leader
+            .segment(rect, Vector2::new(20.0, 15.0))
+            .expect("line is drawn");
+        assert_eq!(end, Point2::new(10.0, 10.0));
+    }
+
+    #[test]
+    fn line_is_not_drawn_for_small_offset() {
+        let leader = LeaderLine::new(Color::BLACK, 1.0).with_min_offset(25.0);
+        let rect = Rect::new(10.0, -5.0, 30.0, 5.0);
+        assert!(leader.segment(rect, Vector2::new(20.0, 0.0)).is_none());
+        assert!(leader.segment(rect, Vector2::new(30.0, 0.0)).is_some());
+
+        // Anchor point inside the label.
+        let leader = LeaderLine::new(Color::BLACK, 1.0);
+        let rect = Rect::new(-10.0, -5.0, 10.0, 5.0);
+        assert!(leader.segment(rect, Vector2::new(0.0, 0.0)).is_none());
+    }
+
+    #[test]
+    fn contour_has_line_width() {
+        let leader = LeaderLine::new(Color::BLACK, 2.0);
+        let rect = Rect::new(10.0, -5.0, 30.0, 5.0);
+        let contour = leader
+            .contour(rect, Vector2::new(20.0, 0.0))
+            .expect("line is drawn");
+
+        assert_eq!(
+            contour.points,
+            vec![
+                Point2::new(0.0, 1.0),
+                Point2::new(10.0, 1.0),
+                Point2::new(10.0, -1.0),
+                Point2::new(0.0, -1.0),
+            ]
+        );
+    }
+}
@@ -1,14 +1,19 @@
 //! Types for text rendering.
 
 use font_provider::FontProvider;
-use galileo_types::cartesian::Vector2;
+use galileo_types::cartesian::{Size, Vector2};
 use serde::{Deserialize, Serialize};
 
 use crate::Color;
 
 pub(crate) mod font_provider;
+pub(crate) mod label_background;
+pub(crate) mod leader_line;
 pub mod text_service;
 
+pub use label_background::LabelBackground;
+pub use leader_line::{LabelPlacement, LeaderLine};
+
 pub(crate) use text_service::TextService;
 
 use crate::render::text::text_service::FontServiceError;
@@ -19,12 +24,31 @@ mod rustybuzz;
 pub use rustybuzz::RustybuzzRasterizer;
 
 /// Style of a text label on the map.
+///
+/// New fields may be added to the style in the future, so prefer filling the fields that are not set explicitly
+/// with `..Default::default()`:
+///
+/// ```
+/// use galileo::render::text::TextStyle;
+/// use galileo::Color;
+///
+/// let style = TextStyle {
+///     font_family: vec!["Noto Sans".to_string()],
+///     font_size: 14.0,
+///     outline_width: 2.0,
+///     outline_color: Color::WHITE,
+///     ..Default::default()
+/// };
+/// ```
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct TextStyle {
     /// Name of the font to use.
     pub font_family: Vec<String>,
-    /// Size of the font in pixels.
+    /// Size of the font in the units given by `font_size_unit`.
     pub font_size: f32,
+    /// Unit of the `font_size`.
+    #[serde(default)]
+    pub font_size_unit: FontSizeUnit,
     /// Color of the font.
     #[serde(default = "default_font_color")]
     pub font_color: Color,
@@ -40,14 +64,58 @@ pub struct TextStyle {
     /// sTyle of the font.
     #[serde(default)]
     pub style: FontStyle,
-    /// Width of the outline around the letters.
+    /// Width of the outline (halo) around the letters in pixels. The outline is drawn beneath the letters and
+    /// extends by this width outside of them. No outline is drawn if the width is `0`.
     #[serde(default)]
     pub outline_width: f32,
     /// Color of the outline around the letters.
     #[serde(default = "default_outline_color")]
     pub outline_color: Color,
+    /// Maximum width of a line of the text in pixels. Longer text is broken into several lines at word
+    /// boundaries. If not set, the text is only broken at explicit line breaks (`\n`).
+    #[serde(default)]
+    pub max_width: Option<f32>,
+    /// Distance between the baselines of two consecutive lines as a multiple of the font size.
+    #[serde(default = "default_line_height")]
+    pub line_height: f32,
+    /// Additional space between the letters in pixels. Negative value brings the letters closer together.
+    #[serde(default)]
+    pub letter_spacing: f32,
 }
 
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            font_family: vec![],
+            font_size: 12.0,
+            font_size_unit: FontSizeUnit::default(),
+            font_color: default_font_color(),
+            horizontal_alignment: HorizontalAlignment::default(),
+            vertical_alignment: VerticalAlignment::default(),
+            weight: FontWeight::default(),
+            style: FontStyle::default(),
+            outline_width: 0.0,
+            outline_color: default_outline_color(),
+            max_width: None,
+            line_height: default_line_height(),
+            letter_spacing: 0.0,
+        }
+    }
+}
+
+impl TextStyle {
+    /// Size of the font in physical pixels of the render target with the given display scale factor.
+    pub fn font_size_px(&self, scale_factor: f32) -> f32 {
+        match self.font_size_unit {
+            FontSizeUnit::Pixels => self.font_size,
+            FontSizeUnit::Points => self.font_size * PIXELS_PER_POINT * scale_factor,
+        }
+    }
+}
+
+/// Number of logical pixels in a typographic point at 96 DPI.
+const PIXELS_PER_POINT: f32 = 96.0 / 72.0;
+
 fn default_font_color() -> Color {
     Color::BLACK
 }
@@ -56,6 +124,61 @@ fn default_outline_color() -> Color {
     Color::TRANSPARENT
 }
 
+fn default_line_height() -> f32 {
+    1.2
+}
+
+/// Splits the text into lines.
+///
+/// The text is always broken at `\n` characters. If `max_width` is given, each line is also broken at word boundaries
+/// so that its width, as returned by the `measure` function, does not exceed `max_width`. Words longer than
+/// `max_width` are placed on their own line and are not split.
+pub(crate) fn break_lines(
+    text: &str,
+    max_width: Option<f32>,
+    measure: impl Fn(&str) -> f32,
+) -> Vec<String> {
+    let mut lines = vec![];
+    for paragraph in text.split('\n') {
+        let paragraph = paragraph.strip_suffix('\r').unwrap_or(paragraph);
+        let Some(max_width) = max_width else {
+            lines.push(paragraph.to_string());
+            continue;
+        };
+
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            if line.is_empty() {
+                line.push_str(word);
+                continue;
+            }
+
+            let candidate = format!("{line} {word}");
+            if measure(&candidate) <= max_width {
+                line = candidate;
+            } else {
+                lines.push(std::mem::replace(&mut line, word.to_string()));
+            }
+        }
+
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// Unit of [`TextStyle::font_size`].
+#[derive(Default, Debug, Copy, Clone, Serialize, Deserialize, PartialEq)]
+pub enum FontSizeUnit {
+    /// Physical pixels of the render target. Text has the same pixel size on every display, so it looks smaller
+    /// on high DPI screens.
+    #[default]
+    Pixels,
+    /// Typographic points (1/72 of an inch) at 96 DPI. The size is converted into physical pixels using the scale
+    /// factor of the display, so a 12pt font has the same apparent size on standard and high DPI screens.
+    Points,
+}
+
 /// Horizontal alignment.
 #[derive(Default, Debug, Copy, Clone, Serialize, Deserialize, PartialEq)]
 pub enum HorizontalAlignment {
@@ -113,6 +236,9 @@ pub struct TessellatedGlyph {
 /// Data provider for font service.
 pub trait TextRasterizer {
     /// Shape text label.
+    ///
+    /// The font size of the `style` is always given in pixels, as [`TextService`](text_service::TextService) converts other units before
+    /// calling the rasterizer.
     fn shape(
         &self,
         text: &str,
@@ -120,6 +246,37 @@ pub trait TextRasterizer {
         offset: Vector2<f32>,
         font_provider: &dyn FontProvider,
     ) -> Result<TextShaping, FontServiceError>;
+
+    /// Measures the size of the text in pixels as it would be drawn with the given style.
+    ///
+    /// The default implementation shapes the text and returns the size of the bounding box of the resulting glyphs.
+    fn measure(
+        &self,
+        text: &str,
+        style: &TextStyle,
+        font_provider: &dyn FontProvider,
+    ) -> Result<Size<f32>, FontServiceError> {
+        let TextShaping::Tessellation { glyphs } =
+            self.shape(text, style, Vector2::default(), font_provider)?
+        else {
+            return Err(FontServiceError::MeasurementNotSupported);
+        };
+
+        let (x_min, y_min, x_max, y_max) =
+            glyphs.iter().flat_map(|glyph| glyph.vertices.iter()).fold(
+                (f32::MAX, f32::MAX, f32::MIN, f32::MIN),
+                |(x_min, y_min, x_max, y_max), v| {
+                    let [x, y] = v.position;
+                    (x_min.min(x), y_min.min(y), x_max.max(x), y_max.max(y))
+                },
+            );
+
+        if x_min > x_max {
+            return Ok(Size::new(0.0, 0.0));
+        }
+
+        Ok(Size::new(x_max - x_min, y_max - y_min))
+    }
 }
 
 /// Font weight.
@@ -142,9 +299,10 @@ impl Default for FontWeight {
 }
 
 /// Font style.
-#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub enum FontStyle {
     /// Normal font.
+    #[default]
     Normal,
     /// Italic font.
     Italic,
@@ -152,12 +310,6 @@ pub enum FontStyle {
     Oblique,
 }
 
-impl Default for FontStyle {
-    fn default() -> Self {
-        Self::Normal
-    }
-}
-
 /// Queryable properties of a font
 pub struct FontProperties {
     /// Font weight
@@ -165,3 +317,36 @@ pub struct FontProperties {
     /// Font style
     pub style: FontStyle,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn char_count(text: &str) -> f32 {
+        text.chars().count() as f32
+    }
+
+    #[test]
+    fn text_without_max_width_is_broken_at_new_lines_only() {
+        let lines = break_lines("first line\nsecond  line", None, char_count);
+        assert_eq!(lines, vec!["first line", "second  line"]);
+    }
+
+    #[test]
+    fn text_is_wrapped_at_word_boundaries() {
+        let lines = break_lines("the quick brown fox jumps", Some(10.0), char_count);
+        assert_eq!(lines, vec!["the quick", "brown fox", "jumps"]);
+    }
+
+    #[test]
+    fn explicit_line_breaks_are_kept_when_wrapping() {
+        let lines = break_lines("a b\n\nc", Some(10.0), char_count);
+        assert_eq!(lines, vec!["a b", "", "c"]);
+    }
+
+    #[test]
+    fn long_words_are_not_split() {
+        let lines = break_lines("a verylongword b", Some(5.0), char_count);
+        assert_eq!(lines, vec!["a", "verylongword", "b"]);
+    }
+}
@@ -1,19 +1,23 @@
+use std::ops::Range;
 use std::sync::Arc;
 
-use galileo_types::cartesian::Vector2;
+use galileo_types::cartesian::{Size, Vector2};
 use lyon::lyon_tessellation::{
     BuffersBuilder, FillOptions, FillTessellator, FillVertex, FillVertexConstructor, VertexBuffers,
 };
 use lyon::path::path::Builder;
 use lyon::path::Path;
-use lyon::tessellation::{StrokeOptions, StrokeTessellator, StrokeVertexConstructor};
+use lyon::tessellation::{
+    LineCap, LineJoin, StrokeOptions, StrokeTessellator, StrokeVertexConstructor,
+};
 use rustybuzz::ttf_parser::{self, GlyphId, OutlineBuilder, Tag};
-use rustybuzz::{Direction, UnicodeBuffer};
+use rustybuzz::{Direction, GlyphInfo, GlyphPosition, UnicodeBuffer};
+use unicode_bidi::BidiInfo;
 
 use super::font_provider::FontProvider;
 use super::text_service::FontServiceError;
 use super::{FontProperties, GlyphVertex};
-use crate::render::text::{TessellatedGlyph, TextRasterizer, TextShaping, TextStyle};
+use crate::render::text::{break_lines, TessellatedGlyph, TextRasterizer, TextShaping, TextStyle};
 use crate::Color;
 
 /// Font service provider that uses `rustybuzz` crate to shape and vectorize text
@@ -48,10 +52,6 @@ impl TextRasterizer for RustybuzzRasterizer {
             return Ok(TextShaping::Tessellation { glyphs: vec![] });
         }
 
-        let mut buffer = UnicodeBuffer::new();
-        buffer.push_str(text);
-        buffer.guess_segment_properties();
-
         let Some((font_data, index)) = self.select_face(text, style, font_provider) else {
             return Err(FontServiceError::FontNotFound);
         };
@@ -65,80 +65,219 @@ impl TextRasterizer for RustybuzzRasterizer {
         let units = face.units_per_em() as f32;
         let scale = style.font_size / units;
 
-        let is_vertical = matches!(
-            buffer.direction(),
-            Direction::TopToBottom | Direction::BottomToTop
-        );
-        let glyph_buffer = rustybuzz::shape(&face, &[], buffer);
+        let lines = break_lines(text, style.max_width, |line| {
+            shape_line(&face, line).width(scale, style.letter_spacing)
+        });
+        let line_step = style.font_size * style.line_height;
+        let last_line = lines.len().saturating_sub(1) as f32;
+
         let mut fill = vec![];
         let mut outline = vec![];
 
-        let (width, height) = if is_vertical {
-            let width = face.units_per_em();
-            let height = glyph_buffer
-                .glyph_positions()
-                .iter()
-                .fold(0, |aggr, glyph| aggr + glyph.y_advance);
-            (width as f32, height as f32)
-        } else {
-            let width = glyph_buffer
-                .glyph_positions()
-                .iter()
-                .fold(0, |aggr, glyph| aggr + glyph.x_advance);
-            let height = face.ascender() + face.descender();
-            (width as f32, height as f32)
-        };
+        for (line_index, line) in lines.iter().enumerate() {
+            let shaped = shape_line(&face, line);
+
+            let (width, height) = if shaped.is_vertical {
+                let width = face.units_per_em();
+                let height = shaped
+                    .glyphs
+                    .iter()
+                    .fold(0, |aggr, (_, glyph)| aggr + glyph.y_advance);
+                (width as f32 * scale, height as f32 * scale)
+            } else {
+                let width = shaped.width(scale, style.letter_spacing);
+                let height = face.ascender() + face.descender();
+                (width, height as f32 * scale)
+            };
+
+            let offset_x = offset.dx()
+                + match style.horizontal_alignment {
+                    super::HorizontalAlignment::Left => 0.0,
+                    super::HorizontalAlignment::Center => -width / 2.0,
+                    super::HorizontalAlignment::Right => -width,
+                };
+
+            // Lines are stacked downwards, and the whole block of lines is aligned to the anchor point.
+            let line_shift = line_index as f32 * line_step;
+            let offset_y = offset.dy() - line_shift
+                + match style.vertical_alignment {
+                    super::VerticalAlignment::Top => -height,
+                    super::VerticalAlignment::Middle => (last_line * line_step - height) / 2.0,
+                    super::VerticalAlignment::Bottom => last_line * line_step,
+                };
+
+            let mut advance_x = 0.0;
+            let mut advance_y = 0.0;
+
+            for &(glyph_info, position) in &shaped.glyphs {
+                let mut path_builder = GlyphPathBuilder::new(scale);
+                face.outline_glyph(GlyphId(glyph_info.glyph_id as u16), &mut path_builder);
+
+                let snapped_x = (position.x_offset as f32 * scale + advance_x + offset_x).round();
+                let snapped_y = (position.y_offset as f32 * scale + advance_y + offset_y).round();
+
+                let glyph_position = Vector2::new(snapped_x, snapped_y);
+
+                if style.outline_width > 0.0 && !style.outline_color.is_transparent() {
+                    outline.push(path_builder.clone().tessellate_outline(
+                        glyph_position,
+                        style.outline_width,
+                        style.outline_color,
+                    ));
+                }
+
+                fill.push(path_builder.tessellate_fill(glyph_position, style.font_color));
+
+                advance_x += position.x_advance as f32 * scale;
+                advance_y += position.y_advance as f32 * scale;
+                if position.x_advance != 0 {
+                    advance_x += style.letter_spacing;
+                }
+            }
+        }
 
-        let width = width * scale;
-        let height = height * scale;
+        outline.append(&mut fill);
 
-        let offset_x = offset.dx()
-            + match style.horizontal_alignment {
-                super::HorizontalAlignment::Left => 0.0,
-                super::HorizontalAlignment::Center => -width / 2.0,
-                super::HorizontalAlignment::Right => -width,
-            };
+        Ok(TextShaping::Tessellation { glyphs: outline })
+    }
 
-        let offset_y = offset.dy()
-            + match style.vertical_alignment {
-                super::VerticalAlignment::Top => -height,
-                super::VerticalAlignment::Middle => -height / 2.0,
-                super::VerticalAlignment::Bottom => 0.0,
+    fn measure(
+        &self,
+        text: &str,
+        style: &TextStyle,
+        font_provider: &dyn FontProvider,
+    ) -> Result<Size<f32>, FontServiceError> {
+        if text.is_empty() {
+            return Ok(Size::new(0.0, 0.0));
+        }
+
+        let Some((font_data, index)) = self.select_face(text, style, font_provider) else {
+            return Err(FontServiceError::FontNotFound);
+        };
+
+        let face = ttf_parser::Face::parse(&font_data, index)?;
+        let mut face = rustybuzz::Face::from_face(face);
+        face.set_variation(Tag::from_bytes(b"wght"), style.weight.0 as f32);
+        face.set_variation(Tag::from_bytes(b"wdth"), 1.0);
+
+        let scale = style.font_size / face.units_per_em() as f32;
+        let lines = break_lines(text, style.max_width, |line| {
+            shape_line(&face, line).width(scale, style.letter_spacing)
+        });
+
+        // Advances are used instead of glyph outlines, so that the spaces and the side bearings of the glyphs are
+        // included in the size.
+        let mut width: f32 = 0.0;
+        let mut height: f32 = 0.0;
+        for line in &lines {
+            let shaped = shape_line(&face, line);
+            let (line_width, line_height) = if shaped.is_vertical {
+                let line_height: i32 = shaped.glyphs.iter().map(|(_, p)| p.y_advance).sum();
+                (
+                    face.units_per_em() as f32 * scale,
+                    line_height.abs() as f32 * scale,
+                )
+            } else {
+                let line_width = shaped.width(scale, style.letter_spacing);
+                let line_height = face.ascender() as i32 - face.descender() as i32;
+                (line_width, line_height as f32 * scale)
             };
 
-        let mut advance_x = 0.0;
-        let mut advance_y = 0.0;
+            width = width.max(line_width);
+            height = height.max(line_height);
+        }
 
-        for index in 0..glyph_buffer.len() {
-            let position = glyph_buffer.glyph_positions()[index];
-            let glyph_info = glyph_buffer.glyph_infos()[index];
+        let line_step = style.font_size * style.line_height;
+        height += lines.len().saturating_sub(1) as f32 * line_step;
 
-            let mut path_builder = GlyphPathBuilder::new(scale);
-            face.outline_glyph(GlyphId(glyph_info.glyph_id as u16), &mut path_builder);
+        Ok(Size::new(width, height))
+    }
+}
 
-            let snapped_x = (position.x_offset as f32 * scale + advance_x + offset_x).round();
-            let snapped_y = (position.y_offset as f32 * scale + advance_y + offset_y).round();
+/// Glyphs of a single line of text in visual order.
+struct ShapedLine {
+    glyphs: Vec<(GlyphInfo, GlyphPosition)>,
+    is_vertical: bool,
+}
 
-            let glyph_position = Vector2::new(snapped_x, snapped_y);
+impl ShapedLine {
+    /// Width of the line in pixels.
+    ///
+    /// The `letter_spacing` is added between the glyphs that have an advance, so that the combining marks stay
+    /// attached to their base glyphs.
+    fn width(&self, scale: f32, letter_spacing: f32) -> f32 {
+        let advance = self
+            .glyphs
+            .iter()
+            .fold(0, |aggr, (_, glyph)| aggr + glyph.x_advance);
+        let spaced_glyphs = self
+            .glyphs
+            .iter()
+            .filter(|(_, glyph)| glyph.x_advance != 0)
+            .count();
+
+        advance as f32 * scale + spaced_glyphs.saturating_sub(1) as f32 * letter_spacing
+    }
+}
 
-            if style.outline_width > 0.0 && !style.outline_color.is_transparent() {
-                outline.push(path_builder.clone().tessellate_outline(
-                    glyph_position,
-                    style.outline_width,
-                    style.outline_color,
-                ));
-            }
+/// Shapes a single line of text.
+///
+/// The line is split into runs of the same direction with the Unicode bidirectional algorithm. Each run is shaped
+/// separately in its own direction, and the glyphs of the runs are put together in the visual order, so the mixed
+/// left-to-right and right-to-left text is laid out correctly.
+fn shape_line(face: &rustybuzz::Face, line: &str) -> ShapedLine {
+    let mut glyphs = vec![];
+    let mut is_vertical = false;
 
-            fill.push(path_builder.tessellate_fill(glyph_position, style.font_color));
+    for (run, is_rtl) in visual_runs(line) {
+        let mut buffer = UnicodeBuffer::new();
+        buffer.push_str(&line[run]);
+        buffer.guess_segment_properties();
 
-            advance_x += position.x_advance as f32 * scale;
-            advance_y += position.y_advance as f32 * scale;
+        let run_is_vertical = matches!(
+            buffer.direction(),
+            Direction::TopToBottom | Direction::BottomToTop
+        );
+        if !run_is_vertical {
+            buffer.set_direction(if is_rtl {
+                Direction::RightToLeft
+            } else {
+                Direction::LeftToRight
+            });
         }
+        is_vertical |= run_is_vertical;
 
-        outline.append(&mut fill);
+        // Glyphs of a right-to-left run are returned by the shaper in the visual order already.
+        let glyph_buffer = rustybuzz::shape(face, &[], buffer);
+        glyphs.extend(
+            glyph_buffer
+                .glyph_infos()
+                .iter()
+                .copied()
+                .zip(glyph_buffer.glyph_positions().iter().copied()),
+        );
+    }
 
-        Ok(TextShaping::Tessellation { glyphs: outline })
+    ShapedLine {
+        glyphs,
+        is_vertical,
+    }
+}
+
+/// Byte ranges of the runs of the same direction in the visual (left to right) order, and whether each run is
+/// right-to-left.
+fn visual_runs(line: &str) -> Vec<(Range<usize>, bool)> {
+    let bidi_info = BidiInfo::new(line, None);
+    let mut result = vec![];
+    for paragraph in &bidi_info.paragraphs {
+        let (levels, runs) = bidi_info.visual_runs(paragraph, paragraph.range.clone());
+        for run in runs {
+            let is_rtl = levels[run.start].is_rtl();
+            result.push((run, is_rtl));
+        }
     }
+
+    result
 }
 
 #[derive(Clone)]
@@ -169,6 +308,8 @@ impl GlyphPathBuilder {
         }
     }
 
+    /// Tessellates the halo around the glyph. The halo extends by `width` pixels outside the glyph outline, and
+    /// the fill of the glyph is expected to be drawn over it.
     fn tessellate_outline(
         self,
         offset: Vector2<f32>,
@@ -181,7 +322,7 @@ impl GlyphPathBuilder {
         if tessellator
             .tessellate(
                 &self.builder.build(),
-                &StrokeOptions::default().with_line_width(width),
+                &halo_stroke_options(width),
                 &mut BuffersBuilder::new(&mut buffers, vertex_constructor),
             )
             .is_ok()
@@ -196,6 +337,17 @@ impl GlyphPathBuilder {
     }
 }
 
+/// Stroke options for the halo of the given width.
+///
+/// The stroke is centered on the glyph outline, so it is twice as wide as the visible part of the halo. Round joins
+/// and caps keep thick halos from producing spikes at the sharp corners of the glyphs.
+fn halo_stroke_options(width: f32) -> StrokeOptions {
+    StrokeOptions::default()
+        .with_line_width(width * 2.0)
+        .with_line_join(LineJoin::Round)
+        .with_line_cap(LineCap::Round)
+}
+
 fn invalid_glyph_substitution() -> TessellatedGlyph {
     todo!()
 }
@@ -268,3 +420,74 @@ impl StrokeVertexConstructor<GlyphVertex> for GlyphVertexConstructor {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn runs(line: &str) -> Vec<(&str, bool)> {
+        visual_runs(line)
+            .into_iter()
+            .map(|(range, is_rtl)| (&line[range], is_rtl))
+            .collect()
+    }
+
+    fn square_glyph() -> GlyphPathBuilder {
+        let mut builder = GlyphPathBuilder::new(1.0);
+        builder.move_to(0.0, 0.0);
+        builder.line_to(10.0, 0.0);
+        builder.line_to(10.0, 10.0);
+        builder.line_to(0.0, 10.0);
+        builder.close();
+        builder
+    }
+
+    fn extent(glyph: &TessellatedGlyph) -> (f32, f32) {
+        glyph
+            .vertices
+            .iter()
+            .flat_map(|v| v.position)
+            .fold((f32::MAX, f32::MIN), |(min, max), c| {
+                (min.min(c), max.max(c))
+            })
+    }
+
+    #[test]
+    fn halo_extends_outside_glyph_by_its_width() {
+        let offset = Vector2::new(0.0, 0.0);
+        let fill = square_glyph().tessellate_fill(offset, Color::BLACK);
+        let halo = square_glyph().tessellate_outline(offset, 2.0, Color::WHITE);
+
+        let (fill_min, fill_max) = extent(&fill);
+        assert_eq!((fill_min, fill_max), (0.0, 10.0));
+
+        let (halo_min, halo_max) = extent(&halo);
+        assert!((halo_min + 2.0).abs() < 0.1, "halo min: {halo_min}");
+        assert!((halo_max - 12.0).abs() < 0.1, "halo max: {halo_max}");
+        assert!(halo.vertices.iter().all(|v| v.color == Color::WHITE));
+    }
+
+    #[test]
+    fn left_to_right_text_is_a_single_run() {
+        assert_eq!(runs("Main street"), vec![("Main street", false)]);
+    }
+
+    #[test]
+    fn right_to_left_run_is_detected() {
+        assert_eq!(
+            runs("abc שלום def"),
+            vec![("abc ", false), ("שלום", true), (" def", false)]
+        );
+    }
+
+    #[test]
+    fn latin_text_is_placed_first_in_right_to_left_paragraph() {
+        let runs = runs("القاهرة ABC");
+        assert_eq!(runs.first(), Some(&("ABC", false)));
+        assert_eq!(runs.last().map(|(_, is_rtl)| *is_rtl), Some(true));
+        assert_eq!(
+            runs.iter().map(|(text, _)| text.len()).sum::<usize>(),
+            "القاهرة ABC".len()
+        );
+    }
+}
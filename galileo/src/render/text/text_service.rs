@@ -1,15 +1,16 @@
 //! Service for text rendering.
 
+use std::borrow::Cow;
 use std::sync::{Arc, OnceLock};
 
-use galileo_types::cartesian::Vector2;
+use galileo_types::cartesian::{Size, Vector2};
 use parking_lot::RwLock;
 use rustybuzz::ttf_parser::FaceParsingError;
 use thiserror::Error;
 
 use super::font_provider::FontProvider;
 use crate::render::text::font_provider::DefaultFontProvider;
-use crate::render::text::{TextRasterizer, TextShaping, TextStyle};
+use crate::render::text::{FontSizeUnit, TextRasterizer, TextShaping, TextStyle};
 
 static INSTANCE: OnceLock<TextService> = OnceLock::new();
 
@@ -27,6 +28,10 @@ pub enum FontServiceError {
     /// Font service is not initialized
     #[error("font service is not initialize")]
     NotInitialized,
+
+    /// The rasterizer cannot measure text
+    #[error("text measurement is not supported by the rasterizer")]
+    MeasurementNotSupported,
 }
 
 /// Provides common access to underlying text shaping engine implementation.
@@ -60,19 +65,58 @@ impl TextService {
     }
 
     /// Shape the given text input with the given style.
+    ///
+    /// Font sizes given in points are treated as logical pixels. Use [`TextService::shape_scaled`] to shape text
+    /// for a HiDPI display.
     pub fn shape(
         text: &str,
         style: &TextStyle,
         offset: Vector2<f32>,
+    ) -> Result<TextShaping, FontServiceError> {
+        Self::shape_scaled(text, style, offset, 1.0)
+    }
+
+    /// Shape the given text input with the given style for a display with the given scale factor.
+    ///
+    /// `scale_factor` is the number of physical pixels in a logical pixel of the display. It is used to convert
+    /// the font size into pixels if it is given in points.
+    pub fn shape_scaled(
+        text: &str,
+        style: &TextStyle,
+        offset: Vector2<f32>,
+        scale_factor: f32,
     ) -> Result<TextShaping, FontServiceError> {
         let Some(service) = Self::instance() else {
             return Err(FontServiceError::NotInitialized);
         };
 
-        service
-            .rasterizer
-            .read()
-            .shape(text, style, offset, &*service.font_provider)
+        shape_with_rasterizer(
+            &**service.rasterizer.read(),
+            text,
+            style,
+            offset,
+            scale_factor,
+            &*service.font_provider,
+        )
+    }
+
+    /// Measures the size of the text in pixels as it would be drawn with the given style.
+    ///
+    /// `scale_factor` has the same meaning as for [`TextService::shape_scaled`].
+    pub fn measure(
+        text: &str,
+        style: &TextStyle,
+        scale_factor: f32,
+    ) -> Result<Size<f32>, FontServiceError> {
+        let Some(service) = Self::instance() else {
+            return Err(FontServiceError::NotInitialized);
+        };
+
+        service.rasterizer.read().measure(
+            text,
+            &style_in_pixels(style, scale_factor),
+            &*service.font_provider,
+        )
     }
 
     /// Load all fonts from the given directory (recursevly).
@@ -100,3 +144,120 @@ impl TextService {
         }
     }
 }
+
+/// Shapes the text with the rasterizer, converting the font size of the style into physical pixels first.
+fn shape_with_rasterizer(
+    rasterizer: &dyn TextRasterizer,
+    text: &str,
+    style: &TextStyle,
+    offset: Vector2<f32>,
+    scale_factor: f32,
+    font_provider: &dyn FontProvider,
+) -> Result<TextShaping, FontServiceError> {
+    rasterizer.shape(
+        text,
+        &style_in_pixels(style, scale_factor),
+        offset,
+        font_provider,
+    )
+}
+
+/// Converts the font size of the style into physical pixels.
+fn style_in_pixels(style: &TextStyle, scale_factor: f32) -> Cow<'_, TextStyle> {
+    match style.font_size_unit {
+        FontSizeUnit::Pixels => Cow::Borrowed(style),
+        FontSizeUnit::Points => Cow::Owned(TextStyle {
+            font_size: style.font_size_px(scale_factor),
+            font_size_unit: FontSizeUnit::Pixels,
+            ..style.clone()
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::text::{GlyphVertex, TessellatedGlyph};
+
+    /// Rasterizer that draws every text as a single square glyph with the side equal to the font size.
+    struct SquareRasterizer;
+
+    impl TextRasterizer for SquareRasterizer {
+        fn shape(
+            &self,
+            _text: &str,
+            style: &TextStyle,
+            _offset: Vector2<f32>,
+            _font_provider: &dyn FontProvider,
+        ) -> Result<TextShaping, FontServiceError> {
+            let size = style.font_size;
+            let vertices = [[0.0, 0.0], [size, 0.0], [size, size], [0.0, size]]
+                .map(|position| GlyphVertex {
+                    position,
+                    color: style.font_color,
+                })
+                .to_vec();
+
+            Ok(TextShaping::Tessellation {
+                glyphs: vec![TessellatedGlyph {
+                    vertices,
+                    indices: vec![0, 1, 2, 0, 2, 3],
+                }],
+            })
+        }
+    }
+
+    fn glyph_height(style: &TextStyle, scale_factor: f32) -> f32 {
+        let shaping = shape_with_rasterizer(
+            &SquareRasterizer,
+            "A",
+            style,
+            Vector2::default(),
+            scale_factor,
+            &DefaultFontProvider::new(),
+        )
+        .expect("shaping succeeds");
+        let TextShaping::Tessellation { glyphs } = shaping else {
+            panic!("text is tessellated");
+        };
+
+        let ys = glyphs[0].vertices.iter().map(|v| v.position[1]);
+        ys.clone().fold(f32::MIN, f32::max) - ys.fold(f32::MAX, f32::min)
+    }
+
+    fn style(font_size_unit: FontSizeUnit) -> TextStyle {
+        TextStyle {
+            font_size: 12.0,
+            font_size_unit,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn points_are_scaled_with_scale_factor() {
+        let style = style(FontSizeUnit::Points);
+        assert!((glyph_height(&style, 1.0) - 16.0).abs() < 1e-4);
+        assert_eq!(glyph_height(&style, 2.0), 2.0 * glyph_height(&style, 1.0));
+    }
+
+    #[test]
+    fn default_measurement_uses_glyph_bounds() {
+        let style = style(FontSizeUnit::Points);
+        let size = SquareRasterizer
+            .measure(
+                "A",
+                &style_in_pixels(&style, 2.0),
+                &DefaultFontProvider::new(),
+            )
+            .expect("measurement succeeds");
+
+        assert!((size.width() - 32.0).abs() < 1e-4);
+        assert!((size.height() - 32.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn pixels_do_not_depend_on_scale_factor() {
+        let style = style(FontSizeUnit::Pixels);
+        assert_eq!(glyph_height(&style, 2.0), 12.0);
+    }
+}
@@ -0,0 +1,165 @@
+use std::time::Duration;
+
+use web_time::Instant;
+use wgpu::SurfaceError;
+
+use super::WgpuRenderer;
+use crate::map::Map;
+
+const DEFAULT_TILE_TIMEOUT: Duration = Duration::from_secs(10);
+const TILE_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Renders a map frame by frame with a fixed time step, e.g. to record an animation into a video.
+///
+/// Animations started with [`Map::animate_to`] are advanced by the time step of the recorder
+/// between frames instead of the wall clock time, so the produced frames do not depend on how
+/// long the rendering takes. Before every frame is rendered, the recorder waits until the layers
+/// of the map load the data for the current view, but not longer than the tile timeout.
+///
+/// The renderer must be created with a texture render target (see
+/// [`WgpuRenderer::new_with_texture_rt`]).
+pub struct FrameRecorder<'a> {
+    renderer: &'a WgpuRenderer,
+    timestep: Duration,
+    tile_timeout: Duration,
+}
+
+impl<'a> FrameRecorder<'a> {
+    /// Creates a new recorder that advances animations by `timestep` between frames.
+    pub fn new(renderer: &'a WgpuRenderer, timestep: Duration) -> Self {
+        Self {
+            renderer,
+            timestep,
+            tile_timeout: DEFAULT_TILE_TIMEOUT,
+        }
+    }
+
+    /// Creates a new recorder that produces `fps` frames per second of animation.
+    pub fn with_fps(renderer: &'a WgpuRenderer, fps: u32) -> Self {
+        Self::new(renderer, Duration::from_secs(1) / fps.max(1))
+    }
+
+    /// Sets the maximum time to wait for the layers to load before every frame. If the data is
+    /// not loaded in time, the frame is rendered with whatever is available.
+    pub fn with_tile_timeout(mut self, timeout: Duration) -> Self {
+        self.tile_timeout = timeout;
+        self
+    }
+
+    /// Time step between two frames.
+    pub fn timestep(&self) -> Duration {
+        self.timestep
+    }
+
+    /// Renders the current view of the map and returns the RGBA bitmap of the frame.
+    pub async fn render_frame(&self, map: &Map) -> Result<Vec<u8>, SurfaceError> {
+        self.wait_for_layers(map).await;
+        self.renderer.render(map)?;
+        self.renderer.get_image().await
+    }
+
+    /// Renders the current animation of the map frame by frame, calling `on_frame` with the
+    /// index and RGBA bitmap of every frame. The first frame shows the map before the animation
+    /// is advanced, the last one shows the target view of the animation.
+    ///
+    /// If the map is not animated, a single frame is rendered. Returns the number of rendered
+    /// frames.
+    pub async fn record(
+        &self,
+        map: &mut Map,
+        mut on_frame: impl FnMut(usize, Vec<u8>),
+    ) -> Result<usize, SurfaceError> {
+        let mut frames = AnimationFrames::new(self.timestep);
+        let mut count = 0;
+        while frames.next_frame(map) {
+            let frame = self.render_frame(map).await?;
+            on_frame(count, frame);
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    async fn wait_for_layers(&self, map: &Map) {
+        map.load_layers();
+
+        let started = Instant::now();
+        while !map.is_ready() {
+            if started.elapsed() >= self.tile_timeout {
+                log::warn!("Layers were not loaded in {:?}", self.tile_timeout);
+                return;
+            }
+
+            crate::async_runtime::sleep(TILE_POLL_INTERVAL).await;
+        }
+    }
+}
+
+/// Steps map animation by a fixed time step, one frame at a time.
+struct AnimationFrames {
+    timestep: Duration,
+    started: bool,
+}
+
+impl AnimationFrames {
+    fn new(timestep: Duration) -> Self {
+        Self {
+            timestep,
+            started: false,
+        }
+    }
+
+    /// Prepares the map for rendering the next frame. Returns `false` if there are no more frames.
+    fn next_frame(&mut self, map: &mut Map) -> bool {
+        if !self.started {
+            self.started = true;
+            return true;
+        }
+
+        if !map.is_animating() {
+            return false;
+        }
+
+        map.advance_animation(self.timestep);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use galileo_types::cartesian::Point2;
+
+    use super::*;
+    use crate::MapBuilder;
+
+    fn test_map() -> Map {
+        MapBuilder::default()
+            .with_projected_position(Point2::new(0.0, 0.0))
+            .with_resolution(1.0)
+            .build()
+    }
+
+    #[test]
+    fn animation_produces_frame_per_timestep() {
+        let mut map = test_map();
+        let target = map.view().with_resolution(2.0);
+        map.animate_to(target, Duration::from_millis(100));
+
+        let mut frames = AnimationFrames::new(Duration::from_millis(25));
+        let mut resolutions = vec![];
+        while frames.next_frame(&mut map) {
+            resolutions.push(map.view().resolution());
+        }
+
+        assert_eq!(resolutions, vec![1.0, 1.25, 1.5, 1.75, 2.0]);
+    }
+
+    #[test]
+    fn static_map_produces_single_frame() {
+        let mut map = test_map();
+        let mut frames = AnimationFrames::new(Duration::from_millis(25));
+
+        assert!(frames.next_frame(&mut map));
+        assert!(!frames.next_frame(&mut map));
+    }
+}
@@ -1,5 +1,4 @@
 use std::any::Any;
-use std::cmp::Ordering;
 use std::hash::{Hash, Hasher};
 use std::mem::size_of;
 use std::sync::{Arc, Weak};
@@ -9,7 +8,6 @@ use ahash::HashMap;
 use cfg_if::cfg_if;
 use effects::horizon::HorizonPipeline;
 use galileo_types::cartesian::{Rect, Size};
-use lyon::tessellation::VertexBuffers;
 use nalgebra::{Point4, Rotation3, Vector3};
 use parking_lot::Mutex;
 use wgpu::util::DeviceExt;
@@ -21,12 +19,16 @@ use wgpu::{
     TextureView, TextureViewDescriptor, WasmNotSendSync,
 };
 
-use super::render_bundle::screen_set::{RenderSetState, ScreenSetData};
-use super::{Canvas, PackedBundle, RenderOptions};
+use super::render_bundle::screen_set::{
+    resolve_collisions, RenderSetState, ScreenSetData, ScreenSetOrder,
+};
+use super::{Canvas, ImageSampling, PackedBundle, RenderOptions};
 use crate::decoded_image::DecodedImage;
 use crate::error::GalileoError;
 use crate::map::Map;
-use crate::render::render_bundle::world_set::{PointInstance, PolyVertex, WorldRenderSet};
+use crate::render::render_bundle::world_set::{
+    DrawCommand, DrawOrder, PointInstance, PolyVertex, WorldRenderSet,
+};
 use crate::render::render_bundle::RenderBundle;
 use crate::render::wgpu::pipelines::image::WgpuImage;
 use crate::render::wgpu::pipelines::Pipelines;
@@ -34,9 +36,13 @@ use crate::view::MapView;
 use crate::Color;
 
 mod effects;
+#[cfg(not(target_arch = "wasm32"))]
+mod frame_recorder;
 mod pipelines;
 
 pub use effects::horizon::HorizonOptions;
+#[cfg(not(target_arch = "wasm32"))]
+pub use frame_recorder::FrameRecorder;
 
 const DEFAULT_BACKGROUND: Color = Color::WHITE;
 const DEPTH_FORMAT: TextureFormat = TextureFormat::Depth24PlusStencil8;
@@ -52,6 +58,7 @@ pub struct WgpuRenderer {
     background: Color,
     textures: Mutex<TexturesMap>,
     horizon_options: Option<HorizonOptions>,
+    scale_factor: f32,
 }
 
 struct RendererTargets {
@@ -141,6 +148,7 @@ impl WgpuRenderer {
             background: DEFAULT_BACKGROUND,
             textures: Default::default(),
             horizon_options: Some(HorizonOptions::default()),
+            scale_factor: 1.0,
         })
     }
 
@@ -333,6 +341,7 @@ impl WgpuRenderer {
             background: DEFAULT_BACKGROUND,
             textures: Default::default(),
             horizon_options: Some(HorizonOptions::default()),
+            scale_factor: 1.0,
         };
         renderer.init_renderer_targets(render_target);
 
@@ -349,6 +358,7 @@ impl WgpuRenderer {
             background: DEFAULT_BACKGROUND,
             textures: Default::default(),
             horizon_options: Some(HorizonOptions::default()),
+            scale_factor: 1.0,
         };
 
         renderer.init_target_texture(size);
@@ -361,6 +371,18 @@ impl WgpuRenderer {
         self.background = color;
     }
 
+    /// Sets the number of physical pixels per logical pixel of the render target.
+    ///
+    /// Layers read this value from the canvas to scale line widths and font sizes on HiDPI displays.
+    pub fn set_scale_factor(&mut self, scale_factor: f32) {
+        self.scale_factor = scale_factor;
+    }
+
+    /// Number of physical pixels per logical pixel of the render target.
+    pub fn scale_factor(&self) -> f32 {
+        self.scale_factor
+    }
+
     /// Returns `true` if the renderer can be used to draw to.
     pub fn initialized(&self) -> bool {
         self.renderer_targets.is_some()
@@ -784,9 +806,29 @@ impl WgpuRenderer {
             .retain(|_, (image_ref, _)| image_ref.strong_count() > 0);
     }
 
-    fn get_or_create_image_texture(&self, image: &Arc<DecodedImage>) -> Arc<BindGroup> {
+    fn get_or_create_image_texture(
+        &self,
+        image: &Arc<DecodedImage>,
+        sampling: ImageSampling,
+    ) -> Arc<BindGroup> {
+        self.get_or_create_texture(image, sampling, wgpu::AddressMode::ClampToEdge)
+    }
+
+    /// Returns the texture of the image that is repeated when sampled outside of the image.
+    fn get_or_create_pattern_texture(&self, image: &Arc<DecodedImage>) -> Arc<BindGroup> {
+        self.get_or_create_texture(image, ImageSampling::Linear, wgpu::AddressMode::Repeat)
+    }
+
+    fn get_or_create_texture(
+        &self,
+        image: &Arc<DecodedImage>,
+        sampling: ImageSampling,
+        address_mode: wgpu::AddressMode,
+    ) -> Arc<BindGroup> {
         let mut hasher = ahash::AHasher::default();
         image.hash(&mut hasher);
+        sampling.hash(&mut hasher);
+        address_mode.hash(&mut hasher);
         let hash = hasher.finish();
 
         if let Some((_, texture)) = self.textures.lock().get(&hash) {
@@ -798,7 +840,7 @@ impl WgpuRenderer {
             .as_ref()
             .expect("trying to use pipelines of uninitialized renderer")
             .pipelines
-            .create_image_texture(&self.device, &self.queue, image);
+            .create_image_texture(&self.device, &self.queue, image, sampling, address_mode);
 
         self.textures
             .lock()
@@ -860,6 +902,10 @@ impl Canvas for WgpuCanvas<'_> {
         self.renderer.size()
     }
 
+    fn scale_factor(&self) -> f32 {
+        self.renderer.scale_factor()
+    }
+
     fn pack_bundle(&self, bundle: &RenderBundle) -> Box<dyn PackedBundle> {
         Box::new(WgpuPackedBundle::new(
             bundle,
@@ -973,7 +1019,7 @@ impl Canvas for WgpuCanvas<'_> {
         let screen_sets = std::mem::take(&mut self.screen_sets);
         let mut sets: Vec<_> = screen_sets
             .iter()
-            .map(|set| {
+            .filter_map(|set| {
                 let locked = set.lock();
                 let projected_anchor = transform
                     * Point4::new(
@@ -983,40 +1029,36 @@ impl Canvas for WgpuCanvas<'_> {
                         1.0,
                     );
                 let normalaized = projected_anchor / projected_anchor.w.abs();
+                if normalaized.w <= 0.0 {
+                    // The point is in imaginary plane
+                    return None;
+                }
+
+                let order = ScreenSetOrder {
+                    displayed: locked.state.is_displayed(),
+                    z_index: locked.z_index,
+                    priority: locked.priority,
+                    depth: normalaized.z,
+                    screen_y: normalaized.y,
+                };
 
-                (locked, normalaized)
+                Some((locked, normalaized, order))
             })
             .collect();
-        sets.sort_by(|a, b| {
-            let displayed_cmp = match (a.0.state.is_displayed(), b.0.state.is_displayed()) {
-                (true, false) => Ordering::Less,
-                (false, true) => Ordering::Greater,
-                _ => Ordering::Equal,
-            };
+        sets.sort_by(|a, b| a.2.collision_cmp(&b.2));
 
-            displayed_cmp.then(
-                a.1.z
-                    .partial_cmp(&b.1.z)
-                    .unwrap_or(std::cmp::Ordering::Equal),
-            )
-        });
+        let visible = resolve_collisions(sets.iter().map(|(set, anchor, _)| {
+            let dx = anchor.x * size.width() / 2.0;
+            let dy = anchor.y * size.height() / 2.0;
+            (set.bbox.shift(dx as f32, dy as f32), set.hide_on_overlay)
+        }));
 
         let now = web_time::Instant::now();
-        let mut displayed: Vec<Rect<f32>> = vec![];
-        let mut filtered_sets: Vec<_> = sets
+        let mut ordered_sets: Vec<_> = sets
             .into_iter()
-            .filter_map(|(mut set, anchor)| {
-                if anchor.w <= 0.0 {
-                    // The point is in imaginary plane
-                    return None;
-                }
-
-                let dx = anchor.x * size.width() / 2.0;
-                let dy = anchor.y * size.height() / 2.0;
-
-                let set_bbox = set.bbox.shift(dx as f32, dy as f32);
-
-                if set.hide_on_overlay && displayed.iter().any(|bbox| bbox.intersects(set_bbox)) {
+            .zip(visible)
+            .filter_map(|((mut set, _, order), is_visible)| {
+                let set = if !is_visible {
                     // Hiding the set
                     match set.state {
                         RenderSetState::Hidden => None,
@@ -1039,8 +1081,6 @@ impl Canvas for WgpuCanvas<'_> {
                     }
                 } else {
                     // Showing the set
-                    displayed.push(set_bbox);
-
                     match set.state {
                         RenderSetState::Hidden => {
                             set.state = RenderSetState::FadingIn {
@@ -1058,9 +1098,13 @@ impl Canvas for WgpuCanvas<'_> {
                     }
 
                     Some(set)
-                }
+                };
+
+                set.map(|set| (set, order))
             })
             .collect();
+        ordered_sets.sort_by(|a, b| a.1.draw_cmp(&b.1));
+        let mut filtered_sets: Vec<_> = ordered_sets.into_iter().map(|(set, _)| set).collect();
 
         let mut is_animating = false;
         let mut encoder =
@@ -1181,6 +1225,8 @@ struct WgpuPackedBundle {
     map_ref_buffers: WgpuVertexBuffers,
     dot_buffers: Option<WgpuDotBuffers>,
     image_buffers: Vec<WgpuImage>,
+    pattern_buffers: Vec<WgpuPatternFill>,
+    draw_commands: Vec<DrawCommand>,
 
     screen_sets: Vec<Arc<Mutex<WgpuScreenSet>>>,
 }
@@ -1191,6 +1237,8 @@ struct WgpuScreenSet {
     anchor_point: [f32; 3],
     bbox: Rect<f32>,
     hide_on_overlay: bool,
+    z_index: i32,
+    priority: f32,
     data: WgpuScreenSetData,
 }
 
@@ -1205,9 +1253,13 @@ struct WgpuVertexBuffers {
     index_count: u32,
 }
 
+struct WgpuPatternFill {
+    texture_bind_group: Arc<BindGroup>,
+    buffers: WgpuVertexBuffers,
+}
+
 struct WgpuDotBuffers {
     buffer: Buffer,
-    point_count: u32,
 }
 
 impl WgpuPackedBundle {
@@ -1219,21 +1271,30 @@ impl WgpuPackedBundle {
         let RenderBundle {
             world_set,
             screen_sets: bundle_screen_sets,
+            ..
         } = bundle;
         let WorldRenderSet {
             poly_tessellation,
-            points,
             images,
             clip_area,
             image_store,
+            pattern_fills,
             ..
         } = world_set;
 
         let clip_area_buffers = clip_area
             .as_ref()
-            .map(|v| Self::write_poly_buffers(v, renderer));
+            .map(|v| Self::write_poly_buffers(&v.vertices, &v.indices, renderer));
+
+        let DrawOrder {
+            pattern_indices,
+            indices,
+            points,
+            commands: draw_commands,
+        } = world_set.draw_order();
 
-        let poly_buffers = Self::write_poly_buffers(poly_tessellation, renderer);
+        let poly_buffers =
+            Self::write_poly_buffers(&poly_tessellation.vertices, &indices, renderer);
 
         let dot_buffers = if points.is_empty() {
             None
@@ -1244,33 +1305,45 @@ impl WgpuPackedBundle {
                     .create_buffer_init(&wgpu::util::BufferInitDescriptor {
                         label: None,
                         usage: wgpu::BufferUsages::VERTEX,
-                        contents: bytemuck::cast_slice(points),
+                        contents: bytemuck::cast_slice(&points),
                     });
-            let count = points.len();
             Some(WgpuDotBuffers {
                 buffer: point_instance_buffer,
-                point_count: count as u32,
             })
         };
 
-        let textures: Vec<_> = image_store
-            .iter()
-            .map(|decoded_image| renderer.get_or_create_image_texture(decoded_image))
-            .collect();
-
         let mut image_buffers = vec![];
         for image_info in images {
+            let decoded_image = image_store
+                .get(image_info.store_index)
+                .expect("image at index must exist");
+            let texture = renderer.get_or_create_image_texture(decoded_image, image_info.sampling);
             let image = renderer_targets.pipelines.image_pipeline().create_image(
                 &renderer.device,
-                textures
-                    .get(image_info.store_index)
-                    .expect("texture at index must exist")
-                    .clone(),
+                texture,
                 &image_info.vertices,
             );
             image_buffers.push(image);
         }
 
+        let pattern_buffers = pattern_fills
+            .iter()
+            .zip(&pattern_indices)
+            .map(|(fill, indices)| {
+                let image = image_store
+                    .get(fill.store_index)
+                    .expect("image at index must exist");
+                WgpuPatternFill {
+                    texture_bind_group: renderer.get_or_create_pattern_texture(image),
+                    buffers: Self::write_poly_buffers(
+                        &fill.tessellation.vertices,
+                        indices,
+                        renderer,
+                    ),
+                }
+            })
+            .collect();
+
         let mut screen_sets = vec![];
         for bundle_screen_set in bundle_screen_sets {
             let data = match &bundle_screen_set.data {
@@ -1302,7 +1375,8 @@ impl WgpuPackedBundle {
                     WgpuScreenSetData::Vertex(buffers)
                 }
                 ScreenSetData::Image { vertices, bitmap } => {
-                    let bind_group = renderer.get_or_create_image_texture(bitmap);
+                    let bind_group =
+                        renderer.get_or_create_image_texture(bitmap, ImageSampling::Linear);
                     let image = renderer_targets
                         .pipelines
                         .screen_set_image_pipeline()
@@ -1317,6 +1391,8 @@ impl WgpuPackedBundle {
                 anchor_point: bundle_screen_set.anchor_point,
                 bbox: bundle_screen_set.bbox,
                 hide_on_overlay: bundle_screen_set.hide_on_overlay,
+                z_index: bundle_screen_set.z_index,
+                priority: bundle_screen_set.priority,
                 data,
             })));
         }
@@ -1325,17 +1401,20 @@ impl WgpuPackedBundle {
             clip_area_buffers,
             map_ref_buffers: poly_buffers,
             image_buffers,
+            pattern_buffers,
             dot_buffers,
+            draw_commands,
             screen_sets,
         }
     }
 
-    fn write_poly_buffers(
-        tessellation: &VertexBuffers<PolyVertex, u32>,
+    fn write_poly_buffers<V: bytemuck::Pod>(
+        vertices: &[V],
+        indices: &[u32],
         renderer: &WgpuRenderer,
     ) -> WgpuVertexBuffers {
-        let index_bytes = bytemuck::cast_slice(&tessellation.indices);
-        let bytes = bytemuck::cast_slice(&tessellation.vertices);
+        let index_bytes = bytemuck::cast_slice(indices);
+        let bytes = bytemuck::cast_slice(vertices);
 
         let index = renderer
             .device
@@ -1356,7 +1435,7 @@ impl WgpuPackedBundle {
         WgpuVertexBuffers {
             index,
             vertex,
-            index_count: tessellation.indices.len() as u32,
+            index_count: indices.len() as u32,
         }
     }
 }
@@ -1477,3 +1556,92 @@ impl ScreenSetInstance {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+
+    use galileo_types::cartesian::{Point2, Rect};
+
+    use super::*;
+    use crate::layer::attribution::Attribution;
+    use crate::layer::Layer;
+    use crate::render::ImagePaint;
+    use crate::{MapBuilder, Messenger};
+
+    // Rows of the read back image must be aligned to `COPY_BYTES_PER_ROW_ALIGNMENT`.
+    const SIZE: u32 = 128;
+
+    /// Draws a 2x1 image with a red and a blue pixel stretched over the whole map.
+    struct TwoColorLayer {
+        sampling: ImageSampling,
+    }
+
+    impl Layer for TwoColorLayer {
+        fn render(&self, _view: &MapView, canvas: &mut dyn Canvas) {
+            let pixels = [Color::RED.to_u8_array(), Color::BLUE.to_u8_array()].concat();
+            let image = DecodedImage::from_raw(pixels, Size::new(2, 1)).expect("valid image");
+            let half = SIZE as f64 / 2.0;
+
+            let mut bundle = RenderBundle::default();
+            bundle.add_image_owned(
+                image,
+                Rect::new(-half, -half, half, half).into_quadrangle(),
+                ImagePaint {
+                    opacity: 255,
+                    sampling: self.sampling,
+                },
+            );
+            let packed = canvas.pack_bundle(&bundle);
+            canvas.draw_bundles(&[&*packed], RenderOptions::default());
+        }
+
+        fn prepare(&self, _view: &MapView) {}
+
+        fn set_messenger(&mut self, _messenger: Box<dyn Messenger>) {}
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn as_any_mut(&mut self) -> &mut dyn Any {
+            self
+        }
+
+        fn attribution(&self) -> Option<Attribution> {
+            None
+        }
+    }
+
+    /// Renders the layer and returns the color of a pixel in the middle row, close to the boundary
+    /// between the image pixels. Returns `None` if there is no GPU adapter available.
+    async fn pixel_near_boundary(sampling: ImageSampling) -> Option<[u8; 4]> {
+        let renderer = WgpuRenderer::new_with_texture_rt(Size::new(SIZE, SIZE)).await?;
+        let mut map = MapBuilder::default()
+            .with_projected_position(Point2::new(0.0, 0.0))
+            .with_resolution(1.0)
+            .with_layer(TwoColorLayer { sampling })
+            .build();
+        map.set_size(Size::new(SIZE as f64, SIZE as f64));
+
+        renderer.render(&map).expect("render succeeds");
+        let image = renderer.get_image().await.expect("image is read");
+
+        let offset = ((SIZE / 2 * SIZE + SIZE / 2 - 5) * 4) as usize;
+        Some([0, 1, 2, 3].map(|i| image[offset + i]))
+    }
+
+    #[tokio::test]
+    async fn nearest_sampling_keeps_pixel_colors() {
+        let Some(nearest) = pixel_near_boundary(ImageSampling::Nearest).await else {
+            log::warn!("No GPU adapter available, skipping the test");
+            return;
+        };
+        assert_eq!(nearest, Color::RED.to_u8_array());
+
+        let linear = pixel_near_boundary(ImageSampling::Linear)
+            .await
+            .expect("adapter is available");
+        assert!(linear[0] > 0 && linear[2] > 0, "{linear:?} is not blended");
+    }
+}
@@ -1,3 +1,5 @@
+use std::ops::Range;
+
 use wgpu::{
     BindGroupLayout, CompareFunction, DepthStencilState, Device, RenderPass, RenderPipeline,
     RenderPipelineDescriptor, StencilFaceState, StencilOperation, StencilState, TextureFormat,
@@ -79,6 +81,7 @@ impl DotPipeline {
     pub fn render<'a>(
         &'a self,
         buffers: &'a WgpuDotBuffers,
+        points: Range<u32>,
         render_pass: &mut RenderPass<'a>,
         render_options: RenderOptions,
         bundle_index: u32,
@@ -90,6 +93,6 @@ impl DotPipeline {
         }
 
         render_pass.set_vertex_buffer(0, buffers.buffer.slice(..));
-        render_pass.draw(0..buffers.point_count, bundle_index..(bundle_index + 1));
+        render_pass.draw(points, bundle_index..(bundle_index + 1));
     }
 }
@@ -122,12 +122,12 @@ impl ImageVertex {
                     offset: (std::mem::size_of::<[f32; 2]>() + std::mem::size_of::<f32>())
                         as wgpu::BufferAddress,
                     shader_location: 2,
-                    format: wgpu::VertexFormat::Float32x2,
+                    format: wgpu::VertexFormat::Float32x3,
                 },
                 wgpu::VertexAttribute {
                     offset: (std::mem::size_of::<[f32; 2]>()
                         + std::mem::size_of::<f32>()
-                        + std::mem::size_of::<[f32; 2]>())
+                        + std::mem::size_of::<[f32; 3]>())
                         as wgpu::BufferAddress,
                     shader_location: 3,
                     format: wgpu::VertexFormat::Float32x2,
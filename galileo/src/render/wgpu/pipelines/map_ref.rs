@@ -1,3 +1,5 @@
+use std::ops::Range;
+
 use wgpu::{BindGroupLayout, Device, RenderPass, RenderPipeline, TextureFormat};
 
 use crate::render::render_bundle::world_set::PolyVertex;
@@ -41,6 +43,7 @@ impl MapRefPipeline {
     pub fn render<'a>(
         &'a self,
         buffers: &'a WgpuVertexBuffers,
+        indices: Range<u32>,
         render_pass: &mut RenderPass<'a>,
         render_options: RenderOptions,
         bundle_index: u32,
@@ -52,6 +55,6 @@ impl MapRefPipeline {
         }
         render_pass.set_vertex_buffer(0, buffers.vertex.slice(..));
         render_pass.set_index_buffer(buffers.index.slice(..), wgpu::IndexFormat::Uint32);
-        render_pass.draw_indexed(0..buffers.index_count, 0, bundle_index..(bundle_index + 1));
+        render_pass.draw_indexed(indices, 0, bundle_index..(bundle_index + 1));
     }
 }
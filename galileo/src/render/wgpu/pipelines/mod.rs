@@ -12,17 +12,20 @@ use wgpu::{
 
 use super::WgpuScreenSetData;
 use crate::decoded_image::{DecodedImage, DecodedImageType};
+use crate::render::render_bundle::world_set::DrawCommand;
 use crate::render::wgpu::pipelines::clip::ClipPipeline;
 use crate::render::wgpu::pipelines::dot::DotPipeline;
 use crate::render::wgpu::pipelines::image::ImagePipeline;
 use crate::render::wgpu::pipelines::map_ref::MapRefPipeline;
+use crate::render::wgpu::pipelines::pattern::PatternPipeline;
 use crate::render::wgpu::{ViewUniform, WgpuPackedBundle, DEPTH_FORMAT};
-use crate::render::RenderOptions;
+use crate::render::{ImageSampling, RenderOptions};
 
 mod clip;
 mod dot;
 pub mod image;
 mod map_ref;
+mod pattern;
 mod screen_set_image;
 mod screen_set_vertex;
 
@@ -34,6 +37,7 @@ pub struct Pipelines {
 
     image: ImagePipeline,
     map_ref: MapRefPipeline,
+    pattern: PatternPipeline,
     clip: ClipPipeline,
     dot: DotPipeline,
     screen_set: ScreenSetPipeline,
@@ -108,6 +112,12 @@ impl Pipelines {
                 &texture_bind_group_layout,
             ),
             map_ref: MapRefPipeline::create(device, format, &map_view_bind_group_layout),
+            pattern: PatternPipeline::create(
+                device,
+                format,
+                &map_view_bind_group_layout,
+                &texture_bind_group_layout,
+            ),
             clip: ClipPipeline::create(device, format, &map_view_bind_group_layout),
             dot: DotPipeline::create(device, format, &map_view_bind_group_layout),
             screen_set: ScreenSetPipeline::create(device, format, &map_view_bind_group_layout),
@@ -133,28 +143,45 @@ impl Pipelines {
             self.clip.clip(clip, render_pass, render_options);
         }
 
-        for image in &bundle.image_buffers {
-            self.image
-                .render(image, render_pass, render_options, bundle_index);
-        }
-
-        if bundle.map_ref_buffers.index_count > 0 {
-            self.map_ref.render(
-                &bundle.map_ref_buffers,
-                render_pass,
-                render_options,
-                bundle_index,
-            );
+        for command in &bundle.draw_commands {
+            match command {
+                DrawCommand::Image(index) => self.image.render(
+                    &bundle.image_buffers[*index],
+                    render_pass,
+                    render_options,
+                    bundle_index,
+                ),
+                DrawCommand::Pattern { fill, indices } => self.pattern.render(
+                    &bundle.pattern_buffers[*fill],
+                    indices.clone(),
+                    render_pass,
+                    render_options,
+                    bundle_index,
+                ),
+                DrawCommand::Polygons(indices) => self.map_ref.render(
+                    &bundle.map_ref_buffers,
+                    indices.clone(),
+                    render_pass,
+                    render_options,
+                    bundle_index,
+                ),
+                DrawCommand::Points(points) => {
+                    if let Some(dot_buffers) = &bundle.dot_buffers {
+                        self.dot.render(
+                            dot_buffers,
+                            points.clone(),
+                            render_pass,
+                            render_options,
+                            bundle_index,
+                        );
+                    }
+                }
+            }
         }
 
         if let Some(clip) = &bundle.clip_area_buffers {
             self.clip.unclip(clip, render_pass, render_options);
         }
-
-        if let Some(dot_buffers) = &bundle.dot_buffers {
-            self.dot
-                .render(dot_buffers, render_pass, render_options, bundle_index);
-        }
     }
 
     pub fn map_view_buffer(&self) -> &Buffer {
@@ -197,6 +224,8 @@ impl Pipelines {
         device: &Device,
         queue: &Queue,
         image: &DecodedImage,
+        sampling: ImageSampling,
+        address_mode: wgpu::AddressMode,
     ) -> Arc<BindGroup> {
         let texture_size = wgpu::Extent3d {
             width: image.width(),
@@ -260,12 +289,16 @@ impl Pipelines {
 
         let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
 
+        let filter = match sampling {
+            ImageSampling::Nearest => wgpu::FilterMode::Nearest,
+            ImageSampling::Linear => wgpu::FilterMode::Linear,
+        };
         let diffuse_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Linear,
+            address_mode_u: address_mode,
+            address_mode_v: address_mode,
+            address_mode_w: address_mode,
+            mag_filter: filter,
+            min_filter: filter,
             mipmap_filter: wgpu::FilterMode::Nearest,
             ..Default::default()
         });
@@ -0,0 +1,109 @@
+use std::ops::Range;
+
+use wgpu::{BindGroup, BindGroupLayout, Device, RenderPass, RenderPipeline, TextureFormat};
+
+use crate::render::render_bundle::world_set::PatternVertex;
+use crate::render::wgpu::pipelines::default_targets;
+use crate::render::wgpu::{pipelines, DisplayInstance, WgpuPatternFill};
+use crate::render::RenderOptions;
+
+pub struct PatternPipeline {
+    wgpu_pipeline: RenderPipeline,
+    pub wgpu_pipeline_antialias: RenderPipeline,
+}
+
+impl PatternPipeline {
+    pub fn create(
+        device: &Device,
+        format: TextureFormat,
+        map_view_layout: &BindGroupLayout,
+        texture_bind_group_layout: &BindGroupLayout,
+    ) -> Self {
+        let shader = device.create_shader_module(wgpu::include_wgsl!("./shaders/pattern.wgsl"));
+        let buffers = [PatternVertex::wgpu_desc(), DisplayInstance::wgpu_desc()];
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[map_view_layout, texture_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let targets = default_targets(format);
+        let mut desc =
+            pipelines::default_pipeline_descriptor(&layout, &shader, &targets, &buffers, false);
+        let wgpu_pipeline = device.create_render_pipeline(&desc);
+
+        desc.multisample.count = 4;
+        let wgpu_pipeline_antialias = device.create_render_pipeline(&desc);
+
+        Self {
+            wgpu_pipeline,
+            wgpu_pipeline_antialias,
+        }
+    }
+
+    pub fn render<'a>(
+        &'a self,
+        fill: &'a WgpuPatternFill,
+        indices: Range<u32>,
+        render_pass: &mut RenderPass<'a>,
+        render_options: RenderOptions,
+        bundle_index: u32,
+    ) {
+        if render_options.antialias {
+            render_pass.set_pipeline(&self.wgpu_pipeline_antialias);
+        } else {
+            render_pass.set_pipeline(&self.wgpu_pipeline);
+        }
+
+        let bind_group: &BindGroup = &fill.texture_bind_group;
+        render_pass.set_bind_group(1, bind_group, &[]);
+        render_pass.set_vertex_buffer(0, fill.buffers.vertex.slice(..));
+        render_pass.set_index_buffer(fill.buffers.index.slice(..), wgpu::IndexFormat::Uint32);
+        render_pass.draw_indexed(indices, 0, bundle_index..(bundle_index + 1));
+    }
+}
+
+impl PatternVertex {
+    fn wgpu_desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<PatternVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: (std::mem::size_of::<[f32; 3]>() + std::mem::size_of::<[f32; 4]>())
+                        as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: (std::mem::size_of::<[f32; 3]>()
+                        + std::mem::size_of::<[f32; 4]>()
+                        + std::mem::size_of::<[f32; 2]>())
+                        as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32,
+                },
+                wgpu::VertexAttribute {
+                    offset: (std::mem::size_of::<[f32; 3]>()
+                        + std::mem::size_of::<[f32; 4]>()
+                        + std::mem::size_of::<[f32; 2]>()
+                        + std::mem::size_of::<f32>())
+                        as wgpu::BufferAddress,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Float32,
+                },
+            ],
+        }
+    }
+}
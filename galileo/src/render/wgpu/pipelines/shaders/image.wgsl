@@ -13,14 +13,14 @@ var<uniform> transform: ViewUniform;
 struct VertexInput {
     @location(0) position: vec2<f32>,
     @location(1) opacity: f32,
-    @location(2) tex_coord: vec2<f32>,
+    @location(2) tex_coord: vec3<f32>,
     @location(3) offset: vec2<f32>,
     @location(10) bundle_opacity: f32,
 }
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
-    @location(1) tex_coord: vec2<f32>,
+    @location(1) tex_coord: vec3<f32>,
     @location(2) opacity: f32,
 };
 
@@ -50,7 +50,7 @@ var s_diffuse: sampler;
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    var color = textureSample(t_diffuse, s_diffuse, in.tex_coord);
+    var color = textureSample(t_diffuse, s_diffuse, in.tex_coord.xy / in.tex_coord.z);
     color[3] = color[3] * in.opacity;
 
     if color[3] == 0.0 {
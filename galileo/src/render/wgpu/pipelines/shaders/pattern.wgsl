@@ -0,0 +1,70 @@
+// Vertex shader
+
+struct ViewUniform {
+    view_proj: mat4x4<f32>,
+    view_rotation: mat4x4<f32>,
+    inv_screen_size: vec2<f32>,
+    resolution: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> transform: ViewUniform;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) pattern_position: vec2<f32>,
+    @location(3) texel_size: f32,
+    @location(4) scale: f32,
+    @location(10) bundle_opacity: f32,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) texel_position: vec2<f32>,
+    @location(3) opacity: f32,
+};
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.color = model.color;
+    out.opacity = model.bundle_opacity;
+
+    // Screen space patterns have no fixed size in map units, so it is calculated from the current resolution.
+    var texel_size = model.texel_size;
+    if (texel_size == 0.0) {
+        texel_size = model.scale * transform.resolution;
+    }
+
+    // Map Y axis points up, while image rows go down.
+    out.texel_position = vec2<f32>(model.pattern_position.x, -model.pattern_position.y) / texel_size;
+    out.clip_position = transform.view_proj * vec4<f32>(model.position, 1.0);
+
+    return out;
+}
+
+
+// Fragment shader
+
+@group(1) @binding(0)
+var t_pattern: texture_2d<f32>;
+@group(1) @binding(1)
+var s_pattern: sampler;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let size = vec2<f32>(textureDimensions(t_pattern));
+    let pattern = textureSample(t_pattern, s_pattern, in.texel_position / size);
+
+    let alpha = pattern.a + in.color.a * (1.0 - pattern.a);
+    if alpha == 0.0 {
+        discard;
+    }
+
+    let rgb = (pattern.rgb * pattern.a + in.color.rgb * in.color.a * (1.0 - pattern.a)) / alpha;
+    return vec4<f32>(rgb, alpha * in.opacity);
+}
@@ -8,6 +8,7 @@ use galileo_types::geo::Crs;
 use js_sys::wasm_bindgen::prelude::wasm_bindgen;
 use serde::{Deserialize, Serialize};
 
+use crate::error::GalileoError;
 use crate::lod::Lod;
 use crate::view::MapView;
 
@@ -22,6 +23,15 @@ pub enum VerticalDirection {
     BottomToTop,
 }
 
+/// Corner of the tile schema bounds where the tile with `X == 0, Y == 0` is located.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum OriginCorner {
+    /// Top left corner, with the Y index growing downwards (XYZ or "slippy map" convention).
+    TopLeft,
+    /// Bottom left corner, with the Y index growing upwards (TMS convention).
+    BottomLeft,
+}
+
 /// Index of a tile.
 #[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, Serialize, Deserialize)]
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
@@ -45,6 +55,19 @@ impl TileIndex {
             display_x: x,
         }
     }
+
+    /// Index of the tile one level up that contains this tile, assuming every tile is split into
+    /// four tiles on the next level.
+    ///
+    /// Returns `None` for tiles with `z == 0`.
+    pub fn parent(&self) -> Option<TileIndex> {
+        Some(Self {
+            z: self.z.checked_sub(1)?,
+            x: self.x.div_euclid(2),
+            y: self.y.div_euclid(2),
+            display_x: self.display_x.div_euclid(2),
+        })
+    }
 }
 
 /// Tile schema specifies how tile indices are calculated based on the map position and resolution.
@@ -78,6 +101,97 @@ impl TileSchema {
         None
     }
 
+    /// Returns the schema with only the levels of detail from `min_zoom` to `max_zoom` inclusive.
+    ///
+    /// Tiles of the removed levels are never requested. When the map is zoomed in beyond
+    /// `max_zoom`, the tiles of `max_zoom` are stretched (overzoomed), and when it is zoomed out
+    /// beyond `min_zoom`, the tiles of `min_zoom` are shrunk. Note that the number of displayed
+    /// tiles grows fourfold with every z-level the map is zoomed out beyond `min_zoom`.
+    ///
+    /// Returns an error if no levels of the schema are in the range.
+    ///
+    /// ```
+    /// use galileo::TileSchema;
+    ///
+    /// let schema = TileSchema::web(18).with_zoom_range(2, 14)?;
+    /// assert_eq!(schema.lod_resolution(1), None);
+    /// assert_eq!(schema.select_lod(1.0).map(|lod| lod.z_index()), Some(14));
+    /// # Ok::<(), galileo::error::GalileoError>(())
+    /// ```
+    pub fn with_zoom_range(mut self, min_zoom: u32, max_zoom: u32) -> Result<Self, GalileoError> {
+        self.lods
+            .retain(|lod| (min_zoom..=max_zoom).contains(&lod.z_index()));
+        if self.lods.is_empty() {
+            return Err(GalileoError::Configuration(format!(
+                "tile schema has no levels of detail in the zoom range {min_zoom}..={max_zoom}"
+            )));
+        }
+
+        Ok(self)
+    }
+
+    /// Places the origin of the schema in the given corner of its bounds and sets the direction of
+    /// the Y index away from it.
+    ///
+    /// ```
+    /// use galileo::tile_schema::{OriginCorner, TileIndex, VerticalDirection};
+    /// use galileo::TileSchema;
+    ///
+    /// let schema = TileSchema::web(18).with_origin_corner(OriginCorner::BottomLeft);
+    /// assert_eq!(schema.y_direction, VerticalDirection::BottomToTop);
+    ///
+    /// let bottom_left = schema.tile_bbox(TileIndex::new(0, 0, 1)).expect("level exists");
+    /// assert_eq!(bottom_left.y_min(), schema.bounds.y_min());
+    /// ```
+    pub fn with_origin_corner(mut self, corner: OriginCorner) -> Self {
+        (self.origin, self.y_direction) = match corner {
+            OriginCorner::TopLeft => (
+                Point2::new(self.bounds.x_min(), self.bounds.y_max()),
+                VerticalDirection::TopToBottom,
+            ),
+            OriginCorner::BottomLeft => (
+                Point2::new(self.bounds.x_min(), self.bounds.y_min()),
+                VerticalDirection::BottomToTop,
+            ),
+        };
+
+        self
+    }
+
+    /// Index of the tile of the z-level `z` that contains the `point`, or `None` if the schema does
+    /// not have this z-level. The point is given in the CRS of the schema and is not checked to
+    /// be inside the schema bounds.
+    pub fn tile_index(
+        &self,
+        point: &impl CartesianPoint2d<Num = f64>,
+        z: u32,
+    ) -> Option<TileIndex> {
+        let resolution = self.lod_resolution(z)?;
+        let x = self.x_adj(point.x()) / (resolution * self.tile_width as f64);
+        let y = self.y_adj(point.y()) / (resolution * self.tile_height as f64);
+
+        Some(TileIndex::new(x.floor() as i32, y.floor() as i32, z))
+    }
+
+    /// Y index of the tile counted from the opposite edge of the schema bounds, or `None` if the
+    /// schema does not have the z-level of the tile.
+    ///
+    /// This is the row number used by the servers with the other tile numbering convention, e.g.
+    /// by TMS servers for the tiles of [`TileSchema::web`]. It is substituted for the `{-y}`
+    /// placeholder of the tile URL templates.
+    ///
+    /// ```
+    /// use galileo::tile_schema::TileIndex;
+    /// use galileo::TileSchema;
+    ///
+    /// let schema = TileSchema::web(18);
+    /// assert_eq!(schema.opposite_y(&TileIndex::new(5, 2, 3)), Some(5));
+    /// ```
+    pub fn opposite_y(&self, index: &TileIndex) -> Option<i32> {
+        let resolution = self.lod_resolution(index.z)?;
+        Some(self.min_y_index(resolution) + self.max_y_index(resolution) - index.y)
+    }
+
     /// Width of a single tile.
     pub fn tile_width(&self) -> u32 {
         self.tile_width
@@ -88,6 +202,38 @@ impl TileSchema {
         self.tile_height
     }
 
+    /// Resolution at the given (possibly fractional) zoom level.
+    ///
+    /// Zoom level `0` corresponds to the resolution at which the whole width of the schema bounds
+    /// fits into a single tile, and every next level halves the resolution.
+    pub fn zoom_to_resolution(&self, zoom: f64) -> f64 {
+        self.top_resolution() / 2f64.powf(zoom)
+    }
+
+    /// Zoom level corresponding to the given resolution. This is the inverse of
+    /// [`TileSchema::zoom_to_resolution`], so the returned value is usually fractional.
+    pub fn resolution_to_zoom(&self, resolution: f64) -> f64 {
+        (self.top_resolution() / resolution).log2()
+    }
+
+    /// Size of a pixel on the ground at the given latitude in degrees for the given map
+    /// resolution.
+    ///
+    /// For Web Mercator the scale of the projection grows with latitude, so the ground resolution
+    /// is smaller than the map resolution everywhere except the equator. For other CRSs the
+    /// resolution is returned unchanged.
+    pub fn ground_resolution(&self, resolution: f64, latitude: f64) -> f64 {
+        if self.crs == Crs::EPSG3857 {
+            resolution * latitude.to_radians().cos()
+        } else {
+            resolution
+        }
+    }
+
+    fn top_resolution(&self) -> f64 {
+        self.bounds.width() / self.tile_width as f64
+    }
+
     /// Select a level of detail for the given resolution.
     pub fn select_lod(&self, resolution: f64) -> Option<Lod> {
         if !resolution.is_finite() {
@@ -118,7 +264,7 @@ impl TileSchema {
         self.iter_tiles_over_bbox(resolution, bounding_box)
     }
 
-    fn iter_tiles_over_bbox(
+    pub(crate) fn iter_tiles_over_bbox(
         &self,
         resolution: f64,
         bounding_box: Rect,
@@ -209,7 +355,9 @@ impl TileSchema {
         }
     }
 
-    pub(crate) fn tile_bbox(&self, index: TileIndex) -> Option<Rect> {
+    /// Area covered by the tile in the CRS of the schema, or `None` if the schema does not have the
+    /// z-level of the tile.
+    pub fn tile_bbox(&self, index: TileIndex) -> Option<Rect> {
         let x_index = index.display_x;
         let y_index = index.y;
 
@@ -275,7 +423,7 @@ impl TileSchema {
     fn min_y_index(&self, resolution: f64) -> i32 {
         match self.y_direction {
             VerticalDirection::TopToBottom => {
-                ((self.bounds.y_min() + self.origin.y()) / resolution / self.tile_height as f64)
+                ((self.origin.y() - self.bounds.y_max()) / resolution / self.tile_height as f64)
                     .floor() as i32
             }
             VerticalDirection::BottomToTop => {
@@ -287,7 +435,7 @@ impl TileSchema {
 
     fn max_y_index(&self, resolution: f64) -> i32 {
         let pix_bound = match self.y_direction {
-            VerticalDirection::TopToBottom => (self.bounds.y_max() + self.origin.y()) / resolution,
+            VerticalDirection::TopToBottom => (self.origin.y() - self.bounds.y_min()) / resolution,
             VerticalDirection::BottomToTop => (self.bounds.y_max() - self.origin.y()) / resolution,
         };
         let floored = pix_bound.floor();
@@ -302,6 +450,8 @@ impl TileSchema {
 #[cfg(test)]
 mod tests {
     use galileo_types::cartesian::Size;
+    use galileo_types::geo::impls::GeoPoint2d;
+    use galileo_types::geo::{GeoPoint, NewGeoPoint};
 
     use super::*;
 
@@ -343,6 +493,38 @@ mod tests {
         assert_eq!(schema.select_lod(1.0).unwrap().z_index(), 2);
     }
 
+    #[test]
+    fn zoom_range_limits_selected_lods() {
+        let schema = simple_schema().with_zoom_range(1, 1).unwrap();
+        assert_eq!(schema.select_lod(16.0).unwrap().z_index(), 1);
+        assert_eq!(schema.select_lod(1.0).unwrap().z_index(), 1);
+
+        let bbox = Rect::new(0.0, 0.0, 2048.0, 2048.0);
+        assert!(schema
+            .iter_tiles(&get_view(1.0, bbox))
+            .unwrap()
+            .all(|index| index.z == 1));
+
+        assert!(simple_schema().with_zoom_range(3, 5).is_err());
+    }
+
+    #[test]
+    fn zoom_resolution_conversion() {
+        let schema = TileSchema::web(18);
+        assert!((schema.zoom_to_resolution(0.0) - 156543.03392800014).abs() < 1e-6);
+        assert!((schema.zoom_to_resolution(5.0) - schema.lod_resolution(5).unwrap()).abs() < 1e-6);
+
+        for zoom in [0.0, 1.5, 7.0, 17.25] {
+            let resolution = schema.zoom_to_resolution(zoom);
+            assert!((schema.resolution_to_zoom(resolution) - zoom).abs() < 1e-9);
+        }
+
+        let resolution = schema.zoom_to_resolution(3.0);
+        assert_eq!(schema.ground_resolution(resolution, 0.0), resolution);
+        assert!((schema.ground_resolution(resolution, 60.0) - resolution / 2.0).abs() < 1e-6);
+        assert_eq!(simple_schema().zoom_to_resolution(1.0), 4.0);
+    }
+
     #[test]
     fn iter_indices_full_bbox() {
         let schema = simple_schema();
@@ -409,6 +591,64 @@ mod tests {
         }
     }
 
+    #[test]
+    fn geo_point_round_trips_through_tile_index() {
+        let projection = Crs::EPSG3857
+            .get_projection::<GeoPoint2d, Point2>()
+            .unwrap();
+        let point = projection
+            .project(&GeoPoint2d::latlon(55.75, 37.62))
+            .unwrap();
+
+        for corner in [OriginCorner::TopLeft, OriginCorner::BottomLeft] {
+            let schema = TileSchema::web(18).with_origin_corner(corner);
+            let index = schema.tile_index(&point, 10).unwrap();
+            let bbox = schema.tile_bbox(index).unwrap();
+            assert!(bbox.contains(&point), "{corner:?}: {index:?} {bbox:?}");
+
+            let geo_center = projection.unproject(&bbox.center()).unwrap();
+            assert!((geo_center.lat() - 55.75).abs() < 0.3);
+            assert!((geo_center.lon() - 37.62).abs() < 0.3);
+        }
+    }
+
+    #[test]
+    fn origin_corners_number_rows_from_opposite_edges() {
+        let xyz = TileSchema::web(18);
+        let tms = TileSchema::web(18).with_origin_corner(OriginCorner::BottomLeft);
+        let point = Point2::new(1_000_000.0, 5_000_000.0);
+
+        let xyz_index = xyz.tile_index(&point, 4).unwrap();
+        let tms_index = tms.tile_index(&point, 4).unwrap();
+        assert_eq!(xyz_index.x, tms_index.x);
+        assert_eq!(xyz.opposite_y(&xyz_index), Some(tms_index.y));
+        assert_eq!(tms.opposite_y(&tms_index), Some(xyz_index.y));
+
+        let xyz_bbox = xyz.tile_bbox(xyz_index).unwrap();
+        let tms_bbox = tms.tile_bbox(tms_index).unwrap();
+        // The bounds of the web schema are a few micrometers off the grid of its levels.
+        assert!((xyz_bbox.y_min() - tms_bbox.y_min()).abs() < 1e-3);
+        assert!((xyz_bbox.y_max() - tms_bbox.y_max()).abs() < 1e-3);
+    }
+
+    #[test]
+    fn custom_origin_is_respected() {
+        let mut schema = simple_schema().with_origin_corner(OriginCorner::TopLeft);
+        assert_eq!(schema.origin, Point2::new(0.0, 2048.0));
+        assert_eq!(schema.opposite_y(&TileIndex::new(0, 0, 0)), Some(0));
+        assert_eq!(schema.opposite_y(&TileIndex::new(0, 0, 2)), Some(3));
+
+        schema.bounds = Rect::new(0.0, 1024.0, 2048.0, 3072.0);
+        schema.origin = Point2::new(0.0, 3072.0);
+        let index = schema.tile_index(&Point2::new(100.0, 3000.0), 2).unwrap();
+        assert_eq!(index, TileIndex::new(0, 0, 2));
+        assert_eq!(schema.opposite_y(&index), Some(3));
+        assert_eq!(
+            schema.tile_bbox(index),
+            Some(Rect::new(0.0, 2560.0, 512.0, 3072.0))
+        );
+    }
+
     #[test]
     fn iter_tiles_outside_of_bbox() {
         let schema = simple_schema();
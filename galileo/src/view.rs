@@ -1,3 +1,5 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
 use galileo_types::cartesian::{
     CartesianPoint2d, CartesianPoint3d, Point2, Point3, Rect, Size, Vector2, Vector3,
 };
@@ -25,6 +27,13 @@ pub struct MapView {
     rotation_z: f64,
     size: Size,
     crs: Crs,
+    generation: u64,
+}
+
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+fn next_generation() -> u64 {
+    NEXT_GENERATION.fetch_add(1, Ordering::Relaxed)
 }
 
 impl MapView {
@@ -46,6 +55,7 @@ impl MapView {
             rotation_x: 0.0,
             size: Default::default(),
             crs,
+            generation: next_generation(),
         }
     }
 
@@ -67,9 +77,20 @@ impl MapView {
             rotation_x: 0.0,
             size: Default::default(),
             crs,
+            generation: next_generation(),
         }
     }
 
+    /// Generation of the view.
+    ///
+    /// Every method that creates a new view assigns it a new unique generation, while clones of a
+    /// view share the generation of the original. So if two views have the same generation, they
+    /// are guaranteed to be identical, which allows caching values calculated for a view without
+    /// comparing all of its parameters.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
     /// CRS of the view.
     pub fn crs(&self) -> &Crs {
         &self.crs
@@ -104,6 +125,7 @@ impl MapView {
         Self {
             projected_position,
             crs: self.crs.clone(),
+            generation: next_generation(),
             ..*self
         }
     }
@@ -118,6 +140,7 @@ impl MapView {
         Self {
             resolution,
             crs: self.crs.clone(),
+            generation: next_generation(),
             ..*self
         }
     }
@@ -132,6 +155,7 @@ impl MapView {
         Self {
             size: new_size,
             crs: self.crs.clone(),
+            generation: next_generation(),
             ..*self
         }
     }
@@ -302,6 +326,7 @@ impl MapView {
         Self {
             rotation_x,
             crs: self.crs.clone(),
+            generation: next_generation(),
             ..*self
         }
     }
@@ -311,6 +336,7 @@ impl MapView {
         Self {
             rotation_z,
             crs: self.crs.clone(),
+            generation: next_generation(),
             ..*self
         }
     }
@@ -321,6 +347,7 @@ impl MapView {
             rotation_x,
             rotation_z,
             crs: self.crs.clone(),
+            generation: next_generation(),
             ..*self
         }
     }
@@ -405,11 +432,13 @@ impl MapView {
                 Self {
                     projected_position: Some(projected_position),
                     crs: self.crs.clone(),
+                    generation: next_generation(),
                     ..*self
                 }
             }
             None => Self {
                 crs: self.crs.clone(),
+                generation: next_generation(),
                 ..*self
             },
         }
@@ -431,23 +460,7 @@ impl MapView {
             projected_position: new_position,
             resolution,
             crs: self.crs.clone(),
-            ..*self
-        }
-    }
-
-    pub(crate) fn interpolate(&self, target: &MapView, k: f64) -> Self {
-        let Some(source_position) = self.projected_position else {
-            return self.clone();
-        };
-        let Some(target_position) = target.projected_position else {
-            return self.clone();
-        };
-
-        let projected_position = source_position + (target_position - source_position) * k;
-        Self {
-            projected_position: Some(projected_position),
-            resolution: self.resolution + (target.resolution - self.resolution) * k,
-            crs: self.crs.clone(),
+            generation: next_generation(),
             ..*self
         }
     }